@@ -0,0 +1,28 @@
+-- Migration: 029_signed_transactions.sql
+-- Description: Account key binding and per-account nonces for signed transactions
+-- Date: 2026-10-16
+-- Purpose: Every user transfer must carry an ed25519 signature from the key bound
+--          to the sender address and a strictly increasing nonce (replay protection)
+
+-- ============================================================================
+-- ACCOUNT KEYS
+-- ============================================================================
+-- One ed25519 verifying key (hex, 32 bytes) per address. First registration wins.
+
+CREATE TABLE IF NOT EXISTS account_keys (
+    address VARCHAR(255) PRIMARY KEY,
+    public_key VARCHAR(64) UNIQUE NOT NULL,
+    registered_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- ============================================================================
+-- ACCOUNT NONCES
+-- ============================================================================
+-- Last accepted nonce per address. Updated with compare-and-set (nonce = nonce + 1)
+-- in the same database transaction that stores the signed transaction.
+
+CREATE TABLE IF NOT EXISTS account_nonces (
+    address VARCHAR(255) PRIMARY KEY,
+    nonce BIGINT NOT NULL DEFAULT 0 CHECK (nonce >= 0),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,22 @@
+-- Migration: 030_fork_choice.sql
+-- Description: Side branches for CPV-weighted fork choice
+-- Date: 2026-10-16
+-- Purpose: Competing blocks are kept until they fall out of the reorg window
+--          (64 blocks) so a heavier branch can replace the canonical head
+
+-- ============================================================================
+-- SIDE BLOCKS
+-- ============================================================================
+-- Same layout as `blocks`, keyed by hash: several blocks may share a height.
+-- Orphaned canonical blocks move here on a reorganization.
+
+CREATE TABLE IF NOT EXISTS side_blocks (
+    hash VARCHAR(255) PRIMARY KEY,
+    height BIGINT NOT NULL,
+    prev_hash VARCHAR(255) NOT NULL,
+    timestamp TIMESTAMPTZ NOT NULL,
+    tx_count INTEGER NOT NULL DEFAULT 0,
+    data JSONB NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_side_blocks_height ON side_blocks(height);
@@ -0,0 +1,36 @@
+-- Migration: 031_finality.sql
+-- Description: Validator attestations and finalized checkpoint for CPV
+-- Date: 2026-10-16
+-- Purpose: A block is final once validators holding a weighted supermajority of
+--          CPV weight (DUJYO_FINALITY_THRESHOLD_BPS, default 6667) attest it.
+--          Finalized blocks are never reorganized; payouts only spend finalized funds.
+
+-- ============================================================================
+-- BLOCK ATTESTATIONS
+-- ============================================================================
+-- One vote per validator and height. A second, different vote at the same height
+-- is equivocation and is never stored here.
+
+CREATE TABLE IF NOT EXISTS block_attestations (
+    validator VARCHAR(255) NOT NULL,
+    height BIGINT NOT NULL,
+    block_hash VARCHAR(255) NOT NULL,
+    public_key VARCHAR(64) NOT NULL,
+    signature VARCHAR(128) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (validator, height)
+);
+
+CREATE INDEX IF NOT EXISTS idx_block_attestations_hash ON block_attestations(block_hash);
+
+-- ============================================================================
+-- CHAIN FINALITY
+-- ============================================================================
+-- Single row with the highest finalized block.
+
+CREATE TABLE IF NOT EXISTS chain_finality (
+    id SMALLINT PRIMARY KEY DEFAULT 1 CHECK (id = 1),
+    finalized_height BIGINT NOT NULL,
+    finalized_hash VARCHAR(255) NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
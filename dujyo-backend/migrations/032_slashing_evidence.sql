@@ -0,0 +1,25 @@
+-- Migration: 032_slashing_evidence.sql
+-- Description: Double-signing evidence behind CPV slashing events
+-- Date: 2026-10-16
+-- Purpose: Every DOUBLE_SIGNING slash is backed by verifiable evidence (two
+--          conflicting signed blocks or attestations at the same height) kept for audit.
+
+-- ============================================================================
+-- SLASHING EVIDENCE
+-- ============================================================================
+-- evidence_id is a hash of (kind, validator, height, conflicting hashes), so the
+-- same offence submitted twice (or in the other order) is only slashed once.
+-- validator_slashing_events.transaction_hash holds the evidence_id.
+
+CREATE TABLE IF NOT EXISTS slashing_evidence (
+    evidence_id VARCHAR(64) PRIMARY KEY,
+    validator_address VARCHAR(255) NOT NULL,
+    height BIGINT NOT NULL,
+    kind VARCHAR(20) NOT NULL CHECK (kind IN ('block', 'attestation')),
+    evidence JSONB NOT NULL,
+    submitted_by VARCHAR(255) NOT NULL,
+    slash_amount BIGINT NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_slashing_evidence_validator ON slashing_evidence(validator_address);
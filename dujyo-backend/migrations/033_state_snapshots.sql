@@ -0,0 +1,24 @@
+-- Migration: 033_state_snapshots.sql
+-- Description: Periodic snapshots of the committed chain state
+-- Date: 2026-10-16
+-- Purpose: Blocks and the balances/nonces they produce are committed in one
+--          database transaction. Every DUJYO_SNAPSHOT_INTERVAL blocks the full
+--          committed state is stored here; on startup the node restores the newest
+--          snapshot and replays the blocks above it (state roots re-verified).
+
+-- ============================================================================
+-- STATE SNAPSHOTS
+-- ============================================================================
+-- balances / nonces are JSON objects { address: value } excluding mempool effects.
+-- state_root = SHA-256 over the sorted accounts (same as Block.state_root).
+-- Snapshots above a reorganization's common ancestor are deleted with the
+-- orphaned blocks. Only the newest few snapshots are kept.
+
+CREATE TABLE IF NOT EXISTS state_snapshots (
+    height BIGINT PRIMARY KEY,
+    block_hash VARCHAR(255) NOT NULL,
+    state_root VARCHAR(64) NOT NULL,
+    balances JSONB NOT NULL,
+    nonces JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
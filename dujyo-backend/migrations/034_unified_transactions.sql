@@ -0,0 +1,31 @@
+-- Migration: 034_unified_transactions.sql
+-- Description: One typed ledger transaction for every on-chain state change
+-- Date: 2026-10-16
+-- Purpose: Transfers, swaps, staking, stream-to-earn payouts, NFT mint/transfer,
+--          tips and governance votes are all `Transaction`s with a `kind`, sharing
+--          one encoding, one hash and one execution path. Stakes, NFTs and votes
+--          are part of the state root and therefore of the state snapshots.
+
+-- ============================================================================
+-- TRANSACTIONS
+-- ============================================================================
+-- transaction_type = TxKind name ('transfer', 'swap', 'stake', 'unstake',
+-- 'stream_earn', 'nft_mint', 'nft_transfer', 'tip', 'governance_vote').
+-- kind = full TxKind JSON ({"type": "swap", "pool_id": ..., ...}); NULL for rows
+-- written before this migration (plain transfers) and for DEX bookkeeping rows.
+
+ALTER TABLE transactions
+    ADD COLUMN IF NOT EXISTS transaction_type VARCHAR(50) DEFAULT 'transfer',
+    ADD COLUMN IF NOT EXISTS nft_id VARCHAR(255),
+    ADD COLUMN IF NOT EXISTS kind JSONB;
+
+CREATE INDEX IF NOT EXISTS idx_transactions_type ON transactions(transaction_type);
+
+-- ============================================================================
+-- STATE SNAPSHOTS
+-- ============================================================================
+-- ledger = { "stakes": {address: amount}, "nfts": {nft_id: {creator, owner,
+-- ipfs_hash}}, "votes": {proposal_id: {voter: support}} } at the snapshot block.
+
+ALTER TABLE state_snapshots
+    ADD COLUMN IF NOT EXISTS ledger JSONB NOT NULL DEFAULT '{}';
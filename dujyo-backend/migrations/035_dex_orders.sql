@@ -0,0 +1,52 @@
+-- Migration: 035_dex_orders.sql
+-- Description: Order book for DEX limit orders and scheduled (DCA) swaps
+-- Date: 2026-10-16
+-- Purpose: Orders rest here with their input escrowed from token_balances. A
+--          background keeper executes due orders against the AMM when the pool
+--          price meets the limit, recording every (partial) fill. Whatever is
+--          left of a cancelled or expired order is refunded.
+
+-- ============================================================================
+-- ORDERS
+-- ============================================================================
+-- kind = 'limit' | 'dca'; status = 'open' | 'filled' | 'cancelled' | 'expired'.
+-- Amounts are micro-tokens like token_balances. min_price is `to_token` per
+-- `from_token` (0 = any price, DCA only). slice_amount is the input of one
+-- execution (the whole order for limit orders).
+
+CREATE TABLE IF NOT EXISTS dex_orders (
+    order_id VARCHAR(255) PRIMARY KEY,
+    user_address VARCHAR(255) NOT NULL,
+    kind VARCHAR(16) NOT NULL,
+    from_token VARCHAR(32) NOT NULL,
+    to_token VARCHAR(32) NOT NULL,
+    amount_in BIGINT NOT NULL,
+    remaining BIGINT NOT NULL,
+    amount_out BIGINT NOT NULL DEFAULT 0,
+    min_price NUMERIC(38, 18) NOT NULL DEFAULT 0,
+    slice_amount BIGINT NOT NULL,
+    interval_secs BIGINT NOT NULL DEFAULT 0,
+    next_execution_at TIMESTAMPTZ NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    status VARCHAR(16) NOT NULL DEFAULT 'open',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (remaining >= 0 AND remaining <= amount_in)
+);
+
+CREATE INDEX IF NOT EXISTS idx_dex_orders_user ON dex_orders(user_address, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_dex_orders_due ON dex_orders(next_execution_at) WHERE status = 'open';
+
+-- ============================================================================
+-- FILLS
+-- ============================================================================
+
+CREATE TABLE IF NOT EXISTS dex_order_fills (
+    fill_id VARCHAR(255) PRIMARY KEY,
+    order_id VARCHAR(255) NOT NULL REFERENCES dex_orders(order_id),
+    amount_in BIGINT NOT NULL,
+    amount_out BIGINT NOT NULL,
+    executed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_dex_order_fills_order ON dex_order_fills(order_id, executed_at);
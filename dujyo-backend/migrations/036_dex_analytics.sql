@@ -0,0 +1,23 @@
+-- Migration: 036_dex_analytics.sql
+-- Description: Pool state on DEX transaction rows for candles and pool analytics
+-- Date: 2026-10-16
+-- Purpose: Every DEX row written by save_dex_transaction (one per pool crossed
+--          by a swap, liquidity changes, order fills) records the swap input
+--          token and the reserves / LP supply it left behind. OHLCV candles
+--          (1m/5m/1h/1d), 24h volume, TVL, fee APR and LP returns are computed
+--          from these rows.
+
+-- ============================================================================
+-- TRANSACTIONS
+-- ============================================================================
+-- token_in: input token of a swap (NULL for liquidity changes).
+-- reserve_a / reserve_b / total_supply: pool state after the row, micro units
+-- like dex_pools; the pool price is reserve_b / reserve_a.
+
+ALTER TABLE transactions
+    ADD COLUMN IF NOT EXISTS token_in VARCHAR(32),
+    ADD COLUMN IF NOT EXISTS reserve_a BIGINT,
+    ADD COLUMN IF NOT EXISTS reserve_b BIGINT,
+    ADD COLUMN IF NOT EXISTS total_supply BIGINT;
+
+CREATE INDEX IF NOT EXISTS idx_transactions_pool_time ON transactions(pool_id, created_at);
@@ -0,0 +1,75 @@
+-- Migration: 037_secured_dex.sql
+-- Description: Secured DEX engine state on dex_pools
+-- Date: 2026-10-16
+-- Purpose: The live DEX checks the constant product on every swap, deposit and
+--          withdrawal and records k_last = sqrt(reserve_a * reserve_b) after
+--          each one. Pools are restored from these rows at startup and must
+--          still cover their k_last. Existing rows are migrated to the engine's
+--          keying: one pool per ordered pair, pool_id = token_a || '_' || token_b
+--          with token_a the smaller symbol. Every swap carries a nonce above
+--          the last one its user spent; dex_nonces keeps that last nonce so a
+--          replay is rejected across restarts.
+
+-- ============================================================================
+-- K_LAST
+-- ============================================================================
+-- Micro units like the reserves; backfilled from the current reserves.
+
+ALTER TABLE dex_pools
+    ADD COLUMN IF NOT EXISTS k_last BIGINT NOT NULL DEFAULT 0;
+
+UPDATE dex_pools
+SET k_last = FLOOR(SQRT(reserve_a::NUMERIC * reserve_b::NUMERIC))::BIGINT
+WHERE k_last = 0;
+
+-- ============================================================================
+-- ORDERED PAIRS
+-- ============================================================================
+-- Rows stored in reverse order swap their tokens and reserves (the right-hand
+-- sides read the old values).
+
+UPDATE dex_pools
+SET token_a = token_b, token_b = token_a, reserve_a = reserve_b, reserve_b = reserve_a
+WHERE token_a > token_b;
+
+-- Re-key rows whose pool_id is not the ordered pair, moving their positions and
+-- transactions along (a copy is recognised by its created_at). A row whose ordered id already exists is left as is (the
+-- server skips it at startup with a warning) rather than merged.
+
+INSERT INTO dex_pools (pool_id, token_a, token_b, reserve_a, reserve_b, total_supply, k_last, created_at, updated_at)
+SELECT token_a || '_' || token_b, token_a, token_b, reserve_a, reserve_b, total_supply, k_last, created_at, NOW()
+FROM dex_pools p
+WHERE p.pool_id <> p.token_a || '_' || p.token_b
+  AND NOT EXISTS (SELECT 1 FROM dex_pools o WHERE o.pool_id = p.token_a || '_' || p.token_b);
+
+UPDATE dex_liquidity_positions l
+SET pool_id = p.token_a || '_' || p.token_b,
+    position_id = l.user_address || '_' || p.token_a || '_' || p.token_b,
+    updated_at = NOW()
+FROM dex_pools p
+WHERE l.pool_id = p.pool_id
+  AND p.pool_id <> p.token_a || '_' || p.token_b
+  AND p.created_at = (SELECT o.created_at FROM dex_pools o WHERE o.pool_id = p.token_a || '_' || p.token_b);
+
+UPDATE transactions t
+SET pool_id = p.token_a || '_' || p.token_b
+FROM dex_pools p
+WHERE t.pool_id = p.pool_id
+  AND p.pool_id <> p.token_a || '_' || p.token_b
+  AND p.created_at = (SELECT o.created_at FROM dex_pools o WHERE o.pool_id = p.token_a || '_' || p.token_b);
+
+DELETE FROM dex_pools p
+WHERE p.pool_id <> p.token_a || '_' || p.token_b
+  AND p.created_at = (SELECT o.created_at FROM dex_pools o WHERE o.pool_id = p.token_a || '_' || p.token_b);
+
+-- ============================================================================
+-- SWAP NONCES
+-- ============================================================================
+-- A signed swap spends its nonce here (compare-and-set on last_nonce) before it
+-- reaches the engine, which restores its nonce registry from this table.
+
+CREATE TABLE IF NOT EXISTS dex_nonces (
+    address VARCHAR(255) PRIMARY KEY,
+    last_nonce BIGINT NOT NULL CHECK (last_nonce >= 0),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,31 @@
+-- Migration: 038_dex_batch_auction.sql
+-- Description: Swaps queued for the DEX batch auction
+-- Date: 2026-10-16
+-- Purpose: With DUJYO_DEX_BATCH_AUCTION enabled, a swap escrows its input from
+--          token_balances and waits here until block production clears every
+--          queued swap of a pool at one uniform price. Filled swaps are credited
+--          their output, rejected ones (missed limit or deadline) are refunded.
+--          Swaps still queued when the node starts are refunded.
+
+-- ============================================================================
+-- BATCH SWAPS
+-- ============================================================================
+-- status = 'queued' | 'filled' | 'rejected'. Amounts are micro-tokens like
+-- token_balances.
+
+CREATE TABLE IF NOT EXISTS dex_batch_swaps (
+    swap_id VARCHAR(255) PRIMARY KEY,
+    user_address VARCHAR(255) NOT NULL,
+    from_token VARCHAR(32) NOT NULL,
+    to_token VARCHAR(32) NOT NULL,
+    amount_in BIGINT NOT NULL,
+    min_received BIGINT NOT NULL DEFAULT 0,
+    amount_out BIGINT NOT NULL DEFAULT 0,
+    status VARCHAR(16) NOT NULL DEFAULT 'queued',
+    reason TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    settled_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_dex_batch_swaps_user ON dex_batch_swaps(user_address, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_dex_batch_swaps_queued ON dex_batch_swaps(status) WHERE status = 'queued';
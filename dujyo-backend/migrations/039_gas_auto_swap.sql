@@ -0,0 +1,19 @@
+-- Migration: 039_gas_auto_swap.sql
+-- Description: Per-user settings of the gas auto-swap
+-- Date: 2026-10-16
+-- Purpose: A signed transaction whose sender is short on DYO for the gas fee
+--          swaps just enough DYS through the DYO/DYS pool, in the same database
+--          transaction and the same block as the transaction itself. Users can
+--          turn the auto-swap off or bound what it may pay above the DYO/DYS
+--          TWAP. Addresses without a row use the defaults (enabled, 100 bps).
+
+-- ============================================================================
+-- AUTO-SWAP SETTINGS
+-- ============================================================================
+
+CREATE TABLE IF NOT EXISTS gas_auto_swap_settings (
+    address VARCHAR(255) PRIMARY KEY,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    max_slippage_bps INTEGER NOT NULL DEFAULT 100 CHECK (max_slippage_bps BETWEEN 0 AND 1000),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
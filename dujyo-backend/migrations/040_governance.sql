@@ -0,0 +1,26 @@
+-- Migration: 040_governance.sql
+-- Description: Stake-weighted governance proposals with executable actions
+-- Date: 2026-10-16
+-- Purpose: Proposals escrow a DYO deposit on the ledger and are voted with
+--          GovernanceVote ledger transactions. Voting power is the voter's
+--          bonded stake plus its CPV creative/community scores when voting
+--          closes. Passed proposals wait out a timelock and then change the CPV
+--          lambdas, a gas fee config or the Stream-to-Earn rates. Executed
+--          proposals are re-applied in order at startup.
+
+-- ============================================================================
+-- PROPOSALS
+-- ============================================================================
+-- status = 'voting' | 'queued' | 'executed' | 'rejected' | 'no_quorum' | 'failed'.
+-- `proposal` is the whole governance::Proposal (action, deposit, tally, times).
+
+CREATE TABLE IF NOT EXISTS governance_proposals (
+    proposal_id VARCHAR(255) PRIMARY KEY,
+    proposer VARCHAR(255) NOT NULL,
+    status VARCHAR(16) NOT NULL DEFAULT 'voting',
+    proposal JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_governance_proposals_status ON governance_proposals(status, created_at);
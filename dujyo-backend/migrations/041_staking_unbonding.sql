@@ -0,0 +1,29 @@
+-- Migration: 041_staking_unbonding.sql
+-- Description: Unbonding queue for unstaked DYO
+-- Date: 2026-10-16
+-- Purpose: Unstaking no longer credits DYO immediately. The amount leaves
+--          staked_balance into an unbonding entry that the unbonding keeper
+--          releases to dyo_balance once the unbonding period
+--          (DUJYO_UNBONDING_SECS) has passed. Entries of a slashed validator
+--          are cut at the same rate as its bond while they wait.
+
+-- ============================================================================
+-- UNBONDING ENTRIES
+-- ============================================================================
+
+CREATE TABLE IF NOT EXISTS staking_unbonding (
+    entry_id VARCHAR(255) PRIMARY KEY,
+    user_address VARCHAR(255) NOT NULL,
+    amount BIGINT NOT NULL,
+    slashed_amount BIGINT NOT NULL DEFAULT 0,
+    started_at BIGINT NOT NULL,
+    completes_at BIGINT NOT NULL,
+    released_at BIGINT
+);
+
+CREATE INDEX IF NOT EXISTS idx_staking_unbonding_user ON staking_unbonding(user_address, completes_at);
+CREATE INDEX IF NOT EXISTS idx_staking_unbonding_pending ON staking_unbonding(completes_at) WHERE released_at IS NULL;
+
+COMMENT ON COLUMN staking_unbonding.amount IS 'Micro-DYO credited on release (after any slashing)';
+COMMENT ON COLUMN staking_unbonding.started_at IS 'Unix timestamp of the unstake';
+COMMENT ON COLUMN staking_unbonding.completes_at IS 'Unix timestamp from which the entry is released';
@@ -0,0 +1,29 @@
+-- Migration: 042_mempool.sql
+-- Description: Durable mempool of full ledger transactions
+-- Date: 2026-10-16
+-- Purpose: Pending transactions used to be restored from `transactions` rows,
+--          which keep neither the signature nor the gas-fee and auto-swap legs
+--          of a submission, so a restart re-queued them as unsigned system
+--          transfers. The mempool now stores every pending ledger transaction
+--          as it sits in the node (kind, auth and tip included), grouped in the
+--          bundles that must enter a block together.
+
+-- ============================================================================
+-- MEMPOOL
+-- ============================================================================
+-- One row per pending transaction. Each accepted submission inserts its bundle;
+-- every block commit and reorg rewrites the bundles up to the last one the node
+-- had assigned, so the table matches the mempool left by the committed head.
+
+CREATE TABLE IF NOT EXISTS mempool_transactions (
+    bundle BIGINT NOT NULL,
+    position INTEGER NOT NULL,
+    priority VARCHAR(10) NOT NULL DEFAULT 'normal',
+    tx_hash VARCHAR(64) NOT NULL,
+    transaction JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (bundle, position)
+);
+
+COMMENT ON COLUMN mempool_transactions.bundle IS 'Admission order of the bundle in the node that stored it';
+COMMENT ON COLUMN mempool_transactions.transaction IS 'Full ledger transaction (from, to, amount, nft_id, kind, auth)';
@@ -0,0 +1,23 @@
+-- Migration: 043_staking_delegations.sql
+-- Description: Durable state of the validator staking contract (delegations)
+-- Date: 2026-10-16
+-- Purpose: Delegations to CPV validators live in the node's StakingManager:
+--          each validator's own bond, the delegators in its pool, its
+--          commission and the reward accumulator. Delegated DYO is held in the
+--          delegator's staked_balance, so every delegate, undelegate,
+--          commission change and slashing stores the whole manager in the same
+--          database transaction as the balance change it accounts for, and the
+--          node restores it from here at startup.
+
+-- ============================================================================
+-- STAKING STATE
+-- ============================================================================
+-- A single row (state_id = 1) with the StakingManager serialized as JSON.
+
+CREATE TABLE IF NOT EXISTS staking_state (
+    state_id SMALLINT PRIMARY KEY CHECK (state_id = 1),
+    state JSONB NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON COLUMN staking_state.state IS 'StakingManager: contracts, validator bonds, delegation pools, commissions and reward accumulators';
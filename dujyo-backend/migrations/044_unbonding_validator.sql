@@ -0,0 +1,15 @@
+-- Migration: 044_unbonding_validator.sql
+-- Description: Validator each unbonding entry is still liable for
+-- Date: 2026-10-17
+-- Purpose: Unbonding DYO stays slashable for the validator it was bonded to,
+--          not for the account that unstaked it. An undelegation records the
+--          validator it left; a validator unstaking its own bond records
+--          itself. Plain stake of accounts that are not validators has no
+--          validator and is never cut. Slashing matches on validator_address.
+
+ALTER TABLE staking_unbonding ADD COLUMN IF NOT EXISTS validator_address VARCHAR(255);
+
+CREATE INDEX IF NOT EXISTS idx_staking_unbonding_validator
+    ON staking_unbonding(validator_address) WHERE released_at IS NULL;
+
+COMMENT ON COLUMN staking_unbonding.validator_address IS 'Validator whose slashing still cuts this entry (NULL: not slashable)';
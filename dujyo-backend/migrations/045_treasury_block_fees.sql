@@ -0,0 +1,23 @@
+-- Migration: 045_treasury_block_fees.sql
+-- Description: Treasury bookings of each block's fee distribution
+-- Date: 2026-10-17
+-- Purpose: TreasuryManager only kept the fee bookings of committed blocks in
+--          memory, so a restart lost them. Each block commit now stores the
+--          split it credited in the same database transaction, a reorg drops
+--          the rows of the orphaned branch and adds the adopted one, and the
+--          node books the stored rows again at startup.
+
+CREATE TABLE IF NOT EXISTS treasury_block_fees (
+    block_hash VARCHAR(64) PRIMARY KEY,
+    height BIGINT NOT NULL,
+    treasury BIGINT NOT NULL CHECK (treasury >= 0),
+    validator BIGINT NOT NULL CHECK (validator >= 0),
+    creative_pool BIGINT NOT NULL CHECK (creative_pool >= 0),
+    burn BIGINT NOT NULL CHECK (burn >= 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_treasury_block_fees_height ON treasury_block_fees(height);
+
+COMMENT ON TABLE treasury_block_fees IS 'Fee distribution of each canonical block, booked by TreasuryManager::collect_block_fees';
+COMMENT ON COLUMN treasury_block_fees.treasury IS 'Ledger cents credited to TREASURY by the block';
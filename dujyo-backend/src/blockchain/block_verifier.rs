@@ -0,0 +1,346 @@
+//! Block Authentication
+//!
+//! A block received from a peer changes state only after the node has checked
+//! who made it and who authorized each of its transactions:
+//! - the proposer signature over the header hash, against the ed25519 key bound
+//!   to the proposer's account (`account_keys`; the bootstrap proposer uses
+//!   `proposer::bootstrap_public_key`);
+//! - the slot VRF proof for (height, parent, round), made with that same key;
+//! - that the VRF output wins the slot lottery under the CPV weights;
+//! - every signed transaction, against the key bound to its sender (the nonce
+//!   is checked when the transaction is applied);
+//! - every relayed permit, against the key bound to its owner and the block time;
+//! - other unsigned transactions only for what the node itself emits.
+//!
+//! Keys live in the database and the chain sits behind a sync mutex, so the
+//! transport looks up the keys a message needs (`BlockVerifier::accounts`)
+//! before locking the chain, as it does with the CPV weights.
+//!
+//! CPV weights describe the current validator set only, so the lottery is
+//! checked for blocks from the last `ELIGIBILITY_WINDOW_SECS` (the span a reorg
+//! can still reach); older history is checked for signatures and VRF proofs.
+
+use std::collections::{HashMap, HashSet};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::blockchain::blockchain::{Block, Transaction};
+use crate::blockchain::fork_choice::{ProposerWeights, MAX_REORG_DEPTH};
+use crate::blockchain::ledger::{is_system_account, Permit, TxKind, FEE_COLLECTOR};
+use crate::blockchain::signed_transaction::{decode_public_key, SignedTransaction};
+use crate::consensus::proposer::{self, SLOT_DURATION_SECS, SYSTEM_PROPOSER};
+
+/// Age up to which a block must have won its slot under the current CPV weights
+pub const ELIGIBILITY_WINDOW_SECS: u64 = MAX_REORG_DEPTH * SLOT_DURATION_SECS;
+
+/// How far ahead of the local clock a block may be (a later timestamp would pick a later round)
+pub const MAX_CLOCK_DRIFT_SECS: u64 = SLOT_DURATION_SECS;
+
+/// What a node knows about proposers and signers when it judges peer blocks
+#[derive(Debug, Clone, Default)]
+pub struct BlockVerifier {
+    pub chain_id: String,
+    /// CPV weight per proposer (snapshot taken from `CPVConsensus`)
+    pub weights: ProposerWeights,
+    /// Account -> hex ed25519 public key bound to it
+    pub account_keys: HashMap<String, String>,
+    /// Unix time the eligibility window is measured from
+    pub now: u64,
+}
+
+impl BlockVerifier {
+    pub fn new(chain_id: String, weights: ProposerWeights) -> Self {
+        BlockVerifier {
+            chain_id,
+            weights,
+            account_keys: HashMap::new(),
+            now: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+        }
+    }
+
+    pub fn insert_key(&mut self, address: String, public_key: String) {
+        self.account_keys.insert(address, public_key.to_lowercase());
+    }
+
+    pub fn with_key(mut self, address: &str, public_key: &str) -> Self {
+        self.insert_key(address.to_string(), public_key.to_string());
+        self
+    }
+
+    /// Accounts whose keys verifying `blocks` needs: proposers and transaction signers
+    pub fn accounts<'a>(blocks: impl IntoIterator<Item = &'a Block>) -> HashSet<String> {
+        let mut accounts = HashSet::new();
+        for block in blocks {
+            accounts.extend(block.validator.clone());
+            accounts.extend(
+                block
+                    .transactions
+                    .iter()
+                    .filter(|transaction| transaction.signed_nonce().is_some())
+                    .map(|transaction| transaction.from.clone()),
+            );
+        }
+        accounts
+    }
+
+    /// Proposer and transaction checks of a block whose parent has `parent_timestamp`
+    pub fn verify(&self, block: &Block, parent_timestamp: u64) -> Result<(), String> {
+        self.verify_proposer(block, parent_timestamp)?;
+        self.verify_transactions(block)
+    }
+
+    /// Signature, slot VRF and lottery win of the block proposer
+    pub fn verify_proposer(&self, block: &Block, parent_timestamp: u64) -> Result<(), String> {
+        let proposer = block
+            .validator
+            .as_deref()
+            .ok_or_else(|| "block has no proposer".to_string())?;
+        let key = self
+            .account_keys
+            .get(proposer)
+            .ok_or_else(|| format!("no key bound to proposer {}", proposer))
+            .and_then(|key| decode_public_key(key).map_err(|e| e.to_string()))?;
+
+        if !block.verify_proposer_signature(&key) {
+            return Err(format!("invalid proposer signature from {}", proposer));
+        }
+
+        if block.timestamp > self.now.saturating_add(MAX_CLOCK_DRIFT_SECS) {
+            return Err(format!("block {} is from the future", block.height));
+        }
+        let vrf = block.vrf.as_ref().ok_or_else(|| "block has no VRF proof".to_string())?;
+        let round = proposer::slot_round(parent_timestamp, block.timestamp);
+        let seed = proposer::slot_seed(block.height, &block.previous_hash, round);
+        if !proposer::verify_slot_vrf(&key, &seed, vrf) {
+            return Err(format!("invalid slot VRF from {}", proposer));
+        }
+
+        // The bootstrap proposer weighs 1 in fork choice and holds no CPV stake
+        let recent = self.now.saturating_sub(block.timestamp) <= ELIGIBILITY_WINDOW_SECS;
+        if proposer != SYSTEM_PROPOSER && recent && !proposer::is_eligible(&self.weights, proposer, &vrf.output) {
+            return Err(format!("{} did not win slot {} at height {}", proposer, round, block.height));
+        }
+        Ok(())
+    }
+
+    /// Every signed transaction must verify against its sender's key; unsigned
+    /// ones are only allowed for relayed permits and what the node emits itself
+    pub fn verify_transactions(&self, block: &Block) -> Result<(), String> {
+        for (index, transaction) in block.transactions.iter().enumerate() {
+            if let TxKind::Permit { nonce, deadline, signature, .. } = &transaction.kind {
+                let key = self
+                    .account_keys
+                    .get(&transaction.from)
+                    .ok_or_else(|| format!("no key bound to {}", transaction.from))?;
+                if block.timestamp > *deadline {
+                    return Err(format!("permit of {} expired before block {}", transaction.from, block.height));
+                }
+                let permit = Permit {
+                    owner: transaction.from.clone(),
+                    spender: transaction.to.clone(),
+                    amount: transaction.amount,
+                    nonce: *nonce,
+                    deadline: *deadline,
+                    public_key: key.clone(),
+                    signature: signature.clone(),
+                };
+                permit
+                    .verify(&self.chain_id, key)
+                    .map_err(|e| format!("transaction {}: {}", transaction.tx_hash(), e))?;
+                continue;
+            }
+            match SignedTransaction::from_transaction(transaction) {
+                Some(signed) => {
+                    let key = self
+                        .account_keys
+                        .get(&transaction.from)
+                        .ok_or_else(|| format!("no key bound to {}", transaction.from))?;
+                    signed
+                        .verify(&self.chain_id, key)
+                        .map_err(|e| format!("transaction {}: {}", transaction.tx_hash(), e))?;
+                }
+                None if is_node_emitted(transaction) || pays_for_signed(&block.transactions[index..]) => {}
+                None => {
+                    return Err(format!(
+                        "unsigned {} from {} in block {}",
+                        transaction.kind.name(),
+                        transaction.from,
+                        block.height
+                    ))
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Fee distribution, DEX results (swaps and liquidity legs), stream-to-earn
+/// payouts, mints, service pulls within an allowance the owner signed, and
+/// payouts out of accounts no user key controls (escrow, pools, fee collector)
+fn is_node_emitted(transaction: &Transaction) -> bool {
+    match transaction.kind {
+        TxKind::FeeDistribution
+        | TxKind::Swap { .. }
+        | TxKind::AddLiquidity { .. }
+        | TxKind::RemoveLiquidity { .. }
+        | TxKind::StreamEarn { .. }
+        | TxKind::NftMint { .. }
+        | TxKind::TransferFrom { .. } => true,
+        _ => is_system_account(&transaction.from),
+    }
+}
+
+/// Gas fee leg (`legs[0]`): a transfer to the fee collector that travels right
+/// before the signed transaction it pays for, with only legs of the same sender between
+fn pays_for_signed(legs: &[Transaction]) -> bool {
+    let fee_leg = &legs[0];
+    fee_leg.kind.is_transfer()
+        && fee_leg.to == FEE_COLLECTOR
+        && legs[1..]
+            .iter()
+            .take_while(|leg| leg.from == fee_leg.from)
+            .any(|leg| leg.auth.is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::ledger::BILLING_SPENDER;
+    use crate::blockchain::signed_transaction::DEFAULT_CHAIN_ID;
+    use ed25519_dalek::SigningKey;
+
+    fn key(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    fn public_hex(seed: u8) -> String {
+        hex::encode(key(seed).verifying_key().to_bytes())
+    }
+
+    fn sealed(proposer: &str, proposer_key: &SigningKey, transactions: Vec<Transaction>) -> Block {
+        let mut block = Block::new(1, 1_700_000_010, transactions, "parent".to_string(), "state".to_string(), Some(proposer.to_string()));
+        let seed = proposer::slot_seed(1, "parent", 1);
+        block.seal(Some(proposer::prove_slot(proposer_key, &seed)), proposer_key);
+        block
+    }
+
+    fn verifier() -> BlockVerifier {
+        BlockVerifier::new(DEFAULT_CHAIN_ID.to_string(), ProposerWeights::new())
+            .with_key(SYSTEM_PROPOSER, &public_hex(1))
+            .with_key("DUalice", &public_hex(2))
+    }
+
+    fn signed_transfer(nonce: u64) -> Transaction {
+        SignedTransaction::unsigned("DUalice".to_string(), "DUbob".to_string(), 10, None, nonce, DEFAULT_CHAIN_ID.to_string(), 5)
+            .signed_with(&key(2))
+            .into_transaction()
+    }
+
+    #[test]
+    fn test_proposer_signature_and_vrf_checked_against_bound_key() {
+        let block = sealed(SYSTEM_PROPOSER, &key(1), vec![]);
+        assert!(verifier().verify(&block, 1_700_000_000).is_ok());
+
+        // Signed with a key that is not the one bound to the proposer
+        let forged = sealed(SYSTEM_PROPOSER, &key(9), vec![]);
+        assert!(verifier().verify(&forged, 1_700_000_000).is_err());
+
+        // Unknown proposer
+        let unknown = sealed("DUmallory", &key(9), vec![]);
+        assert!(verifier().verify(&unknown, 1_700_000_000).is_err());
+
+        // Same VRF replayed for another round
+        assert!(verifier().verify(&block, 1_700_000_010).is_err());
+    }
+
+    #[test]
+    fn test_validator_must_win_the_slot() {
+        let block = sealed("DUvalidator", &key(3), vec![]);
+        let mut weights = ProposerWeights::new();
+        weights.insert("DUvalidator".to_string(), 1_000);
+        let mut verifier = BlockVerifier::new(DEFAULT_CHAIN_ID.to_string(), weights).with_key("DUvalidator", &public_hex(3));
+        verifier.now = block.timestamp;
+        // Sole validator: always wins
+        assert!(verifier.verify(&block, 1_700_000_000).is_ok());
+
+        // Not a CPV validator: cannot win a recent slot
+        verifier.weights.clear();
+        verifier.weights.insert("DUother".to_string(), 1_000);
+        assert!(verifier.verify(&block, 1_700_000_000).is_err());
+
+        // Old history is only checked for signatures
+        verifier.now = block.timestamp + ELIGIBILITY_WINDOW_SECS + 1;
+        assert!(verifier.verify(&block, 1_700_000_000).is_ok());
+    }
+
+    #[test]
+    fn test_transaction_auth_rules() {
+        let verifier = verifier();
+
+        let signed = sealed(SYSTEM_PROPOSER, &key(1), vec![signed_transfer(1)]);
+        assert!(verifier.verify_transactions(&signed).is_ok());
+
+        // Signature by another key than the one bound to the sender
+        let mut wrong = signed_transfer(1);
+        let forged = SignedTransaction::unsigned("DUalice".to_string(), "DUbob".to_string(), 10, None, 1, DEFAULT_CHAIN_ID.to_string(), 5)
+            .signed_with(&key(7))
+            .into_transaction();
+        wrong.auth = forged.auth;
+        assert!(verifier.verify_transactions(&sealed(SYSTEM_PROPOSER, &key(1), vec![wrong])).is_err());
+
+        // Unsigned transfer out of a user account
+        let theft = Transaction::system("DUalice".to_string(), "DUmallory".to_string(), 10, None);
+        assert!(verifier.verify_transactions(&sealed(SYSTEM_PROPOSER, &key(1), vec![theft.clone()])).is_err());
+
+        // Gas leg followed by the sender's signed transaction is fine, a leg paying for nothing is not
+        let gas = Transaction::system("DUalice".to_string(), "GAS_FEE_ADDRESS".to_string(), 3, None);
+        let bundle = sealed(SYSTEM_PROPOSER, &key(1), vec![gas.clone(), signed_transfer(1)]);
+        assert!(verifier.verify_transactions(&bundle).is_ok());
+        let orphan_leg = sealed(SYSTEM_PROPOSER, &key(1), vec![gas, theft, signed_transfer(1)]);
+        assert!(verifier.verify_transactions(&orphan_leg).is_err());
+
+        // Escrow payouts are node-emitted
+        let settlement = Transaction::system("GOVERNANCE_ESCROW".to_string(), "DUalice".to_string(), 10, None);
+        assert!(verifier.verify_transactions(&sealed(SYSTEM_PROPOSER, &key(1), vec![settlement])).is_ok());
+    }
+
+    fn relayed(permit: &Permit) -> Transaction {
+        Transaction::system(permit.owner.clone(), permit.spender.clone(), permit.amount, None).with_kind(TxKind::Permit {
+            nonce: permit.nonce,
+            deadline: permit.deadline,
+            signature: permit.signature.clone(),
+            previous: 0,
+        })
+    }
+
+    #[test]
+    fn test_relayed_permits_checked_against_owner_key() {
+        let verifier = verifier();
+        let permit = Permit::unsigned("DUalice".to_string(), BILLING_SPENDER.to_string(), 500, 1, 1_700_000_100);
+
+        let valid = relayed(&permit.clone().signed_with(&key(2)));
+        assert!(BlockVerifier::accounts([&sealed(SYSTEM_PROPOSER, &key(1), vec![valid.clone()])]).contains("DUalice"));
+        assert!(verifier.verify_transactions(&sealed(SYSTEM_PROPOSER, &key(1), vec![valid.clone()])).is_ok());
+
+        // Signed by a key that is not bound to the owner
+        let forged = relayed(&permit.clone().signed_with(&key(9)));
+        assert!(verifier.verify_transactions(&sealed(SYSTEM_PROPOSER, &key(1), vec![forged])).is_err());
+
+        // Amount raised by the relayer
+        let mut raised = valid.clone();
+        raised.amount = 50_000;
+        assert!(verifier.verify_transactions(&sealed(SYSTEM_PROPOSER, &key(1), vec![raised])).is_err());
+
+        // Included after its deadline
+        let mut late = sealed(SYSTEM_PROPOSER, &key(1), vec![valid.clone()]);
+        late.timestamp = 1_700_000_101;
+        assert!(verifier.verify_transactions(&late).is_err());
+
+        // Pulls within the allowance are node-emitted, an unsigned approval is not
+        let pull = Transaction::system("DUalice".to_string(), "TREASURY".to_string(), 100, None)
+            .with_kind(TxKind::TransferFrom { spender: BILLING_SPENDER.to_string() });
+        assert!(verifier.verify_transactions(&sealed(SYSTEM_PROPOSER, &key(1), vec![valid, pull])).is_ok());
+        let approval = Transaction::system("DUalice".to_string(), BILLING_SPENDER.to_string(), 500, None)
+            .with_kind(TxKind::Approve { previous: 0 });
+        assert!(verifier.verify_transactions(&sealed(SYSTEM_PROPOSER, &key(1), vec![approval])).is_err());
+    }
+}
@@ -1,6 +1,26 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::time::{SystemTime, UNIX_EPOCH};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+
+use crate::blockchain::block_verifier::BlockVerifier;
+use crate::blockchain::fork_choice::{self, BlockImport, ReorgEvent, MAX_REORG_DEPTH};
+use crate::blockchain::gas_fees::{FeeDistribution, FeeSplit};
+use crate::blockchain::ledger::{fee_payouts, pool_account, LedgerState, NftRecord, TxKind, FEE_COLLECTOR, NATIVE_TOKEN};
+use crate::blockchain::mempool::{Bundle, OptimizedMempool, TransactionPriority, MAX_MEMPOOL_SIZE};
+use crate::blockchain::merkle::{self, MerkleProof};
+use crate::blockchain::signed_transaction::{push_field, signed_hash, SignedTransaction};
+use crate::dex::batch_auction::{batches_root, BatchClearing};
+use crate::consensus::proposer::{sign_block_hash, verify_block_signature};
+use crate::utils::vrf::VRFResult;
+use ed25519_dalek::{SigningKey, VerifyingKey};
+
+/// Domain separators for header and system transaction hashing
+const BLOCK_HEADER_DOMAIN: &[u8] = b"DUJYO_BLOCK_HEADER_V1";
+const SYSTEM_TX_DOMAIN: &[u8] = b"DUJYO_SYSTEM_TX_V1";
+const STATE_ROOT_DOMAIN: &[u8] = b"DUJYO_STATE_V1";
+
+/// Fixed genesis timestamp so every node derives the same genesis hash
+pub const GENESIS_TIMESTAMP: u64 = 1_700_000_000;
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Transaction {
@@ -8,50 +28,298 @@ pub struct Transaction {
     pub to: String,
     pub amount: u64,
     pub nft_id: Option<String>, // Si la transacción es de un NFT, tendrá un ID
+    // Qué hace la transacción (transferencia, swap, staking, S2E, NFT, propina, voto)
+    #[serde(default, skip_serializing_if = "TxKind::is_transfer")]
+    pub kind: TxKind,
+    // ✅ SECURITY: Firma ed25519 + nonce del remitente (None para transacciones del sistema)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<TransactionAuth>,
+}
+
+/// Autorización criptográfica de una transacción enviada por un usuario
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TransactionAuth {
+    pub nonce: u64,
+    pub chain_id: String,
+    pub fee: u64,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub tip: u64, // Propina de prioridad (incluida en `fee`)
+    pub public_key: String,
+    pub signature: String,
+}
+
+fn is_zero(value: &u64) -> bool {
+    *value == 0
 }
 
 impl Transaction {
-    fn is_valid(&self) -> bool {
-        !self.from.is_empty() && !self.to.is_empty() && self.amount > 0
+    /// Transacción del sistema (gas, recompensas, pagos S2E) sin firma de usuario
+    pub fn system(from: String, to: String, amount: u64, nft_id: Option<String>) -> Self {
+        Transaction { from, to, amount, nft_id, kind: TxKind::Transfer, auth: None }
+    }
+
+    /// Misma transacción con otro tipo (swap, staking, S2E, NFT, propina, voto)
+    pub fn with_kind(mut self, kind: TxKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Nonce de `from` que consume: el del sobre firmado o el de un permit retransmitido
+    pub fn signed_nonce(&self) -> Option<u64> {
+        self.auth.as_ref().map(|auth| auth.nonce).or(self.kind.permit_nonce())
+    }
+
+    fn check_shape(&self) -> Result<(), String> {
+        self.kind.check_shape(&self.from, &self.to, self.amount, self.nft_id.as_deref())
+    }
+
+    /// Cuentas cuyo saldo puede cambiar (la distribución de tarifas también abona a las cuentas del reparto)
+    pub fn accounts(&self) -> Vec<&str> {
+        let mut accounts = vec![self.from.as_str(), self.to.as_str()];
+        if matches!(self.kind, TxKind::FeeDistribution) {
+            accounts.extend(fee_payouts(&self.to, self.amount).1.iter().map(|(account, _)| *account));
+        }
+        accounts
+    }
+
+    /// Lo que recibe `address` con esta transacción (sin contar lo que se envía a sí misma)
+    pub fn credited_to(&self, address: &str) -> u64 {
+        if self.from == address {
+            return 0;
+        }
+        match self.kind {
+            TxKind::FeeDistribution => fee_payouts(&self.to, self.amount)
+                .1
+                .iter()
+                .filter(|(account, _)| *account == address)
+                .map(|(_, share)| *share)
+                .sum(),
+            _ if self.to == address => self.amount,
+            _ => 0,
+        }
+    }
+
+    /// SHA-256 transaction hash (hex). Signed transactions hash exactly like
+    /// `SignedTransaction::tx_hash`, so the id returned on submit is the Merkle leaf.
+    pub fn tx_hash(&self) -> String {
+        match &self.auth {
+            Some(auth) => {
+                let payload = SignedTransaction::signing_payload(
+                    &self.from,
+                    &self.to,
+                    self.amount,
+                    self.nft_id.as_deref(),
+                    auth.nonce,
+                    &auth.chain_id,
+                    auth.fee,
+                    &self.kind,
+                    auth.tip,
+                );
+                signed_hash(&payload, &auth.signature)
+            }
+            None => {
+                let mut payload = Vec::with_capacity(96);
+                payload.extend_from_slice(SYSTEM_TX_DOMAIN);
+                push_field(&mut payload, self.from.as_bytes());
+                push_field(&mut payload, self.to.as_bytes());
+                payload.extend_from_slice(&self.amount.to_be_bytes());
+                match &self.nft_id {
+                    Some(id) => {
+                        payload.push(1);
+                        push_field(&mut payload, id.as_bytes());
+                    }
+                    None => payload.push(0),
+                }
+                self.kind.encode(&mut payload);
+                hex::encode(Sha256::digest(&payload))
+            }
+        }
     }
 }
 
-#[derive(Clone, Debug)]
-pub struct NFT {
-    pub id: String,
-    pub creator: String,
-    pub owner: String,
-    pub price: u64,
-    pub ipfs_hash: Option<String>, // El hash de IPFS para el contenido asociado al NFT
+/// Cabecera de bloque: todo lo que cubre el hash del bloque
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BlockHeader {
+    pub height: u64,
+    pub previous_hash: String,
+    pub merkle_root: String, // SHA-256 Merkle root de los hashes de transacciones
+    pub state_root: String,  // SHA-256 del estado (balances + nonces) tras aplicar el bloque
+    pub timestamp: u64,
+    pub proposer: Option<String>,
+    pub vrf_output: Option<String>, // Salida VRF (hex) que seleccionó al proponente
+    // SHA-256 de los resultados de subastas por lotes del DEX (solo si el bloque tiene)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dex_batch_root: Option<String>,
+}
+
+impl BlockHeader {
+    /// Bytes canónicos de la cabecera (la firma del proponente firma su hash)
+    pub fn encode(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(256);
+        data.extend_from_slice(BLOCK_HEADER_DOMAIN);
+        data.extend_from_slice(&self.height.to_be_bytes());
+        push_field(&mut data, self.previous_hash.as_bytes());
+        push_field(&mut data, self.merkle_root.as_bytes());
+        push_field(&mut data, self.state_root.as_bytes());
+        data.extend_from_slice(&self.timestamp.to_be_bytes());
+        match &self.proposer {
+            Some(proposer) => {
+                data.push(1);
+                push_field(&mut data, proposer.as_bytes());
+            }
+            None => data.push(0),
+        }
+        match &self.vrf_output {
+            Some(output) => {
+                data.push(1);
+                push_field(&mut data, output.as_bytes());
+            }
+            None => data.push(0),
+        }
+        // Sin subastas no se añade nada: los bloques anteriores conservan su hash
+        if let Some(root) = &self.dex_batch_root {
+            push_field(&mut data, root.as_bytes());
+        }
+        data
+    }
+
+    pub fn hash(&self) -> String {
+        hex::encode(Sha256::digest(self.encode()))
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Block {
+    #[serde(default)]
+    pub height: u64,
     pub timestamp: u64,
     pub transactions: Vec<Transaction>,
     pub previous_hash: String,
     pub hash: String,
-    pub validator: Option<String>,
+    pub validator: Option<String>, // Proponente del bloque
+    #[serde(default)]
+    pub merkle_root: String,
+    #[serde(default)]
+    pub state_root: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proposer_signature: Option<String>, // Firma ed25519 (hex) del proponente sobre `hash`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vrf: Option<VRFResult>, // ✅ CPV: Prueba VRF de la selección del proponente
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dex_batches: Vec<BatchClearing>, // Resultados por usuario de las subastas por lotes del DEX
 }
 
 impl Block {
+    /// Crear un bloque calculando Merkle root y hash de cabecera
+    pub fn new(
+        height: u64,
+        timestamp: u64,
+        transactions: Vec<Transaction>,
+        previous_hash: String,
+        state_root: String,
+        validator: Option<String>,
+    ) -> Self {
+        let mut block = Block {
+            height,
+            timestamp,
+            transactions,
+            previous_hash,
+            hash: String::new(),
+            validator,
+            merkle_root: String::new(),
+            state_root,
+            proposer_signature: None,
+            vrf: None,
+            dex_batches: Vec::new(),
+        };
+        block.merkle_root = block.compute_merkle_root();
+        block.hash = block.calculate_hash();
+        block
+    }
+
+    pub fn header(&self) -> BlockHeader {
+        BlockHeader {
+            height: self.height,
+            previous_hash: self.previous_hash.clone(),
+            merkle_root: self.merkle_root.clone(),
+            state_root: self.state_root.clone(),
+            timestamp: self.timestamp,
+            proposer: self.validator.clone(),
+            vrf_output: self.vrf.as_ref().map(|vrf| hex::encode(vrf.output)),
+            dex_batch_root: batches_root(&self.dex_batches),
+        }
+    }
+
+    /// Sellar el bloque: adjuntar la prueba VRF, recalcular el hash y firmarlo con la clave del proponente
+    pub fn seal(&mut self, vrf: Option<VRFResult>, proposer_key: &SigningKey) {
+        self.vrf = vrf;
+        self.hash = self.calculate_hash();
+        self.proposer_signature = Some(sign_block_hash(proposer_key, &self.hash));
+    }
+
+    /// Verificar la firma del proponente sobre el hash del bloque
+    pub fn verify_proposer_signature(&self, proposer_key: &VerifyingKey) -> bool {
+        match &self.proposer_signature {
+            Some(signature) => verify_block_signature(proposer_key, &self.hash, signature),
+            None => false,
+        }
+    }
+
+    /// SHA-256 de la cabecera (la firma del proponente no forma parte del hash)
     pub fn calculate_hash(&self) -> String {
-        // Simplified hash calculation - in a real implementation you would use a proper hash function
-        let mut data = self.timestamp.to_string();
-        for transaction in &self.transactions {
-            data.push_str(&transaction.from);
-            data.push_str(&transaction.to);
-            data.push_str(&transaction.amount.to_string());
-            if let Some(nft_id) = &transaction.nft_id {
-                data.push_str(nft_id);
-            }
+        self.header().hash()
+    }
+
+    fn leaf_hashes(&self) -> Vec<merkle::Hash32> {
+        self.transactions
+            .iter()
+            .map(|tx| merkle::decode_hash(&tx.tx_hash()).expect("tx_hash is always 32-byte hex"))
+            .collect()
+    }
+
+    pub fn compute_merkle_root(&self) -> String {
+        hex::encode(merkle::merkle_root(&self.leaf_hashes()))
+    }
+
+    /// Comprobar Merkle root, hash de cabecera y la posición de la distribución
+    /// de tarifas (independiente del estado)
+    pub fn verify_structure(&self) -> Result<(), String> {
+        if self.merkle_root != self.compute_merkle_root() {
+            return Err("Merkle root inválido".to_string());
         }
-        data.push_str(&self.previous_hash);
-        if let Some(validator) = &self.validator {
-            data.push_str(validator);
+        if self.hash != self.calculate_hash() {
+            return Err("Hash de bloque inválido".to_string());
+        }
+        let last = self.transactions.len().saturating_sub(1);
+        for (index, transaction) in self.transactions.iter().enumerate() {
+            if !matches!(transaction.kind, TxKind::FeeDistribution) {
+                continue;
+            }
+            if index != last {
+                return Err("La distribución de tarifas debe ser la última transacción del bloque".to_string());
+            }
+            if self.validator.as_deref() != Some(transaction.to.as_str()) {
+                return Err("La distribución de tarifas debe pagar al proponente del bloque".to_string());
+            }
         }
-        let hash_value = data.len() as u64 * 31 + data.chars().map(|c| c as u64).sum::<u64>();
-        format!("{:x}", hash_value)
+        Ok(())
+    }
+
+    /// Reparto de las tarifas recaudadas que hizo este bloque (None si no repartió)
+    pub fn fee_split(&self) -> Option<FeeSplit> {
+        self.transactions
+            .last()
+            .filter(|transaction| matches!(transaction.kind, TxKind::FeeDistribution))
+            .map(|transaction| FeeDistribution::split_cents(transaction.amount))
+    }
+
+    /// Prueba de inclusión de una transacción (por hash) en este bloque
+    pub fn merkle_proof(&self, tx_hash: &str) -> Option<MerkleProof> {
+        let index = self
+            .transactions
+            .iter()
+            .position(|tx| tx.tx_hash().eq_ignore_ascii_case(tx_hash))?;
+        merkle::build_proof(&self.leaf_hashes(), index)
     }
 }
 
@@ -59,33 +327,44 @@ impl Block {
 pub struct Blockchain {
     pub chain: Vec<Block>,
     pub pending_transactions: Vec<Transaction>,
+    pub pending_meta: Vec<PendingMeta>, // Prioridad y grupo de cada transacción pendiente (mismo orden)
+    next_bundle: u64,
     pub validators: HashMap<String, u64>,
     pub minimum_stake: u64,
-    pub balances: HashMap<String, u64>,
-    pub nft_registry: HashMap<String, NFT>, // Registro de NFTs
-    pub proposals: HashMap<String, Proposal>, // Propuestas de gobernanza
-    pub transaction_fees: u64, // Tarifa por transacción
+    pub balances: HashMap<String, u64>, // Centavos de DYO, exactos (Amount::from_cents / to_cents en los bordes)
+    pub transaction_fees: u64, // Tarifa por transacción, en centavos
+    pub nonces: HashMap<String, u64>, // ✅ SECURITY: Último nonce aceptado por cuenta (anti-replay)
+    pub side_blocks: HashMap<String, Block>, // Bloques de ramas laterales (no canónicas) por hash
+    pub finalized_height: u64, // ✅ CPV: Último bloque con supermayoría de atestaciones (nunca se reorganiza)
+    pub finalized_hash: String,
+    pub ledger: LedgerState, // Stakes, NFTs y votos (parte del state root)
 }
 
-#[derive(Clone, Debug)]
-pub struct Proposal {
-    pub description: String,
-    pub votes_for: u64,
-    pub votes_against: u64,
-    pub end_time: u64, // Fecha de finalización de la votación
+/// Prioridad de una transacción pendiente y grupo (bundle) con el que entró al mempool
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PendingMeta {
+    pub priority: TransactionPriority,
+    pub bundle: u64,
 }
 
 impl Blockchain {
     pub fn new() -> Self {
+        let genesis = Blockchain::create_genesis_block();
+        let finalized_hash = genesis.hash.clone();
         let blockchain = Blockchain {
-            chain: vec![Blockchain::create_genesis_block()],
+            chain: vec![genesis],
             pending_transactions: Vec::new(),
+            pending_meta: Vec::new(),
+            next_bundle: 0,
             validators: HashMap::new(),
             minimum_stake: 1000,
             balances: HashMap::new(),
-            nft_registry: HashMap::new(),
-            proposals: HashMap::new(),
             transaction_fees: 10, // Ejemplo de tarifa por transacción
+            nonces: HashMap::new(),
+            side_blocks: HashMap::new(),
+            finalized_height: 0, // El génesis es final por definición
+            finalized_hash,
+            ledger: LedgerState::default(),
         };
         
         blockchain
@@ -101,28 +380,26 @@ impl Blockchain {
         let genesis_address = "0x0000000000000000000000000000000000000000".to_string();
         let recipient_address = "DU1111111111111111111111111111111111111111".to_string();
         
-        let genesis_transaction = Transaction {
-            from: genesis_address.clone(),
-            to: recipient_address.clone(),
-            amount: 1,
-            nft_id: None,
-        };
+        let genesis_transaction = Transaction::system(
+            genesis_address.clone(),
+            recipient_address.clone(),
+            1,
+            None,
+        );
 
         let mut balances = HashMap::new();
         balances.insert(genesis_address.clone(), 1000);
         balances.insert(recipient_address, 0);
 
-        let mut block = Block {
-            timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
-            transactions: vec![genesis_transaction],
-            previous_hash: "0".to_string(),
-            hash: "".to_string(),
-            validator: None,
-        };
-
-        block.hash = block.calculate_hash(); // Calcular el hash del bloque génesis
-
-        block
+        // Timestamp fijo: el bloque génesis debe ser idéntico en todos los nodos
+        Block::new(
+            0,
+            GENESIS_TIMESTAMP,
+            vec![genesis_transaction],
+            "0".to_string(),
+            Blockchain::compute_state_root(&balances, &HashMap::new(), &LedgerState::default()),
+            None,
+        )
     }
 
     pub fn get_latest_block(&self) -> &Block {
@@ -130,109 +407,609 @@ impl Blockchain {
     }
 
     pub fn add_transaction(&mut self, transaction: Transaction) -> Result<(), String> {
-        if !transaction.is_valid() {
-            return Err("Transacción inválida".to_string());
-        }
+        reject_fee_distribution(std::slice::from_ref(&transaction))?;
+        let meta = self.new_bundle(TransactionPriority::Normal);
+        self.push_pending(transaction, meta)
+    }
 
-        let sender_balance = self.balances.get(&transaction.from).cloned().unwrap_or(0);
-        if sender_balance < transaction.amount + self.transaction_fees {
-            return Err("Saldo insuficiente".to_string());
+    /// Añadir varias transacciones al mempool como una unidad: o entran todas,
+    /// seguidas y en este orden (y por tanto en el mismo bloque), o ninguna
+    pub fn add_transactions(&mut self, transactions: Vec<Transaction>, priority: TransactionPriority) -> Result<(), String> {
+        reject_fee_distribution(&transactions)?;
+        self.apply_all(&transactions)?;
+        let meta = self.new_bundle(priority);
+        self.pending_meta.extend(std::iter::repeat_n(meta, transactions.len()));
+        self.pending_transactions.extend(transactions);
+        Ok(())
+    }
+
+    /// Aplicar varias transacciones en orden; si una falla se deshacen las anteriores
+    fn apply_all(&mut self, transactions: &[Transaction]) -> Result<(), String> {
+        for (index, transaction) in transactions.iter().enumerate() {
+            if let Err(e) = self.apply_transaction(transaction) {
+                for applied in transactions[..index].iter().rev() {
+                    self.revert_transaction(applied);
+                }
+                return Err(e);
+            }
         }
+        Ok(())
+    }
 
-        self.balances.insert(transaction.from.clone(), sender_balance - transaction.amount - self.transaction_fees);
-        let receiver_balance = self.balances.get(&transaction.to).cloned().unwrap_or(0);
-        self.balances.insert(transaction.to.clone(), receiver_balance + transaction.amount);
+    fn new_bundle(&mut self, priority: TransactionPriority) -> PendingMeta {
+        self.next_bundle += 1;
+        PendingMeta { priority, bundle: self.next_bundle }
+    }
 
+    fn push_pending(&mut self, transaction: Transaction, meta: PendingMeta) -> Result<(), String> {
+        self.apply_transaction(&transaction)?;
         self.pending_transactions.push(transaction);
+        self.pending_meta.push(meta);
         Ok(())
     }
 
-    // Método para agregar un validador
-    pub fn add_validator(&mut self, address: String, stake: u64) -> bool {
-        if stake >= self.minimum_stake {
-            self.validators.insert(address.clone(), stake);
-            true
-        } else {
-            false
+    /// Metadatos del mempool; las transacciones encoladas sin ellos (p. ej.
+    /// restauradas de la base de datos) van con prioridad normal, cada una en su grupo
+    fn pending_meta_or_default(&self) -> Vec<PendingMeta> {
+        if self.pending_meta.len() == self.pending_transactions.len() {
+            return self.pending_meta.clone();
         }
+        (1..=self.pending_transactions.len() as u64)
+            .map(|offset| PendingMeta { priority: TransactionPriority::Normal, bundle: self.next_bundle + offset })
+            .collect()
+    }
+
+    /// Transacciones pendientes con su prioridad y bundle, en orden de llegada
+    pub fn pending_entries(&self) -> Vec<(Transaction, PendingMeta)> {
+        self.pending_transactions.iter().cloned().zip(self.pending_meta_or_default()).collect()
+    }
+
+    /// Último bundle asignado: el de lo que se acaba de añadir al mempool
+    pub fn last_bundle(&self) -> u64 {
+        self.pending_meta_or_default()
+            .iter()
+            .map(|entry| entry.bundle)
+            .fold(self.next_bundle, u64::max)
+    }
+
+    /// Vaciar el mempool (sin deshacer su efecto en el estado) con sus metadatos
+    fn take_pending(&mut self) -> Vec<(Transaction, PendingMeta)> {
+        let meta = self.pending_meta_or_default();
+        self.next_bundle = meta.iter().map(|entry| entry.bundle).fold(self.next_bundle, u64::max);
+        self.pending_meta.clear();
+        std::mem::take(&mut self.pending_transactions).into_iter().zip(meta).collect()
     }
 
-    // Método para registrar un NFT
-    pub fn register_nft(&mut self, creator: String, price: u64, ipfs_hash: Option<String>) -> Result<String, String> {
-        let creator_balance = self.balances.get(&creator).cloned().unwrap_or(0);
-        if creator_balance < price {
-            return Err("Saldo insuficiente para registrar el NFT".to_string());
+    fn restore_pending(&mut self, pending: Vec<(Transaction, PendingMeta)>) {
+        let (transactions, meta) = pending.into_iter().unzip();
+        self.pending_transactions = transactions;
+        self.pending_meta = meta;
+    }
+
+    /// Vista del mempool por prioridad: cada grupo que entró junto es un bundle
+    pub fn mempool(&self) -> OptimizedMempool {
+        let mut mempool = OptimizedMempool::new(MAX_MEMPOOL_SIZE);
+        let mut bundles: Vec<Bundle> = Vec::new();
+        for (transaction, meta) in self.pending_transactions.iter().zip(self.pending_meta_or_default()) {
+            match bundles.last_mut() {
+                Some(bundle) if bundle.seq == meta.bundle => bundle.transactions.push(transaction.clone()),
+                _ => bundles.push(Bundle {
+                    seq: meta.bundle,
+                    priority: meta.priority,
+                    transactions: vec![transaction.clone()],
+                }),
+            }
+        }
+        for bundle in bundles {
+            mempool.add_bundle(bundle);
         }
+        mempool
+    }
 
-        let nft_id = format!("NFT-{}", self.nft_registry.len() + 1);
-        let nft = NFT {
-            id: nft_id.clone(),
-            creator: creator.clone(),  // Clonamos 'creator'
-            owner: creator.clone(),    // Clonamos 'creator'
-            price,
-            ipfs_hash,
-        };
-        self.nft_registry.insert(nft_id.clone(), nft);
-        Ok(nft_id)
+    /// Elegir las transacciones del próximo bloque: por prioridad (FIFO dentro de
+    /// cada una), como mucho `capacity` y sin partir ningún bundle, y cerrar con
+    /// la distribución de las tarifas recaudadas (con `proposer` como validador).
+    /// Devuelve las transacciones y el state root tras aplicarlas sobre el estado
+    /// confirmado; las que no entran siguen pendientes y las que ya no son válidas
+    /// se descartan. Si la distribución de tarifas falla no hay bloque: el mempool
+    /// se restaura y se devuelve el error.
+    pub fn take_block_transactions(&mut self, capacity: usize, proposer: &str) -> Result<(Vec<Transaction>, String), String> {
+        let mut mempool = self.mempool();
+        let pending = self.take_pending();
+        for (transaction, _) in pending.iter().rev() {
+            self.revert_transaction(transaction);
+        }
+
+        // Un bundle que aún no se puede aplicar (p. ej. el nonce siguiente con más
+        // prioridad que el anterior) se reintenta mientras otros sigan entrando
+        let mut transactions = Vec::new();
+        let mut included = HashSet::new();
+        loop {
+            let selected = transactions.len();
+            let mut deferred = Vec::new();
+            while let Some(bundle) = mempool.next_bundle() {
+                if transactions.len() + bundle.transactions.len() > capacity
+                    || self.apply_all(&bundle.transactions).is_err()
+                {
+                    deferred.push(bundle);
+                    continue;
+                }
+                included.insert(bundle.seq);
+                transactions.extend(bundle.transactions);
+            }
+            if transactions.len() == selected || deferred.is_empty() {
+                break;
+            }
+            for bundle in deferred {
+                mempool.add_bundle(bundle);
+            }
+        }
+
+        // Todo lo recaudado hasta aquí (incluidas las tarifas de este bloque) se reparte
+        let collected = self.get_balance(FEE_COLLECTOR);
+        if collected > 0 {
+            let distribution = Transaction::system(FEE_COLLECTOR.to_string(), proposer.to_string(), collected, None)
+                .with_kind(TxKind::FeeDistribution);
+            if let Err(e) = self.apply_transaction(&distribution) {
+                // Sin reparto no hay bloque: se deshace la selección y el mempool queda como estaba
+                tracing::error!(error = %e, collected, proposer, "Distribución de tarifas fallida, bloque descartado");
+                for transaction in transactions.iter().rev() {
+                    self.revert_transaction(transaction);
+                }
+                for (transaction, meta) in pending {
+                    let _ = self.push_pending(transaction, meta);
+                }
+                return Err(format!("Distribución de tarifas fallida: {}", e));
+            }
+            transactions.push(distribution);
+        }
+
+        let state_root = self.state_root();
+        for (transaction, meta) in pending {
+            if !included.contains(&meta.bundle) {
+                let _ = self.push_pending(transaction, meta);
+            }
+        }
+        Ok((transactions, state_root))
     }
 
-    // Método para crear una propuesta de gobernanza
-    pub fn create_proposal(&mut self, proposal_id: String, description: String, end_time: u64) -> Result<(), String> {
-        if self.proposals.contains_key(&proposal_id) {
-            return Err("Propuesta ya existe".to_string());
+    /// Sacar del mempool las transacciones indicadas (p. ej. si su persistencia
+    /// falló). El resto se reaplica sobre el estado confirmado; las que dejen de
+    /// ser válidas se descartan, como al importar un bloque.
+    pub fn remove_pending(&mut self, transactions: &[Transaction]) {
+        let mut to_remove: Vec<String> = transactions.iter().map(Transaction::tx_hash).collect();
+        let pending = self.take_pending();
+        for (transaction, _) in pending.iter().rev() {
+            self.revert_transaction(transaction);
         }
+        // La aparición más reciente de cada una: las transacciones del sistema
+        // idénticas que añadió otra petición se quedan
+        let mut keep = vec![true; pending.len()];
+        for (index, (transaction, _)) in pending.iter().enumerate().rev() {
+            let hash = transaction.tx_hash();
+            if let Some(position) = to_remove.iter().position(|removed| *removed == hash) {
+                to_remove.swap_remove(position);
+                keep[index] = false;
+            }
+        }
+        for ((transaction, meta), keep) in pending.into_iter().zip(keep) {
+            if keep {
+                let _ = self.push_pending(transaction, meta);
+            }
+        }
+    }
 
-        if end_time <= SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() {
-            return Err("Fecha de finalización inválida".to_string());
+    /// Aplicar una transacción al estado (sin encolarla). Único camino de
+    /// ejecución para todos los tipos; si falla, el estado no cambia.
+    fn apply_transaction(&mut self, transaction: &Transaction) -> Result<(), String> {
+        transaction.check_shape().map_err(|e| format!("Transacción inválida: {}", e))?;
+
+        // ✅ SECURITY: Las transacciones firmadas (y los permits) deben usar exactamente el siguiente nonce
+        if let Some(nonce) = transaction.signed_nonce() {
+            let expected = self.next_nonce(&transaction.from);
+            if nonce != expected {
+                return Err(format!("Nonce inválido: esperado {}, recibido {}", expected, nonce));
+            }
         }
 
-        let proposal = Proposal {
-            description,
-            votes_for: 0,
-            votes_against: 0,
-            end_time,
+        let mut state = StateMut {
+            balances: &mut self.balances,
+            nonces: &mut self.nonces,
+            ledger: &mut self.ledger,
         };
-        self.proposals.insert(proposal_id, proposal);
+        state.apply(transaction, self.transaction_fees)
+    }
+
+    /// ¿Existe ya un bloque con este hash en la cadena?
+    pub fn contains_block(&self, hash: &str) -> bool {
+        self.chain.iter().rev().any(|block| block.hash == hash)
+    }
+
+    /// Importar un bloque producido por otro nodo sobre la cabeza actual.
+    ///
+    /// Antes de tocar el estado se autentica el bloque (`BlockVerifier`): firma y
+    /// VRF del proponente con la clave de su cuenta, sorteo del slot según los
+    /// pesos CPV y firma de cada transacción de usuario. Después se aplica como
+    /// `replay_block`.
+    pub fn import_block(&mut self, block: Block, verifier: &BlockVerifier) -> Result<(), String> {
+        let parent_timestamp = self.get_latest_block().timestamp;
+        verifier.verify(&block, parent_timestamp)?;
+        self.replay_block(block)
+    }
+
+    /// Aplicar sobre la cabeza actual un bloque ya autenticado (importado o leído
+    /// de nuestra propia base de datos al arrancar).
+    ///
+    /// El mempool local se deshace, se aplican las transacciones del bloque sobre
+    /// el estado confirmado y el resultado debe coincidir con `block.state_root`.
+    /// Después se reaplican las transacciones pendientes que el bloque no incluye
+    /// (las que ya no son válidas se descartan). Si algo falla, el estado se restaura.
+    pub(crate) fn replay_block(&mut self, block: Block) -> Result<(), String> {
+        let head = self.get_latest_block();
+        if block.height != head.height + 1 {
+            return Err(format!("Altura inesperada: esperada {}, recibida {}", head.height + 1, block.height));
+        }
+        if block.previous_hash != head.hash {
+            return Err("El bloque no extiende la cabeza actual".to_string());
+        }
+        if block.timestamp < head.timestamp {
+            return Err("Timestamp anterior al bloque padre".to_string());
+        }
+        block.verify_structure()?;
 
-        // Agregar una expresión de retorno explícita
+        let balances_snapshot = self.balances.clone();
+        let nonces_snapshot = self.nonces.clone();
+        let ledger_snapshot = self.ledger.clone();
+        let pending = self.take_pending();
+        for (transaction, _) in pending.iter().rev() {
+            self.revert_transaction(transaction);
+        }
+
+        let mut failure = None;
+        for transaction in &block.transactions {
+            if let Err(e) = self.apply_transaction(transaction) {
+                failure = Some(format!("Transacción {} inválida: {}", transaction.tx_hash(), e));
+                break;
+            }
+        }
+        // ✅ SECURITY: El estado resultante debe ser exactamente el que firmó el proponente
+        if failure.is_none() && self.state_root() != block.state_root {
+            failure = Some(format!("State root inválido en el bloque {}", block.height));
+        }
+        if let Some(e) = failure {
+            self.balances = balances_snapshot;
+            self.nonces = nonces_snapshot;
+            self.ledger = ledger_snapshot;
+            self.restore_pending(pending);
+            return Err(e);
+        }
+
+        let included: HashSet<String> = block.transactions.iter().map(Transaction::tx_hash).collect();
+        self.chain.push(block);
+        for (transaction, meta) in pending {
+            if !included.contains(&transaction.tx_hash()) {
+                let _ = self.push_pending(transaction, meta);
+            }
+        }
         Ok(())
     }
 
-    // Método para votar en una propuesta de gobernanza
-    pub fn vote_on_proposal(&mut self, proposal_id: String, vote_for: bool) -> Result<(), String> {
-        let proposal = self.proposals.get_mut(&proposal_id);
-        if let Some(proposal) = proposal {
-            if vote_for {
-                proposal.votes_for += 1;
-            } else {
-                proposal.votes_against += 1;
+    /// Deshacer exactamente lo que hizo `apply_transaction` (en orden inverso)
+    fn revert_transaction(&mut self, transaction: &Transaction) {
+        let mut state = StateMut {
+            balances: &mut self.balances,
+            nonces: &mut self.nonces,
+            ledger: &mut self.ledger,
+        };
+        state.revert(transaction, self.transaction_fees);
+    }
+
+    /// Estado confirmado en la cabeza: balances, nonces y ledger sin el efecto del mempool
+    pub fn committed_state(&self) -> (HashMap<String, u64>, HashMap<String, u64>, LedgerState) {
+        let mut balances = self.balances.clone();
+        let mut nonces = self.nonces.clone();
+        let mut ledger = self.ledger.clone();
+        let mut state = StateMut {
+            balances: &mut balances,
+            nonces: &mut nonces,
+            ledger: &mut ledger,
+        };
+        for transaction in self.pending_transactions.iter().rev() {
+            state.revert(transaction, self.transaction_fees);
+        }
+        (balances, nonces, ledger)
+    }
+
+    /// ¿Conocemos el bloque (canónico o de rama lateral)?
+    pub fn knows_block(&self, hash: &str) -> bool {
+        self.side_blocks.contains_key(hash) || self.contains_block(hash)
+    }
+
+    /// Proponentes de los bloques que la elección de rama puede tener que pesar:
+    /// la ventana de reorganización canónica y las ramas laterales
+    pub fn fork_choice_proposers(&self) -> HashSet<String> {
+        self.chain
+            .iter()
+            .rev()
+            .take(MAX_REORG_DEPTH as usize + 1)
+            .chain(self.side_blocks.values())
+            .filter_map(|block| block.validator.clone())
+            .collect()
+    }
+
+    /// Ofrecer un bloque recibido: extiende la cabeza, se guarda como rama lateral
+    /// o provoca una reorganización si su rama pesa más según la regla CPV.
+    pub fn accept_block(&mut self, block: Block, verifier: &BlockVerifier) -> Result<BlockImport, String> {
+        if self.knows_block(&block.hash) {
+            return Ok(BlockImport::Known);
+        }
+        if block.previous_hash == self.get_latest_block().hash {
+            self.import_block(block, verifier)?;
+            return Ok(BlockImport::Extended);
+        }
+
+        block.verify_structure()?;
+        let parent_timestamp = self
+            .side_blocks
+            .get(&block.previous_hash)
+            .or_else(|| self.chain.iter().rev().find(|candidate| candidate.hash == block.previous_hash))
+            .map(|parent| parent.timestamp)
+            .ok_or_else(|| format!("Bloque padre desconocido: {}", block.previous_hash))?;
+        // ✅ SECURITY: Una rama lateral sólo se guarda (y pesa) si su proponente está autenticado
+        verifier.verify(&block, parent_timestamp)?;
+
+        // Reconstruir la rama lateral hasta el ancestro común canónico
+        let mut branch = vec![block.clone()];
+        let mut ancestor_hash = block.previous_hash.clone();
+        while let Some(parent) = self.side_blocks.get(&ancestor_hash) {
+            branch.push(parent.clone());
+            ancestor_hash = parent.previous_hash.clone();
+        }
+        branch.reverse();
+
+        let ancestor_index = self
+            .chain
+            .iter()
+            .rposition(|candidate| candidate.hash == ancestor_hash)
+            .ok_or_else(|| "La rama no conecta con la cadena canónica".to_string())?;
+        let ancestor_height = self.chain[ancestor_index].height;
+
+        // ✅ CPV: Nunca revertir bloques finalizados
+        if ancestor_height < self.finalized_height {
+            return Err(format!(
+                "La rama entra en conflicto con el bloque finalizado {}",
+                self.finalized_height
+            ));
+        }
+
+        let head_height = self.get_latest_block().height;
+        if head_height - ancestor_height > MAX_REORG_DEPTH {
+            return Err(format!("Reorganización demasiado profunda ({} bloques)", head_height - ancestor_height));
+        }
+        for (offset, branch_block) in branch.iter().enumerate() {
+            if branch_block.height != ancestor_height + 1 + offset as u64 {
+                return Err("Alturas no consecutivas en la rama".to_string());
+            }
+        }
+
+        self.side_blocks.insert(block.hash.clone(), block);
+
+        if !fork_choice::prefer_branch(&branch, &self.chain[ancestor_index + 1..], &self.chain[ancestor_index], verifier) {
+            self.prune_side_blocks();
+            return Ok(BlockImport::SideBranch);
+        }
+
+        self.reorganize(ancestor_index, branch).map(BlockImport::Reorganized)
+    }
+
+    /// Cambiar la cabeza canónica a `branch` (que cuelga de `chain[ancestor_index]`)
+    fn reorganize(&mut self, ancestor_index: usize, branch: Vec<Block>) -> Result<ReorgEvent, String> {
+        let balances_snapshot = self.balances.clone();
+        let nonces_snapshot = self.nonces.clone();
+        let ledger_snapshot = self.ledger.clone();
+        let old_head = self.get_latest_block().hash.clone();
+        let common_ancestor_height = self.chain[ancestor_index].height;
+
+        // 1. Deshacer mempool y bloques huérfanos (más reciente primero)
+        let pending = self.take_pending();
+        for (transaction, _) in pending.iter().rev() {
+            self.revert_transaction(transaction);
+        }
+        let orphaned = self.chain.split_off(ancestor_index + 1);
+        for orphan in orphaned.iter().rev() {
+            for transaction in orphan.transactions.iter().rev() {
+                self.revert_transaction(transaction);
+            }
+        }
+
+        // 2. Aplicar la nueva rama (cada bloque debe llegar a su state root)
+        for branch_block in &branch {
+            let applied = branch_block
+                .transactions
+                .iter()
+                .try_for_each(|transaction| self.apply_transaction(transaction))
+                .and_then(|_| {
+                    if self.state_root() == branch_block.state_root {
+                        Ok(())
+                    } else {
+                        Err("state root inválido".to_string())
+                    }
+                });
+            if let Err(e) = applied {
+                self.balances = balances_snapshot;
+                self.nonces = nonces_snapshot;
+                self.ledger = ledger_snapshot;
+                self.chain.truncate(ancestor_index + 1);
+                self.chain.extend(orphaned);
+                self.restore_pending(pending);
+                if let Some(tip) = branch.last() {
+                    self.side_blocks.remove(&tip.hash);
+                }
+                return Err(format!("Rama inválida en altura {}: {}", branch_block.height, e));
+            }
+            self.side_blocks.remove(&branch_block.hash);
+            self.chain.push(branch_block.clone());
+        }
+
+        // 3. Reencolar transacciones huérfanas que la nueva rama no incluye
+        let included: HashSet<String> = branch
+            .iter()
+            .flat_map(|branch_block| branch_block.transactions.iter().map(Transaction::tx_hash))
+            .collect();
+        let mut dropped_transactions = 0;
+        let requeue = orphaned
+            .iter()
+            .flat_map(|orphan| orphan.transactions.iter().cloned().map(|transaction| (transaction, None)))
+            .chain(pending.into_iter().map(|(transaction, meta)| (transaction, Some(meta))));
+        for (transaction, meta) in requeue {
+            // La distribución de tarifas es del bloque huérfano: la nueva rama reparte lo suyo
+            if included.contains(&transaction.tx_hash()) || matches!(transaction.kind, TxKind::FeeDistribution) {
+                continue;
+            }
+            let meta = meta.unwrap_or_else(|| self.new_bundle(TransactionPriority::Normal));
+            if self.push_pending(transaction, meta).is_err() {
+                dropped_transactions += 1;
             }
-            Ok(())
+        }
+
+        let event = ReorgEvent {
+            old_head,
+            new_head: self.get_latest_block().hash.clone(),
+            common_ancestor_height,
+            orphaned_blocks: orphaned.iter().map(|orphan| orphan.hash.clone()).collect(),
+            adopted_blocks: branch.iter().map(|branch_block| branch_block.hash.clone()).collect(),
+            dropped_transactions,
+        };
+        for orphan in orphaned {
+            self.side_blocks.insert(orphan.hash.clone(), orphan);
+        }
+        self.prune_side_blocks();
+
+        Ok(event)
+    }
+
+    /// ✅ CPV: Marcar como final un bloque canónico (y con él todos sus ancestros)
+    pub fn finalize(&mut self, height: u64, hash: &str) -> Result<(), String> {
+        if height <= self.finalized_height {
+            return Err(format!("La altura {} ya es final", height));
+        }
+        match self.get_block(height) {
+            Some(block) if block.hash == hash => {}
+            _ => return Err(format!("El bloque {} no es canónico en la altura {}", hash, height)),
+        }
+        self.finalized_height = height;
+        self.finalized_hash = hash.to_string();
+        self.prune_side_blocks();
+        Ok(())
+    }
+
+    /// Créditos recibidos por una cuenta que aún no son finales (bloques no
+    /// finalizados + mempool). Los pagos y retiros no pueden gastarlos.
+    pub fn unfinalized_credits(&self, address: &str) -> u64 {
+        self.chain
+            .iter()
+            .filter(|block| block.height > self.finalized_height)
+            .flat_map(|block| block.transactions.iter())
+            .chain(self.pending_transactions.iter())
+            .fold(0u64, |total, transaction| total.saturating_add(transaction.credited_to(address)))
+    }
+
+    /// Olvidar ramas laterales que ya no pueden reorganizar la cadena
+    fn prune_side_blocks(&mut self) {
+        let min_height = self
+            .get_latest_block()
+            .height
+            .saturating_sub(MAX_REORG_DEPTH)
+            .max(self.finalized_height);
+        self.side_blocks.retain(|_, block| block.height > min_height);
+    }
+
+    /// Siguiente nonce esperado para una cuenta (el primero es 1)
+    pub fn next_nonce(&self, address: &str) -> u64 {
+        self.nonces.get(address).copied().unwrap_or(0) + 1
+    }
+
+    // Método para agregar un validador
+    pub fn add_validator(&mut self, address: String, stake: u64) -> bool {
+        if stake >= self.minimum_stake {
+            self.validators.insert(address.clone(), stake);
+            true
         } else {
-            Err("Propuesta no encontrada".to_string())
+            false
         }
     }
 
+    // Método para registrar un NFT (se acuña con una transacción NftMint del creador)
+    pub fn register_nft(&mut self, creator: String, ipfs_hash: Option<String>) -> Result<String, String> {
+        let nft_id = format!("NFT-{}", self.ledger.nfts.len() + 1);
+        let mint = Transaction::system(creator.clone(), creator, 0, Some(nft_id.clone()))
+            .with_kind(TxKind::NftMint { ipfs_hash });
+        self.add_transaction(mint)?;
+        Ok(nft_id)
+    }
+
     // Método de validación de la cadena
     fn is_block_valid(&self, current_block: &Block, previous_block: &Block) -> bool {
+        current_block.merkle_root == current_block.compute_merkle_root() &&
         current_block.hash == current_block.calculate_hash() &&
-        current_block.previous_hash == previous_block.hash
+        current_block.previous_hash == previous_block.hash &&
+        current_block.height == previous_block.height + 1
     }
     
     pub fn is_chain_valid(&self) -> bool {
-        for i in 1..self.chain.len() {
-            let current_block = &self.chain[i];
-            let previous_block = &self.chain[i - 1];
+        self.chain
+            .windows(2)
+            .all(|pair| self.is_block_valid(&pair[1], &pair[0]))
+    }
 
-            if current_block.hash != current_block.calculate_hash() {
-                return false;
-            }
+    /// Bloque por altura
+    pub fn get_block(&self, height: u64) -> Option<&Block> {
+        self.chain.iter().find(|block| block.height == height)
+    }
 
-            if current_block.previous_hash != previous_block.hash {
-                return false;
-            }
+    /// State root sobre balances y nonces ordenados por dirección, más el ledger
+    /// (stakes, NFTs, votos) si no está vacío
+    pub fn compute_state_root(
+        balances: &HashMap<String, u64>,
+        nonces: &HashMap<String, u64>,
+        ledger: &LedgerState,
+    ) -> String {
+        // Cuentas a cero no forman parte del estado (una reversión puede dejarlas vacías)
+        let mut accounts: Vec<(&String, &u64)> = balances.iter().filter(|(_, balance)| **balance > 0).collect();
+        accounts.sort();
+        let mut account_nonces: Vec<(&String, &u64)> = nonces.iter().collect();
+        account_nonces.sort();
+
+        let mut hasher = Sha256::new();
+        hasher.update(STATE_ROOT_DOMAIN);
+        for (address, balance) in accounts {
+            hasher.update((address.len() as u32).to_be_bytes());
+            hasher.update(address.as_bytes());
+            hasher.update(balance.to_be_bytes());
+        }
+        for (address, nonce) in account_nonces {
+            hasher.update((address.len() as u32).to_be_bytes());
+            hasher.update(address.as_bytes());
+            hasher.update(nonce.to_be_bytes());
         }
-        true
+        // Sin stakes/NFTs/votos el root es el mismo que antes de existir el ledger
+        if !ledger.is_empty() {
+            let mut encoded = Vec::new();
+            ledger.encode(&mut encoded);
+            hasher.update(encoded);
+        }
+        hex::encode(hasher.finalize())
+    }
+
+    /// State root del estado actual
+    pub fn state_root(&self) -> String {
+        Blockchain::compute_state_root(&self.balances, &self.nonces, &self.ledger)
+    }
+
+    /// State root del estado confirmado (el que se guarda en snapshots)
+    pub fn committed_state_root(&self) -> String {
+        let (balances, nonces, ledger) = self.committed_state();
+        Blockchain::compute_state_root(&balances, &nonces, &ledger)
     }
 
     // Implementación del método get_balance
@@ -241,4 +1018,262 @@ impl Blockchain {
     }
 }
 
-// JSON-RPC server removed - use HTTP RPC server instead
\ No newline at end of file
+// JSON-RPC server removed - use HTTP RPC server instead
+
+/// Las distribuciones de tarifas solo las crea el proponente al cerrar su bloque
+fn reject_fee_distribution(transactions: &[Transaction]) -> Result<(), String> {
+    if transactions.iter().any(|transaction| matches!(transaction.kind, TxKind::FeeDistribution)) {
+        return Err("La distribución de tarifas no puede entrar en el mempool".to_string());
+    }
+    Ok(())
+}
+
+/// Vista mutable del estado sobre la que se ejecutan (y revierten) las transacciones
+struct StateMut<'a> {
+    balances: &'a mut HashMap<String, u64>,
+    nonces: &'a mut HashMap<String, u64>,
+    ledger: &'a mut LedgerState,
+}
+
+impl StateMut<'_> {
+    fn balance(&self, address: &str) -> u64 {
+        self.balances.get(address).copied().unwrap_or(0)
+    }
+
+    fn credit(&mut self, address: &str, amount: u64) {
+        let balance = self.balance(address) + amount;
+        self.balances.insert(address.to_string(), balance);
+    }
+
+    fn debit(&mut self, address: &str, amount: u64) {
+        let balance = self.balance(address).saturating_sub(amount);
+        if balance == 0 {
+            self.balances.remove(address);
+        } else {
+            self.balances.insert(address.to_string(), balance);
+        }
+    }
+
+    /// Ejecutar una transacción: primero todas las comprobaciones, después los cambios
+    fn apply(&mut self, transaction: &Transaction, fee: u64) -> Result<(), String> {
+        let from = transaction.from.as_str();
+        let to = transaction.to.as_str();
+        let amount = transaction.amount;
+        let nft_id = transaction.nft_id.as_deref().unwrap_or("");
+
+        // Lo que sale del saldo de `from` (importe + tarifa) según el tipo
+        let sender_cost = transaction.kind.sender_cost(amount, fee);
+        let sender_credit = match &transaction.kind {
+            TxKind::Unstake => amount,
+            _ => 0,
+        };
+        if self.balance(from) + sender_credit < sender_cost {
+            return Err("Saldo insuficiente".to_string());
+        }
+
+        match &transaction.kind {
+            TxKind::Unstake if self.ledger.stake_of(from) < amount => {
+                return Err("Stake insuficiente".to_string());
+            }
+            // ✅ SECURITY: Un swap sólo mueve la cuenta de su propio pool
+            TxKind::Swap { pool_id, .. } if to != pool_account(pool_id) => {
+                return Err(format!("El swap debe ir contra la cuenta del pool {}", pool_id));
+            }
+            TxKind::Swap { token_in, token_out, amount_out, .. } if token_out == NATIVE_TOKEN => {
+                let pool_balance = self.balance(to) + if token_in == NATIVE_TOKEN { amount } else { 0 };
+                if pool_balance < *amount_out {
+                    return Err("Liquidez insuficiente en el pool".to_string());
+                }
+            }
+            TxKind::NftMint { .. } if self.ledger.nfts.contains_key(nft_id) => {
+                return Err(format!("El NFT {} ya existe", nft_id));
+            }
+            TxKind::NftTransfer => match self.ledger.nfts.get(nft_id) {
+                Some(record) if record.owner == from => {}
+                Some(_) => return Err(format!("{} no es el propietario de {}", from, nft_id)),
+                None => return Err(format!("NFT {} desconocido", nft_id)),
+            },
+            TxKind::GovernanceVote { proposal_id, .. }
+                if self.ledger.votes.get(proposal_id).is_some_and(|voters| voters.contains_key(from)) =>
+            {
+                return Err(format!("{} ya votó en la propuesta {}", from, proposal_id));
+            }
+            TxKind::FeeDistribution if self.balance(from) != amount => {
+                return Err(format!("La distribución debe repartir todo lo recaudado ({})", self.balance(from)));
+            }
+            TxKind::Approve { previous } | TxKind::Permit { previous, .. } if self.ledger.allowance(from, to) != *previous => {
+                return Err(format!(
+                    "El allowance de {} cambió: esperado {}, actual {}",
+                    to,
+                    previous,
+                    self.ledger.allowance(from, to)
+                ));
+            }
+            TxKind::TransferFrom { spender } if self.ledger.allowance(from, spender) < amount => {
+                return Err(format!("Allowance insuficiente de {} sobre {}", spender, from));
+            }
+            _ => {}
+        }
+
+        if let Some(nonce) = transaction.signed_nonce() {
+            self.nonces.insert(transaction.from.clone(), nonce);
+        }
+        // Mismo orden de escritura que la transferencia original: emisor y después receptor
+        let sender_balance = self.balance(from) + sender_credit - sender_cost;
+        self.balances.insert(transaction.from.clone(), sender_balance);
+
+        match &transaction.kind {
+            TxKind::Transfer
+            | TxKind::Tip { .. }
+            | TxKind::StreamEarn { .. }
+            | TxKind::AddLiquidity { .. }
+            | TxKind::RemoveLiquidity { .. } => self.credit(to, amount),
+            TxKind::Swap { token_in, token_out, amount_out, .. } => {
+                if token_in == NATIVE_TOKEN {
+                    self.credit(to, amount);
+                }
+                if token_out == NATIVE_TOKEN {
+                    self.debit(to, *amount_out);
+                    self.credit(from, *amount_out);
+                }
+            }
+            TxKind::Stake => {
+                *self.ledger.stakes.entry(transaction.from.clone()).or_insert(0) += amount;
+            }
+            TxKind::Unstake => {
+                let remaining = self.ledger.stake_of(from) - amount;
+                if remaining == 0 {
+                    self.ledger.stakes.remove(from);
+                } else {
+                    self.ledger.stakes.insert(transaction.from.clone(), remaining);
+                }
+            }
+            TxKind::NftMint { ipfs_hash } => {
+                self.ledger.nfts.insert(
+                    nft_id.to_string(),
+                    NftRecord {
+                        creator: transaction.from.clone(),
+                        owner: transaction.to.clone(),
+                        ipfs_hash: ipfs_hash.clone(),
+                    },
+                );
+            }
+            TxKind::NftTransfer => {
+                if let Some(record) = self.ledger.nfts.get_mut(nft_id) {
+                    record.owner = transaction.to.clone();
+                }
+            }
+            TxKind::GovernanceVote { proposal_id, support } => {
+                self.ledger
+                    .votes
+                    .entry(proposal_id.clone())
+                    .or_default()
+                    .insert(transaction.from.clone(), *support);
+            }
+            TxKind::FeeDistribution => {
+                for (account, share) in fee_payouts(to, amount).1 {
+                    if share > 0 {
+                        self.credit(account, share);
+                    }
+                }
+            }
+            TxKind::Approve { .. } | TxKind::Permit { .. } => self.ledger.set_allowance(from, to, amount),
+            TxKind::TransferFrom { spender } => {
+                let remaining = self.ledger.allowance(from, spender) - amount;
+                self.ledger.set_allowance(from, spender, remaining);
+                self.credit(to, amount);
+            }
+        }
+        Ok(())
+    }
+
+    /// Inverso exacto de `apply` (en orden inverso)
+    fn revert(&mut self, transaction: &Transaction, fee: u64) {
+        let from = transaction.from.as_str();
+        let to = transaction.to.as_str();
+        let amount = transaction.amount;
+        let nft_id = transaction.nft_id.as_deref().unwrap_or("");
+
+        match &transaction.kind {
+            TxKind::Transfer
+            | TxKind::Tip { .. }
+            | TxKind::StreamEarn { .. }
+            | TxKind::AddLiquidity { .. } => {
+                self.debit(to, amount);
+                self.credit(from, amount + fee);
+            }
+            TxKind::RemoveLiquidity { .. } => {
+                self.debit(to, amount);
+                self.credit(from, amount);
+            }
+            TxKind::Swap { token_in, token_out, amount_out, .. } => {
+                if token_out == NATIVE_TOKEN {
+                    self.debit(from, *amount_out);
+                    self.credit(to, *amount_out);
+                }
+                if token_in == NATIVE_TOKEN {
+                    self.debit(to, amount);
+                    self.credit(from, amount);
+                }
+                self.credit(from, fee);
+            }
+            TxKind::Stake => {
+                let remaining = self.ledger.stake_of(from).saturating_sub(amount);
+                if remaining == 0 {
+                    self.ledger.stakes.remove(from);
+                } else {
+                    self.ledger.stakes.insert(transaction.from.clone(), remaining);
+                }
+                self.credit(from, amount + fee);
+            }
+            TxKind::Unstake => {
+                *self.ledger.stakes.entry(transaction.from.clone()).or_insert(0) += amount;
+                self.credit(from, fee);
+                self.debit(from, amount);
+            }
+            TxKind::NftMint { .. } => {
+                self.ledger.nfts.remove(nft_id);
+                self.credit(from, fee);
+            }
+            TxKind::NftTransfer => {
+                if let Some(record) = self.ledger.nfts.get_mut(nft_id) {
+                    record.owner = transaction.from.clone();
+                }
+                self.credit(from, fee);
+            }
+            TxKind::GovernanceVote { proposal_id, .. } => {
+                if let Some(voters) = self.ledger.votes.get_mut(proposal_id) {
+                    voters.remove(from);
+                    if voters.is_empty() {
+                        self.ledger.votes.remove(proposal_id);
+                    }
+                }
+                self.credit(from, fee);
+            }
+            TxKind::FeeDistribution => {
+                for (account, share) in fee_payouts(to, amount).1.iter().rev() {
+                    self.debit(account, *share);
+                }
+                self.credit(from, amount);
+            }
+            TxKind::Approve { previous } | TxKind::Permit { previous, .. } => {
+                self.ledger.set_allowance(from, to, *previous);
+                self.credit(from, fee);
+            }
+            TxKind::TransferFrom { spender } => {
+                self.debit(to, amount);
+                let allowance = self.ledger.allowance(from, spender) + amount;
+                self.ledger.set_allowance(from, spender, allowance);
+                self.credit(from, amount + fee);
+            }
+        }
+
+        if let Some(nonce) = transaction.signed_nonce() {
+            if nonce <= 1 {
+                self.nonces.remove(&transaction.from);
+            } else {
+                self.nonces.insert(transaction.from.clone(), nonce - 1);
+            }
+        }
+    }
+}
@@ -0,0 +1,226 @@
+//! Fee Market
+//!
+//! Dynamic gas pricing on top of `GasFeeCalculator`:
+//! - Congestion (0..1) from the mempool backlog and the fullness of recent blocks,
+//!   fed into `NetworkState` (the calculator's 0.5x-2x congestion multiplier)
+//! - EIP-1559 style base fee multiplier that moves up to 1/8 per block toward
+//!   the target block size; derived from the chain itself, so every node agrees
+//! - Optional priority tips mapped to `TransactionPriority` tiers
+
+use serde::{Deserialize, Serialize};
+
+use crate::blockchain::blockchain::Block;
+use crate::blockchain::gas_fees::NetworkState;
+use crate::blockchain::mempool::{MempoolStats, TransactionPriority};
+use crate::utils::amount::{Amount, CENT_DECIMALS};
+
+/// Most transactions a produced block includes
+pub const MAX_BLOCK_TRANSACTIONS: usize = 1_000;
+
+/// Block size the base fee steers toward (half the capacity, as EIP-1559)
+pub const TARGET_BLOCK_TRANSACTIONS: usize = MAX_BLOCK_TRANSACTIONS / 2;
+
+/// Base fee multiplier bounds, in basis points of the calculated gas fee (1x..10x)
+pub const MIN_BASE_FEE_BPS: u64 = 10_000;
+pub const MAX_BASE_FEE_BPS: u64 = 100_000;
+
+/// Max change of the base fee per block: 1/8 (12.5%)
+const BASE_FEE_CHANGE_DENOMINATOR: u64 = 8;
+
+/// Blocks replayed to derive the base fee (older history has decayed to the floor
+/// or ceiling long before)
+const BASE_FEE_WINDOW: usize = 64;
+
+/// Blocks whose fullness counts as "recent"
+const FULLNESS_WINDOW: usize = 8;
+
+const DAY_SECS: u64 = 86_400;
+
+/// Base fee multiplier after a block with `block_transactions` transactions
+pub fn next_base_fee_bps(parent_bps: u64, block_transactions: usize) -> u64 {
+    let used = block_transactions as u64;
+    let target = TARGET_BLOCK_TRANSACTIONS as u64;
+    let next = if used > target {
+        let delta = (parent_bps * (used - target) / target / BASE_FEE_CHANGE_DENOMINATOR).max(1);
+        parent_bps.saturating_add(delta)
+    } else {
+        let delta = parent_bps * (target - used) / target / BASE_FEE_CHANGE_DENOMINATOR;
+        parent_bps.saturating_sub(delta)
+    };
+    next.clamp(MIN_BASE_FEE_BPS, MAX_BASE_FEE_BPS)
+}
+
+/// Priority a transaction gets for its tip: none = low, any = normal, at least
+/// the gas fee (min 1 cent) = high
+pub fn tip_priority(tip_cents: u64, gas_fee_cents: u64) -> TransactionPriority {
+    if tip_cents == 0 {
+        TransactionPriority::Low
+    } else if tip_cents >= gas_fee_cents.max(1) {
+        TransactionPriority::High
+    } else {
+        TransactionPriority::Normal
+    }
+}
+
+/// One fee option offered to wallets
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeeTier {
+    pub priority: TransactionPriority,
+    pub tip_cents: u64,
+    /// Value to sign as the envelope's maximum fee (gas fee + tip)
+    pub max_fee_cents: u64,
+}
+
+/// Fee market at the current head
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeeMarket {
+    /// Multiplier applied to calculated gas fees, in basis points
+    pub base_fee_bps: u64,
+    /// Average fullness of the recent blocks (0..1)
+    pub recent_fullness: f64,
+    /// Transactions included in blocks over the last 24 hours
+    pub daily_transactions: u64,
+    /// DYO those transactions moved
+    pub daily_volume: Amount,
+}
+
+impl FeeMarket {
+    /// Derive the market from the canonical chain (genesis excluded)
+    pub fn from_chain(chain: &[Block], now: u64) -> Self {
+        let blocks = match chain.split_first() {
+            Some((genesis, rest)) if genesis.height == 0 => rest,
+            _ => chain,
+        };
+
+        let base_fee_bps = blocks[blocks.len().saturating_sub(BASE_FEE_WINDOW)..]
+            .iter()
+            .fold(MIN_BASE_FEE_BPS, |bps, block| next_base_fee_bps(bps, block.transactions.len()));
+
+        let recent = &blocks[blocks.len().saturating_sub(FULLNESS_WINDOW)..];
+        let recent_fullness = if recent.is_empty() {
+            0.0
+        } else {
+            let filled: f64 = recent
+                .iter()
+                .map(|block| (block.transactions.len() as f64 / MAX_BLOCK_TRANSACTIONS as f64).min(1.0))
+                .sum();
+            filled / recent.len() as f64
+        };
+
+        let daily = blocks
+            .iter()
+            .rev()
+            .take_while(|block| block.timestamp + DAY_SECS >= now);
+        let daily_transactions = daily.clone().map(|block| block.transactions.len() as u64).sum();
+        let daily_cents = daily
+            .flat_map(|block| block.transactions.iter())
+            .fold(0u64, |cents, tx| cents.saturating_add(tx.amount));
+
+        FeeMarket {
+            base_fee_bps,
+            recent_fullness,
+            daily_transactions,
+            daily_volume: Amount::from_cents(daily_cents),
+        }
+    }
+
+    /// Congestion level (0..1): the larger of the mempool backlog (in blocks'
+    /// worth of transactions) and the recent block fullness
+    pub fn congestion_level(&self, mempool: &MempoolStats) -> f64 {
+        let backlog = mempool.total_transactions as f64 / MAX_BLOCK_TRANSACTIONS as f64;
+        backlog.max(self.recent_fullness).clamp(0.0, 1.0)
+    }
+
+    /// Network state for `GasFeeCalculator`
+    pub fn network_state(&self, mempool: &MempoolStats, dyo_price_usd: Amount) -> NetworkState {
+        NetworkState {
+            congestion_level: self.congestion_level(mempool),
+            dyo_price_usd,
+            daily_volume: self.daily_volume,
+        }
+    }
+
+    /// Gas fee in ledger cents: the calculated fee times the base fee multiplier
+    /// (rounded up, so a nonzero fee never drops to 0; saturates on overflow)
+    pub fn gas_fee_cents(&self, gas_fee_dyo: Amount) -> u64 {
+        gas_fee_dyo
+            .mul_bps(self.base_fee_bps)
+            .and_then(|fee| fee.round_up_to(CENT_DECIMALS))
+            .and_then(|fee| fee.to_cents())
+            .unwrap_or(u64::MAX)
+    }
+
+    /// Slow / standard / fast options for a gas fee
+    pub fn fee_tiers(gas_fee_cents: u64) -> Vec<FeeTier> {
+        [0, (gas_fee_cents / 10).max(1), gas_fee_cents.max(1)]
+            .into_iter()
+            .map(|tip_cents| FeeTier {
+                priority: tip_priority(tip_cents, gas_fee_cents),
+                tip_cents,
+                max_fee_cents: gas_fee_cents.saturating_add(tip_cents),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::blockchain::Transaction;
+
+    fn block(height: u64, timestamp: u64, transactions: usize) -> Block {
+        let transactions = (0..transactions)
+            .map(|i| Transaction::system("DUalice".to_string(), "DUbob".to_string(), i as u64 + 1, None))
+            .collect();
+        Block::new(height, timestamp, transactions, String::new(), String::new(), None)
+    }
+
+    #[test]
+    fn test_base_fee_follows_block_fullness() {
+        assert_eq!(next_base_fee_bps(MIN_BASE_FEE_BPS, TARGET_BLOCK_TRANSACTIONS), MIN_BASE_FEE_BPS);
+        // Full block: +12.5%; empty block: -12.5% (never below the floor)
+        assert_eq!(next_base_fee_bps(20_000, MAX_BLOCK_TRANSACTIONS), 22_500);
+        assert_eq!(next_base_fee_bps(20_000, 0), 17_500);
+        assert_eq!(next_base_fee_bps(MIN_BASE_FEE_BPS, 0), MIN_BASE_FEE_BPS);
+        assert_eq!(next_base_fee_bps(MAX_BASE_FEE_BPS, MAX_BLOCK_TRANSACTIONS), MAX_BASE_FEE_BPS);
+
+        let mut chain = vec![block(0, 0, 1)];
+        chain.extend((1..=3).map(|height| block(height, 1_000 + height, MAX_BLOCK_TRANSACTIONS)));
+        let market = FeeMarket::from_chain(&chain, 1_010);
+        assert_eq!(market.base_fee_bps, 14_238);
+        assert_eq!(market.recent_fullness, 1.0);
+        assert_eq!(market.daily_transactions, 3 * MAX_BLOCK_TRANSACTIONS as u64);
+        // Each block moves 1 + 2 + ... + 1000 cents
+        assert_eq!(market.daily_volume, Amount::from_cents(3 * 500_500));
+        assert_eq!(market.gas_fee_cents(Amount::ONE), 143);
+
+        // Blocks older than a day no longer count as volume
+        let stale = FeeMarket::from_chain(&chain, 1_003 + DAY_SECS + 1);
+        assert_eq!((stale.daily_transactions, stale.daily_volume), (0, Amount::ZERO));
+    }
+
+    #[test]
+    fn test_congestion_and_tip_tiers() {
+        let market = FeeMarket {
+            base_fee_bps: MIN_BASE_FEE_BPS,
+            recent_fullness: 0.25,
+            daily_transactions: 0,
+            daily_volume: Amount::ZERO,
+        };
+        let mut stats = MempoolStats::default();
+        assert_eq!(market.congestion_level(&stats), 0.25);
+        stats.total_transactions = MAX_BLOCK_TRANSACTIONS * 3;
+        assert_eq!(market.congestion_level(&stats), 1.0);
+
+        let tiers = FeeMarket::fee_tiers(40);
+        let priorities: Vec<_> = tiers.iter().map(|tier| tier.priority).collect();
+        assert_eq!(priorities, vec![TransactionPriority::Low, TransactionPriority::Normal, TransactionPriority::High]);
+        assert_eq!(tiers[1].max_fee_cents, 44);
+        assert_eq!(tiers[2].max_fee_cents, 80);
+        assert_eq!(tip_priority(1, 0), TransactionPriority::High);
+
+        // A saturated gas fee keeps saturating instead of overflowing with the tip
+        let tiers = FeeMarket::fee_tiers(u64::MAX);
+        assert!(tiers.iter().all(|tier| tier.max_fee_cents == u64::MAX));
+    }
+}
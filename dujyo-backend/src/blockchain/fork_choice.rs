@@ -0,0 +1,187 @@
+//! Fork Choice for CPV Block Production
+//!
+//! Competing branches are compared from their common ancestor. The weight of a
+//! branch is the sum of the CPV weights of its block proposers (a proposer that
+//! is not an active CPV validator, e.g. the bootstrap "system" proposer, weighs
+//! 1), so a branch built by higher-scored validators wins over a longer branch
+//! built by low-score ones. Only authenticated blocks count: a block whose
+//! proposer signature or slot VRF does not verify against the proposer's bound
+//! key weighs nothing, so naming a heavy validator as proposer buys no weight.
+//! Ties go to the longer branch, then to the lowest tip hash, so every node
+//! picks the same head from the same set of blocks.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::blockchain::block_verifier::BlockVerifier;
+use crate::blockchain::blockchain::Block;
+
+/// Deepest reorganization a node accepts (blocks behind the head)
+pub const MAX_REORG_DEPTH: u64 = 64;
+
+/// Proposer address -> CPV weight (snapshot taken from `CPVConsensus`)
+pub type ProposerWeights = HashMap<String, u64>;
+
+/// Outcome of offering a block to the chain
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockImport {
+    /// Already known (canonical or side branch)
+    Known,
+    /// Appended on top of the current head
+    Extended,
+    /// Stored as a side branch that does not outweigh the canonical chain
+    SideBranch,
+    /// The canonical head switched to another branch
+    Reorganized(ReorgEvent),
+}
+
+/// Canonical head switch, emitted to websocket clients and persisted
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReorgEvent {
+    pub old_head: String,
+    pub new_head: String,
+    pub common_ancestor_height: u64,
+    pub orphaned_blocks: Vec<String>,
+    pub adopted_blocks: Vec<String>,
+    pub dropped_transactions: usize, // Orphaned txs no longer valid on the new branch
+}
+
+impl ReorgEvent {
+    pub fn depth(&self) -> u64 {
+        self.orphaned_blocks.len() as u64
+    }
+}
+
+/// CPV weight of a block proposer
+pub fn proposer_weight(weights: &ProposerWeights, proposer: Option<&str>) -> u64 {
+    proposer
+        .and_then(|address| weights.get(address))
+        .copied()
+        .unwrap_or(1)
+        .max(1)
+}
+
+/// CPV weight of a block on top of a parent with `parent_timestamp` (0 unless its
+/// proposer signature and slot VRF verify)
+pub fn block_weight(block: &Block, parent_timestamp: u64, verifier: &BlockVerifier) -> u64 {
+    match verifier.verify_proposer(block, parent_timestamp) {
+        Ok(()) => proposer_weight(&verifier.weights, block.validator.as_deref()),
+        Err(_) => 0,
+    }
+}
+
+/// Total CPV weight of a branch hanging from `ancestor`
+pub fn branch_weight(blocks: &[Block], ancestor: &Block, verifier: &BlockVerifier) -> u64 {
+    let mut parent_timestamp = ancestor.timestamp;
+    blocks.iter().fold(0u64, |total, block| {
+        let weight = block_weight(block, parent_timestamp, verifier);
+        parent_timestamp = block.timestamp;
+        total.saturating_add(weight)
+    })
+}
+
+/// Whether `candidate` should replace `canonical` (both hanging from `ancestor`)
+pub fn prefer_branch(candidate: &[Block], canonical: &[Block], ancestor: &Block, verifier: &BlockVerifier) -> bool {
+    let (Some(candidate_tip), Some(canonical_tip)) = (candidate.last(), canonical.last()) else {
+        return canonical.is_empty() && !candidate.is_empty();
+    };
+
+    let candidate_weight = branch_weight(candidate, ancestor, verifier);
+    let canonical_weight = branch_weight(canonical, ancestor, verifier);
+    if candidate_weight != canonical_weight {
+        return candidate_weight > canonical_weight;
+    }
+    if candidate.len() != canonical.len() {
+        return candidate.len() > canonical.len();
+    }
+    candidate_tip.hash < canonical_tip.hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::proposer;
+    use ed25519_dalek::SigningKey;
+
+    const ANCESTOR_TIMESTAMP: u64 = 1_700_000_000;
+
+    fn key(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    fn ancestor() -> Block {
+        let mut ancestor = Block::new(4, ANCESTOR_TIMESTAMP, vec![], "root".to_string(), "state".to_string(), None);
+        ancestor.hash = "parent".to_string();
+        ancestor
+    }
+
+    /// Block at `height` sealed by `signer` (the slot VRF too); the hash is forced for tie-break tests
+    fn block(height: u64, proposer: &str, signer: &SigningKey, hash: &str) -> Block {
+        let timestamp = ANCESTOR_TIMESTAMP + 10 * (height - 4);
+        let mut block = Block::new(height, timestamp, vec![], "parent".to_string(), "state".to_string(), Some(proposer.to_string()));
+        let parent_timestamp = timestamp - 10;
+        let seed = proposer::slot_seed(height, "parent", proposer::slot_round(parent_timestamp, timestamp));
+        block.seal(Some(proposer::prove_slot(signer, &seed)), signer);
+        block.hash = hash.to_string();
+        // The forced hash is what the proposer signs
+        block.proposer_signature = Some(proposer::sign_block_hash(signer, hash));
+        block
+    }
+
+    fn verifier(weights: ProposerWeights) -> BlockVerifier {
+        let mut verifier = BlockVerifier::new("dujyo-test".to_string(), weights);
+        for (address, seed) in [("system", 1u8), ("DUcreative", 2), ("DUnobody", 3)] {
+            verifier.insert_key(address.to_string(), hex::encode(key(seed).verifying_key().to_bytes()));
+        }
+        verifier
+    }
+
+    #[test]
+    fn test_heavier_branch_wins_over_longer() {
+        let mut weights = ProposerWeights::new();
+        weights.insert("DUcreative".to_string(), 50_000);
+        let verifier = verifier(weights);
+        let candidate = vec![block(5, "DUcreative", &key(2), "bb")];
+        let canonical = vec![block(5, "system", &key(1), "aa"), block(6, "system", &key(1), "cc")];
+        assert!(prefer_branch(&candidate, &canonical, &ancestor(), &verifier));
+        assert!(!prefer_branch(&canonical, &candidate, &ancestor(), &verifier));
+    }
+
+    #[test]
+    fn test_forged_heavy_branch_weighs_nothing() {
+        let mut weights = ProposerWeights::new();
+        weights.insert("DUcreative".to_string(), 50_000);
+        let verifier = verifier(weights);
+
+        // Claims the heavy validator as proposer but is sealed with another key
+        let forged = vec![block(5, "DUcreative", &key(9), "bb")];
+        let canonical = vec![block(5, "system", &key(1), "cc")];
+        assert_eq!(branch_weight(&forged, &ancestor(), &verifier), 0);
+        assert!(!prefer_branch(&forged, &canonical, &ancestor(), &verifier));
+
+        // Right key, but a VRF evaluated for another slot
+        let mut replayed = block(5, "DUcreative", &key(2), "bb");
+        replayed.vrf = Some(proposer::prove_slot(&key(2), &proposer::slot_seed(5, "other", 1)));
+        assert_eq!(branch_weight(&[replayed], &ancestor(), &verifier), 0);
+    }
+
+    #[test]
+    fn test_equal_weight_prefers_longer_then_lowest_hash() {
+        let verifier = verifier(ProposerWeights::new());
+        let short = vec![block(5, "system", &key(1), "aa")];
+        let long = vec![block(5, "system", &key(1), "bb"), block(6, "system", &key(1), "cc")];
+        assert!(prefer_branch(&long, &short, &ancestor(), &verifier));
+
+        let a = vec![block(5, "system", &key(1), "aa")];
+        let b = vec![block(5, "system", &key(1), "bb")];
+        assert!(prefer_branch(&a, &b, &ancestor(), &verifier));
+        assert!(!prefer_branch(&b, &a, &ancestor(), &verifier));
+    }
+
+    #[test]
+    fn test_unknown_proposer_weighs_one() {
+        let weights = ProposerWeights::new();
+        assert_eq!(proposer_weight(&weights, Some("DUnobody")), 1);
+        assert_eq!(proposer_weight(&weights, None), 1);
+    }
+}
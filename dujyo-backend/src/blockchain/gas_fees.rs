@@ -11,6 +11,17 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::dex::oracle::twap_window_secs;
+use crate::utils::amount::Amount;
+
+/// DYO price used while the DEX oracle has no history yet ($0.001 per DYO)
+pub const DEFAULT_DYO_PRICE_USD: Amount = Amount::from_raw(1_000_000_000_000_000);
+
+/// A fee schedule literal (`"0.001"`), written like its serialized form
+fn decimal(value: &str) -> Amount {
+    value.parse().expect("fee schedule literal is a valid amount")
+}
+
 // ============================================================================
 // DATA STRUCTURES
 // ============================================================================
@@ -50,20 +61,46 @@ pub enum TransactionType {
     Review,
 }
 
+impl TransactionType {
+    /// Every transaction type (fee tables and estimates)
+    pub const ALL: [TransactionType; 20] = [
+        TransactionType::Transfer,
+        TransactionType::TransferWithData,
+        TransactionType::MultiSigTransfer,
+        TransactionType::StreamEarn,
+        TransactionType::UploadContent,
+        TransactionType::MintNFT,
+        TransactionType::TransferNFT,
+        TransactionType::DexSwap,
+        TransactionType::AddLiquidity,
+        TransactionType::RemoveLiquidity,
+        TransactionType::Stake,
+        TransactionType::Unstake,
+        TransactionType::ClaimRewards,
+        TransactionType::RegisterValidator,
+        TransactionType::ProposeBlock,
+        TransactionType::Vote,
+        TransactionType::Follow,
+        TransactionType::Comment,
+        TransactionType::Like,
+        TransactionType::Review,
+    ];
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum GasFeeModel {
     /// Fixed fee in USD (converted to DYO automatically)
-    Fixed(f64),
+    Fixed(Amount),
     
-    /// Percentage of transaction amount
-    Percentage(f64),
+    /// Percentage of transaction amount, as a fraction (0.003 = 0.3%)
+    Percentage(Amount),
     
     /// Hybrid: base fee (USD) + percentage
     Hybrid {
-        base: f64,      // Base fee in USD
-        percentage: f64,
-        min: f64,        // Min fee in USD
-        max: Option<f64>, // Max fee in USD
+        base: Amount,      // Base fee in USD
+        percentage: Amount, // Fraction of the transaction amount
+        min: Amount,        // Min fee in USD
+        max: Option<Amount>, // Max fee in USD
     },
     
     /// Free transaction (no gas fee)
@@ -74,8 +111,38 @@ pub enum GasFeeModel {
 pub struct GasFeeConfig {
     pub transaction_type: TransactionType,
     pub model: GasFeeModel,
-    pub min_fee: f64,
-    pub max_fee: Option<f64>,
+    pub min_fee: Amount, // USD
+    pub max_fee: Option<Amount>, // USD
+}
+
+impl GasFeeConfig {
+    /// Rules for a config replaced at runtime (governance): percentages are
+    /// fractions and caps are above minimums (amounts are never negative)
+    pub fn validate(&self) -> Result<(), String> {
+        let fraction = |value: Amount| -> Result<(), String> {
+            if value <= Amount::ONE {
+                Ok(())
+            } else {
+                Err("percentage must be between 0 and 1".to_string())
+            }
+        };
+        let capped = |name: &str, min: Amount, max: Option<Amount>| -> Result<(), String> {
+            match max {
+                Some(max) if max < min => Err(format!("{} is below the minimum", name)),
+                _ => Ok(()),
+            }
+        };
+
+        match &self.model {
+            GasFeeModel::Free | GasFeeModel::Fixed(_) => {}
+            GasFeeModel::Percentage(percentage) => fraction(*percentage)?,
+            GasFeeModel::Hybrid { percentage, min, max, .. } => {
+                fraction(*percentage)?;
+                capped("hybrid maximum", *min, *max)?;
+            }
+        }
+        capped("max_fee", self.min_fee, self.max_fee)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -90,8 +157,8 @@ pub enum UserTier {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NetworkState {
     pub congestion_level: f64, // 0.0 to 1.0 (0 = no congestion, 1 = max congestion)
-    pub dyo_price_usd: f64,    // Current DYO price in USD
-    pub daily_volume: f64,     // Daily transaction volume
+    pub dyo_price_usd: Amount, // Current DYO price in USD
+    pub daily_volume: Amount,  // DYO moved on-chain over the last 24 hours
 }
 
 // ============================================================================
@@ -110,22 +177,22 @@ impl GasFeeCalculator {
         // Financial transactions
         configs.insert(TransactionType::Transfer, GasFeeConfig {
             transaction_type: TransactionType::Transfer,
-            model: GasFeeModel::Fixed(0.001), // $0.001 USD
-            min_fee: 0.001, // Min in DYO (legacy, will be converted)
+            model: GasFeeModel::Fixed(decimal("0.001")), // $0.001 USD
+            min_fee: decimal("0.001"), // Min in USD
             max_fee: None,
         });
         
         configs.insert(TransactionType::TransferWithData, GasFeeConfig {
             transaction_type: TransactionType::TransferWithData,
-            model: GasFeeModel::Fixed(0.002), // $0.002 USD
-            min_fee: 0.002,
+            model: GasFeeModel::Fixed(decimal("0.002")), // $0.002 USD
+            min_fee: decimal("0.002"),
             max_fee: None,
         });
         
         configs.insert(TransactionType::MultiSigTransfer, GasFeeConfig {
             transaction_type: TransactionType::MultiSigTransfer,
-            model: GasFeeModel::Fixed(0.005), // $0.005 USD
-            min_fee: 0.005,
+            model: GasFeeModel::Fixed(decimal("0.005")), // $0.005 USD
+            min_fee: decimal("0.005"),
             max_fee: None,
         });
         
@@ -133,28 +200,28 @@ impl GasFeeCalculator {
         configs.insert(TransactionType::StreamEarn, GasFeeConfig {
             transaction_type: TransactionType::StreamEarn,
             model: GasFeeModel::Free,
-            min_fee: 0.0,
+            min_fee: Amount::ZERO,
             max_fee: None,
         });
         
         configs.insert(TransactionType::UploadContent, GasFeeConfig {
             transaction_type: TransactionType::UploadContent,
-            model: GasFeeModel::Fixed(0.02), // $0.02 USD
-            min_fee: 0.1, // Legacy min in DYO
+            model: GasFeeModel::Fixed(decimal("0.02")), // $0.02 USD
+            min_fee: decimal("0.1"), // Legacy min in DYO
             max_fee: None,
         });
         
         configs.insert(TransactionType::MintNFT, GasFeeConfig {
             transaction_type: TransactionType::MintNFT,
-            model: GasFeeModel::Fixed(0.05), // $0.05 USD
-            min_fee: 0.05,
+            model: GasFeeModel::Fixed(decimal("0.05")), // $0.05 USD
+            min_fee: decimal("0.05"),
             max_fee: None,
         });
         
         configs.insert(TransactionType::TransferNFT, GasFeeConfig {
             transaction_type: TransactionType::TransferNFT,
-            model: GasFeeModel::Fixed(0.001), // $0.001 USD
-            min_fee: 0.01,
+            model: GasFeeModel::Fixed(decimal("0.001")), // $0.001 USD
+            min_fee: decimal("0.01"),
             max_fee: None,
         });
         
@@ -162,104 +229,104 @@ impl GasFeeCalculator {
         configs.insert(TransactionType::DexSwap, GasFeeConfig {
             transaction_type: TransactionType::DexSwap,
             model: GasFeeModel::Hybrid {
-                base: 0.0, // Base in USD
-                percentage: 0.003, // 0.3%
-                min: 0.01, // Min $0.01 USD
-                max: Some(10.0), // Max $10 USD
+                base: Amount::ZERO, // Base in USD
+                percentage: decimal("0.003"), // 0.3%
+                min: decimal("0.01"), // Min $0.01 USD
+                max: Some(decimal("10")), // Max $10 USD
             },
-            min_fee: 0.01,
-            max_fee: Some(10.0),
+            min_fee: decimal("0.01"),
+            max_fee: Some(decimal("10")),
         });
         
         configs.insert(TransactionType::AddLiquidity, GasFeeConfig {
             transaction_type: TransactionType::AddLiquidity,
-            model: GasFeeModel::Fixed(0.02), // $0.02 USD
-            min_fee: 0.1,
+            model: GasFeeModel::Fixed(decimal("0.02")), // $0.02 USD
+            min_fee: decimal("0.1"),
             max_fee: None,
         });
         
         configs.insert(TransactionType::RemoveLiquidity, GasFeeConfig {
             transaction_type: TransactionType::RemoveLiquidity,
-            model: GasFeeModel::Fixed(0.02), // $0.02 USD
-            min_fee: 0.05,
+            model: GasFeeModel::Fixed(decimal("0.02")), // $0.02 USD
+            min_fee: decimal("0.05"),
             max_fee: None,
         });
         
         // Staking transactions
         configs.insert(TransactionType::Stake, GasFeeConfig {
             transaction_type: TransactionType::Stake,
-            model: GasFeeModel::Fixed(0.02), // $0.02 USD
-            min_fee: 0.02,
+            model: GasFeeModel::Fixed(decimal("0.02")), // $0.02 USD
+            min_fee: decimal("0.02"),
             max_fee: None,
         });
         
         configs.insert(TransactionType::Unstake, GasFeeConfig {
             transaction_type: TransactionType::Unstake,
             model: GasFeeModel::Hybrid {
-                base: 0.05, // $0.05 USD base
-                percentage: 0.01, // 1% if early withdrawal
-                min: 0.05, // Min $0.05 USD
+                base: decimal("0.05"), // $0.05 USD base
+                percentage: decimal("0.01"), // 1% if early withdrawal
+                min: decimal("0.05"), // Min $0.05 USD
                 max: None,
             },
-            min_fee: 0.05,
+            min_fee: decimal("0.05"),
             max_fee: None,
         });
         
         configs.insert(TransactionType::ClaimRewards, GasFeeConfig {
             transaction_type: TransactionType::ClaimRewards,
-            model: GasFeeModel::Fixed(0.01), // $0.01 USD
-            min_fee: 0.01,
+            model: GasFeeModel::Fixed(decimal("0.01")), // $0.01 USD
+            min_fee: decimal("0.01"),
             max_fee: None,
         });
         
         // Validation transactions
         configs.insert(TransactionType::RegisterValidator, GasFeeConfig {
             transaction_type: TransactionType::RegisterValidator,
-            model: GasFeeModel::Fixed(0.1), // $0.1 USD
-            min_fee: 0.1,
+            model: GasFeeModel::Fixed(decimal("0.1")), // $0.1 USD
+            min_fee: decimal("0.1"),
             max_fee: None,
         });
         
         configs.insert(TransactionType::ProposeBlock, GasFeeConfig {
             transaction_type: TransactionType::ProposeBlock,
             model: GasFeeModel::Free,
-            min_fee: 0.0,
+            min_fee: Amount::ZERO,
             max_fee: None,
         });
         
         configs.insert(TransactionType::Vote, GasFeeConfig {
             transaction_type: TransactionType::Vote,
-            model: GasFeeModel::Fixed(0.001), // $0.001 USD
-            min_fee: 0.001,
+            model: GasFeeModel::Fixed(decimal("0.001")), // $0.001 USD
+            min_fee: decimal("0.001"),
             max_fee: None,
         });
         
         // Social transactions
         configs.insert(TransactionType::Follow, GasFeeConfig {
             transaction_type: TransactionType::Follow,
-            model: GasFeeModel::Fixed(0.001), // $0.001 USD
-            min_fee: 0.001,
+            model: GasFeeModel::Fixed(decimal("0.001")), // $0.001 USD
+            min_fee: decimal("0.001"),
             max_fee: None,
         });
         
         configs.insert(TransactionType::Comment, GasFeeConfig {
             transaction_type: TransactionType::Comment,
-            model: GasFeeModel::Fixed(0.002), // $0.002 USD
-            min_fee: 0.002,
+            model: GasFeeModel::Fixed(decimal("0.002")), // $0.002 USD
+            min_fee: decimal("0.002"),
             max_fee: None,
         });
         
         configs.insert(TransactionType::Like, GasFeeConfig {
             transaction_type: TransactionType::Like,
-            model: GasFeeModel::Fixed(0.0005), // $0.0005 USD
-            min_fee: 0.0005,
+            model: GasFeeModel::Fixed(decimal("0.0005")), // $0.0005 USD
+            min_fee: decimal("0.0005"),
             max_fee: None,
         });
         
         configs.insert(TransactionType::Review, GasFeeConfig {
             transaction_type: TransactionType::Review,
-            model: GasFeeModel::Fixed(0.005), // $0.005 USD
-            min_fee: 0.005,
+            model: GasFeeModel::Fixed(decimal("0.005")), // $0.005 USD
+            min_fee: decimal("0.005"),
             max_fee: None,
         });
         
@@ -267,40 +334,38 @@ impl GasFeeCalculator {
     }
     
     /// Calculate gas fee for a transaction
-    /// Returns fee in DYO (converted from USD if needed)
+    /// Returns fee in DYO (converted from USD if needed); `amount` is in DYO
     pub fn calculate_gas_fee(
         &self,
         tx_type: &TransactionType,
-        amount: Option<f64>,
+        amount: Option<Amount>,
         user_tier: &UserTier,
         network_state: &NetworkState,
         is_early_unstake: bool,
-    ) -> Result<f64, String> {
+    ) -> Result<Amount, String> {
         let config = self.configs.get(tx_type)
             .ok_or_else(|| format!("Gas fee config not found for transaction type: {:?}", tx_type))?;
+        let to_usd = |amount: Amount| amount.checked_mul(network_state.dyo_price_usd).map_err(|e| e.to_string());
         
         // Calculate base fee in USD
         let base_fee_usd = match &config.model {
             GasFeeModel::Free => {
-                return Ok(0.0);
+                return Ok(Amount::ZERO);
             }
             GasFeeModel::Fixed(fee_usd) => *fee_usd,
             GasFeeModel::Percentage(percentage) => {
                 // For percentage, calculate in USD based on amount value
                 let amount = amount.ok_or("Amount required for percentage-based fee")?;
                 // Assume amount is in DYO, convert to USD first, then apply percentage
-                let amount_usd = amount * network_state.dyo_price_usd;
-                amount_usd * percentage
+                to_usd(amount)?.checked_mul(*percentage).map_err(|e| e.to_string())?
             }
             GasFeeModel::Hybrid { base, percentage, min, max } => {
                 // Base is in USD, percentage applies to transaction amount
-                let percentage_fee_usd = if let Some(amt) = amount {
-                    let amount_usd = amt * network_state.dyo_price_usd;
-                    amount_usd * percentage
-                } else {
-                    0.0
+                let percentage_fee_usd = match amount {
+                    Some(amt) => to_usd(amt)?.checked_mul(*percentage).map_err(|e| e.to_string())?,
+                    None => Amount::ZERO,
                 };
-                let total_usd = base + percentage_fee_usd;
+                let total_usd = base.checked_add(percentage_fee_usd).map_err(|e| e.to_string())?;
                 
                 // Apply min/max bounds (in USD)
                 let bounded = total_usd.max(*min);
@@ -313,65 +378,81 @@ impl GasFeeCalculator {
         };
         
         // Apply network congestion multiplier (0.5x to 2.0x)
-        let congestion_multiplier = 0.5 + (network_state.congestion_level * 1.5);
-        let adjusted_fee_usd = base_fee_usd * congestion_multiplier;
-        
-        // Apply user tier discount
-        let discount = match user_tier {
-            UserTier::Regular => 0.0,
-            UserTier::Premium => 0.5, // 50% discount
-            UserTier::CreativeValidator => 0.5, // 50% discount
-            UserTier::CommunityValidator => 0.25, // 25% discount
-            UserTier::EconomicValidator => 0.0, // No discount
+        let congestion_bps = (network_state.congestion_level.clamp(0.0, 1.0) * 10_000.0).round() as u64;
+        let adjusted_fee_usd = base_fee_usd
+            .mul_bps(5_000 + congestion_bps * 3 / 2)
+            .map_err(|e| e.to_string())?;
+        
+        // Apply user tier discount (basis points)
+        let discount_bps = match user_tier {
+            UserTier::Regular => 0,
+            UserTier::Premium => 5_000, // 50% discount
+            UserTier::CreativeValidator => 5_000, // 50% discount
+            UserTier::CommunityValidator => 2_500, // 25% discount
+            UserTier::EconomicValidator => 0, // No discount
         };
         
-        let final_fee_usd = adjusted_fee_usd * (1.0 - discount);
+        let final_fee_usd = adjusted_fee_usd.mul_bps(10_000 - discount_bps).map_err(|e| e.to_string())?;
         
         // Apply min/max bounds from config
         // min_fee and max_fee in config are in USD (not DYO)
         // They represent the minimum/maximum fee in USD terms
         // IMPORTANT: Apply discount to min_fee as well so discounts work correctly
-        let min_fee_usd = config.min_fee * (1.0 - discount); // Apply discount to min_fee
+        let min_fee_usd = config.min_fee.mul_bps(10_000 - discount_bps).map_err(|e| e.to_string())?;
         let mut final_fee_usd = final_fee_usd.max(min_fee_usd);
         if let Some(max_fee) = config.max_fee {
-            let max_fee_usd = max_fee; // Max fee doesn't get discount
-            final_fee_usd = final_fee_usd.min(max_fee_usd);
+            // Max fee doesn't get discount
+            final_fee_usd = final_fee_usd.min(max_fee);
         }
         
         // Special case: early unstake penalty (in USD)
         if is_early_unstake && *tx_type == TransactionType::Unstake {
-            let amount_usd = amount.map(|a| a * network_state.dyo_price_usd).unwrap_or(0.0);
-            let penalty_usd = amount_usd * 0.01; // 1% penalty
-            final_fee_usd = final_fee_usd + penalty_usd;
+            let amount_usd = amount.map(to_usd).transpose()?.unwrap_or(Amount::ZERO);
+            let penalty_usd = amount_usd.mul_bps(100).map_err(|e| e.to_string())?; // 1% penalty
+            final_fee_usd = final_fee_usd.checked_add(penalty_usd).map_err(|e| e.to_string())?;
         }
         
         // ✅ MVP-CRITICAL: Convert USD to DYO
-        if network_state.dyo_price_usd <= 0.0 {
+        if network_state.dyo_price_usd.is_zero() {
             return Err("Invalid DYO price in USD. Cannot calculate gas fee.".to_string());
         }
-        let final_fee_dyo = final_fee_usd / network_state.dyo_price_usd;
-        
-        Ok(final_fee_dyo)
+        final_fee_usd.checked_div(network_state.dyo_price_usd).map_err(|e| e.to_string())
     }
     
     /// Calculate gas fee in USD (for display purposes)
     pub fn calculate_gas_fee_usd(
         &self,
         tx_type: &TransactionType,
-        amount: Option<f64>,
+        amount: Option<Amount>,
         user_tier: &UserTier,
         network_state: &NetworkState,
         is_early_unstake: bool,
-    ) -> Result<f64, String> {
+    ) -> Result<Amount, String> {
         let fee_dyo = self.calculate_gas_fee(tx_type, amount, user_tier, network_state, is_early_unstake)?;
-        Ok(fee_dyo * network_state.dyo_price_usd)
+        fee_dyo.checked_mul(network_state.dyo_price_usd).map_err(|e| e.to_string())
     }
     
     /// Get gas fee config for a transaction type
     pub fn get_config(&self, tx_type: &TransactionType) -> Option<&GasFeeConfig> {
         self.configs.get(tx_type)
     }
+
+    /// Replace the config of `config.transaction_type` (governance), returning the old one
+    pub fn set_config(&mut self, config: GasFeeConfig) -> Result<Option<GasFeeConfig>, String> {
+        config.validate()?;
+        Ok(self.configs.insert(config.transaction_type.clone(), config))
+    }
     
+    /// ✅ SECURITY: DYO price in USD from the DYO/DYS time-weighted average
+    /// (DYS is pegged to $1). The spot reserves can be moved by a single swap
+    /// right before a transaction, so they are never read for pricing.
+    pub fn dyo_price_usd(dex: &crate::dex::DEX, now: u64) -> Amount {
+        match dex.twap_price("DYO", "DYS", twap_window_secs(), now) {
+            Ok(price) if !price.is_zero() => price,
+            _ => DEFAULT_DYO_PRICE_USD,
+        }
+    }
+
     /// Check if transaction is free
     pub fn is_free(&self, tx_type: &TransactionType) -> bool {
         if let Some(config) = self.configs.get(tx_type) {
@@ -386,101 +467,141 @@ impl GasFeeCalculator {
 // AUTO-SWAP MECHANISM (MVP-CRITICAL)
 // ============================================================================
 
+/// Max slippage of the gas auto-swap when the user has not set one (1%)
+pub const DEFAULT_AUTO_SWAP_SLIPPAGE_BPS: u64 = 100;
+
+/// Highest max slippage a user can allow for the gas auto-swap (10%)
+pub const MAX_AUTO_SWAP_SLIPPAGE_BPS: u64 = 1_000;
+
+/// Per-user gas auto-swap preferences
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AutoSwapSettings {
+    /// Swap DYS for the missing DYO when a transaction is short on gas
+    pub enabled: bool,
+    /// Most the swap may pay above the DYO/DYS TWAP, pool fee included
+    pub max_slippage_bps: u64,
+}
+
+impl Default for AutoSwapSettings {
+    fn default() -> Self {
+        AutoSwapSettings {
+            enabled: true,
+            max_slippage_bps: DEFAULT_AUTO_SWAP_SLIPPAGE_BPS,
+        }
+    }
+}
+
+impl AutoSwapSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_slippage_bps > MAX_AUTO_SWAP_SLIPPAGE_BPS {
+            return Err(format!(
+                "Max slippage cannot exceed {} bps",
+                MAX_AUTO_SWAP_SLIPPAGE_BPS
+            ));
+        }
+        Ok(())
+    }
+}
+
 /// Result of auto-swap operation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AutoSwapResult {
     pub success: bool,
-    pub dyo_received: f64,
-    pub dys_used: f64,
+    pub dyo_received: Amount,
+    pub dys_used: Amount,
     pub swap_executed: bool,
     pub message: String,
+    /// DEX transaction id of the swap (None when no swap ran)
+    #[serde(default)]
+    pub swap_id: Option<String>,
 }
 
 /// ✅ MVP-CRITICAL: Auto-swap mechanism for gas fees
-/// If user doesn't have enough DYO, automatically swap from DYS (stablecoin)
-/// 
-/// This function should be called before executing a transaction when:
-/// 1. Gas fee is calculated
-/// 2. User balance in DYO is insufficient
-/// 3. User has DYS balance available
-pub async fn handle_gas_fee_with_auto_swap(
-    required_dyo: f64,
-    user_dyo_balance: f64,
-    user_dys_balance: f64,
+/// If user doesn't have enough DYO, swap just enough DYS (stablecoin) for the
+/// missing `dyo_needed` through the DYO/DYS pool.
+///
+/// The DYS input is priced against the DYO/DYS TWAP: if the pool would charge
+/// more than `max_slippage_bps` above it (price impact and pool fee), nothing
+/// is swapped. The swap executes at once, also in batch auction mode, so it
+/// lands in the block of the transaction it pays for; the TWAP bound is what
+/// limits a sandwich. The caller pays the returned `dys_used` and must
+/// `DEX::undo_swap` if that transaction does not go through.
+pub fn handle_gas_fee_with_auto_swap(
+    dyo_needed: Amount,
+    user_dys_balance: Amount,
     user_address: &str,
-    dyo_price_usd: f64,
+    max_slippage_bps: u64,
     dex: &mut crate::dex::DEX,
+    now: u64,
 ) -> Result<AutoSwapResult, String> {
-    // If transaction is free, no swap needed
-    if required_dyo == 0.0 {
-        return Ok(AutoSwapResult {
-            success: true,
-            dyo_received: 0.0,
-            dys_used: 0.0,
-            swap_executed: false,
-            message: "Transaction is free, no gas fee required".to_string(),
-        });
-    }
-    
     // If user has enough DYO, no swap needed
-    if user_dyo_balance >= required_dyo {
+    if dyo_needed.is_zero() {
         return Ok(AutoSwapResult {
             success: true,
-            dyo_received: 0.0,
-            dys_used: 0.0,
+            dyo_received: Amount::ZERO,
+            dys_used: Amount::ZERO,
             swap_executed: false,
             message: "Sufficient DYO balance, no swap needed".to_string(),
+            swap_id: None,
         });
     }
-    
-    // Calculate how much DYO we need to swap
-    let dyo_needed = required_dyo - user_dyo_balance;
-    
-    // Calculate how much DYS we need (DYS is pegged to USD: 1 DYS = $1 USD)
-    // DYO price in USD: dyo_price_usd
-    // So: dyo_needed * dyo_price_usd = dys_needed
-    let dys_needed = dyo_needed * dyo_price_usd;
-    
-    // Add 5% buffer for slippage and DEX fees
-    let dys_with_buffer = dys_needed * 1.05;
-    
+
+    // ✅ SECURITY: The reference price is the TWAP, never the spot reserves
+    let dys_per_dyo = dex
+        .twap_price("DYO", "DYS", twap_window_secs(), now)
+        .map_err(|e| format!("Auto-swap unavailable, no DYO/DYS oracle price: {}", e))?;
+    let dys_limit = dyo_needed
+        .checked_mul(dys_per_dyo)
+        .and_then(|dys| dys.mul_bps(10_000 + max_slippage_bps))
+        .map_err(|e| e.to_string())?;
+
+    // Exact input for the missing DYO, in whole micro-DYS (balances are stored in micro units)
+    let dys_needed = dex
+        .quote_exact_output("DYS", "DYO", dyo_needed)
+        .and_then(|dys| dys.round_up_to(6).map_err(|e| e.to_string()))
+        .map_err(|e| format!("Auto-swap failed: {}", e))?;
+    if dys_needed > dys_limit {
+        return Err(format!(
+            "Auto-swap exceeds your max slippage of {} bps: {} DYS needed for {} DYO (limit {} DYS)",
+            max_slippage_bps, dys_needed, dyo_needed, dys_limit
+        ));
+    }
+
     // Check if user has enough DYS
-    if user_dys_balance < dys_with_buffer {
+    if user_dys_balance < dys_needed {
         return Err(format!(
-            "Insufficient balance. Need {} DYO (or {} DYS), but only have {} DYO and {} DYS",
-            required_dyo, dys_with_buffer, user_dyo_balance, user_dys_balance
+            "Insufficient balance. Need {} more DYO (or {} DYS), but only have {} DYS",
+            dyo_needed, dys_needed, user_dys_balance
         ));
     }
-    
-    // Execute swap: DYS -> DYO
-    // Use DEX swap function
+
+    // Execute swap: DYS -> DYO. The signed transaction authorizes it and it runs
+    // right here, so it takes the user's next DEX nonce and needs no deadline
     let swap_request = crate::dex::SwapRequest {
         from: "DYS".to_string(),
         to: "DYO".to_string(),
-        amount: dys_with_buffer,
-        min_received: dyo_needed * 0.95, // 5% slippage tolerance
+        amount: dys_needed,
+        min_received: dyo_needed,
         user: user_address.to_string(),
+        deadline: u64::MAX,
+        nonce: dex.next_nonce(user_address),
     };
-    
-    match dex.execute_swap(swap_request) {
-        Ok(swap_response) => {
-            if let Some(amount_received) = swap_response.amount_received {
-                Ok(AutoSwapResult {
-                    success: true,
-                    dyo_received: amount_received,
-                    dys_used: dys_with_buffer,
-                    swap_executed: true,
-                    message: format!(
-                        "Auto-swapped {} DYS for {} DYO to pay gas fee",
-                        dys_with_buffer, amount_received
-                    ),
-                })
-            } else {
-                Err("Swap executed but no amount received".to_string())
-            }
-        }
-        Err(e) => Err(format!("Auto-swap failed: {}", e)),
-    }
+
+    let swap_response = dex.execute_swap(swap_request).map_err(|e| format!("Auto-swap failed: {}", e))?;
+    let dyo_received = swap_response
+        .amount_received
+        .ok_or("Swap executed but no amount received")?;
+    Ok(AutoSwapResult {
+        success: true,
+        dyo_received,
+        dys_used: dys_needed,
+        swap_executed: true,
+        message: format!(
+            "Auto-swapped {} DYS for {} DYO to pay gas fee",
+            dys_needed, dyo_received
+        ),
+        swap_id: swap_response.tx_hash,
+    })
 }
 
 impl Default for GasFeeCalculator {
@@ -493,23 +614,65 @@ impl Default for GasFeeCalculator {
 // FEE DISTRIBUTION
 // ============================================================================
 
+/// Share of the collected fees for each recipient, in basis points (sum = 10_000)
+pub const TREASURY_FEE_BPS: u64 = 4_000;
+pub const VALIDATOR_FEE_BPS: u64 = 3_000;
+pub const CREATIVE_POOL_FEE_BPS: u64 = 2_000;
+pub const BURN_FEE_BPS: u64 = 1_000;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FeeDistribution {
-    pub treasury: f64,      // 40%
-    pub validators: f64,    // 30%
-    pub liquidity_providers: f64, // 20%
-    pub burn: f64,          // 10%
+    pub treasury: Amount,      // 40%
+    pub validators: Amount,    // 30%
+    pub creative_pool: Amount, // 20% (creative incentives)
+    pub burn: Amount,          // 10%
 }
 
 impl FeeDistribution {
-    pub fn distribute(fee_amount: f64) -> Self {
+    /// Split of a fee amount; like `split_cents`, rounding remainders go to
+    /// the treasury so the shares add up to `fee_amount`
+    pub fn distribute(fee_amount: Amount) -> Self {
+        let share = |bps: u64| fee_amount.mul_bps(bps).unwrap_or(Amount::ZERO);
+        let validators = share(VALIDATOR_FEE_BPS);
+        let creative_pool = share(CREATIVE_POOL_FEE_BPS);
+        let burn = share(BURN_FEE_BPS);
         Self {
-            treasury: fee_amount * 0.40,
-            validators: fee_amount * 0.30,
-            liquidity_providers: fee_amount * 0.20,
-            burn: fee_amount * 0.10,
+            treasury: fee_amount.saturating_sub(validators).saturating_sub(creative_pool).saturating_sub(burn),
+            validators,
+            creative_pool,
+            burn,
         }
     }
+
+    /// Exact split of `total` ledger cents (what the block fee payout credits).
+    /// Rounding remainders go to the treasury, so the shares always add up to `total`.
+    pub fn split_cents(total: u64) -> FeeSplit {
+        let share = |bps: u64| (total as u128 * bps as u128 / 10_000) as u64;
+        let validator = share(VALIDATOR_FEE_BPS);
+        let creative_pool = share(CREATIVE_POOL_FEE_BPS);
+        let burn = share(BURN_FEE_BPS);
+        FeeSplit {
+            treasury: total - validator - creative_pool - burn,
+            validator,
+            creative_pool,
+            burn,
+        }
+    }
+}
+
+/// Fees of one block as credited on-chain (ledger cents)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeeSplit {
+    pub treasury: u64,
+    pub validator: u64,
+    pub creative_pool: u64,
+    pub burn: u64,
+}
+
+impl FeeSplit {
+    pub fn total(&self) -> u64 {
+        self.treasury + self.validator + self.creative_pool + self.burn
+    }
 }
 
 // ============================================================================
@@ -578,8 +741,8 @@ mod tests {
         let calculator = GasFeeCalculator::new();
         let network_state = NetworkState {
             congestion_level: 0.0,
-            dyo_price_usd: 0.001,
-            daily_volume: 1000.0,
+            dyo_price_usd: decimal("0.001"),
+            daily_volume: Amount::from_units(1_000),
         };
         
         // Stream-to-Earn should be free
@@ -590,7 +753,7 @@ mod tests {
             &network_state,
             false,
         ).unwrap();
-        assert_eq!(fee, 0.0);
+        assert_eq!(fee, Amount::ZERO);
         
         // Propose Block should be free
         let fee = calculator.calculate_gas_fee(
@@ -600,7 +763,7 @@ mod tests {
             &network_state,
             false,
         ).unwrap();
-        assert_eq!(fee, 0.0);
+        assert_eq!(fee, Amount::ZERO);
     }
     
     #[test]
@@ -608,8 +771,8 @@ mod tests {
         let calculator = GasFeeCalculator::new();
         let network_state = NetworkState {
             congestion_level: 0.0,
-            dyo_price_usd: 0.001,
-            daily_volume: 1000.0,
+            dyo_price_usd: decimal("0.001"),
+            daily_volume: Amount::from_units(1_000),
         };
         
         // Regular user
@@ -630,7 +793,7 @@ mod tests {
             false,
         ).unwrap();
         
-        assert_eq!(premium_fee, regular_fee * 0.5);
+        assert_eq!(premium_fee, regular_fee.mul_bps(5_000).unwrap());
     }
     
     #[test]
@@ -638,8 +801,8 @@ mod tests {
         let calculator = GasFeeCalculator::new();
         let network_state = NetworkState {
             congestion_level: 0.0,
-            dyo_price_usd: 0.001, // $0.001 per DYO
-            daily_volume: 1000.0,
+            dyo_price_usd: decimal("0.001"), // $0.001 per DYO
+            daily_volume: Amount::from_units(1_000),
         };
         
         // Swap of 1000 DYO = $1 USD
@@ -647,7 +810,7 @@ mod tests {
         // $0.003 / $0.001 = 3 DYO
         let fee = calculator.calculate_gas_fee(
             &TransactionType::DexSwap,
-            Some(1000.0),
+            Some(Amount::from_units(1_000)),
             &UserTier::Regular,
             &network_state,
             false,
@@ -655,9 +818,33 @@ mod tests {
         
         // Should be approximately 3 DYO (0.3% of $1 USD = $0.003 = 3 DYO at $0.001/DYO)
         // But with min of $0.01 USD = 10 DYO
-        assert!(fee >= 10.0); // Min fee is $0.01 USD = 10 DYO
+        assert!(fee >= Amount::from_units(10)); // Min fee is $0.01 USD = 10 DYO
     }
     
+    #[test]
+    fn test_auto_swap_buys_exact_shortfall_within_slippage() {
+        let mut dex = crate::dex::DEX::new();
+        let now = chrono::Utc::now().timestamp() as u64 + 1_800;
+        dex.record_block_observations(now);
+        let needed = Amount::from_cents(1_250);
+
+        // No swap without a shortfall
+        let result = handle_gas_fee_with_auto_swap(Amount::ZERO, Amount::ZERO, "DUalice", 100, &mut dex, now).unwrap();
+        assert!(!result.swap_executed);
+
+        // The pool fee alone (0.3%) exceeds a 0.1% limit
+        let strict = handle_gas_fee_with_auto_swap(needed, Amount::from_units(100), "DUalice", 10, &mut dex, now);
+        assert!(strict.unwrap_err().contains("max slippage"));
+        assert!(handle_gas_fee_with_auto_swap(needed, Amount::from_cents(5), "DUalice", 100, &mut dex, now).is_err());
+        assert_eq!(dex.get_pool("DYO_DYS").unwrap().reserve_a, Amount::from_units(1_000_000));
+
+        let result = handle_gas_fee_with_auto_swap(needed, Amount::from_units(100), "DUalice", 100, &mut dex, now).unwrap();
+        assert!(result.swap_executed);
+        assert!(result.dyo_received >= needed);
+        assert!(result.dys_used < Amount::from_cents(1_260));
+        assert_eq!(result.dys_used.round_down_to(6).unwrap(), result.dys_used);
+    }
+
     #[test]
     fn test_price_fixing_usd() {
         let calculator = GasFeeCalculator::new();
@@ -665,14 +852,14 @@ mod tests {
         // Test with different DYO prices
         let network_state_low = NetworkState {
             congestion_level: 0.0,
-            dyo_price_usd: 0.0005, // Lower price
-            daily_volume: 1000.0,
+            dyo_price_usd: decimal("0.0005"), // Lower price
+            daily_volume: Amount::from_units(1_000),
         };
         
         let network_state_high = NetworkState {
             congestion_level: 0.0,
-            dyo_price_usd: 0.002, // Higher price
-            daily_volume: 1000.0,
+            dyo_price_usd: decimal("0.002"), // Higher price
+            daily_volume: Amount::from_units(1_000),
         };
         
         // Transfer fee is $0.001 USD fixed
@@ -695,6 +882,56 @@ mod tests {
         // At $0.0005/DYO: $0.001 / $0.0005 = 2 DYO
         // At $0.002/DYO: $0.001 / $0.002 = 0.5 DYO
         assert!(fee_low > fee_high); // Lower DYO price = more DYO needed
+        assert_eq!(fee_low, Amount::from_units(2));
+        assert_eq!(fee_high, decimal("0.5"));
     }
-}
 
+    #[test]
+    fn test_fee_split_adds_up() {
+        let split = FeeDistribution::split_cents(1_000);
+        assert_eq!(split, FeeSplit { treasury: 400, validator: 300, creative_pool: 200, burn: 100 });
+
+        // Rounding remainders stay with the treasury
+        let split = FeeDistribution::split_cents(7);
+        assert_eq!((split.validator, split.creative_pool, split.burn), (2, 1, 0));
+        assert_eq!(split.treasury, 4);
+        assert_eq!(split.total(), 7);
+
+        let distribution = FeeDistribution::distribute(Amount::from_raw(7));
+        assert_eq!(distribution.treasury, Amount::from_raw(4));
+        assert_eq!(distribution.validators, Amount::from_raw(2));
+    }
+
+    #[test]
+    fn test_set_config_validates() {
+        let mut calculator = GasFeeCalculator::new();
+        for tx_type in TransactionType::ALL.iter() {
+            calculator.get_config(tx_type).unwrap().validate().unwrap();
+        }
+
+        let config = GasFeeConfig {
+            transaction_type: TransactionType::Transfer,
+            model: GasFeeModel::Percentage(decimal("1.5")),
+            min_fee: Amount::ZERO,
+            max_fee: None,
+        };
+        assert!(calculator.set_config(config).is_err());
+
+        let config = GasFeeConfig {
+            transaction_type: TransactionType::Transfer,
+            model: GasFeeModel::Fixed(decimal("0.005")),
+            min_fee: decimal("0.005"),
+            max_fee: Some(decimal("0.001")),
+        };
+        assert!(calculator.set_config(config).is_err());
+
+        let config = GasFeeConfig {
+            transaction_type: TransactionType::Transfer,
+            model: GasFeeModel::Free,
+            min_fee: Amount::ZERO,
+            max_fee: None,
+        };
+        assert!(calculator.set_config(config).unwrap().is_some());
+        assert!(calculator.is_free(&TransactionType::Transfer));
+    }
+}
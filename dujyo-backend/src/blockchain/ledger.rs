@@ -0,0 +1,854 @@
+//! Unified Ledger Transactions
+//!
+//! Every state change recorded on chain is a `blockchain::Transaction` whose
+//! `kind` says what it does: transfers, DEX swaps, staking, stream-to-earn
+//! payouts, NFT mint/transfer, tips, governance votes, DYO allowances and the
+//! per-block fee distribution. All kinds share one
+//! encoding (`TxKind::encode`, appended to the transfer payload), one hash
+//! (`Transaction::tx_hash`) and one execution path (`Blockchain::apply_transaction`
+//! and its exact inverse used by reorganizations).
+//!
+//! A `Transfer` adds nothing to the payload, so transfers keep the hashes and
+//! signatures they had before the other kinds existed.
+
+use ed25519_dalek::{Signer, SigningKey, Verifier};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::blockchain::gas_fees::{FeeDistribution, FeeSplit, TransactionType};
+use crate::blockchain::signed_transaction::{chain_id, decode_public_key, decode_signature, push_field};
+
+/// Token tracked by the on-chain ledger (other swap legs live in the DEX)
+pub const NATIVE_TOKEN: &str = "DYO";
+
+/// Ledger account collecting gas fees and priority tips until a block distributes them
+pub const FEE_COLLECTOR: &str = "GAS_FEE_ADDRESS";
+
+/// Recipients of the block fee distribution (the fourth share goes to the block proposer)
+pub const TREASURY_ACCOUNT: &str = "TREASURY";
+pub const CREATIVE_POOL_ACCOUNT: &str = "CREATIVE_POOL";
+/// Burn sink: receives its share and can never send
+pub const BURN_ACCOUNT: &str = "BURN_SINK";
+
+/// Ledger account holding the deposits of open governance proposals
+pub const GOVERNANCE_ESCROW_ACCOUNT: &str = "GOVERNANCE_ESCROW";
+
+/// Ledger account holding the DYO side of DEX pool `pool_id` (the `to` of its swaps)
+pub fn pool_account(pool_id: &str) -> String {
+    format!("POOL_{}", pool_id)
+}
+
+/// Node services that pull DYO from users within an allowance the user signed
+/// (`Approve` or a relayed `Permit`): NFT marketplace purchases and premium billing
+pub const MARKETPLACE_SPENDER: &str = "NFT_MARKETPLACE";
+pub const BILLING_SPENDER: &str = "PREMIUM_BILLING";
+
+pub fn is_service_spender(address: &str) -> bool {
+    matches!(address, MARKETPLACE_SPENDER | BILLING_SPENDER)
+}
+
+/// Domain separator of DYO permits
+const PERMIT_DOMAIN: &[u8] = b"DUJYO_DYO_PERMIT_V1";
+
+/// Allowance signed off-chain by the owner: sets the allowance of `spender` to
+/// `amount` without the owner being online. The node relays it as a `Permit`
+/// transaction. The nonce is the owner's transaction nonce; `deadline` is the
+/// unix timestamp after which the permit is no longer valid.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Permit {
+    pub owner: String,
+    pub spender: String,
+    pub amount: u64,
+    pub nonce: u64,
+    pub deadline: u64,
+    pub public_key: String, // hex ed25519 (the key bound to the owner's account)
+    pub signature: String,  // hex ed25519 over `signing_payload`
+}
+
+impl Permit {
+    /// Canonical bytes covered by the signature
+    pub fn signing_payload(
+        owner: &str,
+        spender: &str,
+        amount: u64,
+        nonce: u64,
+        deadline: u64,
+        chain_id: &str,
+    ) -> Vec<u8> {
+        let mut payload = Vec::with_capacity(96);
+        payload.extend_from_slice(PERMIT_DOMAIN);
+        push_field(&mut payload, chain_id.as_bytes());
+        push_field(&mut payload, owner.as_bytes());
+        push_field(&mut payload, spender.as_bytes());
+        payload.extend_from_slice(&amount.to_be_bytes());
+        payload.extend_from_slice(&nonce.to_be_bytes());
+        payload.extend_from_slice(&deadline.to_be_bytes());
+        payload
+    }
+
+    /// Payload of this permit on chain `chain_id`
+    pub fn payload(&self, chain_id: &str) -> Vec<u8> {
+        Self::signing_payload(&self.owner, &self.spender, self.amount, self.nonce, self.deadline, chain_id)
+    }
+
+    /// Permit without a signature (wallets complete it with `signed_with`)
+    pub fn unsigned(owner: String, spender: String, amount: u64, nonce: u64, deadline: u64) -> Self {
+        Permit {
+            owner,
+            spender,
+            amount,
+            nonce,
+            deadline,
+            public_key: String::new(),
+            signature: String::new(),
+        }
+    }
+
+    /// Check the signature against `registered_key`, the hex key bound to the
+    /// owner's account (the one it signs its transactions with)
+    pub fn verify(&self, chain_id: &str, registered_key: &str) -> Result<(), String> {
+        if !self.public_key.eq_ignore_ascii_case(registered_key) {
+            return Err("Public key is not the one bound to the owner".to_string());
+        }
+        let verifying_key = decode_public_key(&self.public_key).map_err(|e| e.to_string())?;
+        let signature = decode_signature(&self.signature).map_err(|e| e.to_string())?;
+        verifying_key
+            .verify(&self.payload(chain_id), &signature)
+            .map_err(|_| "Signature does not match permit".to_string())
+    }
+
+    /// Sign the permit with the owner's key (wallets, tools and tests)
+    pub fn signed_with(mut self, signing_key: &SigningKey) -> Self {
+        let signature = signing_key.sign(&self.payload(&chain_id()));
+        self.public_key = hex::encode(signing_key.verifying_key().to_bytes());
+        self.signature = hex::encode(signature.to_bytes());
+        self
+    }
+}
+
+/// Accounts no user key controls: only the node moves funds out of them
+pub fn is_system_account(address: &str) -> bool {
+    matches!(
+        address,
+        FEE_COLLECTOR | TREASURY_ACCOUNT | CREATIVE_POOL_ACCOUNT | GOVERNANCE_ESCROW_ACCOUNT
+    ) || address.starts_with("POOL_")
+}
+
+/// What a ledger transaction does. `from` always signs (or is the system
+/// account) and pays the fee; `to` and `amount` depend on the kind.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TxKind {
+    /// `amount` from `from` to `to`
+    #[default]
+    Transfer,
+    /// Trade against DEX pool `to`: `amount` of `token_in` in, `amount_out` of `token_out` back.
+    /// Only the DYO legs move ledger balances.
+    Swap {
+        pool_id: String,
+        token_in: String,
+        token_out: String,
+        amount_out: u64,
+    },
+    /// Bond `amount` of `from`'s balance (`to` must be `from`)
+    Stake,
+    /// Release `amount` of `from`'s bonded stake back to its balance (`to` must be `from`)
+    Unstake,
+    /// Stream-to-earn payout of `amount` from the reward pool `from` to listener/artist `to`
+    StreamEarn { content_id: String, seconds: u64 },
+    /// Create NFT `nft_id` created by `from` and owned by `to` (`amount` = 0)
+    NftMint { ipfs_hash: Option<String> },
+    /// Move NFT `nft_id` from its owner `from` to `to` (`amount` = 0)
+    NftTransfer,
+    /// Fan tip of `amount` from `from` to artist `to`
+    Tip { content_id: Option<String> },
+    /// Vote of `from` on a governance proposal (`amount` = 0, one vote per proposal)
+    GovernanceVote { proposal_id: String, support: bool },
+    /// Block fee payout: the whole balance `amount` of `FEE_COLLECTOR` (`from`) split
+    /// among the treasury, the block proposer `to`, the creative pool and the burn
+    /// sink (`FeeDistribution::split_cents`). Only as the last transaction of a block,
+    /// and without ledger fee.
+    FeeDistribution,
+    /// Owner `from` lets service `to` pull up to `amount` of its DYO (0 revokes).
+    /// `previous` is the allowance it replaces: the approval fails if it changed.
+    Approve { previous: u64 },
+    /// Same as `Approve`, authorized by a `Permit` the owner signed
+    /// off-chain and the node relays. `nonce` is the owner's next account nonce.
+    Permit { nonce: u64, deadline: u64, signature: String, previous: u64 },
+    /// Pull of `amount` from owner `from` to `to` by service `spender`, within
+    /// the allowance `from` gave it (the owner pays the fee, as in a transfer)
+    TransferFrom { spender: String },
+    /// DYO side of a deposit into DEX pool `pool_id`: `amount` from provider `from`
+    /// to the pool account `to`, mirroring the pool's DYO reserve
+    AddLiquidity { pool_id: String },
+    /// DYO side of a withdrawal from DEX pool `pool_id`: `amount` from the pool
+    /// account `from` back to provider `to` (without ledger fee, so the account
+    /// only ever releases reserve)
+    RemoveLiquidity { pool_id: String },
+}
+
+impl TxKind {
+    pub fn is_transfer(&self) -> bool {
+        matches!(self, TxKind::Transfer)
+    }
+
+    /// Kinds a wallet may submit signed. Swaps, stream-to-earn payouts, mints and
+    /// the fee distribution are built by the node from its own DEX / S2E results.
+    /// `Unstake` releases stake at once, so wallets unstake through `/unstake`
+    /// and its slashable unbonding period instead.
+    pub fn is_user_submittable(&self) -> bool {
+        matches!(
+            self,
+            TxKind::Transfer
+                | TxKind::Tip { .. }
+                | TxKind::Stake
+                | TxKind::NftTransfer
+                | TxKind::GovernanceVote { .. }
+                | TxKind::Approve { .. }
+        )
+    }
+
+    /// Nonce of `from` this kind consumes without a signed envelope (relayed permits)
+    pub fn permit_nonce(&self) -> Option<u64> {
+        match self {
+            TxKind::Permit { nonce, .. } => Some(*nonce),
+            _ => None,
+        }
+    }
+
+    /// Stable name (API, explorer and `transactions.tx_type`)
+    pub fn name(&self) -> &'static str {
+        match self {
+            TxKind::Transfer => "transfer",
+            TxKind::Swap { .. } => "swap",
+            TxKind::Stake => "stake",
+            TxKind::Unstake => "unstake",
+            TxKind::StreamEarn { .. } => "stream_earn",
+            TxKind::NftMint { .. } => "nft_mint",
+            TxKind::NftTransfer => "nft_transfer",
+            TxKind::Tip { .. } => "tip",
+            TxKind::GovernanceVote { .. } => "governance_vote",
+            TxKind::FeeDistribution => "fee_distribution",
+            TxKind::Approve { .. } => "approve",
+            TxKind::Permit { .. } => "permit",
+            TxKind::TransferFrom { .. } => "transfer_from",
+            TxKind::AddLiquidity { .. } => "add_liquidity",
+            TxKind::RemoveLiquidity { .. } => "remove_liquidity",
+        }
+    }
+
+    /// Gas fee category of this kind
+    pub fn gas_type(&self) -> TransactionType {
+        match self {
+            TxKind::Transfer
+            | TxKind::Tip { .. }
+            | TxKind::Approve { .. }
+            | TxKind::Permit { .. }
+            | TxKind::TransferFrom { .. } => TransactionType::Transfer,
+            TxKind::Swap { .. } => TransactionType::DexSwap,
+            TxKind::AddLiquidity { .. } => TransactionType::AddLiquidity,
+            TxKind::RemoveLiquidity { .. } => TransactionType::RemoveLiquidity,
+            TxKind::Stake => TransactionType::Stake,
+            TxKind::Unstake => TransactionType::Unstake,
+            TxKind::StreamEarn { .. } => TransactionType::StreamEarn,
+            TxKind::NftMint { .. } => TransactionType::MintNFT,
+            TxKind::NftTransfer => TransactionType::TransferNFT,
+            TxKind::GovernanceVote { .. } => TransactionType::Vote,
+            TxKind::FeeDistribution => TransactionType::ProposeBlock,
+        }
+    }
+
+    /// What leaves `from`'s balance when a transaction of this kind executes:
+    /// the amount when it is paid in DYO, plus the ledger `fee`
+    pub fn sender_cost(&self, amount: u64, fee: u64) -> u64 {
+        match self {
+            TxKind::Transfer
+            | TxKind::Tip { .. }
+            | TxKind::StreamEarn { .. }
+            | TxKind::Stake
+            | TxKind::TransferFrom { .. }
+            | TxKind::AddLiquidity { .. } => amount + fee,
+            TxKind::Swap { token_in, .. } if token_in == NATIVE_TOKEN => amount + fee,
+            TxKind::FeeDistribution | TxKind::RemoveLiquidity { .. } => amount,
+            _ => fee,
+        }
+    }
+
+    /// Bytes appended to the signing / hashing payload (empty for `Transfer`)
+    pub fn encode(&self, payload: &mut Vec<u8>) {
+        let tag: u8 = match self {
+            TxKind::Transfer => return,
+            TxKind::Swap { .. } => 1,
+            TxKind::Stake => 2,
+            TxKind::Unstake => 3,
+            TxKind::StreamEarn { .. } => 4,
+            TxKind::NftMint { .. } => 5,
+            TxKind::NftTransfer => 6,
+            TxKind::Tip { .. } => 7,
+            TxKind::GovernanceVote { .. } => 8,
+            TxKind::FeeDistribution => 9,
+            TxKind::Approve { .. } => 10,
+            TxKind::Permit { .. } => 11,
+            TxKind::TransferFrom { .. } => 12,
+            TxKind::AddLiquidity { .. } => 13,
+            TxKind::RemoveLiquidity { .. } => 14,
+        };
+        payload.push(tag);
+        match self {
+            TxKind::Swap { pool_id, token_in, token_out, amount_out } => {
+                push_field(payload, pool_id.as_bytes());
+                push_field(payload, token_in.as_bytes());
+                push_field(payload, token_out.as_bytes());
+                payload.extend_from_slice(&amount_out.to_be_bytes());
+            }
+            TxKind::StreamEarn { content_id, seconds } => {
+                push_field(payload, content_id.as_bytes());
+                payload.extend_from_slice(&seconds.to_be_bytes());
+            }
+            TxKind::NftMint { ipfs_hash: reference } | TxKind::Tip { content_id: reference } => match reference {
+                Some(value) => {
+                    payload.push(1);
+                    push_field(payload, value.as_bytes());
+                }
+                None => payload.push(0),
+            },
+            TxKind::GovernanceVote { proposal_id, support } => {
+                push_field(payload, proposal_id.as_bytes());
+                payload.push(*support as u8);
+            }
+            TxKind::Approve { previous } => payload.extend_from_slice(&previous.to_be_bytes()),
+            TxKind::Permit { nonce, deadline, signature, previous } => {
+                payload.extend_from_slice(&nonce.to_be_bytes());
+                payload.extend_from_slice(&deadline.to_be_bytes());
+                push_field(payload, signature.as_bytes());
+                payload.extend_from_slice(&previous.to_be_bytes());
+            }
+            TxKind::TransferFrom { spender } => push_field(payload, spender.as_bytes()),
+            TxKind::AddLiquidity { pool_id } | TxKind::RemoveLiquidity { pool_id } => {
+                push_field(payload, pool_id.as_bytes())
+            }
+            TxKind::Transfer | TxKind::Stake | TxKind::Unstake | TxKind::NftTransfer | TxKind::FeeDistribution => {}
+        }
+    }
+
+    /// Stateless shape rules shared by wallets (`SignedTransaction::verify`) and execution
+    pub fn check_shape(&self, from: &str, to: &str, amount: u64, nft_id: Option<&str>) -> Result<(), String> {
+        if from.is_empty() || to.is_empty() {
+            return Err("empty address".to_string());
+        }
+        if from == BURN_ACCOUNT {
+            return Err("the burn sink cannot send".to_string());
+        }
+        let nft_kind = matches!(self, TxKind::NftMint { .. } | TxKind::NftTransfer);
+        match self {
+            TxKind::NftMint { .. } | TxKind::NftTransfer | TxKind::GovernanceVote { .. } if amount != 0 => {
+                return Err(format!("{} does not move funds (amount must be 0)", self.name()));
+            }
+            TxKind::NftMint { .. } | TxKind::NftTransfer | TxKind::GovernanceVote { .. } => {}
+            // The amount of an approval is the allowance (0 revokes it)
+            TxKind::Approve { .. } | TxKind::Permit { .. } => {}
+            _ if amount == 0 => return Err("amount must be greater than 0".to_string()),
+            _ => {}
+        }
+        if nft_kind && nft_id.unwrap_or("").is_empty() {
+            return Err(format!("{} requires an nft_id", self.name()));
+        }
+        if !nft_kind && !self.is_transfer() && nft_id.is_some() {
+            return Err(format!("{} cannot carry an nft_id", self.name()));
+        }
+        match self {
+            TxKind::Stake | TxKind::Unstake if from != to => {
+                Err(format!("{} must be sent to the staker's own address", self.name()))
+            }
+            TxKind::Swap { token_in, token_out, amount_out, .. } => {
+                if token_in == token_out {
+                    Err("swap tokens must differ".to_string())
+                } else if *amount_out == 0 {
+                    Err("swap amount_out must be greater than 0".to_string())
+                } else {
+                    Ok(())
+                }
+            }
+            TxKind::GovernanceVote { proposal_id, .. } if proposal_id.is_empty() => {
+                Err("governance vote requires a proposal_id".to_string())
+            }
+            TxKind::FeeDistribution if from != FEE_COLLECTOR => {
+                Err(format!("{} must be sent from {}", self.name(), FEE_COLLECTOR))
+            }
+            TxKind::Approve { .. } | TxKind::Permit { .. } if !is_service_spender(to) => {
+                Err(format!("{} is not a service that can hold allowances", to))
+            }
+            TxKind::TransferFrom { spender } if !is_service_spender(spender) => {
+                Err(format!("{} is not a service that can hold allowances", spender))
+            }
+            TxKind::AddLiquidity { pool_id } if to != pool_account(pool_id) => {
+                Err(format!("{} must be sent to the account of pool {}", self.name(), pool_id))
+            }
+            TxKind::RemoveLiquidity { pool_id } if from != pool_account(pool_id) => {
+                Err(format!("{} must be sent from the account of pool {}", self.name(), pool_id))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Accounts credited by a fee distribution of `amount` to block proposer `validator`
+pub fn fee_payouts(validator: &str, amount: u64) -> (FeeSplit, [(&str, u64); 4]) {
+    let split = FeeDistribution::split_cents(amount);
+    let payouts = [
+        (TREASURY_ACCOUNT, split.treasury),
+        (validator, split.validator),
+        (CREATIVE_POOL_ACCOUNT, split.creative_pool),
+        (BURN_ACCOUNT, split.burn),
+    ];
+    (split, payouts)
+}
+
+/// On-chain NFT record
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct NftRecord {
+    pub creator: String,
+    pub owner: String,
+    pub ipfs_hash: Option<String>,
+}
+
+/// Ledger state beyond balances and nonces. Ordered maps so the state root and
+/// snapshots encode it deterministically.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct LedgerState {
+    #[serde(default)]
+    pub stakes: BTreeMap<String, u64>, // address -> bonded amount
+    #[serde(default)]
+    pub nfts: BTreeMap<String, NftRecord>, // nft_id -> record
+    #[serde(default)]
+    pub votes: BTreeMap<String, BTreeMap<String, bool>>, // proposal_id -> voter -> support
+    #[serde(default)]
+    pub allowances: BTreeMap<String, BTreeMap<String, u64>>, // owner -> service spender -> DYO
+}
+
+impl LedgerState {
+    pub fn is_empty(&self) -> bool {
+        self.stakes.is_empty() && self.nfts.is_empty() && self.votes.is_empty() && self.allowances.is_empty()
+    }
+
+    pub fn allowance(&self, owner: &str, spender: &str) -> u64 {
+        self.allowances
+            .get(owner)
+            .and_then(|spenders| spenders.get(spender))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Set the allowance of `spender` over `owner`'s DYO (0 removes it)
+    pub fn set_allowance(&mut self, owner: &str, spender: &str, amount: u64) {
+        if amount > 0 {
+            self.allowances
+                .entry(owner.to_string())
+                .or_default()
+                .insert(spender.to_string(), amount);
+        } else if let Some(spenders) = self.allowances.get_mut(owner) {
+            spenders.remove(spender);
+            if spenders.is_empty() {
+                self.allowances.remove(owner);
+            }
+        }
+    }
+
+    pub fn stake_of(&self, address: &str) -> u64 {
+        self.stakes.get(address).copied().unwrap_or(0)
+    }
+
+    /// Canonical bytes hashed into the state root
+    pub fn encode(&self, payload: &mut Vec<u8>) {
+        payload.extend_from_slice(&(self.stakes.len() as u32).to_be_bytes());
+        for (address, stake) in &self.stakes {
+            push_field(payload, address.as_bytes());
+            payload.extend_from_slice(&stake.to_be_bytes());
+        }
+        payload.extend_from_slice(&(self.nfts.len() as u32).to_be_bytes());
+        for (nft_id, record) in &self.nfts {
+            push_field(payload, nft_id.as_bytes());
+            push_field(payload, record.creator.as_bytes());
+            push_field(payload, record.owner.as_bytes());
+            push_field(payload, record.ipfs_hash.as_deref().unwrap_or("").as_bytes());
+        }
+        payload.extend_from_slice(&(self.votes.len() as u32).to_be_bytes());
+        for (proposal_id, voters) in &self.votes {
+            push_field(payload, proposal_id.as_bytes());
+            payload.extend_from_slice(&(voters.len() as u32).to_be_bytes());
+            for (voter, support) in voters {
+                push_field(payload, voter.as_bytes());
+                payload.push(*support as u8);
+            }
+        }
+        // Only once an allowance exists, so the roots of older blocks stay as they were
+        if !self.allowances.is_empty() {
+            payload.extend_from_slice(&(self.allowances.len() as u32).to_be_bytes());
+            for (owner, spenders) in &self.allowances {
+                push_field(payload, owner.as_bytes());
+                payload.extend_from_slice(&(spenders.len() as u32).to_be_bytes());
+                for (spender, amount) in spenders {
+                    push_field(payload, spender.as_bytes());
+                    payload.extend_from_slice(&amount.to_be_bytes());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::blockchain::{Block, Blockchain, Transaction};
+    use crate::blockchain::mempool::TransactionPriority;
+
+    #[test]
+    fn test_transfer_adds_nothing_to_payload() {
+        let mut payload = Vec::new();
+        TxKind::Transfer.encode(&mut payload);
+        assert!(payload.is_empty());
+    }
+
+    #[test]
+    fn test_kinds_encode_differently() {
+        let kinds = [
+            TxKind::Stake,
+            TxKind::Unstake,
+            TxKind::NftTransfer,
+            TxKind::Tip { content_id: None },
+            TxKind::NftMint { ipfs_hash: None },
+            TxKind::GovernanceVote { proposal_id: "p1".to_string(), support: true },
+            TxKind::GovernanceVote { proposal_id: "p1".to_string(), support: false },
+        ];
+        let payloads: Vec<Vec<u8>> = kinds
+            .iter()
+            .map(|kind| {
+                let mut payload = Vec::new();
+                kind.encode(&mut payload);
+                payload
+            })
+            .collect();
+        for (i, a) in payloads.iter().enumerate() {
+            for b in &payloads[i + 1..] {
+                assert_ne!(a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_shape_rules() {
+        assert!(TxKind::Transfer.check_shape("DUa", "DUb", 0, None).is_err());
+        assert!(TxKind::Stake.check_shape("DUa", "DUb", 10, None).is_err());
+        assert!(TxKind::Stake.check_shape("DUa", "DUa", 10, None).is_ok());
+        assert!(TxKind::NftTransfer.check_shape("DUa", "DUb", 0, None).is_err());
+        assert!(TxKind::NftTransfer.check_shape("DUa", "DUb", 5, Some("nft1")).is_err());
+        assert!(TxKind::NftTransfer.check_shape("DUa", "DUb", 0, Some("nft1")).is_ok());
+        assert!(TxKind::Tip { content_id: None }.check_shape("DUa", "DUb", 5, Some("nft1")).is_err());
+    }
+
+    fn funded_chain() -> Blockchain {
+        let mut blockchain = Blockchain::new();
+        blockchain.balances.insert("DUalice".to_string(), 10_000);
+        blockchain.balances.insert(pool_account("DYO_DYS"), 50_000);
+        blockchain
+    }
+
+    fn tx(from: &str, to: &str, amount: u64, nft_id: Option<&str>, kind: TxKind) -> Transaction {
+        Transaction::system(from.to_string(), to.to_string(), amount, nft_id.map(str::to_string)).with_kind(kind)
+    }
+
+    #[test]
+    fn test_stake_and_unstake() {
+        let mut blockchain = funded_chain();
+        blockchain.add_transaction(tx("DUalice", "DUalice", 3_000, None, TxKind::Stake)).unwrap();
+        assert_eq!(blockchain.ledger.stake_of("DUalice"), 3_000);
+        assert_eq!(blockchain.get_balance("DUalice"), 6_990);
+
+        assert!(blockchain.add_transaction(tx("DUalice", "DUalice", 3_001, None, TxKind::Unstake)).is_err());
+        blockchain.add_transaction(tx("DUalice", "DUalice", 1_000, None, TxKind::Unstake)).unwrap();
+        assert_eq!(blockchain.ledger.stake_of("DUalice"), 2_000);
+        assert_eq!(blockchain.get_balance("DUalice"), 7_980);
+    }
+
+    #[test]
+    fn test_nft_ownership_enforced() {
+        let mut blockchain = funded_chain();
+        let mint = TxKind::NftMint { ipfs_hash: Some("Qm1".to_string()) };
+        blockchain.add_transaction(tx("DUalice", "DUalice", 0, Some("nft1"), mint.clone())).unwrap();
+        assert!(blockchain.add_transaction(tx("DUalice", "DUalice", 0, Some("nft1"), mint)).is_err());
+
+        blockchain.balances.insert("DUbob".to_string(), 100);
+        assert!(blockchain.add_transaction(tx("DUbob", "DUbob", 0, Some("nft1"), TxKind::NftTransfer)).is_err());
+        blockchain.add_transaction(tx("DUalice", "DUbob", 0, Some("nft1"), TxKind::NftTransfer)).unwrap();
+        let record = &blockchain.ledger.nfts["nft1"];
+        assert_eq!((record.creator.as_str(), record.owner.as_str()), ("DUalice", "DUbob"));
+    }
+
+    #[test]
+    fn test_one_vote_per_proposal() {
+        let mut blockchain = funded_chain();
+        let vote = TxKind::GovernanceVote { proposal_id: "p1".to_string(), support: true };
+        blockchain.add_transaction(tx("DUalice", "DUalice", 0, None, vote.clone())).unwrap();
+        assert!(blockchain.add_transaction(tx("DUalice", "DUalice", 0, None, vote)).is_err());
+        assert_eq!(blockchain.ledger.votes["p1"].get("DUalice"), Some(&true));
+    }
+
+    #[test]
+    fn test_swap_moves_native_legs() {
+        let mut blockchain = funded_chain();
+        let buy = TxKind::Swap {
+            pool_id: "DYO_DYS".to_string(),
+            token_in: "DYS".to_string(),
+            token_out: NATIVE_TOKEN.to_string(),
+            amount_out: 900,
+        };
+        blockchain.add_transaction(tx("DUalice", "POOL_DYO_DYS", 1_000, None, buy)).unwrap();
+        assert_eq!(blockchain.get_balance("DUalice"), 10_890);
+        assert_eq!(blockchain.get_balance("POOL_DYO_DYS"), 49_100);
+
+        let drain = TxKind::Swap {
+            pool_id: "DYO_DYS".to_string(),
+            token_in: "DYS".to_string(),
+            token_out: NATIVE_TOKEN.to_string(),
+            amount_out: 1_000_000,
+        };
+        assert!(blockchain.add_transaction(tx("DUalice", "POOL_DYO_DYS", 1, None, drain)).is_err());
+
+        // A swap naming one pool cannot pay out of another account
+        blockchain.balances.insert("DUbob".to_string(), 5_000);
+        let redirected = TxKind::Swap {
+            pool_id: "DYO_DYS".to_string(),
+            token_in: "DYS".to_string(),
+            token_out: NATIVE_TOKEN.to_string(),
+            amount_out: 900,
+        };
+        assert!(blockchain.add_transaction(tx("DUalice", "DUbob", 1_000, None, redirected)).is_err());
+        assert_eq!(blockchain.get_balance("DUbob"), 5_000);
+    }
+
+    #[test]
+    fn test_service_pulls_within_allowance() {
+        let mut blockchain = funded_chain();
+        let pull = |amount| tx("DUalice", TREASURY_ACCOUNT, amount, None, TxKind::TransferFrom { spender: BILLING_SPENDER.to_string() });
+
+        // Without an allowance there is no pull, and only services hold allowances
+        assert!(blockchain.add_transaction(pull(100)).is_err());
+        assert!(blockchain.add_transaction(tx("DUalice", "DUbob", 1_000, None, TxKind::Approve { previous: 0 })).is_err());
+
+        blockchain.add_transaction(tx("DUalice", BILLING_SPENDER, 1_000, None, TxKind::Approve { previous: 0 })).unwrap();
+        // An approval based on a stale allowance fails
+        assert!(blockchain.add_transaction(tx("DUalice", BILLING_SPENDER, 5, None, TxKind::Approve { previous: 0 })).is_err());
+
+        blockchain.add_transaction(pull(700)).unwrap();
+        assert_eq!(blockchain.ledger.allowance("DUalice", BILLING_SPENDER), 300);
+        assert_eq!(blockchain.get_balance(TREASURY_ACCOUNT), 700);
+        assert_eq!(blockchain.get_balance("DUalice"), 10_000 - 10 - 710);
+        assert!(blockchain.add_transaction(pull(301)).is_err());
+
+        // Pending approvals and pulls revert exactly
+        let (balances, _, ledger) = blockchain.committed_state();
+        assert!(ledger.allowances.is_empty());
+        assert_eq!(balances.get("DUalice"), Some(&10_000));
+        assert_eq!(blockchain.ledger.allowance("DUalice", MARKETPLACE_SPENDER), 0);
+
+        // Allowances add nothing to the state root until one exists
+        let mut state = LedgerState::default();
+        let mut before = Vec::new();
+        state.encode(&mut before);
+        state.set_allowance("DUalice", BILLING_SPENDER, 5);
+        let mut with_allowance = Vec::new();
+        state.encode(&mut with_allowance);
+        assert_ne!(before, with_allowance);
+        state.set_allowance("DUalice", BILLING_SPENDER, 0);
+        let mut after = Vec::new();
+        state.encode(&mut after);
+        assert_eq!(before, after);
+    }
+
+    #[test]
+    fn test_only_wallet_kinds_are_user_submittable() {
+        assert!(TxKind::Transfer.is_user_submittable());
+        assert!(TxKind::GovernanceVote { proposal_id: "p1".to_string(), support: true }.is_user_submittable());
+        assert!(TxKind::Approve { previous: 0 }.is_user_submittable());
+        assert!(!TxKind::Unstake.is_user_submittable());
+        assert!(!TxKind::TransferFrom { spender: BILLING_SPENDER.to_string() }.is_user_submittable());
+        assert!(!TxKind::FeeDistribution.is_user_submittable());
+        assert!(!TxKind::StreamEarn { content_id: "c1".to_string(), seconds: 1 }.is_user_submittable());
+        assert!(!TxKind::NftMint { ipfs_hash: None }.is_user_submittable());
+        assert!(!TxKind::Swap {
+            pool_id: "DYO_DYS".to_string(),
+            token_in: "DYS".to_string(),
+            token_out: NATIVE_TOKEN.to_string(),
+            amount_out: 1,
+        }
+        .is_user_submittable());
+    }
+
+    #[test]
+    fn test_failed_block_import_leaves_ledger_unchanged() {
+        let mut blockchain = funded_chain();
+        blockchain.add_transaction(tx("DUalice", "DUalice", 1_000, None, TxKind::Stake)).unwrap();
+        let ledger = blockchain.ledger.clone();
+        let root = blockchain.state_root();
+
+        // The stake applies, then the transfer fails: nothing of the block may remain
+        let head = blockchain.get_latest_block().clone();
+        let block = Block::new(
+            head.height + 1,
+            head.timestamp + 10,
+            vec![
+                tx("DUalice", "DUalice", 2_000, None, TxKind::Stake),
+                tx("DUalice", "DUbob", 1_000_000, None, TxKind::Transfer),
+            ],
+            head.hash,
+            root.clone(),
+            Some("system".to_string()),
+        );
+        assert!(blockchain.replay_block(block).is_err());
+        assert_eq!(blockchain.ledger, ledger);
+        assert_eq!(blockchain.ledger.stake_of("DUalice"), 1_000);
+        assert_eq!(blockchain.state_root(), root);
+        assert_eq!(blockchain.pending_transactions.len(), 1);
+    }
+
+    #[test]
+    fn test_transaction_group_is_all_or_nothing() {
+        let mut blockchain = funded_chain();
+        let root = blockchain.state_root();
+        let buy = TxKind::Swap {
+            pool_id: "DYO_DYS".to_string(),
+            token_in: "DYS".to_string(),
+            token_out: NATIVE_TOKEN.to_string(),
+            amount_out: 500,
+        };
+        let swap = tx("DUalice", &pool_account("DYO_DYS"), 600, None, buy);
+        let too_much = tx("DUalice", "DUbob", 20_000, None, TxKind::Transfer);
+        assert!(blockchain.add_transactions(vec![swap.clone(), too_much], TransactionPriority::Normal).is_err());
+        assert_eq!(blockchain.state_root(), root);
+        assert!(blockchain.pending_transactions.is_empty());
+
+        let transfer = tx("DUalice", "DUbob", 10_000, None, TxKind::Transfer);
+        blockchain.add_transactions(vec![swap.clone(), transfer.clone()], TransactionPriority::Normal).unwrap();
+        assert_eq!(blockchain.pending_transactions.len(), 2);
+
+        // Dropping the swap leaves the transfer it paid for unaffordable
+        blockchain.remove_pending(std::slice::from_ref(&swap));
+        assert!(blockchain.pending_transactions.is_empty());
+        assert_eq!(blockchain.state_root(), root);
+    }
+
+    #[test]
+    fn test_committed_state_reverts_every_kind() {
+        let mut blockchain = funded_chain();
+        let root = blockchain.state_root();
+        let kinds = vec![
+            tx("DUalice", "DUbob", 100, None, TxKind::Tip { content_id: Some("c1".to_string()) }),
+            tx("DUalice", "DUalice", 500, None, TxKind::Stake),
+            tx("DUalice", "DUalice", 200, None, TxKind::Unstake),
+            tx("DUalice", "DUbob", 0, Some("nft1"), TxKind::NftMint { ipfs_hash: None }),
+            tx("DUalice", "DUalice", 0, None, TxKind::GovernanceVote { proposal_id: "p1".to_string(), support: false }),
+            tx(
+                "DUalice",
+                "POOL_DYO_DYS",
+                700,
+                None,
+                TxKind::Swap {
+                    pool_id: "DYO_DYS".to_string(),
+                    token_in: NATIVE_TOKEN.to_string(),
+                    token_out: "DYS".to_string(),
+                    amount_out: 650,
+                },
+            ),
+            tx("DUalice", "DUcarol", 50, None, TxKind::StreamEarn { content_id: "c1".to_string(), seconds: 60 }),
+        ];
+        for transaction in kinds {
+            blockchain.add_transaction(transaction).unwrap();
+        }
+        assert_ne!(blockchain.state_root(), root);
+        assert_eq!(blockchain.committed_state_root(), root);
+    }
+
+    #[test]
+    fn test_ledger_is_part_of_state_root() {
+        let blockchain = funded_chain();
+        let mut ledger = LedgerState::default();
+        let empty_root = Blockchain::compute_state_root(&blockchain.balances, &blockchain.nonces, &ledger);
+        assert_eq!(empty_root, blockchain.state_root());
+
+        ledger.stakes.insert("DUalice".to_string(), 1);
+        assert_ne!(Blockchain::compute_state_root(&blockchain.balances, &blockchain.nonces, &ledger), empty_root);
+    }
+
+    #[test]
+    fn test_block_distributes_collected_fees() {
+        let mut blockchain = funded_chain();
+        blockchain.add_transaction(tx("DUalice", FEE_COLLECTOR, 1_000, None, TxKind::Transfer)).unwrap();
+        blockchain.add_transaction(tx("DUalice", "DUbob", 100, None, TxKind::Transfer)).unwrap();
+
+        let (transactions, state_root) = blockchain.take_block_transactions(10, "DUvalidator").unwrap();
+        let distribution = transactions.last().unwrap();
+        assert_eq!(distribution.kind, TxKind::FeeDistribution);
+        assert_eq!((distribution.from.as_str(), distribution.amount), (FEE_COLLECTOR, 1_000));
+        assert_eq!(state_root, blockchain.state_root());
+        assert_eq!(blockchain.get_balance(FEE_COLLECTOR), 0);
+        assert_eq!(blockchain.get_balance(TREASURY_ACCOUNT), 400);
+        assert_eq!(blockchain.get_balance("DUvalidator"), 300);
+        assert_eq!(blockchain.get_balance(CREATIVE_POOL_ACCOUNT), 200);
+        assert_eq!(blockchain.get_balance(BURN_ACCOUNT), 100);
+
+        // Nothing collected, nothing distributed
+        let (transactions, _) = blockchain.take_block_transactions(10, "DUvalidator").unwrap();
+        assert!(transactions.is_empty());
+    }
+
+    #[test]
+    fn test_failed_fee_distribution_produces_no_block() {
+        let mut blockchain = funded_chain();
+        blockchain.add_transaction(tx("DUalice", FEE_COLLECTOR, 1_000, None, TxKind::Transfer)).unwrap();
+        blockchain.add_transaction(tx("DUalice", "DUbob", 100, None, TxKind::Transfer)).unwrap();
+        let root = blockchain.state_root();
+        let hashes = |blockchain: &Blockchain| -> Vec<String> {
+            blockchain.pending_transactions.iter().map(|tx| tx.tx_hash()).collect()
+        };
+        let pending = hashes(&blockchain);
+
+        // A distribution that cannot be applied (no proposer address): no block, mempool and state untouched
+        assert!(blockchain.take_block_transactions(10, "").is_err());
+        assert_eq!(hashes(&blockchain), pending);
+        assert_eq!(blockchain.state_root(), root);
+        assert_eq!(blockchain.get_balance(FEE_COLLECTOR), 1_000);
+    }
+
+    #[test]
+    fn test_fee_distribution_rules() {
+        let mut blockchain = funded_chain();
+        blockchain.balances.insert(FEE_COLLECTOR.to_string(), 500);
+        blockchain.balances.insert(BURN_ACCOUNT.to_string(), 500);
+        let root = blockchain.state_root();
+
+        // Only proposers create distributions, and the burn sink never sends
+        assert!(blockchain.add_transaction(tx(FEE_COLLECTOR, "DUalice", 500, None, TxKind::FeeDistribution)).is_err());
+        assert!(blockchain.add_transaction(tx(BURN_ACCOUNT, "DUalice", 100, None, TxKind::Transfer)).is_err());
+        assert!(TxKind::FeeDistribution.check_shape("DUalice", "DUbob", 500, None).is_err());
+        assert_eq!(blockchain.state_root(), root);
+
+        // A distribution must be last and pay the block proposer
+        let distribution = tx(FEE_COLLECTOR, "DUvalidator", 500, None, TxKind::FeeDistribution);
+        let transfer = tx("DUalice", "DUbob", 100, None, TxKind::Transfer);
+        let block = |transactions: Vec<Transaction>, proposer: &str| {
+            Block::new(1, 10, transactions, "parent".to_string(), "state".to_string(), Some(proposer.to_string()))
+        };
+        assert!(block(vec![transfer.clone(), distribution.clone()], "DUvalidator").verify_structure().is_ok());
+        assert!(block(vec![distribution.clone(), transfer], "DUvalidator").verify_structure().is_err());
+        assert!(block(vec![distribution.clone()], "DUmallory").verify_structure().is_err());
+        assert_eq!(block(vec![distribution], "DUvalidator").fee_split().map(|split| split.total()), Some(500));
+    }
+
+    #[test]
+    fn test_serde_tag() {
+        let kind = TxKind::StreamEarn { content_id: "c1".to_string(), seconds: 30 };
+        let json = serde_json::to_value(&kind).unwrap();
+        assert_eq!(json["type"], "stream_earn");
+        assert_eq!(serde_json::from_value::<TxKind>(json).unwrap(), kind);
+    }
+}
@@ -0,0 +1,183 @@
+//! Priority Mempool
+//!
+//! Pending transactions grouped in bundles (what one submission added together,
+//! e.g. gas auto-swap + gas fee + signed transaction) and queued by
+//! `TransactionPriority`. Block production drains it highest priority first,
+//! FIFO inside a priority, and never splits a bundle across blocks.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use crate::blockchain::blockchain::Transaction;
+
+/// Pending transactions the mempool is sized for (utilization is relative to it)
+pub const MAX_MEMPOOL_SIZE: usize = 10_000;
+
+/// Transaction priority levels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionPriority {
+    Low,    // No priority tip
+    #[default]
+    Normal, // Priority tip, and system transactions
+    High,   // Priority tip of at least the gas fee
+}
+
+impl TransactionPriority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TransactionPriority::Low => "low",
+            TransactionPriority::Normal => "normal",
+            TransactionPriority::High => "high",
+        }
+    }
+}
+
+/// Transactions that enter a block together, in execution order
+#[derive(Debug, Clone)]
+pub struct Bundle {
+    /// Admission order (lower = older)
+    pub seq: u64,
+    pub priority: TransactionPriority,
+    pub transactions: Vec<Transaction>,
+}
+
+/// Optimized mempool with priority queuing
+#[derive(Debug, Clone)]
+pub struct OptimizedMempool {
+    pub high_priority: VecDeque<Bundle>,
+    pub normal_priority: VecDeque<Bundle>,
+    pub low_priority: VecDeque<Bundle>,
+    pub max_size: usize,
+    pub total_size: usize,
+}
+
+impl OptimizedMempool {
+    pub fn new(max_size: usize) -> Self {
+        Self {
+            high_priority: VecDeque::new(),
+            normal_priority: VecDeque::new(),
+            low_priority: VecDeque::new(),
+            max_size,
+            total_size: 0,
+        }
+    }
+
+    /// Add a single transaction with priority
+    pub fn add_transaction(&mut self, transaction: Transaction, seq: u64, priority: TransactionPriority) {
+        self.add_bundle(Bundle { seq, priority, transactions: vec![transaction] });
+    }
+
+    /// Add a bundle (queued by its priority)
+    pub fn add_bundle(&mut self, bundle: Bundle) {
+        self.total_size += bundle.transactions.len();
+        self.queue_mut(bundle.priority).push_back(bundle);
+    }
+
+    /// Next bundle to include: highest priority first, oldest first inside a priority
+    pub fn next_bundle(&mut self) -> Option<Bundle> {
+        let bundle = self.high_priority.pop_front()
+            .or_else(|| self.normal_priority.pop_front())
+            .or_else(|| self.low_priority.pop_front())?;
+        self.total_size -= bundle.transactions.len();
+        Some(bundle)
+    }
+
+    /// Get next batch of at most `batch_size` transactions (bundles that do not
+    /// fit stay queued)
+    pub fn get_next_batch(&mut self, batch_size: usize) -> Vec<Transaction> {
+        let mut batch = Vec::new();
+        for priority in [TransactionPriority::High, TransactionPriority::Normal, TransactionPriority::Low] {
+            let queue = std::mem::take(self.queue_mut(priority));
+            for bundle in queue {
+                if batch.len() + bundle.transactions.len() > batch_size {
+                    self.queue_mut(priority).push_back(bundle);
+                    continue;
+                }
+                self.total_size -= bundle.transactions.len();
+                batch.extend(bundle.transactions);
+            }
+        }
+        batch
+    }
+
+    /// Get mempool statistics
+    pub fn get_stats(&self) -> MempoolStats {
+        let count = |queue: &VecDeque<Bundle>| queue.iter().map(|bundle| bundle.transactions.len()).sum();
+        MempoolStats {
+            total_transactions: self.total_size,
+            high_priority_count: count(&self.high_priority),
+            normal_priority_count: count(&self.normal_priority),
+            low_priority_count: count(&self.low_priority),
+            utilization_percent: if self.max_size == 0 {
+                100.0
+            } else {
+                (self.total_size as f64 / self.max_size as f64) * 100.0
+            },
+        }
+    }
+
+    fn queue_mut(&mut self, priority: TransactionPriority) -> &mut VecDeque<Bundle> {
+        match priority {
+            TransactionPriority::High => &mut self.high_priority,
+            TransactionPriority::Normal => &mut self.normal_priority,
+            TransactionPriority::Low => &mut self.low_priority,
+        }
+    }
+}
+
+/// Mempool statistics
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MempoolStats {
+    pub total_transactions: usize,
+    pub high_priority_count: usize,
+    pub normal_priority_count: usize,
+    pub low_priority_count: usize,
+    pub utilization_percent: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::blockchain::Blockchain;
+    use crate::blockchain::ledger::TxKind;
+
+    fn transfer(from: &str, to: &str, amount: u64) -> Transaction {
+        Transaction::system(from.to_string(), to.to_string(), amount, None)
+    }
+
+    #[test]
+    fn test_block_takes_priority_bundles_first() {
+        let mut blockchain = Blockchain::new();
+        blockchain.balances.insert("DUalice".to_string(), 10_000);
+        blockchain.balances.insert("DUcarol".to_string(), 10_000);
+
+        let untipped = transfer("DUalice", "DUbob", 100);
+        let tipped = vec![transfer("DUcarol", "GAS_FEE_ADDRESS", 5), transfer("DUcarol", "DUbob", 100)];
+        let system = transfer("DUalice", "DUdave", 50);
+        blockchain.add_transactions(vec![untipped.clone()], TransactionPriority::Low).unwrap();
+        blockchain.add_transactions(tipped.clone(), TransactionPriority::High).unwrap();
+        blockchain.add_transaction(system.clone()).unwrap();
+
+        let stats = blockchain.mempool().get_stats();
+        assert_eq!(stats.total_transactions, 4);
+        assert_eq!((stats.high_priority_count, stats.normal_priority_count, stats.low_priority_count), (2, 1, 1));
+
+        // Room for 3: the tipped bundle, then the system transfer; the untipped one waits.
+        // The block closes with the distribution of the collected tip.
+        let (included, state_root) = blockchain.take_block_transactions(3, "DUproposer").unwrap();
+        let hashes: Vec<String> = included[..3].iter().map(Transaction::tx_hash).collect();
+        let expected: Vec<String> = tipped.iter().chain([&system]).map(Transaction::tx_hash).collect();
+        assert_eq!(hashes, expected);
+        assert_eq!(included[3].kind, TxKind::FeeDistribution);
+        assert_eq!(included[3].amount, 5);
+        assert_eq!(blockchain.pending_transactions.len(), 1);
+        assert_eq!(blockchain.pending_transactions[0].tx_hash(), untipped.tx_hash());
+        assert_eq!(blockchain.mempool().get_stats().low_priority_count, 1);
+
+        // The returned root excludes the waiting transfer, the live state includes it
+        assert_eq!(state_root, blockchain.committed_state_root());
+        assert_ne!(state_root, blockchain.state_root());
+        assert_eq!(blockchain.get_balance("DUbob"), 200);
+    }
+}
@@ -0,0 +1,268 @@
+//! SHA-256 Merkle Tree for Block Transactions
+//!
+//! Leaves are the 32-byte transaction hashes. Leaf and interior nodes are hashed
+//! with distinct prefixes (0x00 / 0x01) so an interior node can never be passed
+//! off as a transaction. When a level has an odd number of nodes the last node is
+//! promoted unchanged instead of being duplicated, which avoids the classic
+//! "duplicate last transaction" root collision.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+pub type Hash32 = [u8; 32];
+
+/// Side on which the sibling sits when recomputing the parent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SiblingPosition {
+    Left,
+    Right,
+}
+
+/// One step of an inclusion proof (sibling hash in hex)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MerkleStep {
+    pub hash: String,
+    pub position: SiblingPosition,
+}
+
+/// Inclusion proof of a transaction hash in a block Merkle root
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MerkleProof {
+    pub tx_hash: String,
+    pub leaf_index: usize,
+    pub leaf_count: usize,
+    pub siblings: Vec<MerkleStep>,
+}
+
+/// Hash a leaf (transaction hash)
+pub fn hash_leaf(tx_hash: &Hash32) -> Hash32 {
+    let mut hasher = Sha256::new();
+    hasher.update([LEAF_PREFIX]);
+    hasher.update(tx_hash);
+    hasher.finalize().into()
+}
+
+/// Hash two children into their parent
+pub fn hash_node(left: &Hash32, right: &Hash32) -> Hash32 {
+    let mut hasher = Sha256::new();
+    hasher.update([NODE_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Root of an empty tree (block without transactions)
+pub fn empty_root() -> Hash32 {
+    Sha256::digest(b"").into()
+}
+
+/// Decode a hex transaction hash into 32 bytes
+pub fn decode_hash(hash_hex: &str) -> Result<Hash32, String> {
+    let bytes = hex::decode(hash_hex).map_err(|e| format!("Invalid hash hex: {}", e))?;
+    bytes
+        .as_slice()
+        .try_into()
+        .map_err(|_| format!("Invalid hash length: expected 32 bytes, got {}", bytes.len()))
+}
+
+fn next_level(level: &[Hash32]) -> Vec<Hash32> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => hash_node(left, right),
+            [single] => *single,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+/// Merkle root over transaction hashes (in block order)
+pub fn merkle_root(tx_hashes: &[Hash32]) -> Hash32 {
+    if tx_hashes.is_empty() {
+        return empty_root();
+    }
+
+    let mut level: Vec<Hash32> = tx_hashes.iter().map(hash_leaf).collect();
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level[0]
+}
+
+/// Build the inclusion proof for the leaf at `index`
+pub fn build_proof(tx_hashes: &[Hash32], index: usize) -> Option<MerkleProof> {
+    if index >= tx_hashes.len() {
+        return None;
+    }
+
+    let mut siblings = Vec::new();
+    let mut level: Vec<Hash32> = tx_hashes.iter().map(hash_leaf).collect();
+    let mut position = index;
+
+    while level.len() > 1 {
+        let sibling = if position.is_multiple_of(2) {
+            level.get(position + 1).map(|hash| (hash, SiblingPosition::Right))
+        } else {
+            Some((&level[position - 1], SiblingPosition::Left))
+        };
+        // A promoted odd node has no sibling at this level
+        if let Some((hash, side)) = sibling {
+            siblings.push(MerkleStep {
+                hash: hex::encode(hash),
+                position: side,
+            });
+        }
+        level = next_level(&level);
+        position /= 2;
+    }
+
+    Some(MerkleProof {
+        tx_hash: hex::encode(tx_hashes[index]),
+        leaf_index: index,
+        leaf_count: tx_hashes.len(),
+        siblings,
+    })
+}
+
+/// Verify an inclusion proof against a hex Merkle root
+///
+/// The sibling sides are not trusted: they are derived from `leaf_index` and
+/// `leaf_count`, and the steps must match the shape of a tree of that size
+pub fn verify_proof(merkle_root_hex: &str, proof: &MerkleProof) -> bool {
+    let (Ok(root), Ok(tx_hash)) = (decode_hash(merkle_root_hex), decode_hash(&proof.tx_hash)) else {
+        return false;
+    };
+    if proof.leaf_index >= proof.leaf_count {
+        return false;
+    }
+
+    let mut current = hash_leaf(&tx_hash);
+    let mut steps = proof.siblings.iter();
+    let mut position = proof.leaf_index;
+    let mut width = proof.leaf_count;
+
+    while width > 1 {
+        // A promoted odd node has no sibling at this level
+        if position + 1 < width || !position.is_multiple_of(2) {
+            let expected = if position.is_multiple_of(2) {
+                SiblingPosition::Right
+            } else {
+                SiblingPosition::Left
+            };
+            let Some(step) = steps.next() else {
+                return false;
+            };
+            let Ok(sibling) = decode_hash(&step.hash) else {
+                return false;
+            };
+            if step.position != expected {
+                return false;
+            }
+            current = match expected {
+                SiblingPosition::Left => hash_node(&sibling, &current),
+                SiblingPosition::Right => hash_node(&current, &sibling),
+            };
+        }
+        position /= 2;
+        width = width.div_ceil(2);
+    }
+    steps.next().is_none() && current == root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaves(n: u8) -> Vec<Hash32> {
+        (0..n).map(|i| Sha256::digest([i]).into()).collect()
+    }
+
+    #[test]
+    fn test_single_leaf_root() {
+        let hashes = leaves(1);
+        assert_eq!(merkle_root(&hashes), hash_leaf(&hashes[0]));
+    }
+
+    #[test]
+    fn test_proofs_verify_for_every_leaf() {
+        for n in 1..=9 {
+            let hashes = leaves(n);
+            let root = hex::encode(merkle_root(&hashes));
+            for i in 0..hashes.len() {
+                let proof = build_proof(&hashes, i).unwrap();
+                assert!(verify_proof(&root, &proof), "leaf {} of {} should verify", i, n);
+            }
+        }
+    }
+
+    #[test]
+    fn test_tampered_proof_rejected() {
+        let hashes = leaves(5);
+        let root = hex::encode(merkle_root(&hashes));
+        let mut proof = build_proof(&hashes, 2).unwrap();
+        proof.tx_hash = hex::encode(leaves(9)[8]);
+        assert!(!verify_proof(&root, &proof));
+    }
+
+    #[test]
+    fn test_proof_shape_must_match_leaf_index() {
+        let hashes = leaves(5);
+        let root = hex::encode(merkle_root(&hashes));
+        let proof = build_proof(&hashes, 2).unwrap();
+
+        let mut moved = proof.clone();
+        moved.leaf_index = 3;
+        assert!(!verify_proof(&root, &moved));
+
+        let mut out_of_range = proof.clone();
+        out_of_range.leaf_index = 5;
+        assert!(!verify_proof(&root, &out_of_range));
+
+        // The last leaf is promoted twice: a full tree of 8 would need 3 siblings
+        let mut resized = build_proof(&hashes, 4).unwrap();
+        assert!(verify_proof(&root, &resized));
+        resized.leaf_count = 8;
+        assert!(!verify_proof(&root, &resized));
+
+        let mut flipped = proof.clone();
+        flipped.siblings[0].position = SiblingPosition::Left;
+        assert!(!verify_proof(&root, &flipped));
+
+        let mut padded = proof;
+        padded.siblings.push(padded.siblings[0].clone());
+        assert!(!verify_proof(&root, &padded));
+    }
+
+    #[test]
+    fn test_duplicated_last_leaf_changes_root() {
+        let mut hashes = leaves(3);
+        let root = merkle_root(&hashes);
+        hashes.push(hashes[2]);
+        assert_ne!(root, merkle_root(&hashes));
+    }
+
+    #[test]
+    fn test_block_transaction_proof() {
+        use crate::blockchain::blockchain::{Block, Transaction};
+
+        let transactions: Vec<Transaction> = (1..=3)
+            .map(|i| Transaction::system("S2E_POOL".to_string(), format!("DUartist{}", i), i * 100, None))
+            .collect();
+        let target = transactions[1].tx_hash();
+        let block = Block::new(1, 1_700_000_010, transactions, "parent".to_string(), "state".to_string(), None);
+
+        let proof = block.merkle_proof(&target).unwrap();
+        assert!(verify_proof(&block.header().merkle_root, &proof));
+        assert_eq!(block.hash, block.header().hash());
+        assert!(block.merkle_proof(&hex::encode([0u8; 32])).is_none());
+    }
+
+    #[test]
+    fn test_out_of_range_proof() {
+        assert!(build_proof(&leaves(3), 3).is_none());
+    }
+}
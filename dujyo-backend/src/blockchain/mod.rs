@@ -1,9 +1,16 @@
 // src/blockchain/mod.rs
 
-pub mod block;
 pub mod blockchain;
 pub mod token;
-pub mod transaction;
 pub mod gas_fees;
 pub mod real_blockchain;
-
+pub mod signed_transaction;
+pub mod merkle;
+pub mod fork_choice;
+pub mod block_verifier;
+pub mod state_store;
+pub mod ledger;
+pub mod mempool;
+pub mod fee_market;
+pub mod native_token;
+pub mod staking_rewards;
@@ -275,28 +275,7 @@ impl NativeToken {
         }
 
         // Verificar límites diarios para transfers grandes
-        if request.amount > 50_000_000 { // > $50k USD equivalent
-            if !self.kyc_verified.get(&request.from).unwrap_or(&false) {
-                return Err("KYC verification required for large transfers".to_string());
-            }
-
-            // Verificar límite diario
-            if let Some(limit) = self.daily_limits.get_mut(&request.from) {
-                let now = get_current_timestamp().map_err(|e| format!("Failed to get timestamp: {}", e))?;
-                
-                // Reset diario si es un nuevo día
-                if now - limit.last_reset > 86400 { // 24 horas
-                    limit.used_today = 0;
-                    limit.last_reset = now;
-                }
-
-                if limit.used_today + request.amount > limit.daily_limit {
-                    return Err("Daily transfer limit exceeded".to_string());
-                }
-
-                limit.used_today += request.amount;
-            }
-        }
+        self.check_transfer_limits(&request.from, request.amount)?;
 
         // Verificar si hay timelock delay
         if let Some(delay) = self.timelock_delays.get(&request.from) {
@@ -687,6 +666,33 @@ impl NativeToken {
         Ok(())
     }
 
+    /// KYC y límite diario para transfers grandes
+    fn check_transfer_limits(&mut self, from: &str, amount: u64) -> Result<(), String> {
+        if amount > 50_000_000 { // > $50k USD equivalent
+            if !self.kyc_verified.get(from).unwrap_or(&false) {
+                return Err("KYC verification required for large transfers".to_string());
+            }
+
+            // Verificar límite diario
+            if let Some(limit) = self.daily_limits.get_mut(from) {
+                let now = get_current_timestamp().map_err(|e| format!("Failed to get timestamp: {}", e))?;
+                
+                // Reset diario si es un nuevo día
+                if now - limit.last_reset > 86400 { // 24 horas
+                    limit.used_today = 0;
+                    limit.last_reset = now;
+                }
+
+                if limit.used_today + amount > limit.daily_limit {
+                    return Err("Daily transfer limit exceeded".to_string());
+                }
+
+                limit.used_today += amount;
+            }
+        }
+        Ok(())
+    }
+
     /// Check emergency pause status
     fn check_emergency_pause(&self) -> Result<(), String> {
         if self.emergency_paused {
@@ -4,6 +4,8 @@ use serde::{Deserialize, Serialize};
 use std::time::{SystemTime, UNIX_EPOCH};
 use tracing::error;
 
+use crate::utils::amount::Amount;
+
 /// ✅ SECURITY FIX: Safe timestamp helper
 fn get_current_timestamp() -> Result<u64, String> {
     SystemTime::now()
@@ -15,15 +17,40 @@ fn get_current_timestamp() -> Result<u64, String> {
         })
 }
 
+/// Swap fee of the simulator pools (0.3%)
+const SWAP_FEE_BPS: u64 = 30;
+
+/// Staking rewards, pro rata per second (12% APY)
+const STAKING_APY_BPS: u64 = 1_200;
+
+/// Fee kept when a position is unstaked (1%)
+const UNSTAKE_FEE_BPS: u64 = 100;
+
+/// Unstaked DYO stays bonded, and slashable, this long by default (21 days)
+const DEFAULT_UNBONDING_SECS: u64 = 21 * 24 * 3600;
+
+/// Seconds between unstaking and the release of the DYO (DUJYO_UNBONDING_SECS, default 21 days)
+pub fn unbonding_period_secs() -> u64 {
+    std::env::var("DUJYO_UNBONDING_SECS")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_UNBONDING_SECS)
+}
+
+const SECONDS_PER_YEAR: u128 = 365 * 24 * 3600;
+
+/// Transacción del simulador multi-token (DYO/DYS) de `RealBlockchain`. No es
+/// parte de la cadena: los cambios de estado on-chain son `blockchain::Transaction`
+/// con su `TxKind` (ver `blockchain::ledger`).
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RealTransaction {
     pub from: String,
     pub to: String,
-    pub amount: f64,
+    pub amount: Amount,
     pub token: String, // "DYO" or "DYS"
     pub timestamp: u64,
     pub nonce: u64,
-    pub tx_type: String, // "TRANSFER", "MINT", "STAKE", "UNSTAKE", "SWAP"
+    pub tx_type: String, // "TRANSFER", "MINT", "STAKE", "UNBOND", "UNSTAKE", "SLASH", "SWAP"
     pub data: Option<serde_json::Value>,
 }
 
@@ -38,41 +65,131 @@ pub struct RealBlock {
     pub hash: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TokenBalance {
-    pub dyo: f64,
-    pub dys: f64,
-    pub staked: f64,
-    pub total: f64,
+    pub dyo: Amount,
+    pub dys: Amount,
+    pub staked: Amount,
+    pub total: Amount,
+}
+
+impl TokenBalance {
+    pub fn new(dyo: Amount, dys: Amount, staked: Amount) -> Self {
+        let mut balance = TokenBalance { dyo, dys, staked, total: Amount::ZERO };
+        balance.refresh_total();
+        balance
+    }
+
+    /// From the micro-token columns of `token_balances` (negative values read as zero)
+    pub fn from_micro(dyo: i64, dys: i64, staked: i64) -> Self {
+        let micro = |value: i64| Amount::from_micro(value.max(0) as u64);
+        TokenBalance::new(micro(dyo), micro(dys), micro(staked))
+    }
+
+    /// Micro-token columns for `token_balances`, truncating below 1e-6
+    pub fn to_micro(&self) -> Result<(i64, i64, i64), String> {
+        let micro = |value: Amount| {
+            value
+                .to_micro_floor()
+                .ok()
+                .and_then(|micro| i64::try_from(micro).ok())
+                .ok_or_else(|| format!("Balance {} does not fit in token_balances", value))
+        };
+        Ok((micro(self.dyo)?, micro(self.dys)?, micro(self.staked)?))
+    }
+
+    pub fn refresh_total(&mut self) {
+        self.total = self.dyo.saturating_add(self.dys).saturating_add(self.staked);
+    }
+
+    /// Spendable balance of a DYO/DYS token (`None` for other tokens)
+    pub fn available(&self, token: &str) -> Option<Amount> {
+        match token {
+            "DYO" => Some(self.dyo),
+            "DYS" => Some(self.dys),
+            _ => None,
+        }
+    }
+
+    pub fn debit(&mut self, token: &str, amount: Amount) -> Result<(), String> {
+        let balance = self.token_mut(token).ok_or_else(|| format!("Unsupported token: {}", token))?;
+        *balance = balance.checked_sub(amount)
+            .map_err(|_| format!("Insufficient {} balance. Available: {}, Required: {}", token, balance, amount))?;
+        self.refresh_total();
+        Ok(())
+    }
+
+    pub fn credit(&mut self, token: &str, amount: Amount) -> Result<(), String> {
+        let balance = self.token_mut(token).ok_or_else(|| format!("Unsupported token: {}", token))?;
+        *balance = balance.checked_add(amount).map_err(|e| format!("{} balance overflow: {}", token, e))?;
+        self.refresh_total();
+        Ok(())
+    }
+
+    fn token_mut(&mut self, token: &str) -> Option<&mut Amount> {
+        match token {
+            "DYO" => Some(&mut self.dyo),
+            "DYS" => Some(&mut self.dys),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SwapResult {
     pub success: bool,
-    pub amount_in: f64,
-    pub amount_out: f64,
+    pub amount_in: Amount,
+    pub amount_out: Amount,
     pub price_impact: f64,
-    pub fee: f64,
+    pub fee: Amount,
     pub error: Option<String>,
 }
 
+impl SwapResult {
+    fn rejected(error: &str) -> Self {
+        SwapResult {
+            success: false,
+            amount_in: Amount::ZERO,
+            amount_out: Amount::ZERO,
+            price_impact: 0.0,
+            fee: Amount::ZERO,
+            error: Some(error.to_string()),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StakingResult {
     pub success: bool,
     pub position_id: Option<String>,
-    pub amount: Option<f64>,
-    pub rewards: Option<f64>,
+    pub amount: Option<Amount>,
+    pub rewards: Option<Amount>,
+    /// When unstaked DYO is released (unbonding)
+    pub completes_at: Option<u64>,
     pub error: Option<String>,
 }
 
+impl StakingResult {
+    fn rejected(error: &str) -> Self {
+        StakingResult {
+            success: false,
+            position_id: None,
+            amount: None,
+            rewards: None,
+            completes_at: None,
+            error: Some(error.to_string()),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PoolInfo {
     pub token_a: String,
     pub token_b: String,
-    pub reserve_a: f64,
-    pub reserve_b: f64,
-    pub total_liquidity: f64,
-    pub fee: f64,
+    pub reserve_a: Amount,
+    pub reserve_b: Amount,
+    pub total_liquidity: Amount,
+    pub fee: f64, // percent, display only (swaps charge SWAP_FEE_BPS)
 }
 
 pub struct RealBlockchain {
@@ -80,9 +197,12 @@ pub struct RealBlockchain {
     pub balances: HashMap<String, TokenBalance>,
     pub pools: HashMap<String, PoolInfo>,
     pub staking_positions: HashMap<String, StakingPosition>,
+    /// Unstaked positions waiting out the unbonding period
+    pub unbonding: Vec<UnbondingEntry>,
+    pub unbonding_period_secs: u64,
     pub mempool: Vec<RealTransaction>,
-    pub total_supply_dyo: f64,
-    pub total_supply_dys: f64,
+    pub total_supply_dyo: Amount,
+    pub total_supply_dys: Amount,
     pub is_running: bool,
 }
 
@@ -90,13 +210,45 @@ pub struct RealBlockchain {
 pub struct StakingPosition {
     pub id: String,
     pub user: String,
-    pub amount: f64,
+    pub amount: Amount,
     pub start_time: u64,
     pub end_time: u64,
-    pub rewards: f64,
+    pub rewards: Amount,
     pub is_active: bool,
 }
 
+/// Unstaked DYO on its way out. It stays in the owner's `staked` balance, and
+/// can still be slashed, until `completes_at`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnbondingEntry {
+    pub position_id: String,
+    pub user: String,
+    /// Validator whose slashing still cuts the entry (None: not slashable)
+    pub validator: Option<String>,
+    /// Bonded amount still held (after any slashing)
+    pub amount: Amount,
+    pub fee: Amount,
+    pub rewards: Amount,
+    pub slashed: Amount,
+    pub started_at: u64,
+    pub completes_at: u64,
+}
+
+impl UnbondingEntry {
+    /// DYO credited when the entry is released
+    pub fn payout(&self) -> Amount {
+        self.amount.saturating_sub(self.fee).saturating_add(self.rewards)
+    }
+
+    /// Cut `slash_bps` of the amount still held for a slashing of its validator
+    pub fn slash(&mut self, slash_bps: u64) -> Result<Amount, String> {
+        let cut = self.amount.mul_bps(slash_bps).map_err(|e| e.to_string())?;
+        self.amount = self.amount.saturating_sub(cut);
+        self.slashed = self.slashed.saturating_add(cut);
+        Ok(cut)
+    }
+}
+
 impl RealBlockchain {
     pub fn new() -> Self {
         let mut blockchain = RealBlockchain {
@@ -104,9 +256,11 @@ impl RealBlockchain {
             balances: HashMap::new(),
             pools: HashMap::new(),
             staking_positions: HashMap::new(),
+            unbonding: Vec::new(),
+            unbonding_period_secs: unbonding_period_secs(),
             mempool: Vec::new(),
-            total_supply_dyo: 0.0,
-            total_supply_dys: 0.0,
+            total_supply_dyo: Amount::ZERO,
+            total_supply_dys: Amount::ZERO,
             is_running: true,
         };
 
@@ -116,8 +270,8 @@ impl RealBlockchain {
         });
 
         // Initialize admin wallet with initial supply
-        blockchain.mint_tokens("ADMIN_ADDRESS", 10000000.0, "DYO");
-        blockchain.mint_tokens("ADMIN_ADDRESS", 1000000.0, "DYS");
+        blockchain.mint_tokens("ADMIN_ADDRESS", Amount::from_units(10_000_000), "DYO");
+        blockchain.mint_tokens("ADMIN_ADDRESS", Amount::from_units(1_000_000), "DYS");
 
         // Add initial liquidity
         blockchain.add_initial_liquidity();
@@ -147,9 +301,9 @@ impl RealBlockchain {
         let pool = PoolInfo {
             token_a: "DYO".to_string(),
             token_b: "DYS".to_string(),
-            reserve_a: 100000.0, // 100k DYO
-            reserve_b: 100000.0, // 100k DYS
-            total_liquidity: 100000.0,
+            reserve_a: Amount::from_units(100_000), // 100k DYO
+            reserve_b: Amount::from_units(100_000), // 100k DYS
+            total_liquidity: Amount::from_units(100_000),
             fee: 0.3, // 0.3% fee
         };
 
@@ -158,35 +312,30 @@ impl RealBlockchain {
     }
 
     pub fn get_balance(&self, address: &str) -> TokenBalance {
-        self.balances.get(address).cloned().unwrap_or(TokenBalance {
-            dyo: 0.0,
-            dys: 0.0,
-            staked: 0.0,
-            total: 0.0,
-        })
+        self.balances.get(address).cloned().unwrap_or_default()
     }
 
-    pub fn mint_tokens(&mut self, to: &str, amount: f64, token: &str) -> bool {
-        let balance = self.balances.entry(to.to_string()).or_insert(TokenBalance {
-            dyo: 0.0,
-            dys: 0.0,
-            staked: 0.0,
-            total: 0.0,
-        });
-
-        match token {
-            "DYO" => {
-                balance.dyo += amount;
-                self.total_supply_dyo += amount;
-            }
-            "DYS" => {
-                balance.dys += amount;
-                self.total_supply_dys += amount;
-            }
+    pub fn mint_tokens(&mut self, to: &str, amount: Amount, token: &str) -> bool {
+        let supply = match token {
+            "DYO" => &mut self.total_supply_dyo,
+            "DYS" => &mut self.total_supply_dys,
             _ => return false,
-        }
+        };
+        let new_supply = match supply.checked_add(amount) {
+            Ok(new_supply) => new_supply,
+            Err(_) => return false,
+        };
 
-        balance.total = balance.dyo + balance.dys + balance.staked;
+        let balance = self.balances.entry(to.to_string()).or_default();
+        let Some(token_balance) = balance.token_mut(token) else {
+            return false;
+        };
+        match token_balance.checked_add(amount) {
+            Ok(new_balance) => *token_balance = new_balance,
+            Err(_) => return false,
+        }
+        balance.refresh_total();
+        *supply = new_supply;
 
         // Add transaction to mempool
         let tx = RealTransaction {
@@ -205,54 +354,31 @@ impl RealBlockchain {
         true
     }
 
-    pub fn transfer_tokens(&mut self, from: &str, to: &str, amount: f64, token: &str) -> bool {
+    pub fn transfer_tokens(&mut self, from: &str, to: &str, amount: Amount, token: &str) -> bool {
         // Check if sender has sufficient balance first
-        let from_balance = self.get_balance(from);
-        let from_amount = match token {
-            "DYO" => from_balance.dyo,
-            "DYS" => from_balance.dys,
-            _ => return false,
-        };
-
-        if from_amount < amount {
+        let mut from_balance = self.get_balance(from);
+        let Some(from_amount) = from_balance.token_mut(token) else {
             return false;
+        };
+        match from_amount.checked_sub(amount) {
+            Ok(remaining) => *from_amount = remaining,
+            Err(_) => return false,
         }
+        from_balance.refresh_total();
 
-        // Update balances - handle from and to separately to avoid borrow conflicts
-        {
-            let from_entry = self
-                .balances
-                .entry(from.to_string())
-                .or_insert(TokenBalance {
-                    dyo: 0.0,
-                    dys: 0.0,
-                    staked: 0.0,
-                    total: 0.0,
-                });
-
-            match token {
-                "DYO" => from_entry.dyo -= amount,
-                "DYS" => from_entry.dys -= amount,
-                _ => return false,
-            }
-            from_entry.total = from_entry.dyo + from_entry.dys + from_entry.staked;
+        // Credit the receiver (reads the debited balance on a self-transfer)
+        let mut to_balance = if from == to { from_balance.clone() } else { self.get_balance(to) };
+        let Some(to_amount) = to_balance.token_mut(token) else {
+            return false;
+        };
+        match to_amount.checked_add(amount) {
+            Ok(credited) => *to_amount = credited,
+            Err(_) => return false,
         }
+        to_balance.refresh_total();
 
-        {
-            let to_entry = self.balances.entry(to.to_string()).or_insert(TokenBalance {
-                dyo: 0.0,
-                dys: 0.0,
-                staked: 0.0,
-                total: 0.0,
-            });
-
-            match token {
-                "DYO" => to_entry.dyo += amount,
-                "DYS" => to_entry.dys += amount,
-                _ => return false,
-            }
-            to_entry.total = to_entry.dyo + to_entry.dys + to_entry.staked;
-        }
+        self.balances.insert(from.to_string(), from_balance);
+        self.balances.insert(to.to_string(), to_balance);
 
         // Add transaction to mempool
         let tx = RealTransaction {
@@ -276,8 +402,8 @@ impl RealBlockchain {
         user: &str,
         from_token: &str,
         to_token: &str,
-        amount_in: f64,
-        min_amount_out: f64,
+        amount_in: Amount,
+        min_amount_out: Amount,
     ) -> Result<SwapResult, String> {
         // Use consistent pool ID for both directions (DYO↔DYS)
         let pool_id = if (from_token == "DYO" && to_token == "DYS")
@@ -285,47 +411,23 @@ impl RealBlockchain {
         {
             "DYO-DYS".to_string()
         } else {
-            return Ok(SwapResult {
-                success: false,
-                amount_in: 0.0,
-                amount_out: 0.0,
-                price_impact: 0.0,
-                fee: 0.0,
-                error: Some("Pool not found".to_string()),
-            });
+            return Ok(SwapResult::rejected("Pool not found"));
         };
 
         let pool = match self.pools.get(&pool_id) {
             Some(p) => p.clone(),
-            None => {
-                return Ok(SwapResult {
-                    success: false,
-                    amount_in: 0.0,
-                    amount_out: 0.0,
-                    price_impact: 0.0,
-                    fee: 0.0,
-                    error: Some("Pool not found".to_string()),
-                });
-            }
+            None => return Ok(SwapResult::rejected("Pool not found")),
         };
 
         // Check user balance
-        let user_balance = self.get_balance(user);
-        let user_amount = if from_token == "DYO" {
-            user_balance.dyo
-        } else {
-            user_balance.dys
+        let mut balance = match self.balances.get(user) {
+            Some(balance) => balance.clone(),
+            None => return Ok(SwapResult::rejected("User balance not found")),
         };
+        let user_amount = if from_token == "DYO" { balance.dyo } else { balance.dys };
 
         if user_amount < amount_in {
-            return Ok(SwapResult {
-                success: false,
-                amount_in: 0.0,
-                amount_out: 0.0,
-                price_impact: 0.0,
-                fee: 0.0,
-                error: Some("Insufficient balance".to_string()),
-            });
+            return Ok(SwapResult::rejected("Insufficient balance"));
         }
 
         // Calculate swap using constant product formula
@@ -336,77 +438,36 @@ impl RealBlockchain {
         };
 
         if reserve_in < amount_in {
-            return Ok(SwapResult {
-                success: false,
-                amount_in: 0.0,
-                amount_out: 0.0,
-                price_impact: 0.0,
-                fee: 0.0,
-                error: Some("Insufficient liquidity".to_string()),
-            });
+            return Ok(SwapResult::rejected("Insufficient liquidity"));
         }
 
-        // Calculate fee (0.3%)
-        let fee_amount = amount_in * 0.003;
-        let amount_in_after_fee = amount_in - fee_amount;
-
-        // Calculate output using constant product formula: x * y = k
-        let amount_out = (amount_in_after_fee * reserve_out) / (reserve_in + amount_in_after_fee);
-
-        // Calculate price impact
-        let price_before = reserve_out / reserve_in;
-        let price_after = (reserve_out - amount_out) / (reserve_in + amount_in_after_fee);
-        let price_impact = (price_after - price_before).abs() / price_before;
-
-        if amount_out < min_amount_out {
-            return Ok(SwapResult {
-                success: false,
-                amount_in: 0.0,
-                amount_out: 0.0,
-                price_impact: 0.0,
-                fee: 0.0,
-                error: Some("Insufficient output amount".to_string()),
-            });
-        }
+        let quote = quote_swap(reserve_in, reserve_out, amount_in)?;
 
-        // Execute swap - Update user balances directly
-        if let Some(balance) = self.balances.get_mut(user) {
-            // Deduct input tokens
-            if from_token == "DYO" {
-                balance.dyo -= amount_in;
-            } else {
-                balance.dys -= amount_in;
-            }
-
-            // Add output tokens
-            if to_token == "DYO" {
-                balance.dyo += amount_out;
-            } else {
-                balance.dys += amount_out;
-            }
+        if quote.amount_out < min_amount_out {
+            return Ok(SwapResult::rejected("Insufficient output amount"));
+        }
 
-            // Update total
-            balance.total = balance.dyo + balance.dys + balance.staked;
+        // Execute swap - Update user balances
+        let math = |e: crate::utils::safe_math::SafeMathError| e.to_string();
+        if from_token == "DYO" {
+            balance.dyo = balance.dyo.checked_sub(amount_in).map_err(math)?;
+            balance.dys = balance.dys.checked_add(quote.amount_out).map_err(math)?;
         } else {
-            return Ok(SwapResult {
-                success: false,
-                amount_in: 0.0,
-                amount_out: 0.0,
-                price_impact: 0.0,
-                fee: 0.0,
-                error: Some("User balance not found".to_string()),
-            });
+            balance.dys = balance.dys.checked_sub(amount_in).map_err(math)?;
+            balance.dyo = balance.dyo.checked_add(quote.amount_out).map_err(math)?;
         }
+        balance.refresh_total();
 
         // Update pool reserves
         let mut updated_pool = pool.clone();
         if from_token == "DYO" {
-            updated_pool.reserve_a += amount_in;
-            updated_pool.reserve_b -= amount_out;
+            updated_pool.reserve_a = updated_pool.reserve_a.checked_add(amount_in).map_err(math)?;
+            updated_pool.reserve_b = updated_pool.reserve_b.checked_sub(quote.amount_out).map_err(math)?;
         } else {
-            updated_pool.reserve_b += amount_in;
-            updated_pool.reserve_a -= amount_out;
+            updated_pool.reserve_b = updated_pool.reserve_b.checked_add(amount_in).map_err(math)?;
+            updated_pool.reserve_a = updated_pool.reserve_a.checked_sub(quote.amount_out).map_err(math)?;
         }
+        self.balances.insert(user.to_string(), balance);
         self.pools.insert(pool_id, updated_pool);
 
         // Add transaction to mempool
@@ -420,8 +481,8 @@ impl RealBlockchain {
             tx_type: "SWAP".to_string(),
             data: Some(serde_json::json!({
                 "to_token": to_token,
-                "amount_out": amount_out,
-                "price_impact": price_impact
+                "amount_out": quote.amount_out,
+                "price_impact": quote.price_impact
             })),
         };
 
@@ -431,57 +492,32 @@ impl RealBlockchain {
             "🔄 Swap: {} {} -> {} {} (impact: {:.2}%)",
             amount_in,
             from_token,
-            amount_out,
+            quote.amount_out,
             to_token,
-            price_impact * 100.0
+            quote.price_impact * 100.0
         );
 
-        Ok(SwapResult {
-            success: true,
-            amount_in,
-            amount_out,
-            price_impact,
-            fee: fee_amount,
-            error: None,
-        })
+        Ok(quote)
     }
 
-    pub fn stake_tokens(&mut self, user: &str, amount: f64) -> Result<StakingResult, String> {
+    pub fn stake_tokens(&mut self, user: &str, amount: Amount) -> Result<StakingResult, String> {
         let user_balance = self.get_balance(user);
 
         if user_balance.dyo < amount {
-            return Ok(StakingResult {
-                success: false,
-                position_id: None,
-                amount: None,
-                rewards: None,
-                error: Some("Insufficient balance".to_string()),
-            });
+            return Ok(StakingResult::rejected("Insufficient balance"));
         }
 
-        if amount < 1.0 {
-            return Ok(StakingResult {
-                success: false,
-                position_id: None,
-                amount: None,
-                rewards: None,
-                error: Some("Minimum stake is 1 DYO".to_string()),
-            });
+        if amount < Amount::ONE {
+            return Ok(StakingResult::rejected("Minimum stake is 1 DYO"));
         }
 
         // Update user balance directly (stake tokens)
         if let Some(balance) = self.balances.get_mut(user) {
-            balance.dyo -= amount;
-            balance.staked += amount;
-            balance.total = balance.dyo + balance.dys + balance.staked;
+            balance.dyo = balance.dyo.checked_sub(amount).map_err(|e| e.to_string())?;
+            balance.staked = balance.staked.checked_add(amount).map_err(|e| e.to_string())?;
+            balance.refresh_total();
         } else {
-            return Ok(StakingResult {
-                success: false,
-                position_id: None,
-                amount: None,
-                rewards: None,
-                error: Some("User balance not found".to_string()),
-            });
+            return Ok(StakingResult::rejected("User balance not found"));
         }
 
         // Create staking position
@@ -499,18 +535,12 @@ impl RealBlockchain {
             amount,
             start_time: now,
             end_time,
-            rewards: 0.0,
+            rewards: Amount::ZERO,
             is_active: true,
         };
 
         self.staking_positions.insert(position_id.clone(), position);
 
-        // Update user balance to reflect staked amount
-        if let Some(balance) = self.balances.get_mut(user) {
-            balance.staked += amount;
-            balance.total = balance.dyo + balance.dys + balance.staked;
-        }
-
         // Add transaction to mempool
         let tx = RealTransaction {
             from: user.to_string(),
@@ -535,10 +565,14 @@ impl RealBlockchain {
             position_id: Some(position_id),
             amount: Some(amount),
             rewards: None,
+            completes_at: None,
             error: None,
         })
     }
 
+    /// Close a position into the unbonding queue. The DYO (less the fee, plus
+    /// rewards) is released by `release_matured_unbonding` once the unbonding
+    /// period has passed; until then it can still be slashed.
     pub fn unstake_tokens(
         &mut self,
         user: &str,
@@ -546,71 +580,45 @@ impl RealBlockchain {
     ) -> Result<StakingResult, String> {
         let position = match self.staking_positions.get(position_id) {
             Some(p) => p.clone(),
-            None => {
-                return Ok(StakingResult {
-                    success: false,
-                    position_id: None,
-                    amount: None,
-                    rewards: None,
-                    error: Some("Position not found".to_string()),
-                });
-            }
+            None => return Ok(StakingResult::rejected("Position not found")),
         };
 
         if position.user != user {
-            return Ok(StakingResult {
-                success: false,
-                position_id: None,
-                amount: None,
-                rewards: None,
-                error: Some("Unauthorized".to_string()),
-            });
+            return Ok(StakingResult::rejected("Unauthorized"));
         }
 
         if !position.is_active {
-            return Ok(StakingResult {
-                success: false,
-                position_id: None,
-                amount: None,
-                rewards: None,
-                error: Some("Position is not active".to_string()),
-            });
+            return Ok(StakingResult::rejected("Position is not active"));
         }
 
         let now = get_current_timestamp().map_err(|e| format!("Failed to get timestamp: {}", e))?;
         if now < position.end_time {
-            return Ok(StakingResult {
-                success: false,
-                position_id: None,
-                amount: None,
-                rewards: None,
-                error: Some("Position is still locked".to_string()),
-            });
+            return Ok(StakingResult::rejected("Position is still locked"));
         }
 
-        // Calculate rewards (12% APY)
-        let staking_duration = (now - position.start_time) as f64;
-        let staking_duration_years = staking_duration / (365.0 * 24.0 * 3600.0);
-        let rewards = position.amount * 0.12 * staking_duration_years;
+        // Calculate rewards (12% APY, pro rata per second)
+        let staking_duration = (now - position.start_time) as u128;
+        let rewards = position
+            .amount
+            .mul_ratio(STAKING_APY_BPS as u128 * staking_duration, 10_000 * SECONDS_PER_YEAR)
+            .map_err(|e| e.to_string())?;
 
         // Calculate unstaking fee (1%)
-        let fee_amount = position.amount * 0.01;
-        let amount_to_return = position.amount - fee_amount;
+        let fee_amount = position.amount.mul_bps(UNSTAKE_FEE_BPS).map_err(|e| e.to_string())?;
 
-        // Update user balance directly (unstake tokens + rewards)
-        if let Some(balance) = self.balances.get_mut(user) {
-            balance.staked -= position.amount;
-            balance.dyo += amount_to_return + rewards;
-            balance.total = balance.dyo + balance.dys + balance.staked;
-        } else {
-            return Ok(StakingResult {
-                success: false,
-                position_id: None,
-                amount: None,
-                rewards: None,
-                error: Some("User balance not found".to_string()),
-            });
-        }
+        // The DYO stays in the user's staked balance until the entry is released
+        let entry = UnbondingEntry {
+            position_id: position_id.to_string(),
+            user: user.to_string(),
+            // Stake in the simulator is bonded to the staker itself
+            validator: Some(user.to_string()),
+            amount: position.amount,
+            fee: fee_amount,
+            rewards,
+            slashed: Amount::ZERO,
+            started_at: now,
+            completes_at: now + self.unbonding_period_secs,
+        };
 
         // Update position
         let mut updated_position = position.clone();
@@ -619,50 +627,148 @@ impl RealBlockchain {
         self.staking_positions
             .insert(position_id.to_string(), updated_position);
 
-        // Update user balance
-        if let Some(balance) = self.balances.get_mut(user) {
-            balance.staked -= position.amount;
-            balance.total = balance.dyo + balance.dys + balance.staked;
-        }
-
         // Add transaction to mempool
         let tx = RealTransaction {
-            from: "STAKING_CONTRACT".to_string(),
-            to: user.to_string(),
-            amount: amount_to_return + rewards,
+            from: user.to_string(),
+            to: "STAKING_CONTRACT".to_string(),
+            amount: position.amount,
             token: "DYO".to_string(),
             timestamp: now,
             nonce: 0,
-            tx_type: "UNSTAKE".to_string(),
+            tx_type: "UNBOND".to_string(),
             data: Some(serde_json::json!({
                 "position_id": position_id,
-                "amount": amount_to_return,
                 "rewards": rewards,
-                "fee": fee_amount
+                "fee": fee_amount,
+                "completes_at": entry.completes_at
             })),
         };
 
         self.mempool.push(tx);
 
         println!(
-            "🏦 Unstaked {} DYO + {} rewards for user {}",
-            amount_to_return, rewards, user
+            "🏦 Unbonding {} DYO + {} rewards for user {} until {}",
+            entry.amount, rewards, user, entry.completes_at
         );
 
-        Ok(StakingResult {
+        let result = StakingResult {
             success: true,
             position_id: Some(position_id.to_string()),
-            amount: Some(amount_to_return),
+            amount: Some(entry.amount.saturating_sub(fee_amount)),
             rewards: Some(rewards),
+            completes_at: Some(entry.completes_at),
             error: None,
-        })
+        };
+        self.unbonding.push(entry);
+        Ok(result)
+    }
+
+    /// Pay out the unbonding entries whose period has passed
+    pub fn release_matured_unbonding(&mut self, now: u64) -> Result<Vec<UnbondingEntry>, String> {
+        let (matured, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.unbonding)
+            .into_iter()
+            .partition(|entry| entry.completes_at <= now);
+        self.unbonding = pending;
+
+        for entry in &matured {
+            let payout = entry.payout();
+            let balance = self.balances.entry(entry.user.clone()).or_default();
+            balance.staked = balance.staked.saturating_sub(entry.amount);
+            balance.dyo = balance.dyo.checked_add(payout).map_err(|e| e.to_string())?;
+            balance.refresh_total();
+
+            self.mempool.push(RealTransaction {
+                from: "STAKING_CONTRACT".to_string(),
+                to: entry.user.clone(),
+                amount: payout,
+                token: "DYO".to_string(),
+                timestamp: now,
+                nonce: 0,
+                tx_type: "UNSTAKE".to_string(),
+                data: Some(serde_json::json!({
+                    "position_id": entry.position_id,
+                    "amount": entry.amount.saturating_sub(entry.fee),
+                    "rewards": entry.rewards,
+                    "fee": entry.fee,
+                    "slashed": entry.slashed
+                })),
+            });
+
+            println!("🏦 Released {} DYO to user {}", payout, entry.user);
+        }
+
+        Ok(matured)
+    }
+
+    /// Slash a validator's fault at `slash_bps`: its own positions and the
+    /// matching unbonding entries. The slashed DYO is burned; returns the total.
+    pub fn slash_validator(&mut self, validator: &str, slash_bps: u64, reason: &str) -> Result<Amount, String> {
+        if slash_bps > 10_000 {
+            return Err("Slash rate cannot exceed 100%".to_string());
+        }
+        let now = get_current_timestamp().map_err(|e| format!("Failed to get timestamp: {}", e))?;
+
+        let mut cuts: Vec<(String, Amount)> = Vec::new();
+        for position in self.staking_positions.values_mut() {
+            if position.is_active && position.user == validator {
+                let cut = position.amount.mul_bps(slash_bps).map_err(|e| e.to_string())?;
+                position.amount = position.amount.saturating_sub(cut);
+                cuts.push((position.user.clone(), cut));
+            }
+        }
+        for entry in self.unbonding.iter_mut() {
+            if entry.validator.as_deref() == Some(validator) {
+                let cut = entry.slash(slash_bps)?;
+                cuts.push((entry.user.clone(), cut));
+            }
+        }
+
+        let mut total = Amount::ZERO;
+        for (user, cut) in cuts {
+            if let Some(balance) = self.balances.get_mut(&user) {
+                balance.staked = balance.staked.saturating_sub(cut);
+                balance.refresh_total();
+            }
+            total = total.saturating_add(cut);
+        }
+        self.total_supply_dyo = self.total_supply_dyo.saturating_sub(total);
+
+        self.mempool.push(RealTransaction {
+            from: "STAKING_CONTRACT".to_string(),
+            to: "BURN".to_string(),
+            amount: total,
+            token: "DYO".to_string(),
+            timestamp: now,
+            nonce: 0,
+            tx_type: "SLASH".to_string(),
+            data: Some(serde_json::json!({
+                "validator": validator,
+                "slash_bps": slash_bps,
+                "reason": reason
+            })),
+        });
+
+        println!("⚔️  Slashed {} DYO for validator {} ({})", total, validator, reason);
+        Ok(total)
+    }
+
+    /// Pending unbonding entries of a user, soonest release first
+    pub fn get_unbonding_entries(&self, user: &str) -> Vec<UnbondingEntry> {
+        let mut entries: Vec<UnbondingEntry> = self
+            .unbonding
+            .iter()
+            .filter(|entry| entry.user == user)
+            .cloned()
+            .collect();
+        entries.sort_by_key(|entry| entry.completes_at);
+        entries
     }
 
     pub fn get_swap_quote(
         &self,
         from_token: &str,
         to_token: &str,
-        amount_in: f64,
+        amount_in: Amount,
     ) -> Option<SwapResult> {
         // Use consistent pool ID for both directions (DYO↔DYS)
         let pool_id = if (from_token == "DYO" && to_token == "DYS")
@@ -685,22 +791,7 @@ impl RealBlockchain {
             return None;
         }
 
-        let fee_amount = amount_in * 0.003;
-        let amount_in_after_fee = amount_in - fee_amount;
-        let amount_out = (amount_in_after_fee * reserve_out) / (reserve_in + amount_in_after_fee);
-
-        let price_before = reserve_out / reserve_in;
-        let price_after = (reserve_out - amount_out) / (reserve_in + amount_in_after_fee);
-        let price_impact = (price_after - price_before).abs() / price_before;
-
-        Some(SwapResult {
-            success: true,
-            amount_in,
-            amount_out,
-            price_impact,
-            fee: fee_amount,
-            error: None,
-        })
+        quote_swap(reserve_in, reserve_out, amount_in).ok()
     }
 
     pub fn get_staking_positions(&self, user: &str) -> Vec<StakingPosition> {
@@ -717,15 +808,123 @@ impl RealBlockchain {
     }
 
     pub fn get_network_stats(&self) -> serde_json::Value {
+        let total_staked = self
+            .staking_positions
+            .values()
+            .filter(|p| p.is_active)
+            .fold(Amount::ZERO, |total, p| total.saturating_add(p.amount));
+        let total_unbonding = self
+            .unbonding
+            .iter()
+            .fold(Amount::ZERO, |total, entry| total.saturating_add(entry.amount));
+        let total_liquidity = self
+            .pools
+            .values()
+            .fold(Amount::ZERO, |total, p| total.saturating_add(p.total_liquidity));
+
         serde_json::json!({
             "total_blocks": self.blocks.len(),
             "total_transactions": self.mempool.len(),
             "total_wallets": self.balances.len(),
             "total_supply_dyo": self.total_supply_dyo,
             "total_supply_dys": self.total_supply_dys,
-            "total_staked": self.staking_positions.values().filter(|p| p.is_active).map(|p| p.amount).sum::<f64>(),
-            "total_liquidity": self.pools.values().map(|p| p.total_liquidity).sum::<f64>(),
+            "total_staked": total_staked,
+            "total_unbonding": total_unbonding,
+            "total_liquidity": total_liquidity,
             "is_running": self.is_running
         })
     }
 }
+
+/// Constant product quote (x * y = k) after the 0.3% swap fee
+fn quote_swap(reserve_in: Amount, reserve_out: Amount, amount_in: Amount) -> Result<SwapResult, String> {
+    let math = |e: crate::utils::safe_math::SafeMathError| e.to_string();
+    let fee_amount = amount_in.mul_bps(SWAP_FEE_BPS).map_err(math)?;
+    let amount_in_after_fee = amount_in.checked_sub(fee_amount).map_err(math)?;
+    let new_reserve_in = reserve_in.checked_add(amount_in_after_fee).map_err(math)?;
+    let amount_out = reserve_out.mul_div(amount_in_after_fee, new_reserve_in).map_err(math)?;
+
+    // Price impact is a display ratio, the only place floats remain
+    let price_before = reserve_out.ratio(reserve_in);
+    let price_after = reserve_out.saturating_sub(amount_out).ratio(new_reserve_in);
+    let price_impact = if price_before > 0.0 {
+        (price_after - price_before).abs() / price_before
+    } else {
+        0.0
+    };
+
+    Ok(SwapResult {
+        success: true,
+        amount_in,
+        amount_out,
+        price_impact,
+        fee: fee_amount,
+        error: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A chain where "validator1" staked 1,000 DYO in an unlocked position
+    fn chain_with_stake() -> (RealBlockchain, String) {
+        let mut chain = RealBlockchain::new();
+        chain.unbonding_period_secs = 3600;
+        chain.mint_tokens("validator1", Amount::from_units(1_000), "DYO");
+
+        let result = chain.stake_tokens("validator1", Amount::from_units(1_000)).unwrap();
+        let position_id = result.position_id.unwrap();
+        chain.staking_positions.get_mut(&position_id).unwrap().end_time = 0;
+        (chain, position_id)
+    }
+
+    #[test]
+    fn test_unstake_unbonds_and_stays_slashable() {
+        let (mut chain, position_id) = chain_with_stake();
+
+        let result = chain.unstake_tokens("validator1", &position_id).unwrap();
+        assert!(result.success);
+        assert_eq!(chain.get_balance("validator1").dyo, Amount::ZERO);
+        assert_eq!(chain.get_balance("validator1").staked, Amount::from_units(1_000));
+        assert_eq!(chain.get_unbonding_entries("validator1").len(), 1);
+
+        // Evidence landing during unbonding still reaches the exiting stake
+        let slashed = chain.slash_validator("validator1", 1_000, "double signing").unwrap();
+        assert_eq!(slashed, Amount::from_units(100));
+        assert_eq!(chain.get_balance("validator1").staked, Amount::from_units(900));
+        assert_eq!(chain.slash_validator("validator2", 1_000, "downtime").unwrap(), Amount::ZERO);
+
+        let completes_at = result.completes_at.unwrap();
+        assert!(chain.release_matured_unbonding(completes_at - 1).unwrap().is_empty());
+        let released = chain.release_matured_unbonding(completes_at).unwrap();
+        assert_eq!(released.len(), 1);
+
+        // 900 left after slashing, less the 1% fee on the original 1,000
+        let balance = chain.get_balance("validator1");
+        assert_eq!(balance.dyo, Amount::from_units(890));
+        assert_eq!(balance.staked, Amount::ZERO);
+        assert!(chain.unbonding.is_empty());
+    }
+
+    #[test]
+    fn test_unbonding_entry_slash() {
+        let mut entry = UnbondingEntry {
+            position_id: "unbond_1".to_string(),
+            user: "DUdelegator".to_string(),
+            validator: Some("DUvalidator".to_string()),
+            amount: Amount::from_units(1_000),
+            fee: Amount::ZERO,
+            rewards: Amount::ZERO,
+            slashed: Amount::ZERO,
+            started_at: 0,
+            completes_at: 3600,
+        };
+
+        assert_eq!(entry.slash(500).unwrap(), Amount::from_units(50));
+        assert_eq!(entry.slash(500).unwrap(), Amount::from_micro(47_500_000));
+        assert_eq!(entry.amount, Amount::from_micro(902_500_000));
+        assert_eq!(entry.slashed, Amount::from_micro(97_500_000));
+        assert_eq!(entry.payout(), entry.amount);
+    }
+}
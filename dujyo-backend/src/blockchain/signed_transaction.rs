@@ -0,0 +1,441 @@
+//! Signed Transaction Envelope for Dujyo Blockchain
+//!
+//! Every user-originated transaction must be authorized by the ed25519 key bound to
+//! the sender address. The envelope carries a per-account nonce (strictly
+//! increasing, starting at 1), the chain id and the maximum fee the sender agrees
+//! to pay, so a signature can never be replayed on another chain, at another
+//! position in the account history, or with a higher fee than authorized.
+//! An optional priority tip (paid on top of the gas fee, within the same
+//! maximum) moves the transaction ahead in the mempool.
+
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+use crate::blockchain::blockchain::{Transaction, TransactionAuth};
+use crate::blockchain::ledger::TxKind;
+
+/// Domain separator prepended to every signing payload
+const SIGNING_DOMAIN: &[u8] = b"DUJYO_SIGNED_TX_V1";
+
+/// Marks the priority tip in the payload (never a `TxKind` tag)
+const TIP_MARKER: u8 = 0xFF;
+
+/// Default chain id (overridable with the DUJYO_CHAIN_ID environment variable)
+pub const DEFAULT_CHAIN_ID: &str = "dujyo-mainnet-1";
+
+/// Resolve the chain id this node accepts signatures for
+pub fn chain_id() -> String {
+    std::env::var("DUJYO_CHAIN_ID").unwrap_or_else(|_| DEFAULT_CHAIN_ID.to_string())
+}
+
+/// Errors produced while verifying a signed transaction
+#[derive(Debug, Clone, PartialEq)]
+pub enum SignedTransactionError {
+    InvalidPublicKey,
+    InvalidSignature,
+    SignatureMismatch,
+    PublicKeyMismatch,
+    WrongChainId { expected: String, got: String },
+    InvalidNonce { expected: u64, got: u64 },
+    FeeTooLow { required: u64, max_fee: u64 },
+    InvalidPayload(String),
+}
+
+impl fmt::Display for SignedTransactionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignedTransactionError::InvalidPublicKey => write!(f, "Invalid ed25519 public key"),
+            SignedTransactionError::InvalidSignature => write!(f, "Malformed ed25519 signature"),
+            SignedTransactionError::SignatureMismatch => write!(f, "Signature does not match transaction payload"),
+            SignedTransactionError::PublicKeyMismatch => write!(f, "Public key is not registered for sender address"),
+            SignedTransactionError::WrongChainId { expected, got } => {
+                write!(f, "Wrong chain id: expected {}, got {}", expected, got)
+            }
+            SignedTransactionError::InvalidNonce { expected, got } => {
+                write!(f, "Invalid nonce: expected {}, got {} (replay or gap)", expected, got)
+            }
+            SignedTransactionError::FeeTooLow { required, max_fee } => {
+                write!(f, "Fee too low: required {}, signed max fee {}", required, max_fee)
+            }
+            SignedTransactionError::InvalidPayload(msg) => write!(f, "Invalid transaction payload: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for SignedTransactionError {}
+
+/// Signed transaction as submitted by wallets
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedTransaction {
+    pub from: String,
+    pub to: String,
+    pub amount: u64,
+    pub nft_id: Option<String>,
+    #[serde(default, skip_serializing_if = "TxKind::is_transfer")]
+    pub kind: TxKind,        // Omitted (= transfer) by wallets that only send transfers
+    pub nonce: u64,
+    pub chain_id: String,
+    pub fee: u64,          // Maximum fee (in cents) the sender authorizes, tip included
+    #[serde(default)]
+    pub tip: u64,          // Priority tip (in cents), 0 = none
+    pub public_key: String, // hex-encoded ed25519 verifying key (32 bytes)
+    pub signature: String,  // hex-encoded ed25519 signature (64 bytes)
+}
+
+impl SignedTransaction {
+    /// Canonical bytes covered by the signature.
+    ///
+    /// Variable-length fields are length-prefixed so that no two distinct
+    /// transactions share the same payload. The kind and the tip go last and
+    /// encode to nothing for transfers without tip, so those signatures are unchanged.
+    #[allow(clippy::too_many_arguments)]
+    pub fn signing_payload(
+        from: &str,
+        to: &str,
+        amount: u64,
+        nft_id: Option<&str>,
+        nonce: u64,
+        chain_id: &str,
+        fee: u64,
+        kind: &TxKind,
+        tip: u64,
+    ) -> Vec<u8> {
+        let mut payload = Vec::with_capacity(128);
+        payload.extend_from_slice(SIGNING_DOMAIN);
+        push_field(&mut payload, chain_id.as_bytes());
+        push_field(&mut payload, from.as_bytes());
+        push_field(&mut payload, to.as_bytes());
+        payload.extend_from_slice(&amount.to_be_bytes());
+        match nft_id {
+            Some(id) => {
+                payload.push(1);
+                push_field(&mut payload, id.as_bytes());
+            }
+            None => payload.push(0),
+        }
+        payload.extend_from_slice(&nonce.to_be_bytes());
+        payload.extend_from_slice(&fee.to_be_bytes());
+        kind.encode(&mut payload);
+        if tip > 0 {
+            payload.push(TIP_MARKER);
+            payload.extend_from_slice(&tip.to_be_bytes());
+        }
+        payload
+    }
+
+    /// Payload for this envelope
+    pub fn payload(&self) -> Vec<u8> {
+        Self::signing_payload(
+            &self.from,
+            &self.to,
+            self.amount,
+            self.nft_id.as_deref(),
+            self.nonce,
+            &self.chain_id,
+            self.fee,
+            &self.kind,
+            self.tip,
+        )
+    }
+
+    /// Build an envelope without signature (wallets fill it with `signed_with`)
+    pub fn unsigned(
+        from: String,
+        to: String,
+        amount: u64,
+        nft_id: Option<String>,
+        nonce: u64,
+        chain_id: String,
+        fee: u64,
+    ) -> Self {
+        SignedTransaction {
+            from,
+            to,
+            amount,
+            nft_id,
+            kind: TxKind::Transfer,
+            nonce,
+            chain_id,
+            fee,
+            tip: 0,
+            public_key: String::new(),
+            signature: String::new(),
+        }
+    }
+
+    /// Same envelope for another transaction kind (set before signing)
+    pub fn with_kind(mut self, kind: TxKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Same envelope with a priority tip (set before signing)
+    pub fn with_tip(mut self, tip: u64) -> Self {
+        self.tip = tip;
+        self
+    }
+
+    /// Sign the envelope with the sender key (used by wallets, tools and tests)
+    pub fn signed_with(mut self, signing_key: &SigningKey) -> Self {
+        let signature = signing_key.sign(&self.payload());
+        self.public_key = hex::encode(signing_key.verifying_key().to_bytes());
+        self.signature = hex::encode(signature.to_bytes());
+        self
+    }
+
+    /// Deterministic transaction hash (sha256 over payload and signature)
+    pub fn tx_hash(&self) -> String {
+        signed_hash(&self.payload(), &self.signature)
+    }
+
+    /// Decode the embedded verifying key
+    pub fn verifying_key(&self) -> Result<VerifyingKey, SignedTransactionError> {
+        decode_public_key(&self.public_key)
+    }
+
+    /// Verify the stateless parts of the envelope: shape, chain id and signature.
+    ///
+    /// `registered_key` is the hex key bound to `from`; the envelope must be
+    /// signed by exactly that key.
+    pub fn verify(&self, expected_chain_id: &str, registered_key: &str) -> Result<(), SignedTransactionError> {
+        self.kind
+            .check_shape(&self.from, &self.to, self.amount, self.nft_id.as_deref())
+            .map_err(SignedTransactionError::InvalidPayload)?;
+        if self.nonce == 0 {
+            return Err(SignedTransactionError::InvalidNonce { expected: 1, got: 0 });
+        }
+        if self.chain_id != expected_chain_id {
+            return Err(SignedTransactionError::WrongChainId {
+                expected: expected_chain_id.to_string(),
+                got: self.chain_id.clone(),
+            });
+        }
+        if !self.public_key.eq_ignore_ascii_case(registered_key) {
+            return Err(SignedTransactionError::PublicKeyMismatch);
+        }
+
+        let verifying_key = self.verifying_key()?;
+        let signature = decode_signature(&self.signature)?;
+        verifying_key
+            .verify(&self.payload(), &signature)
+            .map_err(|_| SignedTransactionError::SignatureMismatch)
+    }
+
+    /// Check the envelope nonce against the next expected nonce of the sender
+    pub fn check_nonce(&self, expected: u64) -> Result<(), SignedTransactionError> {
+        if self.nonce != expected {
+            return Err(SignedTransactionError::InvalidNonce { expected, got: self.nonce });
+        }
+        Ok(())
+    }
+
+    /// Check that the computed fee does not exceed the signed maximum
+    pub fn check_fee(&self, required: u64) -> Result<(), SignedTransactionError> {
+        if required > self.fee {
+            return Err(SignedTransactionError::FeeTooLow { required, max_fee: self.fee });
+        }
+        Ok(())
+    }
+
+    /// Rebuild the envelope from a ledger transaction (None for system transactions)
+    pub fn from_transaction(transaction: &Transaction) -> Option<Self> {
+        let auth = transaction.auth.as_ref()?;
+        Some(SignedTransaction {
+            from: transaction.from.clone(),
+            to: transaction.to.clone(),
+            amount: transaction.amount,
+            nft_id: transaction.nft_id.clone(),
+            kind: transaction.kind.clone(),
+            nonce: auth.nonce,
+            chain_id: auth.chain_id.clone(),
+            fee: auth.fee,
+            tip: auth.tip,
+            public_key: auth.public_key.clone(),
+            signature: auth.signature.clone(),
+        })
+    }
+
+    /// Convert into the ledger transaction, keeping the authorization on-chain
+    pub fn into_transaction(self) -> Transaction {
+        Transaction {
+            from: self.from,
+            to: self.to,
+            amount: self.amount,
+            nft_id: self.nft_id,
+            kind: self.kind,
+            auth: Some(TransactionAuth {
+                nonce: self.nonce,
+                chain_id: self.chain_id,
+                fee: self.fee,
+                tip: self.tip,
+                public_key: self.public_key,
+                signature: self.signature,
+            }),
+        }
+    }
+}
+
+/// Decode a hex ed25519 verifying key
+pub fn decode_public_key(public_key_hex: &str) -> Result<VerifyingKey, SignedTransactionError> {
+    let bytes = hex::decode(public_key_hex).map_err(|_| SignedTransactionError::InvalidPublicKey)?;
+    let bytes: [u8; 32] = bytes
+        .as_slice()
+        .try_into()
+        .map_err(|_| SignedTransactionError::InvalidPublicKey)?;
+    VerifyingKey::from_bytes(&bytes).map_err(|_| SignedTransactionError::InvalidPublicKey)
+}
+
+/// Decode a hex ed25519 signature
+pub(crate) fn decode_signature(signature_hex: &str) -> Result<Signature, SignedTransactionError> {
+    let bytes = hex::decode(signature_hex).map_err(|_| SignedTransactionError::InvalidSignature)?;
+    let bytes: [u8; 64] = bytes
+        .as_slice()
+        .try_into()
+        .map_err(|_| SignedTransactionError::InvalidSignature)?;
+    Ok(Signature::from_bytes(&bytes))
+}
+
+/// Hash of a signed envelope; identical for `SignedTransaction` and the ledger `Transaction`
+pub(crate) fn signed_hash(payload: &[u8], signature_hex: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(payload);
+    hasher.update(signature_hex.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+pub(crate) fn push_field(payload: &mut Vec<u8>, field: &[u8]) {
+    payload.extend_from_slice(&(field.len() as u32).to_be_bytes());
+    payload.extend_from_slice(field);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_key(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    fn signed(key: &SigningKey, nonce: u64) -> SignedTransaction {
+        SignedTransaction::unsigned(
+            "DUalice".to_string(),
+            "DUbob".to_string(),
+            500,
+            None,
+            nonce,
+            DEFAULT_CHAIN_ID.to_string(),
+            25,
+        )
+        .signed_with(key)
+    }
+
+    #[test]
+    fn test_valid_signature_verifies() {
+        let key = test_key(7);
+        let tx = signed(&key, 1);
+        let registered = hex::encode(key.verifying_key().to_bytes());
+        assert!(tx.verify(DEFAULT_CHAIN_ID, &registered).is_ok());
+    }
+
+    #[test]
+    fn test_tampered_amount_rejected() {
+        let key = test_key(7);
+        let mut tx = signed(&key, 1);
+        let registered = hex::encode(key.verifying_key().to_bytes());
+        tx.amount = 5_000;
+        assert_eq!(tx.verify(DEFAULT_CHAIN_ID, &registered), Err(SignedTransactionError::SignatureMismatch));
+    }
+
+    #[test]
+    fn test_foreign_key_rejected() {
+        let key = test_key(7);
+        let attacker = test_key(9);
+        let tx = signed(&attacker, 1);
+        let registered = hex::encode(key.verifying_key().to_bytes());
+        assert_eq!(tx.verify(DEFAULT_CHAIN_ID, &registered), Err(SignedTransactionError::PublicKeyMismatch));
+    }
+
+    #[test]
+    fn test_wrong_chain_rejected() {
+        let key = test_key(7);
+        let tx = signed(&key, 1);
+        let registered = hex::encode(key.verifying_key().to_bytes());
+        assert!(matches!(
+            tx.verify("dujyo-testnet-1", &registered),
+            Err(SignedTransactionError::WrongChainId { .. })
+        ));
+    }
+
+    #[test]
+    fn test_nonce_and_fee_checks() {
+        let key = test_key(7);
+        let tx = signed(&key, 3);
+        assert!(tx.check_nonce(3).is_ok());
+        assert!(tx.check_nonce(4).is_err());
+        assert!(tx.check_fee(25).is_ok());
+        assert!(tx.check_fee(26).is_err());
+    }
+
+    #[test]
+    fn test_replayed_transaction_rejected_by_ledger() {
+        use crate::blockchain::blockchain::Blockchain;
+
+        let key = test_key(7);
+        let mut blockchain = Blockchain::new();
+        blockchain.balances.insert("DUalice".to_string(), 10_000);
+
+        let tx = signed(&key, blockchain.next_nonce("DUalice"));
+        assert!(blockchain.add_transaction(tx.clone().into_transaction()).is_ok());
+        assert_eq!(blockchain.next_nonce("DUalice"), 2);
+
+        // Same envelope again: nonce 1 was already consumed
+        assert!(blockchain.add_transaction(tx.into_transaction()).is_err());
+        assert_eq!(blockchain.get_balance("DUbob"), 500);
+    }
+
+    #[test]
+    fn test_kind_is_signed() {
+        let key = test_key(7);
+        let registered = hex::encode(key.verifying_key().to_bytes());
+        let mut tx = SignedTransaction::unsigned(
+            "DUalice".to_string(),
+            "DUalice".to_string(),
+            500,
+            None,
+            1,
+            DEFAULT_CHAIN_ID.to_string(),
+            25,
+        )
+        .with_kind(TxKind::Stake)
+        .signed_with(&key);
+        assert!(tx.verify(DEFAULT_CHAIN_ID, &registered).is_ok());
+        assert_eq!(tx.tx_hash(), tx.clone().into_transaction().tx_hash());
+
+        // Replaying the stake signature as an unstake must fail
+        tx.kind = TxKind::Unstake;
+        assert_eq!(tx.verify(DEFAULT_CHAIN_ID, &registered), Err(SignedTransactionError::SignatureMismatch));
+    }
+
+    #[test]
+    fn test_tip_is_signed() {
+        let key = test_key(7);
+        let registered = hex::encode(key.verifying_key().to_bytes());
+        let untipped = signed(&key, 1);
+        let mut tx = untipped.clone().with_tip(5).signed_with(&key);
+        assert!(tx.verify(DEFAULT_CHAIN_ID, &registered).is_ok());
+        assert_ne!(tx.tx_hash(), untipped.tx_hash());
+        assert_eq!(tx.tx_hash(), tx.clone().into_transaction().tx_hash());
+
+        // A relayer cannot strip or raise the tip
+        tx.tip = 50;
+        assert_eq!(tx.verify(DEFAULT_CHAIN_ID, &registered), Err(SignedTransactionError::SignatureMismatch));
+    }
+
+    #[test]
+    fn test_tx_hash_depends_on_nonce() {
+        let key = test_key(7);
+        assert_ne!(signed(&key, 1).tx_hash(), signed(&key, 2).tx_hash());
+    }
+}
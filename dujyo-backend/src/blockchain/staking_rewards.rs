@@ -1,9 +1,109 @@
+use crate::blockchain::real_blockchain::unbonding_period_secs;
 use crate::utils::access_control::{AccessControlManager, Permission};
-use crate::utils::safe_math::{SafeMath, SafeMathResult};
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use crate::utils::safe_math::SafeMath;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::time::{SystemTime, UNIX_EPOCH};
-use tracing::{error, info, warn};
+use tracing::{error, info};
+
+/// SECURITY: Periodo mínimo de bloqueo de stake y delegaciones (7 días)
+const MIN_LOCK_PERIOD: u64 = 604800;
+
+/// Escala del acumulador reward-per-share
+const REWARD_PRECISION: u128 = 1_000_000_000_000;
+
+/// Rewards de `amount` tokens según el acumulador `acc_reward_per_share`
+fn accrued(amount: u64, acc_reward_per_share: u128) -> u128 {
+    (amount as u128).saturating_mul(acc_reward_per_share) / REWARD_PRECISION
+}
+
+fn to_u64(value: u128) -> u64 {
+    u64::try_from(value).unwrap_or(u64::MAX)
+}
+
+/// Slashing en bps. El estado guardado antes traía `slashing_rate` como porcentaje (5.0)
+fn slashing_bps_from_state<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StoredRate {
+        Bps(u64),
+        Percent(f64),
+    }
+    Ok(match StoredRate::deserialize(deserializer)? {
+        StoredRate::Bps(bps) => bps,
+        StoredRate::Percent(rate) => (rate * 100.0).round() as u64,
+    })
+}
+
+/// Emisión de rewards de staking por bloque: un reward base que se reduce a
+/// la mitad cada `halving_interval_blocks`. Las fees no entran aquí: se
+/// reparten on-chain con `FeeDistribution`. Los stakers con auto-compound
+/// re-stakean al cerrar cada época.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmissionSchedule {
+    pub initial_reward_per_block: u64,
+    pub halving_interval_blocks: u64, // 0 = sin halving
+    pub epoch_length_blocks: u64, // 0 = sin auto-compound
+}
+
+impl Default for EmissionSchedule {
+    fn default() -> Self {
+        Self {
+            initial_reward_per_block: 10,
+            halving_interval_blocks: 2_100_000,
+            epoch_length_blocks: 5_760,
+        }
+    }
+}
+
+impl EmissionSchedule {
+    /// Reward emitido en el bloque `height`
+    pub fn reward_at(&self, height: u64) -> u64 {
+        if self.halving_interval_blocks == 0 {
+            return self.initial_reward_per_block;
+        }
+        let halvings = height / self.halving_interval_blocks;
+        if halvings >= 64 {
+            0
+        } else {
+            self.initial_reward_per_block >> halvings
+        }
+    }
+
+    /// Emisión total de los bloques `(from, to]`, por tramos entre halvings
+    pub fn emission_between(&self, from: u64, to: u64) -> u64 {
+        if self.halving_interval_blocks == 0 {
+            return self.initial_reward_per_block.saturating_mul(to.saturating_sub(from));
+        }
+
+        let mut total: u64 = 0;
+        let mut height = from.saturating_add(1);
+        while height <= to {
+            let reward = self.reward_at(height);
+            if reward == 0 {
+                break;
+            }
+            let era_end = (height / self.halving_interval_blocks + 1)
+                .saturating_mul(self.halving_interval_blocks)
+                .saturating_sub(1)
+                .min(to);
+            total = total.saturating_add(reward.saturating_mul(era_end - height + 1));
+            height = era_end.saturating_add(1);
+        }
+        total
+    }
+
+    /// Si pasar del bloque `last` a `height` cierra una época
+    fn crosses_epoch(&self, last: Option<u64>, height: u64) -> bool {
+        if self.epoch_length_blocks == 0 {
+            return false;
+        }
+        match last {
+            Some(last) => last / self.epoch_length_blocks != height / self.epoch_length_blocks,
+            None => height.is_multiple_of(self.epoch_length_blocks),
+        }
+    }
+}
 
 /// ✅ SECURITY FIX: Safe timestamp helper
 fn get_current_timestamp() -> Result<u64, String> {
@@ -25,6 +125,11 @@ pub struct StakingManager {
     pub access_control: AccessControlManager,
     pub emergency_paused: bool,
     pub emergency_pause_reason: Option<String>,
+    #[serde(default)]
+    pub emission: EmissionSchedule,
+    /// Último bloque cuyos rewards se acumularon
+    #[serde(default)]
+    pub last_accrued_block: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -42,7 +147,18 @@ pub struct StakingContract {
     pub min_stake: u64,
     pub max_stake: Option<u64>,
     pub slashing_enabled: bool,
-    pub slashing_rate: f64, // percentage
+    /// Recorte de cada slashing en bps
+    #[serde(alias = "slashing_rate", deserialize_with = "slashing_bps_from_state")]
+    pub slashing_bps: u64,
+    /// Delegaciones recibidas por cada validador del contrato (validator -> pool)
+    #[serde(default)]
+    pub delegations: HashMap<String, DelegationPool>,
+    /// Rewards acumulados por token en stake, escalados por REWARD_PRECISION
+    #[serde(default)]
+    pub acc_reward_per_share: u128,
+    /// Redelegaciones aún en curso: el validador de origen responde por ellas hasta completes_at
+    #[serde(default)]
+    pub redelegations: Vec<Redelegation>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -55,6 +171,314 @@ pub struct StakerInfo {
     pub total_rewards_claimed: u64,
     pub slashing_events: u32,
     pub is_active: bool,
+    /// Rewards ya contabilizados del stake actual (checkpoint del acumulador)
+    #[serde(default)]
+    pub reward_debt: u128,
+    /// Re-stakear los rewards al cerrar cada época
+    #[serde(default)]
+    pub auto_compound: bool,
+}
+
+impl StakerInfo {
+    /// Rewards acumulados desde el último checkpoint: O(1)
+    fn accrued_since_checkpoint(&self, acc_reward_per_share: u128) -> u64 {
+        to_u64(accrued(self.staked_amount, acc_reward_per_share).saturating_sub(self.reward_debt))
+    }
+
+    /// Pasar lo acumulado a pending_rewards antes de cambiar el stake
+    fn settle(&mut self, acc_reward_per_share: u128) -> Result<(), String> {
+        let rewards = self.accrued_since_checkpoint(acc_reward_per_share);
+        self.pending_rewards = SafeMath::add(self.pending_rewards, rewards, "staking_settle_rewards")
+            .map_err(|e| format!("Failed to settle rewards: {}", e))?;
+        self.checkpoint(acc_reward_per_share);
+        Ok(())
+    }
+
+    fn checkpoint(&mut self, acc_reward_per_share: u128) {
+        self.reward_debt = accrued(self.staked_amount, acc_reward_per_share);
+    }
+}
+
+/// Contrato de los validadores CPV: su stake propio (bond) y las delegaciones que reciben
+pub const VALIDATOR_CONTRACT_ID: &str = "STK_CPV_VALIDATORS";
+
+/// Comisión por defecto de un validador sobre los rewards de sus delegadores (10%)
+pub const DEFAULT_COMMISSION_BPS: u64 = 1_000;
+
+/// Comisión máxima que un validador puede fijar (50%)
+pub const MAX_COMMISSION_BPS: u64 = 5_000;
+
+/// Redelegaciones en curso que un delegador puede tener a la vez
+pub const MAX_REDELEGATIONS_IN_FLIGHT: usize = 7;
+
+/// Stake delegado por fans y oyentes a un validador. Cuenta en el stake
+/// efectivo del validador, cobra rewards proporcionales (menos la comisión)
+/// y sufre el mismo slashing que el validador.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DelegationPool {
+    pub validator: String,
+    pub commission_bps: u64,
+    pub total_delegated: u64,
+    pub delegators: HashMap<String, DelegatorInfo>,
+    /// Checkpoint del acumulador para la comisión del validador
+    #[serde(default)]
+    pub reward_debt: u128,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DelegatorInfo {
+    pub address: String,
+    pub delegated_amount: u64,
+    pub delegated_at: u64,
+    pub pending_rewards: u64,
+    pub total_rewards_claimed: u64,
+    pub total_slashed: u64,
+    #[serde(default)]
+    pub reward_debt: u128,
+    #[serde(default)]
+    pub auto_compound: bool,
+}
+
+/// Stake movido de un validador a otro sin pasar por el unbonding. Hasta
+/// completes_at el validador de origen puede seguir siendo penalizado por
+/// faltas cometidas mientras el stake era suyo, y lo movido no puede volver
+/// a redelegarse desde el destino.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Redelegation {
+    pub delegator: String,
+    pub src_validator: String,
+    pub dst_validator: String,
+    /// Lo que sigue expuesto al slashing del origen (baja si el destino o el origen lo recortan)
+    pub amount: u64,
+    pub started_at: u64,
+    pub completes_at: u64,
+}
+
+impl DelegationPool {
+    fn new(validator: &str) -> Self {
+        Self {
+            validator: validator.to_string(),
+            commission_bps: DEFAULT_COMMISSION_BPS,
+            total_delegated: 0,
+            delegators: HashMap::new(),
+            reward_debt: 0,
+        }
+    }
+
+    /// Comisión acumulada desde el último checkpoint del pool: O(1)
+    fn commission_since_checkpoint(&self, acc_reward_per_share: u128) -> u64 {
+        let gross = accrued(self.total_delegated, acc_reward_per_share).saturating_sub(self.reward_debt);
+        to_u64(gross * self.commission_bps as u128 / 10_000)
+    }
+
+    /// Cobrar la comisión acumulada antes de cambiar el stake delegado o la comisión
+    fn take_commission(&mut self, acc_reward_per_share: u128) -> u64 {
+        let commission = self.commission_since_checkpoint(acc_reward_per_share);
+        self.checkpoint(acc_reward_per_share);
+        commission
+    }
+
+    fn checkpoint(&mut self, acc_reward_per_share: u128) {
+        self.reward_debt = accrued(self.total_delegated, acc_reward_per_share);
+    }
+
+    /// Liquidar a todos los delegadores con la comisión actual
+    fn settle_delegators(&mut self, acc_reward_per_share: u128) -> Result<(), String> {
+        for delegator in self.delegators.values_mut() {
+            delegator.settle(acc_reward_per_share, self.commission_bps)?;
+        }
+        Ok(())
+    }
+}
+
+impl DelegatorInfo {
+    /// Rewards netos de comisión desde el último checkpoint: O(1)
+    fn accrued_since_checkpoint(&self, acc_reward_per_share: u128, commission_bps: u64) -> u64 {
+        let gross = accrued(self.delegated_amount, acc_reward_per_share).saturating_sub(self.reward_debt);
+        to_u64(gross - gross * commission_bps as u128 / 10_000)
+    }
+
+    fn settle(&mut self, acc_reward_per_share: u128, commission_bps: u64) -> Result<(), String> {
+        let rewards = self.accrued_since_checkpoint(acc_reward_per_share, commission_bps);
+        self.pending_rewards = SafeMath::add(self.pending_rewards, rewards, "delegation_settle_rewards")
+            .map_err(|e| format!("Failed to settle delegation rewards: {}", e))?;
+        self.checkpoint(acc_reward_per_share);
+        Ok(())
+    }
+
+    fn checkpoint(&mut self, acc_reward_per_share: u128) {
+        self.reward_debt = accrued(self.delegated_amount, acc_reward_per_share);
+    }
+}
+
+impl StakingContract {
+    /// Pasar la comisión acumulada del pool de un validador a sus rewards pendientes
+    fn settle_commission(&mut self, validator: &str) -> Result<(), String> {
+        let acc = self.acc_reward_per_share;
+        let Some(pool) = self.delegations.get_mut(validator) else {
+            return Ok(());
+        };
+        let commission = pool.take_commission(acc);
+        if let Some(validator_info) = self.stakers.get_mut(validator) {
+            validator_info.pending_rewards = SafeMath::add(
+                validator_info.pending_rewards,
+                commission,
+                "delegation_settle_commission",
+            )
+            .map_err(|e| format!("Failed to settle commission: {}", e))?;
+        }
+        Ok(())
+    }
+
+    /// Sacar `amount` de la delegación de un delegador, liquidando antes la
+    /// comisión y los rewards acumulados. Devuelve lo que sigue delegado.
+    fn withdraw_delegation(&mut self, delegator: &str, validator: &str, amount: u64) -> Result<u64, String> {
+        self.settle_commission(validator)?;
+        let acc_reward_per_share = self.acc_reward_per_share;
+        let pool = self
+            .delegations
+            .get_mut(validator)
+            .ok_or("No delegations to this validator")?;
+        let commission_bps = pool.commission_bps;
+        let delegator_info = pool
+            .delegators
+            .get_mut(delegator)
+            .ok_or("Delegation not found")?;
+        delegator_info.settle(acc_reward_per_share, commission_bps)?;
+
+        delegator_info.delegated_amount = SafeMath::sub(
+            delegator_info.delegated_amount,
+            amount,
+            "undelegate_update_delegator",
+        )
+        .map_err(|e| format!("Failed to update delegator amount: {}", e))?;
+        delegator_info.checkpoint(acc_reward_per_share);
+        let remaining_delegated = delegator_info.delegated_amount;
+
+        // Los rewards pendientes se conservan hasta que se reclamen
+        if remaining_delegated == 0 && delegator_info.pending_rewards == 0 {
+            pool.delegators.remove(delegator);
+        }
+
+        pool.total_delegated = SafeMath::sub(
+            pool.total_delegated,
+            amount,
+            "undelegate_update_pool",
+        )
+        .map_err(|e| format!("Failed to update delegated amount: {}", e))?;
+        pool.checkpoint(acc_reward_per_share);
+
+        self.total_staked = SafeMath::sub(
+            self.total_staked,
+            amount,
+            "undelegate_update_total_staked",
+        )
+        .map_err(|e| format!("Failed to update total staked: {}", e))?;
+
+        Ok(remaining_delegated)
+    }
+
+    /// Recortar las redelegaciones aún en curso desde un validador penalizado:
+    /// el stake sale del validador de destino a la misma tasa. Devuelve el
+    /// recorte de cada delegador; total_staked lo ajusta quien llama.
+    fn slash_redelegations(&mut self, src_validator: &str, now: u64) -> Result<Vec<(String, u64)>, String> {
+        let acc_reward_per_share = self.acc_reward_per_share;
+        let slashing_bps = self.slashing_bps;
+        let mut cuts = Vec::new();
+
+        for index in 0..self.redelegations.len() {
+            let redelegation = &self.redelegations[index];
+            if redelegation.src_validator != src_validator || redelegation.completes_at <= now {
+                continue;
+            }
+            let (delegator, dst_validator) = (redelegation.delegator.clone(), redelegation.dst_validator.clone());
+            let exposed = redelegation.amount;
+
+            self.settle_commission(&dst_validator)?;
+            let Some(pool) = self.delegations.get_mut(&dst_validator) else {
+                continue;
+            };
+            let commission_bps = pool.commission_bps;
+            let Some(delegator_info) = pool.delegators.get_mut(&delegator) else {
+                continue;
+            };
+            delegator_info.settle(acc_reward_per_share, commission_bps)?;
+            let cut = SafeMath::percentage(exposed, slashing_bps, "slash_redelegation")
+                .map_err(|e| format!("Failed to calculate redelegation slash: {}", e))?
+                .min(delegator_info.delegated_amount);
+            if cut == 0 {
+                continue;
+            }
+            delegator_info.delegated_amount -= cut;
+            delegator_info.total_slashed += cut;
+            delegator_info.checkpoint(acc_reward_per_share);
+            pool.total_delegated = pool.total_delegated.saturating_sub(cut);
+            pool.checkpoint(acc_reward_per_share);
+
+            self.redelegations[index].amount -= cut.min(exposed);
+            cuts.push((delegator, cut));
+        }
+        Ok(cuts)
+    }
+
+    /// Sumar el reward de un bloque al acumulador
+    fn accrue(&mut self, reward: u64) -> Result<(), String> {
+        if self.total_staked == 0 || reward == 0 {
+            return Ok(());
+        }
+        let per_share = reward as u128 * REWARD_PRECISION / self.total_staked as u128;
+        self.acc_reward_per_share = self.acc_reward_per_share.saturating_add(per_share);
+        self.total_rewards_pending = SafeMath::add(
+            self.total_rewards_pending,
+            reward,
+            "accrue_contract_pending",
+        )
+        .map_err(|e| format!("Failed to update contract pending rewards: {}", e))?;
+        Ok(())
+    }
+
+    /// Re-stakear los rewards de quienes activaron auto-compound. Devuelve lo re-stakeado.
+    fn compound(&mut self) -> Result<u64, String> {
+        let acc = self.acc_reward_per_share;
+        let mut compounded: u64 = 0;
+
+        // Primero las delegaciones: la comisión cobrada entra en los rewards del validador
+        let validators: Vec<String> = self.delegations.keys().cloned().collect();
+        for validator in validators {
+            self.settle_commission(&validator)?;
+            let Some(pool) = self.delegations.get_mut(&validator) else {
+                continue;
+            };
+            let commission_bps = pool.commission_bps;
+            for delegator in pool.delegators.values_mut().filter(|d| d.auto_compound && d.delegated_amount > 0) {
+                delegator.settle(acc, commission_bps)?;
+                let rewards = std::mem::take(&mut delegator.pending_rewards);
+                delegator.delegated_amount = SafeMath::add(delegator.delegated_amount, rewards, "compound_delegation")
+                    .map_err(|e| format!("Failed to compound delegation: {}", e))?;
+                delegator.total_rewards_claimed += rewards;
+                delegator.checkpoint(acc);
+                pool.total_delegated += rewards;
+                compounded += rewards;
+            }
+            pool.checkpoint(acc);
+        }
+
+        for staker in self.stakers.values_mut().filter(|s| s.auto_compound && s.is_active) {
+            staker.settle(acc)?;
+            let rewards = std::mem::take(&mut staker.pending_rewards);
+            staker.staked_amount = SafeMath::add(staker.staked_amount, rewards, "compound_stake")
+                .map_err(|e| format!("Failed to compound stake: {}", e))?;
+            staker.total_rewards_claimed += rewards;
+            staker.checkpoint(acc);
+            compounded += rewards;
+        }
+
+        self.total_staked = SafeMath::add(self.total_staked, compounded, "compound_total_staked")
+            .map_err(|e| format!("Failed to update total staked: {}", e))?;
+        self.total_rewards_pending = self.total_rewards_pending.saturating_sub(compounded);
+        self.total_rewards_distributed += compounded;
+        Ok(compounded)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -96,6 +520,31 @@ pub struct UnstakeRequest {
     pub amount: u64,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DelegateRequest {
+    pub contract_id: String,
+    pub delegator: String,
+    pub validator: String,
+    pub amount: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UndelegateRequest {
+    pub contract_id: String,
+    pub delegator: String,
+    pub validator: String,
+    pub amount: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedelegateRequest {
+    pub contract_id: String,
+    pub delegator: String,
+    pub src_validator: String,
+    pub dst_validator: String,
+    pub amount: u64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClaimRewardsRequest {
     pub contract_id: String,
@@ -110,7 +559,7 @@ pub struct CreateStakingContractRequest {
     pub max_stake: Option<u64>,
     pub reward_frequency: u64,
     pub slashing_enabled: bool,
-    pub slashing_rate: f64,
+    pub slashing_bps: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -129,6 +578,24 @@ pub struct StakingResponse {
     pub data: Option<serde_json::Value>,
 }
 
+/// Rewards pagados por un claim de un staker o de un delegador
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClaimedRewards {
+    pub claimed_amount: u64,
+    pub total_claimed: u64,
+}
+
+/// Recortes de un slashing: el stake propio del validador y lo delegado en él
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlashOutcome {
+    pub slash_amount: u64,
+    pub remaining_staked: u64,
+    pub slashing_events: u32,
+    pub delegated_slash_amount: u64,
+    /// Recorte de cada delegador (delegaciones directas y redelegaciones en curso)
+    pub delegator_slashes: BTreeMap<String, u64>,
+}
+
 impl StakingManager {
     pub fn new() -> Self {
         Self {
@@ -145,6 +612,8 @@ impl StakingManager {
             access_control: AccessControlManager::new(),
             emergency_paused: false,
             emergency_pause_reason: None,
+            emission: EmissionSchedule::default(),
+            last_accrued_block: None,
         }
     }
 
@@ -154,6 +623,31 @@ impl StakingManager {
         request: CreateStakingContractRequest,
     ) -> Result<StakingResponse, String> {
         let contract_id = self.generate_contract_id(&request.name);
+        self.insert_staking_contract(contract_id, request)
+    }
+
+    /// Crear un contrato con un ID fijo si todavía no existe (p.ej. el de los
+    /// validadores CPV, que el nodo usa en cada arranque). Devuelve si se creó.
+    pub fn ensure_staking_contract(
+        &mut self,
+        contract_id: &str,
+        request: CreateStakingContractRequest,
+    ) -> Result<bool, String> {
+        if self.staking_contracts.contains_key(contract_id) {
+            return Ok(false);
+        }
+        self.insert_staking_contract(contract_id.to_string(), request)?;
+        Ok(true)
+    }
+
+    fn insert_staking_contract(
+        &mut self,
+        contract_id: String,
+        request: CreateStakingContractRequest,
+    ) -> Result<StakingResponse, String> {
+        if request.slashing_bps > 10_000 {
+            return Err(format!("Slashing of {} bps exceeds 100%", request.slashing_bps));
+        }
         let now = get_current_timestamp().map_err(|e| format!("Failed to get timestamp: {}", e))?;
 
         let contract = StakingContract {
@@ -170,7 +664,10 @@ impl StakingManager {
             min_stake: request.min_stake,
             max_stake: request.max_stake,
             slashing_enabled: request.slashing_enabled,
-            slashing_rate: request.slashing_rate,
+            slashing_bps: request.slashing_bps,
+            delegations: HashMap::new(),
+            acc_reward_per_share: 0,
+            redelegations: Vec::new(),
         };
 
         self.staking_contracts.insert(contract_id.clone(), contract);
@@ -244,6 +741,7 @@ impl StakingManager {
         }
 
         let now = get_current_timestamp().map_err(|e| format!("Failed to get timestamp: {}", e))?;
+        let acc_reward_per_share = contract.acc_reward_per_share;
 
         // Actualizar o crear información del staker
         let staker_info = contract
@@ -258,28 +756,12 @@ impl StakingManager {
                 total_rewards_claimed: 0,
                 slashing_events: 0,
                 is_active: true,
+                reward_debt: 0,
+                auto_compound: false,
             });
 
-        // Si es un staker existente, calcular rewards pendientes antes de agregar más stake
-        if staker_info.staked_amount > 0 {
-            let pending = Self::calculate_pending_rewards_static(
-                &StakingContract {
-                    total_staked: contract.total_staked,
-                    reward_frequency: contract.reward_frequency,
-                    ..Default::default()
-                },
-                staker_info,
-                now,
-            )
-            .map_err(|e| format!("Failed to calculate pending rewards: {}", e))?;
-
-            staker_info.pending_rewards = SafeMath::add(
-                staker_info.pending_rewards,
-                pending,
-                "staking_add_pending_rewards",
-            )
-            .map_err(|e| format!("Failed to add pending rewards: {}", e))?;
-        }
+        // Liquidar los rewards acumulados antes de agregar más stake
+        staker_info.settle(acc_reward_per_share)?;
 
         // Actualizar stake con SafeMath
         staker_info.staked_amount = SafeMath::add(
@@ -290,6 +772,7 @@ impl StakingManager {
         .map_err(|e| format!("Failed to update staker amount: {}", e))?;
 
         staker_info.staked_at = now;
+        staker_info.checkpoint(acc_reward_per_share);
 
         contract.total_staked = SafeMath::add(
             contract.total_staked,
@@ -334,6 +817,7 @@ impl StakingManager {
             .staking_contracts
             .get_mut(&request.contract_id)
             .ok_or("Staking contract not found")?;
+        let acc_reward_per_share = contract.acc_reward_per_share;
 
         let staker_info = contract
             .stakers
@@ -347,7 +831,6 @@ impl StakingManager {
         let now = get_current_timestamp().map_err(|e| format!("Failed to get timestamp: {}", e))?;
 
         // SECURITY: Enforce minimum lock period (7 days = 604800 seconds)
-        const MIN_LOCK_PERIOD: u64 = 604800; // 7 days
         let stake_duration = now.saturating_sub(staker_info.staked_at);
 
         if stake_duration < MIN_LOCK_PERIOD {
@@ -378,23 +861,8 @@ impl StakingManager {
         let amount_after_fee = SafeMath::sub(request.amount, fee_amount, "unstake_subtract_fee")
             .map_err(|e| format!("Failed to calculate amount after fee: {}", e))?;
 
-        // Calcular rewards pendientes antes del unstake
-        let pending = Self::calculate_pending_rewards_static(
-            &StakingContract {
-                total_staked: contract.total_staked,
-                reward_frequency: contract.reward_frequency,
-                ..Default::default()
-            },
-            staker_info,
-            now,
-        );
-        staker_info.pending_rewards = SafeMath::add(
-            staker_info.pending_rewards,
-            pending.map_err(|e| e.to_string())?,
-            "unstake_add_pending_rewards",
-        )
-        .map_err(|e| format!("Failed to add pending rewards: {}", e))?;
-
+        // Liquidar los rewards acumulados antes del unstake
+        staker_info.settle(acc_reward_per_share)?;
         staker_info.last_claim = now;
 
         // Actualizar stake con SafeMath
@@ -404,6 +872,7 @@ impl StakingManager {
             "unstake_subtract_staked",
         )
         .map_err(|e| format!("Failed to update staked amount: {}", e))?;
+        staker_info.checkpoint(acc_reward_per_share);
 
         contract.total_staked = SafeMath::sub(
             contract.total_staked,
@@ -450,12 +919,20 @@ impl StakingManager {
     pub fn claim_rewards(
         &mut self,
         request: ClaimRewardsRequest,
-    ) -> Result<StakingResponse, String> {
+    ) -> Result<ClaimedRewards, String> {
         let contract = self
             .staking_contracts
             .get_mut(&request.contract_id)
             .ok_or("Staking contract not found")?;
 
+        if !contract.stakers.contains_key(&request.staker) {
+            return Err("Staker not found".to_string());
+        }
+
+        // La comisión de sus delegadores también es reclamable
+        contract.settle_commission(&request.staker)?;
+        let acc_reward_per_share = contract.acc_reward_per_share;
+
         let staker_info = contract
             .stakers
             .get_mut(&request.staker)
@@ -463,17 +940,8 @@ impl StakingManager {
 
         let now = get_current_timestamp().map_err(|e| format!("Failed to get timestamp: {}", e))?;
 
-        // Calcular rewards pendientes
-        let pending = Self::calculate_pending_rewards_static(
-            &StakingContract {
-                total_staked: contract.total_staked,
-                reward_frequency: contract.reward_frequency,
-                ..Default::default()
-            },
-            staker_info,
-            now,
-        );
-        let total_claimable = staker_info.pending_rewards + pending.map_err(|e| e.to_string())?;
+        staker_info.settle(acc_reward_per_share)?;
+        let total_claimable = staker_info.pending_rewards;
 
         if total_claimable == 0 {
             return Err("No rewards available to claim".to_string());
@@ -486,90 +954,134 @@ impl StakingManager {
 
         // Actualizar estadísticas del contrato
         contract.total_rewards_distributed += total_claimable;
-        contract.total_rewards_pending -= total_claimable;
+        contract.total_rewards_pending = contract.total_rewards_pending.saturating_sub(total_claimable);
 
         // Actualizar estadísticas globales
         self.global_stats.total_rewards_distributed += total_claimable;
-        self.global_stats.total_rewards_pending -= total_claimable;
+        self.global_stats.total_rewards_pending = self
+            .global_stats
+            .total_rewards_pending
+            .saturating_sub(total_claimable);
 
-        Ok(StakingResponse {
-            success: true,
-            message: format!("Claimed {} DYO rewards", total_claimable),
-            data: Some(serde_json::json!({
-                "contract_id": request.contract_id,
-                "staker": request.staker,
-                "claimed_amount": total_claimable,
-                "total_claimed": staker_info.total_rewards_claimed
-            })),
+        info!("{} claimed {} DYO rewards from {}", request.staker, total_claimable, request.contract_id);
+        Ok(ClaimedRewards {
+            claimed_amount: total_claimable,
+            total_claimed: staker_info.total_rewards_claimed,
         })
     }
 
-    /// Calcular rewards pendientes para un staker con SafeMath
-    fn calculate_pending_rewards_static(
-        contract: &StakingContract,
-        staker: &StakerInfo,
-        current_time: u64,
-    ) -> SafeMathResult<u64> {
-        if staker.staked_amount == 0 || !staker.is_active {
-            return Ok(0);
+    /// Delegar tokens a un validador del contrato (fans y oyentes que lo apoyan)
+    pub fn delegate_tokens(&mut self, request: DelegateRequest) -> Result<StakingResponse, String> {
+        if request.amount == 0 {
+            return Err("Delegation amount must be greater than zero".to_string());
         }
+        if request.delegator == request.validator {
+            return Err("Validators stake for themselves with stake_tokens".to_string());
+        }
+
+        let contract = self
+            .staking_contracts
+            .get_mut(&request.contract_id)
+            .ok_or("Staking contract not found")?;
+
+        let own_stake = match contract.stakers.get(&request.validator) {
+            Some(validator) if validator.is_active && validator.staked_amount > 0 => validator.staked_amount,
+            _ => return Err("Validator is not an active staker of this contract".to_string()),
+        };
+
+        let now = get_current_timestamp().map_err(|e| format!("Failed to get timestamp: {}", e))?;
 
-        // Safe time calculation with underflow protection
-        let time_elapsed = current_time.saturating_sub(staker.last_claim);
-        let periods_elapsed = SafeMath::div(
-            time_elapsed,
-            contract.reward_frequency,
-            "staking_periods_elapsed",
-        )?;
+        // Liquidar la comisión acumulada antes de cambiar el stake delegado
+        contract
+            .delegations
+            .entry(request.validator.clone())
+            .or_insert_with(|| DelegationPool::new(&request.validator));
+        contract.settle_commission(&request.validator)?;
+        let acc_reward_per_share = contract.acc_reward_per_share;
+
+        let pool = contract
+            .delegations
+            .get_mut(&request.validator)
+            .ok_or("No delegations to this validator")?;
+
+        let total_delegated = SafeMath::add(
+            pool.total_delegated,
+            request.amount,
+            "delegate_update_pool",
+        )
+        .map_err(|e| format!("Failed to update delegated amount: {}", e))?;
 
-        if periods_elapsed == 0 {
-            return Ok(0);
+        // El límite de stake aplica al stake efectivo del validador
+        let effective_stake = SafeMath::add(own_stake, total_delegated, "delegate_effective_stake")
+            .map_err(|e| format!("Failed to calculate effective stake: {}", e))?;
+        if let Some(max_stake) = contract.max_stake {
+            if effective_stake > max_stake {
+                return Err(format!(
+                    "Validator effective stake would exceed the maximum of {} DYO",
+                    max_stake
+                ));
+            }
         }
 
-        // Validate input ranges for security
-        SafeMath::validate_range(staker.staked_amount, 1, u64::MAX, "staker_amount")?;
-        SafeMath::validate_range(contract.total_staked, 1, u64::MAX, "total_staked")?;
-        SafeMath::validate_range(periods_elapsed, 1, 365 * 24 * 60 * 60, "periods_elapsed")?; // Max 1 year
+        let delegator_info = pool
+            .delegators
+            .entry(request.delegator.clone())
+            .or_insert(DelegatorInfo {
+                address: request.delegator.clone(),
+                delegated_amount: 0,
+                delegated_at: now,
+                pending_rewards: 0,
+                total_rewards_claimed: 0,
+                total_slashed: 0,
+                reward_debt: 0,
+                auto_compound: false,
+            });
+        delegator_info.settle(acc_reward_per_share, pool.commission_bps)?;
 
-        // Calculate reward per period using SafeMath
-        // Formula: (staker_amount * 100) / total_staked (0.01% per period)
-        let reward_per_period = SafeMath::div(
-            SafeMath::mul(staker.staked_amount, 100, "staking_reward_numerator")?,
-            contract.total_staked,
-            "staking_reward_denominator",
-        )?;
+        delegator_info.delegated_amount = SafeMath::add(
+            delegator_info.delegated_amount,
+            request.amount,
+            "delegate_update_delegator",
+        )
+        .map_err(|e| format!("Failed to update delegator amount: {}", e))?;
+        delegator_info.delegated_at = now;
+        delegator_info.checkpoint(acc_reward_per_share);
+        let delegated_amount = delegator_info.delegated_amount;
 
-        // Calculate total pending rewards
-        let total_rewards =
-            SafeMath::mul(reward_per_period, periods_elapsed, "staking_total_rewards")?;
+        pool.total_delegated = total_delegated;
+        pool.checkpoint(acc_reward_per_share);
+        let commission_bps = pool.commission_bps;
 
-        // Apply maximum reward cap to prevent runaway rewards
-        let max_reward_cap =
-            SafeMath::percentage(staker.staked_amount, 10000, "staking_max_reward_cap")?; // 100% of stake as max reward
+        contract.total_staked = SafeMath::add(
+            contract.total_staked,
+            request.amount,
+            "delegate_update_total_staked",
+        )
+        .map_err(|e| format!("Failed to update total staked: {}", e))?;
 
-        let final_rewards = if total_rewards > max_reward_cap {
-            warn!("Staking rewards capped for staker {}: {} -> {} (context: calculate_pending_rewards)", 
-                staker.address, total_rewards, max_reward_cap);
-            max_reward_cap
-        } else {
-            total_rewards
-        };
+        self.global_stats.total_staked += request.amount;
 
-        info!("Calculated pending rewards for staker {}: {} DYO (stake: {}, periods: {}, reward_per_period: {})", 
-            staker.address, final_rewards, staker.staked_amount, periods_elapsed, reward_per_period);
+        info!(
+            "Delegation: {} delegated {} DYO to validator {} (effective stake: {})",
+            request.delegator, request.amount, request.validator, effective_stake
+        );
 
-        Ok(final_rewards)
+        Ok(StakingResponse {
+            success: true,
+            message: format!("Delegated {} DYO to {}", request.amount, request.validator),
+            data: Some(serde_json::json!({
+                "contract_id": request.contract_id,
+                "delegator": request.delegator,
+                "validator": request.validator,
+                "delegated_amount": delegated_amount,
+                "validator_effective_stake": effective_stake,
+                "commission_bps": commission_bps
+            })),
+        })
     }
 
-    /// Distribuir rewards desde un pool con access control
-    pub fn distribute_rewards(
-        &mut self,
-        pool_id: &str,
-        contract_id: &str,
-        amount: u64,
-        caller_address: &str,
-    ) -> Result<StakingResponse, String> {
-        // Check if system is paused
+    /// Retirar una delegación - mismo periodo mínimo de bloqueo que el stake
+    pub fn undelegate_tokens(&mut self, request: UndelegateRequest) -> Result<StakingResponse, String> {
         if self.emergency_paused {
             return Err(format!(
                 "System is emergency paused: {}",
@@ -578,81 +1090,500 @@ impl StakingManager {
                     .unwrap_or("Unknown reason")
             ));
         }
-
-        // Check access control permissions
-        if !self
-            .access_control
-            .has_permission(caller_address, &Permission::StakingDistribute)
-        {
-            return Err("Unauthorized: Insufficient permissions to distribute rewards".to_string());
-        }
-
-        // Validate amount limits
-        if amount > 1_000_000 {
-            // Maximum 1M tokens per distribution
-            return Err(
-                "Amount exceeds maximum distribution limit of 1,000,000 tokens".to_string(),
-            );
+        if request.amount == 0 {
+            return Err("Undelegation amount must be greater than zero".to_string());
         }
-        let pool = self
-            .reward_pools
-            .get_mut(pool_id)
-            .ok_or("Reward pool not found")?;
 
         let contract = self
             .staking_contracts
-            .get_mut(contract_id)
+            .get_mut(&request.contract_id)
             .ok_or("Staking contract not found")?;
 
-        let now = get_current_timestamp().map_err(|e| format!("Failed to get timestamp: {}", e))?;
+        let delegation = contract
+            .delegations
+            .get(&request.validator)
+            .ok_or("No delegations to this validator")?
+            .delegators
+            .get(&request.delegator)
+            .ok_or("Delegation not found")?;
 
-        // Verificar límite diario
-        if now - pool.last_reset > 86400 {
-            // 24 horas
-            pool.daily_distributed = 0;
-            pool.last_reset = now;
+        if delegation.delegated_amount < request.amount {
+            return Err("Insufficient delegated amount".to_string());
         }
 
-        if pool.daily_distributed + amount > pool.max_rewards_per_day {
-            return Err("Daily reward limit exceeded".to_string());
-        }
+        let now = get_current_timestamp().map_err(|e| format!("Failed to get timestamp: {}", e))?;
 
-        if amount > pool.pending_rewards {
-            return Err("Insufficient rewards in pool".to_string());
+        // SECURITY: Una delegación no puede inflar el score de un validador y salir en el mismo bloque
+        let delegation_duration = now.saturating_sub(delegation.delegated_at);
+        if delegation_duration < MIN_LOCK_PERIOD {
+            let remaining = MIN_LOCK_PERIOD - delegation_duration;
+            return Err(format!(
+                "Minimum lock period not met. Please wait {} more seconds ({} days)",
+                remaining,
+                remaining / 86400
+            ));
         }
 
-        // Distribuir rewards a todos los stakers activos
-        let total_staked = contract.total_staked;
-        if total_staked == 0 {
-            return Err("No active stakers to distribute rewards to".to_string());
+        let remaining_delegated = contract.withdraw_delegation(&request.delegator, &request.validator, request.amount)?;
+
+        self.global_stats.total_staked = self
+            .global_stats
+            .total_staked
+            .saturating_sub(request.amount);
+
+        info!(
+            "Undelegation: {} withdrew {} DYO from validator {}",
+            request.delegator, request.amount, request.validator
+        );
+
+        Ok(StakingResponse {
+            success: true,
+            message: format!("Undelegated {} DYO from {}", request.amount, request.validator),
+            data: Some(serde_json::json!({
+                "contract_id": request.contract_id,
+                "delegator": request.delegator,
+                "validator": request.validator,
+                "undelegated_amount": request.amount,
+                "remaining_delegated": remaining_delegated
+            })),
+        })
+    }
+
+    /// Mover una delegación a otro validador sin pasar por el unbonding. Lo
+    /// movido sigue expuesto al slashing del validador de origen hasta que
+    /// termina el periodo de unbonding; mientras tanto no puede volver a
+    /// redelegarse (sin saltos encadenados) y cada delegador tiene como mucho
+    /// MAX_REDELEGATIONS_IN_FLIGHT redelegaciones en curso.
+    pub fn redelegate_tokens(&mut self, request: RedelegateRequest) -> Result<StakingResponse, String> {
+        if self.emergency_paused {
+            return Err(format!(
+                "System is emergency paused: {}",
+                self.emergency_pause_reason
+                    .as_deref()
+                    .unwrap_or("Unknown reason")
+            ));
+        }
+        if request.amount == 0 {
+            return Err("Redelegation amount must be greater than zero".to_string());
+        }
+        if request.src_validator == request.dst_validator {
+            return Err("Source and destination validators must differ".to_string());
         }
 
-        for staker_info in contract.stakers.values_mut() {
-            if staker_info.is_active && staker_info.staked_amount > 0 {
-                // Calculate staker reward using SafeMath
-                let staker_reward = SafeMath::div(
-                    SafeMath::mul(
-                        amount,
-                        staker_info.staked_amount,
-                        "distribute_rewards_numerator",
-                    )
-                    .map_err(|e| format!("Failed to calculate reward numerator: {}", e))?,
-                    total_staked,
-                    "distribute_rewards_denominator",
-                )
-                .map_err(|e| format!("Failed to calculate staker reward: {}", e))?;
+        let now = get_current_timestamp().map_err(|e| format!("Failed to get timestamp: {}", e))?;
+
+        let contract = self
+            .staking_contracts
+            .get_mut(&request.contract_id)
+            .ok_or("Staking contract not found")?;
+
+        // Las redelegaciones completadas ya no obligan al validador de origen
+        contract.redelegations.retain(|redelegation| redelegation.completes_at > now);
+        let in_flight = contract
+            .redelegations
+            .iter()
+            .filter(|redelegation| redelegation.delegator == request.delegator);
+        if in_flight.clone().any(|redelegation| redelegation.dst_validator == request.src_validator) {
+            return Err(format!(
+                "Stake redelegated to {} is still in flight and cannot be redelegated again",
+                request.src_validator
+            ));
+        }
+        if in_flight.count() >= MAX_REDELEGATIONS_IN_FLIGHT {
+            return Err(format!(
+                "At most {} redelegations can be in flight at once",
+                MAX_REDELEGATIONS_IN_FLIGHT
+            ));
+        }
+
+        let delegated = contract
+            .delegations
+            .get(&request.src_validator)
+            .and_then(|pool| pool.delegators.get(&request.delegator))
+            .map(|delegation| delegation.delegated_amount)
+            .ok_or("Delegation not found")?;
+        if delegated < request.amount {
+            return Err("Insufficient delegated amount".to_string());
+        }
+
+        // Si el destino rechaza la delegación, el origen queda como estaba
+        let contract_before = contract.clone();
+        let stats_before = self.global_stats.clone();
+        let moved = contract
+            .withdraw_delegation(&request.delegator, &request.src_validator, request.amount)
+            .and_then(|_| {
+                self.global_stats.total_staked = self.global_stats.total_staked.saturating_sub(request.amount);
+                self.delegate_tokens(DelegateRequest {
+                    contract_id: request.contract_id.clone(),
+                    delegator: request.delegator.clone(),
+                    validator: request.dst_validator.clone(),
+                    amount: request.amount,
+                })
+            });
+        if let Err(e) = moved {
+            self.staking_contracts.insert(request.contract_id.clone(), contract_before);
+            self.global_stats = stats_before;
+            return Err(e);
+        }
+
+        let completes_at = now + unbonding_period_secs();
+        let contract = self
+            .staking_contracts
+            .get_mut(&request.contract_id)
+            .ok_or("Staking contract not found")?;
+        contract.redelegations.push(Redelegation {
+            delegator: request.delegator.clone(),
+            src_validator: request.src_validator.clone(),
+            dst_validator: request.dst_validator.clone(),
+            amount: request.amount,
+            started_at: now,
+            completes_at,
+        });
+
+        info!(
+            "Redelegation: {} moved {} DYO from validator {} to {} (liable to {} until {})",
+            request.delegator, request.amount, request.src_validator, request.dst_validator,
+            request.src_validator, completes_at
+        );
+
+        Ok(StakingResponse {
+            success: true,
+            message: format!(
+                "Redelegated {} DYO from {} to {}",
+                request.amount, request.src_validator, request.dst_validator
+            ),
+            data: Some(serde_json::json!({
+                "contract_id": request.contract_id,
+                "delegator": request.delegator,
+                "src_validator": request.src_validator,
+                "dst_validator": request.dst_validator,
+                "redelegated_amount": request.amount,
+                "completes_at": completes_at
+            })),
+        })
+    }
+
+    /// Redelegaciones en curso de un delegador, las que terminan antes primero
+    pub fn redelegations_of(&self, contract_id: &str, delegator: &str, now: u64) -> Vec<Redelegation> {
+        let mut redelegations: Vec<Redelegation> = self
+            .staking_contracts
+            .get(contract_id)
+            .map(|contract| {
+                contract
+                    .redelegations
+                    .iter()
+                    .filter(|redelegation| redelegation.delegator == delegator && redelegation.completes_at > now)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default();
+        redelegations.sort_by_key(|redelegation| redelegation.completes_at);
+        redelegations
+    }
+
+    /// Reclamar los rewards de una delegación
+    pub fn claim_delegation_rewards(
+        &mut self,
+        contract_id: &str,
+        delegator: &str,
+        validator: &str,
+    ) -> Result<ClaimedRewards, String> {
+        let contract = self
+            .staking_contracts
+            .get_mut(contract_id)
+            .ok_or("Staking contract not found")?;
+
+        let acc_reward_per_share = contract.acc_reward_per_share;
+        let pool = contract.delegations.get_mut(validator).ok_or("Delegation not found")?;
+        let commission_bps = pool.commission_bps;
+        let delegator_info = pool.delegators.get_mut(delegator).ok_or("Delegation not found")?;
+
+        delegator_info.settle(acc_reward_per_share, commission_bps)?;
+        let claimed = delegator_info.pending_rewards;
+        if claimed == 0 {
+            return Err("No rewards available to claim".to_string());
+        }
+
+        delegator_info.pending_rewards = 0;
+        delegator_info.total_rewards_claimed += claimed;
+        let total_claimed = delegator_info.total_rewards_claimed;
+
+        contract.total_rewards_distributed += claimed;
+        contract.total_rewards_pending = contract.total_rewards_pending.saturating_sub(claimed);
+
+        self.global_stats.total_rewards_distributed += claimed;
+        self.global_stats.total_rewards_pending = self
+            .global_stats
+            .total_rewards_pending
+            .saturating_sub(claimed);
+
+        info!("{} claimed {} DYO delegation rewards from {}", delegator, claimed, validator);
+        Ok(ClaimedRewards {
+            claimed_amount: claimed,
+            total_claimed,
+        })
+    }
+
+    /// Fijar la comisión que un validador cobra sobre los rewards de sus
+    /// delegadores. Solo el propio validador puede cambiarla.
+    pub fn set_commission(
+        &mut self,
+        contract_id: &str,
+        caller_address: &str,
+        validator: &str,
+        commission_bps: u64,
+    ) -> Result<StakingResponse, String> {
+        if caller_address != validator {
+            return Err("Unauthorized: only the validator can set its commission".to_string());
+        }
+        if commission_bps > MAX_COMMISSION_BPS {
+            return Err(format!(
+                "Commission cannot exceed {}%",
+                MAX_COMMISSION_BPS / 100
+            ));
+        }
+
+        let contract = self
+            .staking_contracts
+            .get_mut(contract_id)
+            .ok_or("Staking contract not found")?;
+
+        if !contract.stakers.contains_key(validator) {
+            return Err("Validator is not a staker of this contract".to_string());
+        }
+
+        // Lo acumulado hasta ahora se liquida con la comisión anterior
+        contract
+            .delegations
+            .entry(validator.to_string())
+            .or_insert_with(|| DelegationPool::new(validator));
+        contract.settle_commission(validator)?;
+        let acc_reward_per_share = contract.acc_reward_per_share;
+
+        let pool = contract
+            .delegations
+            .get_mut(validator)
+            .ok_or("No delegations to this validator")?;
+        pool.settle_delegators(acc_reward_per_share)?;
+        let previous_bps = pool.commission_bps;
+        pool.commission_bps = commission_bps;
+
+        Ok(StakingResponse {
+            success: true,
+            message: format!("Commission set to {} bps", commission_bps),
+            data: Some(serde_json::json!({
+                "contract_id": contract_id,
+                "validator": validator,
+                "commission_bps": commission_bps,
+                "previous_commission_bps": previous_bps
+            })),
+        })
+    }
+
+    /// Stake delegado a un validador
+    pub fn delegated_stake(&self, contract_id: &str, validator: &str) -> u64 {
+        self.staking_contracts
+            .get(contract_id)
+            .and_then(|contract| contract.delegations.get(validator))
+            .map(|pool| pool.total_delegated)
+            .unwrap_or(0)
+    }
+
+    /// Delegaciones de una cuenta en un contrato (validador -> delegación)
+    pub fn delegations_of(&self, contract_id: &str, delegator: &str) -> Vec<(String, DelegatorInfo)> {
+        let Some(contract) = self.staking_contracts.get(contract_id) else {
+            return Vec::new();
+        };
+        let mut delegations: Vec<(String, DelegatorInfo)> = contract
+            .delegations
+            .iter()
+            .filter_map(|(validator, pool)| {
+                pool.delegators
+                    .get(delegator)
+                    .map(|delegation| (validator.clone(), delegation.clone()))
+            })
+            .collect();
+        delegations.sort_by(|a, b| a.0.cmp(&b.0));
+        delegations
+    }
+
+    /// Total que una cuenta tiene delegado en un contrato
+    pub fn delegated_by(&self, contract_id: &str, delegator: &str) -> u64 {
+        self.delegations_of(contract_id, delegator)
+            .iter()
+            .fold(0u64, |total, (_, delegation)| total.saturating_add(delegation.delegated_amount))
+    }
 
-                // Add reward to pending rewards
-                staker_info.pending_rewards = SafeMath::add(
-                    staker_info.pending_rewards,
-                    staker_reward,
-                    "distribute_rewards_add_pending",
+    /// Reflejar en el contrato el stake propio de un validador (su bond CPV).
+    /// Los rewards acumulados con el stake anterior se liquidan antes del cambio.
+    pub fn bond_validator(&mut self, contract_id: &str, validator: &str, stake: u64) -> Result<(), String> {
+        let contract = self
+            .staking_contracts
+            .get_mut(contract_id)
+            .ok_or("Staking contract not found")?;
+        let acc_reward_per_share = contract.acc_reward_per_share;
+        let now = get_current_timestamp().map_err(|e| format!("Failed to get timestamp: {}", e))?;
+
+        let is_new = !contract.stakers.contains_key(validator);
+        let staker_info = contract
+            .stakers
+            .entry(validator.to_string())
+            .or_insert(StakerInfo {
+                address: validator.to_string(),
+                staked_amount: 0,
+                staked_at: now,
+                last_claim: now,
+                pending_rewards: 0,
+                total_rewards_claimed: 0,
+                slashing_events: 0,
+                is_active: false,
+                reward_debt: 0,
+                auto_compound: false,
+            });
+        staker_info.settle(acc_reward_per_share)?;
+
+        let previous = staker_info.staked_amount;
+        let was_active = staker_info.is_active;
+        staker_info.staked_amount = stake;
+        staker_info.is_active = stake > 0;
+        staker_info.checkpoint(acc_reward_per_share);
+        let is_active = staker_info.is_active;
+
+        contract.total_staked = contract.total_staked.saturating_sub(previous).saturating_add(stake);
+        self.global_stats.total_staked = self
+            .global_stats
+            .total_staked
+            .saturating_sub(previous)
+            .saturating_add(stake);
+        if is_new {
+            self.global_stats.total_stakers += 1;
+        }
+        match (was_active, is_active) {
+            (false, true) => self.global_stats.active_stakers += 1,
+            (true, false) => self.global_stats.active_stakers = self.global_stats.active_stakers.saturating_sub(1),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Stake efectivo de un validador: stake propio más delegaciones
+    pub fn effective_stake(&self, contract_id: &str, validator: &str) -> u64 {
+        let own_stake = self
+            .get_staker_info(contract_id, validator)
+            .filter(|staker| staker.is_active)
+            .map(|staker| staker.staked_amount)
+            .unwrap_or(0);
+        own_stake.saturating_add(self.delegated_stake(contract_id, validator))
+    }
+
+    /// Rewards pendientes de un staker, incluida la comisión aún no liquidada de sus delegadores: O(1)
+    pub fn pending_rewards(&self, contract_id: &str, staker: &str) -> u64 {
+        let Some(contract) = self.staking_contracts.get(contract_id) else {
+            return 0;
+        };
+        let Some(staker_info) = contract.stakers.get(staker) else {
+            return 0;
+        };
+        let commission = contract
+            .delegations
+            .get(staker)
+            .map(|pool| pool.commission_since_checkpoint(contract.acc_reward_per_share))
+            .unwrap_or(0);
+        staker_info
+            .pending_rewards
+            .saturating_add(staker_info.accrued_since_checkpoint(contract.acc_reward_per_share))
+            .saturating_add(commission)
+    }
+
+    /// Rewards pendientes de una delegación, netos de comisión: O(1)
+    pub fn pending_delegation_rewards(&self, contract_id: &str, delegator: &str, validator: &str) -> u64 {
+        let Some(contract) = self.staking_contracts.get(contract_id) else {
+            return 0;
+        };
+        let Some(pool) = contract.delegations.get(validator) else {
+            return 0;
+        };
+        pool.delegators
+            .get(delegator)
+            .map(|delegator_info| {
+                delegator_info.pending_rewards.saturating_add(
+                    delegator_info.accrued_since_checkpoint(contract.acc_reward_per_share, pool.commission_bps),
                 )
-                .map_err(|e| format!("Failed to add reward to pending: {}", e))?;
-            }
+            })
+            .unwrap_or(0)
+    }
+
+    /// Distribuir rewards de un pool a un contrato con access control. El monto
+    /// entra al acumulador del contrato, así que se reparte pro rata entre el
+    /// stake propio y las delegaciones (menos la comisión) como los de bloque.
+    pub fn distribute_rewards(
+        &mut self,
+        pool_id: &str,
+        contract_id: &str,
+        amount: u64,
+        caller_address: &str,
+    ) -> Result<StakingResponse, String> {
+        // Check if system is paused
+        if self.emergency_paused {
+            return Err(format!(
+                "System is emergency paused: {}",
+                self.emergency_pause_reason
+                    .as_deref()
+                    .unwrap_or("Unknown reason")
+            ));
+        }
+
+        // Check access control permissions
+        if !self
+            .access_control
+            .has_permission(caller_address, &Permission::StakingDistribute)
+        {
+            return Err("Unauthorized: Insufficient permissions to distribute rewards".to_string());
+        }
+
+        // Validate amount limits
+        if amount > 1_000_000 {
+            // Maximum 1M tokens per distribution
+            return Err(
+                "Amount exceeds maximum distribution limit of 1,000,000 tokens".to_string(),
+            );
+        }
+        let pool = self
+            .reward_pools
+            .get_mut(pool_id)
+            .ok_or("Reward pool not found")?;
+
+        let contract = self
+            .staking_contracts
+            .get_mut(contract_id)
+            .ok_or("Staking contract not found")?;
+
+        let now = get_current_timestamp().map_err(|e| format!("Failed to get timestamp: {}", e))?;
+
+        // Verificar límite diario
+        if now - pool.last_reset > 86400 {
+            // 24 horas
+            pool.daily_distributed = 0;
+            pool.last_reset = now;
+        }
+
+        if pool.daily_distributed + amount > pool.max_rewards_per_day {
+            return Err("Daily reward limit exceeded".to_string());
+        }
+
+        if amount > pool.pending_rewards {
+            return Err("Insufficient rewards in pool".to_string());
         }
 
-        // Actualizar pools y contratos con SafeMath
+        if contract.total_staked == 0 {
+            return Err("No active stakers to distribute rewards to".to_string());
+        }
+
+        // Repartir por el acumulador: O(1) sin importar cuántos stakers haya
+        contract.accrue(amount)?;
+        contract.last_reward_distribution = now;
+
+        // Actualizar el pool con SafeMath
         pool.distributed_rewards = SafeMath::add(
             pool.distributed_rewards,
             amount,
@@ -674,15 +1605,6 @@ impl StakingManager {
         )
         .map_err(|e| format!("Failed to update daily distributed: {}", e))?;
 
-        contract.total_rewards_pending = SafeMath::add(
-            contract.total_rewards_pending,
-            amount,
-            "distribute_rewards_update_contract_pending",
-        )
-        .map_err(|e| format!("Failed to update contract pending rewards: {}", e))?;
-
-        contract.last_reward_distribution = now;
-
         // Actualizar estadísticas globales
         self.global_stats.total_rewards_pending = SafeMath::add(
             self.global_stats.total_rewards_pending,
@@ -709,6 +1631,169 @@ impl StakingManager {
         })
     }
 
+    /// Acumular los rewards de un bloque con access control: la emisión desde el
+    /// último bloque acumulado, repartida entre los contratos según su stake. Al cerrar una época se re-stakean los
+    /// rewards de quienes activaron auto-compound.
+    pub fn accrue_block_rewards(
+        &mut self,
+        block_height: u64,
+        caller_address: &str,
+    ) -> Result<StakingResponse, String> {
+        // Check if system is paused
+        if self.emergency_paused {
+            return Err(format!(
+                "System is emergency paused: {}",
+                self.emergency_pause_reason
+                    .as_deref()
+                    .unwrap_or("Unknown reason")
+            ));
+        }
+
+        // Check access control permissions
+        if !self
+            .access_control
+            .has_permission(caller_address, &Permission::StakingDistribute)
+        {
+            return Err("Unauthorized: Insufficient permissions to distribute rewards".to_string());
+        }
+
+        if let Some(last) = self.last_accrued_block {
+            if block_height <= last {
+                return Err(format!("Rewards for block {} already accrued", block_height));
+            }
+        }
+
+        let emission = match self.last_accrued_block {
+            Some(last) => self.emission.emission_between(last, block_height),
+            None => self.emission.reward_at(block_height),
+        };
+
+        let now = get_current_timestamp().map_err(|e| format!("Failed to get timestamp: {}", e))?;
+
+        // Sin stake no hay a quién acumular: el reward no se emite
+        let total_staked = self
+            .staking_contracts
+            .values()
+            .fold(0u64, |total, contract| total.saturating_add(contract.total_staked));
+        let mut accrued_amount: u64 = 0;
+        if total_staked > 0 {
+            for contract in self.staking_contracts.values_mut().filter(|c| c.total_staked > 0) {
+                let contract_reward =
+                    to_u64(emission as u128 * contract.total_staked as u128 / total_staked as u128);
+                contract.accrue(contract_reward)?;
+                contract.last_reward_distribution = now;
+                accrued_amount += contract_reward;
+            }
+        }
+
+        self.global_stats.total_rewards_pending = SafeMath::add(
+            self.global_stats.total_rewards_pending,
+            accrued_amount,
+            "accrue_update_global_pending",
+        )
+        .map_err(|e| format!("Failed to update global pending rewards: {}", e))?;
+
+        let epoch_closed = self.emission.crosses_epoch(self.last_accrued_block, block_height);
+        self.last_accrued_block = Some(block_height);
+        let compounded = if epoch_closed { self.compound_rewards()? } else { 0 };
+
+        info!(
+            "Block {} staking rewards accrued: {} DYO (emission: {}, compounded: {}) by {}",
+            block_height, accrued_amount, emission, compounded, caller_address
+        );
+
+        Ok(StakingResponse {
+            success: true,
+            message: format!("Accrued {} DYO rewards for block {}", accrued_amount, block_height),
+            data: Some(serde_json::json!({
+                "block_height": block_height,
+                "emission": emission,
+                "accrued_amount": accrued_amount,
+                "epoch_closed": epoch_closed,
+                "compounded_amount": compounded,
+                "accrued_by": caller_address
+            })),
+        })
+    }
+
+    /// Re-stakear los rewards de stakers y delegadores con auto-compound (cierre de época)
+    fn compound_rewards(&mut self) -> Result<u64, String> {
+        let mut compounded: u64 = 0;
+        for contract in self.staking_contracts.values_mut() {
+            compounded = SafeMath::add(compounded, contract.compound()?, "compound_rewards_total")
+                .map_err(|e| format!("Failed to compound rewards: {}", e))?;
+        }
+
+        self.global_stats.total_staked += compounded;
+        self.global_stats.total_rewards_distributed += compounded;
+        self.global_stats.total_rewards_pending = self
+            .global_stats
+            .total_rewards_pending
+            .saturating_sub(compounded);
+
+        if compounded > 0 {
+            info!("Epoch closed: {} DYO of rewards auto-compounded", compounded);
+        }
+        Ok(compounded)
+    }
+
+    /// Activar o desactivar el auto-compound de un staker
+    pub fn set_auto_compound(
+        &mut self,
+        contract_id: &str,
+        staker: &str,
+        enabled: bool,
+    ) -> Result<StakingResponse, String> {
+        let staker_info = self
+            .staking_contracts
+            .get_mut(contract_id)
+            .ok_or("Staking contract not found")?
+            .stakers
+            .get_mut(staker)
+            .ok_or("Staker not found")?;
+        staker_info.auto_compound = enabled;
+
+        Ok(StakingResponse {
+            success: true,
+            message: format!("Auto-compound {}", if enabled { "enabled" } else { "disabled" }),
+            data: Some(serde_json::json!({
+                "contract_id": contract_id,
+                "staker": staker,
+                "auto_compound": enabled
+            })),
+        })
+    }
+
+    /// Activar o desactivar el auto-compound de una delegación
+    pub fn set_delegation_auto_compound(
+        &mut self,
+        contract_id: &str,
+        delegator: &str,
+        validator: &str,
+        enabled: bool,
+    ) -> Result<StakingResponse, String> {
+        let delegator_info = self
+            .staking_contracts
+            .get_mut(contract_id)
+            .ok_or("Staking contract not found")?
+            .delegations
+            .get_mut(validator)
+            .and_then(|pool| pool.delegators.get_mut(delegator))
+            .ok_or("Delegation not found")?;
+        delegator_info.auto_compound = enabled;
+
+        Ok(StakingResponse {
+            success: true,
+            message: format!("Auto-compound {}", if enabled { "enabled" } else { "disabled" }),
+            data: Some(serde_json::json!({
+                "contract_id": contract_id,
+                "delegator": delegator,
+                "validator": validator,
+                "auto_compound": enabled
+            })),
+        })
+    }
+
     /// Emergency pause staking system
     pub fn emergency_pause(&mut self, reason: String, paused_by: String) -> Result<(), String> {
         if !self
@@ -758,13 +1843,14 @@ impl StakingManager {
         })
     }
 
-    /// Aplicar slashing a un staker
+    /// Aplicar slashing a un staker: su stake y lo delegado en él pierden
+    /// `slashing_bps` del contrato
     pub fn slash_staker(
         &mut self,
         contract_id: &str,
         staker: &str,
         reason: &str,
-    ) -> Result<StakingResponse, String> {
+    ) -> Result<SlashOutcome, String> {
         let contract = self
             .staking_contracts
             .get_mut(contract_id)
@@ -774,24 +1860,59 @@ impl StakingManager {
             return Err("Slashing is not enabled for this contract".to_string());
         }
 
-        let staker_info = contract.stakers.get_mut(staker).ok_or("Staker not found")?;
-
-        if !staker_info.is_active {
+        if !contract.stakers.get(staker).ok_or("Staker not found")?.is_active {
             return Err("Staker is not active".to_string());
         }
 
-        let slash_amount =
-            (staker_info.staked_amount as f64 * contract.slashing_rate / 100.0) as u64;
-
-        if slash_amount > staker_info.staked_amount {
-            return Err("Slash amount exceeds staked amount".to_string());
+        // Los rewards ya acumulados no se recortan: liquidarlos antes del slashing
+        contract.settle_commission(staker)?;
+        let acc_reward_per_share = contract.acc_reward_per_share;
+        if let Some(pool) = contract.delegations.get_mut(staker) {
+            pool.settle_delegators(acc_reward_per_share)?;
         }
 
+        // El stake redelegado desde este validador responde hasta que la redelegación termina
+        let now = get_current_timestamp().map_err(|e| format!("Failed to get timestamp: {}", e))?;
+        let redelegation_slashes = contract.slash_redelegations(staker, now)?;
+
+        let staker_info = contract.stakers.get_mut(staker).ok_or("Staker not found")?;
+        staker_info.settle(acc_reward_per_share)?;
+
+        let slashing_bps = contract.slashing_bps;
+        let slash_amount = SafeMath::percentage(staker_info.staked_amount, slashing_bps, "slash_staker")
+            .map_err(|e| format!("Failed to calculate slash amount: {}", e))?;
+
         // Aplicar slashing
         staker_info.staked_amount -= slash_amount;
         staker_info.slashing_events += 1;
+        staker_info.checkpoint(acc_reward_per_share);
         contract.total_staked -= slash_amount;
 
+        // Los delegadores comparten la pérdida a la misma tasa
+        let mut delegated_slash_amount = 0;
+        let mut delegator_slashes = BTreeMap::new();
+        if let Some(pool) = contract.delegations.get_mut(staker) {
+            for delegator in pool.delegators.values_mut() {
+                let delegator_slash = SafeMath::percentage(delegator.delegated_amount, slashing_bps, "slash_delegator")
+                    .map_err(|e| format!("Failed to calculate delegator slash: {}", e))?;
+                if delegator_slash == 0 {
+                    continue;
+                }
+                delegator.delegated_amount -= delegator_slash;
+                delegator.total_slashed += delegator_slash;
+                delegator.checkpoint(acc_reward_per_share);
+                delegated_slash_amount += delegator_slash;
+                delegator_slashes.insert(delegator.address.clone(), delegator_slash);
+            }
+            pool.total_delegated = pool.total_delegated.saturating_sub(delegated_slash_amount);
+            pool.checkpoint(acc_reward_per_share);
+        }
+        for (delegator, cut) in redelegation_slashes {
+            *delegator_slashes.entry(delegator).or_insert(0) += cut;
+            delegated_slash_amount += cut;
+        }
+        contract.total_staked = contract.total_staked.saturating_sub(delegated_slash_amount);
+
         // Si no queda stake, marcar como inactivo
         if staker_info.staked_amount == 0 {
             staker_info.is_active = false;
@@ -799,20 +1920,21 @@ impl StakingManager {
         }
 
         self.global_stats.total_staked -= slash_amount;
+        self.global_stats.total_staked = self
+            .global_stats
+            .total_staked
+            .saturating_sub(delegated_slash_amount);
 
-        Ok(StakingResponse {
-            success: true,
-            message: format!(
-                "Slashed {} DYO from staker {}: {}",
-                slash_amount, staker, reason
-            ),
-            data: Some(serde_json::json!({
-                "contract_id": contract_id,
-                "staker": staker,
-                "slash_amount": slash_amount,
-                "remaining_staked": staker_info.staked_amount,
-                "slashing_events": staker_info.slashing_events
-            })),
+        info!(
+            "Slashed {} DYO from staker {} and {} DYO from {} delegators: {}",
+            slash_amount, staker, delegated_slash_amount, delegator_slashes.len(), reason
+        );
+        Ok(SlashOutcome {
+            slash_amount,
+            remaining_staked: staker_info.staked_amount,
+            slashing_events: staker_info.slashing_events,
+            delegated_slash_amount,
+            delegator_slashes,
         })
     }
 
@@ -910,53 +2032,53 @@ pub struct StakingConfigs;
 
 impl StakingConfigs {
     /// Configuración para Validadores Económicos
-    pub fn economic_validators() -> (u64, Option<u64>, u64, bool, f64) {
+    pub fn economic_validators() -> (u64, Option<u64>, u64, bool, u64) {
         let min_stake = 1_000_000; // 1M DYO
         let max_stake = Some(100_000_000); // 100M DYO
         let reward_frequency = 86400; // 1 día
         let slashing_enabled = true;
-        let slashing_rate = 5.0; // 5%
+        let slashing_bps = 500; // 5%
 
         (
             min_stake,
             max_stake,
             reward_frequency,
             slashing_enabled,
-            slashing_rate,
+            slashing_bps,
         )
     }
 
     /// Configuración para Validadores Creativos
-    pub fn creative_validators() -> (u64, Option<u64>, u64, bool, f64) {
+    pub fn creative_validators() -> (u64, Option<u64>, u64, bool, u64) {
         let min_stake = 0; // Sin mínimo
         let max_stake = Some(50_000_000); // 50M DYO
         let reward_frequency = 604800; // 1 semana
         let slashing_enabled = false;
-        let slashing_rate = 0.0;
+        let slashing_bps = 0;
 
         (
             min_stake,
             max_stake,
             reward_frequency,
             slashing_enabled,
-            slashing_rate,
+            slashing_bps,
         )
     }
 
     /// Configuración para Validadores Comunitarios
-    pub fn community_validators() -> (u64, Option<u64>, u64, bool, f64) {
+    pub fn community_validators() -> (u64, Option<u64>, u64, bool, u64) {
         let min_stake = 0; // Sin mínimo
         let max_stake = Some(10_000_000); // 10M DYO
         let reward_frequency = 604800; // 1 semana
         let slashing_enabled = false;
-        let slashing_rate = 0.0;
+        let slashing_bps = 0;
 
         (
             min_stake,
             max_stake,
             reward_frequency,
             slashing_enabled,
-            slashing_rate,
+            slashing_bps,
         )
     }
 }
@@ -983,7 +2105,7 @@ mod tests {
             max_stake: Some(100000000),
             reward_frequency: 86400,
             slashing_enabled: true,
-            slashing_rate: 5.0,
+            slashing_bps: 500,
         };
 
         let result = manager.create_staking_contract(request);
@@ -1003,7 +2125,7 @@ mod tests {
             max_stake: None,
             reward_frequency: 86400,
             slashing_enabled: false,
-            slashing_rate: 0.0,
+            slashing_bps: 0,
         };
         let result = manager.create_staking_contract(create_request);
         assert!(result.is_ok());
@@ -1025,4 +2147,329 @@ mod tests {
         assert_eq!(manager.global_stats.total_stakers, 1);
         assert_eq!(manager.global_stats.total_staked, 5000);
     }
+
+    /// Contrato con un validador que ya tiene 6000 DYO en stake propio
+    fn contract_with_validator(manager: &mut StakingManager, slashing_bps: u64) -> String {
+        let create_request = CreateStakingContractRequest {
+            name: "Creative Validators".to_string(),
+            purpose: "CREATIVE".to_string(),
+            min_stake: 1000,
+            max_stake: None,
+            reward_frequency: 86400,
+            slashing_enabled: slashing_bps > 0,
+            slashing_bps,
+        };
+        let contract_id = manager.create_staking_contract(create_request).unwrap().data.unwrap()["contract_id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        manager
+            .stake_tokens(StakeRequest {
+                contract_id: contract_id.clone(),
+                staker: "validator1".to_string(),
+                amount: 6000,
+            })
+            .unwrap();
+        contract_id
+    }
+
+    fn delegate(manager: &mut StakingManager, contract_id: &str, delegator: &str, amount: u64) -> Result<StakingResponse, String> {
+        manager.delegate_tokens(DelegateRequest {
+            contract_id: contract_id.to_string(),
+            delegator: delegator.to_string(),
+            validator: "validator1".to_string(),
+            amount,
+        })
+    }
+
+    #[test]
+    fn test_delegation_counts_in_effective_stake() {
+        let mut manager = StakingManager::new();
+        let contract_id = contract_with_validator(&mut manager, 0);
+
+        assert!(delegate(&mut manager, &contract_id, "fan1", 3000).is_ok());
+        assert_eq!(manager.effective_stake(&contract_id, "validator1"), 9000);
+        assert_eq!(manager.delegated_stake(&contract_id, "validator1"), 3000);
+        assert_eq!(manager.global_stats.total_staked, 9000);
+
+        // Ni auto-delegación ni delegación a quien no es validador
+        assert!(delegate(&mut manager, &contract_id, "validator1", 100).is_err());
+        let to_unknown = manager.delegate_tokens(DelegateRequest {
+            contract_id: contract_id.clone(),
+            delegator: "fan1".to_string(),
+            validator: "nobody".to_string(),
+            amount: 100,
+        });
+        assert!(to_unknown.is_err());
+
+        // La delegación queda bloqueada como el stake
+        let early_exit = manager.undelegate_tokens(UndelegateRequest {
+            contract_id: contract_id.clone(),
+            delegator: "fan1".to_string(),
+            validator: "validator1".to_string(),
+            amount: 3000,
+        });
+        assert!(early_exit.is_err());
+        assert_eq!(manager.effective_stake(&contract_id, "validator1"), 9000);
+    }
+
+    /// Emisión fija por bloque, sin halving ni épocas
+    fn flat_emission(reward_per_block: u64, epoch_length_blocks: u64) -> EmissionSchedule {
+        EmissionSchedule {
+            initial_reward_per_block: reward_per_block,
+            halving_interval_blocks: 0,
+            epoch_length_blocks,
+        }
+    }
+
+    #[test]
+    fn test_emission_schedule_halves() {
+        let schedule = EmissionSchedule {
+            initial_reward_per_block: 100,
+            halving_interval_blocks: 10,
+            epoch_length_blocks: 0,
+        };
+        assert_eq!(schedule.reward_at(9), 100);
+        assert_eq!(schedule.reward_at(10), 50);
+        assert_eq!(schedule.reward_at(25), 25);
+        // Bloques 8..=12: 100 + 100 + 50 + 50 + 50
+        assert_eq!(schedule.emission_between(7, 12), 350);
+        assert_eq!(schedule.emission_between(12, 12), 0);
+    }
+
+    #[test]
+    fn test_block_rewards_split_with_delegators() {
+        let mut manager = StakingManager::new();
+        manager.emission = flat_emission(10_000, 0);
+        let contract_id = contract_with_validator(&mut manager, 0);
+        delegate(&mut manager, &contract_id, "fan1", 3000).unwrap();
+        delegate(&mut manager, &contract_id, "fan2", 1000).unwrap();
+        assert!(manager.set_commission(&contract_id, "validator1", "validator1", MAX_COMMISSION_BPS + 1).is_err());
+        manager.set_commission(&contract_id, "validator1", "validator1", 1_000).unwrap();
+
+        assert!(manager.accrue_block_rewards(1, "attacker").is_err());
+        manager.accrue_block_rewards(1, "system").unwrap();
+
+        // 40% del reward es de los delegadores; el validador cobra el 10% de eso
+        assert_eq!(manager.pending_delegation_rewards(&contract_id, "fan1", "validator1"), 2700);
+        assert_eq!(manager.pending_delegation_rewards(&contract_id, "fan2", "validator1"), 900);
+        assert_eq!(manager.pending_rewards(&contract_id, "validator1"), 6400);
+
+        let claimed = manager.claim_delegation_rewards(&contract_id, "fan1", "validator1").unwrap();
+        assert_eq!(claimed.claimed_amount, 2700);
+        assert!(manager.claim_delegation_rewards(&contract_id, "fan1", "validator1").is_err());
+
+        let claimed = manager
+            .claim_rewards(ClaimRewardsRequest {
+                contract_id: contract_id.clone(),
+                staker: "validator1".to_string(),
+            })
+            .unwrap();
+        assert_eq!(claimed, ClaimedRewards { claimed_amount: 6400, total_claimed: 6400 });
+    }
+
+    #[test]
+    fn test_distribute_rewards_funds_accumulator() {
+        let mut manager = StakingManager::new();
+        let contract_id = contract_with_validator(&mut manager, 0);
+        delegate(&mut manager, &contract_id, "fan1", 4000).unwrap();
+        manager.set_commission(&contract_id, "validator1", "validator1", 1_000).unwrap();
+
+        let pool_id = manager
+            .create_reward_pool(CreateRewardPoolRequest {
+                name: "Creative Rewards".to_string(),
+                purpose: "CREATIVE".to_string(),
+                total_rewards: 1_000_000,
+                reward_rate: 100,
+                max_rewards_per_day: 1_000_000,
+            })
+            .unwrap()
+            .data
+            .unwrap()["pool_id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        assert!(manager.distribute_rewards(&pool_id, &contract_id, 10_000, "attacker").is_err());
+        manager.distribute_rewards(&pool_id, &contract_id, 10_000, "system").unwrap();
+
+        assert_eq!(manager.pending_delegation_rewards(&contract_id, "fan1", "validator1"), 3600);
+        assert_eq!(manager.pending_rewards(&contract_id, "validator1"), 6400);
+        assert_eq!(manager.reward_pools[&pool_id].pending_rewards, 990_000);
+        assert_eq!(manager.get_staking_contract(&contract_id).unwrap().acc_reward_per_share, REWARD_PRECISION);
+    }
+
+    #[test]
+    fn test_auto_compound_at_epoch_boundary() {
+        let mut manager = StakingManager::new();
+        manager.emission = flat_emission(1_000, 10);
+        let contract_id = contract_with_validator(&mut manager, 0);
+        manager
+            .stake_tokens(StakeRequest {
+                contract_id: contract_id.clone(),
+                staker: "staker2".to_string(),
+                amount: 4000,
+            })
+            .unwrap();
+        manager.set_auto_compound(&contract_id, "validator1", true).unwrap();
+
+        let first = manager.accrue_block_rewards(9, "system").unwrap();
+        assert_eq!(first.data.unwrap()["epoch_closed"], false);
+        assert_eq!(manager.pending_rewards(&contract_id, "validator1"), 600);
+
+        let closing = manager.accrue_block_rewards(10, "system").unwrap();
+        assert_eq!(closing.data.unwrap()["compounded_amount"], 1200);
+
+        let contract = manager.get_staking_contract(&contract_id).unwrap();
+        assert_eq!(contract.stakers["validator1"].staked_amount, 7200);
+        assert_eq!(contract.total_staked, 11_200);
+        assert_eq!(manager.pending_rewards(&contract_id, "validator1"), 0);
+        assert_eq!(manager.pending_rewards(&contract_id, "staker2"), 800);
+        assert_eq!(manager.global_stats.total_staked, 11_200);
+
+        assert!(manager.accrue_block_rewards(10, "system").is_err());
+    }
+
+    #[test]
+    fn test_slashing_shared_with_delegators() {
+        let mut manager = StakingManager::new();
+        let contract_id = contract_with_validator(&mut manager, 1_000);
+        delegate(&mut manager, &contract_id, "fan1", 2000).unwrap();
+
+        let slashed = manager.slash_staker(&contract_id, "validator1", "double signing").unwrap();
+        assert_eq!(slashed.slash_amount, 600);
+        assert_eq!(slashed.delegated_slash_amount, 200);
+
+        let contract = manager.get_staking_contract(&contract_id).unwrap();
+        assert_eq!(contract.delegations["validator1"].delegators["fan1"].delegated_amount, 1800);
+        assert_eq!(contract.delegations["validator1"].delegators["fan1"].total_slashed, 200);
+        assert_eq!(contract.total_staked, 7200);
+        assert_eq!(manager.effective_stake(&contract_id, "validator1"), 7200);
+        assert_eq!(manager.global_stats.total_staked, 7200);
+    }
+
+    #[test]
+    fn test_stored_slashing_rate_loads_as_bps() {
+        let mut manager = StakingManager::new();
+        let contract_id = contract_with_validator(&mut manager, 500);
+        let mut stored = serde_json::to_value(manager.get_staking_contract(&contract_id).unwrap()).unwrap();
+        assert_eq!(stored["slashing_bps"], 500);
+
+        // Estado guardado con el porcentaje en f64
+        let legacy = stored.as_object_mut().unwrap();
+        legacy.remove("slashing_bps");
+        legacy.insert("slashing_rate".to_string(), serde_json::json!(5.0));
+        let contract: StakingContract = serde_json::from_value(stored).unwrap();
+        assert_eq!(contract.slashing_bps, 500);
+    }
+
+    #[test]
+    fn test_commission_change_only_by_validator_and_settled_at_old_rate() {
+        let mut manager = StakingManager::new();
+        manager.emission = flat_emission(10_000, 0);
+        let contract_id = contract_with_validator(&mut manager, 0);
+        delegate(&mut manager, &contract_id, "fan1", 4000).unwrap();
+        manager.set_commission(&contract_id, "validator1", "validator1", 1_000).unwrap();
+
+        // Nadie más puede cambiar la comisión del validador
+        assert!(manager.set_commission(&contract_id, "fan1", "validator1", 0).is_err());
+
+        manager.accrue_block_rewards(1, "system").unwrap();
+        manager.set_commission(&contract_id, "validator1", "validator1", 5_000).unwrap();
+
+        // El bloque 1 se liquida al 10%; solo el bloque 2 paga el 50%
+        manager.accrue_block_rewards(2, "system").unwrap();
+        assert_eq!(manager.pending_delegation_rewards(&contract_id, "fan1", "validator1"), 3600 + 2000);
+        assert_eq!(manager.pending_rewards(&contract_id, "validator1"), 6400 + 8000);
+    }
+
+    #[test]
+    fn test_bond_validator_mirrors_own_stake() {
+        let mut manager = StakingManager::new();
+        let contract_id = contract_with_validator(&mut manager, 500);
+        delegate(&mut manager, &contract_id, "fan1", 2000).unwrap();
+
+        manager.bond_validator(&contract_id, "validator1", 10_000).unwrap();
+        manager.bond_validator(&contract_id, "validator2", 3000).unwrap();
+        assert_eq!(manager.effective_stake(&contract_id, "validator1"), 12_000);
+        assert_eq!(manager.effective_stake(&contract_id, "validator2"), 3000);
+        assert_eq!(manager.global_stats.total_staked, 15_000);
+        assert_eq!(manager.delegated_by(&contract_id, "fan1"), 2000);
+
+        let slashed = manager.slash_staker(&contract_id, "validator1", "double signing").unwrap();
+        assert_eq!(slashed.delegator_slashes["fan1"], 100);
+        assert_eq!(manager.delegated_by(&contract_id, "fan1"), 1900);
+
+        manager.bond_validator(&contract_id, "validator2", 0).unwrap();
+        assert!(delegate_to(&mut manager, &contract_id, "fan1", "validator2", 100).is_err());
+    }
+
+    #[test]
+    fn test_redelegation_rules_and_source_liability() {
+        let mut manager = StakingManager::new();
+        let contract_id = contract_with_validator(&mut manager, 1_000);
+        manager.bond_validator(&contract_id, "validator2", 6000).unwrap();
+        manager.bond_validator(&contract_id, "validator3", 6000).unwrap();
+        delegate(&mut manager, &contract_id, "fan1", 2000).unwrap();
+
+        redelegate(&mut manager, &contract_id, "fan1", "validator1", "validator2", 1000).unwrap();
+        assert_eq!(manager.effective_stake(&contract_id, "validator1"), 7000);
+        assert_eq!(manager.effective_stake(&contract_id, "validator2"), 7000);
+        assert_eq!(manager.delegated_by(&contract_id, "fan1"), 2000);
+        assert_eq!(manager.redelegations_of(&contract_id, "fan1", 0).len(), 1);
+
+        // Lo que llega por una redelegación en curso no puede volver a saltar
+        assert!(redelegate(&mut manager, &contract_id, "fan1", "validator2", "validator3", 500).is_err());
+        assert_eq!(manager.effective_stake(&contract_id, "validator2"), 7000);
+
+        // El origen sigue respondiendo por lo redelegado: 100 de la delegación directa y 100 de la redelegada
+        let slashed = manager.slash_staker(&contract_id, "validator1", "double signing").unwrap();
+        assert_eq!(slashed.delegator_slashes["fan1"], 200);
+        let contract = manager.get_staking_contract(&contract_id).unwrap();
+        assert_eq!(contract.delegations["validator2"].delegators["fan1"].delegated_amount, 900);
+        assert_eq!(contract.redelegations[0].amount, 900);
+        assert_eq!(manager.effective_stake(&contract_id, "validator2"), 6900);
+        assert_eq!(manager.delegated_by(&contract_id, "fan1"), 1800);
+
+        // Como mucho MAX_REDELEGATIONS_IN_FLIGHT a la vez
+        delegate_to(&mut manager, &contract_id, "fan2", "validator3", 800).unwrap();
+        for _ in 0..MAX_REDELEGATIONS_IN_FLIGHT {
+            redelegate(&mut manager, &contract_id, "fan2", "validator3", "validator2", 100).unwrap();
+        }
+        assert!(redelegate(&mut manager, &contract_id, "fan2", "validator3", "validator2", 100).is_err());
+        assert_eq!(manager.delegated_by(&contract_id, "fan2"), 800);
+    }
+
+    fn delegate_to(
+        manager: &mut StakingManager,
+        contract_id: &str,
+        delegator: &str,
+        validator: &str,
+        amount: u64,
+    ) -> Result<StakingResponse, String> {
+        manager.delegate_tokens(DelegateRequest {
+            contract_id: contract_id.to_string(),
+            delegator: delegator.to_string(),
+            validator: validator.to_string(),
+            amount,
+        })
+    }
+
+    fn redelegate(
+        manager: &mut StakingManager,
+        contract_id: &str,
+        delegator: &str,
+        src_validator: &str,
+        dst_validator: &str,
+        amount: u64,
+    ) -> Result<StakingResponse, String> {
+        manager.redelegate_tokens(RedelegateRequest {
+            contract_id: contract_id.to_string(),
+            delegator: delegator.to_string(),
+            src_validator: src_validator.to_string(),
+            dst_validator: dst_validator.to_string(),
+            amount,
+        })
+    }
 }
@@ -0,0 +1,297 @@
+//! Persistent Chain State: per-block Commits and Snapshots
+//!
+//! The account state (balances + nonces, plus the stakes, NFTs and votes of the
+//! ledger) is committed to the database in the
+//! same transaction as the block that produced it, and every
+//! `snapshot_interval()` blocks the full committed state is stored as a
+//! snapshot together with its state root. On startup the node restores the
+//! newest valid snapshot and replays the blocks above it through
+//! `Blockchain::replay_block`, so every replayed block must reproduce the state
+//! root its proposer sealed (they were authenticated when first imported).
+//!
+//! The mempool is stored as the ledger transactions themselves (signature, kind
+//! and tip included) grouped in their bundles: each accepted submission writes
+//! its bundle, and every commit rewrites the pending set the new head left, so
+//! a restart re-queues exactly what the node had accepted.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::blockchain::blockchain::{Block, Blockchain, PendingMeta, Transaction};
+use crate::blockchain::ledger::LedgerState;
+
+/// Blocks between two state snapshots (override with DUJYO_SNAPSHOT_INTERVAL)
+pub const DEFAULT_SNAPSHOT_INTERVAL: u64 = 100;
+
+/// Snapshots kept in the database (older ones are pruned on commit)
+pub const SNAPSHOTS_TO_KEEP: i64 = 3;
+
+pub fn snapshot_interval() -> u64 {
+    std::env::var("DUJYO_SNAPSHOT_INTERVAL")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .filter(|interval| *interval > 0)
+        .unwrap_or(DEFAULT_SNAPSHOT_INTERVAL)
+}
+
+/// Does committing a block at this height trigger a snapshot?
+pub fn is_snapshot_height(height: u64, interval: u64) -> bool {
+    interval > 0 && height > 0 && height.is_multiple_of(interval)
+}
+
+/// Full committed account state at one canonical block
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateSnapshot {
+    pub height: u64,
+    pub block_hash: String,
+    pub state_root: String,
+    pub balances: HashMap<String, u64>,
+    pub nonces: HashMap<String, u64>,
+    #[serde(default)]
+    pub ledger: LedgerState,
+}
+
+impl StateSnapshot {
+    /// Committed state at the current head (mempool effects excluded)
+    pub fn capture(blockchain: &Blockchain) -> Self {
+        let head = blockchain.get_latest_block();
+        let (mut balances, nonces, ledger) = blockchain.committed_state();
+        balances.retain(|_, balance| *balance > 0);
+        StateSnapshot {
+            height: head.height,
+            block_hash: head.hash.clone(),
+            state_root: Blockchain::compute_state_root(&balances, &nonces, &ledger),
+            balances,
+            nonces,
+            ledger,
+        }
+    }
+
+    /// The stored root must match the stored accounts
+    pub fn verify(&self) -> Result<(), String> {
+        if Blockchain::compute_state_root(&self.balances, &self.nonces, &self.ledger) != self.state_root {
+            return Err(format!("Snapshot at height {} does not match its state root", self.height));
+        }
+        Ok(())
+    }
+}
+
+/// Pending transactions in mempool order, with the last bundle number assigned
+/// when they were captured (bundles stored later are not part of this view)
+#[derive(Debug, Clone, Default)]
+pub struct MempoolSnapshot {
+    pub entries: Vec<(Transaction, PendingMeta)>,
+    pub last_bundle: u64,
+}
+
+impl MempoolSnapshot {
+    pub fn capture(blockchain: &Blockchain) -> Self {
+        MempoolSnapshot {
+            entries: blockchain.pending_entries(),
+            last_bundle: blockchain.last_bundle(),
+        }
+    }
+}
+
+/// Account rows written together with one or more blocks
+#[derive(Debug, Clone, Default)]
+pub struct StateCommit {
+    pub balances: Vec<(String, u64)>,
+    pub nonces: Vec<(String, u64)>,
+    pub snapshot: Option<StateSnapshot>,
+    /// What is still pending on top of the committed blocks
+    pub mempool: MempoolSnapshot,
+}
+
+impl StateCommit {
+    /// Accounts touched by `blocks` (values as seen by the API, mempool included)
+    /// plus a snapshot of the committed state when one of them is a snapshot height
+    pub fn capture(blockchain: &Blockchain, blocks: &[Block], interval: u64) -> Self {
+        let touched: HashSet<&str> = blocks
+            .iter()
+            .flat_map(|block| block.transactions.iter())
+            .flat_map(Transaction::accounts)
+            .collect();
+        let snapshot_due = blocks.iter().any(|block| is_snapshot_height(block.height, interval));
+
+        StateCommit {
+            balances: touched
+                .iter()
+                .map(|address| (address.to_string(), blockchain.get_balance(address)))
+                .collect(),
+            nonces: touched
+                .iter()
+                .map(|address| (address.to_string(), blockchain.next_nonce(address) - 1))
+                .collect(),
+            snapshot: snapshot_due.then(|| StateSnapshot::capture(blockchain)),
+            mempool: MempoolSnapshot::capture(blockchain),
+        }
+    }
+}
+
+/// Rebuild the chain from stored blocks: load `snapshot` at its block and
+/// replay (and re-verify) every block above it
+pub fn restore(blocks: &[Block], snapshot: &StateSnapshot) -> Result<Blockchain, String> {
+    snapshot.verify()?;
+
+    let mut blockchain = Blockchain::new();
+    let (base, replay): (Vec<&Block>, Vec<&Block>) = blocks
+        .iter()
+        .filter(|block| block.height > 0)
+        .partition(|block| block.height <= snapshot.height);
+    blockchain.chain.extend(base.into_iter().cloned());
+
+    let head = blockchain.get_latest_block();
+    if head.height != snapshot.height || head.hash != snapshot.block_hash {
+        return Err(format!(
+            "Snapshot block {} at height {} is not in the stored chain",
+            snapshot.block_hash, snapshot.height
+        ));
+    }
+    blockchain.balances = snapshot.balances.clone();
+    blockchain.nonces = snapshot.nonces.clone();
+    blockchain.ledger = snapshot.ledger.clone();
+
+    for block in replay {
+        let height = block.height;
+        blockchain
+            .replay_block(block.clone())
+            .map_err(|e| format!("Replay failed at block {}: {}", height, e))?;
+    }
+    Ok(blockchain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::blockchain::TransactionAuth;
+    use crate::blockchain::mempool::TransactionPriority;
+
+    fn produce(blockchain: &mut Blockchain, transactions: Vec<Transaction>) -> Block {
+        for transaction in transactions {
+            blockchain.add_transaction(transaction).unwrap();
+        }
+        let head = blockchain.get_latest_block().clone();
+        blockchain.pending_meta.clear();
+        let block = Block::new(
+            head.height + 1,
+            head.timestamp + 10,
+            std::mem::take(&mut blockchain.pending_transactions),
+            head.hash,
+            blockchain.state_root(),
+            Some("system".to_string()),
+        );
+        blockchain.chain.push(block.clone());
+        block
+    }
+
+    fn transfer(to: &str, amount: u64) -> Transaction {
+        Transaction::system("DUalice".to_string(), to.to_string(), amount, None)
+    }
+
+    fn funded_chain() -> Blockchain {
+        let mut blockchain = Blockchain::new();
+        blockchain.balances.insert("DUalice".to_string(), 10_000);
+        blockchain
+    }
+
+    #[test]
+    fn test_restore_replays_blocks_above_snapshot() {
+        let mut blockchain = funded_chain();
+        let genesis_snapshot = StateSnapshot::capture(&blockchain);
+        produce(&mut blockchain, vec![transfer("DUbob", 100)]);
+        let snapshot = StateSnapshot::capture(&blockchain);
+        produce(&mut blockchain, vec![transfer("DUcarol", 200)]);
+        produce(&mut blockchain, vec![]);
+
+        for base in [&genesis_snapshot, &snapshot] {
+            let restored = restore(&blockchain.chain, base).unwrap();
+            assert_eq!(restored.get_latest_block().hash, blockchain.get_latest_block().hash);
+            assert_eq!(restored.state_root(), blockchain.state_root());
+            assert_eq!(restored.get_balance("DUcarol"), 200);
+        }
+    }
+
+    #[test]
+    fn test_snapshot_excludes_mempool() {
+        let mut blockchain = funded_chain();
+        produce(&mut blockchain, vec![transfer("DUbob", 100)]);
+        let committed_root = blockchain.state_root();
+        blockchain.add_transaction(transfer("DUbob", 50)).unwrap();
+
+        let snapshot = StateSnapshot::capture(&blockchain);
+        assert_eq!(snapshot.state_root, committed_root);
+        assert_eq!(snapshot.balances.get("DUbob"), Some(&100));
+    }
+
+    #[test]
+    fn test_mempool_snapshot_restores_signed_bundles() {
+        let mut blockchain = funded_chain();
+        produce(&mut blockchain, vec![transfer("DUbob", 100)]);
+        let mut signed = transfer("DUcarol", 300);
+        signed.auth = Some(TransactionAuth {
+            nonce: 1,
+            chain_id: "dujyo-test".to_string(),
+            fee: 5,
+            tip: 2,
+            public_key: "ab".repeat(32),
+            signature: "cd".repeat(64),
+        });
+        let gas = Transaction::system("DUalice".to_string(), "DUfees".to_string(), 5, None);
+        blockchain.add_transactions(vec![gas, signed], TransactionPriority::High).unwrap();
+        blockchain.add_transaction(transfer("DUbob", 50)).unwrap();
+
+        // What storage writes and reads back, grouped into bundles again
+        let mempool = MempoolSnapshot::capture(&blockchain);
+        let stored = serde_json::to_string(&mempool.entries.iter().map(|(tx, _)| tx).collect::<Vec<_>>()).unwrap();
+        let loaded: Vec<Transaction> = serde_json::from_str(&stored).unwrap();
+        let mut bundles: Vec<(PendingMeta, Vec<Transaction>)> = Vec::new();
+        for ((_, meta), transaction) in mempool.entries.iter().zip(loaded) {
+            match bundles.last_mut() {
+                Some((last, transactions)) if last.bundle == meta.bundle => transactions.push(transaction),
+                _ => bundles.push((*meta, vec![transaction])),
+            }
+        }
+        assert_eq!(bundles.len(), 2);
+
+        let mut restored = restore(&blockchain.chain, &StateSnapshot::capture(&blockchain)).unwrap();
+        for (meta, transactions) in bundles {
+            restored.add_transactions(transactions, meta.priority).unwrap();
+        }
+        assert_eq!(restored.state_root(), blockchain.state_root());
+        assert_eq!(restored.get_balance("DUcarol"), 300);
+        let pending = restored.pending_entries();
+        assert_eq!(pending[1].0.auth, blockchain.pending_transactions[1].auth);
+        assert_eq!(pending[1].1.priority, TransactionPriority::High);
+        assert_eq!(pending[0].1.bundle, pending[1].1.bundle);
+        assert_ne!(pending[1].1.bundle, pending[2].1.bundle);
+    }
+
+    #[test]
+    fn test_tampered_block_fails_replay() {
+        let mut blockchain = funded_chain();
+        let snapshot = StateSnapshot::capture(&blockchain);
+        let mut block = produce(&mut blockchain, vec![transfer("DUbob", 100)]);
+        block.transactions[0].amount = 5_000;
+        block.merkle_root = block.compute_merkle_root();
+        block.hash = block.calculate_hash();
+
+        assert!(restore(&[block], &snapshot).is_err());
+    }
+
+    #[test]
+    fn test_tampered_snapshot_rejected() {
+        let blockchain = funded_chain();
+        let mut snapshot = StateSnapshot::capture(&blockchain);
+        snapshot.balances.insert("DUmallory".to_string(), 1_000_000);
+        assert!(snapshot.verify().is_err());
+        assert!(restore(&blockchain.chain, &snapshot).is_err());
+    }
+
+    #[test]
+    fn test_snapshot_heights() {
+        assert!(!is_snapshot_height(0, 100));
+        assert!(!is_snapshot_height(99, 100));
+        assert!(is_snapshot_height(200, 100));
+    }
+}
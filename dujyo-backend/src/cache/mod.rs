@@ -14,6 +14,7 @@ use serde::{Deserialize, Serialize};
 use std::time::Duration;
 use tracing::{info, warn, error, debug};
 use crate::blockchain::real_blockchain::TokenBalance;
+use crate::utils::amount::Amount;
 
 /// Cache configuration with TTL settings
 #[derive(Debug, Clone)]
@@ -70,10 +71,10 @@ pub struct CachedBalance {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CachedTokenBalance {
     pub address: String,
-    pub dyo: f64,
-    pub dys: f64,
-    pub staked: f64,
-    pub total: f64,
+    pub dyo: Amount,
+    pub dys: Amount,
+    pub staked: Amount,
+    pub total: Amount,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
@@ -95,9 +96,9 @@ pub struct CachedDexPool {
     pub pool_id: String,
     pub token_a: String,
     pub token_b: String,
-    pub reserve_a: f64,
-    pub reserve_b: f64,
-    pub total_liquidity: f64,
+    pub reserve_a: Amount,
+    pub reserve_b: Amount,
+    pub total_liquidity: Amount,
     pub price_impact: f64,
     pub last_updated: chrono::DateTime<chrono::Utc>,
 }
@@ -58,12 +58,7 @@ impl CompatibilityShim {
         } else {
             // Usar lógica JSON-RPC existente (directamente desde blockchain)
             let mut blockchain = self.blockchain.lock().unwrap();
-            let transaction = crate::blockchain::blockchain::Transaction {
-                from,
-                to,
-                amount,
-                nft_id,
-            };
+            let transaction = crate::blockchain::blockchain::Transaction::system(from, to, amount, nft_id);
             
             blockchain.add_transaction(transaction)?;
             Ok("transaction_added_via_jsonrpc".to_string())
@@ -1,4 +1,5 @@
-use crate::utils::vrf::VRFManager;
+use crate::consensus::proposer::{self, ProposerKeyring};
+use crate::utils::vrf::{VRFManager, VRFResult};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::time::{SystemTime, UNIX_EPOCH};
@@ -25,6 +26,15 @@ pub struct EconomicValidator {
     pub validation_count: u64,
     pub is_active: bool,
     pub last_validation: u64,
+    #[serde(default)]
+    pub delegated_stake: u64, // Stake delegado por fans y oyentes
+}
+
+impl EconomicValidator {
+    /// Own bond plus the stake delegated to the validator
+    pub fn effective_stake(&self) -> u64 {
+        self.stake.saturating_add(self.delegated_stake)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -157,6 +167,7 @@ impl CPVConsensus {
                 validation_count: row.get::<i64, _>("validation_count") as u64,
                 is_active: row.get("is_active"),
                 last_validation: last_validated_at.map(|dt| dt.timestamp() as u64).unwrap_or(0),
+                delegated_stake: 0,
             }
         })
         .fetch_all(pool)
@@ -377,6 +388,7 @@ impl CPVConsensus {
             validation_count: 0,
             is_active: true,
             last_validation: 0,
+            delegated_stake: 0,
         };
 
         self.economic_validators.insert(address.clone(), validator.clone());
@@ -676,6 +688,29 @@ impl CPVConsensus {
         }
     }
 
+    // ✅ SECURITY: Stake at risk for a registered validator of any type
+    // (creative and community validators have no stake: slashing only hits their reputation)
+    pub fn validator_stake(&self, address: &str) -> Option<u64> {
+        if let Some(validator) = self.economic_validators.get(address) {
+            return Some(validator.stake);
+        }
+        if self.creative_validators.contains_key(address) || self.community_validators.contains_key(address) {
+            return Some(0);
+        }
+        None
+    }
+
+    // Delegations count in the economic score; the bond used for fork choice and
+    // finality weight stays the validator's own stake
+    pub fn set_delegated_stake(&mut self, address: &str, delegated_stake: u64) -> Result<(), String> {
+        let validator = self
+            .economic_validators
+            .get_mut(address)
+            .ok_or_else(|| format!("Economic validator {} not found", address))?;
+        validator.delegated_stake = delegated_stake;
+        Ok(())
+    }
+
     // ✅ SECURITY FIX #3: Slashing mechanism
     pub async fn slash_validator(
         &mut self,
@@ -685,6 +720,11 @@ impl CPVConsensus {
         block_height: Option<u64>,
         transaction_hash: Option<String>,
     ) -> Result<(), String> {
+        // Keep the in-memory stake (proposer / fork choice / finality weight) in line with the DB
+        if let Some(validator) = self.economic_validators.get_mut(address) {
+            validator.stake = validator.stake.saturating_sub(slash_amount);
+        }
+
         if let Some(ref pool) = self.db_pool {
             // Record slashing event in database
             let timestamp = SystemTime::now()
@@ -863,21 +903,8 @@ impl CPVConsensus {
         }
     }
 
-    // Select validator using CPV with VRF for secure randomness
-    pub fn select_validator(&mut self) -> Result<CPVValidator, String> {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-
-        // Check cooldown period to prevent rapid successive selections
-        if now - self.last_selection_timestamp < self.selection_cooldown {
-            return Err(format!(
-                "Selection cooldown active. Wait {} more seconds",
-                self.selection_cooldown - (now - self.last_selection_timestamp)
-            ));
-        }
-
+    // Active validators of all three types with their CPV weighted score
+    fn active_validators(&self) -> Vec<CPVValidator> {
         let mut all_validators = Vec::new();
 
         // Add economic validators
@@ -925,6 +952,76 @@ impl CPVConsensus {
             }
         }
 
+        all_validators
+    }
+
+    // ✅ CPV: Proposer weights used by the fork choice (same integer weights as proposer selection)
+    pub fn proposer_weights(&self) -> HashMap<String, u64> {
+        self.active_validators()
+            .into_iter()
+            .map(|v| (v.address, (v.total_score * 1000.0) as u64))
+            .collect()
+    }
+
+    // ✅ CPV: Slot lottery for block production. Every validator hosted by this
+    // node evaluates the slot VRF with its own key; the first one whose output falls
+    // under its share of the CPV weight proposes, and its VRF result goes into the
+    // block so peers can check the win against the key bound to its account.
+    pub fn select_proposer(
+        &mut self,
+        keyring: &ProposerKeyring,
+        slot_seed: &[u8],
+    ) -> Result<(CPVValidator, VRFResult), String> {
+        let all_validators = self.active_validators();
+        if all_validators.is_empty() {
+            return Err("No hay validadores activos".to_string());
+        }
+        let weights = self.proposer_weights();
+
+        for (address, key) in keyring.validators() {
+            let vrf_result = proposer::prove_slot(key, slot_seed);
+            if !proposer::is_eligible(&weights, address, &vrf_result.output) {
+                continue;
+            }
+            let Some(selected) = all_validators.iter().find(|v| v.address == address).cloned() else {
+                continue;
+            };
+
+            self.last_selection_timestamp = vrf_result.timestamp;
+            info!(
+                "Slot proposer selected via VRF: {} (type: {:?}, score: {})",
+                selected.address,
+                selected.validator_type,
+                selected.total_score
+            );
+            return Ok((selected, vrf_result));
+        }
+
+        Err("Ningún validador de este nodo ganó el slot".to_string())
+    }
+
+    // Without active validators the bootstrap node proposes
+    pub fn has_active_validators(&self) -> bool {
+        !self.active_validators().is_empty()
+    }
+
+    // Select validator using CPV with VRF for secure randomness
+    pub fn select_validator(&mut self) -> Result<CPVValidator, String> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        // Check cooldown period to prevent rapid successive selections
+        if now - self.last_selection_timestamp < self.selection_cooldown {
+            return Err(format!(
+                "Selection cooldown active. Wait {} more seconds",
+                self.selection_cooldown - (now - self.last_selection_timestamp)
+            ));
+        }
+
+        let all_validators = self.active_validators();
+
         if all_validators.is_empty() {
             return Err("No hay validadores activos".to_string());
         }
@@ -962,7 +1059,7 @@ impl CPVConsensus {
 
     // Calculate economic score total
     fn calculate_economic_score(&self, validator: &EconomicValidator) -> f64 {
-        let stake_score = (validator.stake as f64 / self.minimum_stake as f64) * 100.0;
+        let stake_score = (validator.effective_stake() as f64 / self.minimum_stake as f64) * 100.0;
         let activity_score = validator.economic_activity;
         let validation_score = validator.validation_count as f64 * 2.0;
 
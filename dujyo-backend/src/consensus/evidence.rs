@@ -0,0 +1,307 @@
+//! Double-Signing Evidence for CPV
+//!
+//! A validator that signs two different blocks (as proposer) or two different
+//! attestations at the same height has equivocated. Evidence carries both signed
+//! messages and the offender's public key, so anyone can verify it without
+//! trusting the submitter; the caller checks that the key is the one registered
+//! for the offender before slashing through `CPVConsensus::slash_validator`.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::blockchain::blockchain::{Block, BlockHeader};
+use crate::blockchain::fork_choice::MAX_REORG_DEPTH;
+use crate::blockchain::signed_transaction::{decode_public_key, push_field};
+use crate::consensus::finality::{Attestation, FinalityError};
+use crate::consensus::proposer::verify_block_signature;
+
+/// Share of an economic validator's stake burned for double signing (basis points)
+pub const DOUBLE_SIGN_SLASH_BPS: u64 = 500;
+
+/// Domain separator for evidence ids
+const EVIDENCE_DOMAIN: &[u8] = b"DUJYO_DOUBLE_SIGN_V1";
+
+/// Two conflicting signed messages from the same validator at the same height
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DoubleSignEvidence {
+    /// Two block headers sealed by the same proposer
+    Block {
+        public_key: String,
+        header_a: BlockHeader,
+        signature_a: String,
+        header_b: BlockHeader,
+        signature_b: String,
+    },
+    /// Two attestations for different blocks
+    Attestation { first: Attestation, second: Attestation },
+}
+
+/// Errors raised while verifying evidence
+#[derive(Debug, Clone, PartialEq)]
+pub enum EvidenceError {
+    /// The two messages do not conflict (different signer or height, or the same block)
+    NotConflicting(String),
+    InvalidSignature,
+    WrongChainId(String),
+}
+
+impl fmt::Display for EvidenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvidenceError::NotConflicting(reason) => write!(f, "Not double signing: {}", reason),
+            EvidenceError::InvalidSignature => write!(f, "Invalid signature in evidence"),
+            EvidenceError::WrongChainId(chain_id) => write!(f, "Evidence for chain {}", chain_id),
+        }
+    }
+}
+
+impl std::error::Error for EvidenceError {}
+
+impl DoubleSignEvidence {
+    /// Evidence from two sealed blocks (None if they do not conflict or are unsigned)
+    pub fn from_blocks(public_key: &str, a: &Block, b: &Block) -> Option<Self> {
+        let evidence = DoubleSignEvidence::Block {
+            public_key: public_key.to_lowercase(),
+            header_a: a.header(),
+            signature_a: a.proposer_signature.clone()?,
+            header_b: b.header(),
+            signature_b: b.proposer_signature.clone()?,
+        };
+        Some(evidence)
+    }
+
+    pub fn offender(&self) -> &str {
+        match self {
+            DoubleSignEvidence::Block { header_a, .. } => header_a.proposer.as_deref().unwrap_or_default(),
+            DoubleSignEvidence::Attestation { first, .. } => &first.validator,
+        }
+    }
+
+    pub fn height(&self) -> u64 {
+        match self {
+            DoubleSignEvidence::Block { header_a, .. } => header_a.height,
+            DoubleSignEvidence::Attestation { first, .. } => first.height,
+        }
+    }
+
+    /// Key that signed both messages (must be the offender's registered key)
+    pub fn public_key(&self) -> &str {
+        match self {
+            DoubleSignEvidence::Block { public_key, .. } => public_key,
+            DoubleSignEvidence::Attestation { first, .. } => &first.public_key,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            DoubleSignEvidence::Block { .. } => "block",
+            DoubleSignEvidence::Attestation { .. } => "attestation",
+        }
+    }
+
+    /// The two conflicting block hashes, sorted
+    fn conflicting_hashes(&self) -> (String, String) {
+        let (a, b) = match self {
+            DoubleSignEvidence::Block { header_a, header_b, .. } => (header_a.hash(), header_b.hash()),
+            DoubleSignEvidence::Attestation { first, second } => (first.block_hash.clone(), second.block_hash.clone()),
+        };
+        if a <= b {
+            (a, b)
+        } else {
+            (b, a)
+        }
+    }
+
+    /// Stable id of the offence: the same two messages in either order give the same id
+    pub fn id(&self) -> String {
+        let (low, high) = self.conflicting_hashes();
+        let mut payload = Vec::with_capacity(192);
+        payload.extend_from_slice(EVIDENCE_DOMAIN);
+        push_field(&mut payload, self.kind().as_bytes());
+        push_field(&mut payload, self.offender().as_bytes());
+        payload.extend_from_slice(&self.height().to_be_bytes());
+        push_field(&mut payload, low.as_bytes());
+        push_field(&mut payload, high.as_bytes());
+        hex::encode(Sha256::digest(&payload))
+    }
+
+    /// Check that both messages are validly signed by `public_key()` and conflict
+    pub fn verify(&self, expected_chain_id: &str) -> Result<(), EvidenceError> {
+        match self {
+            DoubleSignEvidence::Block {
+                public_key,
+                header_a,
+                signature_a,
+                header_b,
+                signature_b,
+            } => {
+                if header_a.height != header_b.height {
+                    return Err(EvidenceError::NotConflicting("different heights".to_string()));
+                }
+                if header_a.proposer.is_none() || header_a.proposer != header_b.proposer {
+                    return Err(EvidenceError::NotConflicting("different proposers".to_string()));
+                }
+                let (hash_a, hash_b) = (header_a.hash(), header_b.hash());
+                if hash_a == hash_b {
+                    return Err(EvidenceError::NotConflicting("same block".to_string()));
+                }
+                let key = decode_public_key(public_key).map_err(|_| EvidenceError::InvalidSignature)?;
+                if !verify_block_signature(&key, &hash_a, signature_a) || !verify_block_signature(&key, &hash_b, signature_b) {
+                    return Err(EvidenceError::InvalidSignature);
+                }
+                Ok(())
+            }
+            DoubleSignEvidence::Attestation { first, second } => {
+                if first.validator != second.validator || first.public_key.to_lowercase() != second.public_key.to_lowercase() {
+                    return Err(EvidenceError::NotConflicting("different validators".to_string()));
+                }
+                if first.height != second.height {
+                    return Err(EvidenceError::NotConflicting("different heights".to_string()));
+                }
+                if first.block_hash == second.block_hash {
+                    return Err(EvidenceError::NotConflicting("same block".to_string()));
+                }
+                for attestation in [first, second] {
+                    attestation.verify(expected_chain_id).map_err(|e| match e {
+                        FinalityError::WrongChainId(chain_id) => EvidenceError::WrongChainId(chain_id),
+                        _ => EvidenceError::InvalidSignature,
+                    })?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Signed blocks seen per (proposer, height) and evidence already handled
+#[derive(Default)]
+pub struct EvidencePool {
+    seen_blocks: HashMap<(String, u64), Block>,
+    processed: HashSet<String>,
+}
+
+impl EvidencePool {
+    pub fn new() -> Self {
+        EvidencePool::default()
+    }
+
+    /// Track a block signed by its proposer; returns evidence if the proposer already
+    /// sealed a different block at the same height. `public_key` is the proposer's
+    /// registered key: blocks whose signature does not verify under it are ignored.
+    pub fn observe_block(&mut self, block: &Block, public_key: &str) -> Option<DoubleSignEvidence> {
+        let proposer = block.validator.clone()?;
+        let signature = block.proposer_signature.as_deref()?;
+        let key = decode_public_key(public_key).ok()?;
+        if block.hash != block.calculate_hash() || !verify_block_signature(&key, &block.hash, signature) {
+            return None;
+        }
+
+        // Only heights that can still be reorganized are worth remembering
+        self.seen_blocks
+            .retain(|(_, height), _| height.saturating_add(MAX_REORG_DEPTH) >= block.height);
+
+        match self.seen_blocks.get(&(proposer.clone(), block.height)) {
+            Some(first) if first.hash != block.hash => DoubleSignEvidence::from_blocks(public_key, first, block),
+            Some(_) => None,
+            None => {
+                self.seen_blocks.insert((proposer, block.height), block.clone());
+                None
+            }
+        }
+    }
+
+    /// Whether evidence with this id was already handled (slashing happens once)
+    pub fn is_processed(&self, evidence_id: &str) -> bool {
+        self.processed.contains(evidence_id)
+    }
+
+    pub fn mark_processed(&mut self, evidence_id: String) {
+        self.processed.insert(evidence_id);
+    }
+}
+
+/// Amount slashed for double signing from a validator's stake
+pub fn double_sign_slash_amount(stake: u64) -> u64 {
+    (stake as u128 * DOUBLE_SIGN_SLASH_BPS as u128 / 10_000) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::signed_transaction::DEFAULT_CHAIN_ID;
+    use ed25519_dalek::SigningKey;
+
+    fn key() -> SigningKey {
+        SigningKey::from_bytes(&[5u8; 32])
+    }
+
+    fn public_key_hex() -> String {
+        hex::encode(key().verifying_key().to_bytes())
+    }
+
+    fn sealed(height: u64, timestamp: u64) -> Block {
+        let mut block = Block::new(height, timestamp, vec![], "parent".to_string(), "state".to_string(), Some("DUvalidator".to_string()));
+        block.seal(None, &key());
+        block
+    }
+
+    #[test]
+    fn test_two_blocks_at_same_height_produce_evidence() {
+        let mut pool = EvidencePool::new();
+        assert!(pool.observe_block(&sealed(7, 100), &public_key_hex()).is_none());
+        assert!(pool.observe_block(&sealed(7, 100), &public_key_hex()).is_none());
+
+        let evidence = pool.observe_block(&sealed(7, 101), &public_key_hex()).unwrap();
+        assert_eq!(evidence.offender(), "DUvalidator");
+        assert_eq!(evidence.height(), 7);
+        assert!(evidence.verify(DEFAULT_CHAIN_ID).is_ok());
+
+        // Evidence survives a JSON roundtrip (API / gossip format)
+        let decoded: DoubleSignEvidence = serde_json::from_str(&serde_json::to_string(&evidence).unwrap()).unwrap();
+        assert_eq!(decoded.id(), evidence.id());
+    }
+
+    #[test]
+    fn test_evidence_id_is_order_independent() {
+        let (a, b) = (sealed(3, 10), sealed(3, 11));
+        let ab = DoubleSignEvidence::from_blocks(&public_key_hex(), &a, &b).unwrap();
+        let ba = DoubleSignEvidence::from_blocks(&public_key_hex(), &b, &a).unwrap();
+        assert_eq!(ab.id(), ba.id());
+    }
+
+    #[test]
+    fn test_forged_or_non_conflicting_evidence_rejected() {
+        let (a, b) = (sealed(3, 10), sealed(3, 11));
+        let other_key = hex::encode(SigningKey::from_bytes(&[6u8; 32]).verifying_key().to_bytes());
+        let forged = DoubleSignEvidence::from_blocks(&other_key, &a, &b).unwrap();
+        assert_eq!(forged.verify(DEFAULT_CHAIN_ID), Err(EvidenceError::InvalidSignature));
+
+        let same = DoubleSignEvidence::from_blocks(&public_key_hex(), &a, &a).unwrap();
+        assert!(matches!(same.verify(DEFAULT_CHAIN_ID), Err(EvidenceError::NotConflicting(_))));
+
+        let later = sealed(4, 10);
+        let heights = DoubleSignEvidence::from_blocks(&public_key_hex(), &a, &later).unwrap();
+        assert!(matches!(heights.verify(DEFAULT_CHAIN_ID), Err(EvidenceError::NotConflicting(_))));
+    }
+
+    #[test]
+    fn test_conflicting_attestations_are_evidence() {
+        let first = Attestation::sign("DUvalidator", 9, &"aa".repeat(32), DEFAULT_CHAIN_ID, &key());
+        let second = Attestation::sign("DUvalidator", 9, &"bb".repeat(32), DEFAULT_CHAIN_ID, &key());
+        let evidence = DoubleSignEvidence::Attestation { first: first.clone(), second };
+        assert!(evidence.verify(DEFAULT_CHAIN_ID).is_ok());
+        assert_eq!(evidence.public_key(), public_key_hex());
+
+        let duplicate = DoubleSignEvidence::Attestation { first: first.clone(), second: first };
+        assert!(matches!(duplicate.verify(DEFAULT_CHAIN_ID), Err(EvidenceError::NotConflicting(_))));
+    }
+
+    #[test]
+    fn test_slash_amount() {
+        assert_eq!(double_sign_slash_amount(10_000), 500);
+        assert_eq!(double_sign_slash_amount(0), 0);
+    }
+}
@@ -0,0 +1,427 @@
+//! Finality Gadget for CPV
+//!
+//! Validators registered in `CPVConsensus` (economic, creative, community) sign
+//! attestations over canonical block hashes. A block is final once validators
+//! holding at least `threshold_bps` of the total CPV weight attested it;
+//! finalizing a block finalizes all its ancestors, and the fork choice never
+//! reorganizes below the finalized height.
+//!
+//! While no CPV validator is active the node key ("system") is the only voter,
+//! so a bootstrap network still finalizes the blocks it produces.
+
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use tracing::{info, warn};
+
+use crate::blockchain::blockchain::Blockchain;
+use crate::blockchain::fork_choice::ProposerWeights;
+use crate::blockchain::signed_transaction::{decode_public_key, push_field};
+use crate::consensus::proposer::{ProposerKeyring, SYSTEM_PROPOSER};
+
+/// Default supermajority: 2/3 of the CPV weight (basis points)
+pub const DEFAULT_FINALITY_THRESHOLD_BPS: u64 = 6_667;
+
+/// Domain separator for attestation signatures
+const ATTESTATION_DOMAIN: &[u8] = b"DUJYO_ATTESTATION_V1";
+
+/// A validator's vote for a canonical block
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attestation {
+    pub validator: String,
+    pub height: u64,
+    pub block_hash: String,
+    pub chain_id: String,
+    pub public_key: String, // hex ed25519 key (bound to `validator` in account_keys)
+    pub signature: String,  // hex ed25519 signature over `signing_payload`
+}
+
+impl Attestation {
+    /// Canonical bytes covered by the signature
+    pub fn signing_payload(validator: &str, height: u64, block_hash: &str, chain_id: &str) -> Vec<u8> {
+        let mut payload = Vec::with_capacity(160);
+        payload.extend_from_slice(ATTESTATION_DOMAIN);
+        push_field(&mut payload, chain_id.as_bytes());
+        push_field(&mut payload, validator.as_bytes());
+        payload.extend_from_slice(&height.to_be_bytes());
+        push_field(&mut payload, block_hash.as_bytes());
+        payload
+    }
+
+    pub fn sign(validator: &str, height: u64, block_hash: &str, chain_id: &str, key: &SigningKey) -> Self {
+        let payload = Attestation::signing_payload(validator, height, block_hash, chain_id);
+        Attestation {
+            validator: validator.to_string(),
+            height,
+            block_hash: block_hash.to_string(),
+            chain_id: chain_id.to_string(),
+            public_key: hex::encode(key.verifying_key().to_bytes()),
+            signature: hex::encode(key.sign(&payload).to_bytes()),
+        }
+    }
+
+    /// Check chain id and signature (the key-to-validator binding is checked by the caller)
+    pub fn verify(&self, expected_chain_id: &str) -> Result<(), FinalityError> {
+        if self.chain_id != expected_chain_id {
+            return Err(FinalityError::WrongChainId(self.chain_id.clone()));
+        }
+        let public_key = decode_public_key(&self.public_key).map_err(|_| FinalityError::InvalidSignature)?;
+        let signature = hex::decode(&self.signature)
+            .ok()
+            .and_then(|bytes| <[u8; 64]>::try_from(bytes.as_slice()).ok())
+            .map(|bytes| Signature::from_bytes(&bytes))
+            .ok_or(FinalityError::InvalidSignature)?;
+        let payload = Attestation::signing_payload(&self.validator, self.height, &self.block_hash, &self.chain_id);
+        public_key
+            .verify(&payload, &signature)
+            .map_err(|_| FinalityError::InvalidSignature)
+    }
+}
+
+/// Newly finalized block
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FinalizedCheckpoint {
+    pub height: u64,
+    pub block_hash: String,
+}
+
+/// Errors raised while handling attestations
+#[derive(Debug, Clone, PartialEq)]
+pub enum FinalityError {
+    WrongChainId(String),
+    InvalidSignature,
+    /// The signer is not an active CPV validator
+    UnknownValidator(String),
+    /// Same validator attested two different blocks at the same height
+    Equivocation {
+        validator: String,
+        height: u64,
+        first_hash: String,
+        second_hash: String,
+    },
+}
+
+impl fmt::Display for FinalityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FinalityError::WrongChainId(chain_id) => write!(f, "Attestation for chain {}", chain_id),
+            FinalityError::InvalidSignature => write!(f, "Invalid attestation signature"),
+            FinalityError::UnknownValidator(validator) => write!(f, "{} is not an active validator", validator),
+            FinalityError::Equivocation { validator, height, .. } => {
+                write!(f, "{} attested two blocks at height {}", validator, height)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FinalityError {}
+
+/// Attestation pool and supermajority rule
+pub struct FinalityGadget {
+    chain_id: String,
+    threshold_bps: u64,
+    attestations: HashMap<String, HashMap<String, Attestation>>, // block hash -> validator -> vote
+    votes: HashMap<(String, u64), String>,                         // (validator, height) -> block hash
+}
+
+impl FinalityGadget {
+    pub fn new(chain_id: String, threshold_bps: u64) -> Result<Self, String> {
+        // A minority must never be able to finalize two conflicting blocks
+        if threshold_bps <= 5_000 || threshold_bps > 10_000 {
+            return Err(format!("Finality threshold must be in (5000, 10000] bps, got {}", threshold_bps));
+        }
+        Ok(FinalityGadget {
+            chain_id,
+            threshold_bps,
+            attestations: HashMap::new(),
+            votes: HashMap::new(),
+        })
+    }
+
+    /// Threshold from DUJYO_FINALITY_THRESHOLD_BPS (default 2/3)
+    pub fn from_env(chain_id: String) -> Self {
+        let threshold_bps = std::env::var("DUJYO_FINALITY_THRESHOLD_BPS")
+            .ok()
+            .and_then(|value| value.trim().parse().ok())
+            .unwrap_or(DEFAULT_FINALITY_THRESHOLD_BPS);
+        FinalityGadget::new(chain_id.clone(), threshold_bps).unwrap_or_else(|e| {
+            warn!("{}, using default", e);
+            FinalityGadget {
+                chain_id,
+                threshold_bps: DEFAULT_FINALITY_THRESHOLD_BPS,
+                attestations: HashMap::new(),
+                votes: HashMap::new(),
+            }
+        })
+    }
+
+    pub fn threshold_bps(&self) -> u64 {
+        self.threshold_bps
+    }
+
+    /// Voters and their weight: active CPV validators, or the node key while there are none
+    pub fn voting_weights(weights: &ProposerWeights) -> ProposerWeights {
+        if weights.is_empty() {
+            return ProposerWeights::from([(SYSTEM_PROPOSER.to_string(), 1)]);
+        }
+        weights.clone()
+    }
+
+    /// Record a verified attestation. Returns false if it was already known or is
+    /// at or below the finalized height (nothing to relay).
+    pub fn add_attestation(
+        &mut self,
+        attestation: Attestation,
+        weights: &ProposerWeights,
+        blockchain: &Blockchain,
+    ) -> Result<bool, FinalityError> {
+        attestation.verify(&self.chain_id)?;
+        if !FinalityGadget::voting_weights(weights).contains_key(&attestation.validator) {
+            return Err(FinalityError::UnknownValidator(attestation.validator));
+        }
+        if attestation.height <= blockchain.finalized_height {
+            return Ok(false);
+        }
+
+        let vote = (attestation.validator.clone(), attestation.height);
+        if let Some(first_hash) = self.votes.get(&vote) {
+            if *first_hash == attestation.block_hash {
+                return Ok(false);
+            }
+            return Err(FinalityError::Equivocation {
+                validator: attestation.validator,
+                height: attestation.height,
+                first_hash: first_hash.clone(),
+                second_hash: attestation.block_hash,
+            });
+        }
+
+        self.votes.insert(vote, attestation.block_hash.clone());
+        self.attestations
+            .entry(attestation.block_hash.clone())
+            .or_default()
+            .insert(attestation.validator.clone(), attestation);
+        Ok(true)
+    }
+
+    /// CPV weight that attested a block
+    pub fn attested_weight(&self, block_hash: &str, weights: &ProposerWeights) -> u64 {
+        let voters = FinalityGadget::voting_weights(weights);
+        self.attestations
+            .get(block_hash)
+            .map(|votes| {
+                votes
+                    .keys()
+                    .filter_map(|validator| voters.get(validator))
+                    .fold(0u64, |total, weight| total.saturating_add(*weight))
+            })
+            .unwrap_or(0)
+    }
+
+    /// Vote already recorded from `validator` for `block_hash` (used to build equivocation evidence)
+    pub fn attestation(&self, block_hash: &str, validator: &str) -> Option<&Attestation> {
+        self.attestations.get(block_hash)?.get(validator)
+    }
+
+    fn is_supermajority(&self, attested: u64, total: u64) -> bool {
+        total > 0 && attested as u128 * 10_000 >= total as u128 * self.threshold_bps as u128
+    }
+
+    /// Finalize the highest canonical block with a supermajority (and, implicitly, its ancestors)
+    pub fn try_finalize(&mut self, blockchain: &mut Blockchain, weights: &ProposerWeights) -> Option<FinalizedCheckpoint> {
+        let total: u64 = FinalityGadget::voting_weights(weights)
+            .values()
+            .fold(0u64, |total, weight| total.saturating_add(*weight));
+
+        let checkpoint = blockchain
+            .chain
+            .iter()
+            .rev()
+            .take_while(|block| block.height > blockchain.finalized_height)
+            .find(|block| self.is_supermajority(self.attested_weight(&block.hash, weights), total))
+            .map(|block| FinalizedCheckpoint {
+                height: block.height,
+                block_hash: block.hash.clone(),
+            })?;
+
+        if let Err(e) = blockchain.finalize(checkpoint.height, &checkpoint.block_hash) {
+            warn!(error = %e, "Could not finalize block");
+            return None;
+        }
+        info!(height = checkpoint.height, hash = %checkpoint.block_hash, "Block finalized");
+
+        // Votes at or below the finalized height can no longer change anything
+        let finalized_height = checkpoint.height;
+        self.votes.retain(|(_, height), _| *height > finalized_height);
+        self.attestations
+            .retain(|_, votes| votes.values().any(|vote| vote.height > finalized_height));
+        Some(checkpoint)
+    }
+
+    /// Attestations for the current head by the voters whose keys this node hosts
+    pub fn attest_head(
+        &self,
+        keyring: &ProposerKeyring,
+        weights: &ProposerWeights,
+        blockchain: &Blockchain,
+    ) -> Vec<Attestation> {
+        let head = blockchain.get_latest_block();
+        if head.height <= blockchain.finalized_height {
+            return Vec::new();
+        }
+        let mut voters: Vec<String> = FinalityGadget::voting_weights(weights).into_keys().collect();
+        voters.sort();
+        voters
+            .into_iter()
+            .filter_map(|validator| {
+                keyring
+                    .signing_key(&validator)
+                    .map(|key| Attestation::sign(&validator, head.height, &head.hash, &self.chain_id, key))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::block_verifier::BlockVerifier;
+    use crate::blockchain::blockchain::Block;
+    use crate::blockchain::signed_transaction::DEFAULT_CHAIN_ID;
+    use crate::consensus::proposer;
+
+    fn key(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    fn chain_with_blocks(count: u64) -> Blockchain {
+        let mut blockchain = Blockchain::new();
+        for _ in 0..count {
+            let head = blockchain.get_latest_block().clone();
+            let block = Block::new(
+                head.height + 1,
+                head.timestamp + 10,
+                vec![],
+                head.hash,
+                blockchain.state_root(),
+                Some("system".to_string()),
+            );
+            blockchain.chain.push(block);
+        }
+        blockchain
+    }
+
+    fn weights() -> ProposerWeights {
+        ProposerWeights::from([
+            ("DUeconomic".to_string(), 40),
+            ("DUcreative".to_string(), 35),
+            ("DUcommunity".to_string(), 25),
+        ])
+    }
+
+    fn attest(validator: &str, seed: u8, blockchain: &Blockchain, height: u64) -> Attestation {
+        let block = blockchain.get_block(height).unwrap();
+        Attestation::sign(validator, height, &block.hash, DEFAULT_CHAIN_ID, &key(seed))
+    }
+
+    #[test]
+    fn test_attestation_signature_roundtrip() {
+        let blockchain = chain_with_blocks(1);
+        let mut attestation = attest("DUeconomic", 1, &blockchain, 1);
+        assert!(attestation.verify(DEFAULT_CHAIN_ID).is_ok());
+        assert!(matches!(attestation.verify("dujyo-testnet-1"), Err(FinalityError::WrongChainId(_))));
+
+        attestation.height = 2;
+        assert_eq!(attestation.verify(DEFAULT_CHAIN_ID), Err(FinalityError::InvalidSignature));
+    }
+
+    #[test]
+    fn test_supermajority_finalizes_block_and_ancestors() {
+        let mut blockchain = chain_with_blocks(3);
+        let mut gadget = FinalityGadget::new(DEFAULT_CHAIN_ID.to_string(), DEFAULT_FINALITY_THRESHOLD_BPS).unwrap();
+        let weights = weights();
+
+        // 40 + 25 = 65% < 66.67%
+        gadget.add_attestation(attest("DUeconomic", 1, &blockchain, 3), &weights, &blockchain).unwrap();
+        gadget.add_attestation(attest("DUcommunity", 3, &blockchain, 3), &weights, &blockchain).unwrap();
+        assert_eq!(gadget.try_finalize(&mut blockchain, &weights), None);
+        assert_eq!(blockchain.finalized_height, 0);
+
+        // + 35 = 100%
+        gadget.add_attestation(attest("DUcreative", 2, &blockchain, 3), &weights, &blockchain).unwrap();
+        let checkpoint = gadget.try_finalize(&mut blockchain, &weights).unwrap();
+        assert_eq!(checkpoint.height, 3);
+        assert_eq!(blockchain.finalized_height, 3);
+        assert_eq!(blockchain.finalized_hash, blockchain.get_latest_block().hash);
+
+        // Old votes are now stale
+        let stale = attest("DUeconomic", 1, &blockchain, 2);
+        assert_eq!(gadget.add_attestation(stale, &weights, &blockchain), Ok(false));
+    }
+
+    #[test]
+    fn test_equivocation_and_unknown_validator_rejected() {
+        let blockchain = chain_with_blocks(2);
+        let mut gadget = FinalityGadget::new(DEFAULT_CHAIN_ID.to_string(), DEFAULT_FINALITY_THRESHOLD_BPS).unwrap();
+        let weights = weights();
+
+        let first = attest("DUeconomic", 1, &blockchain, 2);
+        assert_eq!(gadget.add_attestation(first.clone(), &weights, &blockchain), Ok(true));
+        assert_eq!(gadget.add_attestation(first, &weights, &blockchain), Ok(false));
+
+        let conflicting = Attestation::sign("DUeconomic", 2, "ff".repeat(32).as_str(), DEFAULT_CHAIN_ID, &key(1));
+        assert!(matches!(
+            gadget.add_attestation(conflicting, &weights, &blockchain),
+            Err(FinalityError::Equivocation { height: 2, .. })
+        ));
+
+        let outsider = attest("DUnobody", 9, &blockchain, 2);
+        assert!(matches!(
+            gadget.add_attestation(outsider, &weights, &blockchain),
+            Err(FinalityError::UnknownValidator(_))
+        ));
+    }
+
+    #[test]
+    fn test_bootstrap_node_key_finalizes_alone() {
+        let mut blockchain = chain_with_blocks(2);
+        let mut gadget = FinalityGadget::new(DEFAULT_CHAIN_ID.to_string(), DEFAULT_FINALITY_THRESHOLD_BPS).unwrap();
+        let mut keyring = ProposerKeyring::new();
+        keyring.insert(SYSTEM_PROPOSER.to_string(), key(7));
+        let no_validators = ProposerWeights::new();
+
+        let votes = gadget.attest_head(&keyring, &no_validators, &blockchain);
+        assert_eq!(votes.len(), 1);
+        for vote in votes {
+            gadget.add_attestation(vote, &no_validators, &blockchain).unwrap();
+        }
+        assert_eq!(gadget.try_finalize(&mut blockchain, &no_validators).map(|c| c.height), Some(2));
+    }
+
+    #[test]
+    fn test_fork_choice_never_reverts_finalized_blocks() {
+        let mut blockchain = chain_with_blocks(2);
+        let genesis = blockchain.chain[0].clone();
+        let finalized = blockchain.get_block(1).unwrap().hash.clone();
+        blockchain.finalize(1, &finalized).unwrap();
+
+        // Heavier competing block at height 1 (forks below the finalized block), properly sealed
+        let mut rival = Block::new(1, genesis.timestamp + 10, vec![], genesis.hash.clone(), blockchain.state_root(), Some("DUeconomic".to_string()));
+        let seed = proposer::slot_seed(1, &genesis.hash, 1);
+        rival.seal(Some(proposer::prove_slot(&key(1), &seed)), &key(1));
+        let verifier = BlockVerifier::new(DEFAULT_CHAIN_ID.to_string(), ProposerWeights::from([("DUeconomic".to_string(), 40)]))
+            .with_key("DUeconomic", &hex::encode(key(1).verifying_key().to_bytes()));
+        let rejected = blockchain.accept_block(rival, &verifier).unwrap_err();
+        assert!(rejected.contains("finalizado"), "{}", rejected);
+        assert_eq!(blockchain.finalized_height, 1);
+        assert_eq!(blockchain.get_latest_block().height, 2);
+    }
+
+    #[test]
+    fn test_threshold_must_be_supermajority() {
+        assert!(FinalityGadget::new(DEFAULT_CHAIN_ID.to_string(), 5_000).is_err());
+        assert!(FinalityGadget::new(DEFAULT_CHAIN_ID.to_string(), 10_001).is_err());
+        assert!(FinalityGadget::new(DEFAULT_CHAIN_ID.to_string(), 10_000).is_ok());
+    }
+}
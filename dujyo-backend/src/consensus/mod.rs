@@ -1 +1,4 @@
 pub mod cpv;
+pub mod proposer;
+pub mod finality;
+pub mod evidence;
@@ -0,0 +1,275 @@
+//! Block Proposer Keys for CPV Consensus
+//!
+//! The node holds the ed25519 signing keys of the validators it operates.
+//! When CPV selects one of them for a slot the node seals the block with that
+//! key; a selected validator whose key is not hosted here misses the slot.
+//!
+//! Keys are configured with `DUJYO_VALIDATOR_KEYS="ADDRESS:HEX_SEED,..."`; peers
+//! verify blocks against the key bound to the validator account (`/account/key`).
+//! `DUJYO_NODE_KEY` (hex seed) is the node's own key, used only while no CPV
+//! validator is active so that a fresh network can still produce blocks.
+//!
+//! Eligibility is a private lottery: a validator evaluates the slot VRF with
+//! its own key and may propose when the output falls under its share of the
+//! CPV weight. Any node can check the proof against the key bound to the
+//! proposer's account, so a block cannot claim a slot its proposer did not win.
+//! The slot VRF is the proposer's ed25519 signature over the slot seed
+//! (deterministic, RFC 8032) and its output is the SHA-256 of that signature.
+
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+use crate::utils::vrf::{VRFProof, VRFResult};
+
+/// Proposer recorded when no CPV validator is active (bootstrap only)
+pub const SYSTEM_PROPOSER: &str = "system";
+
+/// Seconds per lottery round: a height nobody won is retried in the next round
+pub const SLOT_DURATION_SECS: u64 = 10;
+
+/// Domain separator for the per-slot VRF input
+const SLOT_SEED_DOMAIN: &[u8] = b"DUJYO_CPV_SLOT_V1";
+/// Domain separator for the slot VRF output
+const SLOT_VRF_DOMAIN: &[u8] = b"DUJYO_CPV_SLOT_VRF_V1";
+
+/// Signing keys of the validators operated by this node
+pub struct ProposerKeyring {
+    keys: HashMap<String, SigningKey>,
+}
+
+impl ProposerKeyring {
+    pub fn new() -> Self {
+        ProposerKeyring { keys: HashMap::new() }
+    }
+
+    /// Load validator keys and the node key from the environment
+    pub fn from_env() -> Self {
+        let mut keyring = ProposerKeyring::new();
+
+        if let Ok(entries) = std::env::var("DUJYO_VALIDATOR_KEYS") {
+            for entry in entries.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+                match entry.split_once(':').map(|(address, seed)| (address, parse_seed(seed))) {
+                    Some((address, Ok(key))) => {
+                        info!("Loaded proposer key for validator {}", address);
+                        keyring.insert(address.to_string(), key);
+                    }
+                    _ => warn!("Ignoring malformed DUJYO_VALIDATOR_KEYS entry"),
+                }
+            }
+        }
+
+        let node_key = std::env::var("DUJYO_NODE_KEY")
+            .ok()
+            .and_then(|seed| parse_seed(&seed).ok())
+            .unwrap_or_else(|| {
+                warn!("DUJYO_NODE_KEY not set, generating ephemeral node key (DEVELOPMENT ONLY)");
+                SigningKey::from_bytes(&rand::random::<[u8; 32]>())
+            });
+        keyring.insert(SYSTEM_PROPOSER.to_string(), node_key);
+
+        keyring
+    }
+
+    pub fn insert(&mut self, address: String, key: SigningKey) {
+        self.keys.insert(address, key);
+    }
+
+    pub fn signing_key(&self, address: &str) -> Option<&SigningKey> {
+        self.keys.get(address)
+    }
+
+    /// Hosted proposers in address order (the bootstrap key excluded)
+    pub fn validators(&self) -> Vec<(&str, &SigningKey)> {
+        let mut validators: Vec<(&str, &SigningKey)> = self
+            .keys
+            .iter()
+            .filter(|(address, _)| address.as_str() != SYSTEM_PROPOSER)
+            .map(|(address, key)| (address.as_str(), key))
+            .collect();
+        validators.sort_by(|a, b| a.0.cmp(b.0));
+        validators
+    }
+
+    /// Hex public key of a hosted proposer
+    pub fn public_key_hex(&self, address: &str) -> Option<String> {
+        self.keys
+            .get(address)
+            .map(|key| hex::encode(key.verifying_key().to_bytes()))
+    }
+}
+
+impl Default for ProposerKeyring {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Public key (hex) of the bootstrap proposer: `DUJYO_BOOTSTRAP_PUBLIC_KEY` on
+/// nodes that sync from the bootstrap node, the local node key on the bootstrap node
+pub fn bootstrap_public_key(keyring: &ProposerKeyring) -> Option<String> {
+    std::env::var("DUJYO_BOOTSTRAP_PUBLIC_KEY")
+        .ok()
+        .map(|key| key.trim().to_lowercase())
+        .filter(|key| !key.is_empty())
+        .or_else(|| keyring.public_key_hex(SYSTEM_PROPOSER))
+}
+
+/// Lottery round of a block: whole slots elapsed since its parent
+pub fn slot_round(parent_timestamp: u64, timestamp: u64) -> u64 {
+    timestamp.saturating_sub(parent_timestamp) / SLOT_DURATION_SECS
+}
+
+/// VRF input for a slot: binds the selection to the chain position, parent and round
+pub fn slot_seed(height: u64, previous_hash: &str, round: u64) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update(SLOT_SEED_DOMAIN);
+    hasher.update(height.to_be_bytes());
+    hasher.update(previous_hash.as_bytes());
+    hasher.update(round.to_be_bytes());
+    hasher.finalize().to_vec()
+}
+
+/// Evaluate the slot VRF with a proposer key (the signature halves are the proof)
+pub fn prove_slot(key: &SigningKey, seed: &[u8]) -> VRFResult {
+    let signature = key.sign(&slot_message(seed)).to_bytes();
+    let (r, s) = signature.split_at(32);
+    let output = slot_output(&signature);
+    VRFResult {
+        output,
+        proof: VRFProof {
+            gamma: output,
+            c: r.try_into().expect("ed25519 signature is 64 bytes"),
+            s: s.try_into().expect("ed25519 signature is 64 bytes"),
+            alpha: seed.to_vec(),
+            public_key: key.verifying_key().to_bytes(),
+        },
+        timestamp: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+    }
+}
+
+/// Check a slot VRF: made by `public_key` over `seed`, with the output its proof implies
+pub fn verify_slot_vrf(public_key: &VerifyingKey, seed: &[u8], vrf: &VRFResult) -> bool {
+    if vrf.proof.alpha != seed || vrf.proof.public_key != public_key.to_bytes() {
+        return false;
+    }
+    let mut signature = [0u8; 64];
+    signature[..32].copy_from_slice(&vrf.proof.c);
+    signature[32..].copy_from_slice(&vrf.proof.s);
+    public_key
+        .verify_strict(&slot_message(seed), &Signature::from_bytes(&signature))
+        .is_ok()
+        && vrf.output == slot_output(&signature)
+        && vrf.proof.gamma == vrf.output
+}
+
+/// Did `address` win the slot with this VRF output? Each validator wins with
+/// probability weight / total weight, so one proposer per round is expected.
+pub fn is_eligible(weights: &HashMap<String, u64>, address: &str, output: &[u8; 32]) -> bool {
+    let weight = weights.get(address).copied().unwrap_or(0) as u128;
+    let total: u128 = weights.values().map(|weight| *weight as u128).sum();
+    if weight == 0 || total == 0 {
+        return false;
+    }
+    let mut prefix = [0u8; 8];
+    prefix.copy_from_slice(&output[..8]);
+    // output / 2^64 < weight / total
+    (u64::from_be_bytes(prefix) as u128) * total < weight << 64
+}
+
+fn slot_message(seed: &[u8]) -> Vec<u8> {
+    [SLOT_VRF_DOMAIN, seed].concat()
+}
+
+fn slot_output(signature: &[u8; 64]) -> [u8; 32] {
+    Sha256::digest(signature).into()
+}
+
+/// Sign a block hash (hex) with the proposer key
+pub fn sign_block_hash(key: &SigningKey, block_hash: &str) -> String {
+    hex::encode(key.sign(block_hash.as_bytes()).to_bytes())
+}
+
+/// Verify a proposer signature (hex) over a block hash (hex)
+pub fn verify_block_signature(public_key: &VerifyingKey, block_hash: &str, signature_hex: &str) -> bool {
+    let Ok(bytes) = hex::decode(signature_hex) else {
+        return false;
+    };
+    let Ok(bytes) = <[u8; 64]>::try_from(bytes.as_slice()) else {
+        return false;
+    };
+    public_key
+        .verify(block_hash.as_bytes(), &Signature::from_bytes(&bytes))
+        .is_ok()
+}
+
+fn parse_seed(seed_hex: &str) -> Result<SigningKey, String> {
+    let bytes = hex::decode(seed_hex.trim()).map_err(|e| format!("Invalid key hex: {}", e))?;
+    let seed: [u8; 32] = bytes
+        .as_slice()
+        .try_into()
+        .map_err(|_| "Signing key seed must be 32 bytes".to_string())?;
+    Ok(SigningKey::from_bytes(&seed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_signature_roundtrip() {
+        let key = SigningKey::from_bytes(&[3u8; 32]);
+        let signature = sign_block_hash(&key, "abc123");
+        assert!(verify_block_signature(&key.verifying_key(), "abc123", &signature));
+        assert!(!verify_block_signature(&key.verifying_key(), "abc124", &signature));
+        assert!(!verify_block_signature(&key.verifying_key(), "abc123", "zz"));
+    }
+
+    #[test]
+    fn test_slot_seed_changes_with_height_and_parent() {
+        assert_ne!(slot_seed(1, "parent", 0), slot_seed(2, "parent", 0));
+        assert_ne!(slot_seed(1, "parent", 0), slot_seed(1, "other", 0));
+        assert_ne!(slot_seed(1, "parent", 0), slot_seed(1, "parent", 1));
+    }
+
+    #[test]
+    fn test_slot_vrf_verifies_only_for_its_key_and_seed() {
+        let key = SigningKey::from_bytes(&[3u8; 32]);
+        let other = SigningKey::from_bytes(&[4u8; 32]);
+        let seed = slot_seed(5, "parent", 0);
+        let vrf = prove_slot(&key, &seed);
+        assert!(verify_slot_vrf(&key.verifying_key(), &seed, &vrf));
+        assert!(!verify_slot_vrf(&other.verifying_key(), &seed, &vrf));
+        assert!(!verify_slot_vrf(&key.verifying_key(), &slot_seed(5, "parent", 1), &vrf));
+
+        let mut forged = vrf.clone();
+        forged.output = [0u8; 32];
+        forged.proof.gamma = [0u8; 32];
+        assert!(!verify_slot_vrf(&key.verifying_key(), &seed, &forged));
+    }
+
+    #[test]
+    fn test_eligibility_follows_weight_share() {
+        let mut weights = HashMap::new();
+        weights.insert("DUa".to_string(), 1);
+        weights.insert("DUb".to_string(), 3);
+        let low = [0u8; 32];
+        let high = [0xffu8; 32];
+        assert!(is_eligible(&weights, "DUa", &low));
+        assert!(!is_eligible(&weights, "DUa", &high));
+        assert!(!is_eligible(&weights, "DUnobody", &low));
+
+        let mut mid = [0u8; 32];
+        mid[0] = 0x80; // output 1/2: under DUb's 3/4 share, over DUa's 1/4
+        assert!(is_eligible(&weights, "DUb", &mid));
+        assert!(!is_eligible(&weights, "DUa", &mid));
+    }
+
+    #[test]
+    fn test_parse_seed() {
+        assert!(parse_seed(&hex::encode([1u8; 32])).is_ok());
+        assert!(parse_seed("abcd").is_err());
+    }
+}
@@ -0,0 +1,320 @@
+//! Pool Analytics: OHLCV Candles, Volume, TVL, Fee APR and LP Returns
+//!
+//! Every DEX row saved with `save_dex_transaction` carries the pool state it
+//! left behind (reserves and LP supply) and, for swaps, the input token. The
+//! price of a pool is `reserve_b / reserve_a` after each row, so candles are
+//! built from those prices and volumes are the swapped amounts on each side.
+//!
+//! Storage aggregates rows per bucket; this module makes the series continuous
+//! (each candle opens at the previous close, empty buckets repeat it) and
+//! derives the 24h pool statistics. TVL, fees and LP value are expressed in
+//! `token_b` units.
+
+use serde::{Deserialize, Serialize};
+
+use crate::dex::Pool;
+use crate::utils::amount::Amount;
+
+/// Most candles returned by one query
+pub const MAX_CANDLES: u64 = 1_000;
+
+pub const DAY_SECS: u64 = 86_400;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CandleInterval {
+    #[serde(rename = "1m")]
+    OneMinute,
+    #[serde(rename = "5m")]
+    FiveMinutes,
+    #[serde(rename = "1h")]
+    OneHour,
+    #[serde(rename = "1d")]
+    OneDay,
+}
+
+impl CandleInterval {
+    pub const ALL: [CandleInterval; 4] = [
+        CandleInterval::OneMinute,
+        CandleInterval::FiveMinutes,
+        CandleInterval::OneHour,
+        CandleInterval::OneDay,
+    ];
+
+    pub fn secs(&self) -> u64 {
+        match self {
+            CandleInterval::OneMinute => 60,
+            CandleInterval::FiveMinutes => 300,
+            CandleInterval::OneHour => 3_600,
+            CandleInterval::OneDay => DAY_SECS,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CandleInterval::OneMinute => "1m",
+            CandleInterval::FiveMinutes => "5m",
+            CandleInterval::OneHour => "1h",
+            CandleInterval::OneDay => "1d",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|interval| interval.as_str() == value)
+    }
+
+    /// Start of the bucket containing `timestamp`
+    pub fn bucket_start(&self, timestamp: u64) -> u64 {
+        timestamp - timestamp % self.secs()
+    }
+}
+
+/// One OHLCV bucket; prices are `token_b` per `token_a`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Candle {
+    pub open_time: u64,
+    pub open: Amount,
+    pub high: Amount,
+    pub low: Amount,
+    pub close: Amount,
+    /// Swapped amounts on each side of the pool
+    pub volume_a: Amount,
+    pub volume_b: Amount,
+    pub trades: u64,
+}
+
+impl Candle {
+    /// Bucket without activity: flat at the previous close
+    pub fn flat(open_time: u64, price: Amount) -> Self {
+        Self {
+            open_time,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume_a: Amount::ZERO,
+            volume_b: Amount::ZERO,
+            trades: 0,
+        }
+    }
+}
+
+/// Swap totals of a pool over a period
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VolumeTotals {
+    /// Amounts paid in, per side (fees are charged on the input)
+    pub token_a_in: Amount,
+    pub token_b_in: Amount,
+    /// Both directions, per side
+    pub volume_a: Amount,
+    pub volume_b: Amount,
+    pub trades: u64,
+}
+
+/// Pool state recorded with a DEX row
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PoolState {
+    pub timestamp: u64,
+    pub reserve_a: Amount,
+    pub reserve_b: Amount,
+    pub total_liquidity: Amount,
+}
+
+impl PoolState {
+    pub fn of(pool: &Pool, timestamp: u64) -> Self {
+        Self {
+            timestamp,
+            reserve_a: pool.reserve_a,
+            reserve_b: pool.reserve_b,
+            total_liquidity: pool.total_liquidity,
+        }
+    }
+
+    pub fn price(&self) -> Amount {
+        self.reserve_b.checked_div(self.reserve_a).unwrap_or(Amount::ZERO)
+    }
+
+    /// Both sides valued in token_b (the reserves are balanced at the pool price)
+    pub fn tvl(&self) -> Amount {
+        self.reserve_b.saturating_add(self.reserve_b)
+    }
+
+    /// token_b value of one LP share
+    pub fn lp_share_value(&self) -> Amount {
+        self.tvl().checked_div(self.total_liquidity).unwrap_or(Amount::ZERO)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PoolStats {
+    pub pool_id: String,
+    pub token_a: String,
+    pub token_b: String,
+    pub price: Amount,
+    pub tvl: Amount,
+    pub volume_24h_a: Amount,
+    pub volume_24h_b: Amount,
+    pub trades_24h: u64,
+    pub fees_24h: Amount,
+    /// Annualized 24h fees over TVL (0.05 = 5%)
+    pub fee_apr: f64,
+    pub lp_share_value: Amount,
+    /// Change of the LP share value over 24h, fees and price moves included
+    pub lp_return_24h: Option<f64>,
+}
+
+/// Continuous series over `[from, to)` from the aggregated buckets (sorted by
+/// `open_time`, aligned to `interval`). Each candle opens at the previous
+/// close; buckets before the first known price are omitted.
+pub fn fill_candles(
+    buckets: Vec<Candle>,
+    interval: CandleInterval,
+    from: u64,
+    to: u64,
+    previous_close: Option<Amount>,
+) -> Vec<Candle> {
+    let step = interval.secs();
+    let mut candles = Vec::new();
+    let mut close = previous_close;
+    let mut buckets = buckets.into_iter().peekable();
+
+    let mut open_time = interval.bucket_start(from);
+    while open_time < to && (candles.len() as u64) < MAX_CANDLES {
+        // Buckets before the range (misaligned input) are skipped
+        while buckets.peek().is_some_and(|bucket| bucket.open_time < open_time) {
+            buckets.next();
+        }
+        match buckets.next_if(|bucket| bucket.open_time == open_time) {
+            Some(mut candle) => {
+                if let Some(previous) = close {
+                    candle.open = previous;
+                    candle.high = candle.high.max(previous);
+                    candle.low = candle.low.min(previous);
+                }
+                close = Some(candle.close);
+                candles.push(candle);
+            }
+            None => {
+                if let Some(price) = close {
+                    candles.push(Candle::flat(open_time, price));
+                }
+            }
+        }
+        open_time += step;
+    }
+    candles
+}
+
+/// 24h statistics from the pool's current state, its swap totals over the last
+/// day and its state a day ago (if it existed then)
+pub fn pool_stats(pool: &Pool, fee_rate_bps: u64, volume: &VolumeTotals, day_ago: Option<&PoolState>) -> PoolStats {
+    let now = PoolState::of(pool, 0);
+    let price = now.price();
+    let tvl = now.tvl();
+
+    // Fees stay in the pool in the input token; token_a fees valued at the current price
+    let fees_b = volume.token_b_in.mul_bps(fee_rate_bps).unwrap_or(Amount::ZERO);
+    let fees_a = volume.token_a_in.mul_bps(fee_rate_bps).unwrap_or(Amount::ZERO);
+    let fees_24h = fees_b.saturating_add(fees_a.checked_mul(price).unwrap_or(Amount::ZERO));
+
+    let lp_share_value = now.lp_share_value();
+    let lp_return_24h = day_ago
+        .map(PoolState::lp_share_value)
+        .filter(|value| !value.is_zero())
+        .map(|value| lp_share_value.ratio(value) - 1.0);
+
+    PoolStats {
+        pool_id: pool.id.clone(),
+        token_a: pool.token_a.clone(),
+        token_b: pool.token_b.clone(),
+        price,
+        tvl,
+        volume_24h_a: volume.volume_a,
+        volume_24h_b: volume.volume_b,
+        trades_24h: volume.trades,
+        fees_24h,
+        fee_apr: fees_24h.ratio(tvl) * 365.0,
+        lp_share_value,
+        lp_return_24h,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn amount(value: &str) -> Amount {
+        value.parse().unwrap()
+    }
+
+    fn bucket(open_time: u64, open: &str, high: &str, low: &str, close: &str) -> Candle {
+        Candle {
+            open_time,
+            open: amount(open),
+            high: amount(high),
+            low: amount(low),
+            close: amount(close),
+            volume_a: amount("10"),
+            volume_b: amount("20"),
+            trades: 2,
+        }
+    }
+
+    #[test]
+    fn test_candles_are_continuous() {
+        let interval = CandleInterval::OneMinute;
+        let buckets = vec![bucket(60, "2", "2.5", "2", "2.4"), bucket(240, "2.2", "2.2", "2.1", "2.1")];
+
+        // No earlier price: the series starts at the first trade
+        let candles = fill_candles(buckets.clone(), interval, 0, 300, None);
+        assert_eq!(candles.iter().map(|c| c.open_time).collect::<Vec<_>>(), vec![60, 120, 180, 240]);
+        assert_eq!(candles[1], Candle::flat(120, amount("2.4")));
+        // Opens at the previous close, which extends the range
+        assert_eq!((candles[3].open, candles[3].high), (amount("2.4"), amount("2.4")));
+
+        let candles = fill_candles(buckets, interval, 30, 120, Some(amount("1.9")));
+        assert_eq!(candles.len(), 2);
+        assert_eq!((candles[0].open_time, candles[0].close), (0, amount("1.9")));
+        assert_eq!((candles[1].open, candles[1].low), (amount("1.9"), amount("1.9")));
+    }
+
+    #[test]
+    fn test_interval_parsing_and_limits() {
+        assert_eq!(CandleInterval::parse("5m"), Some(CandleInterval::FiveMinutes));
+        assert_eq!(CandleInterval::parse("2h"), None);
+        assert_eq!(CandleInterval::OneHour.bucket_start(7_399), 7_200);
+        let candles = fill_candles(vec![], CandleInterval::OneMinute, 0, 10 * MAX_CANDLES * 60, Some(Amount::ONE));
+        assert_eq!(candles.len() as u64, MAX_CANDLES);
+    }
+
+    #[test]
+    fn test_pool_stats() {
+        let pool = Pool {
+            id: "DYO_DYS".to_string(),
+            token_a: "DYO".to_string(),
+            token_b: "DYS".to_string(),
+            reserve_a: amount("1000"),
+            reserve_b: amount("2000"),
+            total_liquidity: amount("1000"),
+            lp_balances: Default::default(),
+            k_last: Amount::ZERO,
+        };
+        let volume = VolumeTotals {
+            token_a_in: amount("100"),
+            token_b_in: amount("200"),
+            volume_a: amount("200"),
+            volume_b: amount("400"),
+            trades: 4,
+        };
+        let day_ago = PoolState { timestamp: 0, reserve_a: amount("1000"), reserve_b: amount("1900"), total_liquidity: amount("1000") };
+
+        let stats = pool_stats(&pool, 30, &volume, Some(&day_ago));
+        assert_eq!(stats.price, amount("2"));
+        assert_eq!(stats.tvl, amount("4000"));
+        // 0.3% of 200 DYS + 0.3% of 100 DYO at 2 DYS
+        assert_eq!(stats.fees_24h, amount("1.2"));
+        assert!((stats.fee_apr - 1.2 / 4000.0 * 365.0).abs() < 1e-12);
+        assert_eq!(stats.lp_share_value, amount("4"));
+        assert!((stats.lp_return_24h.unwrap() - (4.0 / 3.8 - 1.0)).abs() < 1e-12);
+        assert_eq!(pool_stats(&pool, 30, &volume, None).lp_return_24h, None);
+    }
+}
@@ -0,0 +1,604 @@
+//! Batch Auction Mode for DEX Swaps
+//!
+//! With `DUJYO_DEX_BATCH_AUCTION` enabled, swaps are not executed on arrival:
+//! they are collected in a `BatchMempool` during the block interval and cleared
+//! together at block production, so arrival order inside a block is irrelevant
+//! and a swap cannot be sandwiched.
+//!
+//! The batch is kept apart from the ledger's `OptimizedMempool`: that mempool is
+//! a view of `Blockchain::pending_transactions`, which peers re-execute, while
+//! DEX pools, swap nonces and the input escrow are local to the node. Only the
+//! clearing results enter the block (`Block::dex_batches`).
+//!
+//! Clearing of one pool:
+//!
+//! - Opposing swaps are matched against each other; only the net imbalance
+//!   trades against the AMM (with the pool fee).
+//! - Every swap of the pool gets the same price: the net amount `d` sent to the
+//!   AMM is chosen so that its average AMM price equals the price at which the
+//!   matched side is paid (`out(d) / d == other_in / (excess_in - d)`).
+//! - Each side's proceeds are shared pro rata to the inputs, rounded down; the
+//!   rounding dust stays in the pool.
+//! - A swap whose share misses its `min_received` (or whose deadline passed) is
+//!   rejected and refunded, and the pool is cleared again without it.
+//!
+//! Every pool of a batch is cleared on a copy, after the constant-product
+//! checks of `dex_secured`; a pool that fails to clear has its swaps rejected
+//! without holding back the others. The copies are installed once the node
+//! has stored the results (`BatchSettlement`). The per-user results are
+//! reported in the block (`Block::dex_batches`).
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet, VecDeque};
+
+use crate::dex::dex_secured::{check_deadline, check_invariant, root_k};
+use crate::dex::{pool_id, GuardRelease, Pool, SwapRequest, DEX};
+use crate::utils::amount::Amount;
+
+/// Swaps a batch can hold; submissions beyond it are refused until the next block
+pub const MAX_BATCH_SWAPS: usize = 10_000;
+
+/// Whether swaps go through the batch auction (DUJYO_DEX_BATCH_AUCTION=1|true)
+pub fn batch_auction_enabled() -> bool {
+    std::env::var("DUJYO_DEX_BATCH_AUCTION")
+        .map(|value| value == "1" || value.eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+}
+
+/// A swap waiting for the next batch (its input is escrowed)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchSwap {
+    pub swap_id: String,
+    pub user: String,
+    pub from: String,
+    pub to: String,
+    pub amount_in: Amount,
+    pub min_received: Amount,
+    pub deadline: u64,
+    pub submitted_at: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchFillStatus {
+    Filled,
+    /// Not executed, the input is refunded
+    Rejected,
+}
+
+impl BatchFillStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BatchFillStatus::Filled => "filled",
+            BatchFillStatus::Rejected => "rejected",
+        }
+    }
+}
+
+/// Result of one swap in a batch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchFill {
+    pub swap_id: String,
+    pub user: String,
+    pub token_in: String,
+    pub token_out: String,
+    pub amount_in: Amount,
+    /// Zero when rejected
+    pub amount_out: Amount,
+    pub status: BatchFillStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl BatchFill {
+    fn rejected(swap: &BatchSwap, reason: String) -> Self {
+        BatchFill {
+            swap_id: swap.swap_id.clone(),
+            user: swap.user.clone(),
+            token_in: swap.from.clone(),
+            token_out: swap.to.clone(),
+            amount_in: swap.amount_in,
+            amount_out: Amount::ZERO,
+            status: BatchFillStatus::Rejected,
+            reason: Some(reason),
+        }
+    }
+}
+
+/// Uniform-price clearing of one pool, as reported in the block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchClearing {
+    pub pool_id: String,
+    /// `token_b` per `token_a` paid to every filled swap (zero if none filled)
+    pub clearing_price: Amount,
+    /// Net trade against the AMM: `amm_in` of `amm_token_in` for `amm_out`
+    pub amm_token_in: Option<String>,
+    pub amm_in: Amount,
+    pub amm_out: Amount,
+    pub fills: Vec<BatchFill>,
+}
+
+impl BatchClearing {
+    /// Every swap of pool `pool_id` rejected (and refunded) for `reason`
+    fn rejected(pool_id: String, swaps: &[BatchSwap], reason: &str) -> Self {
+        BatchClearing {
+            pool_id,
+            clearing_price: Amount::ZERO,
+            amm_token_in: None,
+            amm_in: Amount::ZERO,
+            amm_out: Amount::ZERO,
+            fills: swaps.iter().map(|swap| BatchFill::rejected(swap, reason.to_string())).collect(),
+        }
+    }
+
+    /// The same swaps, all rejected for `reason` (results that could not be stored)
+    pub fn into_rejected(self, reason: &str) -> Self {
+        BatchClearing {
+            pool_id: self.pool_id,
+            clearing_price: Amount::ZERO,
+            amm_token_in: None,
+            amm_in: Amount::ZERO,
+            amm_out: Amount::ZERO,
+            fills: self
+                .fills
+                .into_iter()
+                .map(|fill| BatchFill {
+                    amount_out: Amount::ZERO,
+                    status: BatchFillStatus::Rejected,
+                    reason: Some(reason.to_string()),
+                    ..fill
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A batch cleared on copies of its pools, not installed yet. It holds the DEX
+/// guard, so no other operation changes the pools until `DEX::install_batch`.
+pub struct BatchSettlement {
+    pub clearings: Vec<BatchClearing>,
+    pools: Vec<Pool>,
+    _guard: Option<GuardRelease>,
+}
+
+/// Swaps collected during the block interval, in arrival order (which the
+/// clearing ignores). Lives in the DEX next to the pools it clears, not in the
+/// ledger mempool (see the module docs).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BatchMempool {
+    queue: VecDeque<BatchSwap>,
+}
+
+impl BatchMempool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    fn push(&mut self, swap: BatchSwap) -> Result<(), String> {
+        if self.queue.len() >= MAX_BATCH_SWAPS {
+            return Err("Batch is full, retry in the next block".to_string());
+        }
+        self.queue.push_back(swap);
+        Ok(())
+    }
+}
+
+/// SHA-256 (hex) of the batch results of a block, None when it has none
+pub fn batches_root(batches: &[BatchClearing]) -> Option<String> {
+    if batches.is_empty() {
+        return None;
+    }
+    let encoded = serde_json::to_vec(batches).expect("batch results always serialize");
+    Some(hex::encode(Sha256::digest(encoded)))
+}
+
+impl DEX {
+    /// Queue a direct-pair swap for the next batch. Deadline and nonce are
+    /// checked (and the nonce spent) now; the deadline is checked again at clearing.
+    pub fn submit_batch_swap(&mut self, request: &SwapRequest, swap_id: String, now: u64) -> Result<BatchSwap, String> {
+        if request.from == request.to {
+            return Err("Cannot swap a token for itself".to_string());
+        }
+        if request.amount.is_zero() {
+            return Err("Invalid swap amount".to_string());
+        }
+        let id = pool_id(&request.from, &request.to);
+        if !self.pools.get(&id).is_some_and(|pool| pool.has_liquidity()) {
+            return Err(format!("Pool {} not found (batch swaps trade a single pool)", id));
+        }
+        check_deadline(request.deadline, now)?;
+        self.nonces.check(&request.user, request.nonce)?;
+
+        let swap = BatchSwap {
+            swap_id,
+            user: request.user.clone(),
+            from: request.from.clone(),
+            to: request.to.clone(),
+            amount_in: request.amount,
+            min_received: request.min_received,
+            deadline: request.deadline,
+            submitted_at: now,
+        };
+        self.batch.push(swap.clone())?;
+        self.nonces.spend(&request.user, request.nonce);
+        Ok(swap)
+    }
+
+    /// Clear every queued swap at block production and install the result
+    pub fn settle_batch(&mut self, now: u64) -> Result<Vec<BatchClearing>, String> {
+        let settlement = self.clear_batch(now)?;
+        let clearings = settlement.clearings.clone();
+        self.install_batch(settlement, &[], now);
+        Ok(clearings)
+    }
+
+    /// Clear every queued swap on copies of the pools. A pool whose clearing
+    /// fails gets all its swaps rejected; the error is only for a DEX that cannot
+    /// clear at all (paused or busy), see `reject_batch`.
+    pub fn clear_batch(&self, now: u64) -> Result<BatchSettlement, String> {
+        if self.batch.is_empty() {
+            return Ok(BatchSettlement { clearings: Vec::new(), pools: Vec::new(), _guard: None });
+        }
+        let guard = self.enter()?;
+
+        // Pools in id order, so the report does not depend on arrival order
+        let mut by_pool: BTreeMap<String, Vec<BatchSwap>> = BTreeMap::new();
+        for swap in &self.batch.queue {
+            by_pool.entry(pool_id(&swap.from, &swap.to)).or_default().push(swap.clone());
+        }
+
+        let mut clearings = Vec::with_capacity(by_pool.len());
+        let mut pools = Vec::with_capacity(by_pool.len());
+        for (id, swaps) in by_pool {
+            let Some(before) = self.pools.get(&id) else {
+                let reason = format!("Pool {} not found", id);
+                clearings.push(BatchClearing::rejected(id, &swaps, &reason));
+                continue;
+            };
+            let cleared = self.clear_pool(before, swaps.clone(), now).and_then(|(mut pool, clearing)| {
+                check_invariant(before, &pool)?;
+                pool.k_last = root_k(&pool);
+                Ok((pool, clearing))
+            });
+            match cleared {
+                Ok((pool, clearing)) => {
+                    pools.push(pool);
+                    clearings.push(clearing);
+                }
+                Err(e) => {
+                    let reason = format!("Batch of pool {} not cleared: {}", id, e);
+                    clearings.push(BatchClearing::rejected(id, &swaps, &reason));
+                }
+            }
+        }
+        Ok(BatchSettlement { clearings, pools, _guard: Some(guard) })
+    }
+
+    /// Install a cleared batch, except the pools in `skipped` (whose results were
+    /// not stored and whose swaps were refunded instead). Every swap of the batch
+    /// leaves the queue; swaps queued since `clear_batch` stay for the next one.
+    pub fn install_batch(&mut self, settlement: BatchSettlement, skipped: &[String], now: u64) {
+        let settled: HashSet<&str> = settlement
+            .clearings
+            .iter()
+            .flat_map(|clearing| clearing.fills.iter())
+            .map(|fill| fill.swap_id.as_str())
+            .collect();
+        self.batch.queue.retain(|swap| !settled.contains(swap.swap_id.as_str()));
+
+        for pool in settlement.pools.iter().filter(|pool| !skipped.contains(&pool.id)) {
+            self.oracle.record(pool, now);
+            self.pools.insert(pool.id.clone(), pool.clone());
+        }
+    }
+
+    /// Reject every queued swap for `reason` (a batch the DEX cannot clear is
+    /// refunded instead of waiting for a block that may never clear it)
+    pub fn reject_batch(&mut self, reason: &str) -> Vec<BatchClearing> {
+        let mut by_pool: BTreeMap<String, Vec<BatchSwap>> = BTreeMap::new();
+        for swap in self.batch.queue.drain(..) {
+            by_pool.entry(pool_id(&swap.from, &swap.to)).or_default().push(swap);
+        }
+        by_pool
+            .into_iter()
+            .map(|(id, swaps)| BatchClearing::rejected(id, &swaps, reason))
+            .collect()
+    }
+
+    /// Clear the swaps of one pool, dropping those whose limit or deadline the
+    /// uniform price misses until the rest all pass
+    fn clear_pool(&self, pool: &Pool, mut swaps: Vec<BatchSwap>, now: u64) -> Result<(Pool, BatchClearing), String> {
+        // Deterministic order inside the pool as well
+        swaps.sort_by(|a, b| a.swap_id.cmp(&b.swap_id));
+        let mut rejected: Vec<BatchFill> = Vec::new();
+        swaps.retain(|swap| match check_deadline(swap.deadline, now) {
+            Ok(()) => true,
+            Err(e) => {
+                rejected.push(BatchFill::rejected(swap, e));
+                false
+            }
+        });
+
+        loop {
+            let (after, mut clearing) = self.clear_once(pool, &swaps)?;
+            let missed: Vec<usize> = clearing.fills.iter().enumerate()
+                .filter(|(index, fill)| fill.amount_out < swaps[*index].min_received)
+                .map(|(index, _)| index)
+                .collect();
+            if missed.is_empty() {
+                clearing.fills.extend(rejected);
+                return Ok((after, clearing));
+            }
+            for index in missed.into_iter().rev() {
+                let swap = swaps.remove(index);
+                let reason = format!("Clearing price misses the limit: expected at least {}, got {}",
+                    swap.min_received, clearing.fills[index].amount_out);
+                rejected.push(BatchFill::rejected(&swap, reason));
+            }
+        }
+    }
+
+    /// One uniform-price clearing of `swaps` (all filled) against `pool`
+    fn clear_once(&self, pool: &Pool, swaps: &[BatchSwap]) -> Result<(Pool, BatchClearing), String> {
+        let total = |token: &str| swaps.iter().filter(|swap| swap.from == token)
+            .try_fold(Amount::ZERO, |sum, swap| sum.checked_add(swap.amount_in))
+            .map_err(|e| format!("Arithmetic overflow in batch of {}: {}", pool.id, e));
+        let (in_a, in_b) = (total(&pool.token_a)?, total(&pool.token_b)?);
+
+        // The side whose input is worth more at the pool price is the excess side
+        let a_in_excess = in_a.wide_mul(pool.reserve_b) >= in_b.wide_mul(pool.reserve_a);
+        let (excess_token, excess_in, other_in) = if a_in_excess {
+            (&pool.token_a, in_a, in_b)
+        } else {
+            (&pool.token_b, in_b, in_a)
+        };
+        let (reserve_in, reserve_out) = pool.reserves_for(excess_token)
+            .ok_or_else(|| format!("Token {} is not in pool {}", excess_token, pool.id))?;
+
+        // Largest net input d with out(d) / d >= other_in / (excess_in - d):
+        // the left side falls and the right side grows with d
+        let amm_out = |d: Amount| if d.is_zero() {
+            Ok(Amount::ZERO)
+        } else {
+            self.calculate_swap_output(reserve_in, reserve_out, d)
+        };
+        let (mut low, mut high) = (0u128, excess_in.raw());
+        while low < high {
+            let mid = low + (high - low).div_ceil(2);
+            let d = Amount::from_raw(mid);
+            let fits = match amm_out(d) {
+                Ok(out) => out.wide_mul(excess_in.saturating_sub(d)) >= other_in.wide_mul(d),
+                Err(_) => false,
+            };
+            if fits {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+        let amm_in = Amount::from_raw(low);
+        let amm_out = amm_out(amm_in)?;
+
+        // Proceeds of each side, shared pro rata to the inputs
+        let excess_proceeds = other_in.checked_add(amm_out)
+            .map_err(|e| format!("Arithmetic overflow in batch of {}: {}", pool.id, e))?;
+        let other_proceeds = excess_in.checked_sub(amm_in)
+            .map_err(|e| format!("Arithmetic underflow in batch of {}: {}", pool.id, e))?;
+        let mut fills = Vec::with_capacity(swaps.len());
+        let (mut paid_excess_side, mut paid_other_side) = (Amount::ZERO, Amount::ZERO);
+        for swap in swaps {
+            let (proceeds, side_in) = if &swap.from == excess_token {
+                (excess_proceeds, excess_in)
+            } else {
+                (other_proceeds, other_in)
+            };
+            let amount_out = proceeds.mul_div(swap.amount_in, side_in)
+                .map_err(|e| format!("Arithmetic error in batch payout: {}", e))?;
+            if &swap.from == excess_token {
+                paid_excess_side = paid_excess_side.saturating_add(amount_out);
+            } else {
+                paid_other_side = paid_other_side.saturating_add(amount_out);
+            }
+            fills.push(BatchFill {
+                swap_id: swap.swap_id.clone(),
+                user: swap.user.clone(),
+                token_in: swap.from.clone(),
+                token_out: swap.to.clone(),
+                amount_in: swap.amount_in,
+                amount_out,
+                status: BatchFillStatus::Filled,
+                reason: None,
+            });
+        }
+
+        // Pool receives every input and pays every output (dust included)
+        let mut after = pool.clone();
+        let new_in = reserve_in.checked_add(excess_in)
+            .and_then(|reserve| reserve.checked_sub(paid_other_side))
+            .map_err(|e| format!("Arithmetic error in reserves of {}: {}", pool.id, e))?;
+        let new_out = reserve_out.checked_add(other_in)
+            .and_then(|reserve| reserve.checked_sub(paid_excess_side))
+            .map_err(|e| format!("Arithmetic error in reserves of {}: {}", pool.id, e))?;
+        if a_in_excess {
+            (after.reserve_a, after.reserve_b) = (new_in, new_out);
+        } else {
+            (after.reserve_b, after.reserve_a) = (new_in, new_out);
+        }
+
+        // token_b per token_a on either side
+        let clearing_price = if swaps.is_empty() {
+            Amount::ZERO
+        } else if a_in_excess {
+            excess_proceeds.checked_div(excess_in)
+                .map_err(|e| format!("Arithmetic error in clearing price: {}", e))?
+        } else {
+            excess_in.checked_div(excess_proceeds)
+                .map_err(|e| format!("Arithmetic error in clearing price: {}", e))?
+        };
+
+        Ok((after, BatchClearing {
+            pool_id: pool.id.clone(),
+            clearing_price,
+            amm_token_in: (!amm_in.is_zero()).then(|| excess_token.clone()),
+            amm_in,
+            amm_out,
+            fills,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn amount(value: &str) -> Amount {
+        value.parse().unwrap()
+    }
+
+    fn request(user: &str, from: &str, to: &str, amount_in: &str, min_received: &str) -> SwapRequest {
+        static NONCE: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+        SwapRequest {
+            from: from.to_string(),
+            to: to.to_string(),
+            amount: amount(amount_in),
+            min_received: amount(min_received),
+            user: user.to_string(),
+            deadline: u64::MAX,
+            nonce: NONCE.fetch_add(1, std::sync::atomic::Ordering::Relaxed),
+        }
+    }
+
+    fn fill<'a>(clearings: &'a [BatchClearing], swap_id: &str) -> &'a BatchFill {
+        clearings.iter().flat_map(|clearing| clearing.fills.iter())
+            .find(|fill| fill.swap_id == swap_id)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_batch_clears_at_uniform_price_regardless_of_order() {
+        let settle = |order: &[(&str, &str, &str, &str)]| {
+            let mut dex = DEX::new();
+            for (id, user, from, to) in order {
+                let amount_in = if *from == "DYO" { "1000" } else { "400" };
+                dex.submit_batch_swap(&request(user, from, to, amount_in, "0"), id.to_string(), 10).unwrap();
+            }
+            let clearings = dex.settle_batch(10).unwrap();
+            (clearings, dex.get_pool("DYO_DYS").unwrap().clone())
+        };
+        let swaps = [("s1", "victim", "DYO", "DYS"), ("s2", "bot", "DYO", "DYS"), ("s3", "alice", "DYS", "DYO")];
+        let (clearings, pool) = settle(&swaps);
+        let (reversed, reversed_pool) = settle(&[swaps[2], swaps[1], swaps[0]]);
+
+        // Same payouts and reserves whatever the arrival order
+        assert_eq!(fill(&clearings, "s1").amount_out, fill(&reversed, "s1").amount_out);
+        assert_eq!(pool.reserve_a, reversed_pool.reserve_a);
+        // Both DYO sellers get the same price, the DYS seller trades at its inverse
+        assert_eq!(fill(&clearings, "s1").amount_out, fill(&clearings, "s2").amount_out);
+        let clearing = &clearings[0];
+        assert_eq!(clearing.amm_token_in.as_deref(), Some("DYO"));
+        let price = clearing.clearing_price;
+        assert!((fill(&clearings, "s1").amount_out.to_f64() - 1000.0 * price.to_f64()).abs() < 0.001);
+        let alice = fill(&clearings, "s3").amount_out;
+        assert!((alice.to_f64() - 400.0 / price.to_f64()).abs() < 0.001);
+        // Only the net 1600 DYO minus the matched part went through the AMM
+        assert!(clearing.amm_in < amount("1600"));
+        assert!(pool.reserve_a.wide_mul(pool.reserve_b) >= amount("1000000").wide_mul(amount("1000000")));
+        assert_eq!(pool.k_last, root_k(&pool));
+    }
+
+    #[test]
+    fn test_batch_rejects_missed_limits_and_keeps_others() {
+        let mut dex = DEX::new();
+        dex.submit_batch_swap(&request("alice", "DYO", "DYS", "100", "99"), "a".into(), 10).unwrap();
+        dex.submit_batch_swap(&request("greedy", "DYO", "DYS", "100", "150"), "g".into(), 10).unwrap();
+        let late = SwapRequest { deadline: 12, ..request("late", "DYS", "DYO", "100", "0") };
+        dex.submit_batch_swap(&late, "l".into(), 10).unwrap();
+
+        let clearings = dex.settle_batch(20).unwrap();
+        assert_eq!(fill(&clearings, "a").status, BatchFillStatus::Filled);
+        assert_eq!(fill(&clearings, "g").status, BatchFillStatus::Rejected);
+        assert!(fill(&clearings, "l").reason.as_deref().unwrap().contains("deadline"));
+        // Only alice's 100 DYO reached the pool
+        assert_eq!(dex.get_pool("DYO_DYS").unwrap().reserve_a, amount("1000100"));
+        assert!(dex.batch.is_empty());
+    }
+
+    #[test]
+    fn test_batch_installs_only_stored_pools() {
+        let mut dex = DEX::new();
+        let usdc_pool = Pool {
+            id: "DYO_USDC".to_string(),
+            token_b: "USDC".to_string(),
+            ..dex.get_pool("DYO_DYS").unwrap().clone()
+        };
+        dex.pools.insert(usdc_pool.id.clone(), usdc_pool);
+        dex.submit_batch_swap(&request("alice", "DYO", "DYS", "100", "0"), "a".into(), 10).unwrap();
+        dex.submit_batch_swap(&request("bob", "DYO", "USDC", "100", "0"), "b".into(), 10).unwrap();
+        let before = dex.get_pool("DYO_USDC").unwrap().clone();
+
+        // The pools stay as they are (and locked) until the results are stored
+        let settlement = dex.clear_batch(10).unwrap();
+        assert_eq!(settlement.clearings.len(), 2);
+        assert_eq!(dex.get_pool("DYO_DYS").unwrap().reserve_a, amount("1000000"));
+        assert!(dex.execute_swap(request("carol", "DYO", "DYS", "1", "0")).is_err());
+        dex.submit_batch_swap(&request("dave", "DYS", "DYO", "5", "0"), "d".into(), 11).unwrap();
+
+        // DYO_USDC results were not stored: that pool is left untouched
+        dex.install_batch(settlement, &["DYO_USDC".to_string()], 12);
+        assert_eq!(dex.get_pool("DYO_DYS").unwrap().reserve_a, amount("1000100"));
+        assert_eq!(dex.get_pool("DYO_USDC").unwrap().reserve_a, before.reserve_a);
+        // Only the swap queued after the clearing waits for the next batch
+        assert_eq!(dex.batch.len(), 1);
+        assert!(dex.execute_swap(request("carol", "DYO", "DYS", "1", "0")).is_ok());
+    }
+
+    #[test]
+    fn test_paused_dex_refunds_the_batch() {
+        let mut dex = DEX::new();
+        dex.submit_batch_swap(&request("alice", "DYO", "DYS", "100", "0"), "a".into(), 10).unwrap();
+        dex.emergency_pause("maintenance".to_string()).unwrap();
+
+        assert!(dex.clear_batch(10).is_err());
+        let rejected = dex.reject_batch("DEX paused");
+        assert_eq!(fill(&rejected, "a").status, BatchFillStatus::Rejected);
+        assert_eq!(fill(&rejected, "a").amount_in, amount("100"));
+        assert!(dex.batch.is_empty());
+    }
+
+    #[test]
+    fn test_batch_submission_checks() {
+        let mut dex = DEX::new();
+        assert!(dex.submit_batch_swap(&request("alice", "DYO", "USDC", "1", "0"), "x".into(), 10).is_err());
+        let signed = SwapRequest { nonce: 3, ..request("alice", "DYO", "DYS", "1", "0") };
+        dex.submit_batch_swap(&signed, "s".into(), 10).unwrap();
+        assert!(dex.submit_batch_swap(&signed, "r".into(), 10).unwrap_err().contains("nonce"));
+        assert_eq!(dex.batch.len(), 1);
+    }
+
+    #[test]
+    fn test_batch_results_are_covered_by_block_hash() {
+        use crate::blockchain::blockchain::Block;
+
+        let mut dex = DEX::new();
+        dex.submit_batch_swap(&request("alice", "DYO", "DYS", "10", "0"), "a".into(), 10).unwrap();
+        let clearings = dex.settle_batch(10).unwrap();
+
+        let mut block = Block::new(1, 10, vec![], "parent".to_string(), "state".to_string(), None);
+        let plain_hash = block.hash.clone();
+        assert!(batches_root(&block.dex_batches).is_none());
+        block.dex_batches = clearings;
+        assert!(block.verify_structure().is_err());
+        block.hash = block.calculate_hash();
+        assert_ne!(block.hash, plain_hash);
+        assert!(block.verify_structure().is_ok());
+    }
+}
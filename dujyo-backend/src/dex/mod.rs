@@ -1,16 +1,39 @@
 // src/dex/mod.rs
 
 pub mod payment_system;
-// pub mod dex_secured; // TODO: Fix SafeMath error mapping before enabling
+pub mod analytics;
+pub mod oracle;
+pub mod orders;
+pub mod dex_secured;
+pub mod batch_auction;
 
 // Re-exportar estructuras necesarias para compatibilidad
 use serde::{Deserialize, Serialize};
 use chrono;
 use tracing::info;
 use std::sync::{Arc, Mutex};
+use crate::blockchain::blockchain::{Blockchain, Transaction};
+use crate::blockchain::ledger::{pool_account, TxKind, NATIVE_TOKEN};
+use crate::utils::amount::Amount;
+use oracle::PriceOracle;
+use batch_auction::BatchMempool;
+use dex_secured::{check_deadline, check_invariant, check_k_last, root_k, NonceRegistry};
 
+/// Longest swap path the router considers (pools crossed)
+pub const MAX_ROUTE_HOPS: usize = 3;
+
+/// LP shares locked forever by the first deposit into a pool, so its supply
+/// never returns to zero and the share price cannot be reset by a dust deposit
+pub const MINIMUM_LIQUIDITY: Amount = Amount::from_micro(1);
+
+/// Holder of the locked `MINIMUM_LIQUIDITY` shares (no key, never withdraws)
+pub const LOCKED_LIQUIDITY_HOLDER: &str = "DEX_LOCKED_LIQUIDITY";
+
+/// The live DEX engine: `Amount` (u128) reserves, constant-product and `k_last`
+/// checks on every operation, mandatory deadlines and nonces on every swap (see
+/// `dex_secured`)
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DEX {
+pub struct SecuredDEX {
     pub pools: std::collections::HashMap<String, Pool>,
     pub mempool: Vec<SwapTransaction>,
     pub fee_rate: u64, // Fee rate in basis points (30 = 0.3%)
@@ -23,16 +46,106 @@ pub struct DEX {
     // ✅ SECURITY FIX VULN-006: Reentrancy protection
     #[serde(skip)]
     pub reentrancy_guard: Arc<Mutex<bool>>, // Reentrancy guard (not serialized, uses Arc for Clone)
+
+    // TWAP observations of every pool (pricing must never read spot reserves)
+    #[serde(default)]
+    pub oracle: PriceOracle,
+
+    // Replay protection: last swap nonce spent by each user (restored from
+    // `dex_nonces` at startup)
+    #[serde(default)]
+    pub nonces: NonceRegistry,
+
+    // Swaps waiting for the next batch auction (batch mode only)
+    #[serde(default)]
+    pub batch: BatchMempool,
 }
 
+/// Name the rest of the backend uses for the engine
+pub type DEX = SecuredDEX;
+
+/// One pool per unordered token pair. `token_a` is the smaller symbol, so both
+/// swap directions and every deposit resolve to the same `pool_id`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Pool {
     pub id: String,
     pub token_a: String,
     pub token_b: String,
-    pub reserve_a: f64,
-    pub reserve_b: f64,
-    pub total_liquidity: f64,
+    pub reserve_a: Amount,
+    pub reserve_b: Amount,
+    pub total_liquidity: Amount,
+    /// LP shares per address; they always add up to `total_liquidity`
+    #[serde(default)]
+    pub lp_balances: std::collections::HashMap<String, Amount>,
+    /// `sqrt(reserve_a * reserve_b)` after the last operation
+    #[serde(default)]
+    pub k_last: Amount,
+}
+
+impl Pool {
+    pub fn lp_balance_of(&self, address: &str) -> Amount {
+        self.lp_balances.get(address).copied().unwrap_or(Amount::ZERO)
+    }
+
+    pub fn has_liquidity(&self) -> bool {
+        !self.reserve_a.is_zero() && !self.reserve_b.is_zero()
+    }
+
+    /// (reserve of `token`, reserve of the other token)
+    pub fn reserves_for(&self, token: &str) -> Option<(Amount, Amount)> {
+        if token == self.token_a {
+            Some((self.reserve_a, self.reserve_b))
+        } else if token == self.token_b {
+            Some((self.reserve_b, self.reserve_a))
+        } else {
+            None
+        }
+    }
+
+    /// Move `amount_in` of `token_in` into the pool and `amount_out` of the other token out
+    fn apply_swap(&mut self, token_in: &str, amount_in: Amount, amount_out: Amount) -> Result<(), String> {
+        let (reserve_in, reserve_out) = if token_in == self.token_a {
+            (&mut self.reserve_a, &mut self.reserve_b)
+        } else if token_in == self.token_b {
+            (&mut self.reserve_b, &mut self.reserve_a)
+        } else {
+            return Err(format!("Token {} is not in pool {}", token_in, self.id));
+        };
+        // ✅ SECURITY FIX VULN-005: Use checked fixed-point arithmetic for reserve updates
+        *reserve_in = reserve_in.checked_add(amount_in)
+            .map_err(|e| format!("Arithmetic overflow in reserve of {}: {}", token_in, e))?;
+        *reserve_out = reserve_out.checked_sub(amount_out)
+            .map_err(|e| format!("Arithmetic underflow in pool {}: {}", self.id, e))?;
+        Ok(())
+    }
+}
+
+/// Tokens of a pair in pool order
+pub fn ordered_pair<'a>(token_x: &'a str, token_y: &'a str) -> (&'a str, &'a str) {
+    if token_x <= token_y {
+        (token_x, token_y)
+    } else {
+        (token_y, token_x)
+    }
+}
+
+/// Reserve of each side of the `DYO_DYS` pool every DEX starts with (owned by the
+/// DEX contract); its DYO side is the opening balance of the pool's ledger account
+pub const SEEDED_POOL_ID: &str = "DYO_DYS";
+pub const SEEDED_POOL_UNITS: u64 = 1_000_000;
+
+/// Pool id of a token pair, the same for either order (e.g. `DYO_DYS`)
+pub fn pool_id(token_x: &str, token_y: &str) -> String {
+    let (token_a, token_b) = ordered_pair(token_x, token_y);
+    format!("{}_{}", token_a, token_b)
+}
+
+/// Token symbols are short ASCII alphanumerics, so `_` can separate them in pool ids
+fn validate_token_symbol(symbol: &str) -> Result<(), String> {
+    if symbol.is_empty() || symbol.len() > 16 || !symbol.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(format!("Invalid token symbol: {:?}", symbol));
+    }
+    Ok(())
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,8 +153,8 @@ pub struct SwapTransaction {
     pub id: String,
     pub from_token: String,
     pub to_token: String,
-    pub amount_in: f64,
-    pub amount_out: f64,
+    pub amount_in: Amount,
+    pub amount_out: Amount,
     pub user: String,
     pub timestamp: u64,
 }
@@ -50,17 +163,33 @@ pub struct SwapTransaction {
 pub struct SwapRequest {
     pub from: String,
     pub to: String,
-    pub amount: f64,
-    pub min_received: f64,
+    pub amount: Amount,
+    pub min_received: Amount,
     pub user: String,
+    /// Unix seconds after which the swap must not execute
+    pub deadline: u64,
+    /// Must be above the last nonce the user spent (replay protection)
+    pub nonce: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LiquidityRequest {
     pub token_a: String,
     pub token_b: String,
-    pub amount_a: f64,
-    pub amount_b: f64,
+    pub amount_a: Amount,
+    pub amount_b: Amount,
+    pub user: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoveLiquidityRequest {
+    pub token_a: String,
+    pub token_b: String,
+    pub lp_tokens: Amount,
+    #[serde(default)]
+    pub min_amount_a: Amount,
+    #[serde(default)]
+    pub min_amount_b: Amount,
     pub user: String,
 }
 
@@ -69,8 +198,22 @@ pub struct SwapResponse {
     pub success: bool,
     pub message: String,
     pub tx_hash: Option<String>,
-    pub amount_received: Option<f64>,
-    pub price_impact: Option<f64>,
+    pub amount_received: Option<Amount>,
+    pub price_impact: Option<f64>, // Ratio (0.01 = 1%), not a token amount
+    pub route: Option<Vec<String>>, // Tokens crossed, from input to output
+    pub amounts: Option<Vec<Amount>>, // Amount entering each hop, followed by the final output
+}
+
+/// A priced swap path
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SwapRoute {
+    pub path: Vec<String>,
+    pub pools: Vec<String>,
+    /// Amount entering each hop, followed by the final output
+    pub amounts: Vec<Amount>,
+    pub amount_in: Amount,
+    pub amount_out: Amount,
+    pub price_impact: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -78,10 +221,16 @@ pub struct LiquidityResponse {
     pub success: bool,
     pub message: String,
     pub tx_hash: Option<String>,
-    pub lp_tokens_minted: Option<f64>,
+    pub lp_tokens_minted: Option<Amount>,
+    pub lp_tokens_burned: Option<Amount>,
+    /// Token amounts deposited (add) or paid out (remove)
+    pub amount_a: Option<Amount>,
+    pub amount_b: Option<Amount>,
+    /// Provider's LP balance after the operation
+    pub lp_balance: Option<Amount>,
 }
 
-impl DEX {
+impl SecuredDEX {
     pub fn new() -> Self {
         let mut dex = Self {
             pools: std::collections::HashMap::new(),
@@ -92,28 +241,28 @@ impl DEX {
             emergency_pause_reason: None,
             // ✅ SECURITY FIX VULN-006: Initialize reentrancy guard
             reentrancy_guard: Arc::new(Mutex::new(false)),
+            oracle: PriceOracle::new(),
+            nonces: NonceRegistry::new(),
+            batch: BatchMempool::new(),
         };
         
         // ✅ Crear pools iniciales para DYO/DYS
-        let dyo_dys_pool = Pool {
-            id: "DYO_DYS".to_string(),
+        // La liquidez inicial (sqrt(1M * 1M) = 1M LP) pertenece al contrato del DEX
+        let mut dyo_dys_pool = Pool {
+            id: SEEDED_POOL_ID.to_string(),
             token_a: "DYO".to_string(),
             token_b: "DYS".to_string(),
-            reserve_a: 1000000.0, // 1M DYO inicial
-            reserve_b: 1000000.0, // 1M DYS inicial (1:1 ratio)
-            total_liquidity: 1000000.0,
+            reserve_a: Amount::from_units(SEEDED_POOL_UNITS), // 1M DYO inicial
+            reserve_b: Amount::from_units(SEEDED_POOL_UNITS), // 1M DYS inicial (1:1 ratio)
+            total_liquidity: Amount::from_units(SEEDED_POOL_UNITS),
+            lp_balances: [("DEX_CONTRACT".to_string(), Amount::from_units(SEEDED_POOL_UNITS))].into_iter().collect(),
+            k_last: Amount::ZERO,
         };
+        dyo_dys_pool.k_last = root_k(&dyo_dys_pool);
         
-        dex.pools.insert("DYO_DYS".to_string(), dyo_dys_pool.clone());
-        // También agregar el pool invertido para búsqueda
-        dex.pools.insert("DYS_DYO".to_string(), Pool {
-            id: "DYS_DYO".to_string(),
-            token_a: "DYS".to_string(),
-            token_b: "DYO".to_string(),
-            reserve_a: dyo_dys_pool.reserve_b,
-            reserve_b: dyo_dys_pool.reserve_a,
-            total_liquidity: dyo_dys_pool.total_liquidity,
-        });
+        // Un solo pool por par: DYO_DYS sirve ambas direcciones
+        dex.oracle.record(&dyo_dys_pool, chrono::Utc::now().timestamp() as u64);
+        dex.pools.insert(dyo_dys_pool.id.clone(), dyo_dys_pool);
         
         println!("✅ DEX initialized with DYO/DYS pool (1M:1M ratio)");
         
@@ -167,190 +316,378 @@ impl DEX {
     pub fn get_pool(&self, pool_id: &str) -> Option<&Pool> {
         self.pools.get(pool_id)
     }
-    
+
+    /// Pool of a token pair, in either order
+    pub fn get_pair(&self, token_x: &str, token_y: &str) -> Option<&Pool> {
+        self.pools.get(&pool_id(token_x, token_y))
+    }
+
     pub fn get_all_pools(&self) -> Vec<&Pool> {
         self.pools.values().collect()
     }
-    
-    // ✅ SECURITY FIX VULN-006: Execute swap with reentrancy protection and checks-effects-interactions pattern
-    pub fn execute_swap(&mut self, request: SwapRequest) -> Result<SwapResponse, String> {
-        // ✅ CHECKS PHASE: All validations before state changes
-        // Check emergency pause first
+
+    /// Smallest swap nonce `user` can send next
+    pub fn next_nonce(&self, user: &str) -> u64 {
+        self.nonces.next_nonce(user)
+    }
+
+    /// Install the last swap nonce of each user loaded from `dex_nonces`
+    pub fn restore_nonces(&mut self, spent: &[(String, u64)]) {
+        for (user, last) in spent {
+            self.nonces.restore(user, *last);
+        }
+    }
+
+    /// Install a pool loaded from storage, replacing the in-memory one. LP shares
+    /// missing from the loaded balances are assigned to `LOCKED_LIQUIDITY_HOLDER`,
+    /// so the balances add up to the supply again.
+    pub fn restore_pool(&mut self, mut pool: Pool) -> Result<(), String> {
+        validate_token_symbol(&pool.token_a)?;
+        validate_token_symbol(&pool.token_b)?;
+        if pool.token_a >= pool.token_b || pool.id != pool_id(&pool.token_a, &pool.token_b) {
+            return Err(format!("Pool {} is not keyed by its ordered pair", pool.id));
+        }
+        if pool.has_liquidity() == pool.total_liquidity.is_zero() {
+            return Err(format!("Pool {} reserves and LP supply disagree", pool.id));
+        }
+        check_k_last(&pool)?;
+
+        let held = pool.lp_balances.values().try_fold(Amount::ZERO, |sum, share| sum.checked_add(*share))
+            .map_err(|e| format!("Arithmetic overflow in LP balances of {}: {}", pool.id, e))?;
+        let unassigned = pool.total_liquidity.checked_sub(held)
+            .map_err(|_| format!("Pool {} LP balances exceed its supply", pool.id))?;
+        if !unassigned.is_zero() {
+            let locked = pool.lp_balance_of(LOCKED_LIQUIDITY_HOLDER).saturating_add(unassigned);
+            pool.lp_balances.insert(LOCKED_LIQUIDITY_HOLDER.to_string(), locked);
+        }
+
+        pool.k_last = root_k(&pool);
+        self.oracle.record(&pool, chrono::Utc::now().timestamp() as u64);
+        self.pools.insert(pool.id.clone(), pool);
+        Ok(())
+    }
+
+    // ✅ SECURITY FIX VULN-006: Pause and reentrancy checks, then hold the guard
+    // until the returned value is dropped
+    fn enter(&self) -> Result<GuardRelease, String> {
         self.check_emergency_pause()?;
-        
-        // ✅ SECURITY FIX: Check reentrancy guard
         self.check_reentrancy()?;
-        
-        // ✅ Set reentrancy guard BEFORE any state changes
         self.set_reentrancy_guard(true)?;
-        
-        // Use defer-like pattern to ensure guard is released
-        struct GuardRelease {
-            guard: Arc<Mutex<bool>>,
+        Ok(GuardRelease {
+            guard: Arc::clone(&self.reentrancy_guard),
+        })
+    }
+
+    /// Permissionless pool factory: register the pool of a new token pair and
+    /// seed it with the creator's first deposit (which sets the initial price)
+    pub fn create_pool(&mut self, request: LiquidityRequest) -> Result<LiquidityResponse, String> {
+        self.check_emergency_pause()?;
+        validate_token_symbol(&request.token_a)?;
+        validate_token_symbol(&request.token_b)?;
+        if request.token_a == request.token_b {
+            return Err("A pool needs two different tokens".to_string());
         }
-        
-        impl Drop for GuardRelease {
-            fn drop(&mut self) {
-                if let Ok(mut g) = self.guard.lock() {
-                    *g = false;
+
+        let id = pool_id(&request.token_a, &request.token_b);
+        if self.pools.contains_key(&id) {
+            return Err(format!("Pool {} already exists", id));
+        }
+
+        let (token_a, token_b) = ordered_pair(&request.token_a, &request.token_b);
+        self.pools.insert(id.clone(), Pool {
+            id: id.clone(),
+            token_a: token_a.to_string(),
+            token_b: token_b.to_string(),
+            reserve_a: Amount::ZERO,
+            reserve_b: Amount::ZERO,
+            total_liquidity: Amount::ZERO,
+            lp_balances: std::collections::HashMap::new(),
+            k_last: Amount::ZERO,
+        });
+
+        // A pool only exists once it holds liquidity
+        let result = self.add_liquidity(request);
+        if result.is_err() {
+            self.pools.remove(&id);
+        } else {
+            info!("Pool {} created", id);
+        }
+        result
+    }
+
+    /// Swap through the single pool of the `from`/`to` pair (either direction)
+    pub fn execute_swap(&mut self, request: SwapRequest) -> Result<SwapResponse, String> {
+        // ✅ CHECKS PHASE: All validations before state changes
+        let guard_release = self.enter()?;
+        let route = self.quote_path(&[request.from.clone(), request.to.clone()], request.amount)?;
+        let response = self.settle_route(&request, route);
+
+        // Guard is released automatically when guard_release is dropped
+        drop(guard_release);
+        response
+    }
+
+    /// Give back a direct-pair swap whose surrounding operation failed: the pool
+    /// returns `amount_in` of `from` and recovers `amount_out` of `to`
+    pub fn undo_swap(&mut self, from: &str, to: &str, amount_in: Amount, amount_out: Amount) -> Result<(), String> {
+        let guard_release = self.enter()?;
+        let id = pool_id(from, to);
+        let mut pool = self.pools.get(&id).cloned()
+            .ok_or_else(|| format!("Pool {} not found", id))?;
+        let (reserve_in, reserve_out) = pool.reserves_for(from)
+            .ok_or_else(|| format!("Token {} is not in pool {}", from, id))?;
+        let reserve_in = reserve_in.checked_sub(amount_in)
+            .map_err(|e| format!("Arithmetic underflow undoing swap in {}: {}", id, e))?;
+        let reserve_out = reserve_out.checked_add(amount_out)
+            .map_err(|e| format!("Arithmetic overflow undoing swap in {}: {}", id, e))?;
+        if pool.token_a == from {
+            (pool.reserve_a, pool.reserve_b) = (reserve_in, reserve_out);
+        } else {
+            (pool.reserve_b, pool.reserve_a) = (reserve_in, reserve_out);
+        }
+        pool.k_last = root_k(&pool);
+        self.pools.insert(id, pool);
+
+        drop(guard_release);
+        Ok(())
+    }
+
+    /// Swap along the best route of up to `MAX_ROUTE_HOPS` pools; `min_received`
+    /// bounds the final output only
+    pub fn execute_routed_swap(&mut self, request: SwapRequest) -> Result<SwapResponse, String> {
+        let guard_release = self.enter()?;
+        let route = self.find_best_route(&request.from, &request.to, request.amount)?;
+        let response = self.settle_route(&request, route);
+
+        drop(guard_release);
+        response
+    }
+
+    /// Best-output route from `from` to `to` across pools with liquidity.
+    /// Ties go to the route with fewer hops.
+    pub fn find_best_route(&self, from: &str, to: &str, amount_in: Amount) -> Result<SwapRoute, String> {
+        if from == to {
+            return Err("Cannot swap a token for itself".to_string());
+        }
+
+        let mut neighbours: std::collections::HashMap<&str, Vec<&str>> = std::collections::HashMap::new();
+        for pool in self.pools.values().filter(|pool| pool.has_liquidity()) {
+            neighbours.entry(pool.token_a.as_str()).or_default().push(pool.token_b.as_str());
+            neighbours.entry(pool.token_b.as_str()).or_default().push(pool.token_a.as_str());
+        }
+        // Deterministic search order, so equal quotes always pick the same route
+        for tokens in neighbours.values_mut() {
+            tokens.sort_unstable();
+        }
+
+        let mut best: Option<SwapRoute> = None;
+        let mut pending: Vec<Vec<&str>> = vec![vec![from]];
+        while let Some(path) = pending.pop() {
+            let last = path[path.len() - 1];
+            if last == to {
+                let tokens: Vec<String> = path.iter().map(|token| token.to_string()).collect();
+                // Paths that cannot absorb the amount are skipped
+                if let Ok(route) = self.quote_path(&tokens, amount_in) {
+                    let better = best.as_ref().is_none_or(|current| {
+                        route.amount_out > current.amount_out
+                            || (route.amount_out == current.amount_out && route.pools.len() < current.pools.len())
+                    });
+                    if better {
+                        best = Some(route);
+                    }
+                }
+                continue;
+            }
+            if path.len() > MAX_ROUTE_HOPS {
+                continue;
+            }
+            for next in neighbours.get(last).into_iter().flatten() {
+                if !path.contains(next) {
+                    let mut extended = path.clone();
+                    extended.push(next);
+                    pending.push(extended);
                 }
             }
         }
-        
-        let guard_release = GuardRelease {
-            guard: Arc::clone(&self.reentrancy_guard),
-        };
-        
-        let pool_id = format!("{}_{}", request.from, request.to);
-        
-        // ✅ CHECKS: Get pool for validation (immutable borrow)
-        let (reserve_a, reserve_b) = {
-            let pool = self.pools.get(&pool_id)
-                .ok_or("Pool not found")?;
-            (pool.reserve_a, pool.reserve_b)
-        };
 
-        // ✅ CHECKS: Validate input
-        if request.amount <= 0.0 {
+        best.ok_or_else(|| format!("No route from {} to {}", from, to))
+    }
+
+    /// Price a swap along `path` (tokens, input first) against the current reserves
+    pub fn quote_path(&self, path: &[String], amount_in: Amount) -> Result<SwapRoute, String> {
+        if path.len() < 2 || path.len() > MAX_ROUTE_HOPS + 1 {
+            return Err(format!("A route crosses between 1 and {} pools", MAX_ROUTE_HOPS));
+        }
+        // ✅ CHECKS: Validate input (amounts are unsigned fixed-point, only zero is invalid)
+        if amount_in.is_zero() {
             return Err("Invalid swap amount".to_string());
         }
 
-        if request.min_received < 0.0 {
-            return Err("Invalid minimum received amount".to_string());
+        let mut pools: Vec<String> = Vec::with_capacity(path.len() - 1);
+        let mut amounts = vec![amount_in];
+        let mut spot_price = 1.0;
+        for hop in path.windows(2) {
+            let id = pool_id(&hop[0], &hop[1]);
+            // Each pool is priced once, so the quote matches sequential execution
+            if pools.contains(&id) {
+                return Err(format!("Route crosses pool {} twice", id));
+            }
+            let (reserve_in, reserve_out) = self.pools.get(&id)
+                .and_then(|pool| pool.reserves_for(&hop[0]))
+                .ok_or_else(|| format!("Pool {} not found", id))?;
+
+            // ✅ CHECKS: Calculate swap output using Constant Product Market Maker formula
+            let amount_out = self.calculate_swap_output(reserve_in, reserve_out, amounts[amounts.len() - 1])?;
+            spot_price *= reserve_out.ratio(reserve_in);
+            amounts.push(amount_out);
+            pools.push(id);
         }
 
-        // ✅ CHECKS: Calculate swap output using Constant Product Market Maker formula
-        let amount_out = self.calculate_swap_output(
-            reserve_a, 
-            reserve_b, 
-            request.amount
-        )?;
+        let amount_out = amounts[amounts.len() - 1];
+        Ok(SwapRoute {
+            path: path.to_vec(),
+            pools,
+            amounts,
+            amount_in,
+            amount_out,
+            price_impact: calculate_price_impact(spot_price, amount_in, amount_out),
+        })
+    }
 
-        // ✅ CHECKS: Calculate price impact
-        let price_impact = self.calculate_price_impact_from_reserves(reserve_a, reserve_b, request.amount, amount_out)?;
+    /// Smallest input of `from` that buys at least `amount_out` of `to` in their
+    /// pool at the current reserves
+    pub fn quote_exact_output(&self, from: &str, to: &str, amount_out: Amount) -> Result<Amount, String> {
+        if amount_out.is_zero() {
+            return Err("Invalid swap amount".to_string());
+        }
+        let id = pool_id(from, to);
+        let (reserve_in, reserve_out) = self.pools.get(&id)
+            .and_then(|pool| pool.reserves_for(from))
+            .ok_or_else(|| format!("Pool {} not found", id))?;
+        if amount_out >= reserve_out {
+            return Err("Insufficient liquidity".to_string());
+        }
 
-        // ✅ CHECKS: Check slippage protection
-        if amount_out < request.min_received {
-            return Err(format!("Slippage too high. Expected at least {}, got {}", 
-                request.min_received, amount_out));
+        // The output grows with the input: double an upper bound, then bisect
+        let buys = |amount_in: u128| {
+            self.calculate_swap_output(reserve_in, reserve_out, Amount::from_raw(amount_in))
+                .is_ok_and(|out| out >= amount_out)
+        };
+        let mut high = amount_out.raw();
+        while !buys(high) {
+            high = high.checked_mul(2).ok_or("Insufficient liquidity")?;
+        }
+        let mut low = 0u128;
+        while high - low > 1 {
+            let mid = low + (high - low) / 2;
+            if buys(mid) {
+                high = mid;
+            } else {
+                low = mid;
+            }
         }
+        Ok(Amount::from_raw(high))
+    }
+
+    // ✅ SECURITY FIX VULN-006: Checks-effects-interactions for a quoted route
+    // (caller holds the reentrancy guard)
+    fn settle_route(&mut self, request: &SwapRequest, route: SwapRoute) -> Result<SwapResponse, String> {
+        // Create transaction with timestamp for uniqueness
+        let timestamp = chrono::Utc::now().timestamp() as u64;
+
+        // ✅ CHECKS: Deadline and nonce (front-running and replay protection)
+        check_deadline(request.deadline, timestamp)?;
+        self.nonces.check(&request.user, request.nonce)?;
 
-        // ✅ CHECKS: Check maximum slippage limit
-        let slippage_basis_points = ((request.min_received - amount_out) / request.min_received * 10000.0) as u64;
-        if slippage_basis_points > self.max_slippage {
-            return Err(format!("Slippage {}% exceeds maximum {}%", 
-                slippage_basis_points as f64 / 100.0, self.max_slippage as f64 / 100.0));
+        // ✅ CHECKS: Check slippage protection (single bound on the final output)
+        if route.amount_out < request.min_received {
+            return Err(format!("Slippage too high. Expected at least {}, got {}",
+                request.min_received, route.amount_out));
         }
 
-        // ✅ EFFECTS PHASE: Update state BEFORE external interactions
-        // Update pool reserves (state change) - now we can borrow mutably
-        {
-            let pool = self.pools.get_mut(&pool_id)
+        // ✅ EFFECTS PHASE: Every hop is applied to a copy first and checked
+        // against the constant product, so a route either settles completely
+        // or leaves all pools untouched
+        let mut updated = Vec::with_capacity(route.pools.len());
+        for (index, id) in route.pools.iter().enumerate() {
+            let before = self.pools.get(id)
                 .ok_or("Pool not found")?;
-            
-            // ✅ Update reserves atomically (within guard)
-            // ✅ SECURITY FIX VULN-005: Use safe arithmetic for reserve updates
-            use crate::utils::arithmetic::Arithmetic;
-            pool.reserve_a = Arithmetic::checked_add_f64(pool.reserve_a, request.amount, "dex_reserve_a_add")
-                .map_err(|e| format!("Arithmetic overflow in reserve_a: {}", e))?;
-            pool.reserve_b = Arithmetic::checked_sub_f64(pool.reserve_b, amount_out, "dex_reserve_b_sub")
-                .map_err(|e| format!("Arithmetic underflow in reserve_b: {}", e))?;
+            let mut pool = before.clone();
+            pool.apply_swap(&route.path[index], route.amounts[index], route.amounts[index + 1])?;
+            check_invariant(before, &pool)?;
+            pool.k_last = root_k(&pool);
+            updated.push(pool);
+        }
+        self.nonces.spend(&request.user, request.nonce);
+        for pool in updated {
+            self.oracle.record(&pool, timestamp);
+            self.pools.insert(pool.id.clone(), pool);
         }
-        
-        // Create transaction with timestamp for uniqueness
-        let timestamp = chrono::Utc::now().timestamp() as u64;
         let tx_id = format!("swap_{}_{}", timestamp, request.user);
 
-        let transaction = SwapTransaction {
+        // ✅ Update mempool (state change)
+        self.mempool.push(SwapTransaction {
             id: tx_id.clone(),
             from_token: request.from.clone(),
             to_token: request.to.clone(),
-            amount_in: request.amount,
-            amount_out,
+            amount_in: route.amount_in,
+            amount_out: route.amount_out,
             user: request.user.clone(),
             timestamp,
-        };
-        
-        // ✅ Update mempool (state change)
-        self.mempool.push(transaction.clone());
+        });
 
         // ✅ INTERACTIONS PHASE: External calls happen AFTER state updates
         // In a real implementation, token transfers would happen here
         // But since state is already updated, reentrancy is prevented
-        
-        info!("Swap executed: {} {} -> {} {} (price impact: {:.4}%)", 
-            request.amount, request.from, amount_out, request.to, price_impact * 100.0);
-        
-        // Guard is released automatically when guard_release is dropped
-        drop(guard_release);
-        
+
+        info!("Swap executed: {} {} -> {} {} via {} (price impact: {:.4}%)",
+            route.amount_in, request.from, route.amount_out, request.to,
+            route.path.join(" -> "), route.price_impact * 100.0);
+
         Ok(SwapResponse {
             success: true,
             message: "Swap executed successfully".to_string(),
             tx_hash: Some(tx_id),
-            amount_received: Some(amount_out),
-            price_impact: Some(price_impact),
+            amount_received: Some(route.amount_out),
+            price_impact: Some(route.price_impact),
+            route: Some(route.path),
+            amounts: Some(route.amounts),
         })
     }
-    
-    // ✅ Helper function to calculate price impact without borrowing pool
-    fn calculate_price_impact_from_reserves(&self, reserve_a: f64, reserve_b: f64, amount_in: f64, amount_out: f64) -> Result<f64, String> {
-        if reserve_a <= 0.0 || reserve_b <= 0.0 {
-            return Err("Invalid reserves".to_string());
-        }
-        
-        // Calculate current price (reserve_out / reserve_in)
-        // ✅ SECURITY FIX VULN-005: Use safe division
-        use crate::utils::arithmetic::Arithmetic;
-        let current_price = Arithmetic::checked_div_f64(reserve_b, reserve_a, "dex_price_impact_current_price")
-            .map_err(|e| format!("Arithmetic error in current price: {}", e))?;
-        
-        // Calculate execution price (amount_out / amount_in)
-        if amount_in <= 0.0 {
-            return Err("Invalid amount_in".to_string());
+
+    /// Advance every pool's TWAP observations at block production
+    pub fn record_block_observations(&mut self, timestamp: u64) {
+        for pool in self.pools.values() {
+            self.oracle.record(pool, timestamp);
         }
-        let execution_price = Arithmetic::checked_div_f64(amount_out, amount_in, "dex_execution_price")
-            .map_err(|e| format!("Arithmetic error in execution price: {}", e))?;
-        
-        // Calculate price impact: (current_price - execution_price) / current_price
-        if current_price <= 0.0 {
-            return Err("Invalid current price".to_string());
-        }
-        let price_diff = Arithmetic::checked_sub_f64(current_price, execution_price, "dex_price_diff")
-            .map_err(|e| format!("Arithmetic error in price diff: {}", e))?;
-        let price_impact = Arithmetic::checked_div_f64(price_diff.abs(), current_price, "dex_price_impact")
-            .map_err(|e| format!("Arithmetic error in price impact: {}", e))?;
-        
-        Ok(price_impact)
+    }
+
+    /// Time-weighted price of `base` in `quote` over `window_secs` before `now`
+    pub fn twap_price(&self, base: &str, quote: &str, window_secs: u64, now: u64) -> Result<Amount, String> {
+        let pool = self.get_pair(base, quote)
+            .ok_or_else(|| format!("Pool {} not found", pool_id(base, quote)))?;
+        let twap = self.oracle.twap(&pool.id, window_secs, now)?;
+        Ok(if pool.token_a == base { twap.price_a } else { twap.price_b })
     }
 
     /// Calculate swap output using Constant Product Market Maker formula: x * y = k
-    fn calculate_swap_output(&self, reserve_in: f64, reserve_out: f64, amount_in: f64) -> Result<f64, String> {
-        if reserve_in <= 0.0 || reserve_out <= 0.0 || amount_in <= 0.0 {
+    fn calculate_swap_output(&self, reserve_in: Amount, reserve_out: Amount, amount_in: Amount) -> Result<Amount, String> {
+        if reserve_in.is_zero() || reserve_out.is_zero() || amount_in.is_zero() {
             return Err("Invalid reserve or amount values".to_string());
         }
 
-        // Apply fee: amount_in_with_fee = amount_in * (1 - fee_rate)
-        let fee_multiplier = 10000 - self.fee_rate; // Convert to basis points
-        // ✅ SECURITY FIX VULN-005: Use safe arithmetic for fee calculation
-        use crate::utils::arithmetic::Arithmetic;
-        let fee_ratio = Arithmetic::checked_div_f64(fee_multiplier as f64, 10000.0, "dex_fee_ratio")
-            .map_err(|e| format!("Arithmetic error in fee ratio: {}", e))?;
-        let amount_in_with_fee = Arithmetic::checked_mul_f64(amount_in, fee_ratio, "dex_amount_in_with_fee")
-            .map_err(|e| format!("Arithmetic overflow in amount_in_with_fee: {}", e))?;
+        // Apply fee: amount_in_with_fee = amount_in * (10000 - fee_rate) / 10000
+        let fee_multiplier = 10000u64.saturating_sub(self.fee_rate);
+        let amount_in_with_fee = amount_in.mul_bps(fee_multiplier)
+            .map_err(|e| format!("Arithmetic error in amount_in_with_fee: {}", e))?;
 
         // Constant product formula: (reserve_in + amount_in_with_fee) * (reserve_out - amount_out) = reserve_in * reserve_out
         // Solving for amount_out: amount_out = (reserve_out * amount_in_with_fee) / (reserve_in + amount_in_with_fee)
-        let numerator = Arithmetic::checked_mul_f64(reserve_out, amount_in_with_fee, "dex_swap_numerator")
-            .map_err(|e| format!("Arithmetic overflow in swap numerator: {}", e))?;
-        let denominator = Arithmetic::checked_add_f64(reserve_in, amount_in_with_fee, "dex_swap_denominator")
+        // Rounded down, so the pool never pays out more than the invariant allows
+        let denominator = reserve_in.checked_add(amount_in_with_fee)
             .map_err(|e| format!("Arithmetic overflow in swap denominator: {}", e))?;
-        
-        if denominator == 0.0 {
-            return Err("Division by zero".to_string());
-        }
-
-        let amount_out = numerator / denominator;
+        let amount_out = reserve_out.mul_div(amount_in_with_fee, denominator)
+            .map_err(|e| format!("Arithmetic error in swap output: {}", e))?;
 
         // Ensure we don't drain the pool
         if amount_out >= reserve_out {
@@ -360,92 +697,618 @@ impl DEX {
         Ok(amount_out)
     }
 
-    /// Calculate price impact of a swap
-    #[allow(dead_code)] // ✅ SECURITY FIX: Allow dead code (used in tests or future features)
-    fn calculate_price_impact(&self, pool: &Pool, amount_in: f64, amount_out: f64) -> Result<f64, String> {
-        // Calculate current price (reserve_out / reserve_in)
-        if pool.reserve_a <= 0.0 {
-            return Err("Division by zero".to_string());
-        }
+    /// Deposit into an existing pool; amounts follow the request's token order
+    pub fn add_liquidity(&mut self, request: LiquidityRequest) -> Result<LiquidityResponse, String> {
+        // ✅ SECURITY FIX VULN-006: Reentrancy protection for add_liquidity
+        let guard_release = self.enter()?;
 
-        // ✅ SECURITY FIX VULN-005: Use safe division
-        use crate::utils::arithmetic::Arithmetic;
-        let current_price = Arithmetic::checked_div_f64(pool.reserve_b, pool.reserve_a, "dex_current_price")
-            .map_err(|e| format!("Arithmetic error in current price: {}", e))?;
-        
-        // Calculate execution price (amount_out / amount_in)
-        if amount_in <= 0.0 {
-            return Err("Division by zero".to_string());
-        }
+        let pool_id = pool_id(&request.token_a, &request.token_b);
 
-        let execution_price = Arithmetic::checked_div_f64(amount_out, amount_in, "dex_price_impact_execution_price")
-            .map_err(|e| format!("Arithmetic error in execution price: {}", e))?;
+        // ✅ CHECKS: Price the deposit against the current reserves (in pool order)
+        let (flipped, deposit) = {
+            let pool = self.pools.get(&pool_id)
+                .ok_or("Pool not found")?;
+            let flipped = pool.token_a != request.token_a;
+            let (amount_a, amount_b) = if flipped {
+                (request.amount_b, request.amount_a)
+            } else {
+                (request.amount_a, request.amount_b)
+            };
+            (flipped, quote_add_liquidity(pool, amount_a, amount_b)?)
+        };
+        let (amount_a, amount_b, minted) = deposit;
 
-        // Calculate price impact: (current_price - execution_price) / current_price
-        if current_price <= 0.0 {
-            return Err("Division by zero".to_string());
-        }
+        // ✅ EFFECTS: Applied to a copy, installed once the invariant holds
+        let lp_balance = {
+            let before = self.pools.get(&pool_id)
+                .ok_or("Pool not found")?;
+            let mut pool = before.clone();
+            if pool.total_liquidity.is_zero() {
+                pool.lp_balances.insert(LOCKED_LIQUIDITY_HOLDER.to_string(), MINIMUM_LIQUIDITY);
+                pool.total_liquidity = MINIMUM_LIQUIDITY;
+            }
+            pool.reserve_a = pool.reserve_a.checked_add(amount_a)
+                .map_err(|e| format!("Arithmetic overflow in reserve_a: {}", e))?;
+            pool.reserve_b = pool.reserve_b.checked_add(amount_b)
+                .map_err(|e| format!("Arithmetic overflow in reserve_b: {}", e))?;
+            pool.total_liquidity = pool.total_liquidity.checked_add(minted)
+                .map_err(|e| format!("Arithmetic overflow in total liquidity: {}", e))?;
+            let lp_balance = pool.lp_balance_of(&request.user).saturating_add(minted);
+            pool.lp_balances.insert(request.user.clone(), lp_balance);
+            check_invariant(before, &pool)?;
+            pool.k_last = root_k(&pool);
+            self.oracle.record(&pool, chrono::Utc::now().timestamp() as u64);
+            self.pools.insert(pool_id.clone(), pool);
+            lp_balance
+        };
 
-        let price_impact = (current_price - execution_price) / current_price;
-        
-        // Ensure price impact is positive (price goes down when selling)
-        Ok(price_impact.abs())
+        info!("Liquidity added to {}: {} + {} -> {} LP", pool_id, amount_a, amount_b, minted);
+
+        // Guard is released automatically when guard_release is dropped
+        drop(guard_release);
+
+        let (amount_a, amount_b) = if flipped { (amount_b, amount_a) } else { (amount_a, amount_b) };
+        Ok(LiquidityResponse {
+            success: true,
+            message: "Liquidity added successfully".to_string(),
+            tx_hash: Some(format!("liq_{}_{}", chrono::Utc::now().timestamp(), request.user)),
+            lp_tokens_minted: Some(minted),
+            lp_tokens_burned: None,
+            amount_a: Some(amount_a),
+            amount_b: Some(amount_b),
+            lp_balance: Some(lp_balance),
+        })
     }
-    
-    pub fn add_liquidity(&mut self, request: LiquidityRequest) -> Result<LiquidityResponse, String> {
-        // ✅ SECURITY FIX VULN-006: Reentrancy protection for add_liquidity
-        // Check emergency pause first
-        self.check_emergency_pause()?;
-        
-        // ✅ SECURITY FIX: Check reentrancy guard
-        self.check_reentrancy()?;
-        
-        // ✅ Set reentrancy guard BEFORE any state changes
-        self.set_reentrancy_guard(true)?;
-        
-        // Use defer-like pattern to ensure guard is released
-        struct GuardRelease {
-            guard: Arc<Mutex<bool>>,
-        }
-        
-        impl Drop for GuardRelease {
-            fn drop(&mut self) {
-                if let Ok(mut g) = self.guard.lock() {
-                    *g = false;
-                }
+
+    /// Burn LP shares for their pro-rata part of both reserves. Swap fees stay in
+    /// the reserves, so the payout includes the fees accrued since the deposit.
+    pub fn remove_liquidity(&mut self, request: RemoveLiquidityRequest) -> Result<LiquidityResponse, String> {
+        // ✅ SECURITY FIX VULN-006: Reentrancy protection for remove_liquidity
+        let guard_release = self.enter()?;
+
+        let pool_id = pool_id(&request.token_a, &request.token_b);
+
+        // ✅ CHECKS: Shares owned and payout against the current reserves
+        let (flipped, amount_a, amount_b) = {
+            let pool = self.pools.get(&pool_id)
+                .ok_or("Pool not found")?;
+            if request.lp_tokens.is_zero() {
+                return Err("Invalid LP token amount".to_string());
             }
-        }
-        
-        let guard_release = GuardRelease {
-            guard: Arc::clone(&self.reentrancy_guard),
+            let owned = pool.lp_balance_of(&request.user);
+            if request.lp_tokens > owned {
+                return Err(format!("Insufficient LP balance. Available: {}, Required: {}",
+                    owned, request.lp_tokens));
+            }
+            let (amount_a, amount_b) = quote_remove_liquidity(pool, request.lp_tokens)?;
+            (pool.token_a != request.token_a, amount_a, amount_b)
         };
-        
-        let pool_id = format!("{}_{}", request.token_a, request.token_b);
-        
-        // ✅ SECURITY FIX VULN-005: Use SafeMath for liquidity calculation
-        use crate::utils::safe_math::SafeMath;
-        let total_liquidity = SafeMath::f64_add(request.amount_a, request.amount_b, "add_liquidity_total")
-            .map_err(|e| format!("Arithmetic overflow in liquidity calculation: {}", e))?;
-        
-        let pool = Pool {
-            id: pool_id.clone(),
-            token_a: request.token_a.clone(),
-            token_b: request.token_b.clone(),
-            reserve_a: request.amount_a,
-            reserve_b: request.amount_b,
-            total_liquidity,
+
+        // Slippage bounds follow the request's token order
+        let (paid_a, paid_b) = if flipped { (amount_b, amount_a) } else { (amount_a, amount_b) };
+        if paid_a < request.min_amount_a || paid_b < request.min_amount_b {
+            return Err(format!("Slippage too high. Expected at least {} {} and {} {}, got {} and {}",
+                request.min_amount_a, request.token_a, request.min_amount_b, request.token_b, paid_a, paid_b));
+        }
+
+        // ✅ EFFECTS: Burn the shares and release the reserves (on a copy,
+        // installed once the invariant holds)
+        let lp_balance = {
+            let before = self.pools.get(&pool_id)
+                .ok_or("Pool not found")?;
+            let mut pool = before.clone();
+            pool.reserve_a = pool.reserve_a.checked_sub(amount_a)
+                .map_err(|e| format!("Arithmetic underflow in reserve_a: {}", e))?;
+            pool.reserve_b = pool.reserve_b.checked_sub(amount_b)
+                .map_err(|e| format!("Arithmetic underflow in reserve_b: {}", e))?;
+            pool.total_liquidity = pool.total_liquidity.checked_sub(request.lp_tokens)
+                .map_err(|e| format!("Arithmetic underflow in total liquidity: {}", e))?;
+
+            let lp_balance = pool.lp_balance_of(&request.user).saturating_sub(request.lp_tokens);
+            if lp_balance.is_zero() {
+                pool.lp_balances.remove(&request.user);
+            } else {
+                pool.lp_balances.insert(request.user.clone(), lp_balance);
+            }
+            check_invariant(before, &pool)?;
+            pool.k_last = root_k(&pool);
+            self.oracle.record(&pool, chrono::Utc::now().timestamp() as u64);
+            self.pools.insert(pool_id.clone(), pool);
+            lp_balance
         };
-        
-        self.pools.insert(pool_id.clone(), pool);
-        
+
+        info!("Liquidity removed from {}: {} LP -> {} {} + {} {}",
+            pool_id, request.lp_tokens, paid_a, request.token_a, paid_b, request.token_b);
+
         // Guard is released automatically when guard_release is dropped
         drop(guard_release);
-        
+
         Ok(LiquidityResponse {
             success: true,
-            message: "Liquidity added successfully".to_string(),
-            tx_hash: Some(format!("liq_{}", chrono::Utc::now().timestamp())),
-            lp_tokens_minted: Some(total_liquidity),
+            message: "Liquidity removed successfully".to_string(),
+            tx_hash: Some(format!("liq_remove_{}_{}", chrono::Utc::now().timestamp(), request.user)),
+            lp_tokens_minted: None,
+            lp_tokens_burned: Some(request.lp_tokens),
+            amount_a: Some(paid_a),
+            amount_b: Some(paid_b),
+            lp_balance: Some(lp_balance),
+        })
+    }
+}
+
+// ✅ SECURITY FIX VULN-006: Releases the reentrancy guard when dropped (defer-like pattern)
+struct GuardRelease {
+    guard: Arc<Mutex<bool>>,
+}
+
+impl Drop for GuardRelease {
+    fn drop(&mut self) {
+        if let Ok(mut g) = self.guard.lock() {
+            *g = false;
+        }
+    }
+}
+
+/// Price impact of a swap: |spot_price - execution_price| / spot_price.
+/// Prices are ratios: the only place the DEX uses floating point.
+fn calculate_price_impact(spot_price: f64, amount_in: Amount, amount_out: Amount) -> f64 {
+    if spot_price <= 0.0 {
+        return 0.0;
+    }
+    let execution_price = amount_out.ratio(amount_in);
+    (spot_price - execution_price).abs() / spot_price
+}
+
+/// Amounts taken from a deposit and LP shares minted for it: `(amount_a, amount_b, minted)`,
+/// all in pool order.
+///
+/// The first deposit mints `sqrt(amount_a * amount_b)` minus the locked
+/// `MINIMUM_LIQUIDITY`. Later deposits are trimmed to the pool ratio (the excess
+/// of one side stays with the provider) and mint shares proportional to the
+/// smaller contribution, rounded down.
+pub fn quote_add_liquidity(pool: &Pool, amount_a: Amount, amount_b: Amount) -> Result<(Amount, Amount, Amount), String> {
+    if amount_a.is_zero() || amount_b.is_zero() {
+        return Err("Invalid liquidity amounts".to_string());
+    }
+
+    if pool.total_liquidity.is_zero() {
+        let minted = amount_a.geometric_mean(amount_b).checked_sub(MINIMUM_LIQUIDITY)
+            .ok()
+            .filter(|minted| !minted.is_zero())
+            .ok_or("Initial liquidity too small")?;
+        return Ok((amount_a, amount_b, minted));
+    }
+    let optimal_b = amount_a.mul_div(pool.reserve_b, pool.reserve_a)
+        .map_err(|e| format!("Arithmetic error in deposit ratio: {}", e))?;
+    let (used_a, used_b) = if optimal_b <= amount_b {
+        (amount_a, optimal_b)
+    } else {
+        let optimal_a = amount_b.mul_div(pool.reserve_a, pool.reserve_b)
+            .map_err(|e| format!("Arithmetic error in deposit ratio: {}", e))?;
+        (optimal_a, amount_b)
+    };
+
+    let minted_a = used_a.mul_div(pool.total_liquidity, pool.reserve_a)
+        .map_err(|e| format!("Arithmetic error in LP minting: {}", e))?;
+    let minted_b = used_b.mul_div(pool.total_liquidity, pool.reserve_b)
+        .map_err(|e| format!("Arithmetic error in LP minting: {}", e))?;
+    let minted = minted_a.min(minted_b);
+    if minted.is_zero() {
+        return Err("Liquidity amount too small".to_string());
+    }
+    Ok((used_a, used_b, minted))
+}
+
+/// Reserves paid out for burning `lp_tokens` shares: `(amount_a, amount_b)`, rounded down
+pub fn quote_remove_liquidity(pool: &Pool, lp_tokens: Amount) -> Result<(Amount, Amount), String> {
+    if lp_tokens > pool.total_liquidity {
+        return Err("LP amount exceeds pool supply".to_string());
+    }
+    let amount_a = pool.reserve_a.mul_div(lp_tokens, pool.total_liquidity)
+        .map_err(|e| format!("Arithmetic error in withdrawal: {}", e))?;
+    let amount_b = pool.reserve_b.mul_div(lp_tokens, pool.total_liquidity)
+        .map_err(|e| format!("Arithmetic error in withdrawal: {}", e))?;
+    if amount_a.is_zero() && amount_b.is_zero() {
+        return Err("Liquidity amount too small".to_string());
+    }
+    Ok((amount_a, amount_b))
+}
+
+/// DYO reserve of a pool (`None` for pairs without DYO)
+fn dyo_reserve(pool: &Pool) -> Option<Amount> {
+    if pool.token_a == NATIVE_TOKEN {
+        Some(pool.reserve_a)
+    } else if pool.token_b == NATIVE_TOKEN {
+        Some(pool.reserve_b)
+    } else {
+        None
+    }
+}
+
+/// Ledger leg mirroring a liquidity change by `provider` from pool `before` (`None`
+/// for a new pool) to `after`: the DYO the reserve gained or paid out moves between
+/// the provider and the pool account, which the gas auto-swap pays DYO out of.
+/// Deposits round up and withdrawals down, so the account never holds less than the reserve.
+pub fn liquidity_ledger_leg(before: Option<&Pool>, after: &Pool, provider: &str) -> Result<Option<Transaction>, String> {
+    let Some(reserve_after) = dyo_reserve(after) else {
+        return Ok(None);
+    };
+    let reserve_before = before.and_then(dyo_reserve).unwrap_or(Amount::ZERO);
+    let pool_id = after.id.clone();
+    let leg = if reserve_after >= reserve_before {
+        let cents = reserve_after.saturating_sub(reserve_before).round_up_to(2).and_then(Amount::to_cents)
+            .map_err(|e| format!("Invalid liquidity amount: {}", e))?;
+        (cents > 0).then(|| {
+            Transaction::system(provider.to_string(), pool_account(&pool_id), cents, None)
+                .with_kind(TxKind::AddLiquidity { pool_id })
+        })
+    } else {
+        let cents = reserve_before.saturating_sub(reserve_after).to_cents_floor()
+            .map_err(|e| format!("Invalid liquidity amount: {}", e))?;
+        (cents > 0).then(|| {
+            Transaction::system(pool_account(&pool_id), provider.to_string(), cents, None)
+                .with_kind(TxKind::RemoveLiquidity { pool_id })
         })
+    };
+    Ok(leg)
+}
+
+/// Run a liquidity change of pool `pool_id` (creation, deposit or withdrawal) and
+/// queue its DYO leg on the ledger: both or neither (the pool is put back when the
+/// ledger rejects the leg, e.g. a provider without the DYO it deposits)
+pub fn change_liquidity(
+    dex: &mut DEX,
+    blockchain: &mut Blockchain,
+    pool_id: &str,
+    provider: &str,
+    change: impl FnOnce(&mut DEX) -> Result<LiquidityResponse, String>,
+) -> Result<(LiquidityResponse, Option<Transaction>), String> {
+    let before = dex.get_pool(pool_id).cloned();
+    let response = change(dex)?;
+
+    let queued = dex
+        .get_pool(pool_id)
+        .ok_or_else(|| format!("Pool {} not found", pool_id))
+        .and_then(|after| liquidity_ledger_leg(before.as_ref(), after, provider))
+        .and_then(|leg| match leg {
+            Some(leg) => blockchain.add_transaction(leg.clone()).map(|()| Some(leg)),
+            None => Ok(None),
+        });
+    match queued {
+        Ok(leg) => Ok((response, leg)),
+        Err(e) => {
+            match before {
+                Some(pool) => dex.pools.insert(pool_id.to_string(), pool),
+                None => dex.pools.remove(pool_id),
+            };
+            Err(format!("DYO leg rejected by the ledger: {}", e))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn amount(value: &str) -> Amount {
+        value.parse().unwrap()
+    }
+
+    fn liquidity(token_a: &str, token_b: &str, amount_a: &str, amount_b: &str, user: &str) -> LiquidityRequest {
+        LiquidityRequest {
+            token_a: token_a.to_string(),
+            token_b: token_b.to_string(),
+            amount_a: amount(amount_a),
+            amount_b: amount(amount_b),
+            user: user.to_string(),
+        }
+    }
+
+    fn deposit(dex: &mut DEX, user: &str, amount_a: &str, amount_b: &str) -> Result<LiquidityResponse, String> {
+        let request = liquidity("DYO", "USDC", amount_a, amount_b, user);
+        if dex.get_pair("DYO", "USDC").is_none() {
+            return dex.create_pool(request);
+        }
+        dex.add_liquidity(request)
+    }
+
+    fn withdraw(dex: &mut DEX, user: &str, lp_tokens: Amount) -> Result<LiquidityResponse, String> {
+        dex.remove_liquidity(RemoveLiquidityRequest {
+            token_a: "DYO".to_string(),
+            token_b: "USDC".to_string(),
+            lp_tokens,
+            min_amount_a: Amount::ZERO,
+            min_amount_b: Amount::ZERO,
+            user: user.to_string(),
+        })
+    }
+
+    // Every request gets a fresh nonce, above any a test has spent
+    fn swap(from: &str, to: &str, amount_in: &str, min_received: &str) -> SwapRequest {
+        static NONCE: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+        SwapRequest {
+            from: from.to_string(),
+            to: to.to_string(),
+            amount: amount(amount_in),
+            min_received: amount(min_received),
+            user: "trader".to_string(),
+            deadline: u64::MAX,
+            nonce: NONCE.fetch_add(1, std::sync::atomic::Ordering::Relaxed),
+        }
+    }
+
+    #[test]
+    fn test_first_deposit_mints_sqrt_and_locks_minimum() {
+        let mut dex = DEX::new();
+        let response = deposit(&mut dex, "alice", "100", "400").unwrap();
+
+        let pool = dex.get_pool("DYO_USDC").unwrap();
+        assert_eq!(pool.total_liquidity, amount("200"));
+        assert_eq!(response.lp_tokens_minted, Some(amount("199.999999")));
+        assert_eq!(pool.lp_balance_of("alice"), amount("199.999999"));
+        assert_eq!(pool.lp_balance_of(LOCKED_LIQUIDITY_HOLDER), MINIMUM_LIQUIDITY);
+
+        // A deposit worth less than the locked minimum cannot open a pool
+        let mut dex = DEX::new();
+        assert!(deposit(&mut dex, "alice", "0.000001", "0.000001").is_err());
+        assert!(dex.get_pool("DYO_USDC").is_none());
+    }
+
+    #[test]
+    fn test_pool_account_mirrors_dyo_reserve_from_creation() {
+        let mut dex = DEX::new();
+        let mut blockchain = Blockchain::new();
+        blockchain.balances.insert("DUalice".to_string(), 50_000);
+        let account = pool_account("DYO_USDC");
+        let reserve_cents = |dex: &DEX| dex.get_pool("DYO_USDC").unwrap().reserve_a.to_cents_floor().unwrap();
+
+        // Creation moves the DYO side from the provider's ledger balance to the pool account
+        let (created, leg) = change_liquidity(&mut dex, &mut blockchain, "DYO_USDC", "DUalice", |dex| {
+            dex.create_pool(liquidity("USDC", "DYO", "400", "100", "DUalice"))
+        })
+        .unwrap();
+        assert_eq!(leg.unwrap().kind, TxKind::AddLiquidity { pool_id: "DYO_USDC".to_string() });
+        assert_eq!(blockchain.get_balance(&account), 10_000);
+        let fee = blockchain.transaction_fees;
+        assert_eq!(blockchain.get_balance("DUalice"), 40_000 - fee);
+
+        // The gas auto-swap's ledger leg pays its DYO out of that account
+        let received = dex.execute_swap(swap("USDC", "DYO", "40", "0")).unwrap().amount_received.unwrap();
+        let bought = received.to_cents_floor().unwrap();
+        let auto_swap = Transaction::system("DUbob".to_string(), account.clone(), 4_000, None).with_kind(TxKind::Swap {
+            pool_id: "DYO_USDC".to_string(),
+            token_in: "USDC".to_string(),
+            token_out: NATIVE_TOKEN.to_string(),
+            amount_out: bought,
+        });
+        blockchain.balances.insert("DUbob".to_string(), fee);
+        blockchain.add_transaction(auto_swap).unwrap();
+        assert_eq!(blockchain.get_balance("DUbob"), bought);
+        assert!(blockchain.get_balance(&account) >= reserve_cents(&dex));
+
+        // Withdrawing pays the DYO side back out of the account, never more than it holds
+        let lp_tokens = created.lp_balance.unwrap();
+        let (_, leg) = change_liquidity(&mut dex, &mut blockchain, "DYO_USDC", "DUalice", |dex| {
+            dex.remove_liquidity(RemoveLiquidityRequest {
+                token_a: "DYO".to_string(),
+                token_b: "USDC".to_string(),
+                lp_tokens,
+                min_amount_a: Amount::ZERO,
+                min_amount_b: Amount::ZERO,
+                user: "DUalice".to_string(),
+            })
+        })
+        .unwrap();
+        assert_eq!(leg.unwrap().from, account);
+        assert!(blockchain.get_balance(&account) >= reserve_cents(&dex));
+        assert!(blockchain.get_balance("DUalice") > 40_000 - fee);
+
+        // A provider without the DYO it deposits leaves no pool behind
+        assert!(change_liquidity(&mut dex, &mut blockchain, "DYO_ETH", "DUcarol", |dex| {
+            dex.create_pool(liquidity("DYO", "ETH", "10", "1", "DUcarol"))
+        })
+        .is_err());
+        assert!(dex.get_pool("DYO_ETH").is_none());
+        assert_eq!(blockchain.get_balance(&pool_account("DYO_ETH")), 0);
+    }
+
+    #[test]
+    fn test_later_deposits_are_proportional() {
+        let mut dex = DEX::new();
+        deposit(&mut dex, "alice", "100", "400").unwrap();
+
+        // Excess USDC beyond the 1:4 pool ratio is not taken
+        let response = deposit(&mut dex, "bob", "10", "100").unwrap();
+        assert_eq!(response.amount_a, Some(amount("10")));
+        assert_eq!(response.amount_b, Some(amount("40")));
+        assert_eq!(response.lp_tokens_minted, Some(amount("20")));
+
+        let pool = dex.get_pool("DYO_USDC").unwrap();
+        assert_eq!((pool.reserve_a, pool.reserve_b), (amount("110"), amount("440")));
+        let shares = pool.lp_balances.values().fold(Amount::ZERO, |sum, share| sum.saturating_add(*share));
+        assert_eq!(shares, pool.total_liquidity);
+    }
+
+    #[test]
+    fn test_remove_returns_share_of_reserves_with_fees() {
+        let mut dex = DEX::new();
+        deposit(&mut dex, "alice", "1000", "1000").unwrap();
+        deposit(&mut dex, "bob", "1000", "1000").unwrap();
+
+        dex.execute_swap(swap("DYO", "USDC", "100", "0")).unwrap();
+
+        let shares = dex.get_pool("DYO_USDC").unwrap().lp_balance_of("bob");
+        let response = withdraw(&mut dex, "bob", shares).unwrap();
+        let (out_a, out_b) = (response.amount_a.unwrap(), response.amount_b.unwrap());
+
+        // Half the pool, including half of the swap fee kept in the reserves
+        assert_eq!(out_a, amount("1050"));
+        assert!(out_a.geometric_mean(out_b) > amount("1000"));
+        assert_eq!(response.lp_balance, Some(Amount::ZERO));
+        assert_eq!(dex.get_pool("DYO_USDC").unwrap().lp_balance_of("bob"), Amount::ZERO);
+    }
+
+    #[test]
+    fn test_remove_checks_balance_and_slippage() {
+        let mut dex = DEX::new();
+        deposit(&mut dex, "alice", "100", "100").unwrap();
+
+        assert!(withdraw(&mut dex, "bob", amount("1")).is_err());
+        assert!(withdraw(&mut dex, "alice", amount("100")).is_err());
+
+        let result = dex.remove_liquidity(RemoveLiquidityRequest {
+            token_a: "DYO".to_string(),
+            token_b: "USDC".to_string(),
+            lp_tokens: amount("10"),
+            min_amount_a: amount("11"),
+            min_amount_b: Amount::ZERO,
+            user: "alice".to_string(),
+        });
+        assert!(result.is_err());
+        assert_eq!(dex.get_pool("DYO_USDC").unwrap().reserve_a, amount("100"));
+    }
+
+    #[test]
+    fn test_pool_factory_uses_ordered_pair() {
+        let mut dex = DEX::new();
+        let response = dex.create_pool(liquidity("USDC", "DYO", "400", "100", "alice")).unwrap();
+        // Amounts are reported in request order, stored in pool order
+        assert_eq!(response.amount_a, Some(amount("400")));
+
+        let pool = dex.get_pair("USDC", "DYO").unwrap();
+        assert_eq!(pool.id, "DYO_USDC");
+        assert_eq!((pool.reserve_a, pool.reserve_b), (amount("100"), amount("400")));
+
+        assert!(dex.create_pool(liquidity("DYO", "USDC", "1", "1", "bob")).is_err());
+        assert!(dex.create_pool(liquidity("DYO", "DYO", "1", "1", "bob")).is_err());
+        assert!(dex.create_pool(liquidity("DYO_X", "USDC", "1", "1", "bob")).is_err());
+    }
+
+    #[test]
+    fn test_swaps_work_in_both_directions() {
+        let mut dex = DEX::new();
+        let forward = dex.execute_swap(swap("DYO", "DYS", "1000", "0")).unwrap();
+        let received = forward.amount_received.unwrap();
+
+        let reverse = dex.execute_swap(swap("DYS", "DYO", &received.to_string(), "0")).unwrap();
+        assert_eq!(reverse.route, Some(vec!["DYS".to_string(), "DYO".to_string()]));
+        // Fees on both legs: slightly less than the original amount comes back
+        assert!(reverse.amount_received.unwrap() < amount("1000"));
+        assert_eq!(dex.get_all_pools().len(), 1);
+    }
+
+    #[test]
+    fn test_router_finds_multi_hop_route() {
+        let mut dex = DEX::new();
+        dex.create_pool(liquidity("DYO", "USDC", "1000000", "1000000", "alice")).unwrap();
+        dex.create_pool(liquidity("USDC", "ETH", "1000000", "500", "alice")).unwrap();
+
+        let route = dex.find_best_route("DYO", "ETH", amount("1000")).unwrap();
+        assert_eq!(route.path, vec!["DYO", "USDC", "ETH"]);
+        assert_eq!(route.pools, vec!["DYO_USDC", "ETH_USDC"]);
+
+        // Three hops: DYS -> DYO -> USDC -> ETH
+        let route = dex.find_best_route("DYS", "ETH", amount("1000")).unwrap();
+        assert_eq!(route.pools.len(), 3);
+
+        let response = dex.execute_routed_swap(swap("DYS", "ETH", "1000", "0.4")).unwrap();
+        assert_eq!(response.amount_received, Some(route.amount_out));
+        assert!(dex.find_best_route("DYO", "BTC", amount("1")).is_err());
+    }
+
+    #[test]
+    fn test_router_prefers_better_price_and_bounds_final_output() {
+        let mut dex = DEX::new();
+        // Thin direct pool vs deep two-hop path at the same price
+        dex.create_pool(liquidity("DYO", "ETH", "1000", "1", "alice")).unwrap();
+        dex.create_pool(liquidity("DYO", "USDC", "1000000", "1000000", "alice")).unwrap();
+        dex.create_pool(liquidity("ETH", "USDC", "1000", "1000000", "alice")).unwrap();
+
+        let route = dex.find_best_route("DYO", "ETH", amount("500")).unwrap();
+        assert_eq!(route.path, vec!["DYO", "USDC", "ETH"]);
+
+        // Slippage bound on the last hop rejects the whole route atomically
+        let before = dex.get_pair("DYO", "USDC").unwrap().reserve_a;
+        let too_greedy = swap("DYO", "ETH", "500", &route.amount_out.saturating_add(Amount::ONE).to_string());
+        assert!(dex.execute_routed_swap(too_greedy).is_err());
+        assert_eq!(dex.get_pair("DYO", "USDC").unwrap().reserve_a, before);
+    }
+
+    #[test]
+    fn test_swap_deadline_and_nonce() {
+        let mut dex = DEX::new();
+        let expired = SwapRequest { deadline: 1, ..swap("DYO", "DYS", "10", "0") };
+        assert!(dex.execute_swap(expired).unwrap_err().contains("deadline"));
+
+        let signed = SwapRequest { nonce: 7, ..swap("DYO", "DYS", "10", "0") };
+        dex.execute_swap(signed.clone()).unwrap();
+        assert_eq!(dex.next_nonce("trader"), 8);
+
+        // Nonces loaded from storage never lower the registry
+        dex.restore_nonces(&[("trader".to_string(), 3), ("alice".to_string(), 4)]);
+        assert_eq!(dex.next_nonce("trader"), 8);
+        assert_eq!(dex.next_nonce("alice"), 5);
+
+        // The replay is rejected before any reserve moves
+        let reserve_a = dex.get_pool("DYO_DYS").unwrap().reserve_a;
+        assert!(dex.execute_swap(signed).unwrap_err().contains("nonce"));
+        assert_eq!(dex.get_pool("DYO_DYS").unwrap().reserve_a, reserve_a);
+        assert_eq!(dex.get_pool("DYO_DYS").unwrap().k_last, dex_secured::root_k(dex.get_pool("DYO_DYS").unwrap()));
+    }
+
+    #[test]
+    fn test_restore_pool_from_storage() {
+        let mut dex = DEX::new();
+        let mut pool = dex.get_pool("DYO_DYS").unwrap().clone();
+        pool.reserve_a = amount("2000000");
+        pool.lp_balances = [("alice".to_string(), amount("400000"))].into_iter().collect();
+        dex.restore_pool(pool.clone()).unwrap();
+
+        let restored = dex.get_pool("DYO_DYS").unwrap();
+        assert_eq!(restored.reserve_a, amount("2000000"));
+        // Shares nobody is recorded for stay locked
+        assert_eq!(restored.lp_balance_of(LOCKED_LIQUIDITY_HOLDER), amount("600000"));
+
+        // Reserves below the recorded invariant are refused
+        pool.k_last = amount("2000000");
+        assert!(dex.restore_pool(pool.clone()).is_err());
+        pool.k_last = Amount::ZERO;
+        pool.id = "DYS_DYO".to_string();
+        assert!(dex.restore_pool(pool).is_err());
+    }
+
+    #[test]
+    fn test_exact_output_quote_and_undo() {
+        let mut dex = DEX::new();
+        let amount_in = dex.quote_exact_output("DYS", "DYO", amount("250")).unwrap();
+        let smaller = Amount::from_raw(amount_in.raw() - 1);
+        let quote = |amount_in| dex.quote_path(&["DYS".to_string(), "DYO".to_string()], amount_in).unwrap().amount_out;
+        assert!(quote(amount_in) >= amount("250"));
+        assert!(quote(smaller) < amount("250"));
+        assert!(dex.quote_exact_output("DYS", "DYO", amount("1000000")).is_err());
+
+        let before = dex.get_pool("DYO_DYS").unwrap().clone();
+        let response = dex.execute_swap(swap("DYS", "DYO", &amount_in.to_string(), "250")).unwrap();
+        dex.undo_swap("DYS", "DYO", amount_in, response.amount_received.unwrap()).unwrap();
+        let after = dex.get_pool("DYO_DYS").unwrap();
+        assert_eq!((after.reserve_a, after.reserve_b), (before.reserve_a, before.reserve_b));
+        assert_eq!(after.k_last, before.k_last);
+    }
+
+    #[test]
+    fn test_twap_resists_swap_right_before_read() {
+        let mut dex = DEX::new();
+        let start = chrono::Utc::now().timestamp() as u64;
+        dex.record_block_observations(start + 1_800);
+
+        // Pump DYO just before the price is read
+        dex.execute_swap(swap("DYS", "DYO", "1000000", "0")).unwrap();
+        let (spot, _) = oracle::spot_prices(dex.get_pool("DYO_DYS").unwrap());
+        assert!(spot > amount("3.9"));
+
+        assert_eq!(dex.twap_price("DYO", "DYS", 1_800, start + 1_800).unwrap(), Amount::ONE);
+        assert!(dex.twap_price("DYO", "DYS", 1_800, start + 1_810).unwrap() < amount("1.02"));
+        assert!(dex.twap_price("DYS", "DYO", 1_800, start + 1_810).unwrap() > amount("0.98"));
+        assert!(dex.twap_price("DYO", "ETH", 1_800, start + 1_810).is_err());
     }
 }
@@ -0,0 +1,231 @@
+//! Time-Weighted Average Price Oracle for DEX Pools
+//!
+//! Every pool keeps cumulative prices (`price * seconds`, both directions) that
+//! are advanced whenever its reserves change and once per produced block. The
+//! time-weighted average over a window is the difference of two cumulative
+//! values divided by the elapsed time, so a swap only moves the average in
+//! proportion to how long its price stays in the pool: moving the spot price
+//! right before a transaction barely changes the TWAP read by that transaction.
+//!
+//! Observations are stored with the price that held *after* them, and the
+//! cumulative value at any instant is interpolated from the last observation
+//! before it. Cumulative values wrap on overflow; differences stay correct.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+use crate::dex::Pool;
+use crate::utils::amount::Amount;
+
+/// TWAP window used for pricing (override with DUJYO_TWAP_WINDOW_SECS)
+pub const DEFAULT_TWAP_WINDOW_SECS: u64 = 1_800;
+
+/// Longest window that can be queried (older observations are pruned)
+pub const MAX_TWAP_WINDOW_SECS: u64 = 86_400;
+
+/// Observations kept per pool, whatever their age
+pub const MAX_OBSERVATIONS: usize = 16_384;
+
+pub fn twap_window_secs() -> u64 {
+    std::env::var("DUJYO_TWAP_WINDOW_SECS")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .filter(|window| *window > 0)
+        .unwrap_or(DEFAULT_TWAP_WINDOW_SECS)
+        .min(MAX_TWAP_WINDOW_SECS)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Observation {
+    pub timestamp: u64,
+    /// Sum of `price_a * seconds` up to `timestamp` (raw 18-decimal units, wrapping)
+    pub price_a_cumulative: u128,
+    pub price_b_cumulative: u128,
+    /// Spot prices from `timestamp` on: token_b per token_a and token_a per token_b
+    pub price_a: Amount,
+    pub price_b: Amount,
+}
+
+impl Observation {
+    /// Cumulative prices at a later instant, assuming the price held since this observation
+    fn cumulative_at(&self, timestamp: u64) -> (u128, u128) {
+        let elapsed = timestamp.saturating_sub(self.timestamp) as u128;
+        (
+            self.price_a_cumulative.wrapping_add(self.price_a.raw().wrapping_mul(elapsed)),
+            self.price_b_cumulative.wrapping_add(self.price_b.raw().wrapping_mul(elapsed)),
+        )
+    }
+}
+
+/// Average prices of one pool over a window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Twap {
+    /// token_b per token_a
+    pub price_a: Amount,
+    /// token_a per token_b
+    pub price_b: Amount,
+    /// Seconds actually covered (shorter than requested while history is short)
+    pub window_secs: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PriceOracle {
+    observations: HashMap<String, VecDeque<Observation>>,
+}
+
+impl PriceOracle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Advance the cumulative prices of `pool` to `now` and store its current spot price
+    pub fn record(&mut self, pool: &Pool, now: u64) {
+        let (price_a, price_b) = spot_prices(pool);
+        let observations = self.observations.entry(pool.id.clone()).or_default();
+
+        match observations.back_mut() {
+            // Same second: the cumulative value is unchanged, only the price moves on
+            Some(last) if now <= last.timestamp => {
+                last.price_a = price_a;
+                last.price_b = price_b;
+            }
+            Some(last) => {
+                let (price_a_cumulative, price_b_cumulative) = last.cumulative_at(now);
+                observations.push_back(Observation {
+                    timestamp: now,
+                    price_a_cumulative,
+                    price_b_cumulative,
+                    price_a,
+                    price_b,
+                });
+            }
+            None => observations.push_back(Observation {
+                timestamp: now,
+                price_a_cumulative: 0,
+                price_b_cumulative: 0,
+                price_a,
+                price_b,
+            }),
+        }
+
+        // Keep one observation at or before the oldest queryable instant
+        let cutoff = now.saturating_sub(MAX_TWAP_WINDOW_SECS);
+        while observations.len() > 1 && observations[1].timestamp <= cutoff {
+            observations.pop_front();
+        }
+        while observations.len() > MAX_OBSERVATIONS {
+            observations.pop_front();
+        }
+    }
+
+    /// Time-weighted average prices of a pool over the `window_secs` before `now`
+    pub fn twap(&self, pool_id: &str, window_secs: u64, now: u64) -> Result<Twap, String> {
+        let observations = self
+            .observations
+            .get(pool_id)
+            .filter(|observations| !observations.is_empty())
+            .ok_or_else(|| format!("No price observations for pool {}", pool_id))?;
+
+        let last = &observations[observations.len() - 1];
+        let now = now.max(last.timestamp);
+        let (end_a, end_b) = last.cumulative_at(now);
+
+        // Newest observation at or before the window start (or the oldest one)
+        let start = now.saturating_sub(window_secs.min(MAX_TWAP_WINDOW_SECS));
+        let index = observations.partition_point(|observation| observation.timestamp <= start);
+        let first = &observations[index.saturating_sub(1)];
+        let start = start.max(first.timestamp);
+        let (start_a, start_b) = first.cumulative_at(start);
+
+        let elapsed = now - start;
+        if elapsed == 0 {
+            return Err(format!("Not enough price history for pool {}", pool_id));
+        }
+        Ok(Twap {
+            price_a: Amount::from_raw(end_a.wrapping_sub(start_a) / elapsed as u128),
+            price_b: Amount::from_raw(end_b.wrapping_sub(start_b) / elapsed as u128),
+            window_secs: elapsed,
+        })
+    }
+
+    pub fn observation_count(&self, pool_id: &str) -> usize {
+        self.observations.get(pool_id).map_or(0, VecDeque::len)
+    }
+
+    pub fn last_update(&self, pool_id: &str) -> Option<u64> {
+        self.observations.get(pool_id).and_then(|observations| observations.back()).map(|last| last.timestamp)
+    }
+}
+
+/// (token_b per token_a, token_a per token_b); zero for an empty pool
+pub fn spot_prices(pool: &Pool) -> (Amount, Amount) {
+    (
+        pool.reserve_b.checked_div(pool.reserve_a).unwrap_or(Amount::ZERO),
+        pool.reserve_a.checked_div(pool.reserve_b).unwrap_or(Amount::ZERO),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(reserve_a: u64, reserve_b: u64) -> Pool {
+        Pool {
+            id: "DYO_DYS".to_string(),
+            token_a: "DYO".to_string(),
+            token_b: "DYS".to_string(),
+            reserve_a: Amount::from_units(reserve_a),
+            reserve_b: Amount::from_units(reserve_b),
+            total_liquidity: Amount::ZERO,
+            lp_balances: HashMap::new(),
+            k_last: Amount::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_twap_weights_prices_by_time() {
+        let mut oracle = PriceOracle::new();
+        oracle.record(&pool(1_000, 1_000), 0);
+        oracle.record(&pool(1_000, 3_000), 600);
+
+        // 600s at 1.0 then 600s at 3.0
+        let twap = oracle.twap("DYO_DYS", 1_200, 1_200).unwrap();
+        assert_eq!(twap.price_a, Amount::from_units(2));
+        assert_eq!(twap.window_secs, 1_200);
+
+        // Window starting between observations is interpolated: 300s at 1.0, 600s at 3.0
+        let twap = oracle.twap("DYO_DYS", 900, 1_200).unwrap();
+        assert_eq!(twap.price_a.to_string(), "2.333333333333333333");
+    }
+
+    #[test]
+    fn test_last_second_manipulation_barely_moves_twap() {
+        let mut oracle = PriceOracle::new();
+        oracle.record(&pool(1_000, 1_000), 0);
+        for block in 1..=180 {
+            oracle.record(&pool(1_000, 1_000), block * 10);
+        }
+        // Price pumped 4x by a swap in the last block
+        oracle.record(&pool(500, 2_000), 1_800);
+
+        let twap = oracle.twap("DYO_DYS", DEFAULT_TWAP_WINDOW_SECS, 1_800).unwrap();
+        assert_eq!(twap.price_a, Amount::ONE);
+        let twap = oracle.twap("DYO_DYS", DEFAULT_TWAP_WINDOW_SECS, 1_810).unwrap();
+        assert!(twap.price_a < "1.02".parse().unwrap());
+    }
+
+    #[test]
+    fn test_short_history_and_pruning() {
+        let mut oracle = PriceOracle::new();
+        assert!(oracle.twap("DYO_DYS", 60, 0).is_err());
+
+        oracle.record(&pool(1_000, 2_000), 100);
+        assert!(oracle.twap("DYO_DYS", 60, 100).is_err());
+        let twap = oracle.twap("DYO_DYS", 600, 130).unwrap();
+        assert_eq!((twap.price_a, twap.window_secs), (Amount::from_units(2), 30));
+
+        oracle.record(&pool(1_000, 2_000), 100 + MAX_TWAP_WINDOW_SECS + 10);
+        oracle.record(&pool(1_000, 2_000), 100 + 2 * MAX_TWAP_WINDOW_SECS + 20);
+        assert_eq!(oracle.observation_count("DYO_DYS"), 2);
+    }
+}
@@ -0,0 +1,418 @@
+//! Limit Orders and Scheduled (DCA) Swaps
+//!
+//! Orders rest in the `dex_orders` table with their input escrowed from the
+//! user's balance, and a background keeper executes them against the AMM:
+//!
+//! - **Limit**: swap `amount_in` of one token for at least `min_price` of the
+//!   other per unit, until `expires_at`. When the pool can only absorb part of
+//!   the order at that price, the largest part that still meets it is filled.
+//! - **DCA**: swap `amount_in` in equal slices, one every `interval_secs`. A
+//!   slot whose price is below the optional `min_price` is skipped.
+//!
+//! Every execution is a regular `execute_swap` whose `min_received` enforces
+//! the limit, so a price that moves between planning and execution rejects the
+//! fill instead of filling it badly. What is left of an order when it is
+//! cancelled or expires is refunded.
+
+use serde::{Deserialize, Serialize};
+
+use crate::dex::DEX;
+use crate::utils::amount::Amount;
+
+/// Lifetime of an order placed without `expires_at`
+pub const DEFAULT_ORDER_LIFETIME_SECS: u64 = 7 * 86_400;
+
+/// Longest lifetime an order can ask for
+pub const MAX_ORDER_LIFETIME_SECS: u64 = 90 * 86_400;
+
+pub const MIN_DCA_INTERVAL_SECS: u64 = 60;
+pub const MAX_DCA_SLICES: u32 = 1_000;
+
+/// Smallest partial fill of a limit order, in bps of the order (the rest of the
+/// order is always fillable)
+pub const MIN_PARTIAL_FILL_BPS: u64 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OrderKind {
+    Limit,
+    Dca,
+}
+
+impl OrderKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderKind::Limit => "limit",
+            OrderKind::Dca => "dca",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "limit" => Some(OrderKind::Limit),
+            "dca" => Some(OrderKind::Dca),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OrderStatus {
+    Open,
+    Filled,
+    Cancelled,
+    Expired,
+}
+
+impl OrderStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderStatus::Open => "open",
+            OrderStatus::Filled => "filled",
+            OrderStatus::Cancelled => "cancelled",
+            OrderStatus::Expired => "expired",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "open" => Some(OrderStatus::Open),
+            "filled" => Some(OrderStatus::Filled),
+            "cancelled" => Some(OrderStatus::Cancelled),
+            "expired" => Some(OrderStatus::Expired),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PlaceOrderRequest {
+    pub kind: OrderKind,
+    pub from: String,
+    pub to: String,
+    pub amount: Amount,
+    /// Minimum `to` received per `from` (required for limit orders)
+    #[serde(default)]
+    pub min_price: Option<Amount>,
+    /// DCA: number of swaps `amount` is split into
+    #[serde(default)]
+    pub slices: Option<u32>,
+    /// DCA: seconds between swaps
+    #[serde(default)]
+    pub interval_secs: Option<u64>,
+    /// Unix seconds
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Order {
+    pub order_id: String,
+    pub user: String,
+    pub kind: OrderKind,
+    pub from_token: String,
+    pub to_token: String,
+    pub amount_in: Amount,
+    /// Input still escrowed
+    pub remaining: Amount,
+    /// Output received by all fills so far
+    pub amount_out: Amount,
+    /// Minimum `to_token` per `from_token`; zero accepts any price (DCA only)
+    pub min_price: Amount,
+    /// Input of one execution (the whole order for limit orders)
+    pub slice_amount: Amount,
+    pub interval_secs: u64,
+    pub next_execution_at: u64,
+    pub expires_at: u64,
+    pub status: OrderStatus,
+    pub created_at: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderFill {
+    pub fill_id: String,
+    pub order_id: String,
+    pub amount_in: Amount,
+    pub amount_out: Amount,
+    pub executed_at: u64,
+}
+
+/// Order progress carried by `DexUpdate` websocket messages
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderEvent {
+    pub order_id: String,
+    pub user: String,
+    pub status: OrderStatus,
+    /// This fill (or refund) only
+    pub amount_in: Amount,
+    pub amount_out: Amount,
+    pub remaining: Amount,
+}
+
+/// What the keeper should do with a due order
+#[derive(Debug, Clone, PartialEq)]
+pub enum FillPlan {
+    /// Swap `amount_in` now, receiving at least `min_received`
+    Execute { amount_in: Amount, min_received: Amount },
+    /// Limit price not reached: leave the order resting
+    Wait,
+    /// DCA slot priced below the limit: move on to the next interval
+    Skip,
+}
+
+impl Order {
+    pub fn new(order_id: String, user: String, request: &PlaceOrderRequest, now: u64) -> Result<Self, String> {
+        if request.from == request.to {
+            return Err("Cannot swap a token for itself".to_string());
+        }
+        if request.amount.is_zero() {
+            return Err("Invalid order amount".to_string());
+        }
+        // Escrow and fills are stored in micro units
+        request.amount.to_micro().map_err(|_| "Order amounts have at most 6 decimals".to_string())?;
+
+        let (slice_amount, interval_secs, min_price, default_expiry) = match request.kind {
+            OrderKind::Limit => {
+                let min_price = request.min_price.filter(|price| !price.is_zero())
+                    .ok_or("Limit orders need a min_price")?;
+                (request.amount, 0, min_price, now + DEFAULT_ORDER_LIFETIME_SECS)
+            }
+            OrderKind::Dca => {
+                let slices = request.slices.ok_or("DCA orders need a number of slices")?;
+                if !(2..=MAX_DCA_SLICES).contains(&slices) {
+                    return Err(format!("DCA orders have between 2 and {} slices", MAX_DCA_SLICES));
+                }
+                let interval_secs = request.interval_secs.ok_or("DCA orders need an interval")?;
+                if interval_secs < MIN_DCA_INTERVAL_SECS {
+                    return Err(format!("DCA interval must be at least {}s", MIN_DCA_INTERVAL_SECS));
+                }
+                let slice_amount = ceil_micro(request.amount, slices)?;
+                // Every slot, plus one interval of slack for the keeper
+                let schedule = interval_secs.saturating_mul(slices as u64);
+                (slice_amount, interval_secs, request.min_price.unwrap_or(Amount::ZERO), now.saturating_add(schedule))
+            }
+        };
+
+        let expires_at = request.expires_at.unwrap_or(default_expiry);
+        if expires_at <= now {
+            return Err("Order expiry must be in the future".to_string());
+        }
+        if expires_at - now > MAX_ORDER_LIFETIME_SECS {
+            return Err(format!("Orders live at most {} days", MAX_ORDER_LIFETIME_SECS / 86_400));
+        }
+
+        Ok(Self {
+            order_id,
+            user,
+            kind: request.kind,
+            from_token: request.from.clone(),
+            to_token: request.to.clone(),
+            amount_in: request.amount,
+            remaining: request.amount,
+            amount_out: Amount::ZERO,
+            min_price,
+            slice_amount,
+            interval_secs,
+            next_execution_at: now,
+            expires_at,
+            status: OrderStatus::Open,
+            created_at: now,
+        })
+    }
+
+    pub fn is_expired(&self, now: u64) -> bool {
+        now >= self.expires_at
+    }
+
+    /// Minimum output of a fill of `amount_in` at the order's limit
+    pub fn min_received(&self, amount_in: Amount) -> Result<Amount, String> {
+        amount_in.checked_mul(self.min_price).map_err(|e| e.to_string())
+    }
+
+    /// Decide the next execution against the current pool reserves
+    pub fn plan_fill(&self, dex: &DEX, now: u64) -> Result<FillPlan, String> {
+        if self.status != OrderStatus::Open || now < self.next_execution_at || self.remaining.is_zero() {
+            return Ok(FillPlan::Wait);
+        }
+
+        let path = [self.from_token.clone(), self.to_token.clone()];
+        let target = self.remaining.min(self.slice_amount);
+        let fills = |amount_in: Amount| -> Result<bool, String> {
+            let quote = dex.quote_path(&path, amount_in)?;
+            Ok(quote.amount_out >= self.min_received(amount_in)?)
+        };
+
+        if fills(target)? {
+            return Ok(FillPlan::Execute { amount_in: target, min_received: self.min_received(target)? });
+        }
+        if self.kind == OrderKind::Dca {
+            return Ok(FillPlan::Skip);
+        }
+
+        // Execution price falls as the input grows: binary search the largest
+        // micro amount that still meets the limit
+        let (mut low, mut high) = (0u64, target.to_micro().map_err(|e| e.to_string())?);
+        while high - low > 1 {
+            let mid = low + (high - low) / 2;
+            if fills(Amount::from_micro(mid))? {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        let partial = Amount::from_micro(low);
+        let min_partial = self.amount_in.mul_bps(MIN_PARTIAL_FILL_BPS).map_err(|e| e.to_string())?;
+        if partial.is_zero() || partial < min_partial {
+            return Ok(FillPlan::Wait);
+        }
+        Ok(FillPlan::Execute { amount_in: partial, min_received: self.min_received(partial)? })
+    }
+
+    /// Record an executed fill
+    pub fn apply_fill(&mut self, amount_in: Amount, amount_out: Amount, now: u64) -> Result<(), String> {
+        self.remaining = self.remaining.checked_sub(amount_in)
+            .map_err(|_| format!("Fill of {} exceeds the remaining {}", amount_in, self.remaining))?;
+        self.amount_out = self.amount_out.checked_add(amount_out).map_err(|e| e.to_string())?;
+        if self.remaining.is_zero() {
+            self.status = OrderStatus::Filled;
+        } else if self.kind == OrderKind::Dca {
+            self.next_execution_at = now.saturating_add(self.interval_secs);
+        }
+        Ok(())
+    }
+
+    /// Move a DCA order to its next slot without executing
+    pub fn skip_slot(&mut self, now: u64) {
+        self.next_execution_at = now.saturating_add(self.interval_secs);
+    }
+
+    pub fn event(&self, amount_in: Amount, amount_out: Amount) -> OrderEvent {
+        OrderEvent {
+            order_id: self.order_id.clone(),
+            user: self.user.clone(),
+            status: self.status,
+            amount_in,
+            amount_out,
+            remaining: self.remaining,
+        }
+    }
+}
+
+/// `amount / parts` rounded up to a whole micro unit, so `parts` slices cover it
+fn ceil_micro(amount: Amount, parts: u32) -> Result<Amount, String> {
+    let micro = amount.to_micro().map_err(|e| e.to_string())?;
+    Ok(Amount::from_micro(micro.div_ceil(parts as u64)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn amount(value: &str) -> Amount {
+        value.parse().unwrap()
+    }
+
+    fn request(kind: OrderKind, from: &str, to: &str, value: &str, min_price: Option<&str>) -> PlaceOrderRequest {
+        PlaceOrderRequest {
+            kind,
+            from: from.to_string(),
+            to: to.to_string(),
+            amount: amount(value),
+            min_price: min_price.map(amount),
+            slices: None,
+            interval_secs: None,
+            expires_at: None,
+        }
+    }
+
+    #[test]
+    fn test_order_validation() {
+        let limit = request(OrderKind::Limit, "DYO", "DYS", "100", None);
+        assert!(Order::new("o1".into(), "alice".into(), &limit, 1_000).is_err());
+
+        let mut dca = request(OrderKind::Dca, "DYS", "DYO", "10", None);
+        dca.slices = Some(3);
+        dca.interval_secs = Some(30);
+        assert!(Order::new("o2".into(), "alice".into(), &dca, 1_000).is_err());
+
+        dca.interval_secs = Some(3_600);
+        let order = Order::new("o2".into(), "alice".into(), &dca, 1_000).unwrap();
+        // 10 / 3 rounded up to a micro unit: three slices cover the order
+        assert_eq!(order.slice_amount, amount("3.333334"));
+        assert_eq!(order.expires_at, 1_000 + 3 * 3_600);
+
+        let dust = request(OrderKind::Limit, "DYO", "DYS", "0.0000001", Some("1"));
+        assert!(Order::new("o3".into(), "alice".into(), &dust, 1_000).is_err());
+    }
+
+    #[test]
+    fn test_limit_order_waits_then_fills_partially_and_completely() {
+        let mut dex = DEX::new();
+        // 1M DYO : 1M DYS, selling DYO for at least 1.1 DYS each
+        let sell = request(OrderKind::Limit, "DYO", "DYS", "200000", Some("1.1"));
+        let mut order = Order::new("o1".into(), "alice".into(), &sell, 0).unwrap();
+        assert_eq!(order.plan_fill(&dex, 0).unwrap(), FillPlan::Wait);
+
+        // Someone buys DYO: price rises past the limit, but not for the whole order
+        dex.execute_swap(crate::dex::SwapRequest {
+            from: "DYS".into(),
+            to: "DYO".into(),
+            amount: amount("150000"),
+            min_received: Amount::ZERO,
+            user: "bob".into(),
+            deadline: u64::MAX,
+            nonce: 0,
+        }).unwrap();
+        let FillPlan::Execute { amount_in, min_received } = order.plan_fill(&dex, 10).unwrap() else {
+            panic!("limit crossed, expected a fill");
+        };
+        assert!(amount_in < order.remaining && !amount_in.is_zero());
+
+        let response = dex.execute_swap(crate::dex::SwapRequest {
+            from: "DYO".into(),
+            to: "DYS".into(),
+            amount: amount_in,
+            min_received,
+            user: "alice".into(),
+            deadline: u64::MAX,
+            nonce: 0,
+        }).unwrap();
+        let received = response.amount_received.unwrap();
+        assert!(received >= amount_in.checked_mul(amount("1.1")).unwrap());
+        order.apply_fill(amount_in, received, 10).unwrap();
+        assert_eq!(order.status, OrderStatus::Open);
+
+        // The pool is back near the limit: the rest keeps resting
+        assert_eq!(order.plan_fill(&dex, 20).unwrap(), FillPlan::Wait);
+
+        let remaining = order.remaining;
+        order.apply_fill(remaining, Amount::ONE, 30).unwrap();
+        assert_eq!(order.status, OrderStatus::Filled);
+        assert!(order.apply_fill(Amount::ONE, Amount::ONE, 40).is_err());
+    }
+
+    #[test]
+    fn test_dca_executes_slices_on_schedule_and_skips_bad_prices() {
+        let dex = DEX::new();
+        let mut dca = request(OrderKind::Dca, "DYS", "DYO", "300", Some("0.9"));
+        dca.slices = Some(3);
+        dca.interval_secs = Some(3_600);
+        let mut order = Order::new("o1".into(), "alice".into(), &dca, 0).unwrap();
+
+        let FillPlan::Execute { amount_in, .. } = order.plan_fill(&dex, 0).unwrap() else {
+            panic!("first slice is due immediately");
+        };
+        assert_eq!(amount_in, amount("100"));
+        order.apply_fill(amount_in, amount("99"), 0).unwrap();
+        assert_eq!(order.next_execution_at, 3_600);
+        assert_eq!(order.plan_fill(&dex, 1_000).unwrap(), FillPlan::Wait);
+
+        // Price guard above the pool price: the slot is skipped, not filled
+        order.min_price = amount("2");
+        assert_eq!(order.plan_fill(&dex, 3_600).unwrap(), FillPlan::Skip);
+        order.skip_slot(3_600);
+        assert_eq!(order.next_execution_at, 7_200);
+        assert_eq!(order.remaining, amount("200"));
+    }
+}
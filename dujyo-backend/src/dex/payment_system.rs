@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::utils::amount::Amount;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum PaymentTier {
     MegaStar,  // Bad Bunny, The Weeknd - 60% stable, 30% DYO, 10% bonus
@@ -10,33 +12,39 @@ pub enum PaymentTier {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HybridPayment {
-    pub stablecoin_amount: f64,
-    pub token_amount: f64,
-    pub loyalty_bonus: f64,
+    pub stablecoin_amount: Amount,
+    pub token_amount: Amount,
+    pub loyalty_bonus: Amount,
     pub tier: PaymentTier,
 }
 
 impl HybridPayment {
-    pub fn calculate_payment(total_revenue: f64, artist_share: f64, tier: PaymentTier) -> Self {
-        let (stable_percent, token_percent, bonus_percent) = match tier {
-            PaymentTier::MegaStar => (0.6, 0.3, 0.1),
-            PaymentTier::MidTier => (0.7, 0.3, 0.0),
-            PaymentTier::Emerging => (0.8, 0.2, 0.0),
-            PaymentTier::Community => (0.9, 0.1, 0.0),
+    /// Split `artist_share_bps` of the revenue by tier; the stablecoin leg takes
+    /// the rounding remainder so the three legs always add up to the payment
+    pub fn calculate_payment(total_revenue: Amount, artist_share_bps: u64, tier: PaymentTier) -> Self {
+        let (token_bps, bonus_bps) = match tier {
+            PaymentTier::MegaStar => (3_000, 1_000),
+            PaymentTier::MidTier => (3_000, 0),
+            PaymentTier::Emerging => (2_000, 0),
+            PaymentTier::Community => (1_000, 0),
         };
 
-        let total_payment = total_revenue * artist_share;
+        let total_payment = total_revenue.mul_bps(artist_share_bps).unwrap_or(Amount::ZERO);
+        let token_amount = total_payment.mul_bps(token_bps).unwrap_or(Amount::ZERO);
+        let loyalty_bonus = total_payment.mul_bps(bonus_bps).unwrap_or(Amount::ZERO);
 
         HybridPayment {
-            stablecoin_amount: total_payment * stable_percent,
-            token_amount: total_payment * token_percent,
-            loyalty_bonus: total_payment * bonus_percent,
+            stablecoin_amount: total_payment.saturating_sub(token_amount).saturating_sub(loyalty_bonus),
+            token_amount,
+            loyalty_bonus,
             tier,
         }
     }
 
-    pub fn get_total_amount(&self) -> f64 {
-        self.stablecoin_amount + self.token_amount + self.loyalty_bonus
+    pub fn get_total_amount(&self) -> Amount {
+        self.stablecoin_amount
+            .saturating_add(self.token_amount)
+            .saturating_add(self.loyalty_bonus)
     }
 }
 
@@ -55,11 +63,11 @@ impl PaymentManager {
     pub fn process_payment(
         &mut self,
         artist_address: String,
-        total_revenue: f64,
-        artist_share: f64,
+        total_revenue: Amount,
+        artist_share_bps: u64,
         tier: PaymentTier,
     ) -> HybridPayment {
-        let payment = HybridPayment::calculate_payment(total_revenue, artist_share, tier);
+        let payment = HybridPayment::calculate_payment(total_revenue, artist_share_bps, tier);
 
         self.payments
             .entry(artist_address)
@@ -4,8 +4,10 @@
 use serde::{Deserialize, Serialize};
 use crate::gas::creative_gas_engine::{CreativeGasEngine, GasQuote, TransactionType, UserTier};
 
+/// Solicitud de gas para una operación (no es una transacción del ledger:
+/// lo que se ejecuta en cadena es un `blockchain::Transaction` con su `TxKind`)
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Transaction {
+pub struct GasRequest {
     pub id: String,
     pub transaction_type: TransactionType,
     pub user_id: String,
@@ -35,7 +37,7 @@ pub enum GasError {
 
 /// Maneja una transacción con auto-swap automático si es necesario
 pub async fn handle_transaction_with_auto_swap(
-    tx: Transaction,
+    tx: GasRequest,
     user_id: &str,
     user_tier: &UserTier,
     user_dyo_balance: f64,
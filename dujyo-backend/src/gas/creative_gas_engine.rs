@@ -141,6 +141,12 @@ impl CreativeGasEngine {
         self.dyo_price_usd = price_usd;
     }
     
+    /// Actualiza el precio de DYO con el TWAP del pool DYO/DYS (nunca con el precio spot,
+    /// que un solo swap puede mover justo antes de una transacción)
+    pub fn update_dyo_price_from_oracle(&mut self, dex: &crate::dex::DEX, now: u64) {
+        self.update_dyo_price(crate::blockchain::gas_fees::GasFeeCalculator::dyo_price_usd(dex, now).to_f64());
+    }
+    
     /// Calcula el gas fee para una transacción
     pub fn calculate_gas(
         &mut self,
@@ -0,0 +1,730 @@
+//! On-chain Governance
+//!
+//! One subsystem for protocol changes. A proposal carries an executable action
+//! and locks a DYO deposit in `GOVERNANCE_ESCROW_ACCOUNT`. Votes are signed
+//! `GovernanceVote` ledger transactions (one per voter and proposal). Voting
+//! power is the DYO bonded in the staking system (the staked balance that
+//! `/stake` and delegations move DYO into) plus the CPV creative and community
+//! scores, snapshotted when the proposal is created and stored with it, so a
+//! stake moved from one voter to another only counts once and every node
+//! tallies the same power.
+//!
+//! Periods are measured in blocks: the votes in blocks up to the proposal's end
+//! height are tallied once the chain reaches it. A proposal that reaches quorum
+//! and the approval threshold waits out a timelock of blocks and then changes
+//! the live parameters: the CPV lambdas, the gas fee
+//! config of a transaction type or the Stream-to-Earn rates. The deposit goes
+//! back to the proposer once quorum is reached, whatever the outcome, and is
+//! burned otherwise. A proposal records that its deposit was released, and is
+//! stored together with the escrow or release transaction, so a deposit is
+//! never released twice.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+use crate::blockchain::blockchain::{Block, Transaction};
+use crate::blockchain::gas_fees::{GasFeeCalculator, GasFeeConfig};
+use crate::blockchain::ledger::{TxKind, BURN_ACCOUNT, GOVERNANCE_ESCROW_ACCOUNT};
+use crate::consensus::cpv::CPVConsensus;
+use crate::routes::stream_earn::S2ERates;
+
+/// Minimum proposal deposit (ledger cents: 1,000 DYO)
+pub const MIN_PROPOSAL_DEPOSIT: u64 = 100_000;
+/// About 7 days of 30 second blocks
+const DEFAULT_VOTING_PERIOD_BLOCKS: u64 = 20_160;
+/// About 2 days of 30 second blocks
+const DEFAULT_TIMELOCK_BLOCKS: u64 = 5_760;
+const BPS: u128 = 10_000;
+/// How far the CPV lambdas may be from adding up to exactly 1
+const LAMBDA_SUM_TOLERANCE: f64 = 1e-9;
+
+// ============================================================================
+// PARAMETERS
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GovernanceParams {
+    /// Minimum deposit in ledger cents
+    pub min_deposit: u64,
+    /// Blocks a proposal is open for voting
+    pub voting_period_blocks: u64,
+    /// Blocks between the end of a passing vote and its execution
+    pub timelock_blocks: u64,
+    /// Share of the total voting power that has to vote (bps)
+    pub quorum_bps: u64,
+    /// Share of the power voting for or against that has to be for (bps, strictly above)
+    pub threshold_bps: u64,
+    /// Voting power (ledger cents) per point of CPV creative / community score
+    pub creative_power_per_point: u64,
+    pub community_power_per_point: u64,
+}
+
+impl Default for GovernanceParams {
+    fn default() -> Self {
+        Self {
+            min_deposit: MIN_PROPOSAL_DEPOSIT,
+            voting_period_blocks: DEFAULT_VOTING_PERIOD_BLOCKS,
+            timelock_blocks: DEFAULT_TIMELOCK_BLOCKS,
+            quorum_bps: 2_000,
+            threshold_bps: 5_000,
+            creative_power_per_point: 1_000,
+            community_power_per_point: 1_000,
+        }
+    }
+}
+
+impl GovernanceParams {
+    /// Defaults, with the voting period and timelock from DUJYO_GOVERNANCE_VOTING_BLOCKS
+    /// and DUJYO_GOVERNANCE_TIMELOCK_BLOCKS when set
+    pub fn from_env() -> Self {
+        let blocks = |name: &str, default: u64| {
+            std::env::var(name)
+                .ok()
+                .and_then(|value| value.parse::<u64>().ok())
+                .unwrap_or(default)
+        };
+        Self {
+            voting_period_blocks: blocks("DUJYO_GOVERNANCE_VOTING_BLOCKS", DEFAULT_VOTING_PERIOD_BLOCKS).max(1),
+            timelock_blocks: blocks("DUJYO_GOVERNANCE_TIMELOCK_BLOCKS", DEFAULT_TIMELOCK_BLOCKS),
+            ..Self::default()
+        }
+    }
+}
+
+// ============================================================================
+// ACTIONS
+// ============================================================================
+
+/// What an executed proposal changes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GovernanceAction {
+    /// New CPV weights (each between 0 and 1, adding up to 1)
+    ConsensusWeights {
+        lambda_economic: f64,
+        lambda_creative: f64,
+        lambda_community: f64,
+    },
+    /// Replace the gas fee config of `config.transaction_type`
+    GasFee { config: GasFeeConfig },
+    /// New Stream-to-Earn rates
+    StreamEarnRates { rates: S2ERates },
+}
+
+/// Live parameters a proposal can change
+pub struct ParameterTargets<'a> {
+    pub consensus: &'a mut CPVConsensus,
+    pub gas_fees: &'a mut GasFeeCalculator,
+    pub s2e_rates: &'a mut S2ERates,
+}
+
+impl GovernanceAction {
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            GovernanceAction::ConsensusWeights { lambda_economic, lambda_creative, lambda_community } => {
+                let weights = [*lambda_economic, *lambda_creative, *lambda_community];
+                if weights.iter().any(|weight| !weight.is_finite() || *weight <= 0.0 || *weight >= 1.0) {
+                    return Err("each lambda must be between 0 and 1".to_string());
+                }
+                if (weights.iter().sum::<f64>() - 1.0).abs() > LAMBDA_SUM_TOLERANCE {
+                    return Err("lambdas must add up to 1".to_string());
+                }
+                Ok(())
+            }
+            GovernanceAction::GasFee { config } => config.validate(),
+            GovernanceAction::StreamEarnRates { rates } => rates.validate(),
+        }
+    }
+
+    /// Change the live parameters. Validated again: nothing half-applies.
+    pub fn execute(&self, targets: ParameterTargets<'_>) -> Result<(), String> {
+        self.validate()?;
+        match self {
+            GovernanceAction::ConsensusWeights { lambda_economic, lambda_creative, lambda_community } => {
+                targets.consensus.lambda_economic = *lambda_economic;
+                targets.consensus.lambda_creative = *lambda_creative;
+                targets.consensus.lambda_community = *lambda_community;
+            }
+            GovernanceAction::GasFee { config } => {
+                targets.gas_fees.set_config(config.clone())?;
+            }
+            GovernanceAction::StreamEarnRates { rates } => *targets.s2e_rates = *rates,
+        }
+        Ok(())
+    }
+}
+
+// ============================================================================
+// VOTING POWER
+// ============================================================================
+
+/// DYO each address has bonded, in ledger cents: its staked balance, which
+/// holds both its own `/stake` deposits and its delegations
+pub type BondedStakes = BTreeMap<String, u64>;
+
+/// Voting power of an address, in ledger cents
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VotingPower {
+    pub staked: u64,
+    pub creative: u64,
+    pub community: u64,
+}
+
+impl VotingPower {
+    pub fn total(&self) -> u64 {
+        self.staked.saturating_add(self.creative).saturating_add(self.community)
+    }
+}
+
+fn score_power(score: f64, power_per_point: u64) -> u64 {
+    if score.is_finite() && score > 0.0 {
+        (score * power_per_point as f64) as u64
+    } else {
+        0
+    }
+}
+
+/// Bonded stake plus the scores of the address's active CPV validators
+pub fn voting_power(
+    stakes: &BondedStakes,
+    consensus: &CPVConsensus,
+    params: &GovernanceParams,
+    address: &str,
+) -> VotingPower {
+    let creative = consensus.creative_validators.get(address)
+        .filter(|validator| validator.is_active)
+        .map_or(0, |validator| score_power(validator.creative_score, params.creative_power_per_point));
+    let community = consensus.community_validators.get(address)
+        .filter(|validator| validator.is_active)
+        .map_or(0, |validator| score_power(validator.community_score, params.community_power_per_point));
+    let staked = stakes.get(address).copied().unwrap_or(0);
+    VotingPower { staked, creative, community }
+}
+
+/// Voting power of all addresses together (the quorum base)
+pub fn total_voting_power(stakes: &BondedStakes, consensus: &CPVConsensus, params: &GovernanceParams) -> u64 {
+    let staked = stakes.values().fold(0u64, |total, stake| total.saturating_add(*stake));
+    let creative = consensus.creative_validators.values()
+        .filter(|validator| validator.is_active)
+        .fold(0u64, |total, validator| {
+            total.saturating_add(score_power(validator.creative_score, params.creative_power_per_point))
+        });
+    let community = consensus.community_validators.values()
+        .filter(|validator| validator.is_active)
+        .fold(0u64, |total, validator| {
+            total.saturating_add(score_power(validator.community_score, params.community_power_per_point))
+        });
+    staked.saturating_add(creative).saturating_add(community)
+}
+
+/// Power for and against a proposal
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tally {
+    pub votes_for: u64,
+    pub votes_against: u64,
+    pub voters: u64,
+    pub total_power: u64,
+}
+
+impl Tally {
+    pub fn quorum_reached(&self, params: &GovernanceParams) -> bool {
+        let cast = self.votes_for as u128 + self.votes_against as u128;
+        self.total_power > 0 && cast * BPS >= self.total_power as u128 * params.quorum_bps as u128
+    }
+
+    pub fn approved(&self, params: &GovernanceParams) -> bool {
+        let cast = self.votes_for as u128 + self.votes_against as u128;
+        cast > 0 && self.votes_for as u128 * BPS > cast * params.threshold_bps as u128
+    }
+}
+
+/// Voting power of every address holding some, as a proposal snapshots it
+pub fn power_snapshot(stakes: &BondedStakes, consensus: &CPVConsensus, params: &GovernanceParams) -> BTreeMap<String, u64> {
+    let addresses = stakes.keys()
+        .chain(consensus.creative_validators.keys())
+        .chain(consensus.community_validators.keys());
+    let mut snapshot = BTreeMap::new();
+    for address in addresses {
+        let power = voting_power(stakes, consensus, params, address).total();
+        if power > 0 {
+            snapshot.insert(address.clone(), power);
+        }
+    }
+    snapshot
+}
+
+/// The votes on `proposal_id` in `chain` up to block `height`, voter -> support
+pub fn chain_votes(chain: &[Block], proposal_id: &str, height: u64) -> BTreeMap<String, bool> {
+    let mut votes = BTreeMap::new();
+    for block in chain.iter().take_while(|block| block.height <= height) {
+        for transaction in &block.transactions {
+            if let TxKind::GovernanceVote { proposal_id: voted, support } = &transaction.kind {
+                if voted == proposal_id {
+                    // The ledger accepts one vote per voter and proposal
+                    votes.entry(transaction.from.clone()).or_insert(*support);
+                }
+            }
+        }
+    }
+    votes
+}
+
+/// Count `votes` on `proposal` with the power its voters had when it was created
+pub fn tally(proposal: &Proposal, votes: &BTreeMap<String, bool>) -> Tally {
+    let mut tally = Tally { total_power: proposal.total_power, ..Tally::default() };
+    for (voter, support) in votes {
+        let power = proposal.voting_power.get(voter).copied().unwrap_or(0);
+        tally.voters += 1;
+        if *support {
+            tally.votes_for = tally.votes_for.saturating_add(power);
+        } else {
+            tally.votes_against = tally.votes_against.saturating_add(power);
+        }
+    }
+    tally
+}
+
+// ============================================================================
+// PROPOSALS
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProposalStatus {
+    Voting,
+    /// Passed, waiting for the timelock
+    Queued,
+    Executed,
+    /// Quorum reached, threshold not
+    Rejected,
+    /// Quorum not reached (deposit burned)
+    NoQuorum,
+    /// Passed, but the action could not be applied
+    Failed,
+}
+
+impl ProposalStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProposalStatus::Voting => "voting",
+            ProposalStatus::Queued => "queued",
+            ProposalStatus::Executed => "executed",
+            ProposalStatus::Rejected => "rejected",
+            ProposalStatus::NoQuorum => "no_quorum",
+            ProposalStatus::Failed => "failed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Proposal {
+    pub proposal_id: String,
+    pub proposer: String,
+    pub title: String,
+    pub description: String,
+    pub action: GovernanceAction,
+    /// Escrowed deposit (ledger cents)
+    pub deposit: u64,
+    pub status: ProposalStatus,
+    pub created_at: u64,
+    /// Chain height the voting power was snapshotted at
+    pub created_height: u64,
+    /// Last block whose votes count
+    pub voting_ends_height: u64,
+    /// Voting power per address at `created_height` (ledger cents)
+    pub voting_power: BTreeMap<String, u64>,
+    /// Voting power of all addresses at `created_height` (the quorum base)
+    pub total_power: u64,
+    /// Earliest execution height, once queued
+    pub executable_height: Option<u64>,
+    /// Final tally, once voting closed
+    pub tally: Option<Tally>,
+    pub executed_height: Option<u64>,
+    /// Why the execution failed
+    pub error: Option<String>,
+    /// The deposit release is in the mempool or on chain
+    #[serde(default)]
+    pub deposit_settled: bool,
+}
+
+/// Ledger transaction moving a proposal deposit into escrow. It also carries the
+/// ledger fee of the settlement, so the escrow always holds exactly the open deposits.
+pub fn deposit_escrow(proposer: &str, deposit: u64, ledger_fee: u64) -> Transaction {
+    Transaction::system(
+        proposer.to_string(),
+        GOVERNANCE_ESCROW_ACCOUNT.to_string(),
+        deposit.saturating_add(ledger_fee),
+        None,
+    )
+}
+
+/// Ledger transaction releasing the deposit of a closed proposal: back to the
+/// proposer, or to the burn sink when quorum was not reached
+pub fn deposit_settlement(proposal: &Proposal) -> Transaction {
+    let to = match proposal.status {
+        ProposalStatus::NoQuorum => BURN_ACCOUNT.to_string(),
+        _ => proposal.proposer.clone(),
+    };
+    Transaction::system(GOVERNANCE_ESCROW_ACCOUNT.to_string(), to, proposal.deposit, None)
+}
+
+pub struct Governance {
+    params: GovernanceParams,
+    proposals: BTreeMap<String, Proposal>,
+}
+
+impl Governance {
+    pub fn new(params: GovernanceParams) -> Self {
+        Self { params, proposals: BTreeMap::new() }
+    }
+
+    pub fn params(&self) -> &GovernanceParams {
+        &self.params
+    }
+
+    /// Put back a proposal loaded from storage
+    pub fn restore(&mut self, proposal: Proposal) {
+        self.proposals.insert(proposal.proposal_id.clone(), proposal);
+    }
+
+    pub fn proposal(&self, proposal_id: &str) -> Option<&Proposal> {
+        self.proposals.get(proposal_id)
+    }
+
+    /// All proposals, newest first
+    pub fn proposals(&self) -> Vec<&Proposal> {
+        let mut proposals: Vec<&Proposal> = self.proposals.values().collect();
+        proposals.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        proposals
+    }
+
+    /// Validate a new proposal created at chain `height`, with the voting power
+    /// snapshot of that height. It only opens (`open`) once its deposit is escrowed.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_proposal(
+        &self,
+        proposer: &str,
+        title: String,
+        description: String,
+        action: GovernanceAction,
+        deposit: u64,
+        now: u64,
+        height: u64,
+        voting_power: BTreeMap<String, u64>,
+    ) -> Result<Proposal, String> {
+        if title.trim().is_empty() {
+            return Err("Proposal title is required".to_string());
+        }
+        if deposit < self.params.min_deposit {
+            return Err(format!("Deposit must be at least {} cents", self.params.min_deposit));
+        }
+        action.validate()?;
+
+        Ok(Proposal {
+            proposal_id: Uuid::new_v4().to_string(),
+            proposer: proposer.to_string(),
+            title,
+            description,
+            action,
+            deposit,
+            status: ProposalStatus::Voting,
+            created_at: now,
+            created_height: height,
+            voting_ends_height: height + self.params.voting_period_blocks,
+            total_power: voting_power.values().fold(0u64, |total, power| total.saturating_add(*power)),
+            voting_power,
+            executable_height: None,
+            tally: None,
+            executed_height: None,
+            error: None,
+            deposit_settled: false,
+        })
+    }
+
+    pub fn open(&mut self, proposal: Proposal) {
+        self.proposals.insert(proposal.proposal_id.clone(), proposal);
+    }
+
+    /// Closed proposals whose deposit has not been released yet, marked as
+    /// released. Each proposal is returned once, unless `unsettle` puts it back.
+    pub fn settle_deposits(&mut self) -> Vec<Proposal> {
+        self.proposals.values_mut()
+            .filter(|proposal| proposal.status != ProposalStatus::Voting && !proposal.deposit_settled)
+            .map(|proposal| {
+                proposal.deposit_settled = true;
+                proposal.clone()
+            })
+            .collect()
+    }
+
+    /// The release of a proposal's deposit did not go through: try again on the next pass
+    pub fn unsettle(&mut self, proposal_id: &str) {
+        if let Some(proposal) = self.proposals.get_mut(proposal_id) {
+            proposal.deposit_settled = false;
+        }
+    }
+
+    /// Votes are only accepted on known proposals while the chain (at `height`)
+    /// has not reached their end height
+    pub fn check_vote(&self, proposal_id: &str, height: u64) -> Result<(), String> {
+        match self.proposals.get(proposal_id) {
+            None => Err(format!("Unknown proposal {}", proposal_id)),
+            Some(proposal) if proposal.status != ProposalStatus::Voting || height >= proposal.voting_ends_height => {
+                Err(format!("Voting on proposal {} is closed", proposal_id))
+            }
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// Close every proposal whose end height the chain (at `height`) reached,
+    /// counting it with `tally`. Returns the closed proposals (their deposits are due).
+    pub fn close_voting(&mut self, height: u64, mut tally: impl FnMut(&Proposal) -> Tally) -> Vec<Proposal> {
+        let mut closed = Vec::new();
+        for proposal in self.proposals.values_mut() {
+            if proposal.status != ProposalStatus::Voting || height < proposal.voting_ends_height {
+                continue;
+            }
+            let result = tally(proposal);
+            proposal.status = if !result.quorum_reached(&self.params) {
+                ProposalStatus::NoQuorum
+            } else if result.approved(&self.params) {
+                proposal.executable_height = Some(proposal.voting_ends_height + self.params.timelock_blocks);
+                ProposalStatus::Queued
+            } else {
+                ProposalStatus::Rejected
+            };
+            proposal.tally = Some(result);
+            closed.push(proposal.clone());
+        }
+        closed
+    }
+
+    /// Queued proposals whose timelock ended by chain `height`, oldest first
+    pub fn due(&self, height: u64) -> Vec<Proposal> {
+        let mut due: Vec<Proposal> = self.proposals.values()
+            .filter(|proposal| {
+                proposal.status == ProposalStatus::Queued
+                    && proposal.executable_height.is_some_and(|executable_height| executable_height <= height)
+            })
+            .cloned()
+            .collect();
+        due.sort_by_key(|proposal| proposal.executable_height);
+        due
+    }
+
+    /// Record the outcome of executing a queued proposal at chain `height`
+    pub fn record_execution(&mut self, proposal_id: &str, height: u64, result: Result<(), String>) -> Option<Proposal> {
+        let proposal = self.proposals.get_mut(proposal_id)
+            .filter(|proposal| proposal.status == ProposalStatus::Queued)?;
+        match result {
+            Ok(()) => {
+                proposal.status = ProposalStatus::Executed;
+                proposal.executed_height = Some(height);
+            }
+            Err(e) => {
+                proposal.status = ProposalStatus::Failed;
+                proposal.error = Some(e);
+            }
+        }
+        Some(proposal.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::gas_fees::{GasFeeModel, TransactionType};
+    use crate::consensus::cpv::CreativeValidator;
+    use crate::utils::amount::Amount;
+
+    fn params() -> GovernanceParams {
+        GovernanceParams { voting_period_blocks: 100, timelock_blocks: 50, ..GovernanceParams::default() }
+    }
+
+    fn weights(lambda_economic: f64, lambda_creative: f64, lambda_community: f64) -> GovernanceAction {
+        GovernanceAction::ConsensusWeights { lambda_economic, lambda_creative, lambda_community }
+    }
+
+    fn creative(address: &str, creative_score: f64) -> CreativeValidator {
+        CreativeValidator {
+            address: address.to_string(),
+            verified_nfts: Vec::new(),
+            creative_score,
+            royalty_earnings: 0,
+            validation_count: 0,
+            is_active: true,
+            last_validation: 0,
+        }
+    }
+
+    #[test]
+    fn test_voting_power_counts_stake_and_scores() {
+        let stakes = BondedStakes::from([("DUalice".to_string(), 5_000), ("DUbob".to_string(), 3_000)]);
+        let mut consensus = CPVConsensus::new();
+        consensus.creative_validators.insert("DUbob".to_string(), creative("DUbob", 2.5));
+        let params = params();
+
+        let bob = voting_power(&stakes, &consensus, &params, "DUbob");
+        assert_eq!(bob, VotingPower { staked: 3_000, creative: 2_500, community: 0 });
+        assert_eq!(bob.total(), 5_500);
+        assert_eq!(total_voting_power(&stakes, &consensus, &params), 10_500);
+
+        // Inactive validators keep their score but lose its voting power
+        consensus.creative_validators.get_mut("DUbob").unwrap().is_active = false;
+        assert_eq!(voting_power(&stakes, &consensus, &params, "DUbob").total(), 3_000);
+    }
+
+    fn vote_block(height: u64, votes: &[(&str, &str, bool)]) -> Block {
+        let transactions = votes.iter()
+            .map(|(voter, proposal_id, support)| {
+                Transaction::system(voter.to_string(), voter.to_string(), 0, None)
+                    .with_kind(TxKind::GovernanceVote { proposal_id: proposal_id.to_string(), support: *support })
+            })
+            .collect();
+        Block::new(height, 0, transactions, String::new(), String::new(), None)
+    }
+
+    #[test]
+    fn test_tally_counts_snapshot_power_of_chain_votes() {
+        let mut stakes = BondedStakes::new();
+        for (address, stake) in [("DUalice", 6_000), ("DUbob", 3_000), ("DUcarol", 11_000)] {
+            stakes.insert(address.to_string(), stake);
+        }
+        let consensus = CPVConsensus::new();
+        let params = params();
+        let governance = Governance::new(params.clone());
+        let snapshot = power_snapshot(&stakes, &consensus, &params);
+        let proposal = governance
+            .new_proposal("DUalice", "Weights".into(), String::new(), weights(0.5, 0.3, 0.2), MIN_PROPOSAL_DEPOSIT, 0, 10, snapshot)
+            .unwrap();
+        assert_eq!((proposal.total_power, proposal.voting_ends_height), (20_000, 110));
+
+        // 9,000 of 20,000 voted by the end height (quorum 20%), 6,000 of them for;
+        // carol's vote landed after it
+        let chain = vec![
+            vote_block(11, &[("DUalice", &proposal.proposal_id, true), ("DUcarol", "other", true)]),
+            vote_block(110, &[("DUbob", &proposal.proposal_id, false)]),
+            vote_block(111, &[("DUcarol", &proposal.proposal_id, false)]),
+        ];
+        let votes = chain_votes(&chain, &proposal.proposal_id, proposal.voting_ends_height);
+        let result = tally(&proposal, &votes);
+        assert_eq!(result, Tally { votes_for: 6_000, votes_against: 3_000, voters: 2, total_power: 20_000 });
+        assert!(result.quorum_reached(&params));
+        assert!(result.approved(&params));
+
+        // A stake moved to another voter after the snapshot does not count again
+        stakes.insert("DUbob".to_string(), 9_000);
+        stakes.insert("DUalice".to_string(), 0);
+        assert_eq!(tally(&proposal, &votes), result);
+
+        // Exactly half is not a majority; under the quorum nothing passes
+        let even = Tally { votes_for: 500, votes_against: 500, voters: 2, total_power: 2_000 };
+        assert!(!even.approved(&params));
+        let small = Tally { votes_for: 300, votes_against: 0, voters: 1, total_power: 2_000 };
+        assert!(!small.quorum_reached(&params));
+    }
+
+    #[test]
+    fn test_proposal_lifecycle() {
+        let mut governance = Governance::new(params());
+        assert!(governance.new_proposal("DUalice", "Weights".into(), String::new(), weights(0.5, 0.3, 0.2), 10, 0, 0, BTreeMap::new()).is_err());
+        assert!(governance.new_proposal("DUalice", "Weights".into(), String::new(), weights(0.5, 0.3, 0.3), MIN_PROPOSAL_DEPOSIT, 0, 0, BTreeMap::new()).is_err());
+
+        let passing = governance.new_proposal("DUalice", "Weights".into(), String::new(), weights(0.5, 0.3, 0.2), MIN_PROPOSAL_DEPOSIT, 0, 1_000, BTreeMap::new()).unwrap();
+        let ignored = governance.new_proposal("DUbob", "Nobody cares".into(), String::new(), weights(0.4, 0.4, 0.2), MIN_PROPOSAL_DEPOSIT, 0, 1_000, BTreeMap::new()).unwrap();
+        let (passing_id, ignored_id) = (passing.proposal_id.clone(), ignored.proposal_id.clone());
+        assert!(governance.check_vote(&passing_id, 1_000).is_err());
+        governance.open(passing);
+        governance.open(ignored);
+        assert!(governance.check_vote(&passing_id, 1_099).is_ok());
+        assert!(governance.check_vote(&passing_id, 1_100).is_err());
+
+        assert!(governance.close_voting(1_099, |_| Tally::default()).is_empty());
+        assert!(governance.settle_deposits().is_empty());
+        let closed = governance.close_voting(1_100, |proposal| {
+            if proposal.proposal_id == passing_id {
+                Tally { votes_for: 700, votes_against: 300, voters: 2, total_power: 1_000 }
+            } else {
+                Tally { votes_for: 100, votes_against: 0, voters: 1, total_power: 1_000 }
+            }
+        });
+        assert_eq!(closed.len(), 2);
+        let passing = governance.proposal(&passing_id).unwrap();
+        assert_eq!(passing.status, ProposalStatus::Queued);
+        assert_eq!(passing.executable_height, Some(1_150));
+        assert_eq!(deposit_settlement(passing).to, "DUalice");
+        let ignored = governance.proposal(&ignored_id).unwrap();
+        assert_eq!(ignored.status, ProposalStatus::NoQuorum);
+        assert_eq!(deposit_settlement(ignored).to, BURN_ACCOUNT);
+
+        // Each deposit is released once; a release that did not persist is retried
+        let settled = governance.settle_deposits();
+        assert_eq!(settled.len(), 2);
+        assert!(settled.iter().all(|proposal| proposal.deposit_settled));
+        assert!(governance.settle_deposits().is_empty());
+        governance.unsettle(&ignored_id);
+        let retried = governance.settle_deposits();
+        assert_eq!(retried.len(), 1);
+        assert_eq!(retried[0].proposal_id, ignored_id);
+        assert!(governance.settle_deposits().is_empty());
+
+        // Timelock
+        assert!(governance.due(1_149).is_empty());
+        let due = governance.due(1_150);
+        assert_eq!(due.len(), 1);
+
+        let mut consensus = CPVConsensus::new();
+        let mut gas_fees = GasFeeCalculator::new();
+        let mut s2e_rates = S2ERates::default();
+        let result = due[0].action.execute(ParameterTargets {
+            consensus: &mut consensus,
+            gas_fees: &mut gas_fees,
+            s2e_rates: &mut s2e_rates,
+        });
+        let executed = governance.record_execution(&passing_id, 1_150, result).unwrap();
+        assert_eq!(executed.status, ProposalStatus::Executed);
+        assert_eq!((consensus.lambda_economic, consensus.lambda_creative, consensus.lambda_community), (0.5, 0.3, 0.2));
+        assert!(governance.due(2_000).is_empty());
+        assert!(governance.record_execution(&passing_id, 2_000, Ok(())).is_none());
+    }
+
+    #[test]
+    fn test_actions_change_gas_and_s2e_parameters() {
+        let mut consensus = CPVConsensus::new();
+        let mut gas_fees = GasFeeCalculator::new();
+        let mut s2e_rates = S2ERates::default();
+
+        let free_likes = GovernanceAction::GasFee {
+            config: GasFeeConfig {
+                transaction_type: TransactionType::Like,
+                model: GasFeeModel::Free,
+                min_fee: Amount::ZERO,
+                max_fee: None,
+            },
+        };
+        let rates = S2ERates {
+            listener_rate_per_minute: Amount::from_cents(20),
+            artist_rate_per_minute: Amount::from_cents(60),
+        };
+        for action in [free_likes, GovernanceAction::StreamEarnRates { rates }] {
+            action.execute(ParameterTargets {
+                consensus: &mut consensus,
+                gas_fees: &mut gas_fees,
+                s2e_rates: &mut s2e_rates,
+            }).unwrap();
+        }
+        assert!(gas_fees.is_free(&TransactionType::Like));
+        assert_eq!(s2e_rates, rates);
+
+        // Out of range rates never reach the live parameters
+        let too_high = S2ERates { listener_rate_per_minute: Amount::from_units(11), ..rates };
+        let result = GovernanceAction::StreamEarnRates { rates: too_high }.execute(ParameterTargets {
+            consensus: &mut consensus,
+            gas_fees: &mut gas_fees,
+            s2e_rates: &mut s2e_rates,
+        });
+        assert!(result.is_err());
+        assert_eq!(s2e_rates, rates);
+    }
+}
@@ -76,12 +76,7 @@ pub async fn transfer_funds(
     }
     
     // Create transaction
-    let transaction = Transaction {
-        from: request.from.clone(),
-        to: request.to.clone(),
-        amount: request.amount,
-        nft_id: None,
-    };
+    let transaction = Transaction::system(request.from.clone(), request.to.clone(), request.amount, None);
     
     // Add transaction to blockchain
     blockchain_guard.add_transaction(transaction).map_err(|e| format!("Failed to add transaction: {}", e))?;
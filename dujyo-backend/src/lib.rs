@@ -8,16 +8,23 @@ pub mod blockchain {
     pub mod artist_vesting;
     pub mod emergency_functions;
     // Export blockchain modules from src/blockchain/
-    pub mod block;
     pub mod blockchain;
     pub mod token;
-    pub mod transaction;
     pub mod gas_fees;
     pub mod real_blockchain;
+    pub mod signed_transaction;
+    pub mod merkle;
+    pub mod fork_choice;
+    pub mod block_verifier;
+    pub mod state_store;
+    pub mod ledger;
+    pub mod mempool;
+    pub mod fee_market;
 }
 
 pub mod utils {
     pub mod safe_math;
+    pub mod amount;
     pub mod arithmetic;
     pub mod access_control;
     pub mod vrf;
@@ -28,6 +35,14 @@ pub mod utils {
 pub mod dex;
 pub mod consensus {
     pub mod cpv;
+    pub mod proposer;
+    pub mod finality;
+    pub mod evidence;
+}
+
+pub mod p2p {
+    pub mod protocol;
+    pub mod sync;
 }
 
 pub mod rewards {
@@ -1,9 +1,13 @@
 mod blockchain;
+mod consensus; // ✅ CPV consensus: proposer selection for block production
+mod p2p; // Node-to-node block/tx propagation and chain sync
+mod websocket; // Client notifications (blocks, reorgs, balances)
 mod handlers;
 pub mod services;
 mod models;
 pub mod utils {
     pub mod safe_math;
+    pub mod amount;
     pub mod arithmetic;
     pub mod access_control;
     pub mod vrf;
@@ -13,6 +17,8 @@ mod server;
 mod storage;
 mod auth;
 mod dex;
+mod monetization; // Treasury books fed by the block fee distribution
+mod governance; // Stake-weighted proposals that change live protocol parameters
 mod routes; // ✅ ONBOARDING EXTENSION: Add routes module
 mod redis; // ✅ MVP-CRITICAL: Redis module for rate limiting and caching
 mod security; // ✅ MVP-CRITICAL: Security module for rate limiting
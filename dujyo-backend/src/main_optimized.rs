@@ -30,6 +30,7 @@ use crate::blockchain::native_token::{NativeToken, VestingRequest as NativeVesti
 use crate::blockchain::multisig::{MultisigWallet, MultisigRequest, SignRequest};
 use crate::blockchain::vesting::{VestingManager, CreateVestingRequest, ReleaseVestingRequest};
 use crate::blockchain::real_blockchain::RealBlockchain;
+use crate::utils::amount::Amount;
 use crate::blockchain::artist_vesting::ArtistVestingManager;
 use crate::storage_optimized::OptimizedBlockchainStorage;
 use crate::cache::{CacheService, CacheConfig};
@@ -88,10 +89,10 @@ pub struct BalanceResponse {
 #[derive(Debug, Serialize)]
 pub struct TokenBalanceResponse {
     pub address: String,
-    pub dyo: f64,
-    pub dys: f64,
-    pub staked: f64,
-    pub total: f64,
+    pub dyo: Amount,
+    pub dys: Amount,
+    pub staked: Amount,
+    pub total: Amount,
     pub timestamp: u64,
 }
 
@@ -254,7 +255,7 @@ pub async fn start_optimized_server() -> Result<(), Box<dyn std::error::Error>>
         Ok(balances) => {
             let mut token_guard = token.lock().unwrap();
             for (address, balance) in balances {
-                token_guard.balances.insert(address, balance);
+                token_guard.balances.insert(address, Amount::from_cents(balance));
             }
             info!("✅ Loaded {} balances from database", token_guard.balances.len());
         }
@@ -548,12 +549,7 @@ async fn optimized_submit_transaction(
 ) -> Result<Json<TransactionResponse>, StatusCode> {
     let start_time = std::time::Instant::now();
     
-    let transaction = Transaction {
-        from: request.from.clone(),
-        to: request.to.clone(),
-        amount: request.amount,
-        nft_id: request.nft_id,
-    };
+    let transaction = Transaction::system(request.from.clone(), request.to.clone(), request.amount, request.nft_id);
     
     // Save transaction to database
     match state.storage.save_transaction(&transaction).await {
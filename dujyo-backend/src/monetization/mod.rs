@@ -0,0 +1,3 @@
+// src/monetization/mod.rs
+
+pub mod treasury;
@@ -16,6 +16,8 @@ use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
+use crate::blockchain::gas_fees::FeeSplit;
+
 // ===========================================
 // TYPES & STRUCTS
 // ===========================================
@@ -174,6 +176,27 @@ impl TreasuryManager {
     // ===========================================
 
     pub async fn collect_fees(&self, amount: u64, source: String) -> Result<String, String> {
+        self.collect_fees_with_metadata(amount, source, HashMap::new()).await
+    }
+
+    /// Book the treasury share of a block's on-chain fee distribution; the
+    /// block totals (every share) are kept in the record's metadata
+    pub async fn collect_block_fees(&self, block_height: u64, fees: &FeeSplit) -> Result<String, String> {
+        let mut block_totals = HashMap::new();
+        block_totals.insert("block_height".to_string(), serde_json::json!(block_height));
+        block_totals.insert("block_fees_total".to_string(), serde_json::json!(fees.total()));
+        block_totals.insert("validator_share".to_string(), serde_json::json!(fees.validator));
+        block_totals.insert("creative_pool_share".to_string(), serde_json::json!(fees.creative_pool));
+        block_totals.insert("burned".to_string(), serde_json::json!(fees.burn));
+        self.collect_fees_with_metadata(fees.treasury, format!("block_fees:{}", block_height), block_totals).await
+    }
+
+    async fn collect_fees_with_metadata(
+        &self,
+        amount: u64,
+        source: String,
+        extra_metadata: HashMap<String, serde_json::Value>,
+    ) -> Result<String, String> {
         let mut treasury = self.treasury.write().await;
         
         // Update total assets
@@ -202,7 +225,7 @@ impl TreasuryManager {
             to_fund: Some(TreasuryFund::Main),
             description: format!("Fee collection from {}", source),
             metadata: {
-                let mut meta = HashMap::new();
+                let mut meta = extra_metadata;
                 meta.insert("source".to_string(), serde_json::json!(source));
                 meta.insert("reserve_allocation".to_string(), serde_json::json!(reserve_amount));
                 meta.insert("development_allocation".to_string(), serde_json::json!(development_amount));
@@ -213,8 +236,10 @@ impl TreasuryManager {
             executed_by: "system".to_string(),
         }).await;
         
-        // Check if auto-conversion is needed
-        if treasury.auto_conversion_enabled && treasury.dyo_balance >= treasury.conversion_threshold {
+        // Check if auto-conversion is needed (it takes the treasury lock itself)
+        let convert = treasury.auto_conversion_enabled && treasury.dyo_balance >= treasury.conversion_threshold;
+        drop(treasury);
+        if convert {
             self.auto_convert_to_stablecoin().await?;
         }
         
@@ -444,10 +469,11 @@ impl TreasuryManager {
             created_at: Utc::now(),
         };
         
+        let wallet_id = wallet.id.clone();
         let mut wallets = self.multisig_wallets.write().await;
-        wallets.insert(wallet.id.clone(), wallet);
+        wallets.insert(wallet_id.clone(), wallet);
         
-        Ok(wallet.id)
+        Ok(wallet_id)
     }
 
     pub async fn get_multisig_wallets(&self) -> Vec<MultisigWallet> {
@@ -557,7 +583,7 @@ impl TreasuryManager {
         };
         
         RiskAssessment {
-            overall_risk,
+            overall_risk: overall_risk.clone(),
             reserve_ratio,
             diversification_score,
             liquidity_score,
@@ -666,6 +692,21 @@ mod tests {
         assert_eq!(treasury.dys_balance, 1000); // Converted to stablecoin
     }
 
+    #[tokio::test]
+    async fn test_block_fees_keep_block_totals() {
+        let manager = TreasuryManager::new(TreasuryPolicy::default());
+        let fees = FeeSplit { treasury: 400, validator: 300, creative_pool: 200, burn: 100 };
+
+        let transaction_id = manager.collect_block_fees(42, &fees).await.unwrap();
+
+        assert_eq!(manager.get_treasury_status().await.total_assets, 400);
+        let transactions = manager.transactions.read().await;
+        let record = transactions.iter().find(|transaction| transaction.id == transaction_id).unwrap();
+        assert_eq!(record.metadata["source"], serde_json::json!("block_fees:42"));
+        assert_eq!(record.metadata["block_fees_total"], serde_json::json!(1000));
+        assert_eq!(record.metadata["burned"], serde_json::json!(100));
+    }
+
     #[tokio::test]
     async fn test_burn_execution() {
         let policy = TreasuryPolicy::default();
@@ -1,113 +1,686 @@
-use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
-use futures_util::{StreamExt, SinkExt};
-use std::collections::HashMap;
-use tokio::sync::broadcast;
-use serde::{Deserialize, Serialize};
+//! WebSocket transport between Dujyo nodes
+//!
+//! Outbound peers come from `DUJYO_PEERS` (comma-separated `ws://host:port/p2p`
+//! URLs); inbound peers connect to the `/p2p` route. Both sides send `Hello`
+//! first and then exchange `SyncMessage` frames handled by `ChainSync`.
+//! Imported blocks, side branches and reorganizations are persisted here and
+//! announced to `/ws` clients; validator attestations feed the finality gadget
+//! and conflicting signatures become slashing evidence.
+
+use axum::extract::ws::{Message as AxumMessage, WebSocket};
+use futures_util::{SinkExt, StreamExt};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
 use std::time::Duration;
+use tokio::sync::mpsc;
 use tokio::time::sleep;
+use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
 
-#[derive(Serialize, Deserialize)]
-pub struct MessageData {
-    pub type_: String,
-    pub data: serde_json::Value,
-}
+use crate::blockchain::block_verifier::BlockVerifier;
+use crate::blockchain::blockchain::{Block, Blockchain};
+use crate::blockchain::fork_choice::ReorgEvent;
+use crate::blockchain::mempool::TransactionPriority;
+use crate::blockchain::signed_transaction::chain_id;
+use crate::blockchain::staking_rewards::VALIDATOR_CONTRACT_ID;
+use crate::blockchain::state_store::{self, MempoolSnapshot, StateCommit};
+use crate::consensus::cpv::SlashReason;
+use crate::consensus::evidence::{double_sign_slash_amount, DoubleSignEvidence, DOUBLE_SIGN_SLASH_BPS};
+use crate::consensus::finality::{Attestation, FinalityError, FinalizedCheckpoint};
+use crate::consensus::proposer::{self, SYSTEM_PROPOSER};
+use crate::p2p::protocol::{SyncError, SyncMessage};
+use crate::p2p::sync::ChainSync;
+use crate::server::{self, AppState};
+use crate::utils::amount::Amount;
+use crate::websocket;
 
 pub struct PeerNetwork {
-    peers: HashMap<String, tokio_tungstenite::WebSocketStream<tokio::net::TcpStream>>,
-    tx: broadcast::Sender<MessageData>,
+    sync: ChainSync,
+    peers: Mutex<HashMap<String, mpsc::UnboundedSender<String>>>, // peer_id -> cola de salida
+    snapshot_interval: u64,
 }
 
 impl PeerNetwork {
-    pub fn new() -> Self {
-        let (tx, _) = broadcast::channel(16); // Canal para enviar mensajes a todos los peers
+    pub fn new(node_id: String) -> Self {
         PeerNetwork {
-            peers: HashMap::new(),
-            tx,
-        }
-    }
-
-    // Validar si el peer tiene un ID válido
-    fn validate_peer(peer_id: &str) -> bool {
-        // Aquí puedes agregar una lógica de validación más compleja si es necesario
-        !peer_id.is_empty()
-    }
-
-    // Método para conectar a un peer con reintentos
-    pub async fn connect_to_peer(&mut self, address: &str) -> Result<(), Box<dyn std::error::Error>> {
-        let mut attempts = 0;
-        while attempts < 5 {
-            match connect_async(address).await {
-                Ok((ws_stream, _)) => {
-                    let peer_id = address.to_string(); // Utiliza la dirección como peer_id
-                    if Self::validate_peer(&peer_id) {
-                        self.peers.insert(peer_id, ws_stream);
-                        println!("Connected to peer: {}", peer_id);
-                        return Ok(());
-                    } else {
-                        println!("Invalid peer ID: {}", peer_id);
-                    }
-                }
-                Err(e) => {
-                    println!("Error connecting to {}: {}. Retrying...", address, e);
-                    attempts += 1;
-                    sleep(Duration::from_secs(2)).await; // Reintentar después de 2 segundos
+            sync: ChainSync::new(node_id, chain_id()),
+            peers: Mutex::new(HashMap::new()),
+            snapshot_interval: state_store::snapshot_interval(),
+        }
+    }
+
+    /// Node id from DUJYO_NODE_ID (random if unset)
+    pub fn from_env() -> Self {
+        let node_id = std::env::var("DUJYO_NODE_ID")
+            .unwrap_or_else(|_| format!("node-{}", &uuid::Uuid::new_v4().to_string()[..8]));
+        PeerNetwork::new(node_id)
+    }
+
+    /// Peer URLs configured in DUJYO_PEERS
+    pub fn configured_peers() -> Vec<String> {
+        std::env::var("DUJYO_PEERS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|peer| peer.trim().to_string())
+            .filter(|peer| !peer.is_empty())
+            .collect()
+    }
+
+    pub fn sync(&self) -> &ChainSync {
+        &self.sync
+    }
+
+    pub fn peer_count(&self) -> usize {
+        self.peers.lock().map(|peers| peers.len()).unwrap_or(0)
+    }
+
+    fn register(&self, peer_id: &str, sender: mpsc::UnboundedSender<String>) {
+        if let Ok(mut peers) = self.peers.lock() {
+            peers.insert(peer_id.to_string(), sender);
+        }
+    }
+
+    fn send_to(&self, peer_id: &str, message: &SyncMessage) {
+        let Ok(text) = message.encode() else {
+            return;
+        };
+        if let Ok(peers) = self.peers.lock() {
+            if let Some(sender) = peers.get(peer_id) {
+                let _ = sender.send(text);
+            }
+        }
+    }
+
+    // Enviar mensaje a todos los peers conectados (excepto el origen del mensaje)
+    pub fn broadcast(&self, message: &SyncMessage, except: Option<&str>) {
+        let Ok(text) = message.encode() else {
+            return;
+        };
+        if let Ok(peers) = self.peers.lock() {
+            for (peer_id, sender) in peers.iter() {
+                if Some(peer_id.as_str()) != except {
+                    let _ = sender.send(text.clone());
                 }
             }
         }
-        Err(Box::new(std::io::Error::new(std::io::ErrorKind::Other, "Failed to connect after 5 attempts")))
     }
 
-    // Enviar mensaje a todos los peers conectados
-    pub async fn broadcast(&self, message: MessageData) -> Result<(), Box<dyn std::error::Error>> {
-        let message = serde_json::to_string(&message)?;
+    // Manejo de la desconexión de un peer
+    pub fn handle_peer_disconnection(&self, peer_id: &str) {
+        if let Ok(mut peers) = self.peers.lock() {
+            if peers.remove(peer_id).is_some() {
+                tracing::info!(peer = %peer_id, "Peer disconnected");
+            }
+        }
+    }
+}
+
+// Método para conectar a un peer con reintentos
+pub async fn connect_to_peer(state: AppState, address: String) {
+    let mut attempts = 0;
+    while attempts < 5 {
+        match connect_async(address.as_str()).await {
+            Ok((ws_stream, _)) => {
+                tracing::info!(peer = %address, "Connected to peer");
+                let (mut sink, mut stream) = ws_stream.split();
+                let (sender, mut outbox) = mpsc::unbounded_channel::<String>();
+                tokio::spawn(async move {
+                    while let Some(text) = outbox.recv().await {
+                        if sink.send(Message::Text(text)).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+
+                start_session(&state, &address, sender);
+                while let Some(Ok(frame)) = stream.next().await {
+                    match frame {
+                        Message::Text(text) => process_message(&state, &address, &text).await,
+                        Message::Close(_) => break,
+                        _ => {}
+                    }
+                }
+                state.peer_network.handle_peer_disconnection(&address);
+                return;
+            }
+            Err(e) => {
+                tracing::warn!(peer = %address, error = %e, "Error connecting to peer, retrying");
+                attempts += 1;
+                sleep(Duration::from_secs(2)).await; // Reintentar después de 2 segundos
+            }
+        }
+    }
+    tracing::error!(peer = %address, "Failed to connect to peer after 5 attempts");
+}
 
-        for (peer_id, peer_stream) in &self.peers {
-            if let Err(e) = peer_stream.send(Message::Text(message.clone())).await {
-                println!("Error sending message to peer {}: {}", peer_id, e);
+// Conexión entrante desde la ruta /p2p
+pub async fn handle_inbound_peer(socket: WebSocket, state: AppState) {
+    let peer_id = format!("inbound-{}", uuid::Uuid::new_v4());
+    let (mut sink, mut stream) = socket.split();
+    let (sender, mut outbox) = mpsc::unbounded_channel::<String>();
+    tokio::spawn(async move {
+        while let Some(text) = outbox.recv().await {
+            if sink.send(AxumMessage::Text(text)).await.is_err() {
+                break;
             }
         }
-        Ok(())
+    });
+
+    start_session(&state, &peer_id, sender);
+    while let Some(Ok(frame)) = stream.next().await {
+        match frame {
+            AxumMessage::Text(text) => process_message(&state, &peer_id, &text).await,
+            AxumMessage::Close(_) => break,
+            _ => {}
+        }
     }
+    state.peer_network.handle_peer_disconnection(&peer_id);
+}
 
-    // Manejar mensajes entrantes de los peers
-    pub async fn handle_peer_messages(&mut self) {
-        let mut rx = self.tx.subscribe();
-        while let Ok(message) = rx.recv().await {
-            println!("Received message from peer network: {:?}", message);
+fn start_session(state: &AppState, peer_id: &str, sender: mpsc::UnboundedSender<String>) {
+    let network = &state.peer_network;
+    network.register(peer_id, sender);
+    let hello = match state.blockchain.lock() {
+        Ok(blockchain) => network.sync().hello(&blockchain),
+        Err(_) => return,
+    };
+    network.send_to(peer_id, &hello);
+}
+
+// Manejar mensajes entrantes de los peers
+async fn process_message(state: &AppState, peer_id: &str, text: &str) {
+    let network = &state.peer_network;
+    let message = match SyncMessage::decode(text) {
+        Ok(message) => message,
+        Err(e) => {
+            tracing::warn!(peer = %peer_id, error = %e, "Dropping message from peer");
+            return;
+        }
+    };
+
+    // ✅ SECURITY: Gossiped transactions must be signed by the key registered for the sender
+    if let SyncMessage::NewTransaction(transaction) = &message {
+        let registered = state.storage.get_account_key(&transaction.from).await.ok().flatten();
+        let signed_key = transaction.auth.as_ref().map(|auth| auth.public_key.to_lowercase());
+        if registered.is_none() || registered != signed_key {
+            tracing::warn!(peer = %peer_id, from = %transaction.from, "Dropping transaction gossip: key not registered for sender");
+            return;
         }
     }
 
-    // Manejo de la desconexión de un peer
-    pub async fn handle_peer_disconnection(&mut self, peer_id: &str) {
-        if let Some(peer_stream) = self.peers.remove(peer_id) {
-            if let Err(e) = peer_stream.close().await {
-                println!("Error closing connection with peer {}: {}", peer_id, e);
+    // ✅ CPV: Fork choice / finality weights (snapshot before locking the chain)
+    let weights = state.cpv_consensus.lock().await.proposer_weights();
+
+    if let SyncMessage::Attestation(attestation) = message {
+        handle_attestation(state, peer_id, attestation, &weights).await;
+        return;
+    }
+    if let SyncMessage::Evidence(evidence) = message {
+        if let Err(e) = process_evidence(state, evidence, peer_id, Some(peer_id)).await {
+            tracing::warn!(peer = %peer_id, error = %e, "Rejected evidence from peer");
+        }
+        return;
+    }
+
+    // ✅ SECURITY: Keys of the proposers and signers of the carried blocks (looked up before locking the chain)
+    let verifier = match block_verifier(state, &message, weights).await {
+        Ok(verifier) => verifier,
+        Err(e) => {
+            tracing::error!(peer = %peer_id, error = %e, "Could not load account keys to verify blocks");
+            return;
+        }
+    };
+
+    let (result, reorgs, state_commit) = {
+        let Ok(mut blockchain) = state.blockchain.lock() else {
+            return;
+        };
+        let result = network.sync().handle_message(&mut blockchain, message, &verifier);
+        let (reorgs, state_commit): (Vec<ReorgRecord>, StateCommit) = match &result {
+            Ok(outcome) => (
+                outcome
+                    .reorgs
+                    .iter()
+                    .map(|event| ReorgRecord::capture(&blockchain, event))
+                    .collect(),
+                StateCommit::capture(&blockchain, &outcome.imported, network.snapshot_interval),
+            ),
+            Err(_) => (Vec::new(), StateCommit::default()),
+        };
+        (result, reorgs, state_commit)
+    };
+
+    let outcome = match result {
+        Ok(outcome) => outcome,
+        Err(SyncError::IncompatiblePeer(reason)) => {
+            tracing::warn!(peer = %peer_id, reason = %reason, "Disconnecting incompatible peer");
+            network.handle_peer_disconnection(peer_id);
+            return;
+        }
+        Err(e) => {
+            tracing::warn!(peer = %peer_id, error = %e, "Rejected message from peer");
+            return;
+        }
+    };
+
+    // Persist imported blocks and the state they produced atomically
+    if !outcome.imported.is_empty() {
+        if let Err(e) = state.storage.commit_blocks(&outcome.imported, &state_commit).await {
+            tracing::error!(peer = %peer_id, error = %e, "Failed to commit synced blocks, resyncing");
+            resync_from_storage(state, peer_id).await;
+            return;
+        }
+        server::record_block_fees(state, &outcome.imported).await;
+        server::accrue_staking_rewards(state, &outcome.imported).await;
+    }
+    for block in &outcome.imported {
+        websocket::broadcast_new_block(
+            &state.ws_tx,
+            block.height,
+            block.hash.clone(),
+            block.timestamp as i64,
+            block.transactions.len(),
+        )
+        .await;
+    }
+
+    // Gossiped transactions survive a restart like local submissions
+    for (bundle, transaction) in &outcome.pending {
+        if let Err(e) = state
+            .storage
+            .save_pending_bundle(*bundle, TransactionPriority::Normal, std::slice::from_ref(transaction))
+            .await
+        {
+            tracing::error!(peer = %peer_id, error = %e, "Failed to store gossiped transaction, resyncing");
+            resync_from_storage(state, peer_id).await;
+            return;
+        }
+    }
+
+    for block in &outcome.side_blocks {
+        if let Err(e) = state.storage.save_side_block(block).await {
+            tracing::error!(peer = %peer_id, error = %e, "Failed to store side block, resyncing");
+            resync_from_storage(state, peer_id).await;
+            return;
+        }
+    }
+
+    // Fork choice: switch the stored chain to the adopted branch and notify clients
+    for record in &reorgs {
+        if let Err(e) = state
+            .storage
+            .apply_reorg(
+                record.event.common_ancestor_height,
+                &record.orphaned,
+                &record.adopted,
+                &record.balances,
+                &record.nonces,
+                &record.mempool,
+            )
+            .await
+        {
+            tracing::error!(peer = %peer_id, error = %e, "Failed to persist chain reorganization, resyncing");
+            resync_from_storage(state, peer_id).await;
+            return;
+        }
+        websocket::broadcast_reorg(&state.ws_tx, &record.event).await;
+    }
+
+    // ✅ SECURITY: Two sealed blocks from one proposer at one height are slashable
+    observe_blocks(state, outcome.imported.iter().chain(outcome.side_blocks.iter())).await;
+
+    // Vote for the new head with the validators hosted here
+    if !outcome.imported.is_empty() || !reorgs.is_empty() {
+        attest_head(state).await;
+    }
+
+    for reply in &outcome.reply {
+        network.send_to(peer_id, reply);
+    }
+    for relay in &outcome.relay {
+        network.broadcast(relay, Some(peer_id));
+    }
+}
+
+/// After a failed write the in-memory chain is ahead of the database: put it
+/// back on what storage holds, dropping what could not be stored
+pub(crate) async fn reload_chain(state: &AppState) -> bool {
+    match state.storage.restore_blockchain().await {
+        Ok(restored) => match state.blockchain.lock() {
+            Ok(mut blockchain) => {
+                *blockchain = restored;
+                true
             }
-            println!("Peer {} disconnected", peer_id);
+            Err(_) => false,
+        },
+        Err(e) => {
+            tracing::error!(error = %e, "Could not reload the chain from the database");
+            false
         }
     }
 }
 
-#[tokio::main]
-async fn main() {
-    let mut peer_network = PeerNetwork::new();
+/// Reload the chain and handshake with `peer_id` again so it resends what is missing
+async fn resync_from_storage(state: &AppState, peer_id: &str) {
+    if !reload_chain(state).await {
+        return;
+    }
+    let hello = match state.blockchain.lock() {
+        Ok(blockchain) => state.peer_network.sync().hello(&blockchain),
+        Err(_) => return,
+    };
+    state.peer_network.send_to(peer_id, &hello);
+}
+
+/// Verifier for the blocks of `message`: CPV weights plus the keys bound to
+/// their proposers and signers and to the proposers fork choice may weigh
+/// against them (the bootstrap proposer's from the node config)
+async fn block_verifier(
+    state: &AppState,
+    message: &SyncMessage,
+    weights: HashMap<String, u64>,
+) -> Result<BlockVerifier, sqlx::Error> {
+    let mut verifier = BlockVerifier::new(state.peer_network.sync().chain_id.clone(), weights);
+    if message.blocks().is_empty() {
+        return Ok(verifier);
+    }
+    // Fork choice also weighs the canonical blocks and side branches the new ones compete with
+    let mut accounts = BlockVerifier::accounts(message.blocks());
+    if let Ok(blockchain) = state.blockchain.lock() {
+        accounts.extend(blockchain.fork_choice_proposers());
+    }
+    let accounts: Vec<String> = accounts.into_iter().collect();
+    for (address, public_key) in state.storage.get_account_keys(&accounts).await? {
+        verifier.insert_key(address, public_key);
+    }
+    if let Some(public_key) = proposer::bootstrap_public_key(&state.proposer_keys) {
+        verifier.insert_key(SYSTEM_PROPOSER.to_string(), public_key);
+    }
+    Ok(verifier)
+}
+
+// ✅ CPV: Verificar, registrar y reenviar la atestación de otro nodo
+async fn handle_attestation(
+    state: &AppState,
+    peer_id: &str,
+    attestation: Attestation,
+    weights: &HashMap<String, u64>,
+) {
+    // ✅ SECURITY: The vote must be signed by the key registered for the validator
+    let registered = state.storage.get_account_key(&attestation.validator).await.ok().flatten();
+    if registered.is_none() || registered.as_deref() != Some(attestation.public_key.to_lowercase().as_str()) {
+        tracing::warn!(peer = %peer_id, validator = %attestation.validator, "Dropping attestation: key not registered for validator");
+        return;
+    }
+
+    let (accepted, checkpoint, evidence) = {
+        let (Ok(mut blockchain), Ok(mut finality)) = (state.blockchain.lock(), state.finality.lock()) else {
+            return;
+        };
+        match finality.add_attestation(attestation.clone(), weights, &blockchain) {
+            Ok(true) => (true, finality.try_finalize(&mut blockchain, weights), None),
+            Ok(false) => (false, None, None),
+            Err(FinalityError::Equivocation { first_hash, .. }) => {
+                // The first vote is in the pool: together they prove double signing
+                let first = finality.attestation(&first_hash, &attestation.validator).cloned();
+                let evidence = first.map(|first| DoubleSignEvidence::Attestation {
+                    first,
+                    second: attestation.clone(),
+                });
+                (false, None, evidence)
+            }
+            Err(e) => {
+                tracing::warn!(peer = %peer_id, error = %e, "Rejected attestation from peer");
+                return;
+            }
+        }
+    };
+
+    if let Some(evidence) = evidence {
+        tracing::warn!(peer = %peer_id, validator = %attestation.validator, "Conflicting attestation");
+        let reporter = state.peer_network.sync().node_id.clone();
+        if let Err(e) = process_evidence(state, evidence, &reporter, None).await {
+            tracing::error!(error = %e, "Could not process attestation evidence");
+        }
+        return;
+    }
+
+    // Only votes we could store are relayed
+    if accepted && persist_finality(state, std::slice::from_ref(&attestation), checkpoint).await {
+        state
+            .peer_network
+            .broadcast(&SyncMessage::Attestation(attestation), Some(peer_id));
+    }
+}
+
+/// Attest the current head with every voter hosted by this node and gossip the votes
+pub async fn attest_head(state: &AppState) {
+    let weights = state.cpv_consensus.lock().await.proposer_weights();
+    let (attestations, checkpoint) = {
+        let (Ok(mut blockchain), Ok(mut finality)) = (state.blockchain.lock(), state.finality.lock()) else {
+            return;
+        };
+        let attestations: Vec<Attestation> = finality
+            .attest_head(&state.proposer_keys, &weights, &blockchain)
+            .into_iter()
+            .filter(|attestation| {
+                matches!(finality.add_attestation(attestation.clone(), &weights, &blockchain), Ok(true))
+            })
+            .collect();
+        let checkpoint = finality.try_finalize(&mut blockchain, &weights);
+        (attestations, checkpoint)
+    };
 
-    // Conectar a un peer con reintentos
-    if let Err(e) = peer_network.connect_to_peer("ws://localhost:8080").await {
-        eprintln!("Failed to connect to peer: {}", e);
+    if !persist_finality(state, &attestations, checkpoint).await {
         return;
     }
+    for attestation in &attestations {
+        state
+            .peer_network
+            .broadcast(&SyncMessage::Attestation(attestation.clone()), None);
+    }
+}
+
+/// Store the votes, then the checkpoint they completed. Stops at the first
+/// failure (a checkpoint is never stored without its votes); false if anything
+/// was left unstored.
+async fn persist_finality(state: &AppState, attestations: &[Attestation], checkpoint: Option<FinalizedCheckpoint>) -> bool {
+    for attestation in attestations {
+        if let Err(e) = state.storage.save_attestation(attestation).await {
+            tracing::error!(height = attestation.height, validator = %attestation.validator, error = %e, "Failed to store attestation");
+            return false;
+        }
+    }
+    if let Some(checkpoint) = checkpoint {
+        if let Err(e) = state.storage.save_finalized(checkpoint.height, &checkpoint.block_hash).await {
+            tracing::error!(height = checkpoint.height, error = %e, "Failed to store finalized checkpoint");
+            return false;
+        }
+    }
+    true
+}
+
+/// Result of handling double-signing evidence
+#[derive(Debug, Clone)]
+pub enum EvidenceOutcome {
+    Slashed {
+        evidence_id: String,
+        validator: String,
+        slash_amount: u64,
+    },
+    /// Same offence already handled (each offence is slashed once)
+    AlreadyProcessed { evidence_id: String },
+}
+
+// ✅ SECURITY: Verify evidence, slash the offender once, keep the evidence for audit and gossip it
+pub async fn process_evidence(
+    state: &AppState,
+    evidence: DoubleSignEvidence,
+    submitted_by: &str,
+    origin_peer: Option<&str>,
+) -> Result<EvidenceOutcome, String> {
+    evidence.verify(&chain_id()).map_err(|e| e.to_string())?;
+    let evidence_id = evidence.id();
+    let already_processed = state
+        .evidence_pool
+        .lock()
+        .map(|pool| pool.is_processed(&evidence_id))
+        .unwrap_or(false);
+    if already_processed {
+        return Ok(EvidenceOutcome::AlreadyProcessed { evidence_id });
+    }
+
+    let offender = evidence.offender().to_string();
+    let registered = state
+        .storage
+        .get_account_key(&offender)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+    if registered.as_deref() != Some(evidence.public_key().to_lowercase().as_str()) {
+        return Err(format!("Evidence is not signed with the key registered for {}", offender));
+    }
+
+    let mut consensus = state.cpv_consensus.lock().await;
+    let stake = consensus
+        .validator_stake(&offender)
+        .ok_or_else(|| format!("{} is not a CPV validator", offender))?;
+    let slash_amount = double_sign_slash_amount(stake);
+
+    let inserted = state
+        .storage
+        .save_evidence(&evidence, submitted_by, slash_amount)
+        .await
+        .map_err(|e| format!("Database error storing evidence: {}", e))?;
+    if let Ok(mut pool) = state.evidence_pool.lock() {
+        pool.mark_processed(evidence_id.clone());
+    }
+    if !inserted {
+        return Ok(EvidenceOutcome::AlreadyProcessed { evidence_id });
+    }
 
-    // Enviar un mensaje a todos los peers
-    let message = MessageData {
-        type_: "info".to_string(),
-        data: serde_json::json!({"status": "peer connected"}),
+    consensus
+        .slash_validator(
+            &offender,
+            SlashReason::DOUBLE_SIGNING,
+            slash_amount,
+            Some(evidence.height()),
+            Some(evidence_id.clone()),
+        )
+        .await?;
+    drop(consensus);
+    slash_delegations(state, &offender).await;
+
+    state.peer_network.broadcast(&SyncMessage::Evidence(evidence.clone()), origin_peer);
+    websocket::broadcast_system_notification(
+        &state.ws_tx,
+        format!("Validator {} slashed for double signing at height {}", offender, evidence.height()),
+        "warning".to_string(),
+    )
+    .await;
+
+    Ok(EvidenceOutcome::Slashed {
+        evidence_id,
+        validator: offender,
+        slash_amount,
+    })
+}
+
+// Delegators share the offence: their delegations to the offender are cut at the
+// validator contract's slashing rate, in the staking state and in their staked DYO;
+// stake still unbonding from the offender is cut at the double-signing rate
+async fn slash_delegations(state: &AppState, offender: &str) {
+    let mut staking = state.staking.lock().await;
+    let previous = staking.clone();
+    let slashes: Vec<(String, Amount)> = match staking.slash_staker(VALIDATOR_CONTRACT_ID, offender, "double signing") {
+        Ok(slashed) => slashed
+            .delegator_slashes
+            .into_iter()
+            .map(|(delegator, cut)| (delegator, Amount::from_units(cut)))
+            .collect(),
+        Err(e) => {
+            tracing::warn!(validator = %offender, error = %e, "No bond to slash in the validator contract");
+            Vec::new()
+        }
     };
 
-    if let Err(e) = peer_network.broadcast(message).await {
-        eprintln!("Error broadcasting message: {}", e);
+    if let Err(e) = state
+        .storage
+        .save_delegation_slashes(&staking, offender, DOUBLE_SIGN_SLASH_BPS, &slashes)
+        .await
+    {
+        *staking = previous;
+        tracing::error!(validator = %offender, error = %e, "Failed to store delegation slashing");
+        return;
+    }
+    server::sync_validator_stakes(&mut staking, &mut *state.cpv_consensus.lock().await);
+}
+
+// Remember sealed blocks per proposer and height; a second, different one is evidence
+async fn observe_blocks<'a>(state: &AppState, blocks: impl Iterator<Item = &'a Block>) {
+    for block in blocks {
+        let Some(proposer) = block.validator.as_deref() else {
+            continue;
+        };
+        let Ok(Some(public_key)) = state.storage.get_account_key(proposer).await else {
+            continue;
+        };
+        let evidence = match state.evidence_pool.lock() {
+            Ok(mut pool) => pool.observe_block(block, &public_key),
+            Err(_) => None,
+        };
+        if let Some(evidence) = evidence {
+            let reporter = state.peer_network.sync().node_id.clone();
+            if let Err(e) = process_evidence(state, evidence, &reporter, None).await {
+                tracing::error!(error = %e, "Could not process block evidence");
+            }
+        }
     }
+}
+
+/// Everything needed to persist one reorganization, captured under the chain lock
+struct ReorgRecord {
+    event: ReorgEvent,
+    orphaned: Vec<Block>,
+    adopted: Vec<Block>,
+    balances: Vec<(String, u64)>,
+    nonces: Vec<(String, u64)>,
+    mempool: MempoolSnapshot,
+}
 
-    // Manejar la desconexión de un peer
-    peer_network.handle_peer_disconnection("ws://localhost:8080").await;
+impl ReorgRecord {
+    fn capture(blockchain: &Blockchain, event: &ReorgEvent) -> Self {
+        let orphaned: Vec<Block> = event
+            .orphaned_blocks
+            .iter()
+            .filter_map(|hash| blockchain.side_blocks.get(hash).cloned())
+            .collect();
+        let adopted: Vec<Block> = event
+            .adopted_blocks
+            .iter()
+            .filter_map(|hash| blockchain.chain.iter().rev().find(|block| &block.hash == hash).cloned())
+            .collect();
+
+        // Accounts touched by either branch or by the re-queued mempool
+        let touched: HashSet<String> = orphaned
+            .iter()
+            .chain(adopted.iter())
+            .flat_map(|block| block.transactions.iter())
+            .chain(blockchain.pending_transactions.iter())
+            .flat_map(|transaction| transaction.accounts().into_iter().map(str::to_string))
+            .collect();
+
+        ReorgRecord {
+            event: event.clone(),
+            balances: touched
+                .iter()
+                .map(|address| (address.clone(), blockchain.get_balance(address)))
+                .collect(),
+            nonces: touched
+                .iter()
+                .map(|address| (address.clone(), blockchain.next_nonce(address) - 1))
+                .collect(),
+            orphaned,
+            adopted,
+            mempool: MempoolSnapshot::capture(blockchain),
+        }
+    }
 }
@@ -0,0 +1,6 @@
+// src/p2p/mod.rs
+
+pub mod protocol;
+pub mod sync;
+#[path = "PeerNetwork.rs"]
+pub mod peer_network;
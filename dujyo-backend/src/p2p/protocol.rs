@@ -0,0 +1,131 @@
+//! Node-to-Node Sync Protocol Messages
+//!
+//! Every frame exchanged between Dujyo nodes is one JSON-encoded `SyncMessage`.
+//! A connection starts with `Hello` in both directions (chain id, genesis hash,
+//! height and head), after which peers gossip transactions, new blocks,
+//! validator attestations and double-signing evidence and fetch missing blocks
+//! by height range.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::blockchain::blockchain::{Block, Blockchain, Transaction};
+use crate::consensus::evidence::DoubleSignEvidence;
+use crate::consensus::finality::Attestation;
+
+/// Bumped on any incompatible change to `SyncMessage`
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// Maximum number of blocks served per `GetBlocks` request
+pub const MAX_BLOCKS_PER_REQUEST: u64 = 64;
+
+/// Chain view a node advertises in `Hello` / `Status`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeStatus {
+    pub protocol_version: u32,
+    pub node_id: String,
+    pub chain_id: String,
+    pub genesis_hash: String,
+    pub height: u64,
+    pub head_hash: String,
+}
+
+impl NodeStatus {
+    /// Status of the local chain
+    pub fn local(blockchain: &Blockchain, node_id: &str, chain_id: &str) -> Self {
+        let head = blockchain.get_latest_block();
+        NodeStatus {
+            protocol_version: PROTOCOL_VERSION,
+            node_id: node_id.to_string(),
+            chain_id: chain_id.to_string(),
+            genesis_hash: blockchain.chain[0].hash.clone(),
+            height: head.height,
+            head_hash: head.hash.clone(),
+        }
+    }
+}
+
+/// Wire messages
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data")]
+pub enum SyncMessage {
+    /// Handshake, sent first by both sides
+    Hello(NodeStatus),
+    /// Reply to `Hello` (does not trigger another reply)
+    Status(NodeStatus),
+    /// Signed transaction gossip
+    NewTransaction(Transaction),
+    /// New block announcement (full block)
+    NewBlock(Block),
+    /// Range request by height (inclusive)
+    GetBlocks { from_height: u64, to_height: u64 },
+    /// Range response, ordered by height
+    Blocks(Vec<Block>),
+    /// ✅ CPV: Validator vote for a canonical block (finality gadget)
+    Attestation(Attestation),
+    /// ✅ SECURITY: Proof that a validator signed two blocks/attestations at one height
+    Evidence(DoubleSignEvidence),
+}
+
+impl SyncMessage {
+    pub fn encode(&self) -> Result<String, SyncError> {
+        serde_json::to_string(self).map_err(|e| SyncError::InvalidMessage(e.to_string()))
+    }
+
+    pub fn decode(text: &str) -> Result<Self, SyncError> {
+        serde_json::from_str(text).map_err(|e| SyncError::InvalidMessage(e.to_string()))
+    }
+
+    /// Blocks carried by the message (their proposer and signer keys are looked up before handling)
+    pub fn blocks(&self) -> &[Block] {
+        match self {
+            SyncMessage::NewBlock(block) => std::slice::from_ref(block),
+            SyncMessage::Blocks(blocks) => blocks,
+            _ => &[],
+        }
+    }
+}
+
+/// Errors raised while handling peer messages
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyncError {
+    /// Different chain id, genesis or protocol version: the peer must be dropped
+    IncompatiblePeer(String),
+    InvalidBlock(String),
+    InvalidTransaction(String),
+    InvalidMessage(String),
+}
+
+impl fmt::Display for SyncError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyncError::IncompatiblePeer(msg) => write!(f, "Incompatible peer: {}", msg),
+            SyncError::InvalidBlock(msg) => write!(f, "Invalid block: {}", msg),
+            SyncError::InvalidTransaction(msg) => write!(f, "Invalid transaction: {}", msg),
+            SyncError::InvalidMessage(msg) => write!(f, "Invalid message: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for SyncError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_roundtrip() {
+        let message = SyncMessage::GetBlocks { from_height: 3, to_height: 9 };
+        let encoded = message.encode().unwrap();
+        assert!(encoded.contains("\"type\":\"GetBlocks\""));
+        match SyncMessage::decode(&encoded).unwrap() {
+            SyncMessage::GetBlocks { from_height, to_height } => assert_eq!((from_height, to_height), (3, 9)),
+            other => panic!("unexpected message {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_garbage_rejected() {
+        assert!(matches!(SyncMessage::decode("{\"type\":\"Nope\"}"), Err(SyncError::InvalidMessage(_))));
+    }
+}
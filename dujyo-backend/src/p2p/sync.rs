@@ -0,0 +1,238 @@
+//! Chain Sync State Machine
+//!
+//! Pure message handling on top of `Blockchain`: no sockets, no database.
+//! The transport (`PeerNetwork`) feeds every decoded message from a peer into
+//! `ChainSync::handle_message` and sends back `reply` to that peer and
+//! `relay` to every other peer. Every block is authenticated first (proposer
+//! signature, slot VRF and lottery, transaction signatures: `BlockVerifier`);
+//! competing blocks go through the CPV-weighted fork choice
+//! (`Blockchain::accept_block`), which may reorganize the chain.
+
+use tracing::{info, warn};
+
+use crate::blockchain::block_verifier::BlockVerifier;
+use crate::blockchain::blockchain::{Block, Blockchain, Transaction};
+use crate::blockchain::fork_choice::{BlockImport, ReorgEvent, MAX_REORG_DEPTH};
+use crate::blockchain::signed_transaction::SignedTransaction;
+use crate::p2p::protocol::{NodeStatus, SyncError, SyncMessage, MAX_BLOCKS_PER_REQUEST, PROTOCOL_VERSION};
+
+/// Result of handling one peer message
+#[derive(Debug, Default)]
+pub struct SyncOutcome {
+    /// Messages for the peer that sent the message
+    pub reply: Vec<SyncMessage>,
+    /// Messages for all other peers (gossip)
+    pub relay: Vec<SyncMessage>,
+    /// Blocks appended to the local chain (the caller persists them)
+    pub imported: Vec<Block>,
+    /// Side-branch blocks stored without changing the head
+    pub side_blocks: Vec<Block>,
+    /// Head switches caused by a heavier branch
+    pub reorgs: Vec<ReorgEvent>,
+    /// Gossiped transactions added to the mempool, with their bundle (the caller persists them)
+    pub pending: Vec<(u64, Transaction)>,
+}
+
+/// Local identity used in handshakes
+#[derive(Debug, Clone)]
+pub struct ChainSync {
+    pub node_id: String,
+    pub chain_id: String,
+}
+
+impl ChainSync {
+    pub fn new(node_id: String, chain_id: String) -> Self {
+        ChainSync { node_id, chain_id }
+    }
+
+    /// Handshake message for a new connection
+    pub fn hello(&self, blockchain: &Blockchain) -> SyncMessage {
+        SyncMessage::Hello(NodeStatus::local(blockchain, &self.node_id, &self.chain_id))
+    }
+
+    pub fn handle_message(
+        &self,
+        blockchain: &mut Blockchain,
+        message: SyncMessage,
+        verifier: &BlockVerifier,
+    ) -> Result<SyncOutcome, SyncError> {
+        let mut outcome = SyncOutcome::default();
+
+        match message {
+            SyncMessage::Hello(status) => {
+                self.check_compatible(blockchain, &status)?;
+                outcome.reply.push(SyncMessage::Status(NodeStatus::local(
+                    blockchain,
+                    &self.node_id,
+                    &self.chain_id,
+                )));
+                outcome.reply.extend(self.request_missing(blockchain, status.height));
+            }
+            SyncMessage::Status(status) => {
+                self.check_compatible(blockchain, &status)?;
+                outcome.reply.extend(self.request_missing(blockchain, status.height));
+            }
+            SyncMessage::NewTransaction(transaction) => {
+                if self.is_known_transaction(blockchain, &transaction) {
+                    return Ok(outcome);
+                }
+                self.verify_gossiped_transaction(&transaction)?;
+                blockchain
+                    .add_transaction(transaction.clone())
+                    .map_err(SyncError::InvalidTransaction)?;
+                outcome.pending.push((blockchain.last_bundle(), transaction.clone()));
+                outcome.relay.push(SyncMessage::NewTransaction(transaction));
+            }
+            SyncMessage::NewBlock(block) => {
+                if blockchain.knows_block(&block.hash) {
+                    return Ok(outcome);
+                }
+                if block.height > blockchain.get_latest_block().height + 1 {
+                    // We are behind: fetch the gap, the announced block comes with it
+                    outcome.reply.extend(self.request_missing(blockchain, block.height));
+                } else if !blockchain.knows_block(&block.previous_hash) {
+                    // Competing branch forked before what we know: fetch it from the reorg window
+                    outcome.reply.extend(self.request_branch(blockchain, &block));
+                } else {
+                    let hash = block.hash.clone();
+                    self.accept(blockchain, block.clone(), verifier, &mut outcome)?;
+                    info!(height = block.height, hash = %hash, "Accepted announced block");
+                    outcome.relay.push(SyncMessage::NewBlock(block));
+                }
+            }
+            SyncMessage::GetBlocks { from_height, to_height } => {
+                let to_height = to_height.min(from_height.saturating_add(MAX_BLOCKS_PER_REQUEST - 1));
+                let blocks: Vec<Block> = blockchain
+                    .chain
+                    .iter()
+                    .filter(|block| block.height >= from_height && block.height <= to_height)
+                    .cloned()
+                    .collect();
+                outcome.reply.push(SyncMessage::Blocks(blocks));
+            }
+            SyncMessage::Attestation(_) | SyncMessage::Evidence(_) => {
+                // Need the validator key binding, the attestation pool and slashing: handled by the transport
+            }
+            SyncMessage::Blocks(blocks) => {
+                let received = blocks.len() as u64;
+                if let Some(first) = blocks.first() {
+                    if !blockchain.knows_block(&first.previous_hash) {
+                        outcome.reply.extend(self.request_branch(blockchain, first));
+                        return Ok(outcome);
+                    }
+                }
+                for block in blocks {
+                    if blockchain.knows_block(&block.hash) {
+                        continue;
+                    }
+                    self.accept(blockchain, block, verifier, &mut outcome)?;
+                }
+                if !outcome.imported.is_empty() {
+                    info!(count = outcome.imported.len(), height = blockchain.get_latest_block().height, "Range sync imported blocks");
+                }
+                // A full batch means the peer may have more
+                if received == MAX_BLOCKS_PER_REQUEST {
+                    let next = blockchain.get_latest_block().height + 1;
+                    outcome.reply.push(SyncMessage::GetBlocks {
+                        from_height: next,
+                        to_height: next + MAX_BLOCKS_PER_REQUEST - 1,
+                    });
+                }
+            }
+        }
+
+        Ok(outcome)
+    }
+
+    fn check_compatible(&self, blockchain: &Blockchain, status: &NodeStatus) -> Result<(), SyncError> {
+        if status.protocol_version != PROTOCOL_VERSION {
+            return Err(SyncError::IncompatiblePeer(format!(
+                "protocol version {} (local {})",
+                status.protocol_version, PROTOCOL_VERSION
+            )));
+        }
+        if status.chain_id != self.chain_id {
+            return Err(SyncError::IncompatiblePeer(format!("chain id {}", status.chain_id)));
+        }
+        if status.genesis_hash != blockchain.chain[0].hash {
+            return Err(SyncError::IncompatiblePeer(format!("genesis {}", status.genesis_hash)));
+        }
+        Ok(())
+    }
+
+    /// Offer one block to the fork choice and record what happened
+    fn accept(
+        &self,
+        blockchain: &mut Blockchain,
+        block: Block,
+        verifier: &BlockVerifier,
+        outcome: &mut SyncOutcome,
+    ) -> Result<(), SyncError> {
+        match blockchain
+            .accept_block(block.clone(), verifier)
+            .map_err(SyncError::InvalidBlock)?
+        {
+            BlockImport::Known => {}
+            BlockImport::Extended => outcome.imported.push(block),
+            BlockImport::SideBranch => outcome.side_blocks.push(block),
+            BlockImport::Reorganized(event) => {
+                warn!(
+                    old_head = %event.old_head,
+                    new_head = %event.new_head,
+                    depth = event.depth(),
+                    "Chain reorganized to heavier branch"
+                );
+                outcome.reorgs.push(event);
+            }
+        }
+        Ok(())
+    }
+
+    /// Ask for the competing branch starting inside the reorg window
+    fn request_branch(&self, blockchain: &Blockchain, block: &Block) -> Option<SyncMessage> {
+        let from_height = blockchain
+            .get_latest_block()
+            .height
+            .saturating_sub(MAX_REORG_DEPTH)
+            .max(1);
+        // Already asked from the window start: the branch is deeper than we accept
+        if block.height <= from_height {
+            warn!(height = block.height, hash = %block.hash, "Dropping branch outside the reorg window");
+            return None;
+        }
+        Some(SyncMessage::GetBlocks {
+            from_height,
+            to_height: block.height.min(from_height + MAX_BLOCKS_PER_REQUEST - 1),
+        })
+    }
+
+    fn request_missing(&self, blockchain: &Blockchain, peer_height: u64) -> Option<SyncMessage> {
+        let local_height = blockchain.get_latest_block().height;
+        if peer_height <= local_height {
+            return None;
+        }
+        let from_height = local_height + 1;
+        Some(SyncMessage::GetBlocks {
+            from_height,
+            to_height: peer_height.min(from_height + MAX_BLOCKS_PER_REQUEST - 1),
+        })
+    }
+
+    fn is_known_transaction(&self, blockchain: &Blockchain, transaction: &Transaction) -> bool {
+        let tx_hash = transaction.tx_hash();
+        blockchain.pending_transactions.iter().any(|tx| tx.tx_hash() == tx_hash)
+    }
+
+    /// Only user-signed transactions are gossiped; system transactions travel inside blocks.
+    /// The key-to-address binding is checked by the transport against `account_keys`.
+    fn verify_gossiped_transaction(&self, transaction: &Transaction) -> Result<(), SyncError> {
+        let signed = SignedTransaction::from_transaction(transaction)
+            .ok_or_else(|| SyncError::InvalidTransaction("unsigned transaction gossip".to_string()))?;
+        if !signed.kind.is_user_submittable() {
+            return Err(SyncError::InvalidTransaction(format!("{} gossip", signed.kind.name())));
+        }
+        signed
+            .verify(&self.chain_id, &signed.public_key)
+            .map_err(|e| SyncError::InvalidTransaction(e.to_string()))
+    }
+}
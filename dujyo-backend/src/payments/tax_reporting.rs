@@ -2,6 +2,11 @@ use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc, NaiveDate};
 use std::collections::HashMap;
 
+use crate::utils::amount::Amount;
+
+/// Default withholding for US independent contractors (24%)
+pub const DEFAULT_WITHHOLDING_BPS: u64 = 2_400;
+
 // ============================================================================
 // DATA STRUCTURES
 // ============================================================================
@@ -10,9 +15,9 @@ use std::collections::HashMap;
 pub struct TaxReport {
     pub user_id: String,
     pub year: i32,
-    pub total_earnings: f64,
-    pub total_withdrawals: f64,
-    pub tax_withheld: f64,
+    pub total_earnings: Amount,
+    pub total_withdrawals: Amount,
+    pub tax_withheld: Amount,
     pub transactions: Vec<TaxTransaction>,
     pub form_type: TaxFormType,
     pub generated_at: DateTime<Utc>,
@@ -31,7 +36,7 @@ pub struct TaxTransaction {
     pub transaction_id: String,
     pub date: NaiveDate,
     pub description: String,
-    pub amount: f64,
+    pub amount: Amount,
     pub currency: String,
     pub transaction_type: TransactionType,
     pub tax_category: TaxCategory,
@@ -58,9 +63,9 @@ pub enum TaxCategory {
 pub struct TaxWithholding {
     pub user_id: String,
     pub year: i32,
-    pub total_income: f64,
-    pub withholding_rate: f64,
-    pub amount_withheld: f64,
+    pub total_income: Amount,
+    pub withholding_bps: u64,
+    pub amount_withheld: Amount,
 }
 
 // ============================================================================
@@ -86,7 +91,9 @@ impl TaxReportingService {
         year: i32,
         transactions: Vec<TaxTransaction>,
     ) -> TaxReport {
-        let total_earnings: f64 = transactions.iter().map(|t| t.amount).sum();
+        let total_earnings = transactions
+            .iter()
+            .fold(Amount::ZERO, |total, t| total.saturating_add(t.amount));
         
         // Get withholding information
         let withholding_key = format!("{}:{}", user_id, year);
@@ -95,11 +102,11 @@ impl TaxReportingService {
             records
                 .get(&withholding_key)
                 .map(|w| w.amount_withheld)
-                .unwrap_or(0.0)
+                .unwrap_or(Amount::ZERO)
         };
 
         // Calculate total withdrawals (simplified - in production would query withdrawal service)
-        let total_withdrawals = 0.0; // TODO: Query from withdrawal service
+        let total_withdrawals = Amount::ZERO; // TODO: Query from withdrawal service
 
         // Determine form type based on user location/status
         let form_type = self.determine_form_type(user_id).await;
@@ -127,19 +134,20 @@ impl TaxReportingService {
     pub async fn calculate_withholding(
         &self,
         user_id: &str,
-        income: f64,
+        income: Amount,
         year: i32,
     ) -> TaxWithholding {
         // Default withholding rate (can be customized based on user location)
-        let withholding_rate = 0.24; // 24% default for US independent contractors
+        let withholding_bps = DEFAULT_WITHHOLDING_BPS;
         
-        let amount_withheld = income * withholding_rate;
+        // Rounded down: never withhold more than the rate
+        let amount_withheld = income.mul_bps(withholding_bps).unwrap_or(Amount::ZERO);
 
         let withholding = TaxWithholding {
             user_id: user_id.to_string(),
             year,
             total_income: income,
-            withholding_rate,
+            withholding_bps,
             amount_withheld,
         };
 
@@ -148,8 +156,8 @@ impl TaxReportingService {
             let withholding_key = format!("{}:{}", user_id, year);
             let mut records = self.withholding_records.write().await;
             if let Some(existing) = records.get_mut(&withholding_key) {
-                existing.total_income += income;
-                existing.amount_withheld += amount_withheld;
+                existing.total_income = existing.total_income.saturating_add(income);
+                existing.amount_withheld = existing.amount_withheld.saturating_add(amount_withheld);
             } else {
                 records.insert(withholding_key, withholding.clone());
             }
@@ -1,8 +1,12 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use chrono::{DateTime, Utc, Datelike};
 use uuid::Uuid;
 
+use crate::dex::DEX;
+use crate::dex::oracle::twap_window_secs;
+
 // ============================================================================
 // DATA STRUCTURES
 // ============================================================================
@@ -74,6 +78,9 @@ pub struct WithdrawalRecord {
     pub user_id: String,
     pub amount: f64,
     pub currency: String,
+    /// USD value at request time (limits are enforced in USD)
+    #[serde(default)]
+    pub amount_usd: f64,
     pub fee: f64,
     pub net_amount: f64,
     pub status: WithdrawalStatus,
@@ -92,6 +99,8 @@ pub struct WithdrawalService {
     // For now, using in-memory storage
     withdrawals: std::sync::Arc<tokio::sync::RwLock<HashMap<String, WithdrawalRecord>>>,
     limits: WithdrawalLimit,
+    // DEX whose TWAP oracle prices DYO withdrawals (required for DYO)
+    price_oracle: Option<Arc<Mutex<DEX>>>,
 }
 
 impl WithdrawalService {
@@ -105,6 +114,34 @@ impl WithdrawalService {
                 max_amount: 50000.0, // Maximum $50,000 per withdrawal
                 kyc_required: true, // KYC required for withdrawals
             },
+            price_oracle: None,
+        }
+    }
+
+    /// Price DYO withdrawals with the TWAP of the given DEX
+    pub fn with_price_oracle(mut self, dex: Arc<Mutex<DEX>>) -> Self {
+        self.price_oracle = Some(dex);
+        self
+    }
+
+    /// USD value of a withdrawal amount. DYS is pegged to $1; DYO uses the
+    /// DYO/DYS time-weighted average so a swap right before the request cannot
+    /// shrink its value under the limits.
+    pub fn amount_usd(&self, currency: &WithdrawalCurrency, amount: f64) -> Result<f64, String> {
+        match currency {
+            WithdrawalCurrency::USD | WithdrawalCurrency::DYS => Ok(amount),
+            WithdrawalCurrency::DYO => {
+                let dex = self.price_oracle.as_ref()
+                    .ok_or("DYO price oracle not configured")?;
+                let price = {
+                    let dex = dex.lock().map_err(|_| "DEX lock poisoned".to_string())?;
+                    dex.twap_price("DYO", "DYS", twap_window_secs(), Utc::now().timestamp() as u64)?
+                };
+                if price.is_zero() {
+                    return Err("DYO price unavailable".to_string());
+                }
+                Ok(amount * price.to_f64())
+            }
         }
     }
 
@@ -126,7 +163,7 @@ impl WithdrawalService {
         }
     }
 
-    /// Check if withdrawal is within limits
+    /// Check if withdrawal is within limits (`amount` in USD, see `amount_usd`)
     pub async fn check_limits(&self, user_id: &str, amount: f64) -> Result<(), String> {
         // Check minimum amount
         if amount < self.limits.min_amount {
@@ -171,7 +208,7 @@ impl WithdrawalService {
                     && w.created_at.date_naive() == today
                     && matches!(w.status, WithdrawalStatus::Completed)
             })
-            .map(|w| w.amount)
+            .map(|w| w.amount_usd)
             .sum()
     }
 
@@ -194,7 +231,7 @@ impl WithdrawalService {
                     && w.created_at.date_naive() >= month_start
                     && matches!(w.status, WithdrawalStatus::Completed)
             })
-            .map(|w| w.amount)
+            .map(|w| w.amount_usd)
             .sum()
     }
 
@@ -210,7 +247,8 @@ impl WithdrawalService {
         }
 
         // Check limits
-        self.check_limits(&request.user_id, request.amount).await?;
+        let amount_usd = self.amount_usd(&request.currency, request.amount)?;
+        self.check_limits(&request.user_id, amount_usd).await?;
 
         // Calculate fee
         let fee = self.calculate_fee(&request.currency, request.amount);
@@ -223,6 +261,7 @@ impl WithdrawalService {
             user_id: request.user_id.clone(),
             amount: request.amount,
             currency: format!("{:?}", request.currency),
+            amount_usd,
             fee,
             net_amount,
             status: WithdrawalStatus::Pending,
@@ -0,0 +1,161 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use chrono::Utc;
+use serde::Serialize;
+use crate::blockchain::blockchain::Transaction;
+use crate::blockchain::ledger::{is_service_spender, Permit, TxKind};
+use crate::blockchain::mempool::TransactionPriority;
+use crate::blockchain::signed_transaction::chain_id;
+use crate::server::AppState;
+
+#[derive(Serialize)]
+struct AllowanceResponse {
+    success: bool,
+    message: Option<String>,
+    owner: String,
+    spender: String,
+    /// Ledger cents `spender` may still pull from `owner` (pending transactions included)
+    allowance: u64,
+    tx_hash: Option<String>,
+}
+
+/// Pull of `amount` of `owner`'s DYO to `to` by service `spender`, within the
+/// allowance the owner signed (`Approve` or a relayed permit). Callers add it to
+/// the mempool together with what the payment buys.
+pub(crate) fn transfer_from(spender: &str, owner: &str, to: &str, amount: u64) -> Transaction {
+    Transaction::system(owner.to_string(), to.to_string(), amount, None)
+        .with_kind(TxKind::TransferFrom { spender: spender.to_string() })
+}
+
+/// GET /api/v1/allowances/:owner/:spender - DYO a service may pull from an owner
+async fn get_allowance(
+    State(state): State<AppState>,
+    Path((owner, spender)): Path<(String, String)>,
+) -> Result<Json<AllowanceResponse>, StatusCode> {
+    let allowance = state.blockchain.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ledger
+        .allowance(&owner, &spender);
+
+    Ok(Json(AllowanceResponse {
+        success: true,
+        message: None,
+        owner,
+        spender,
+        allowance,
+        tx_hash: None,
+    }))
+}
+
+/// POST /api/v1/allowances/permit - Relay a permit the owner signed off-chain.
+/// Anyone may relay it (the marketplace or billing frontend); the signature must
+/// come from the key bound to the owner's account (POST /account/key) and the
+/// nonce is the owner's next account nonce.
+async fn submit_permit(
+    State(state): State<AppState>,
+    Json(permit): Json<Permit>,
+) -> Result<Json<AllowanceResponse>, StatusCode> {
+    let rejected = |permit: &Permit, message: String| AllowanceResponse {
+        success: false,
+        message: Some(message),
+        owner: permit.owner.clone(),
+        spender: permit.spender.clone(),
+        allowance: 0,
+        tx_hash: None,
+    };
+
+    if !is_service_spender(&permit.spender) {
+        return Ok(Json(rejected(&permit, format!("{} is not a service that can hold allowances", permit.spender))));
+    }
+    if Utc::now().timestamp() as u64 > permit.deadline {
+        return Ok(Json(rejected(&permit, format!("Permit expired at {}", permit.deadline))));
+    }
+
+    let registered_key = match state.storage.get_account_key(&permit.owner).await {
+        Ok(Some(key)) => key,
+        Ok(None) => {
+            return Ok(Json(rejected(&permit, "No public key bound to the owner's account".to_string())));
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to load account key");
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    if let Err(e) = permit.verify(&chain_id(), &registered_key) {
+        tracing::warn!(owner = %permit.owner, error = %e, "Rejected permit");
+        return Ok(Json(rejected(&permit, e)));
+    }
+
+    let mut tx = state.storage.pool.begin().await.map_err(|e| {
+        tracing::error!(error = %e, "Failed to begin transaction");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    // Permits share the owner's nonce sequence with its signed transactions
+    match state.storage.commit_account_nonce_atomic(&permit.owner, permit.nonce, &mut tx).await {
+        Ok(true) => {}
+        Ok(false) => {
+            tx.rollback().await.ok();
+            return Ok(Json(rejected(&permit, format!("Nonce {} already used or out of order (replay rejected)", permit.nonce))));
+        }
+        Err(e) => {
+            tx.rollback().await.ok();
+            tracing::error!(error = %e, "Failed to persist account nonce");
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+
+    let added = {
+        let mut blockchain = state.blockchain.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let transaction = Transaction::system(permit.owner.clone(), permit.spender.clone(), permit.amount, None)
+            .with_kind(TxKind::Permit {
+                nonce: permit.nonce,
+                deadline: permit.deadline,
+                signature: permit.signature.to_lowercase(),
+                previous: blockchain.ledger.allowance(&permit.owner, &permit.spender),
+            });
+        let tx_hash = transaction.tx_hash();
+        blockchain
+            .add_transaction(transaction.clone())
+            .map(|()| (transaction, tx_hash, blockchain.last_bundle()))
+    };
+    let (transaction, tx_hash, bundle) = match added {
+        Ok(added) => added,
+        Err(e) => {
+            tx.rollback().await.ok();
+            return Ok(Json(rejected(&permit, e)));
+        }
+    };
+
+    if let Err(e) = state.storage
+        .save_pending_bundle_atomic(bundle, TransactionPriority::Normal, std::slice::from_ref(&transaction), &mut tx)
+        .await
+    {
+        tracing::error!(error = %e, "Failed to persist permit to the mempool");
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+    if let Err(e) = tx.commit().await {
+        tracing::error!(error = %e, "Failed to commit permit nonce");
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+    tracing::info!(owner = %permit.owner, spender = %permit.spender, amount = permit.amount, "Permit relayed");
+
+    Ok(Json(AllowanceResponse {
+        success: true,
+        message: Some(format!("{} may pull up to {} DYO cents of {}", permit.spender, permit.amount, permit.owner)),
+        owner: permit.owner,
+        spender: permit.spender,
+        allowance: permit.amount,
+        tx_hash: Some(tx_hash),
+    }))
+}
+
+pub fn allowance_routes() -> Router<AppState> {
+    Router::new()
+        .route("/permit", post(submit_permit))
+        .route("/:owner/:spender", get(get_allowance))
+}
@@ -1,13 +1,25 @@
 use axum::{
-    extract::State,
+    extract::{Extension, Path, Query, State},
     http::StatusCode,
     response::Json,
-    routing::get,
+    routing::{get, post},
     Router,
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use sqlx::Row;
-use crate::server::AppState;
+use crate::auth::Claims;
+use crate::dex::SwapRoute;
+use crate::dex::analytics::{self, Candle, CandleInterval, PoolStats};
+use crate::dex::batch_auction::batch_auction_enabled;
+use crate::dex::oracle::{self, twap_window_secs};
+use crate::dex::orders::{Order, OrderFill, OrderStatus, PlaceOrderRequest};
+use crate::blockchain::ledger::NATIVE_TOKEN;
+use crate::server::{
+    announce_order, change_liquidity, persist_liquidity_leg, queue_batch_swap, record_dex_transaction, spend_swap_nonce,
+    sync_dex_pools, AppState,
+};
+use crate::storage::DexTransactionRecord;
+use crate::utils::amount::Amount;
 
 #[derive(Serialize)]
 struct TopTrader {
@@ -88,8 +100,663 @@ async fn get_top_traders(
     }
 }
 
+#[derive(Serialize)]
+struct PoolSummary {
+    pool_id: String,
+    token_a: String,
+    token_b: String,
+    reserve_a: Amount,
+    reserve_b: Amount,
+    total_liquidity: Amount,
+    price: f64, // token_b per token_a
+}
+
+#[derive(Serialize)]
+struct PoolsResponse {
+    pools: Vec<PoolSummary>,
+}
+
+#[derive(Deserialize)]
+pub struct CreatePoolRequest {
+    pub token_a: String,
+    pub token_b: String,
+    pub amount_a: Amount,
+    pub amount_b: Amount,
+}
+
+#[derive(Serialize)]
+pub struct CreatePoolResponse {
+    pub success: bool,
+    pub message: String,
+    pub pool_id: Option<String>,
+    pub tx_hash: Option<String>,
+    pub lp_tokens_minted: Option<Amount>,
+}
+
+#[derive(Deserialize)]
+pub struct QuoteQuery {
+    pub from: String,
+    pub to: String,
+    pub amount: Amount,
+}
+
+#[derive(Serialize)]
+pub struct QuoteResponse {
+    pub success: bool,
+    pub message: String,
+    pub route: Option<SwapRoute>,
+}
+
+#[derive(Deserialize)]
+pub struct OracleQuery {
+    /// TWAP window in seconds (defaults to DUJYO_TWAP_WINDOW_SECS)
+    pub window: Option<u64>,
+}
+
+#[derive(Serialize, Default)]
+pub struct OracleResponse {
+    pub success: bool,
+    pub message: String,
+    pub pool_id: Option<String>,
+    pub base: String,
+    pub quote: String,
+    /// Time-weighted price of `base` in `quote`
+    pub twap_price: Option<Amount>,
+    /// Current reserve ratio, for comparison only
+    pub spot_price: Option<Amount>,
+    /// Seconds actually covered by `twap_price`
+    pub window_secs: Option<u64>,
+    pub observations: usize,
+    pub last_update: Option<u64>,
+}
+
+#[derive(Deserialize)]
+pub struct CandlesQuery {
+    /// 1m, 5m, 1h or 1d (default 1h)
+    pub interval: Option<String>,
+    /// Unix seconds; defaults to the last 500 candles up to now
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+}
+
+#[derive(Serialize, Default)]
+pub struct CandlesResponse {
+    pub success: bool,
+    pub message: String,
+    pub pool_id: String,
+    pub interval: Option<CandleInterval>,
+    pub candles: Vec<Candle>,
+}
+
+#[derive(Serialize)]
+pub struct PoolStatsResponse {
+    pub success: bool,
+    pub message: String,
+    pub stats: Option<PoolStats>,
+}
+
+#[derive(Serialize)]
+pub struct OrderResponse {
+    pub success: bool,
+    pub message: String,
+    pub order: Option<Order>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fills: Option<Vec<OrderFill>>,
+}
+
+impl OrderResponse {
+    fn rejected(message: impl Into<String>) -> Json<Self> {
+        Json(Self { success: false, message: message.into(), order: None, fills: None })
+    }
+}
+
+#[derive(Serialize)]
+pub struct NonceResponse {
+    pub address: String,
+    /// Smallest nonce the next swap can carry
+    pub next_nonce: u64,
+}
+
+#[derive(Serialize)]
+pub struct OrdersResponse {
+    pub orders: Vec<Order>,
+}
+
+#[derive(Deserialize)]
+pub struct RoutedSwapRequest {
+    pub from: String,
+    pub to: String,
+    pub amount: Amount,
+    pub min_received: Amount,
+    /// Unix seconds after which the swap must not execute
+    pub deadline: u64,
+    /// Above the last nonce spent (see `GET /nonce`)
+    pub nonce: u64,
+}
+
+#[derive(Serialize)]
+pub struct RoutedSwapResponse {
+    pub success: bool,
+    pub message: String,
+    pub tx_hash: Option<String>,
+    pub amount_received: Option<Amount>,
+    pub price_impact: Option<f64>,
+    pub route: Option<Vec<String>>,
+}
+
+impl RoutedSwapResponse {
+    fn rejected(message: String) -> Self {
+        RoutedSwapResponse {
+            success: false,
+            message,
+            tx_hash: None,
+            amount_received: None,
+            price_impact: None,
+            route: None,
+        }
+    }
+}
+
+/// GET /api/v1/dex/pools - Every pool with its reserves and spot price
+async fn list_pools(State(state): State<AppState>) -> Json<PoolsResponse> {
+    let dex = state.dex.lock().unwrap();
+    let mut pools: Vec<PoolSummary> = dex
+        .get_all_pools()
+        .into_iter()
+        .map(|pool| PoolSummary {
+            pool_id: pool.id.clone(),
+            token_a: pool.token_a.clone(),
+            token_b: pool.token_b.clone(),
+            reserve_a: pool.reserve_a,
+            reserve_b: pool.reserve_b,
+            total_liquidity: pool.total_liquidity,
+            price: pool.reserve_b.ratio(pool.reserve_a),
+        })
+        .collect();
+    pools.sort_by(|a, b| a.pool_id.cmp(&b.pool_id));
+    Json(PoolsResponse { pools })
+}
+
+/// POST /api/v1/dex/pools - Create the pool of a new token pair (permissionless);
+/// the creator's deposit sets the initial price
+async fn create_pool(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(request): Json<CreatePoolRequest>,
+) -> Result<Json<CreatePoolResponse>, StatusCode> {
+    let user = claims.sub;
+    let rejected = |message: String| Json(CreatePoolResponse {
+        success: false,
+        message,
+        pool_id: None,
+        tx_hash: None,
+        lp_tokens_minted: None,
+    });
+
+    // Deposits are settled like /liquidity/add: a DYO side on the ledger (funding
+    // the pool account), the other side from the token balance
+    let token_deposits: Vec<Amount> = [(&request.token_a, request.amount_a), (&request.token_b, request.amount_b)]
+        .into_iter()
+        .filter(|(symbol, _)| symbol.as_str() != NATIVE_TOKEN)
+        .map(|(_, amount)| amount)
+        .collect();
+    let has_balance = {
+        let token = state.token.lock().unwrap();
+        token.has_balance(&user, token_deposits.iter().fold(Amount::ZERO, |total, amount| total.saturating_add(*amount)))
+    };
+    if !has_balance {
+        return Ok(rejected("Insufficient balance for the initial deposit".to_string()));
+    }
+
+    let pool_id = crate::dex::pool_id(&request.token_a, &request.token_b);
+    let creation = change_liquidity(&state, &pool_id, &user, |dex| {
+        dex.create_pool(crate::dex::LiquidityRequest {
+            token_a: request.token_a.clone(),
+            token_b: request.token_b.clone(),
+            amount_a: request.amount_a,
+            amount_b: request.amount_b,
+            user: user.clone(),
+        })
+    });
+    let liquidity_response = match creation {
+        Ok((response, ledger_leg)) => {
+            persist_liquidity_leg(&state, ledger_leg).await;
+            response
+        }
+        Err(e) => return Ok(rejected(e)),
+    };
+
+    let transfer_result = {
+        let mut token = state.token.lock().unwrap();
+        token_deposits
+            .into_iter()
+            .try_for_each(|amount| token.transfer(&user, "DEX_CONTRACT", amount, "").map(|_| ()))
+    };
+    if let Err(e) = transfer_result {
+        return Ok(rejected(format!("Failed to deduct the initial deposit: {}", e)));
+    }
+
+    let synced = sync_dex_pools(&state, std::slice::from_ref(&pool_id)).await;
+    if let (Some(tx_hash), Some(pool)) = (&liquidity_response.tx_hash, synced.first()) {
+        record_dex_transaction(&state, &DexTransactionRecord {
+            tx_hash,
+            from: &user,
+            to: "DEX_CONTRACT",
+            amount_in: request.amount_a.saturating_add(request.amount_b),
+            amount_out: Amount::ZERO,
+            transaction_type: "liquidity_add",
+            token_in: None,
+            pool,
+        }).await;
+    }
+    if let Some(lp_balance) = liquidity_response.lp_balance {
+        let position_id = format!("{}_{}", user, pool_id);
+        if let Err(e) = state.storage.save_liquidity_position(&position_id, &user, &pool_id, lp_balance).await {
+            tracing::warn!("⚠️  Failed to save liquidity position to DB: {}", e);
+        }
+    }
+
+    Ok(Json(CreatePoolResponse {
+        success: true,
+        message: format!("Pool {} created", pool_id),
+        pool_id: Some(pool_id),
+        tx_hash: liquidity_response.tx_hash,
+        lp_tokens_minted: liquidity_response.lp_tokens_minted,
+    }))
+}
+
+/// GET /api/v1/dex/quote?from=DYO&to=ETH&amount=10 - Best route (up to three pools)
+async fn get_quote(
+    State(state): State<AppState>,
+    Query(query): Query<QuoteQuery>,
+) -> Json<QuoteResponse> {
+    let quote = {
+        let dex = state.dex.lock().unwrap();
+        dex.find_best_route(&query.from, &query.to, query.amount)
+    };
+    match quote {
+        Ok(route) => Json(QuoteResponse {
+            success: true,
+            message: format!("Best route: {}", route.path.join(" -> ")),
+            route: Some(route),
+        }),
+        Err(e) => Json(QuoteResponse {
+            success: false,
+            message: e,
+            route: None,
+        }),
+    }
+}
+
+/// GET /api/v1/dex/oracle/DYO_DYS?window=1800 - TWAP of the first token in the second
+async fn get_oracle_price(
+    State(state): State<AppState>,
+    Path(pair): Path<String>,
+    Query(query): Query<OracleQuery>,
+) -> Json<OracleResponse> {
+    let Some((base, quote)) = pair.split_once(['_', '-', '/']) else {
+        return Json(OracleResponse {
+            message: format!("Invalid pair {}, expected BASE_QUOTE", pair),
+            ..Default::default()
+        });
+    };
+    let mut response = OracleResponse {
+        base: base.to_uppercase(),
+        quote: quote.to_uppercase(),
+        ..Default::default()
+    };
+
+    let window = query.window.unwrap_or_else(twap_window_secs);
+    let now = chrono::Utc::now().timestamp() as u64;
+    let dex = state.dex.lock().unwrap();
+    let Some(pool) = dex.get_pair(&response.base, &response.quote) else {
+        response.message = format!("Pool {} not found", crate::dex::pool_id(&response.base, &response.quote));
+        return Json(response);
+    };
+    let (price_a, price_b) = oracle::spot_prices(pool);
+    let base_is_a = pool.token_a == response.base;
+    response.pool_id = Some(pool.id.clone());
+    response.spot_price = Some(if base_is_a { price_a } else { price_b });
+    response.observations = dex.oracle.observation_count(&pool.id);
+    response.last_update = dex.oracle.last_update(&pool.id);
+
+    match dex.oracle.twap(&pool.id, window, now) {
+        Ok(twap) => {
+            response.success = true;
+            response.message = format!("TWAP over {}s", twap.window_secs);
+            response.twap_price = Some(if base_is_a { twap.price_a } else { twap.price_b });
+            response.window_secs = Some(twap.window_secs);
+        }
+        Err(e) => response.message = e,
+    }
+    Json(response)
+}
+
+/// GET /api/v1/dex/pools/DYO_DYS/candles?interval=5m&from=..&to=.. - OHLCV series
+async fn get_pool_candles(
+    State(state): State<AppState>,
+    Path(pool_id): Path<String>,
+    Query(query): Query<CandlesQuery>,
+) -> Result<Json<CandlesResponse>, StatusCode> {
+    let mut response = CandlesResponse { pool_id: pool_id.clone(), ..Default::default() };
+    let interval_name = query.interval.as_deref().unwrap_or("1h");
+    let Some(interval) = CandleInterval::parse(interval_name) else {
+        response.message = format!("Unsupported interval {}, expected 1m, 5m, 1h or 1d", interval_name);
+        return Ok(Json(response));
+    };
+    let pool = {
+        let dex = state.dex.lock().unwrap();
+        dex.get_pool(&pool_id).cloned()
+    };
+    let Some(pool) = pool else {
+        response.message = format!("Pool {} not found", pool_id);
+        return Ok(Json(response));
+    };
+
+    let to = query.to.unwrap_or_else(|| chrono::Utc::now().timestamp() as u64 + 1);
+    let span = interval.secs() * (analytics::MAX_CANDLES / 2);
+    let from = interval.bucket_start(query.from.unwrap_or(to.saturating_sub(span)));
+    if from >= to {
+        response.message = "`from` must be before `to`".to_string();
+        return Ok(Json(response));
+    }
+    // Never scan more rows than MAX_CANDLES buckets
+    let to = to.min(from + interval.secs() * analytics::MAX_CANDLES);
+
+    let buckets = state.storage.get_dex_candles(&pool, interval, from, to).await.map_err(|e| {
+        tracing::error!("Failed to load candles of {}: {}", pool_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let previous = state.storage.get_dex_pool_state_before(&pool_id, from).await.map_err(|e| {
+        tracing::error!("Failed to load the pool state of {}: {}", pool_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    response.candles = analytics::fill_candles(buckets, interval, from, to, previous.map(|prior| prior.price()));
+    response.success = true;
+    response.message = format!("{} {} candles", response.candles.len(), interval.as_str());
+    response.interval = Some(interval);
+    Ok(Json(response))
+}
+
+/// GET /api/v1/dex/pools/DYO_DYS/stats - Price, TVL, 24h volume, fee APR and LP return
+async fn get_pool_stats(
+    State(state): State<AppState>,
+    Path(pool_id): Path<String>,
+) -> Result<Json<PoolStatsResponse>, StatusCode> {
+    let (pool, fee_rate) = {
+        let dex = state.dex.lock().unwrap();
+        (dex.get_pool(&pool_id).cloned(), dex.fee_rate)
+    };
+    let Some(pool) = pool else {
+        return Ok(Json(PoolStatsResponse {
+            success: false,
+            message: format!("Pool {} not found", pool_id),
+            stats: None,
+        }));
+    };
+
+    let day_ago = (chrono::Utc::now().timestamp() as u64).saturating_sub(analytics::DAY_SECS);
+    let volume = state.storage.get_dex_volume(&pool, day_ago).await;
+    let previous = state.storage.get_dex_pool_state_before(&pool_id, day_ago).await;
+    let (volume, previous) = match (volume, previous) {
+        (Ok(volume), Ok(previous)) => (volume, previous),
+        (Err(e), _) | (_, Err(e)) => {
+            tracing::error!("Failed to load the 24h activity of {}: {}", pool_id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    Ok(Json(PoolStatsResponse {
+        success: true,
+        message: format!("Pool {} stats", pool_id),
+        stats: Some(analytics::pool_stats(&pool, fee_rate, &volume, previous.as_ref())),
+    }))
+}
+
+/// POST /api/v1/dex/orders - Place a limit or DCA order; its input is escrowed
+/// from the user's balance until filled, cancelled or expired
+async fn place_order(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(request): Json<PlaceOrderRequest>,
+) -> Result<Json<OrderResponse>, StatusCode> {
+    let now = chrono::Utc::now().timestamp() as u64;
+    let order_id = format!("order_{}", uuid::Uuid::new_v4());
+    let order = match Order::new(order_id, claims.sub, &request, now) {
+        Ok(order) => order,
+        Err(e) => return Ok(OrderResponse::rejected(e)),
+    };
+    {
+        let dex = state.dex.lock().unwrap();
+        if dex.get_pair(&order.from_token, &order.to_token).is_none() {
+            return Ok(OrderResponse::rejected(format!(
+                "Pool {} not found", crate::dex::pool_id(&order.from_token, &order.to_token))));
+        }
+    }
+    // Escrow and fills settle in token_balances, which holds DYO and DYS only
+    if [&order.from_token, &order.to_token].iter().any(|token| !matches!(token.as_str(), "DYO" | "DYS")) {
+        return Ok(OrderResponse::rejected("Orders trade DYO and DYS balances only"));
+    }
+
+    match state.storage.place_dex_order(&order).await {
+        Ok(true) => Ok(Json(OrderResponse {
+            success: true,
+            message: format!("{} order {} placed", order.kind.as_str(), order.order_id),
+            order: Some(order),
+            fills: None,
+        })),
+        Ok(false) => Ok(OrderResponse::rejected(format!("Insufficient {} balance", order.from_token))),
+        Err(e) => {
+            tracing::error!("Failed to place DEX order: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// GET /api/v1/dex/nonce - Next swap nonce of the caller
+async fn get_nonce(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Json<NonceResponse> {
+    let next_nonce = state.dex.lock().unwrap().next_nonce(&claims.sub);
+    Json(NonceResponse { address: claims.sub, next_nonce })
+}
+
+/// GET /api/v1/dex/orders - The caller's orders, newest first
+async fn list_orders(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<OrdersResponse>, StatusCode> {
+    let orders = state.storage.get_user_dex_orders(&claims.sub, 100).await.map_err(|e| {
+        tracing::error!("Failed to load DEX orders: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(OrdersResponse { orders }))
+}
+
+/// GET /api/v1/dex/orders/:id - One of the caller's orders with its fills
+async fn get_order(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(order_id): Path<String>,
+) -> Result<Json<OrderResponse>, StatusCode> {
+    let order = state.storage.get_dex_order(&order_id).await.map_err(|e| {
+        tracing::error!("Failed to load DEX order: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let Some(order) = order.filter(|order| order.user == claims.sub) else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+    let fills = state.storage.get_dex_order_fills(&order_id).await.map_err(|e| {
+        tracing::error!("Failed to load DEX order fills: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(OrderResponse {
+        success: true,
+        message: format!("Order {} is {}", order_id, order.status.as_str()),
+        order: Some(order),
+        fills: Some(fills),
+    }))
+}
+
+/// DELETE /api/v1/dex/orders/:id - Cancel an open order and refund what is left
+async fn cancel_order(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(order_id): Path<String>,
+) -> Result<Json<OrderResponse>, StatusCode> {
+    let closed = state.storage.close_dex_order(&order_id, Some(&claims.sub), OrderStatus::Cancelled).await
+        .map_err(|e| {
+            tracing::error!("Failed to cancel DEX order: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    let Some(order) = closed else {
+        return Ok(OrderResponse::rejected(format!("Order {} is not open", order_id)));
+    };
+
+    let pool_id = crate::dex::pool_id(&order.from_token, &order.to_token);
+    announce_order(&state, &pool_id, order.event(order.remaining, Amount::ZERO)).await;
+    Ok(Json(OrderResponse {
+        success: true,
+        message: format!("Order cancelled, {} {} refunded", order.remaining, order.from_token),
+        order: Some(order),
+        fills: None,
+    }))
+}
+
+/// POST /api/v1/dex/swap - Swap along the best route; `min_received` bounds the
+/// final output. Only the input and output tokens touch the user's balances.
+/// In batch auction mode the swap (direct pair only) is queued for the next block.
+async fn routed_swap(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(request): Json<RoutedSwapRequest>,
+) -> Result<Json<RoutedSwapResponse>, StatusCode> {
+    let user = claims.sub;
+
+    // ✅ MEV: In batch mode the swap waits for the uniform-price auction of the next block
+    if batch_auction_enabled() {
+        let request = crate::dex::SwapRequest {
+            from: request.from,
+            to: request.to,
+            amount: request.amount,
+            min_received: request.min_received,
+            user,
+            deadline: request.deadline,
+            nonce: request.nonce,
+        };
+        return Ok(Json(match queue_batch_swap(&state, request).await {
+            Ok(swap) => RoutedSwapResponse {
+                success: true,
+                message: "Swap queued for the batch auction of the next block".to_string(),
+                tx_hash: Some(swap.swap_id),
+                amount_received: None,
+                price_impact: None,
+                route: Some(vec![swap.from, swap.to]),
+            },
+            Err(e) => RoutedSwapResponse::rejected(e),
+        }));
+    }
+
+    let mut balance = state.storage.get_token_balance(&user).await.map_err(|e| {
+        tracing::error!("Failed to get token balance from database: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if balance.available(&request.to).is_none() {
+        return Ok(Json(RoutedSwapResponse::rejected(format!("Unsupported token: {}", request.to))));
+    }
+    if let Err(e) = balance.debit(&request.from, request.amount) {
+        return Ok(Json(RoutedSwapResponse::rejected(e)));
+    }
+
+    // ✅ SECURITY: Spend the nonce first (compare-and-set) so concurrent replays lose the race
+    if let Err(e) = spend_swap_nonce(&state, &user, request.nonce).await {
+        return Ok(Json(RoutedSwapResponse::rejected(e)));
+    }
+
+    let swap_result = {
+        let mut dex = state.dex.lock().unwrap();
+        dex.execute_routed_swap(crate::dex::SwapRequest {
+            from: request.from.clone(),
+            to: request.to.clone(),
+            amount: request.amount,
+            min_received: request.min_received,
+            user: user.clone(),
+            deadline: request.deadline,
+            nonce: request.nonce,
+        })
+    };
+    let swap_response = match swap_result {
+        Ok(response) => response,
+        Err(e) => return Ok(Json(RoutedSwapResponse::rejected(e))),
+    };
+
+    let amount_received = swap_response.amount_received.unwrap_or(Amount::ZERO);
+    let path = swap_response.route.clone().unwrap_or_default();
+    let pools: Vec<String> = path.windows(2).map(|hop| crate::dex::pool_id(&hop[0], &hop[1])).collect();
+
+    if let Err(e) = balance.credit(&request.to, amount_received) {
+        return Ok(Json(RoutedSwapResponse::rejected(e)));
+    }
+    if let Err(e) = state.storage.save_token_balance(&user, &balance).await {
+        tracing::error!("⚠️  Failed to update balance in DB: {}", e);
+        return Ok(Json(RoutedSwapResponse {
+            success: false,
+            message: format!("Swap executed but failed to update balance: {}", e),
+            ..RoutedSwapResponse::from(swap_response)
+        }));
+    }
+
+    // One row per hop, so every pool on the route gets its own volume and price
+    let synced = sync_dex_pools(&state, &pools).await;
+    let amounts = swap_response.amounts.clone().unwrap_or_default();
+    if let Some(tx_hash) = &swap_response.tx_hash {
+        for (hop, pool) in synced.iter().enumerate() {
+            let (Some(&amount_in), Some(&amount_out)) = (amounts.get(hop), amounts.get(hop + 1)) else {
+                continue;
+            };
+            let hop_hash = if hop == 0 { tx_hash.clone() } else { format!("{}_hop{}", tx_hash, hop) };
+            record_dex_transaction(&state, &DexTransactionRecord {
+                tx_hash: &hop_hash,
+                from: &user,
+                to: "DEX_CONTRACT",
+                amount_in,
+                amount_out,
+                transaction_type: "swap",
+                token_in: Some(&path[hop]),
+                pool,
+            }).await;
+        }
+    }
+
+    Ok(Json(swap_response.into()))
+}
+
+impl From<crate::dex::SwapResponse> for RoutedSwapResponse {
+    fn from(response: crate::dex::SwapResponse) -> Self {
+        RoutedSwapResponse {
+            success: response.success,
+            message: response.message,
+            tx_hash: response.tx_hash,
+            amount_received: response.amount_received,
+            price_impact: response.price_impact,
+            route: response.route,
+        }
+    }
+}
+
 pub fn dex_routes() -> Router<AppState> {
     Router::new()
         .route("/top-traders", get(get_top_traders))
+        .route("/pools", get(list_pools).post(create_pool))
+        .route("/pools/:id/candles", get(get_pool_candles))
+        .route("/pools/:id/stats", get(get_pool_stats))
+        .route("/quote", get(get_quote))
+        .route("/oracle/:pair", get(get_oracle_price))
+        .route("/orders", get(list_orders).post(place_order))
+        .route("/orders/:id", get(get_order).delete(cancel_order))
+        .route("/nonce", get(get_nonce))
+        .route("/swap", post(routed_swap))
 }
 
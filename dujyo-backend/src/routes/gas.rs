@@ -0,0 +1,162 @@
+use axum::{
+    extract::{Extension, Query, State},
+    http::StatusCode,
+    response::Json,
+    routing::get,
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use crate::auth::Claims;
+use crate::blockchain::fee_market::{FeeMarket, FeeTier};
+use crate::blockchain::gas_fees::{AutoSwapSettings, TransactionType, UserTier};
+use crate::blockchain::mempool::MempoolStats;
+use crate::server::{current_fee_market, AppState};
+use crate::utils::amount::Amount;
+
+#[derive(Serialize)]
+struct AutoSwapSettingsResponse {
+    success: bool,
+    message: Option<String>,
+    address: String,
+    settings: AutoSwapSettings,
+}
+
+/// Partial update: fields left out keep their current value
+#[derive(Deserialize)]
+struct UpdateAutoSwapRequest {
+    enabled: Option<bool>,
+    max_slippage_bps: Option<u64>,
+}
+
+#[derive(Deserialize)]
+pub struct GasEstimateQuery {
+    /// Transaction amount in DYO (percentage-based fees need it)
+    amount: Option<Amount>,
+    /// Only this transaction type (all types when omitted)
+    tx_type: Option<TransactionType>,
+}
+
+#[derive(Serialize)]
+struct GasEstimate {
+    transaction_type: TransactionType,
+    gas_fee_cents: u64,
+    gas_fee_dyo: Amount,
+    /// Slow (no tip), standard and fast (high priority) options
+    tiers: Vec<FeeTier>,
+    /// Why this type has no estimate (e.g. the amount is required)
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct GasEstimateResponse {
+    success: bool,
+    base_fee_bps: u64,
+    congestion_level: f64,
+    dyo_price_usd: Amount,
+    mempool: MempoolStats,
+    estimates: Vec<GasEstimate>,
+}
+
+/// GET /api/v1/gas/estimate - Gas fee and tip tiers per transaction type at the
+/// current congestion and base fee (public)
+pub async fn estimate_gas_fees(
+    State(state): State<AppState>,
+    Query(query): Query<GasEstimateQuery>,
+) -> Result<Json<GasEstimateResponse>, StatusCode> {
+    let (fee_market, network_state, mempool) = current_fee_market(&state)?;
+    let calculator = state.gas_fees.read().unwrap();
+
+    let estimates = TransactionType::ALL
+        .iter()
+        .filter(|tx_type| query.tx_type.as_ref().is_none_or(|wanted| wanted == *tx_type))
+        .map(|tx_type| {
+            match calculator.calculate_gas_fee(tx_type, query.amount, &UserTier::Regular, &network_state, false) {
+                Ok(gas_fee_dyo) => {
+                    let gas_fee_cents = fee_market.gas_fee_cents(gas_fee_dyo);
+                    GasEstimate {
+                        transaction_type: tx_type.clone(),
+                        gas_fee_cents,
+                        gas_fee_dyo: Amount::from_cents(gas_fee_cents),
+                        tiers: FeeMarket::fee_tiers(gas_fee_cents),
+                        error: None,
+                    }
+                }
+                Err(e) => GasEstimate {
+                    transaction_type: tx_type.clone(),
+                    gas_fee_cents: 0,
+                    gas_fee_dyo: Amount::ZERO,
+                    tiers: Vec::new(),
+                    error: Some(e),
+                },
+            }
+        })
+        .collect();
+
+    Ok(Json(GasEstimateResponse {
+        success: true,
+        base_fee_bps: fee_market.base_fee_bps,
+        congestion_level: network_state.congestion_level,
+        dyo_price_usd: network_state.dyo_price_usd,
+        mempool,
+        estimates,
+    }))
+}
+
+/// GET /api/v1/gas/auto-swap - The caller's gas auto-swap settings
+async fn get_auto_swap_settings(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<AutoSwapSettingsResponse>, StatusCode> {
+    let settings = state.storage.get_auto_swap_settings(&claims.sub).await.map_err(|e| {
+        tracing::error!("Failed to load auto-swap settings: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(AutoSwapSettingsResponse {
+        success: true,
+        message: None,
+        address: claims.sub,
+        settings,
+    }))
+}
+
+/// PUT /api/v1/gas/auto-swap - Turn the auto-swap on/off or change its max slippage
+async fn update_auto_swap_settings(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(request): Json<UpdateAutoSwapRequest>,
+) -> Result<Json<AutoSwapSettingsResponse>, StatusCode> {
+    let mut settings = state.storage.get_auto_swap_settings(&claims.sub).await.map_err(|e| {
+        tracing::error!("Failed to load auto-swap settings: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if let Some(enabled) = request.enabled {
+        settings.enabled = enabled;
+    }
+    if let Some(max_slippage_bps) = request.max_slippage_bps {
+        settings.max_slippage_bps = max_slippage_bps;
+    }
+    if let Err(e) = settings.validate() {
+        return Ok(Json(AutoSwapSettingsResponse {
+            success: false,
+            message: Some(e),
+            address: claims.sub,
+            settings,
+        }));
+    }
+
+    state.storage.save_auto_swap_settings(&claims.sub, &settings).await.map_err(|e| {
+        tracing::error!("Failed to save auto-swap settings: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(AutoSwapSettingsResponse {
+        success: true,
+        message: Some("Auto-swap settings updated".to_string()),
+        address: claims.sub,
+        settings,
+    }))
+}
+
+pub fn gas_routes() -> Router<AppState> {
+    Router::new()
+        .route("/auto-swap", get(get_auto_swap_settings).put(update_auto_swap_settings))
+}
@@ -0,0 +1,263 @@
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use crate::auth::Claims;
+use crate::blockchain::ledger::TxKind;
+use crate::blockchain::mempool::TransactionPriority;
+use crate::blockchain::signed_transaction::SignedTransaction;
+use crate::governance::{self, GovernanceAction, GovernanceParams, Proposal, ProposalStatus, Tally, VotingPower};
+use crate::server::{submit_signed_transaction, AppState};
+
+#[derive(Serialize)]
+struct ProposalView {
+    #[serde(flatten)]
+    proposal: Proposal,
+    /// Votes on chain so far, at the power snapshotted for the proposal (proposals still in voting)
+    live_tally: Option<Tally>,
+}
+
+#[derive(Serialize)]
+struct ProposalListResponse {
+    success: bool,
+    params: GovernanceParams,
+    proposals: Vec<ProposalView>,
+}
+
+#[derive(Serialize)]
+struct ProposalResponse {
+    success: bool,
+    message: Option<String>,
+    proposal: Option<ProposalView>,
+}
+
+#[derive(Deserialize)]
+struct CreateProposalRequest {
+    title: String,
+    #[serde(default)]
+    description: String,
+    action: GovernanceAction,
+    /// Deposit in ledger cents (the minimum when omitted)
+    deposit: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct VoteResponse {
+    success: bool,
+    message: String,
+    proposal_id: String,
+    support: bool,
+    tx_hash: Option<String>,
+    /// The caller's power in the proposal's snapshot, which the vote counts with
+    voting_power: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct VotingPowerResponse {
+    address: String,
+    voting_power: VotingPower,
+    total: u64,
+    total_voting_power: u64,
+}
+
+/// Attach the live tally to the proposals still in voting
+fn with_live_tally(state: &AppState, proposals: Vec<Proposal>) -> Result<Vec<ProposalView>, StatusCode> {
+    let blockchain = state.blockchain.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(proposals
+        .into_iter()
+        .map(|proposal| {
+            let live_tally = (proposal.status == ProposalStatus::Voting).then(|| {
+                let votes = governance::chain_votes(&blockchain.chain, &proposal.proposal_id, proposal.voting_ends_height);
+                governance::tally(&proposal, &votes)
+            });
+            ProposalView { proposal, live_tally }
+        })
+        .collect())
+}
+
+/// GET /api/v1/governance/proposals - All proposals, newest first
+async fn list_proposals(State(state): State<AppState>) -> Result<Json<ProposalListResponse>, StatusCode> {
+    let (params, proposals) = {
+        let governance = state.governance.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let proposals: Vec<Proposal> = governance.proposals().into_iter().cloned().collect();
+        (governance.params().clone(), proposals)
+    };
+
+    Ok(Json(ProposalListResponse {
+        success: true,
+        params,
+        proposals: with_live_tally(&state, proposals)?,
+    }))
+}
+
+/// GET /api/v1/governance/proposals/:id - One proposal with its (live) tally
+async fn get_proposal(
+    State(state): State<AppState>,
+    Path(proposal_id): Path<String>,
+) -> Result<Json<ProposalResponse>, StatusCode> {
+    let proposal = state.governance.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .proposal(&proposal_id)
+        .cloned()
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(ProposalResponse {
+        success: true,
+        message: None,
+        proposal: with_live_tally(&state, vec![proposal])?.pop(),
+    }))
+}
+
+/// POST /api/v1/governance/proposals - Open a proposal, escrowing the caller's deposit.
+/// Voting power is snapshotted from the stored staked balances and the CPV scores now.
+async fn create_proposal(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(request): Json<CreateProposalRequest>,
+) -> Result<Json<ProposalResponse>, StatusCode> {
+    let now = Utc::now().timestamp() as u64;
+    let stakes = state.storage.load_bonded_stakes().await.map_err(|e| {
+        tracing::error!(error = %e, "Failed to load staked balances");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let validated = {
+        let consensus = state.cpv_consensus.lock().await;
+        let governance = state.governance.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let blockchain = state.blockchain.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let snapshot = governance::power_snapshot(&stakes, &consensus, governance.params());
+        let deposit = request.deposit.unwrap_or(governance.params().min_deposit);
+        governance.new_proposal(
+            &claims.sub,
+            request.title,
+            request.description,
+            request.action,
+            deposit,
+            now,
+            blockchain.get_latest_block().height,
+            snapshot,
+        )
+    };
+    let escrowed = validated.and_then(|proposal| {
+        let mut blockchain = state.blockchain.lock().map_err(|_| "Blockchain unavailable".to_string())?;
+        let escrow = governance::deposit_escrow(&proposal.proposer, proposal.deposit, blockchain.transaction_fees);
+        blockchain.add_transaction(escrow.clone())
+            .map(|()| (proposal, blockchain.last_bundle(), escrow))
+            .map_err(|e| format!("Deposit could not be escrowed: {}", e))
+    });
+    let (proposal, escrow_bundle, escrow) = match escrowed {
+        Ok(escrowed) => escrowed,
+        Err(e) => {
+            return Ok(Json(ProposalResponse {
+                success: false,
+                message: Some(e),
+                proposal: None,
+            }));
+        }
+    };
+
+    // The escrow and the proposal row commit together; the proposal only opens once both are stored
+    let stored = async {
+        let mut tx = state.storage.pool.begin().await?;
+        state.storage
+            .save_pending_bundle_atomic(escrow_bundle, TransactionPriority::Normal, std::slice::from_ref(&escrow), &mut tx)
+            .await?;
+        state.storage.save_governance_proposal_atomic(&proposal, &mut tx).await?;
+        tx.commit().await
+    }
+    .await;
+    if let Err(e) = stored {
+        tracing::error!(proposal_id = %proposal.proposal_id, error = %e, "Failed to store governance proposal");
+        state.blockchain.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .remove_pending(std::slice::from_ref(&escrow));
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+    state.governance.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?.open(proposal.clone());
+    tracing::info!(proposal_id = %proposal.proposal_id, proposer = %proposal.proposer, "Governance proposal opened");
+
+    Ok(Json(ProposalResponse {
+        success: true,
+        message: Some(format!("Proposal open for voting until block {}", proposal.voting_ends_height)),
+        proposal: Some(ProposalView { proposal, live_tally: None }),
+    }))
+}
+
+/// POST /api/v1/governance/proposals/:id/vote - Submit the caller's signed vote.
+/// The body is a `SignedTransaction` of kind `governance_vote` on this proposal,
+/// sent from the caller's address; it goes through the same checks, nonce and
+/// gas fee as POST /transaction.
+async fn vote_on_proposal(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(proposal_id): Path<String>,
+    Json(request): Json<SignedTransaction>,
+) -> Result<Json<VoteResponse>, StatusCode> {
+    let rejected = |message: String, support: bool| VoteResponse {
+        success: false,
+        message,
+        proposal_id: proposal_id.clone(),
+        support,
+        tx_hash: None,
+        voting_power: None,
+    };
+
+    let support = match &request.kind {
+        TxKind::GovernanceVote { proposal_id: voted, support } if *voted == proposal_id => *support,
+        _ => return Ok(Json(rejected(format!("Expected a signed governance_vote on proposal {}", proposal_id), false))),
+    };
+    if request.from != claims.sub {
+        return Ok(Json(rejected("Votes must be sent from the caller's own address".to_string(), support)));
+    }
+
+    let submitted = submit_signed_transaction(&state, request).await?;
+    if !submitted.success {
+        return Ok(Json(rejected(submitted.message, support)));
+    }
+    let voting_power = state.governance.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .proposal(&proposal_id)
+        .map(|proposal| proposal.voting_power.get(&claims.sub).copied().unwrap_or(0));
+
+    Ok(Json(VoteResponse {
+        success: true,
+        message: "Vote submitted; it counts once included in a block before voting ends".to_string(),
+        proposal_id: proposal_id.clone(),
+        support,
+        tx_hash: submitted.transaction_id,
+        voting_power,
+    }))
+}
+
+/// GET /api/v1/governance/voting-power - The caller's voting power and the total now
+/// (what a proposal created at the current head would snapshot)
+async fn get_voting_power(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<VotingPowerResponse>, StatusCode> {
+    let stakes = state.storage.load_bonded_stakes().await.map_err(|e| {
+        tracing::error!(error = %e, "Failed to load staked balances");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let consensus = state.cpv_consensus.lock().await;
+    let params = state.governance.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?.params().clone();
+
+    let voting_power = governance::voting_power(&stakes, &consensus, &params, &claims.sub);
+    Ok(Json(VotingPowerResponse {
+        total: voting_power.total(),
+        total_voting_power: governance::total_voting_power(&stakes, &consensus, &params),
+        address: claims.sub,
+        voting_power,
+    }))
+}
+
+pub fn governance_routes() -> Router<AppState> {
+    Router::new()
+        .route("/proposals", get(list_proposals).post(create_proposal))
+        .route("/proposals/:id", get(get_proposal))
+        .route("/proposals/:id/vote", post(vote_on_proposal))
+        .route("/voting-power", get(get_voting_power))
+}
@@ -23,6 +23,10 @@ pub mod premium; // ✅ Premium subscriptions
 pub mod achievements; // ✅ Achievements system
 pub mod trending;
 pub mod dex; // ✅ Trending algorithms
+pub mod gas; // ✅ Gas auto-swap settings
+pub mod governance; // ✅ Governance proposals and stake-weighted voting
+pub mod staking; // ✅ Staking unbonding queue
+pub mod allowances; // ✅ DYO allowances and relayed permits
 pub mod nfts; // ✅ NFT routes
 pub mod metrics; // ✅ MVP-CRITICAL: Métricas para monitoreo
 pub mod payout;
@@ -7,6 +7,7 @@ use axum::{
 };
 use serde::Serialize;
 use crate::server::AppState;
+use crate::utils::amount::Amount;
 use tracing::error;
 use chrono::Utc;
 
@@ -33,7 +34,7 @@ pub struct RedisHealth {
 
 #[derive(Debug, Serialize)]
 pub struct S2EHealth {
-    pub pool_remaining: f64,
+    pub pool_remaining: Amount,
     pub pool_percent: f64,
     pub daily_emission: f64,
     pub active_users_today: i64,
@@ -66,7 +67,7 @@ pub async fn get_health_handler(
     let s2e_health = get_s2e_health(&state).await.unwrap_or_else(|e| {
         error!("Failed to get S2E health: {}", e);
         S2EHealth {
-            pool_remaining: 0.0,
+            pool_remaining: Amount::ZERO,
             pool_percent: 0.0,
             daily_emission: 0.0,
             active_users_today: 0,
@@ -102,16 +103,16 @@ async fn get_s2e_health(state: &AppState) -> Result<S2EHealth, sqlx::Error> {
     let pool_data = state.storage.get_current_pool().await.unwrap_or_else(|_| {
         crate::storage::S2EPool {
             month_year: today.format("%Y-%m").to_string(),
-            total_amount: 2000000.0,
-            remaining_amount: 2000000.0,
-            artist_pool: 1200000.0,
-            listener_pool: 800000.0,
-            artist_spent: 0.0,
-            listener_spent: 0.0,
+            total_amount: Amount::from_units(2_000_000),
+            remaining_amount: Amount::from_units(2_000_000),
+            artist_pool: Amount::from_units(1_200_000),
+            listener_pool: Amount::from_units(800_000),
+            artist_spent: Amount::ZERO,
+            listener_spent: Amount::ZERO,
         }
     });
 
-    let pool_percent = (pool_data.remaining_amount / pool_data.total_amount) * 100.0;
+    let pool_percent = pool_data.remaining_percent();
 
     // Get daily emission
     let daily_emission: f64 = sqlx::query_scalar(
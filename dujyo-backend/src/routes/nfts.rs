@@ -11,6 +11,11 @@ use chrono::Utc;
 use uuid::Uuid;
 use crate::server::AppState;
 use crate::auth::Claims;
+use crate::blockchain::blockchain::Transaction;
+use crate::blockchain::ledger::{TxKind, MARKETPLACE_SPENDER, TREASURY_ACCOUNT};
+use crate::blockchain::mempool::TransactionPriority;
+use crate::routes::allowances;
+use crate::utils::amount::Amount;
 
 #[derive(Serialize, Clone)]
 pub struct NFT {
@@ -24,7 +29,7 @@ pub struct NFT {
 
 #[derive(Deserialize)]
 pub struct MockBuyRequest {
-    pub price_dyo: Option<f64>,        // default 5.0
+    pub price_dyo: Option<Amount>,     // default 5.0
     pub name: Option<String>,          // e.g., "Genesis Pass"
     pub image: Option<String>,         // optional image url
     pub description: Option<String>,   // optional description
@@ -35,8 +40,8 @@ pub struct MockBuyResponse {
     pub success: bool,
     pub message: String,
     pub nft_id: Option<String>,
-    pub price_dyo: f64,
-    pub new_balance_dyo: f64,
+    pub price_dyo: Amount,
+    pub new_balance_dyo: Amount,
 }
 
 #[derive(Serialize)]
@@ -283,7 +288,8 @@ pub async fn transfer_nft(
 }
 
 /// POST /api/v1/nfts/mock-buy
-/// Deducts DYO from storage and mints a mock NFT to the buyer
+/// Pulls the price from the buyer's DYO within the allowance it gave the
+/// marketplace (approve or permit) and mints a mock NFT to the buyer
 pub async fn mock_buy_nft(
     State(state): State<AppState>,
     Extension(claims): Extension<Claims>,
@@ -291,36 +297,51 @@ pub async fn mock_buy_nft(
 ) -> Result<Json<MockBuyResponse>, StatusCode> {
     let pool = &state.storage.pool;
     let buyer = &claims.sub;
-    let price_dyo = request.price_dyo.unwrap_or(5.0).max(0.0);
-    if price_dyo <= 0.0 {
-        return Ok(Json(MockBuyResponse {
-            success: false,
-            message: "Price must be > 0".to_string(),
-            nft_id: None,
-            price_dyo,
-            new_balance_dyo: 0.0,
-        }));
-    }
-    let price_cents = (price_dyo * 100.0).round() as u64;
+    let price_dyo = request.price_dyo.unwrap_or(Amount::from_units(5));
+    let price_cents = match price_dyo.to_cents() {
+        Ok(cents) if cents > 0 => cents,
+        Ok(_) | Err(_) => {
+            return Ok(Json(MockBuyResponse {
+                success: false,
+                message: "Price must be > 0 with at most 2 decimals".to_string(),
+                nft_id: None,
+                price_dyo,
+                new_balance_dyo: Amount::ZERO,
+            }));
+        }
+    };
 
-    // Check and deduct from legacy storage balance (source of truth for wallet UI)
-    let current_cents = state.storage.get_balance(buyer).await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    if current_cents < price_cents {
-        return Ok(Json(MockBuyResponse {
-            success: false,
-            message: format!("Insufficient balance. Required: {:.2} DYO", price_dyo),
-            nft_id: None,
-            price_dyo,
-            new_balance_dyo: (current_cents as f64) / 100.0,
-        }));
+    let nft_id = Uuid::new_v4().to_string();
+    let purchase = {
+        let mut chain = state.blockchain.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let payment = allowances::transfer_from(MARKETPLACE_SPENDER, buyer, TREASURY_ACCOUNT, price_cents);
+        let tx_hash = payment.tx_hash();
+        chain
+            .add_transaction(payment.clone())
+            .map(|()| (payment, tx_hash, chain.last_bundle(), chain.get_balance(buyer)))
+    };
+    let (payment, payment_hash, payment_bundle, updated_cents) = match purchase {
+        Ok(purchase) => purchase,
+        Err(e) => {
+            let balance = state.blockchain.lock().map(|chain| chain.get_balance(buyer)).unwrap_or(0);
+            return Ok(Json(MockBuyResponse {
+                success: false,
+                message: format!("Payment of {} DYO failed: {}", price_dyo, e),
+                nft_id: None,
+                price_dyo,
+                new_balance_dyo: Amount::from_cents(balance),
+            }));
+        }
+    };
+
+    if let Err(e) = state.storage
+        .save_pending_bundle(payment_bundle, TransactionPriority::Normal, std::slice::from_ref(&payment))
+        .await
+    {
+        tracing::error!(tx_hash = %payment_hash, error = %e, "Failed to persist NFT payment to the mempool");
     }
-    let updated_cents = current_cents.saturating_sub(price_cents);
-    state.storage.update_balance(buyer, updated_cents).await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     // Mint NFT record (DB best-effort; if table missing, still succeed)
-    let nft_id = Uuid::new_v4().to_string();
     let metadata = serde_json::json!({
         "name": request.name.clone().unwrap_or_else(|| "DUJYO Genesis NFT".to_string()),
         "description": request.description.clone().unwrap_or_else(|| "Mock NFT purchase for MVP".to_string()),
@@ -351,29 +372,35 @@ pub async fn mock_buy_nft(
     let _ = sqlx::query(
         r#"
         INSERT INTO transactions (tx_hash, from_address, to_address, amount, status, created_at)
-        VALUES ($1, $2, $3, $4, 'success', NOW())
+        VALUES ($1, $2, $3, $4, 'pending', NOW())
         "#
     )
-    .bind(&Uuid::new_v4().to_string())
+    .bind(&payment_hash)
     .bind(buyer)
-    .bind("NFT_MARKETPLACE")
+    .bind(TREASURY_ACCOUNT)
     .bind(price_cents as i64)
     .execute(pool)
     .await;
 
     // Add blockchain tx with nft_id for visibility in chain explorer (best-effort)
-    {
-        use crate::blockchain::blockchain::Transaction;
-        if let Ok(mut chain) = state.blockchain.lock() {
-            let tx = Transaction {
-                from: "NFT_MARKETPLACE".to_string(),
-                to: buyer.clone(),
-                amount: 0, // NFT mint has no DYO transfer here (price already deducted from storage)
-                nft_id: Some(nft_id.clone()),
-            };
-            if let Err(e) = chain.add_transaction(tx) {
+    let mint = Transaction::system(MARKETPLACE_SPENDER.to_string(), buyer.clone(), 0, Some(nft_id.clone()))
+        .with_kind(TxKind::NftMint { ipfs_hash: None });
+    let mint_bundle = match state.blockchain.lock() {
+        Ok(mut chain) => match chain.add_transaction(mint.clone()) {
+            Ok(()) => Some(chain.last_bundle()),
+            Err(e) => {
                 eprintln!("⚠️  Could not add NFT mint tx to blockchain: {}", e);
+                None
             }
+        },
+        Err(_) => None,
+    };
+    if let Some(bundle) = mint_bundle {
+        if let Err(e) = state.storage
+            .save_pending_bundle(bundle, TransactionPriority::Normal, std::slice::from_ref(&mint))
+            .await
+        {
+            tracing::error!(nft_id = %nft_id, error = %e, "Failed to persist NFT mint to the mempool");
         }
     }
 
@@ -382,7 +409,7 @@ pub async fn mock_buy_nft(
         message: "NFT purchased successfully".to_string(),
         nft_id: Some(nft_id),
         price_dyo,
-        new_balance_dyo: (updated_cents as f64) / 100.0,
+        new_balance_dyo: Amount::from_cents(updated_cents),
     }))
 }
 
@@ -11,6 +11,7 @@ use serde::{Deserialize, Serialize};
 // use crate::compliance::kyc_service::KycService;
 use crate::auth::Claims;
 use crate::server::AppState;
+use crate::blockchain::mempool::TransactionPriority;
 use std::sync::Arc;
 
 #[derive(Deserialize)]
@@ -119,6 +120,7 @@ pub async fn create_withdrawal(
     
     // ✅ ATOMIC OPERATION: Verify balance and deduct atomically using mutex lock
     // CRITICAL: All balance operations must happen within the same lock to prevent TOCTOU
+    let mut pending_deduction = None;
     let balance_verified = match request.currency.as_str() {
         "DYO" => {
             // Lock blockchain mutex for atomic check + deduction
@@ -145,16 +147,17 @@ pub async fn create_withdrawal(
             
             // ✅ ATOMIC DEDUCTION: Deduct balance within same lock (prevents TOCTOU)
             // Create transaction to deduct balance
-            let tx_blockchain = crate::blockchain::blockchain::Transaction {
-                from: user_id.clone(),
-                to: "WITHDRAWAL_ADDRESS".to_string(), // Special address for withdrawals
-                amount: deduction_cents,
-                nft_id: None,
-            };
-            blockchain.add_transaction(tx_blockchain).map_err(|e| {
+            let tx_blockchain = crate::blockchain::blockchain::Transaction::system(
+                user_id.clone(),
+                "WITHDRAWAL_ADDRESS".to_string(), // Special address for withdrawals
+                deduction_cents,
+                None,
+            );
+            blockchain.add_transaction(tx_blockchain.clone()).map_err(|e| {
                 eprintln!("❌ Error adding withdrawal transaction: {}", e);
                 StatusCode::INTERNAL_SERVER_ERROR
             })?;
+            pending_deduction = Some((blockchain.last_bundle(), tx_blockchain));
             
             // Lock released here - balance already deducted
             true
@@ -216,6 +219,17 @@ pub async fn create_withdrawal(
         _ => return Err(StatusCode::BAD_REQUEST),
     };
 
+    // The DYO deduction must survive a restart like any other queued transaction
+    if let Some((bundle, deduction)) = pending_deduction {
+        state.storage
+            .save_pending_bundle(bundle, TransactionPriority::Normal, std::slice::from_ref(&deduction))
+            .await
+            .map_err(|e| {
+                eprintln!("❌ Error persisting withdrawal transaction: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+    }
+
     // Convert request to service format
     let currency = match request.currency.as_str() {
         "DYO" => crate::payments::withdrawal_service::WithdrawalCurrency::DYO,
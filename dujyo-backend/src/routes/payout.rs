@@ -8,27 +8,28 @@ use axum::{
 use serde::{Deserialize, Serialize};
 use crate::auth::Claims;
 use crate::server::AppState;
+use crate::utils::amount::Amount;
 use uuid::Uuid;
 use chrono::Utc;
 use sqlx::Row;
 
 #[derive(Deserialize)]
 pub struct PayoutRequest {
-    pub amount: f64, // in DYO
+    pub amount: Amount, // in DYO
 }
 
-const MIN_PAYOUT_DYO: f64 = 10.0;
+const MIN_PAYOUT_DYO: Amount = Amount::from_units(10);
 
 #[derive(Debug, Deserialize)]
 pub struct FaucetRequest {
-    pub amount: f64, // in DYO
+    pub amount: Amount, // in DYO
 }
 
 #[derive(Debug, Serialize)]
 pub struct FaucetResponse {
     pub success: bool,
     pub message: String,
-    pub new_balance_dyo: f64,
+    pub new_balance_dyo: Amount,
 }
 
 /// POST /api/v1/payments/payout
@@ -42,7 +43,7 @@ pub async fn payout_handler(
     Json(req): Json<PayoutRequest>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
     let user_address = &claims.sub;
-    if req.amount <= 0.0 {
+    if req.amount.is_zero() {
         return Ok(Json(serde_json::json!({
             "success": false,
             "message": "Amount must be greater than 0"
@@ -56,7 +57,30 @@ pub async fn payout_handler(
     }
 
     // 1) Convert amount to cents (storage-based payout for MVP)
-    let amount_cents: u64 = (req.amount * 100.0).round() as u64;
+    let amount_cents = match req.amount.to_cents() {
+        Ok(cents) => cents,
+        Err(_) => {
+            return Ok(Json(serde_json::json!({
+                "success": false,
+                "message": "Amount cannot have more than 2 decimals"
+            })));
+        }
+    };
+
+    // ✅ CPV: Only finalized funds can leave the system
+    let current_balance_cents = state.storage.get_balance(user_address).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let (spendable_cents, awaiting_cents) = finalized_spendable(&state, user_address, current_balance_cents)?;
+    if amount_cents > spendable_cents {
+        return Ok(Json(serde_json::json!({
+            "success": false,
+            "message": format!(
+                "Insufficient finalized balance. Available: {} DYO ({} DYO awaiting finality)",
+                Amount::from_cents(spendable_cents),
+                Amount::from_cents(awaiting_cents)
+            )
+        })));
+    }
 
     // 2) Record payout in royalty_payments for history (amount in DYO)
     let tx_hash = {
@@ -74,7 +98,7 @@ pub async fn payout_handler(
         )
         .bind(&payout_id)
         .bind(user_address)
-        .bind(req.amount) // store as DYO (float)
+        .bind(req.amount.to_f64()) // store as DYO (float column, history only)
         .bind("DYO")
         .bind("completed")
         .bind(Utc::now().date_naive())
@@ -115,11 +139,23 @@ pub async fn payout_handler(
         "message": "Payout processed",
         "payout_amount": req.amount,
         "min_payout": MIN_PAYOUT_DYO,
-        "new_balance_dyo": (new_balance_cents as f64) / 100.0,
+        "new_balance_dyo": Amount::from_cents(new_balance_cents),
         "tx_hash": tx_hash
     })))
 }
 
+/// ✅ CPV: Split a stored balance (cents) into the part backed by finalized blocks
+/// and the part credited by blocks or pending transactions that could still be reverted.
+/// Returns (spendable, awaiting_finality).
+pub fn finalized_spendable(state: &AppState, address: &str, balance_cents: u64) -> Result<(u64, u64), StatusCode> {
+    let awaiting = state
+        .blockchain
+        .lock()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .unfinalized_credits(address);
+    Ok((balance_cents.saturating_sub(awaiting), awaiting.min(balance_cents)))
+}
+
 /// POST /api/v1/payments/faucet
 /// Dev helper: credit DYO into storage balance
 pub async fn faucet_handler(
@@ -128,14 +164,20 @@ pub async fn faucet_handler(
     Json(req): Json<FaucetRequest>,
 ) -> Result<Json<FaucetResponse>, StatusCode> {
     let user_address = &claims.sub;
-    if req.amount <= 0.0 {
+    if req.amount.is_zero() {
         return Ok(Json(FaucetResponse {
             success: false,
             message: "Amount must be greater than 0".to_string(),
-            new_balance_dyo: 0.0,
+            new_balance_dyo: Amount::ZERO,
         }));
     }
-    let add_cents = (req.amount * 100.0).round() as u64;
+    let Ok(add_cents) = req.amount.to_cents() else {
+        return Ok(Json(FaucetResponse {
+            success: false,
+            message: "Amount cannot have more than 2 decimals".to_string(),
+            new_balance_dyo: Amount::ZERO,
+        }));
+    };
     let current = state.storage.get_balance(user_address).await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     let updated = current.saturating_add(add_cents);
@@ -143,8 +185,8 @@ pub async fn faucet_handler(
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     Ok(Json(FaucetResponse {
         success: true,
-        message: format!("Credited {:.2} DYO", req.amount),
-        new_balance_dyo: (updated as f64) / 100.0,
+        message: format!("Credited {} DYO", req.amount),
+        new_balance_dyo: Amount::from_cents(updated),
     }))
 }
 
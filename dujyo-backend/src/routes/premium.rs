@@ -7,6 +7,23 @@ use serde::{Deserialize, Serialize};
 use sqlx::Row;
 use crate::server::AppState;
 use crate::auth::Claims;
+use crate::blockchain::ledger::{BILLING_SPENDER, TREASURY_ACCOUNT};
+use crate::blockchain::mempool::TransactionPriority;
+use crate::routes::allowances;
+
+/// Payment method charged on chain: the plan price is pulled from the user's DYO
+/// within the allowance it gave `PREMIUM_BILLING` (approve or permit)
+const DYO_ALLOWANCE_PAYMENT: &str = "dyo_allowance";
+
+/// DYO price of a plan in ledger cents
+fn plan_price_cents(plan_type: &str) -> Option<u64> {
+    match plan_type {
+        "monthly" => Some(999),
+        "yearly" => Some(9_999),
+        "lifetime" => Some(29_999),
+        _ => None,
+    }
+}
 
 #[derive(Serialize, Deserialize)]
 pub struct PremiumSubscription {
@@ -69,11 +86,17 @@ pub async fn create_subscription(
         })),
     };
     
+    let charge_cents = match (request.payment_method.as_deref(), plan_price_cents(&request.plan_type)) {
+        (Some(DYO_ALLOWANCE_PAYMENT), Some(price)) => Some(price),
+        _ => None,
+    };
+
+    let mut tx = pool.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     sqlx::query(
         r#"
         INSERT INTO premium_subscriptions 
-        (subscription_id, user_id, plan_type, status, expires_at, payment_method)
-        VALUES ($1, $2, $3, 'active', $4, $5)
+        (subscription_id, user_id, plan_type, status, expires_at, payment_method, price_paid)
+        VALUES ($1, $2, $3, 'active', $4, $5, $6::numeric / 100)
         "#
     )
     .bind(&subscription_id)
@@ -81,9 +104,35 @@ pub async fn create_subscription(
     .bind(&request.plan_type)
     .bind(expires_at)
     .bind(&request.payment_method)
-    .execute(pool)
+    .bind(charge_cents.map(|cents| cents as i64))
+    .execute(&mut *tx)
     .await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    // The subscription is only saved once the charge is in the mempool
+    if let Some(price) = charge_cents {
+        let charge = allowances::transfer_from(BILLING_SPENDER, user_id, TREASURY_ACCOUNT, price);
+        let charged = {
+            let mut blockchain = state.blockchain.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            blockchain.add_transaction(charge.clone()).map(|()| blockchain.last_bundle())
+        };
+        let bundle = match charged {
+            Ok(bundle) => bundle,
+            Err(e) => {
+                tx.rollback().await.ok();
+                return Ok(Json(SubscriptionResponse {
+                    success: false,
+                    subscription: None,
+                    message: format!("Payment failed: {}", e),
+                }));
+            }
+        };
+        state.storage
+            .save_pending_bundle_atomic(bundle, TransactionPriority::Normal, std::slice::from_ref(&charge), &mut tx)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    }
+    tx.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     
     let row = sqlx::query(
         "SELECT subscription_id, plan_type, status, started_at, expires_at, cancelled_at FROM premium_subscriptions WHERE subscription_id = $1"
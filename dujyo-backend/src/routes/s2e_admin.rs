@@ -9,6 +9,7 @@ use serde::{Deserialize, Serialize};
 use sqlx::Row;
 use crate::server::AppState;
 use crate::auth::Claims;
+use crate::utils::amount::Amount;
 use tracing::{info, error, warn};
 use std::collections::HashMap;
 
@@ -17,8 +18,8 @@ pub struct S2EAdminStats {
     pub total_users: i64,
     pub active_users_today: i64,
     pub dyo_distributed: f64,
-    pub pool_remaining: f64,
-    pub pool_total: f64,
+    pub pool_remaining: Amount,
+    pub pool_total: Amount,
     pub pool_remaining_percent: f64,
 }
 
@@ -98,20 +99,16 @@ pub async fn get_admin_stats_handler(
     let pool_data = state.storage.get_current_pool().await.unwrap_or_else(|_| {
         crate::storage::S2EPool {
             month_year: chrono::Utc::now().format("%Y-%m").to_string(),
-            total_amount: 2000000.0,
-            remaining_amount: 2000000.0,
-            artist_pool: 1200000.0,
-            listener_pool: 800000.0,
-            artist_spent: 0.0,
-            listener_spent: 0.0,
+            total_amount: Amount::from_units(2_000_000),
+            remaining_amount: Amount::from_units(2_000_000),
+            artist_pool: Amount::from_units(1_200_000),
+            listener_pool: Amount::from_units(800_000),
+            artist_spent: Amount::ZERO,
+            listener_spent: Amount::ZERO,
         }
     });
 
-    let pool_remaining_percent = if pool_data.total_amount > 0.0 {
-        (pool_data.remaining_amount / pool_data.total_amount) * 100.0
-    } else {
-        0.0
-    };
+    let pool_remaining_percent = pool_data.remaining_percent();
 
     Ok(Json(S2EAdminStats {
         total_users,
@@ -7,16 +7,17 @@ use axum::{
 };
 use serde::Serialize;
 use crate::server::AppState;
+use crate::utils::amount::Amount;
 use tracing::error;
 
 #[derive(Debug, Serialize)]
 pub struct S2EConfigResponse {
-    pub listener_rate: f64,
-    pub artist_rate: f64,
+    pub listener_rate: Amount,
+    pub artist_rate: Amount,
     pub daily_limit_listener: i32,
     pub daily_limit_artist: i32,
-    pub pool_total: f64,
-    pub pool_remaining: f64,
+    pub pool_total: Amount,
+    pub pool_remaining: Amount,
     pub pool_month: String,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
@@ -26,6 +27,9 @@ pub struct S2EConfigResponse {
 pub async fn get_s2e_config_handler(
     State(state): State<AppState>,
 ) -> Result<Json<S2EConfigResponse>, StatusCode> {
+    // Rates in force (the A3 defaults unless governance changed them)
+    let rates = *state.s2e_rates.read().unwrap();
+
     // Get current pool
     let pool = match state.storage.get_current_pool().await {
         Ok(p) => p,
@@ -33,23 +37,21 @@ pub async fn get_s2e_config_handler(
             error!("❌ Failed to get current pool: {}", e);
             // Return default pool values if query fails
             return Ok(Json(S2EConfigResponse {
-                listener_rate: 0.10,  // Opción A3
-                artist_rate: 0.50,    // Opción A3
+                listener_rate: rates.listener_rate_per_minute,
+                artist_rate: rates.artist_rate_per_minute,
                 daily_limit_listener: 90,
                 daily_limit_artist: 120,
-                pool_total: 2000000.0,  // Pool 2M
-                pool_remaining: 2000000.0,
+                pool_total: Amount::from_units(2_000_000),  // Pool 2M
+                pool_remaining: Amount::from_units(2_000_000),
                 pool_month: chrono::Utc::now().format("%Y-%m").to_string(),
                 updated_at: chrono::Utc::now(),
             }));
         }
     };
 
-    // ⚠️ CRITICAL: These rates are hardcoded for now but should match stream_earn.rs constants
-    // TODO: Move to database or environment variables for dynamic configuration
     let config = S2EConfigResponse {
-        listener_rate: 0.10,  // Must match LISTENER_RATE_PER_MINUTE in stream_earn.rs (Opción A3)
-        artist_rate: 0.50,      // Must match ARTIST_RATE_PER_MINUTE in stream_earn.rs (Opción A3)
+        listener_rate: rates.listener_rate_per_minute,
+        artist_rate: rates.artist_rate_per_minute,
         daily_limit_listener: 90,  // Conservative limit for listeners
         daily_limit_artist: 120,   // Must match DAILY_LIMIT_MINUTES in stream_earn.rs
         pool_total: pool.total_amount,
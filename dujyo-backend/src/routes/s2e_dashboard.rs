@@ -7,11 +7,12 @@ use axum::{
 };
 use serde::Serialize;
 use crate::server::AppState;
+use crate::utils::amount::Amount;
 use tracing::error;
 
 #[derive(Debug, Serialize)]
 pub struct S2EDashboardResponse {
-    pub pool_remaining_dyo: f64,
+    pub pool_remaining_dyo: Amount,
     pub pool_remaining_percent: f64,
     pub daily_emission: f64,
     pub active_users_today: i64,
@@ -34,11 +35,7 @@ pub async fn get_s2e_dashboard_handler(
     };
 
     // Calculate pool remaining percentage
-    let pool_remaining_percent = if pool.total_amount > 0.0 {
-        (pool.remaining_amount / pool.total_amount) * 100.0
-    } else {
-        0.0
-    };
+    let pool_remaining_percent = pool.remaining_percent();
 
     // Get daily emission (sum of tokens_earned from stream_logs today)
     let today = chrono::Utc::now().date_naive();
@@ -80,7 +77,7 @@ pub async fn get_s2e_dashboard_handler(
     }
     
     // Alert if daily emission > 33% of monthly pool (expected ~3.33% per day)
-    let expected_daily_emission = pool.total_amount / 30.0; // Expected daily emission
+    let expected_daily_emission = pool.total_amount.to_f64() / 30.0; // Expected daily emission
     if daily_emission > expected_daily_emission * 1.5 {
         alerts.push(format!("⚠️ High daily emission: {:.0} DYO (expected: {:.0} DYO)", 
             daily_emission, expected_daily_emission));
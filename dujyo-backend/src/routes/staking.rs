@@ -0,0 +1,393 @@
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use crate::auth::Claims;
+use crate::blockchain::real_blockchain::{unbonding_period_secs, UnbondingEntry};
+use crate::blockchain::staking_rewards::{
+    ClaimRewardsRequest, DelegateRequest, RedelegateRequest, Redelegation, StakingManager, StakingResponse,
+    UndelegateRequest, VALIDATOR_CONTRACT_ID,
+};
+use crate::consensus::cpv::CPVConsensus;
+use crate::server::{sync_validator_stakes, AppState};
+use crate::utils::amount::Amount;
+
+#[derive(Serialize)]
+struct UnbondingResponse {
+    success: bool,
+    address: String,
+    unbonding_period_secs: u64,
+    /// DYO still on its way out, released by the unbonding keeper
+    total_unbonding: Amount,
+    entries: Vec<UnbondingEntry>,
+}
+
+#[derive(Deserialize)]
+struct DelegationRequest {
+    validator: String,
+    /// Whole DYO, the unit of CPV stake
+    amount: u64,
+}
+
+#[derive(Deserialize)]
+struct RedelegationRequest {
+    src_validator: String,
+    dst_validator: String,
+    /// Whole DYO, the unit of CPV stake
+    amount: u64,
+}
+
+#[derive(Deserialize)]
+struct CommissionRequest {
+    commission_bps: u64,
+}
+
+#[derive(Deserialize)]
+struct AutoCompoundRequest {
+    validator: String,
+    enabled: bool,
+}
+
+#[derive(Deserialize)]
+struct ClaimRequest {
+    /// Delegation to claim from; the caller's own validator rewards (bond and
+    /// commission) when omitted
+    validator: Option<String>,
+}
+
+#[derive(Serialize)]
+struct DelegationView {
+    validator: String,
+    delegated_amount: u64,
+    pending_rewards: u64,
+    commission_bps: u64,
+    total_slashed: u64,
+    auto_compound: bool,
+}
+
+#[derive(Serialize)]
+struct DelegationsResponse {
+    success: bool,
+    address: String,
+    total_delegated: u64,
+    delegations: Vec<DelegationView>,
+    /// Redelegations still liable to their source validator's slashing
+    redelegations: Vec<Redelegation>,
+}
+
+fn rejected(message: impl Into<String>) -> Json<StakingResponse> {
+    Json(StakingResponse {
+        success: false,
+        message: message.into(),
+        data: None,
+    })
+}
+
+/// Push a validator's delegated stake from the staking contract into CPV
+fn update_delegated_stake(staking: &StakingManager, consensus: &mut CPVConsensus, validator: &str) {
+    let delegated = staking.delegated_stake(VALIDATOR_CONTRACT_ID, validator);
+    if let Err(e) = consensus.set_delegated_stake(validator, delegated) {
+        tracing::error!(validator = %validator, error = %e, "Failed to set delegated stake");
+    }
+}
+
+/// GET /api/v1/staking/unbonding - The caller's unstaked DYO waiting out the unbonding period
+async fn get_unbonding(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<UnbondingResponse>, StatusCode> {
+    let entries = state.storage.get_unbonding_entries(&claims.sub).await.map_err(|e| {
+        tracing::error!(address = %claims.sub, error = %e, "Failed to load unbonding entries");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(UnbondingResponse {
+        success: true,
+        unbonding_period_secs: unbonding_period_secs(),
+        total_unbonding: entries.iter().fold(Amount::ZERO, |total, entry| total.saturating_add(entry.payout())),
+        address: claims.sub,
+        entries,
+    }))
+}
+
+/// GET /api/v1/staking/delegations - The caller's delegations to CPV validators
+async fn get_delegations(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<DelegationsResponse>, StatusCode> {
+    let staking = state.staking.lock().await;
+    let delegations: Vec<DelegationView> = staking
+        .delegations_of(VALIDATOR_CONTRACT_ID, &claims.sub)
+        .into_iter()
+        .map(|(validator, delegation)| DelegationView {
+            pending_rewards: staking.pending_delegation_rewards(VALIDATOR_CONTRACT_ID, &claims.sub, &validator),
+            commission_bps: staking
+                .get_staking_contract(VALIDATOR_CONTRACT_ID)
+                .and_then(|contract| contract.delegations.get(&validator))
+                .map(|pool| pool.commission_bps)
+                .unwrap_or(0),
+            delegated_amount: delegation.delegated_amount,
+            total_slashed: delegation.total_slashed,
+            auto_compound: delegation.auto_compound,
+            validator,
+        })
+        .collect();
+
+    let redelegations = staking.redelegations_of(VALIDATOR_CONTRACT_ID, &claims.sub, Utc::now().timestamp() as u64);
+
+    Ok(Json(DelegationsResponse {
+        success: true,
+        total_delegated: delegations.iter().fold(0u64, |total, d| total.saturating_add(d.delegated_amount)),
+        address: claims.sub,
+        delegations,
+        redelegations,
+    }))
+}
+
+/// POST /api/v1/staking/delegate - Delegate the caller's DYO to a CPV economic
+/// validator. The DYO moves into the caller's staked balance and counts in the
+/// validator's effective stake; it comes back through undelegation.
+async fn delegate(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(request): Json<DelegationRequest>,
+) -> Result<Json<StakingResponse>, StatusCode> {
+    let mut staking = state.staking.lock().await;
+    // The validator's bond may have changed (registration, slashing) since the last sync
+    sync_validator_stakes(&mut staking, &mut *state.cpv_consensus.lock().await);
+
+    let previous = staking.clone();
+    let response = match staking.delegate_tokens(DelegateRequest {
+        contract_id: VALIDATOR_CONTRACT_ID.to_string(),
+        delegator: claims.sub.clone(),
+        validator: request.validator.clone(),
+        amount: request.amount,
+    }) {
+        Ok(response) => response,
+        Err(e) => return Ok(rejected(e)),
+    };
+
+    match state.storage.bond_delegation(&staking, &claims.sub, Amount::from_units(request.amount)).await {
+        Ok(true) => {}
+        Ok(false) => {
+            *staking = previous;
+            return Ok(rejected("Insufficient DYO balance"));
+        }
+        Err(e) => {
+            *staking = previous;
+            tracing::error!(delegator = %claims.sub, validator = %request.validator, error = %e, "Failed to store delegation");
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+    update_delegated_stake(&staking, &mut *state.cpv_consensus.lock().await, &request.validator);
+
+    tracing::info!(delegator = %claims.sub, validator = %request.validator, amount = request.amount, "Delegated to validator");
+    Ok(Json(response))
+}
+
+/// POST /api/v1/staking/undelegate - Withdraw a delegation. The DYO leaves the
+/// validator's effective stake at once and waits out the unbonding period.
+async fn undelegate(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(request): Json<DelegationRequest>,
+) -> Result<Json<StakingResponse>, StatusCode> {
+    let mut staking = state.staking.lock().await;
+
+    let previous = staking.clone();
+    let mut response = match staking.undelegate_tokens(UndelegateRequest {
+        contract_id: VALIDATOR_CONTRACT_ID.to_string(),
+        delegator: claims.sub.clone(),
+        validator: request.validator.clone(),
+        amount: request.amount,
+    }) {
+        Ok(response) => response,
+        Err(e) => return Ok(rejected(e)),
+    };
+
+    let now = Utc::now().timestamp() as u64;
+    let completes_at = now + unbonding_period_secs();
+    let amount = Amount::from_units(request.amount);
+    let entry = match state.storage.unbond_delegation(&staking, &claims.sub, &request.validator, amount, now, completes_at).await {
+        Ok(Some(entry)) => entry,
+        Ok(None) => {
+            *staking = previous;
+            return Ok(rejected("Insufficient staked balance"));
+        }
+        Err(e) => {
+            *staking = previous;
+            tracing::error!(delegator = %claims.sub, validator = %request.validator, error = %e, "Failed to store undelegation");
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    update_delegated_stake(&staking, &mut *state.cpv_consensus.lock().await, &request.validator);
+
+    if let Some(serde_json::Value::Object(data)) = response.data.as_mut() {
+        data.insert("unbonding_entry".to_string(), entry.position_id.clone().into());
+        data.insert("completes_at".to_string(), entry.completes_at.into());
+    }
+    response.message = format!("{}; released after the unbonding period (at {})", response.message, entry.completes_at);
+    Ok(Json(response))
+}
+
+/// POST /api/v1/staking/redelegate - Move a delegation to another validator
+/// without unbonding. The source validator's slashing still reaches the moved
+/// DYO until the unbonding period has passed; until then it cannot be moved on
+/// again, and at most MAX_REDELEGATIONS_IN_FLIGHT can be open at once.
+async fn redelegate(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(request): Json<RedelegationRequest>,
+) -> Result<Json<StakingResponse>, StatusCode> {
+    let mut staking = state.staking.lock().await;
+    // The destination's bond may have changed (registration, slashing) since the last sync
+    sync_validator_stakes(&mut staking, &mut *state.cpv_consensus.lock().await);
+
+    let previous = staking.clone();
+    let response = match staking.redelegate_tokens(RedelegateRequest {
+        contract_id: VALIDATOR_CONTRACT_ID.to_string(),
+        delegator: claims.sub.clone(),
+        src_validator: request.src_validator.clone(),
+        dst_validator: request.dst_validator.clone(),
+        amount: request.amount,
+    }) {
+        Ok(response) => response,
+        Err(e) => return Ok(rejected(e)),
+    };
+
+    // The DYO stays in the delegator's staked balance; only the staking state changes
+    if let Err(e) = state.storage.save_staking_state(&staking).await {
+        *staking = previous;
+        tracing::error!(
+            delegator = %claims.sub,
+            src_validator = %request.src_validator,
+            dst_validator = %request.dst_validator,
+            error = %e,
+            "Failed to store redelegation"
+        );
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+    let mut consensus = state.cpv_consensus.lock().await;
+    update_delegated_stake(&staking, &mut consensus, &request.src_validator);
+    update_delegated_stake(&staking, &mut consensus, &request.dst_validator);
+
+    tracing::info!(
+        delegator = %claims.sub,
+        src_validator = %request.src_validator,
+        dst_validator = %request.dst_validator,
+        amount = request.amount,
+        "Redelegated"
+    );
+    Ok(Json(response))
+}
+
+/// POST /api/v1/staking/validators/:validator/commission - Set the commission a
+/// validator takes from its delegators' rewards. Only the validator itself may
+/// change it; rewards accrued so far are settled at the previous rate.
+async fn set_commission(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(validator): Path<String>,
+    Json(request): Json<CommissionRequest>,
+) -> Result<Json<StakingResponse>, StatusCode> {
+    let mut staking = state.staking.lock().await;
+
+    let previous = staking.clone();
+    let response = match staking.set_commission(VALIDATOR_CONTRACT_ID, &claims.sub, &validator, request.commission_bps) {
+        Ok(response) => response,
+        Err(e) => return Ok(rejected(e)),
+    };
+
+    if let Err(e) = state.storage.save_staking_state(&staking).await {
+        *staking = previous;
+        tracing::error!(validator = %validator, error = %e, "Failed to store validator commission");
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+    Ok(Json(response))
+}
+
+/// POST /api/v1/staking/auto-compound - Opt a delegation in or out of
+/// re-staking its rewards at each epoch close
+async fn set_auto_compound(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(request): Json<AutoCompoundRequest>,
+) -> Result<Json<StakingResponse>, StatusCode> {
+    let mut staking = state.staking.lock().await;
+
+    let previous = staking.clone();
+    let response = match staking.set_delegation_auto_compound(
+        VALIDATOR_CONTRACT_ID,
+        &claims.sub,
+        &request.validator,
+        request.enabled,
+    ) {
+        Ok(response) => response,
+        Err(e) => return Ok(rejected(e)),
+    };
+
+    if let Err(e) = state.storage.save_staking_state(&staking).await {
+        *staking = previous;
+        tracing::error!(delegator = %claims.sub, validator = %request.validator, error = %e, "Failed to store auto-compound setting");
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+    Ok(Json(response))
+}
+
+/// POST /api/v1/staking/rewards/claim - Pay the caller's accrued staking rewards
+/// into their DYO balance
+async fn claim_rewards(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(request): Json<ClaimRequest>,
+) -> Result<Json<StakingResponse>, StatusCode> {
+    let mut staking = state.staking.lock().await;
+
+    let previous = staking.clone();
+    let claimed = match &request.validator {
+        Some(validator) => staking.claim_delegation_rewards(VALIDATOR_CONTRACT_ID, &claims.sub, validator),
+        None => staking.claim_rewards(ClaimRewardsRequest {
+            contract_id: VALIDATOR_CONTRACT_ID.to_string(),
+            staker: claims.sub.clone(),
+        }),
+    };
+    let claimed = match claimed {
+        Ok(claimed) => claimed,
+        Err(e) => return Ok(rejected(e)),
+    };
+
+    let amount = Amount::from_units(claimed.claimed_amount);
+    if let Err(e) = state.storage.pay_staking_rewards(&staking, &claims.sub, amount).await {
+        *staking = previous;
+        tracing::error!(address = %claims.sub, error = %e, "Failed to pay staking rewards");
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+    Ok(Json(StakingResponse {
+        success: true,
+        message: format!("Claimed {} DYO rewards", claimed.claimed_amount),
+        data: Some(serde_json::json!({
+            "contract_id": VALIDATOR_CONTRACT_ID,
+            "address": claims.sub,
+            "validator": request.validator,
+            "claimed_amount": claimed.claimed_amount,
+            "total_claimed": claimed.total_claimed
+        })),
+    }))
+}
+
+pub fn staking_routes() -> Router<AppState> {
+    Router::new()
+        .route("/unbonding", get(get_unbonding))
+        .route("/delegations", get(get_delegations))
+        .route("/delegate", post(delegate))
+        .route("/undelegate", post(undelegate))
+        .route("/redelegate", post(redelegate))
+        .route("/validators/:validator/commission", post(set_commission))
+        .route("/auto-compound", post(set_auto_compound))
+        .route("/rewards/claim", post(claim_rewards))
+}
@@ -11,6 +11,8 @@ use crate::server::AppState;
 use crate::auth::Claims;
 use tracing::{info, error};
 use crate::middleware::beta_access;
+use crate::routes::payout::finalized_spendable;
+use crate::utils::amount::{Amount, MICRO_DECIMALS};
 
 // ============================================================================
 // DATA STRUCTURES
@@ -30,18 +32,18 @@ pub struct StreamEarnRequest {
 pub struct StreamEarnResponse {
     pub success: bool,
     pub transaction_id: String,
-    pub tokens_earned: f64,
-    pub total_earned_today: f64,
+    pub tokens_earned: Amount,
+    pub total_earned_today: Amount,
     pub message: String,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub new_balance: Option<f64>, // ✅ NEW: Current balance after earning (for real-time UI update)
+    pub new_balance: Option<Amount>, // ✅ NEW: Current balance after earning (for real-time UI update)
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct StreamHistoryResponse {
     pub success: bool,
     pub records: Vec<StreamRecord>,
-    pub total_earned_today: f64,
+    pub total_earned_today: Amount,
     pub daily_limit_minutes: i32,
     pub minutes_used_today: i32,
 }
@@ -53,7 +55,7 @@ pub struct StreamRecord {
     pub track_title: String,
     pub artist_id: String,
     pub duration_seconds: i32,
-    pub tokens_earned: f64,
+    pub tokens_earned: Amount,
     pub stream_type: String,
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
@@ -63,8 +65,50 @@ pub struct StreamRecord {
 // ============================================================================
 
 const DAILY_LIMIT_MINUTES: i32 = 120; // 120 minutes daily limit
-const ARTIST_RATE_PER_MINUTE: f64 = 0.50; // 0.50 DYO per minute for artists (REDUCED from 1.5 for economic sustainability - Opción A3)
-const LISTENER_RATE_PER_MINUTE: f64 = 0.10; // 0.10 DYO per minute for listeners (REDUCED from 0.3 for economic sustainability - Opción A3)
+pub const ARTIST_RATE_PER_MINUTE: Amount = Amount::from_cents(50); // 0.50 DYO per minute for artists (REDUCED from 1.5 for economic sustainability - Opción A3)
+pub const LISTENER_RATE_PER_MINUTE: Amount = Amount::from_cents(10); // 0.10 DYO per minute for listeners (REDUCED from 0.3 for economic sustainability - Opción A3)
+/// Highest per-minute rate a governance proposal may set
+pub const MAX_RATE_PER_MINUTE: Amount = Amount::from_units(10);
+
+/// Stream-to-Earn rates in force (AppState::s2e_rates). They start at the
+/// constants above and only change through executed governance proposals.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct S2ERates {
+    pub listener_rate_per_minute: Amount,
+    pub artist_rate_per_minute: Amount,
+}
+
+impl Default for S2ERates {
+    fn default() -> Self {
+        Self {
+            listener_rate_per_minute: LISTENER_RATE_PER_MINUTE,
+            artist_rate_per_minute: ARTIST_RATE_PER_MINUTE,
+        }
+    }
+}
+
+impl S2ERates {
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, rate) in [("listener", self.listener_rate_per_minute), ("artist", self.artist_rate_per_minute)] {
+            if rate.is_zero() || rate > MAX_RATE_PER_MINUTE {
+                return Err(format!("{} rate must be above 0 and at most {} DYO per minute", name, MAX_RATE_PER_MINUTE));
+            }
+            if rate.round_down_to(MICRO_DECIMALS).ok() != Some(rate) {
+                return Err(format!("{} rate has more than {} decimals", name, MICRO_DECIMALS));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Tokens for `duration_seconds` of streaming at `rate_per_minute`, truncated to
+/// the 6 decimals that token_balances and the S2E DECIMAL columns can hold
+fn tokens_for_duration(rate_per_minute: Amount, duration_seconds: i32) -> Amount {
+    rate_per_minute
+        .mul_ratio(duration_seconds.max(0) as u128, 60)
+        .and_then(|tokens| tokens.round_down_to(MICRO_DECIMALS))
+        .unwrap_or(Amount::ZERO)
+}
 
 // ============================================================================
 // HANDLERS
@@ -88,15 +132,16 @@ pub async fn stream_earn_artist_handler(
         return Ok(Json(StreamEarnResponse {
             success: false,
             transaction_id: String::new(),
-            tokens_earned: 0.0,
-            total_earned_today: 0.0,
+            tokens_earned: Amount::ZERO,
+            total_earned_today: Amount::ZERO,
             new_balance: None,
             message: format!("Daily streaming limit reached ({} minutes)", DAILY_LIMIT_MINUTES),
         }));
     }
 
     // Calculate tokens earned (artist rate)
-    let tokens_earned = duration_minutes * ARTIST_RATE_PER_MINUTE;
+    let rates = *state.s2e_rates.read().unwrap();
+    let tokens_earned = tokens_for_duration(rates.artist_rate_per_minute, request.duration_seconds);
     
     // Generate transaction ID
     let transaction_id = Uuid::new_v4().to_string();
@@ -142,7 +187,7 @@ pub async fn stream_earn_artist_handler(
         })?;
     
     info!(
-        "🎵 Artist stream earn: {} earned {:.2} DYO for track '{}' ({} seconds)",
+        "🎵 Artist stream earn: {} earned {} DYO for track '{}' ({} seconds)",
         user_address, tokens_earned, request.track_title, request.duration_seconds
     );
     
@@ -159,10 +204,10 @@ pub async fn stream_earn_artist_handler(
         .flatten();
         
         match result {
-            Some((dyo_balance, _, _)) => Some(dyo_balance as f64 / 1_000_000.0),
+            Some((dyo_balance, _, _)) => Some(Amount::from_micro(dyo_balance.max(0) as u64)),
             None => {
                 let legacy = state.storage.get_balance(user_address).await.unwrap_or(0);
-                Some((legacy as f64) / 100.0)
+                Some(Amount::from_cents(legacy))
             }
         }
     };
@@ -172,7 +217,7 @@ pub async fn stream_earn_artist_handler(
         transaction_id,
         tokens_earned,
         total_earned_today,
-        message: format!("Artist earned {:.2} DYO for streaming", tokens_earned),
+        message: format!("Artist earned {} DYO for streaming", tokens_earned),
         new_balance: updated_balance,
     }))
 }
@@ -201,8 +246,8 @@ pub async fn stream_earn_listener_handler(
             return Ok(Json(StreamEarnResponse {
                 success: false,
                 transaction_id: String::new(),
-                tokens_earned: 0.0,
-                total_earned_today: 0.0,
+                tokens_earned: Amount::ZERO,
+                total_earned_today: Amount::ZERO,
                 message: "Beta access required. Please request access at /api/v1/s2e/request-beta-access".to_string(),
                 new_balance: None,
             }));
@@ -236,8 +281,8 @@ pub async fn stream_earn_listener_handler(
         return Ok(Json(StreamEarnResponse {
             success: false,
             transaction_id: String::new(),
-            tokens_earned: 0.0,
-            total_earned_today: 0.0,
+            tokens_earned: Amount::ZERO,
+            total_earned_today: Amount::ZERO,
             new_balance: None,
             message: "Artists cannot earn DYO from listening to their own content. Focus on growing your fanbase!".to_string(),
         }));
@@ -257,8 +302,8 @@ pub async fn stream_earn_listener_handler(
             return Ok(Json(StreamEarnResponse {
                 success: false,
                 transaction_id: String::new(),
-                tokens_earned: 0.0,
-                total_earned_today: 0.0,
+                tokens_earned: Amount::ZERO,
+                total_earned_today: Amount::ZERO,
                 message: "Please wait 5 minutes between streaming sessions to prevent farming.".to_string(),
                 new_balance: None,
             }));
@@ -269,8 +314,8 @@ pub async fn stream_earn_listener_handler(
             return Ok(Json(StreamEarnResponse {
                 success: false,
                 transaction_id: String::new(),
-                tokens_earned: 0.0,
-                total_earned_today: 0.0,
+                tokens_earned: Amount::ZERO,
+                total_earned_today: Amount::ZERO,
                 message: format!("System error checking session cooldown. Please try again later."),
                 new_balance: None,
             }));
@@ -284,8 +329,8 @@ pub async fn stream_earn_listener_handler(
             return Ok(Json(StreamEarnResponse {
                 success: false,
                 transaction_id: String::new(),
-                tokens_earned: 0.0,
-                total_earned_today: 0.0,
+                tokens_earned: Amount::ZERO,
+                total_earned_today: Amount::ZERO,
                 message: "Continuous session limit reached (60 minutes). Please take a break before continuing.".to_string(),
                 new_balance: None,
             }));
@@ -296,8 +341,8 @@ pub async fn stream_earn_listener_handler(
             return Ok(Json(StreamEarnResponse {
                 success: false,
                 transaction_id: String::new(),
-                tokens_earned: 0.0,
-                total_earned_today: 0.0,
+                tokens_earned: Amount::ZERO,
+                total_earned_today: Amount::ZERO,
                 message: format!("System error checking session limit. Please try again later."),
                 new_balance: None,
             }));
@@ -312,8 +357,8 @@ pub async fn stream_earn_listener_handler(
             return Ok(Json(StreamEarnResponse {
                 success: false,
                 transaction_id: String::new(),
-                tokens_earned: 0.0,
-                total_earned_today: 0.0,
+                tokens_earned: Amount::ZERO,
+                total_earned_today: Amount::ZERO,
                 message: format!("Daily limit reached for this content (10 minutes per content per day). Try exploring other tracks!").to_string(),
                 new_balance: None,
             }));
@@ -324,8 +369,8 @@ pub async fn stream_earn_listener_handler(
             return Ok(Json(StreamEarnResponse {
                 success: false,
                 transaction_id: String::new(),
-                tokens_earned: 0.0,
-                total_earned_today: 0.0,
+                tokens_earned: Amount::ZERO,
+                total_earned_today: Amount::ZERO,
                 message: format!("System error checking content limit. Please try again later."),
                 new_balance: None,
             }));
@@ -335,7 +380,7 @@ pub async fn stream_earn_listener_handler(
     // 🆕 Get current pool to calculate dynamic rate
     let current_pool = match state.storage.get_current_pool().await {
         Ok(pool) => {
-            info!("📊 [StreamEarn] Pool retrieved: remaining={:.2} DYO", pool.remaining_amount);
+            info!("📊 [StreamEarn] Pool retrieved: remaining={} DYO", pool.remaining_amount);
             pool
         },
         Err(e) => {
@@ -344,38 +389,34 @@ pub async fn stream_earn_listener_handler(
             return Ok(Json(StreamEarnResponse {
                 success: false,
                 transaction_id: String::new(),
-                tokens_earned: 0.0,
-                total_earned_today: 0.0,
+                tokens_earned: Amount::ZERO,
+                total_earned_today: Amount::ZERO,
                 message: format!("System error retrieving pool data. Please try again later. Error: {}", e),
                 new_balance: None,
             }));
         }
     };
     
-    // ✅ VALIDATION: Check for NaN or Infinity in pool values
-    if current_pool.remaining_amount.is_nan() || current_pool.remaining_amount.is_infinite() {
-        error!("❌ [StreamEarn] Invalid pool remaining_amount: {}", current_pool.remaining_amount);
-        return Err(StatusCode::INTERNAL_SERVER_ERROR);
-    }
-    
     // ✅ FIX: Use FIXED rates, NOT dynamic pool calculation
     // The pool monthly (2M DYO) is for distribution among ALL users
     // Each individual user earns at FIXED rates: 0.10 DYO/min (listener), 0.50 DYO/min (artist)
-    let rate_per_minute = LISTENER_RATE_PER_MINUTE; // 0.10 DYO per minute (FIXED)
+    // unless a governance proposal changed them
+    let rates = *state.s2e_rates.read().unwrap();
+    let rate_per_minute = rates.listener_rate_per_minute;
     
     // 🆕 DEBUG: Log pool and rate information
     info!(
-        "📊 S2E Pool: remaining={:.2} DYO, listener_rate={:.2} DYO/min (FIXED), minutes={:.2}",
+        "📊 S2E Pool: remaining={} DYO, listener_rate={} DYO/min (FIXED), minutes={:.2}",
         current_pool.remaining_amount, rate_per_minute, duration_minutes
     );
     
     // ✅ Calculate tokens using FIXED rate (0.10 DYO per minute)
-    let tokens_listener = duration_minutes * LISTENER_RATE_PER_MINUTE;
+    let tokens_listener = tokens_for_duration(rate_per_minute, request.duration_seconds);
     
     // ✅ Artist earns at FIXED rate (0.50 DYO per minute) when fans listen
     // Artist earns 5x more than listener (0.50 / 0.10 = 5x)
-    let tokens_artist = duration_minutes * ARTIST_RATE_PER_MINUTE;
-    let tokens_needed = tokens_listener + tokens_artist;
+    let tokens_artist = tokens_for_duration(rates.artist_rate_per_minute, request.duration_seconds);
+    let tokens_needed = tokens_listener.saturating_add(tokens_artist);
 
     // ⚠️ CRITICAL: Check monthly pool has sufficient funds BEFORE processing
     if !state.storage.check_pool_has_funds(tokens_needed).await
@@ -386,8 +427,8 @@ pub async fn stream_earn_listener_handler(
         return Ok(Json(StreamEarnResponse {
             success: false,
             transaction_id: String::new(),
-            tokens_earned: 0.0,
-            total_earned_today: 0.0,
+            tokens_earned: Amount::ZERO,
+            total_earned_today: Amount::ZERO,
             new_balance: None,
             message: "Monthly S2E pool exhausted. Try again next month!".to_string(),
         }));
@@ -395,7 +436,7 @@ pub async fn stream_earn_listener_handler(
     
     // 🆕 DEBUG: Log calculated earnings
     info!(
-        "💰 Calculated earnings: listener={:.6} DYO, artist={:.6} DYO, total_needed={:.6} DYO",
+        "💰 Calculated earnings: listener={} DYO, artist={} DYO, total_needed={} DYO",
         tokens_listener, tokens_artist, tokens_needed
     );
 
@@ -404,8 +445,8 @@ pub async fn stream_earn_listener_handler(
         return Ok(Json(StreamEarnResponse {
             success: false,
             transaction_id: String::new(),
-            tokens_earned: 0.0,
-            total_earned_today: 0.0,
+            tokens_earned: Amount::ZERO,
+            total_earned_today: Amount::ZERO,
             new_balance: None,
             message: format!("Daily streaming limit reached ({} minutes)", DAILY_LIMIT_MINUTES),
         }));
@@ -435,8 +476,8 @@ pub async fn stream_earn_listener_handler(
                     return Ok(Json(StreamEarnResponse {
                         success: false,
                         transaction_id: String::new(),
-                        tokens_earned: 0.0,
-                        total_earned_today: 0.0,
+                        tokens_earned: Amount::ZERO,
+                        total_earned_today: Amount::ZERO,
                         message: "Content not found. Cannot process stream earnings.".to_string(),
                         new_balance: None,
                     }));
@@ -447,8 +488,8 @@ pub async fn stream_earn_listener_handler(
             return Ok(Json(StreamEarnResponse {
                 success: false,
                 transaction_id: String::new(),
-                tokens_earned: 0.0,
-                total_earned_today: 0.0,
+                tokens_earned: Amount::ZERO,
+                total_earned_today: Amount::ZERO,
                 message: "Content ID required for stream earnings.".to_string(),
                 new_balance: None,
             }));
@@ -500,7 +541,7 @@ pub async fn stream_earn_listener_handler(
     
     // Update daily usage
     if let Err(e) = update_daily_usage(pool, user_address, duration_minutes, tokens_earned, "listener").await {
-        error!("❌ [StreamEarn] Failed to update daily usage: {} (user: {}, minutes: {:.2}, tokens: {:.6})", 
+        error!("❌ [StreamEarn] Failed to update daily usage: {} (user: {}, minutes: {:.2}, tokens: {})", 
                e, user_address, duration_minutes, tokens_earned);
         return Err(StatusCode::INTERNAL_SERVER_ERROR);
     }
@@ -520,19 +561,19 @@ pub async fn stream_earn_listener_handler(
     
     // Update token balance in blockchain storage
     if let Err(e) = update_token_balance(&state, user_address, tokens_earned).await {
-        error!("❌ [StreamEarn] Failed to update listener token balance: {} (user: {}, tokens: {:.6})", 
+        error!("❌ [StreamEarn] Failed to update listener token balance: {} (user: {}, tokens: {})", 
                e, user_address, tokens_earned);
         return Err(StatusCode::INTERNAL_SERVER_ERROR);
     }
-    info!("✅ [StreamEarn] Listener balance updated: user={}, tokens={:.6}", user_address, tokens_earned);
+    info!("✅ [StreamEarn] Listener balance updated: user={}, tokens={}", user_address, tokens_earned);
     
     // Also reward the content artist
     if let Err(e) = update_token_balance(&state, &artist_id, tokens_artist).await {
-        error!("❌ [StreamEarn] Failed to update artist token balance (artist_id: {}, tokens: {:.6}): {}", 
+        error!("❌ [StreamEarn] Failed to update artist token balance (artist_id: {}, tokens: {}): {}", 
                artist_id, tokens_artist, e);
         // Do not fail the whole request; listener award already applied
     } else {
-        info!("✅ [StreamEarn] Artist balance updated: artist={}, tokens={:.6}", artist_id, tokens_artist);
+        info!("✅ [StreamEarn] Artist balance updated: artist={}, tokens={}", artist_id, tokens_artist);
     }
     
     // ⚠️ CRITICAL: Decrement monthly pool AFTER successful balance updates
@@ -550,8 +591,8 @@ pub async fn stream_earn_listener_handler(
         })?;
     
     info!(
-        "🎧 Listener earned {:.6} DYO! (user: {}, artist: {}, track: '{}', {} seconds, rate: {:.2} DYO/min FIXED)",
-        tokens_earned, user_address, artist_id, request.track_title, request.duration_seconds, LISTENER_RATE_PER_MINUTE
+        "🎧 Listener earned {} DYO! (user: {}, artist: {}, track: '{}', {} seconds, rate: {} DYO/min FIXED)",
+        tokens_earned, user_address, artist_id, request.track_title, request.duration_seconds, rate_per_minute
     );
     
     // ✅ Get updated balance after earning to return in response
@@ -567,13 +608,11 @@ pub async fn stream_earn_listener_handler(
         .flatten();
         
         match result {
-            Some((dyo_balance, dys_balance, staked_balance)) => {
-                (dyo_balance as f64 / 1_000_000.0, dys_balance as f64 / 1_000_000.0, staked_balance as f64 / 1_000_000.0)
-            },
+            Some((dyo_balance, _, _)) => Amount::from_micro(dyo_balance.max(0) as u64),
             None => {
                 // Fallback to legacy balance
                 let legacy = state.storage.get_balance(user_address).await.unwrap_or(0);
-                ((legacy as f64) / 100.0, 0.0, 0.0)
+                Amount::from_cents(legacy)
             }
         }
     };
@@ -583,12 +622,12 @@ pub async fn stream_earn_listener_handler(
         transaction_id,
         tokens_earned,
         total_earned_today,
-        message: format!("Listener earned {:.2} DYO; artist rewarded {:.2} DYO", tokens_earned, tokens_artist),
-        new_balance: Some(updated_balance.0), // ✅ Return new balance for immediate UI update
+        message: format!("Listener earned {} DYO; artist rewarded {} DYO", tokens_earned, tokens_artist),
+        new_balance: Some(updated_balance), // ✅ Return new balance for immediate UI update
     };
     
-    info!("💰 [StreamEarn] Final balance for {}: {:.2} DYO (earned {:.6} DYO this tick)", 
-          user_address, updated_balance.0, tokens_earned);
+    info!("💰 [StreamEarn] Final balance for {}: {} DYO (earned {} DYO this tick)", 
+          user_address, updated_balance, tokens_earned);
     Ok(Json(response))
 }
 
@@ -774,19 +813,19 @@ async fn update_content_daily_limit(
     user_address: &str,
     content_id: &str,
     duration_seconds: i32,
-    tokens_earned: f64,
+    tokens_earned: Amount,
 ) -> Result<(), sqlx::Error> {
     let today = Utc::now().date_naive();
     
     sqlx::query(
         r#"
         INSERT INTO content_stream_limits (user_address, content_id, date, streams_count, total_duration_seconds, tokens_earned, updated_at)
-        VALUES ($1, $2, $3, 1, $4, $5, NOW())
+        VALUES ($1, $2, $3, 1, $4, $5::numeric, NOW())
         ON CONFLICT (user_address, content_id, date)
         DO UPDATE SET
             streams_count = content_stream_limits.streams_count + 1,
             total_duration_seconds = content_stream_limits.total_duration_seconds + $4,
-            tokens_earned = content_stream_limits.tokens_earned + $5,
+            tokens_earned = content_stream_limits.tokens_earned + $5::numeric,
             updated_at = NOW()
         "#
     )
@@ -794,7 +833,7 @@ async fn update_content_daily_limit(
     .bind(content_id)
     .bind(today)
     .bind(duration_seconds)
-    .bind(tokens_earned)
+    .bind(tokens_earned.to_string())
     .execute(pool)
     .await?;
     
@@ -836,7 +875,7 @@ async fn store_stream_log(
     user_address: &str,
     stream_type: &str,
     duration_seconds: i32,
-    tokens_earned: f64,
+    tokens_earned: Amount,
     track_id: &str,
     track_title: &str,
     genre: Option<&str>,
@@ -846,7 +885,7 @@ async fn store_stream_log(
         INSERT INTO stream_logs (
             log_id, content_id, artist_id, user_address, stream_type,
             duration_seconds, tokens_earned, track_id, track_title, track_genre, created_at
-        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, NOW())
+        ) VALUES ($1, $2, $3, $4, $5, $6, $7::numeric, $8, $9, $10, NOW())
         ON CONFLICT (log_id) DO NOTHING
         "#
     )
@@ -856,7 +895,7 @@ async fn store_stream_log(
     .bind(user_address)
     .bind(stream_type)
     .bind(duration_seconds)
-    .bind(tokens_earned)
+    .bind(tokens_earned.to_string())
     .bind(track_id)
     .bind(track_title)
     .bind(genre)
@@ -870,7 +909,7 @@ async fn update_daily_usage(
     pool: &PgPool,
     user_address: &str,
     duration_minutes: f64,
-    tokens_earned: f64,
+    tokens_earned: Amount,
     user_type: &str,
 ) -> Result<(), sqlx::Error> {
     let today = Utc::now().date_naive();
@@ -878,18 +917,18 @@ async fn update_daily_usage(
     sqlx::query(
         r#"
         INSERT INTO user_daily_usage (user_address, date, minutes_used, tokens_earned, user_type, updated_at)
-        VALUES ($1, $2, $3, $4, $5, NOW())
+        VALUES ($1, $2, $3, $4::numeric, $5, NOW())
         ON CONFLICT (user_address, date) 
         DO UPDATE SET 
             minutes_used = user_daily_usage.minutes_used + $3,
-            tokens_earned = user_daily_usage.tokens_earned + $4,
+            tokens_earned = user_daily_usage.tokens_earned + $4::numeric,
             updated_at = NOW()
         "#
     )
     .bind(user_address)
     .bind(today)
     .bind((duration_minutes * 60.0) as i64) // Convert to seconds for storage
-    .bind(tokens_earned)
+    .bind(tokens_earned.to_string())
     .bind(user_type)
     .execute(pool)
     .await?;
@@ -897,7 +936,27 @@ async fn update_daily_usage(
     Ok(())
 }
 
-async fn update_token_balance(state: &AppState, user_address: &str, tokens_earned: f64) -> Result<(), String> {
+/// ✅ CPV: Legacy balance (cents) after a stream-earn payout. The payout lands on the
+/// finalized part and the part still awaiting finality is carried over untouched, so
+/// payouts and withdrawals keep holding it back until its block is final.
+fn credit_stream_earn(spendable_cents: u64, awaiting_cents: u64, tokens_earned: Amount) -> Result<u64, String> {
+    let earned_cents = tokens_earned
+        .to_cents_floor()
+        .map_err(|_| format!("Earned amount {} does not fit in the legacy balance", tokens_earned))?;
+    spendable_cents
+        .checked_add(earned_cents)
+        .and_then(|finalized| finalized.checked_add(awaiting_cents))
+        .ok_or_else(|| format!("Legacy balance overflows after crediting {}", tokens_earned))
+}
+
+async fn update_token_balance(state: &AppState, user_address: &str, tokens_earned: Amount) -> Result<(), String> {
+    // ✅ CPV: Work out the legacy credit against finalized state before paying anything
+    let legacy_cents = state.storage.get_balance(user_address).await
+        .map_err(|e| format!("Failed to get legacy balance: {}", e))?;
+    let (spendable_cents, awaiting_cents) = finalized_spendable(state, user_address, legacy_cents)
+        .map_err(|_| "Failed to read finalized state".to_string())?;
+    let new_legacy_cents = credit_stream_earn(spendable_cents, awaiting_cents, tokens_earned)?;
+
     // ✅ FIX: Update token_balances table (not legacy balances table)
     let pool = &state.storage.pool;
     
@@ -911,7 +970,11 @@ async fn update_token_balance(state: &AppState, user_address: &str, tokens_earne
     .map_err(|e| format!("Failed to get token balance: {}", e))?;
     
     // Convert tokens_earned to micro-DYO (1 DYO = 1,000,000 micro-DYO)
-    let tokens_earned_micro = (tokens_earned * 1_000_000.0).round() as i64;
+    let tokens_earned_micro = tokens_earned
+        .to_micro_floor()
+        .ok()
+        .and_then(|micro| i64::try_from(micro).ok())
+        .ok_or_else(|| format!("Earned amount {} does not fit in token_balances", tokens_earned))?;
     
     // Calculate new balance
     let (new_dyo, new_dys, new_staked) = match current_balance {
@@ -952,25 +1015,33 @@ async fn update_token_balance(state: &AppState, user_address: &str, tokens_earne
     .await
     .map_err(|e| format!("Failed to update token balance: {}", e))?;
     
-    // Also update legacy balances table for backward compatibility
-    let _ = state.storage.update_balance(user_address, (tokens_earned * 100.0) as u64).await;
+    // Also update legacy balances table for backward compatibility (payouts read it)
+    state.storage.update_balance(user_address, new_legacy_cents).await
+        .map_err(|e| format!("Failed to update legacy balance: {}", e))?;
     
     Ok(())
 }
 
-async fn get_total_earned_today(pool: &PgPool, user_address: &str) -> Result<f64, sqlx::Error> {
+async fn get_total_earned_today(pool: &PgPool, user_address: &str) -> Result<Amount, sqlx::Error> {
     let today = Utc::now().date_naive();
     
-    // Use query_scalar with f64 directly (PostgreSQL DECIMAL converts to f64)
-    let result: Option<f64> = sqlx::query_scalar(
-        "SELECT tokens_earned::float8 FROM user_daily_usage WHERE user_address = $1 AND date = $2"
+    // DECIMAL read as text so the total is not rounded through f64
+    let result: Option<String> = sqlx::query_scalar(
+        "SELECT tokens_earned::text FROM user_daily_usage WHERE user_address = $1 AND date = $2"
     )
     .bind(user_address)
     .bind(today)
     .fetch_optional(pool)
     .await?;
     
-    Ok(result.unwrap_or(0.0))
+    result.map_or(Ok(Amount::ZERO), |text| parse_decimal(&text, "tokens_earned"))
+}
+
+fn parse_decimal(text: &str, column: &str) -> Result<Amount, sqlx::Error> {
+    text.parse().map_err(|e: crate::utils::safe_math::SafeMathError| sqlx::Error::ColumnDecode {
+        index: column.to_string(),
+        source: Box::new(e),
+    })
 }
 
 async fn get_minutes_used_today(pool: &PgPool, user_address: &str) -> Result<i32, sqlx::Error> {
@@ -992,7 +1063,7 @@ async fn get_user_stream_records(pool: &PgPool, user_address: &str) -> Result<Ve
         r#"
         SELECT 
             log_id, track_id, track_title, artist_id, duration_seconds,
-            tokens_earned::float8, stream_type, created_at
+            tokens_earned::text, stream_type, created_at
         FROM stream_logs
         WHERE user_address = $1
         ORDER BY created_at DESC
@@ -1010,7 +1081,7 @@ async fn get_user_stream_records(pool: &PgPool, user_address: &str) -> Result<Ve
         let track_title: String = row.get::<String, _>(2);
         let artist_id: String = row.get::<String, _>(3);
         let duration_seconds: i32 = row.get::<i32, _>(4);
-        let tokens_earned: f64 = row.get::<f64, _>(5);
+        let tokens_earned = parse_decimal(&row.get::<String, _>(5), "tokens_earned")?;
         let stream_type: String = row.get::<String, _>(6);
         let created_at: chrono::DateTime<chrono::Utc> = row.get::<chrono::DateTime<chrono::Utc>, _>(7);
         
@@ -1057,3 +1128,21 @@ pub fn stream_earn_routes() -> axum::Router<AppState> {
         // ✅ NOTE: JWT middleware is applied at the server.rs level via protected_routes
         // The middleware should work with .nest() routes, but if it doesn't, we may need to apply it here
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_earn_credit_keeps_unfinalized_funds_held_back() {
+        // 12.00 DYO finalized, 3.00 DYO credited by blocks that are not final yet
+        let credited = credit_stream_earn(1_200, 300, Amount::from_cents(150)).unwrap();
+        assert_eq!(credited, 1_650);
+        // Only the finalized part grows: payouts still see the 3.00 DYO as awaiting
+        assert_eq!(credited - 300, 1_200 + 150);
+
+        // Sub-cent dust stays in token_balances only
+        assert_eq!(credit_stream_earn(0, 0, Amount::from_micro(9_999)).unwrap(), 0);
+        assert!(credit_stream_earn(u64::MAX, 1, Amount::ZERO).is_err());
+    }
+}
@@ -11,6 +11,7 @@ use chrono::Utc;
 use sqlx::Row;
 use crate::server::AppState;
 use crate::auth::Claims;
+use crate::routes::payout::finalized_spendable;
 
 #[derive(Debug, Serialize)]
 pub struct ConnectStartResponse {
@@ -174,14 +175,15 @@ pub async fn stripe_payout_handler(
         }));
     }
 
-    // Check balance (storage source of truth)
+    // Check balance (storage source of truth, ✅ CPV: finalized funds only)
     let current_cents = state.storage.get_balance(user_address).await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let (spendable_cents, _) = finalized_spendable(&state, user_address, current_cents)?;
     let amount_cents = (req.amount_dyo * 100.0).round() as u64;
-    if current_cents < amount_cents {
+    if spendable_cents < amount_cents {
         return Ok(Json(StripePayoutResponse {
             success: false,
-            message: format!("Insufficient balance. Available: {:.2} DYO", (current_cents as f64)/100.0),
+            message: format!("Insufficient finalized balance. Available: {:.2} DYO", (spendable_cents as f64)/100.0),
             payout_id: None,
             stripe_tx_id: None,
             new_balance_dyo: Some((current_cents as f64)/100.0),
@@ -21,6 +21,7 @@ use sqlx::{self, Row};
 
 use crate::server::AppState;
 use crate::auth::Claims;
+use crate::utils::amount::Amount;
 // ✅ FIX: Temporarily commented - module doesn't exist
 // use crate::security::rate_limiting_redis;
 
@@ -637,7 +638,7 @@ pub async fn upload_content_handler(
         // Return a dummy lock - this is a fallback, but should not happen in practice
         panic!("Token lock poisoned");
     });
-    let reward_amount = 10.0; // Reward artist with 10 tokens per upload
+    let reward_amount = Amount::from_units(10); // Reward artist with 10 tokens per upload
     match token.mint(user_address, reward_amount) {
         Ok(_) => {
             println!("✅ Rewarded artist {} with {} tokens for uploading content", user_address, reward_amount);
@@ -1,5 +1,5 @@
 use axum::{
-    extract::{State, Extension},
+    extract::{Path, State, Extension},
     http::StatusCode,
     response::Json,
     routing::{get, post},
@@ -10,6 +10,10 @@ use std::sync::Arc;
 
 use crate::server::AppState;
 use crate::auth::Claims;
+use crate::consensus::evidence::DoubleSignEvidence;
+use crate::p2p::peer_network::{self, EvidenceOutcome};
+use crate::storage::DbSlashingEvidence;
+use crate::utils::amount::Amount;
 
 // ============================================================================
 // DATA STRUCTURES
@@ -29,6 +33,22 @@ pub struct ConsensusStatsResponse {
     stats: serde_json::Value,
 }
 
+#[derive(Serialize)]
+pub struct EvidenceResponse {
+    success: bool,
+    message: String,
+    evidence_id: Option<String>,
+    validator: Option<String>,
+    slash_amount: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct ValidatorEvidenceResponse {
+    success: bool,
+    address: String,
+    evidence: Vec<DbSlashingEvidence>,
+}
+
 #[derive(Deserialize)]
 pub struct EconomicValidatorRequest {
     pub stake: Option<u64>,
@@ -55,18 +75,13 @@ pub async fn register_economic_validator(
     let address = &claims.sub;
     let stake = request.stake.unwrap_or(1000); // Default minimum stake
     
-    // ✅ FIX: Temporarily commented - cpv_consensus doesn't exist in AppState
-    // TODO: Add cpv_consensus to AppState or implement alternative
     // ✅ SECURITY FIX #4: Verify blockchain balance BEFORE registering validator
     // CRITICAL: Stake verification bypass - system only checked if stake was locked, not if user has balance
     // SOLUTION: Verify actual blockchain balance before allowing registration
-    /*
     let minimum_stake_required = {
         let consensus = state.cpv_consensus.lock().await;
         consensus.minimum_stake
     };
-    */
-    let minimum_stake_required = 1000u64; // ✅ FIX: Temporary default value
     
     // Verify stake meets minimum requirement
     if stake < minimum_stake_required {
@@ -86,8 +101,7 @@ pub async fn register_economic_validator(
         // Lock blockchain mutex to atomically check balance
         let blockchain = state.blockchain.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
         let balance_cents = blockchain.get_balance(address);
-        let balance_dyo = balance_cents as f64 / 100.0;
-        let stake_required_cents = (stake as f64 * 100.0) as u64;
+        let stake_required_cents = Amount::from_units(stake).to_cents().unwrap_or(u64::MAX);
         
         // SECURITY CHECK: Verify user has sufficient balance in blockchain
         if balance_cents < stake_required_cents {
@@ -95,7 +109,7 @@ pub async fn register_economic_validator(
                 success: false,
                 message: format!(
                     "Insufficient blockchain balance. Required: {} DYO, Available: {} DYO",
-                    stake, balance_dyo
+                    stake, Amount::from_cents(balance_cents)
                 ),
                 validator_type: "economic".to_string(),
                 address: address.clone(),
@@ -118,9 +132,7 @@ pub async fn register_economic_validator(
     // ✅ SECURITY FIX: Use async registration with security checks
     let address_clone = address.clone();
     
-    // ✅ FIX: Temporarily commented - cpv_consensus doesn't exist in AppState
     // Use tokio::sync::Mutex which supports await
-    /*
     let mut consensus = state.cpv_consensus.lock().await;
     
     if consensus.db_pool.is_none() {
@@ -136,9 +148,6 @@ pub async fn register_economic_validator(
     // NOTE: Balance already verified above, but verify_and_lock_stake will also check
     // This provides defense in depth
     let result = consensus.register_economic_validator(address_clone.clone(), stake).await;
-    */
-    // ✅ FIX: Temporary response - cpv_consensus module needs to be added to AppState
-    let result: Result<(), String> = Err("CPV Consensus module not available".to_string());
     
     match result {
         Ok(_) => {
@@ -176,9 +185,7 @@ pub async fn register_creative_validator(
     let address_clone = address.clone();
     let verified_nfts_clone = verified_nfts.clone();
     
-    // ✅ FIX: Temporarily commented - cpv_consensus doesn't exist in AppState
     // Use tokio::sync::Mutex which supports await
-    /*
     let mut consensus = state.cpv_consensus.lock().await;
     
     if consensus.db_pool.is_none() {
@@ -192,9 +199,6 @@ pub async fn register_creative_validator(
     
     // Perform async registration (tokio::sync::Mutex allows await)
     let result = consensus.register_creative_validator(address_clone.clone(), verified_nfts_clone.clone()).await;
-    */
-    // ✅ FIX: Temporary response - cpv_consensus module needs to be added to AppState
-    let result: Result<(), String> = Err("CPV Consensus module not available".to_string());
     
     match result {
         Ok(_) => {
@@ -229,9 +233,7 @@ pub async fn register_community_validator(
     // ✅ SECURITY FIX: Use async registration with security checks
     let address_clone = address.clone();
     
-    // ✅ FIX: Temporarily commented - cpv_consensus doesn't exist in AppState
     // Use tokio::sync::Mutex which supports await
-    /*
     let mut consensus = state.cpv_consensus.lock().await;
     
     if consensus.db_pool.is_none() {
@@ -245,9 +247,6 @@ pub async fn register_community_validator(
     
     // Perform async registration (tokio::sync::Mutex allows await)
     let result = consensus.register_community_validator(address_clone.clone()).await;
-    */
-    // ✅ FIX: Temporary response - cpv_consensus module needs to be added to AppState
-    let result: Result<(), String> = Err("CPV Consensus module not available".to_string());
     
     match result {
         Ok(_) => {
@@ -274,11 +273,9 @@ pub async fn register_community_validator(
 /// Get Consensus Statistics (Protected - requires auth)
 /// GET /api/v1/consensus/stats
 pub async fn get_consensus_stats(
-    State(_state): State<AppState>,
+    State(state): State<AppState>,
     Extension(_claims): Extension<Claims>,
 ) -> Result<Json<ConsensusStatsResponse>, StatusCode> {
-    // ✅ FIX: Temporarily commented - cpv_consensus doesn't exist in AppState
-    /*
     let consensus = state.cpv_consensus.lock().await;
     
     let stats = consensus.get_consensus_stats();
@@ -287,26 +284,13 @@ pub async fn get_consensus_stats(
         success: true,
         stats: serde_json::to_value(stats).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?,
     }))
-    */
-    // ✅ FIX: Temporary response - cpv_consensus module needs to be added to AppState
-    Ok(Json(ConsensusStatsResponse {
-        success: true,
-        stats: serde_json::json!({
-            "message": "CPV Consensus module not available",
-            "economic_validators": 0,
-            "creative_validators": 0,
-            "community_validators": 0,
-        }),
-    }))
 }
 
 /// Get Consensus Statistics (Public - no auth required)
 /// GET /api/v1/consensus/stats (public route)
 pub async fn get_consensus_stats_public(
-    State(_state): State<AppState>,
+    State(state): State<AppState>,
 ) -> Result<Json<ConsensusStatsResponse>, StatusCode> {
-    // ✅ FIX: Temporarily commented - cpv_consensus doesn't exist in AppState
-    /*
     let consensus = state.cpv_consensus.lock().await;
     
     let stats = consensus.get_consensus_stats();
@@ -315,16 +299,65 @@ pub async fn get_consensus_stats_public(
         success: true,
         stats: serde_json::to_value(stats).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?,
     }))
-    */
-    // ✅ FIX: Temporary response - cpv_consensus module needs to be added to AppState
-    Ok(Json(ConsensusStatsResponse {
+}
+
+/// Submit double-signing evidence (Protected - requires auth)
+/// POST /api/v1/consensus/evidence
+///
+/// Anyone can submit two conflicting signed blocks or attestations; the
+/// signatures are checked against the offender's registered key before slashing.
+pub async fn submit_evidence(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(evidence): Json<DoubleSignEvidence>,
+) -> Result<Json<EvidenceResponse>, StatusCode> {
+    match peer_network::process_evidence(&state, evidence, &claims.sub, None).await {
+        Ok(EvidenceOutcome::Slashed { evidence_id, validator, slash_amount }) => {
+            println!("⚔️  Double-signing evidence {} accepted from {}: slashed {} by {}", evidence_id, claims.sub, validator, slash_amount);
+            Ok(Json(EvidenceResponse {
+                success: true,
+                message: "Evidence verified and validator slashed".to_string(),
+                evidence_id: Some(evidence_id),
+                validator: Some(validator),
+                slash_amount: Some(slash_amount),
+            }))
+        }
+        Ok(EvidenceOutcome::AlreadyProcessed { evidence_id }) => Ok(Json(EvidenceResponse {
+            success: false,
+            message: "Evidence already processed".to_string(),
+            evidence_id: Some(evidence_id),
+            validator: None,
+            slash_amount: None,
+        })),
+        Err(e) => {
+            println!("❌ Rejected double-signing evidence from {}: {}", claims.sub, e);
+            Ok(Json(EvidenceResponse {
+                success: false,
+                message: format!("Evidence rejected: {}", e),
+                evidence_id: None,
+                validator: None,
+                slash_amount: None,
+            }))
+        }
+    }
+}
+
+/// Get slashing evidence recorded against a validator (Public - audit trail)
+/// GET /api/v1/consensus/evidence/:address
+pub async fn get_validator_evidence(
+    State(state): State<AppState>,
+    Path(address): Path<String>,
+) -> Result<Json<ValidatorEvidenceResponse>, StatusCode> {
+    let evidence = state
+        .storage
+        .get_evidence_for_validator(&address)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(ValidatorEvidenceResponse {
         success: true,
-        stats: serde_json::json!({
-            "message": "CPV Consensus module not available",
-            "economic_validators": 0,
-            "creative_validators": 0,
-            "community_validators": 0,
-        }),
+        address,
+        evidence,
     }))
 }
 
@@ -337,6 +370,7 @@ pub fn validator_registration_routes() -> Router<AppState> {
         .route("/register/economic", post(register_economic_validator))
         .route("/register/creative", post(register_creative_validator))
         .route("/register/community", post(register_community_validator))
+        .route("/evidence", post(submit_evidence))
         // Note: /stats route is defined in public_routes in server.rs to avoid duplication
 }
 
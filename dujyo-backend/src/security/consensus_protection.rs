@@ -5,7 +5,9 @@
 //! - Automatic validator rotation
 //! - Anti-sybil mechanisms
 //! - Slashing conditions for misbehavior
-//! - Governance voting for parameters
+//!
+//! Protocol parameters are governed by `crate::governance` (stake-weighted,
+//! executable proposals), not here.
 
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -24,7 +26,6 @@ pub struct ConsensusSecurityConfig {
     pub max_validator_count: usize,         // Maximum number of validators
     pub rotation_interval: Duration,        // How often to rotate validators
     pub slashing_threshold: f64,            // Threshold for slashing (0.1 = 10%)
-    pub governance_threshold: f64,          // Threshold for governance votes (0.67 = 67%)
     pub anti_sybil_enabled: bool,
     pub reputation_system_enabled: bool,
     pub penalty_multiplier: f64,            // Multiplier for penalties
@@ -38,7 +39,6 @@ impl Default for ConsensusSecurityConfig {
             max_validator_count: 100,       // Maximum 100 validators
             rotation_interval: Duration::from_secs(86400), // 24 hours
             slashing_threshold: 0.1,        // 10% threshold for slashing
-            governance_threshold: 0.67,     // 67% threshold for governance
             anti_sybil_enabled: true,
             reputation_system_enabled: true,
             penalty_multiplier: 2.0,        // 2x penalty for repeat offenses
@@ -97,51 +97,12 @@ pub enum ViolationSeverity {
     Critical, // Slashing
 }
 
-/// Governance proposal
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct GovernanceProposal {
-    pub id: String,
-    pub proposer: String,
-    pub title: String,
-    pub description: String,
-    pub proposal_type: ProposalType,
-    pub parameters: serde_json::Value,
-    pub voting_power_required: f64,
-    pub votes_for: u64,
-    pub votes_against: u64,
-    pub total_voting_power: u64,
-    pub status: ProposalStatus,
-    pub created_at: DateTime<Utc>,
-    pub voting_end: DateTime<Utc>,
-    pub executed_at: Option<DateTime<Utc>>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum ProposalType {
-    ParameterChange,    // Change consensus parameters
-    ValidatorAddition,  // Add new validator
-    ValidatorRemoval,   // Remove validator
-    Slashing,          // Slash validator
-    Emergency,         // Emergency action
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[derive(PartialEq)]
-pub enum ProposalStatus {
-    Active,
-    Passed,
-    Rejected,
-    Executed,
-    Expired,
-}
-
 /// Consensus protection service
 pub struct ConsensusProtection {
     db_pool: PgPool,
     config: ConsensusSecurityConfig,
     validators: Arc<RwLock<HashMap<String, ValidatorInfo>>>,
     last_rotation: Arc<RwLock<DateTime<Utc>>>,
-    governance_proposals: Arc<RwLock<HashMap<String, GovernanceProposal>>>,
 }
 
 impl ConsensusProtection {
@@ -154,15 +115,11 @@ impl ConsensusProtection {
             config,
             validators: Arc::new(RwLock::new(HashMap::new())),
             last_rotation: Arc::new(RwLock::new(Utc::now())),
-            governance_proposals: Arc::new(RwLock::new(HashMap::new())),
         };
 
         // Load validators from database
         protection.load_validators().await?;
         
-        // Load governance proposals
-        protection.load_governance_proposals().await?;
-
         // Start background tasks
         protection.start_background_tasks().await;
 
@@ -371,181 +328,6 @@ impl ConsensusProtection {
         Ok(())
     }
 
-    /// Create governance proposal
-    pub async fn create_governance_proposal(&self, proposer: &str, proposal: GovernanceProposal) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-        let proposal_id = uuid::Uuid::new_v4().to_string();
-        
-        let mut proposals = self.governance_proposals.write().await;
-        proposals.insert(proposal_id.clone(), proposal.clone());
-
-        // Save to database
-        sqlx::query!(
-            r#"
-            INSERT INTO governance_proposals (
-                id, proposer, title, description, proposal_type,
-                parameters, voting_power_required, votes_for, votes_against,
-                total_voting_power, status, created_at, voting_end
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
-            "#,
-            proposal_id,
-            proposer,
-            proposal.title,
-            proposal.description,
-            format!("{:?}", proposal.proposal_type),
-            proposal.parameters,
-            proposal.voting_power_required,
-            proposal.votes_for as i64,
-            proposal.votes_against as i64,
-            proposal.total_voting_power as i64,
-            format!("{:?}", proposal.status),
-            proposal.created_at,
-            proposal.voting_end
-        )
-        .execute(&self.db_pool)
-        .await?;
-
-        info!("📋 Created governance proposal: {}", proposal_id);
-        Ok(proposal_id)
-    }
-
-    /// Vote on governance proposal
-    pub async fn vote_on_proposal(&self, proposal_id: &str, voter: &str, vote: bool, voting_power: u64) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let mut proposals = self.governance_proposals.write().await;
-        
-        if let Some(proposal) = proposals.get_mut(proposal_id) {
-            if proposal.status != ProposalStatus::Active {
-                return Err("Proposal is not active".into());
-            }
-
-            if Utc::now() > proposal.voting_end {
-                proposal.status = ProposalStatus::Expired;
-                return Err("Voting period has ended".into());
-            }
-
-            // Update votes
-            if vote {
-                proposal.votes_for += voting_power;
-            } else {
-                proposal.votes_against += voting_power;
-            }
-
-            proposal.total_voting_power += voting_power;
-
-            // Check if proposal has passed
-            let total_votes = proposal.votes_for + proposal.votes_against;
-            if total_votes > 0 {
-                let approval_rate = proposal.votes_for as f64 / total_votes as f64;
-                if approval_rate >= self.config.governance_threshold {
-                    proposal.status = ProposalStatus::Passed;
-                }
-            }
-
-            // Save vote to database
-            sqlx::query!(
-                r#"
-                INSERT INTO governance_votes (proposal_id, voter, vote, voting_power, timestamp)
-                VALUES ($1, $2, $3, $4, $5)
-                ON CONFLICT (proposal_id, voter) DO UPDATE SET
-                vote = $3, voting_power = $4, timestamp = $5
-                "#,
-                proposal_id,
-                voter,
-                vote,
-                voting_power as i64,
-                Utc::now()
-            )
-            .execute(&self.db_pool)
-            .await?;
-
-            info!("🗳️ Vote recorded for proposal {}: {} ({})", proposal_id, if vote { "FOR" } else { "AGAINST" }, voting_power);
-        } else {
-            return Err("Proposal not found".into());
-        }
-
-        Ok(())
-    }
-
-    /// Execute governance proposal
-    pub async fn execute_proposal(&self, proposal_id: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let mut proposals = self.governance_proposals.write().await;
-        
-        if let Some(proposal) = proposals.get_mut(proposal_id) {
-            if proposal.status != ProposalStatus::Passed {
-                return Err("Proposal has not passed".into());
-            }
-
-            // Execute based on proposal type
-            match proposal.proposal_type {
-                ProposalType::ParameterChange => {
-                    self.execute_parameter_change(&proposal.parameters).await?;
-                }
-                ProposalType::ValidatorAddition => {
-                    self.execute_validator_addition(&proposal.parameters).await?;
-                }
-                ProposalType::ValidatorRemoval => {
-                    self.execute_validator_removal(&proposal.parameters).await?;
-                }
-                ProposalType::Slashing => {
-                    self.execute_slashing(&proposal.parameters).await?;
-                }
-                ProposalType::Emergency => {
-                    self.execute_emergency_action(&proposal.parameters).await?;
-                }
-            }
-
-            proposal.status = ProposalStatus::Executed;
-            proposal.executed_at = Some(Utc::now());
-
-            info!("✅ Executed governance proposal: {}", proposal_id);
-        } else {
-            return Err("Proposal not found".into());
-        }
-
-        Ok(())
-    }
-
-    /// Execute parameter change
-    async fn execute_parameter_change(&self, parameters: &serde_json::Value) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        // Update consensus parameters based on proposal
-        info!("🔧 Executing parameter change: {:?}", parameters);
-        // Implementation would update the consensus configuration
-        Ok(())
-    }
-
-    /// Execute validator addition
-    async fn execute_validator_addition(&self, parameters: &serde_json::Value) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        if let Some(address) = parameters.get("address").and_then(|v| v.as_str()) {
-            info!("➕ Adding validator: {}", address);
-            // Implementation would add the validator to the active set
-        }
-        Ok(())
-    }
-
-    /// Execute validator removal
-    async fn execute_validator_removal(&self, parameters: &serde_json::Value) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        if let Some(address) = parameters.get("address").and_then(|v| v.as_str()) {
-            info!("➖ Removing validator: {}", address);
-            // Implementation would remove the validator from the active set
-        }
-        Ok(())
-    }
-
-    /// Execute slashing
-    async fn execute_slashing(&self, parameters: &serde_json::Value) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        if let Some(address) = parameters.get("address").and_then(|v| v.as_str()) {
-            info!("⚡ Executing slashing for validator: {}", address);
-            // Implementation would apply slashing to the validator
-        }
-        Ok(())
-    }
-
-    /// Execute emergency action
-    async fn execute_emergency_action(&self, parameters: &serde_json::Value) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        info!("🚨 Executing emergency action: {:?}", parameters);
-        // Implementation would execute emergency measures
-        Ok(())
-    }
-
     /// Load validators from database
     async fn load_validators(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         let validators: Vec<ValidatorInfo> = sqlx::query_as!(
@@ -569,31 +351,6 @@ impl ConsensusProtection {
         Ok(())
     }
 
-    /// Load governance proposals from database
-    async fn load_governance_proposals(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let proposals: Vec<GovernanceProposal> = sqlx::query_as!(
-            GovernanceProposal,
-            r#"
-            SELECT 
-                id, proposer, title, description, proposal_type,
-                parameters, voting_power_required, votes_for, votes_against,
-                total_voting_power, status, created_at, voting_end, executed_at
-            FROM governance_proposals
-            WHERE status IN ('Active', 'Passed')
-            "#
-        )
-        .fetch_all(&self.db_pool)
-        .await?;
-
-        let mut proposal_map = self.governance_proposals.write().await;
-        for proposal in proposals {
-            proposal_map.insert(proposal.id.clone(), proposal);
-        }
-
-        info!("✅ Loaded {} governance proposals", proposal_map.len());
-        Ok(())
-    }
-
     /// Save validator to database
     async fn save_validator_to_db(&self, validator: &ValidatorInfo) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         sqlx::query!(
@@ -652,22 +409,18 @@ impl ConsensusProtection {
     /// Get consensus security statistics
     pub async fn get_security_stats(&self) -> Result<ConsensusSecurityStats, Box<dyn std::error::Error + Send + Sync>> {
         let validators = self.validators.read().await;
-        let proposals = self.governance_proposals.read().await;
 
         let total_validators = validators.len();
         let active_validators = validators.values().filter(|v| v.is_active).count();
         let total_stake: u64 = validators.values().map(|v| v.stake).sum();
         let total_violations: usize = validators.values().map(|v| v.violations.len()).sum();
-        let active_proposals = proposals.values().filter(|p| p.status == ProposalStatus::Active).count();
 
         Ok(ConsensusSecurityStats {
             total_validators,
             active_validators,
             total_stake,
             total_violations,
-            active_proposals,
             max_validator_power: self.config.max_validator_power,
-            governance_threshold: self.config.governance_threshold,
         })
     }
 }
@@ -679,9 +432,7 @@ pub struct ConsensusSecurityStats {
     pub active_validators: usize,
     pub total_stake: u64,
     pub total_violations: usize,
-    pub active_proposals: usize,
     pub max_validator_power: f64,
-    pub governance_threshold: f64,
 }
 
 #[cfg(test)]
@@ -18,7 +18,7 @@ pub use rate_limiter_memory::{RateLimiter, RateLimitConfig, RateLimitResult, Abu
 pub use rate_limiting_redis::{check_rate_limit, get_remaining_requests, reset_rate_limit, RateLimitError};
 pub use content_verifier::{ContentVerifier, ContentVerificationConfig, StreamVerificationResult, ViolationType};
 // pub use input_validator; // ⚠️ TEMPORALMENTE DESHABILITADO
-// pub use consensus_protection::{ConsensusProtection, ConsensusSecurityConfig, ValidatorInfo, GovernanceProposal};
+// pub use consensus_protection::{ConsensusProtection, ConsensusSecurityConfig, ValidatorInfo};
 // pub use input_validator::{InputValidator, ValidationConfig, ValidationResult, ValidationError};
 // pub use circuit_breaker::{CircuitBreaker, CircuitBreakerConfig, CircuitBreakerManager, CircuitBreakerError};
 // pub use security_headers::{SecurityHeadersConfig, security_headers_middleware, create_strict_security_config};
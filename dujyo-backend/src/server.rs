@@ -4,30 +4,53 @@ use axum::{
     response::{Json, Response},
     routing::{get, post},
     Router,
+    Extension,
     middleware::Next,
 };
 use serde::{Deserialize, Serialize};
-use std::sync::{Arc, Mutex};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::time;
 use chrono::{DateTime, Utc};
 use tower_http::cors::CorsLayer;
 use tower_http::services::ServeDir;
-use futures_util::{sink::SinkExt, stream::StreamExt};
-use axum::extract::ws::{Message, WebSocket};
 use tracing;
 use sqlx::Postgres;
 use sqlx::Transaction as SqlxTransaction;
 
-use crate::blockchain::blockchain::{Blockchain, Transaction, Block};
+use crate::blockchain::blockchain::{Blockchain, Transaction, Block, BlockHeader};
+use crate::blockchain::fee_market::{tip_priority, FeeMarket, MAX_BLOCK_TRANSACTIONS};
+use crate::blockchain::ledger::{pool_account, TxKind, NATIVE_TOKEN};
+use crate::blockchain::mempool::{MempoolStats, TransactionPriority};
+use crate::blockchain::merkle::MerkleProof;
+use crate::blockchain::state_store::{self, StateCommit};
+use crate::blockchain::signed_transaction::{SignedTransaction, chain_id, decode_public_key};
 use crate::blockchain::token::Token;
-use crate::blockchain::real_blockchain::TokenBalance;
-use crate::blockchain::gas_fees::{GasFeeCalculator, NetworkState, UserTier, TransactionType, handle_gas_fee_with_auto_swap};
-use crate::storage::BlockchainStorage;
-use crate::auth::{JwtConfig, jwt_middleware, login_handler};
-use crate::dex::DEX;
+use crate::blockchain::real_blockchain::{unbonding_period_secs, TokenBalance};
+use crate::blockchain::staking_rewards::{CreateStakingContractRequest, StakingConfigs, StakingManager, VALIDATOR_CONTRACT_ID};
+use crate::utils::amount::Amount;
+use crate::blockchain::gas_fees::{AutoSwapResult, FeeSplit, GasFeeCalculator, NetworkState, UserTier, handle_gas_fee_with_auto_swap};
+use crate::storage::{BlockchainStorage, DexTransactionRecord};
+use crate::consensus::cpv::{CPVConsensus, CPVValidator};
+use crate::consensus::proposer::{self, ProposerKeyring, SYSTEM_PROPOSER};
+use crate::consensus::finality::FinalityGadget;
+use crate::consensus::evidence::EvidencePool;
+use crate::monetization::treasury::{TreasuryManager, TreasuryPolicy};
+use crate::governance::{self, Governance, GovernanceParams, ParameterTargets, Proposal, ProposalStatus};
+use crate::routes::stream_earn::S2ERates;
+use tokio::sync::Mutex as TokioMutex;
+use crate::p2p::peer_network::{self, PeerNetwork};
+use crate::p2p::protocol::SyncMessage;
+use crate::websocket::{self, WsMessage};
+use tokio::sync::broadcast;
+use crate::auth::{Claims, JwtConfig, jwt_middleware, login_handler};
+use crate::dex::SecuredDEX;
+use crate::dex::analytics::{self as dex_analytics, CandleInterval};
+use crate::dex::batch_auction::{batch_auction_enabled, BatchClearing, BatchFillStatus, BatchSwap};
+use crate::dex::orders::{FillPlan, Order, OrderEvent, OrderFill, OrderStatus};
 use crate::handlers::wallet_handlers::{self, ConnectWalletRequest, ConnectWalletResponse, WalletSession};
-use crate::routes::{user, onboarding, stream_earn, s2e_config, s2e_dashboard, s2e_user, s2e_beta, s2e_admin, analytics, royalties, upload, playlists, search, recommendations, follows, comments, reviews, notifications, user_stats, premium, achievements, trending, dex, nfts, metrics, monitoring, health}; // ✅ Import routes
+use crate::routes::{user, onboarding, stream_earn, s2e_config, s2e_dashboard, s2e_user, s2e_beta, s2e_admin, analytics, royalties, upload, playlists, search, recommendations, follows, comments, reviews, notifications, user_stats, premium, achievements, trending, dex, gas, nfts, metrics, monitoring, health, validator_registration}; // ✅ Import routes
 use bb8_redis::{bb8::Pool, RedisConnectionManager};
 use crate::redis::create_redis_pool;
 use crate::middleware::rate_limiting::{redis_rate_limiting_middleware, RedisRateLimitState, RateLimitRules};
@@ -40,26 +63,51 @@ use crate::middleware::input_validation_middleware;
 pub struct AppState {
     pub blockchain: Arc<Mutex<Blockchain>>,
     pub token: Arc<Mutex<Token>>,
-    pub dex: Arc<Mutex<DEX>>,
+    pub dex: Arc<Mutex<SecuredDEX>>,
     pub websocket_clients: Arc<Mutex<Vec<axum::extract::ws::WebSocket>>>,
     pub storage: Arc<BlockchainStorage>,
     pub jwt_config: JwtConfig,
     pub redis_pool: Option<Arc<Pool<RedisConnectionManager>>>, // ✅ MVP-CRITICAL: Redis pool for rate limiting
+    pub cpv_consensus: Arc<TokioMutex<CPVConsensus>>, // ✅ CPV: Proposer selection (tokio Mutex: DB updates are awaited)
+    pub proposer_keys: Arc<ProposerKeyring>, // ✅ CPV: Signing keys of validators hosted by this node
+    pub peer_network: Arc<PeerNetwork>, // Node-to-node gossip and chain sync
+    pub ws_tx: broadcast::Sender<WsMessage>, // Notifications for /ws clients (blocks, reorgs)
+    pub finality: Arc<Mutex<FinalityGadget>>, // ✅ CPV: Validator attestations and finalized height
+    pub evidence_pool: Arc<Mutex<EvidencePool>>, // ✅ SECURITY: Double-signing detection
+    pub treasury: Arc<TreasuryManager>, // Treasury books, fed by each block's fee distribution
+    pub governance: Arc<Mutex<Governance>>, // Proposals, voting and the timelock queue
+    pub gas_fees: Arc<RwLock<GasFeeCalculator>>, // Gas fee configs in force (changed by governance)
+    pub s2e_rates: Arc<RwLock<S2ERates>>, // Stream-to-Earn rates in force (changed by governance)
+    pub staking: Arc<TokioMutex<StakingManager>>, // Validator bonds and delegations (tokio Mutex: held across the DB commit)
 }
 
 // Request/Response types
 #[derive(Deserialize)]
-pub struct TransactionRequest {
-    pub from: String,
-    pub to: String,
-    pub amount: u64,
-    pub nft_id: Option<String>,
+pub struct RegisterKeyRequest {
+    pub address: String,
+    pub public_key: String, // hex-encoded ed25519 verifying key
+}
+
+#[derive(Serialize)]
+pub struct AccountKeyResponse {
+    pub success: bool,
+    pub message: String,
+    pub address: String,
+    pub public_key: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct AccountNonceResponse {
+    pub address: String,
+    pub next_nonce: u64,
+    pub chain_id: String,
+    pub public_key: Option<String>,
 }
 
 #[derive(Deserialize)]
 pub struct MintRequest {
     pub account: String,
-    pub amount: f64,
+    pub amount: Amount,
 }
 
 #[derive(Serialize)]
@@ -83,8 +131,39 @@ pub struct MintResponse {
 
 #[derive(Serialize)]
 pub struct BlockResponse {
-    pub blocks: Vec<Block>,
+    pub blocks: Vec<ExplorerBlock>,
     pub total_blocks: usize,
+    pub finalized_height: u64, // ✅ CPV: Blocks up to this height can no longer be reverted
+    pub finalized_hash: String,
+}
+
+/// Block as shown by the explorer, with the fees it distributed
+#[derive(Serialize)]
+pub struct ExplorerBlock {
+    #[serde(flatten)]
+    pub block: Block,
+    pub fees_collected: u64, // Ledger cents split by the block's fee distribution
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee_distribution: Option<FeeSplit>,
+}
+
+impl From<Block> for ExplorerBlock {
+    fn from(block: Block) -> Self {
+        let fee_distribution = block.fee_split();
+        ExplorerBlock {
+            block,
+            fees_collected: fee_distribution.map(|split| split.total()).unwrap_or(0),
+            fee_distribution,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct TransactionProofResponse {
+    pub block_height: u64,
+    pub block_hash: String,
+    pub header: BlockHeader,
+    pub proof: MerkleProof,
 }
 
 // DEX structures
@@ -92,15 +171,19 @@ pub struct BlockResponse {
 pub struct SwapRequest {
     pub from: String,
     pub to: String,
-    pub amount: f64,
-    pub min_received: f64,
+    pub amount: Amount,
+    pub min_received: Amount,
     pub user: String,
+    /// Unix seconds after which the swap must not execute
+    pub deadline: u64,
+    /// Above the last nonce the user spent (see `GET /api/v1/dex/nonce`)
+    pub nonce: u64,
 }
 
 #[derive(Deserialize, Clone)]
 pub struct LiquidityRequest {
     pub pool_id: String,
-    pub amounts: Vec<f64>, // [amount_a, amount_b]
+    pub amounts: Vec<Amount>, // [amount_a, amount_b]
     pub user: String,
 }
 
@@ -109,7 +192,7 @@ pub struct SwapResponse {
     pub success: bool,
     pub message: String,
     pub tx_hash: Option<String>,
-    pub amount_received: Option<f64>,
+    pub amount_received: Option<Amount>,
     pub price_impact: Option<f64>,
 }
 
@@ -118,21 +201,39 @@ pub struct LiquidityResponse {
     pub success: bool,
     pub message: String,
     pub tx_hash: Option<String>,
-    pub lp_tokens_minted: Option<f64>,
+    pub lp_tokens_minted: Option<Amount>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct RemoveLiquidityRequest {
+    pub pool_id: String,
+    pub lp_tokens: Amount,
+    #[serde(default)]
+    pub min_amounts: Option<Vec<Amount>>, // [min_amount_a, min_amount_b]
+    pub user: String,
+}
+
+#[derive(Serialize)]
+pub struct RemoveLiquidityResponse {
+    pub success: bool,
+    pub message: String,
+    pub tx_hash: Option<String>,
+    pub lp_tokens_burned: Option<Amount>,
+    pub amounts: Option<Vec<Amount>>, // [amount_a, amount_b], accrued fees included
 }
 
 // Staking structures
 #[derive(Deserialize)]
 pub struct ServerStakeRequest {
     pub account: String,
-    pub amount: f64,
+    pub amount: Amount,
     pub lock_period_days: Option<u32>, // User-configurable lock period (default: 30 days)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerUnstakeRequest {
     pub account: String,
-    pub amount: f64,
+    pub amount: Amount,
 }
 
 #[derive(Serialize)]
@@ -140,7 +241,7 @@ pub struct StakeResponse {
     pub success: bool,
     pub message: String,
     pub tx_hash: Option<String>,
-    pub new_balance: Option<f64>,
+    pub new_balance: Option<Amount>,
 }
 
 #[derive(Serialize)]
@@ -153,49 +254,115 @@ pub struct PoolResponse {
 // Handler functions
 async fn get_blocks(State(state): State<AppState>) -> Result<Json<BlockResponse>, StatusCode> {
     let blockchain = state.blockchain.lock().unwrap();
-    let blocks = blockchain.chain.clone();
+    let blocks: Vec<ExplorerBlock> = blockchain.chain.iter().cloned().map(ExplorerBlock::from).collect();
     let total_blocks = blocks.len();
     
     Ok(Json(BlockResponse {
         blocks,
         total_blocks,
+        finalized_height: blockchain.finalized_height,
+        finalized_hash: blockchain.finalized_hash.clone(),
+    }))
+}
+
+// Merkle inclusion proof for a transaction (light clients verify against the block header)
+async fn get_transaction_proof(
+    State(state): State<AppState>,
+    Path((height, tx_hash)): Path<(u64, String)>,
+) -> Result<Json<TransactionProofResponse>, StatusCode> {
+    let blockchain = state.blockchain.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let block = blockchain.get_block(height).ok_or(StatusCode::NOT_FOUND)?;
+    let proof = block.merkle_proof(&tx_hash).ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(TransactionProofResponse {
+        block_height: block.height,
+        block_hash: block.hash.clone(),
+        header: block.header(),
+        proof,
     }))
 }
 
 async fn submit_transaction(
     State(state): State<AppState>,
-    Json(request): Json<TransactionRequest>,
+    Json(request): Json<SignedTransaction>,
 ) -> Result<Json<TransactionResponse>, StatusCode> {
-    // ✅ MVP-CRITICAL: Calculate gas fee with price fixing in USD
-    let gas_calculator = GasFeeCalculator::new();
-    
-    // Get network state (DYO price from DEX pool)
-    let dyo_price_usd = {
-        let dex = state.dex.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-        // Get DYO/DYS pool to calculate price
-        if let Some(pool) = dex.pools.get("DYO_DYS") {
-            // Price = reserve_b (DYS) / reserve_a (DYO)
-            // DYS is pegged to $1 USD, so if 1M DYO : 1M DYS, then 1 DYO = $1 USD
-            if pool.reserve_a > 0.0 {
-                pool.reserve_b / pool.reserve_a
-            } else {
-                0.001 // Default fallback: $0.001 per DYO
-            }
-        } else {
-            0.001 // Default fallback: $0.001 per DYO
+    submit_signed_transaction(&state, request).await.map(Json)
+}
+
+/// Verify a signed transaction, charge its gas fee and add it to the mempool
+/// (POST /transaction and the routes that take signed transactions of one kind)
+pub(crate) async fn submit_signed_transaction(
+    state: &AppState,
+    request: SignedTransaction,
+) -> Result<TransactionResponse, StatusCode> {
+    // ✅ SECURITY: Swaps, S2E payouts, mints and fee distributions only come from the node's own results
+    if !request.kind.is_user_submittable() {
+        metrics::increment_transaction_failed();
+        return Ok(TransactionResponse {
+            success: false,
+            message: format!("{} transactions cannot be submitted", request.kind.name()),
+            transaction_id: None,
+        })
+    }
+
+    // ✅ SECURITY: Only ed25519-signed transactions from a registered key are accepted
+    let registered_key = match state.storage.get_account_key(&request.from).await {
+        Ok(Some(key)) => key,
+        Ok(None) => {
+            metrics::increment_transaction_failed();
+            return Ok(TransactionResponse {
+                success: false,
+                message: format!("No public key registered for address {}", request.from),
+                transaction_id: None,
+            })
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to load account key");
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
         }
     };
-    
-    let network_state = NetworkState {
-        congestion_level: 0.0, // TODO: Calculate from pending transactions
-        dyo_price_usd,
-        daily_volume: 0.0, // TODO: Get from database
+
+    let expected_nonce = {
+        let blockchain = state.blockchain.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        blockchain.next_nonce(&request.from)
     };
+
+    if let Err(e) = request
+        .verify(&chain_id(), &registered_key)
+        .and_then(|_| request.check_nonce(expected_nonce))
+    {
+        tracing::warn!(from = %request.from, error = %e, "Rejected signed transaction");
+        metrics::increment_transaction_failed();
+        return Ok(TransactionResponse {
+            success: false,
+            message: e.to_string(),
+            transaction_id: None,
+        })
+    }
+
+    // Governance votes only count on known proposals before their end height
+    if let TxKind::GovernanceVote { proposal_id, .. } = &request.kind {
+        let height = state.blockchain.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?.get_latest_block().height;
+        let open = state.governance.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .check_vote(proposal_id, height);
+        if let Err(e) = open {
+            metrics::increment_transaction_failed();
+            return Ok(TransactionResponse {
+                success: false,
+                message: e,
+                transaction_id: None,
+            })
+        }
+    }
+
+    // ✅ MVP-CRITICAL: Calculate gas fee with price fixing in USD
+    // Congestion and base fee from the mempool and recent blocks, DYO price from the DEX oracle
+    let (fee_market, network_state, _) = current_fee_market(state)?;
     
-    // Calculate gas fee for Transfer transaction
-    let gas_fee_dyo = gas_calculator.calculate_gas_fee(
-        &TransactionType::Transfer,
-        Some(request.amount as f64 / 100.0), // Convert cents to DYO
+    // Calculate gas fee for the transaction kind (transfer, swap, stake, NFT, vote...)
+    let gas_fee_dyo = state.gas_fees.read().unwrap().calculate_gas_fee(
+        &request.kind.gas_type(),
+        Some(Amount::from_cents(request.amount)), // Ledger cents to DYO
         &UserTier::Regular, // TODO: Get from user profile
         &network_state,
         false,
@@ -203,90 +370,79 @@ async fn submit_transaction(
         tracing::error!(error = %e, "Failed to calculate gas fee");
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
-    
-    // Get user balances
-    let (user_dyo_balance, user_dys_balance) = {
-        let blockchain = state.blockchain.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-        let token = state.token.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-        let dyo_balance = (blockchain.get_balance(&request.from) as f64) / 100.0; // Convert cents to DYO
-        let dys_balance = token.balance_of(&request.from); // DYS balance
-        (dyo_balance, dys_balance)
-    };
-    
-    // ✅ MVP-CRITICAL: Handle auto-swap if needed
-    // TODO: Implementar auto-swap async cuando DEX soporte async
-    // Por ahora, verificamos balance y continuamos
-    let swap_result = crate::blockchain::gas_fees::AutoSwapResult {
-        success: true,
-        dyo_received: 0.0,
-        dys_used: 0.0,
-        swap_executed: false,
-        message: "Auto-swap pending implementation".to_string(),
-    };
-    
-    // Verificar si hay suficiente balance
-    if user_dyo_balance < gas_fee_dyo {
-        return Ok(Json(TransactionResponse {
+    let gas_fee_cents = fee_market.gas_fee_cents(gas_fee_dyo);
+    let gas_fee = Amount::from_cents(gas_fee_cents);
+
+    // Optional priority tip, paid with the gas fee; it decides the mempool priority
+    let tip_cents = request.tip;
+    let fee_cents = gas_fee_cents.saturating_add(tip_cents);
+    let priority = tip_priority(tip_cents, gas_fee_cents);
+
+    // ✅ SECURITY: The sender signed a maximum fee; never charge more than that
+    if let Err(e) = request.check_fee(fee_cents) {
+        metrics::increment_transaction_failed();
+        return Ok(TransactionResponse {
             success: false,
-            message: format!("Insufficient DYO balance for gas fee. Required: {} DYO, Available: {} DYO. Auto-swap coming soon.", gas_fee_dyo, user_dyo_balance),
+            message: e.to_string(),
             transaction_id: None,
-        }));
-    }
-    
-    if swap_result.swap_executed {
-        tracing::info!(
-            user = %request.from,
-            dyo_received = swap_result.dyo_received,
-            dys_used = swap_result.dys_used,
-            "Auto-swapped DYS for DYO to pay gas fee"
-        );
+        })
     }
     
-    // Verify final balance after swap
-    let final_dyo_balance = if swap_result.swap_executed {
-        user_dyo_balance + swap_result.dyo_received
-    } else {
-        user_dyo_balance
+    let tx_hash = request.tx_hash();
+    let nonce = request.nonce;
+    let transaction = request.into_transaction();
+
+    // DYO (ledger cents) the sender needs: the transaction, its gas fee and the
+    // ledger fee of every transaction added for it
+    let (user_dyo_cents, ledger_fee) = {
+        let blockchain = state.blockchain.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        (blockchain.get_balance(&transaction.from), blockchain.transaction_fees)
     };
-    
-    if final_dyo_balance < gas_fee_dyo {
-        return Ok(Json(TransactionResponse {
-            success: false,
-            message: format!("Insufficient DYO balance for gas fee. Required: {} DYO, Available: {} DYO", gas_fee_dyo, final_dyo_balance),
-            transaction_id: None,
-        }));
-    }
-    
-    // Deduct gas fee from balance
-    {
-        let mut blockchain = state.blockchain.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-        let gas_fee_cents = (gas_fee_dyo * 100.0) as u64;
-        let current_balance = blockchain.get_balance(&request.from);
-        if current_balance < gas_fee_cents {
-            return Ok(Json(TransactionResponse {
+    let user_cost = transaction.kind.sender_cost(transaction.amount, ledger_fee);
+    let gas_tx_cost = if fee_cents > 0 { fee_cents + ledger_fee } else { 0 };
+    let required_cents = user_cost + gas_tx_cost;
+    let principal_cents = user_cost - ledger_fee;
+
+    // ✅ MVP-CRITICAL: Short on DYO for the fees only: swap just enough DYS (the
+    // swap's own ledger fee included), within the user's max slippage
+    let mut dyo_shortfall = Amount::ZERO;
+    let mut max_slippage_bps = 0;
+    if user_dyo_cents < required_cents {
+        let available = Amount::from_cents(user_dyo_cents);
+        if user_dyo_cents < principal_cents {
+            metrics::increment_transaction_failed();
+            return Ok(TransactionResponse {
                 success: false,
-                message: format!("Insufficient balance for gas fee"),
+                message: format!("Insufficient DYO balance. Required: {} DYO plus {} DYO gas fee, Available: {} DYO",
+                    Amount::from_cents(principal_cents), Amount::from_cents(fee_cents), available),
                 transaction_id: None,
-            }));
+            })
         }
-        // Create gas fee transaction
-        let gas_fee_tx = Transaction {
-            from: request.from.clone(),
-            to: "GAS_FEE_ADDRESS".to_string(),
-            amount: gas_fee_cents,
-            nft_id: None,
-        };
-        blockchain.add_transaction(gas_fee_tx).map_err(|e| {
-            tracing::error!(error = %e, "Failed to add gas fee transaction");
+        let settings = state.storage.get_auto_swap_settings(&transaction.from).await.map_err(|e| {
+            tracing::error!(error = %e, "Failed to load auto-swap settings");
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
+        if !settings.enabled {
+            metrics::increment_transaction_failed();
+            return Ok(TransactionResponse {
+                success: false,
+                message: format!("Insufficient DYO balance for gas fee. Required: {} DYO, Available: {} DYO (auto-swap disabled)",
+                    Amount::from_cents(required_cents), available),
+                transaction_id: None,
+            })
+        }
+        dyo_shortfall = Amount::from_cents(required_cents + ledger_fee - user_dyo_cents);
+        max_slippage_bps = settings.max_slippage_bps;
     }
-    
-    let transaction = Transaction {
-        from: request.from.clone(),
-        to: request.to.clone(),
-        amount: request.amount,
-        nft_id: request.nft_id,
+    let user_dys_balance = if dyo_shortfall.is_zero() {
+        Amount::ZERO
+    } else {
+        state.storage.get_token_balance(&transaction.from).await
+            .map_err(|e| {
+                tracing::error!(error = %e, "Failed to load token balance");
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?
+            .dys
     };
     
     let pool = &state.storage.pool;
@@ -297,90 +453,340 @@ async fn submit_transaction(
             tracing::error!(error = %e, "Failed to begin transaction");
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
+
+    // ✅ SECURITY: Persist the nonce first (compare-and-set) so concurrent replays lose the race
+    match state.storage.commit_account_nonce_atomic(&transaction.from, nonce, &mut tx).await {
+        Ok(true) => {}
+        Ok(false) => {
+            tx.rollback().await.ok();
+            metrics::increment_transaction_failed();
+            return Ok(TransactionResponse {
+                success: false,
+                message: format!("Nonce {} already used or out of order (replay rejected)", nonce),
+                transaction_id: None,
+            })
+        }
+        Err(e) => {
+            tx.rollback().await.ok();
+            tracing::error!(error = %e, "Failed to persist account nonce");
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
     
-    // Add transaction to blockchain (within transaction context)
+    // Auto-swap, gas fee and signed transaction enter the mempool together (so the
+    // same block) or not at all; a swap whose ledger side fails is undone
     let add_result = {
-        let mut blockchain = state.blockchain.lock()
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-        blockchain.add_transaction(transaction.clone())
-    };
-    
-    match add_result {
-        Ok(_) => {
-            // Save transaction to database in same transaction
-            match state.storage.save_transaction_atomic(&transaction, &mut tx).await {
-                Ok(tx_hash) => {
-                    // Create audit log
-                    let audit_id = uuid::Uuid::new_v4();
-                    let audit_details = serde_json::json!({
-                        "from": transaction.from,
-                        "to": transaction.to,
-                        "amount": transaction.amount
+        let mut dex = state.dex.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let now = chrono::Utc::now().timestamp() as u64;
+        handle_gas_fee_with_auto_swap(dyo_shortfall, user_dys_balance, &transaction.from, max_slippage_bps, &mut dex, now)
+            .and_then(|swap_result| {
+                let added = gas_ledger_transactions(&transaction, fee_cents, &swap_result)
+                    .and_then(|ledger_txs| {
+                        let mut blockchain = state.blockchain.lock()
+                            .map_err(|_| "Blockchain lock poisoned".to_string())?;
+                        blockchain
+                            .add_transactions(ledger_txs.clone(), priority)
+                            .map(|()| (ledger_txs, blockchain.last_bundle()))
                     });
-                    sqlx::query(
-                        "INSERT INTO audit_logs (id, timestamp, action_type, resource, details, success, status_code)
-                         VALUES ($1, NOW(), $2, $3, $4, true, 200)"
-                    )
-                    .bind(audit_id)
-                    .bind("transaction_submitted")
-                    .bind(&tx_hash)
-                    .bind(audit_details)
-                    .execute(&mut *tx)
-                    .await
-                    .map_err(|e| {
-                        tracing::error!(error = %e, "Failed to create audit log");
-                        StatusCode::INTERNAL_SERVER_ERROR
-                    })?;
-                    
-                    // Commit transaction
-                    tx.commit().await
-                        .map_err(|e| {
-                            tracing::error!(error = %e, "Failed to commit transaction");
-                            StatusCode::INTERNAL_SERVER_ERROR
-                        })?;
-                    
-                    let message = if swap_result.swap_executed {
-                        format!("Transaction added successfully. Gas fee: {} DYO (auto-swapped {} DYS)", gas_fee_dyo, swap_result.dys_used)
-                    } else {
-                        format!("Transaction added successfully. Gas fee: {} DYO", gas_fee_dyo)
-                    };
-                    
-                    // ✅ MVP-CRITICAL: Registrar métrica de transacción exitosa
-                    metrics::increment_transaction_success();
-                    
-                    Ok(Json(TransactionResponse {
-                        success: true,
-                        message,
-                        transaction_id: Some(tx_hash),
-                    }))
-                }
-                Err(e) => {
-                    tx.rollback().await.ok(); // Ignore rollback errors
-                    tracing::error!(error = %e, "Failed to save transaction to database");
-                    // ✅ MVP-CRITICAL: Registrar métrica de transacción fallida
-                    metrics::increment_transaction_failed();
-                    Ok(Json(TransactionResponse {
-                        success: false,
-                        message: format!("Database error: {}", e),
-                        transaction_id: None,
-                    }))
+                match added {
+                    Ok((ledger_txs, bundle)) => Ok((swap_result, ledger_txs, bundle)),
+                    Err(e) => {
+                        if swap_result.swap_executed {
+                            if let Err(undo) = dex.undo_swap("DYS", NATIVE_TOKEN, swap_result.dys_used, swap_result.dyo_received) {
+                                tracing::error!(error = %undo, "Failed to undo gas auto-swap");
+                            }
+                        }
+                        Err(e)
+                    }
                 }
-            }
-        }
+            })
+    };
+    let (swap_result, ledger_txs, bundle) = match add_result {
+        Ok(added) => added,
         Err(e) => {
             tx.rollback().await.ok();
             tracing::error!(error = %e, "Failed to add transaction to blockchain");
             // ✅ MVP-CRITICAL: Registrar métrica de transacción fallida
             metrics::increment_transaction_failed();
-            Ok(Json(TransactionResponse {
+            return Ok(TransactionResponse {
                 success: false,
                 message: e,
                 transaction_id: None,
-            }))
+            })
+        }
+    };
+
+    // DYS debit, transaction row and audit log in the same database transaction
+    let persisted: Result<(), String> = async {
+        if swap_result.swap_executed
+            && !state.storage.debit_auto_swap_atomic(&transaction.from, swap_result.dys_used, &mut tx).await
+                .map_err(|e| format!("Database error: {}", e))?
+        {
+            return Err(format!("Insufficient DYS balance for the gas auto-swap ({} DYS)", swap_result.dys_used));
+        }
+        state.storage.save_signed_transaction_atomic(&transaction, &tx_hash, &mut tx).await
+            .map_err(|e| format!("Database error: {}", e))?;
+        // The whole bundle (auto-swap and gas legs too) is what a restart re-queues
+        state.storage.save_pending_bundle_atomic(bundle, priority, &ledger_txs, &mut tx).await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+        // Create audit log
+        let audit_id = uuid::Uuid::new_v4();
+        let audit_details = serde_json::json!({
+            "from": transaction.from,
+            "to": transaction.to,
+            "amount": transaction.amount,
+            "nonce": nonce,
+            "gas_fee_cents": gas_fee_cents,
+            "tip_cents": tip_cents,
+            "priority": priority.as_str(),
+            "auto_swap_dys": swap_result.swap_executed.then(|| swap_result.dys_used.to_string())
+        });
+        sqlx::query(
+            "INSERT INTO audit_logs (id, timestamp, action_type, resource, details, success, status_code)
+             VALUES ($1, NOW(), $2, $3, $4, true, 200)"
+        )
+        .bind(audit_id)
+        .bind("transaction_submitted")
+        .bind(&tx_hash)
+        .bind(audit_details)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+        Ok(())
+    }.await;
+    let committed = match persisted {
+        Ok(()) => tx.commit().await.map_err(|e| format!("Database error: {}", e)),
+        Err(e) => {
+            tx.rollback().await.ok(); // Ignore rollback errors
+            Err(e)
+        }
+    };
+    if let Err(e) = committed {
+        tracing::error!(error = %e, "Failed to persist transaction");
+        undo_submission(state, &ledger_txs, &swap_result);
+        // ✅ MVP-CRITICAL: Registrar métrica de transacción fallida
+        metrics::increment_transaction_failed();
+        return Ok(TransactionResponse {
+            success: false,
+            message: e,
+            transaction_id: None,
+        })
+    }
+
+    let gas_fee_text = if tip_cents > 0 {
+        format!("{} DYO + {} DYO priority tip", gas_fee, Amount::from_cents(tip_cents))
+    } else {
+        format!("{} DYO", gas_fee)
+    };
+    let message = if swap_result.swap_executed {
+        tracing::info!(
+            user = %transaction.from,
+            dyo_received = %swap_result.dyo_received,
+            dys_used = %swap_result.dys_used,
+            "Auto-swapped DYS for DYO to pay gas fee"
+        );
+        record_gas_auto_swap(state, &transaction.from, &swap_result).await;
+        format!("Transaction added successfully. Gas fee: {} (auto-swapped {} DYS)", gas_fee_text, swap_result.dys_used)
+    } else {
+        format!("Transaction added successfully. Gas fee: {}", gas_fee_text)
+    };
+    
+    // ✅ MVP-CRITICAL: Registrar métrica de transacción exitosa
+    metrics::increment_transaction_success();
+    
+    // Gossip the signed transaction to the other nodes
+    state.peer_network.broadcast(&SyncMessage::NewTransaction(transaction.clone()), None);
+    
+    Ok(TransactionResponse {
+        success: true,
+        message,
+        transaction_id: Some(tx_hash),
+    })
+}
+
+/// Ledger transactions of a signed submission, in execution order: the DYO leg
+/// of the gas auto-swap, the gas fee (priority tip included) and the transaction itself
+fn gas_ledger_transactions(
+    transaction: &Transaction,
+    gas_fee_cents: u64,
+    swap_result: &AutoSwapResult,
+) -> Result<Vec<Transaction>, String> {
+    let mut ledger_txs = Vec::with_capacity(3);
+    if swap_result.swap_executed {
+        let dex_pool = crate::dex::pool_id("DYS", NATIVE_TOKEN);
+        let dys_cents = swap_result.dys_used.round_up_to(2)
+            .and_then(Amount::to_cents)
+            .map_err(|e| format!("Invalid auto-swap amount: {}", e))?;
+        let dyo_cents = swap_result.dyo_received.to_cents_floor()
+            .map_err(|e| format!("Invalid auto-swap amount: {}", e))?;
+        ledger_txs.push(
+            Transaction::system(transaction.from.clone(), pool_account(&dex_pool), dys_cents, None).with_kind(TxKind::Swap {
+                pool_id: dex_pool,
+                token_in: "DYS".to_string(),
+                token_out: NATIVE_TOKEN.to_string(),
+                amount_out: dyo_cents,
+            }),
+        );
+    }
+    if gas_fee_cents > 0 {
+        ledger_txs.push(Transaction::system(
+            transaction.from.clone(),
+            "GAS_FEE_ADDRESS".to_string(),
+            gas_fee_cents,
+            None,
+        ));
+    }
+    ledger_txs.push(transaction.clone());
+    Ok(ledger_txs)
+}
+
+/// Run a liquidity change of pool `pool_id` on the DEX and queue its DYO leg on the
+/// ledger (`dex::change_liquidity`). The queued leg comes back with its mempool
+/// bundle for `persist_liquidity_leg`.
+pub(crate) fn change_liquidity(
+    state: &AppState,
+    pool_id: &str,
+    provider: &str,
+    change: impl FnOnce(&mut SecuredDEX) -> Result<crate::dex::LiquidityResponse, String>,
+) -> Result<(crate::dex::LiquidityResponse, Option<(u64, Transaction)>), String> {
+    let mut dex = state.dex.lock().map_err(|_| "DEX lock poisoned".to_string())?;
+    let mut blockchain = state.blockchain.lock().map_err(|_| "Blockchain lock poisoned".to_string())?;
+    let (response, leg) = crate::dex::change_liquidity(&mut dex, &mut blockchain, pool_id, provider, change)?;
+    Ok((response, leg.map(|leg| (blockchain.last_bundle(), leg))))
+}
+
+/// Give back DEX debits the pools did not take (a failed or partly filled deposit)
+async fn refund_dex_balances(state: &AppState, user: &str, debits: &[(&str, Amount)]) {
+    for &(symbol, amount) in debits.iter().filter(|(_, amount)| !amount.is_zero()) {
+        if let Err(e) = state.storage.credit_dex_balance(user, symbol, amount).await {
+            tracing::error!(user = %user, token = %symbol, %amount, error = %e, "DEX debit was not refunded");
+        }
+    }
+}
+
+/// Store the ledger leg `change_liquidity` queued with the rest of the mempool
+pub(crate) async fn persist_liquidity_leg(state: &AppState, queued: Option<(u64, Transaction)>) {
+    if let Some((bundle, leg)) = queued {
+        if let Err(e) = state.storage
+            .save_pending_bundle(bundle, TransactionPriority::Normal, std::slice::from_ref(&leg))
+            .await
+        {
+            tracing::error!(tx_hash = %leg.tx_hash(), error = %e, "Failed to persist liquidity leg");
+        }
+    }
+}
+
+/// Fee market at the head and the `NetworkState` gas fees are calculated with:
+/// congestion from the mempool and recent blocks, DYO price from the DEX TWAP
+/// oracle (not the manipulable spot reserves)
+pub(crate) fn current_fee_market(state: &AppState) -> Result<(FeeMarket, NetworkState, MempoolStats), StatusCode> {
+    let now = chrono::Utc::now().timestamp() as u64;
+    let dyo_price_usd = {
+        let dex = state.dex.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        GasFeeCalculator::dyo_price_usd(&dex, now)
+    };
+    let (fee_market, mempool_stats) = {
+        let blockchain = state.blockchain.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        (FeeMarket::from_chain(&blockchain.chain, now), blockchain.mempool().get_stats())
+    };
+    let network_state = fee_market.network_state(&mempool_stats, dyo_price_usd);
+    Ok((fee_market, network_state, mempool_stats))
+}
+
+/// Take back what `submit_transaction` put in memory when its database
+/// transaction did not commit: the ledger transactions and the gas auto-swap
+fn undo_submission(state: &AppState, ledger_txs: &[Transaction], swap_result: &AutoSwapResult) {
+    if let Ok(mut blockchain) = state.blockchain.lock() {
+        blockchain.remove_pending(ledger_txs);
+    }
+    if swap_result.swap_executed {
+        let undone = state.dex.lock()
+            .map_err(|_| "DEX lock poisoned".to_string())
+            .and_then(|mut dex| dex.undo_swap("DYS", NATIVE_TOKEN, swap_result.dys_used, swap_result.dyo_received));
+        if let Err(e) = undone {
+            tracing::error!(error = %e, "Failed to undo gas auto-swap");
+        }
+    }
+}
+
+/// Pool row and DEX history of a committed gas auto-swap (failures are logged)
+async fn record_gas_auto_swap(state: &AppState, user: &str, swap_result: &AutoSwapResult) {
+    let pool_id = crate::dex::pool_id("DYS", NATIVE_TOKEN);
+    let synced = sync_dex_pools(state, std::slice::from_ref(&pool_id)).await;
+    if let (Some(pool), Some(swap_id)) = (synced.first(), &swap_result.swap_id) {
+        record_dex_transaction(state, &DexTransactionRecord {
+            tx_hash: swap_id,
+            from: user,
+            to: "DEX_CONTRACT",
+            amount_in: swap_result.dys_used,
+            amount_out: swap_result.dyo_received,
+            transaction_type: "gas_auto_swap",
+            token_in: Some("DYS"),
+            pool,
+        }).await;
+    }
+}
+
+// ✅ SECURITY: Bind an ed25519 public key to the authenticated account (one-time)
+async fn register_account_key(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(request): Json<RegisterKeyRequest>,
+) -> Result<Json<AccountKeyResponse>, StatusCode> {
+    if claims.sub != request.address {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    if let Err(e) = decode_public_key(&request.public_key) {
+        return Ok(Json(AccountKeyResponse {
+            success: false,
+            message: e.to_string(),
+            address: request.address,
+            public_key: None,
+        }));
+    }
+
+    match state.storage.register_account_key(&request.address, &request.public_key).await {
+        Ok(true) => Ok(Json(AccountKeyResponse {
+            success: true,
+            message: "Public key registered".to_string(),
+            address: request.address,
+            public_key: Some(request.public_key.to_lowercase()),
+        })),
+        Ok(false) => Ok(Json(AccountKeyResponse {
+            success: false,
+            message: "A public key is already registered for this address (or key already in use)".to_string(),
+            address: request.address,
+            public_key: None,
+        })),
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to register account key");
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
         }
     }
 }
 
+// Next nonce and registered key for an address (wallets need both to sign)
+async fn get_account_nonce(
+    State(state): State<AppState>,
+    Path(address): Path<String>,
+) -> Result<Json<AccountNonceResponse>, StatusCode> {
+    let public_key = state.storage.get_account_key(&address).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let next_nonce = {
+        let blockchain = state.blockchain.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        blockchain.next_nonce(&address)
+    };
+
+    Ok(Json(AccountNonceResponse {
+        address,
+        next_nonce,
+        chain_id: chain_id(),
+        public_key,
+    }))
+}
+
 async fn mint_tokens(
     State(state): State<AppState>,
     Json(request): Json<MintRequest>,
@@ -423,8 +829,7 @@ async fn get_balance(
     let balance_centavos = match result {
         Some((Some(dyo_micro), _, _)) => {
             // Convert from micro-DYO to centavos (for backward compatibility)
-            let dyo = dyo_micro as f64 / 1_000_000.0;
-            (dyo * 100.0) as u64
+            Amount::from_micro(dyo_micro.max(0) as u64).to_cents_floor().unwrap_or(0)
         },
         None => {
             // Fallback to legacy balance if no token_balances record exists
@@ -851,11 +1256,11 @@ async fn get_earnings_predictions_handler(
 #[derive(Serialize)]
 struct BalanceDetailResponse {
     address: String,
-    dyo: f64,
-    dys: f64,
-    staked: f64,
-    total: f64,
-    available_dyo: f64,
+    dyo: Amount,
+    dys: Amount,
+    staked: Amount,
+    total: Amount,
+    available_dyo: Amount,
 }
 
 async fn get_balance_detail(
@@ -879,24 +1284,14 @@ async fn get_balance_detail(
         match result {
             Some((dyo_balance, dys_balance, staked_balance)) => {
                 // Convert from micro-DYO (1_000_000 = 1 DYO) to DYO
-                TokenBalance {
-                    dyo: dyo_balance as f64 / 1_000_000.0,
-                    dys: dys_balance as f64 / 1_000_000.0,
-                    staked: staked_balance as f64 / 1_000_000.0,
-                    total: (dyo_balance + dys_balance + staked_balance) as f64 / 1_000_000.0,
-                }
+                TokenBalance::from_micro(dyo_balance, dys_balance, staked_balance)
             },
             None => {
                 // If no record in token_balances, check legacy balances table
                 let legacy_balance = state.storage.get_balance(&address).await.unwrap_or(0);
-                let legacy_dyo = (legacy_balance as f64) / 100.0; // Convert centavos to DYO
+                let legacy_dyo = Amount::from_cents(legacy_balance); // Convert centavos to DYO
                 
-                TokenBalance {
-                    dyo: legacy_dyo,
-        dys: 0.0,
-        staked: 0.0,
-                    total: legacy_dyo,
-                }
+                TokenBalance::new(legacy_dyo, Amount::ZERO, Amount::ZERO)
             },
         }
     };
@@ -941,8 +1336,8 @@ async fn get_transaction_history(
     let pool = &state.storage.pool;
     
     // Get transactions from database with created_at
-    let transactions_result: Result<Vec<(String, String, String, i64, String, Option<i64>, chrono::DateTime<chrono::Utc>)>, sqlx::Error> = sqlx::query_as(
-        "SELECT tx_hash, from_address, to_address, amount, status, block_height, created_at FROM transactions WHERE from_address = $1 OR to_address = $1 ORDER BY created_at DESC LIMIT 50"
+    let transactions_result: Result<Vec<(String, String, String, i64, String, Option<i64>, chrono::DateTime<chrono::Utc>, String, Option<serde_json::Value>)>, sqlx::Error> = sqlx::query_as(
+        "SELECT tx_hash, from_address, to_address, amount, status, block_height, created_at, COALESCE(transaction_type, 'transfer'), kind FROM transactions WHERE from_address = $1 OR to_address = $1 ORDER BY created_at DESC LIMIT 50"
     )
     .bind(&address)
     .fetch_all(pool)
@@ -950,9 +1345,11 @@ async fn get_transaction_history(
     
     let transactions = match transactions_result {
         Ok(rows) => rows.into_iter()
-            .map(|(tx_hash, from_address, to_address, amount, status, block_height, created_at)| {
+            .map(|(tx_hash, from_address, to_address, amount, status, block_height, created_at, tx_type, kind)| {
                 serde_json::json!({
                     "hash": tx_hash,
+                    "type": tx_type,
+                    "kind": kind,
                     "from": from_address,
                     "to": to_address,
                     "amount": amount,
@@ -974,33 +1371,35 @@ async fn get_transaction_history(
 // Block production task
 async fn block_production_task(state: AppState) {
     let mut interval = time::interval(Duration::from_secs(10)); // Produce block every 10 seconds
+    let snapshot_interval = state_store::snapshot_interval();
+    // DEX batch results settled but not yet reported in a block
+    let mut unreported_batches: Vec<BatchClearing> = Vec::new();
     
     loop {
         interval.tick().await;
         
-        // Get blockchain data and release lock before async operations
-        let (transactions, previous_hash, current_height, should_create_block) = {
-            let mut blockchain = state.blockchain.lock().unwrap();
+        let batch_waiting = !unreported_batches.is_empty()
+            || state.dex.lock().map(|dex| !dex.batch.is_empty()).unwrap_or(false);
+        
+        // Decide whether this slot produces a block (pending txs stay queued until sealed)
+        let (previous_hash, current_height, should_create_block) = {
+            let blockchain = state.blockchain.lock().unwrap();
             let current_height = blockchain.chain.len() as i64;
-            let latest_block = blockchain.get_latest_block().clone(); // Clone to avoid borrow issues
+            let latest_block = blockchain.get_latest_block();
             
-            if !blockchain.pending_transactions.is_empty() {
-                // Always create block if there are pending transactions
-                let transactions = blockchain.pending_transactions.clone();
-                blockchain.pending_transactions.clear();
-                let previous_hash = latest_block.hash.clone();
-                (Some(transactions), previous_hash, current_height, true)
+            if !blockchain.pending_transactions.is_empty() || batch_waiting {
+                // Always create block if there are pending transactions (or DEX batch swaps)
+                (latest_block.hash.clone(), current_height, true)
             } else {
                 // Only create empty block if it's been more than 30 seconds since last block
                 let last_block_timestamp = latest_block.timestamp;
                 let current_timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
                 
-                if current_timestamp - last_block_timestamp < 30 {
+                if current_timestamp.saturating_sub(last_block_timestamp) < 30 {
                     // Skip creating empty block
-                    (None, String::new(), current_height, false)
+                    (String::new(), current_height, false)
                 } else {
-                    let previous_hash = latest_block.hash.clone();
-                    (None, previous_hash, current_height, true)
+                    (latest_block.hash.clone(), current_height, true)
                 }
             }
         };
@@ -1010,57 +1409,207 @@ async fn block_production_task(state: AppState) {
             continue; // Skip this iteration
         }
         
-        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        // ✅ CPV: Slot lottery over (height, parent hash, round): a validator hosted here
+        // proposes when its own VRF output wins under the CPV weights
+        let (timestamp, parent_timestamp) = {
+            let blockchain = state.blockchain.lock().unwrap();
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+            (now, blockchain.get_latest_block().timestamp)
+        };
+        let round = proposer::slot_round(parent_timestamp, timestamp);
+        let slot_seed = proposer::slot_seed(current_height as u64, &previous_hash, round);
+        let (selected_validator, vrf_result): (Option<CPVValidator>, _) = {
+            let mut consensus = state.cpv_consensus.lock().await;
+            match consensus.select_proposer(&state.proposer_keys, &slot_seed) {
+                Ok((validator, vrf_result)) => (Some(validator), vrf_result),
+                Err(e) if !consensus.has_active_validators() && PeerNetwork::configured_peers().is_empty() => {
+                    // Bootstrap: no active validators yet, the (single) node key proposes
+                    tracing::warn!(error = %e, "CPV proposer selection failed, node proposes this slot");
+                    let Some(node_key) = state.proposer_keys.signing_key(SYSTEM_PROPOSER) else {
+                        continue;
+                    };
+                    (None, proposer::prove_slot(node_key, &slot_seed))
+                }
+                Err(_) => {
+                    // No validator hosted here won this round (nodes that sync from
+                    // peers never bootstrap-propose, avoiding competing system blocks)
+                    continue;
+                }
+            }
+        };
+        let proposer_address = selected_validator
+            .as_ref()
+            .map(|validator| validator.address.clone())
+            .unwrap_or_else(|| SYSTEM_PROPOSER.to_string());
+        let Some(proposer_key) = state.proposer_keys.signing_key(&proposer_address) else {
+            continue;
+        };
+        
+        // ✅ MEV: Clear the DEX swaps collected during this interval (batch auction mode)
+        unreported_batches.extend(settle_dex_batch(&state).await);
         
-        // Create new block with unique hash (include timestamp in hash calculation)
-        let mut new_block = Block {
-            timestamp,
-            transactions: transactions.clone().unwrap_or_default(),
-            previous_hash,
-            hash: String::new(),
-            validator: Some("system".to_string()),
+        // Take the pending transactions, seal and append under one lock so a block
+        // imported (or a reorg) while we were selecting the proposer cannot leave us
+        // building on a stale parent. The state it produced is captured in the same step.
+        let (new_block, state_commit) = {
+            let mut blockchain = state.blockchain.lock().unwrap();
+            if blockchain.get_latest_block().hash != previous_hash {
+                tracing::info!(height = current_height, "Head moved during proposer selection, slot skipped");
+                continue;
+            }
+            // Highest priority bundles first, up to the block capacity (the rest stays pending),
+            // then the collected fees split among treasury, proposer, creative pool and burn sink
+            let (transactions, state_root) = match blockchain.take_block_transactions(MAX_BLOCK_TRANSACTIONS, &proposer_address) {
+                Ok(selection) => selection,
+                Err(e) => {
+                    tracing::error!(height = current_height, error = %e, "Block not produced, slot skipped");
+                    continue;
+                }
+            };
+            
+            // Create new block: header hash covers height, parent, Merkle root, state root, proposer and VRF output
+            let mut new_block = Block::new(
+                current_height as u64,
+                timestamp,
+                transactions,
+                previous_hash,
+                state_root,
+                Some(proposer_address.clone()),
+            );
+            // Per-user batch results are part of the header hash
+            new_block.dex_batches = std::mem::take(&mut unreported_batches);
+            new_block.seal(Some(vrf_result), proposer_key);
+            blockchain.chain.push(new_block.clone());
+            let state_commit = StateCommit::capture(&blockchain, std::slice::from_ref(&new_block), snapshot_interval);
+            (new_block, state_commit)
         };
+        let transactions = &new_block.transactions;
+        
+        // Block, confirmed transactions, touched balances/nonces and snapshot in one DB transaction
+        if let Err(e) = state.storage.commit_blocks(std::slice::from_ref(&new_block), &state_commit).await {
+            // Never announce a block the database does not hold: drop it and retry next slot
+            tracing::error!(height = new_block.height, error = %e, "Failed to commit block, reloading the chain");
+            peer_network::reload_chain(&state).await;
+            unreported_batches = new_block.dex_batches;
+            continue;
+        }
+        record_block_fees(&state, std::slice::from_ref(&new_block)).await;
+        accrue_staking_rewards(&state, std::slice::from_ref(&new_block)).await;
+
+        // Advance the DEX price oracle once per block so quiet pools keep accruing time
+        if let Ok(mut dex) = state.dex.lock() {
+            dex.record_block_observations(new_block.timestamp);
+        }
+
+        let block_hash = new_block.hash.clone();
+        
+        // Announce the block to peers and websocket clients
+        state.peer_network.broadcast(&SyncMessage::NewBlock(new_block.clone()), None);
+        websocket::broadcast_new_block(
+            &state.ws_tx,
+            new_block.height,
+            block_hash.clone(),
+            new_block.timestamp as i64,
+            transactions.len(),
+        )
+        .await;
         
-        // Calculate hash - this will be unique because timestamp is included
-        new_block.hash = new_block.calculate_hash();
+        // ✅ CPV: Our own validators attest the new head (finality gadget)
+        peer_network::attest_head(&state).await;
         
-        // Save block to database (will silently ignore duplicates)
-        if let Err(e) = state.storage.save_block(&new_block, current_height).await {
-            // Only log if it's not a duplicate key error
-            if !e.to_string().contains("duplicate key") {
-                eprintln!("Error saving block to database: {}", e);
+        // ✅ CPV: Record the round and reward the proposer's reputation
+        if let Some(validator) = selected_validator {
+            let mut consensus = state.cpv_consensus.lock().await;
+            consensus.record_validation_round(&validator, block_hash).await;
+            if let Err(e) = consensus.update_reputation_after_block(&validator.address, true).await {
+                tracing::error!(error = %e, "Failed to update proposer reputation");
             }
         }
         
-        // Update balances in database if there are transactions
-        if let Some(ref transactions) = transactions {
-            for transaction in transactions {
-                let (from_balance, to_balance) = {
-                    let blockchain = state.blockchain.lock().unwrap();
-                    (blockchain.get_balance(&transaction.from), blockchain.get_balance(&transaction.to))
-                };
-                
-                if let Err(e) = state.storage.update_balance(&transaction.from, from_balance).await {
-                    println!("Error updating balance for {}: {}", transaction.from, e);
-                }
-                if let Err(e) = state.storage.update_balance(&transaction.to, to_balance).await {
-                    println!("Error updating balance for {}: {}", transaction.to, e);
+        if !transactions.is_empty() {
+            tracing::info!(transactions = transactions.len(), proposer = %proposer_address, "New block created");
+        } else {
+            tracing::info!(proposer = %proposer_address, "Empty block created");
+        }
+    }
+}
+
+/// Book the treasury share of each committed block's fee distribution. The
+/// split itself is stored with the block commit and booked again at startup
+/// (failures are logged, the on-chain credit stands either way)
+pub(crate) async fn record_block_fees(state: &AppState, blocks: &[Block]) {
+    for block in blocks {
+        let Some(fees) = block.fee_split() else { continue };
+        if let Err(e) = state.treasury.collect_block_fees(block.height, &fees).await {
+            tracing::warn!(height = block.height, error = %e, "Block fees not booked in the treasury");
+        }
+    }
+}
+
+/// Treasury books rebuilt from the fee distributions stored with each block commit
+async fn restore_treasury(storage: &BlockchainStorage) -> TreasuryManager {
+    let treasury = TreasuryManager::new(TreasuryPolicy::default());
+    match storage.load_block_fees().await {
+        Ok(bookings) => {
+            for (height, fees) in &bookings {
+                if let Err(e) = treasury.collect_block_fees(*height, fees).await {
+                    tracing::warn!(height, error = %e, "Block fees not booked in the treasury");
                 }
             }
         }
-        
-        // Add block to blockchain
-        {
-            let mut blockchain = state.blockchain.lock().unwrap();
-            blockchain.chain.push(new_block);
+        Err(e) => tracing::warn!(error = %e, "Could not load treasury block fees"),
+    }
+    treasury
+}
+
+/// Accrue each committed block's staking emission in the staking manager and
+/// store the accumulator. Block fees are not part of it: the on-chain
+/// `FeeDistribution` already pays all of them out.
+/// Delegations auto-compounded at an epoch close are added to the delegators'
+/// staked DYO in the same commit.
+pub(crate) async fn accrue_staking_rewards(state: &AppState, blocks: &[Block]) {
+    let mut staking = state.staking.lock().await;
+    let previous = staking.clone();
+    let delegated_before = delegated_by_account(&staking);
+
+    let mut accrued = false;
+    for block in blocks {
+        match staking.accrue_block_rewards(block.height, SYSTEM_PROPOSER) {
+            Ok(_) => accrued = true,
+            Err(e) => tracing::warn!(height = block.height, error = %e, "Staking rewards not accrued"),
         }
-        
-        if let Some(ref transactions) = transactions {
-            println!("New block created with {} transactions", transactions.len());
-        } else {
-            println!("Empty block created");
+    }
+    if !accrued {
+        return;
+    }
+
+    let compounded: Vec<(String, Amount)> = delegated_by_account(&staking)
+        .into_iter()
+        .filter_map(|(delegator, total)| {
+            let gained = total.saturating_sub(delegated_before.get(&delegator).copied().unwrap_or(0));
+            (gained > 0).then(|| (delegator, Amount::from_units(gained)))
+        })
+        .collect();
+    if let Err(e) = state.storage.save_compounded_delegations(&staking, &compounded).await {
+        *staking = previous;
+        tracing::error!(error = %e, "Failed to store staking rewards");
+        return;
+    }
+    if !compounded.is_empty() {
+        sync_validator_stakes(&mut staking, &mut *state.cpv_consensus.lock().await);
+    }
+}
+
+// DYO each account has delegated in the validator contract
+fn delegated_by_account(staking: &StakingManager) -> HashMap<String, u64> {
+    let mut totals: HashMap<String, u64> = HashMap::new();
+    if let Some(contract) = staking.get_staking_contract(VALIDATOR_CONTRACT_ID) {
+        for delegation in contract.delegations.values().flat_map(|pool| pool.delegators.values()) {
+            let total = totals.entry(delegation.address.clone()).or_insert(0);
+            *total = total.saturating_add(delegation.delegated_amount);
         }
     }
+    totals
 }
 
 // Health check endpoint
@@ -1072,41 +1621,14 @@ async fn health_check() -> Result<Json<serde_json::Value>, StatusCode> {
     })))
 }
 
-// WebSocket handler
-async fn websocket_handler(
+// Peer node WebSocket handler
+async fn p2p_handler(
     ws: WebSocketUpgrade,
     State(state): State<AppState>,
-) -> axum::response::Response {
-    ws.on_upgrade(|socket| websocket_connection(socket, state))
+) -> Response {
+    ws.on_upgrade(|socket| peer_network::handle_inbound_peer(socket, state))
 }
 
-async fn websocket_connection(socket: WebSocket, _state: AppState) {
-    let (mut sender, mut receiver) = socket.split();
-    
-    println!("New WebSocket connection established");
-    
-    // Handle incoming messages
-    while let Some(msg) = receiver.next().await {
-        match msg {
-            Ok(Message::Text(text)) => {
-                println!("Received WebSocket message: {}", text);
-                
-                // Echo back the message
-                if let Err(e) = sender.send(Message::Text(format!("Echo: {}", text))).await {
-                    println!("Error sending WebSocket message: {}", e);
-                    break;
-                }
-            }
-            Ok(Message::Close(_)) => {
-                println!("WebSocket connection closed");
-                break;
-            }
-            _ => {}
-        }
-    }
-}
-
-
 async fn get_pool(
     State(state): State<AppState>,
     Path(pool_id): Path<String>,
@@ -1131,6 +1653,525 @@ async fn get_pool(
     }
 }
 
+/// Request amount in the micro-DYO unit of `token_balances`
+fn micro_units(amount: Amount) -> Result<i64, String> {
+    amount
+        .to_micro()
+        .ok()
+        .and_then(|micro| i64::try_from(micro).ok())
+        .ok_or_else(|| format!("Invalid amount {}: balances hold at most 6 decimals", amount))
+}
+
+/// Write the current state of DEX pools to `dex_pools` and announce them as
+/// `DexUpdate` websocket messages (failures are logged, never fatal). Returns
+/// the synced pool states.
+pub(crate) async fn sync_dex_pools(state: &AppState, pool_ids: &[String]) -> Vec<crate::dex::Pool> {
+    let pools: Vec<crate::dex::Pool> = {
+        let dex = state.dex.lock().unwrap();
+        pool_ids.iter().filter_map(|id| dex.get_pool(id).cloned()).collect()
+    };
+    for pool in &pools {
+        if let Err(e) = state.storage.save_dex_pool(pool).await {
+            tracing::warn!("⚠️  Failed to save DEX pool {} to DB: {}", pool.id, e);
+        }
+        websocket::broadcast_dex_update(
+            &state.ws_tx,
+            pool.id.clone(),
+            pool.reserve_b.ratio(pool.reserve_a),
+            pool.total_liquidity.to_f64(),
+        ).await;
+    }
+    pools
+}
+
+/// Save a DEX row and stream the pool's current candles to websocket clients
+/// as `DexCandle` messages (failures are logged, never fatal)
+pub(crate) async fn record_dex_transaction(state: &AppState, record: &DexTransactionRecord<'_>) {
+    if let Err(e) = state.storage.save_dex_transaction(record).await {
+        tracing::warn!("⚠️  Failed to save DEX transaction {} to DB: {}", record.tx_hash, e);
+        return;
+    }
+
+    let now = Utc::now().timestamp() as u64;
+    for interval in CandleInterval::ALL {
+        let open_time = interval.bucket_start(now);
+        let candle = async {
+            let buckets = state.storage.get_dex_candles(record.pool, interval, open_time, now + 1).await?;
+            let previous = state.storage.get_dex_pool_state_before(&record.pool.id, open_time).await?;
+            Ok::<_, sqlx::Error>(
+                dex_analytics::fill_candles(buckets, interval, open_time, now + 1, previous.map(|prior| prior.price())).pop(),
+            )
+        }.await;
+        match candle {
+            Ok(Some(candle)) => websocket::broadcast_dex_candle(&state.ws_tx, record.pool.id.clone(), interval, candle).await,
+            Ok(None) => {}
+            Err(e) => tracing::warn!("⚠️  Failed to load {} candle of {}: {}", interval.as_str(), record.pool.id, e),
+        }
+    }
+}
+
+/// Spend the nonce of a swap signed by `user` before it reaches the DEX, so a
+/// replay loses the race even across restarts
+pub(crate) async fn spend_swap_nonce(state: &AppState, user: &str, nonce: u64) -> Result<(), String> {
+    match state.storage.spend_dex_nonce(user, nonce).await {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(format!("Nonce {} already used (replay rejected)", nonce)),
+        Err(e) => {
+            tracing::error!(user = %user, error = %e, "Failed to persist DEX nonce");
+            Err("Could not record the swap nonce".to_string())
+        }
+    }
+}
+
+/// Batch auction mode: escrow the swap input and queue the swap for the
+/// uniform-price clearing of the next block
+pub(crate) async fn queue_batch_swap(state: &AppState, mut request: crate::dex::SwapRequest) -> Result<BatchSwap, String> {
+    // Balances hold 6 decimals, the escrow and the cleared amount must match
+    request.amount = request.amount.round_down_to(6).map_err(|e| e.to_string())?;
+    spend_swap_nonce(state, &request.user, request.nonce).await?;
+    let swap_id = format!("batch_{}", uuid::Uuid::new_v4());
+    let escrowed = state.storage.queue_dex_batch_swap(&swap_id, &request).await
+        .map_err(|e| e.to_string())?;
+    if !escrowed {
+        return Err(format!("Insufficient {} balance for {}", request.from, request.amount));
+    }
+
+    let now = Utc::now().timestamp() as u64;
+    let submitted = state.dex.lock().unwrap().submit_batch_swap(&request, swap_id.clone(), now);
+    if let Err(reason) = &submitted {
+        if let Err(e) = state.storage.reject_dex_batch_swap(&swap_id, reason).await {
+            tracing::error!(swap_id = %swap_id, error = %e, "Batch swap refused but its escrow was not refunded");
+        }
+    }
+    submitted
+}
+
+/// Clear the queued batch swaps, pay them out and record one DEX row per
+/// filled swap. Returns the results to report in the block (empty when batch
+/// mode is off or nothing was queued).
+async fn settle_dex_batch(state: &AppState) -> Vec<BatchClearing> {
+    let now = Utc::now().timestamp() as u64;
+    // Cleared on copies of the pools, which stay locked until the results are stored
+    let cleared = state.dex.lock().unwrap().clear_batch(now);
+    let settlement = match cleared {
+        Ok(settlement) => settlement,
+        Err(e) => {
+            // A batch the DEX cannot clear is refunded, not left queued
+            tracing::warn!(error = %e, "DEX batch not cleared, refunding its swaps");
+            let rejected = state.dex.lock().unwrap().reject_batch(&format!("Batch not cleared: {}", e));
+            for clearing in &rejected {
+                if let Err(e) = state.storage.settle_dex_batch_clearing(clearing).await {
+                    tracing::error!(pool_id = %clearing.pool_id, error = %e, "Failed to refund DEX batch swaps");
+                }
+            }
+            return rejected;
+        }
+    };
+    if settlement.clearings.is_empty() {
+        return Vec::new();
+    }
+
+    // One database transaction per pool: a pool whose results cannot be stored is
+    // not installed and its swaps are refunded, the other pools settle
+    let mut clearings = Vec::with_capacity(settlement.clearings.len());
+    let mut skipped = Vec::new();
+    for clearing in settlement.clearings.iter().cloned() {
+        if let Err(e) = state.storage.settle_dex_batch_clearing(&clearing).await {
+            tracing::error!(pool_id = %clearing.pool_id, error = %e, "DEX batch results not recorded, refunding the pool's swaps");
+            let reason = "Batch results could not be recorded";
+            for fill in &clearing.fills {
+                if let Err(e) = state.storage.reject_dex_batch_swap(&fill.swap_id, reason).await {
+                    // Still queued in the database: refunded at the next restart
+                    tracing::error!(swap_id = %fill.swap_id, error = %e, "Failed to refund DEX batch swap");
+                }
+            }
+            skipped.push(clearing.pool_id.clone());
+            clearings.push(clearing.into_rejected(reason));
+            continue;
+        }
+        clearings.push(clearing);
+    }
+    state.dex.lock().unwrap().install_batch(settlement, &skipped, now);
+
+    let pool_ids: Vec<String> = clearings.iter().map(|clearing| clearing.pool_id.clone()).collect();
+    for pool in sync_dex_pools(state, &pool_ids).await {
+        let fills = clearings.iter()
+            .filter(|clearing| clearing.pool_id == pool.id)
+            .flat_map(|clearing| clearing.fills.iter())
+            .filter(|fill| fill.status == BatchFillStatus::Filled);
+        for fill in fills {
+            record_dex_transaction(state, &DexTransactionRecord {
+                tx_hash: &fill.swap_id,
+                from: &fill.user,
+                to: "DEX_CONTRACT",
+                amount_in: fill.amount_in,
+                amount_out: fill.amount_out,
+                transaction_type: "batch_swap",
+                token_in: Some(&fill.token_in),
+                pool: &pool,
+            }).await;
+        }
+    }
+    clearings
+}
+
+/// Seconds between governance keeper passes (DUJYO_GOVERNANCE_KEEPER_SECS, default 30)
+fn governance_keeper_interval() -> Duration {
+    let secs = std::env::var("DUJYO_GOVERNANCE_KEEPER_SECS")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(30);
+    Duration::from_secs(secs)
+}
+
+/// Background keeper: closes the votes whose end height the chain reached
+/// (settling their deposits) and executes queued proposals once the chain
+/// passed their timelock
+async fn governance_keeper_task(state: AppState) {
+    let mut interval = time::interval(governance_keeper_interval());
+
+    loop {
+        interval.tick().await;
+
+        let height = state.blockchain.lock().unwrap().get_latest_block().height;
+        let mut changed = close_governance_votes(&state, height).await;
+        let due = state.governance.lock().unwrap().due(height);
+        for proposal in due {
+            changed.extend(execute_governance_proposal(&state, &proposal, height).await);
+        }
+        for proposal in changed {
+            if let Err(e) = state.storage.save_governance_proposal(&proposal).await {
+                tracing::error!(proposal_id = %proposal.proposal_id, error = %e, "Failed to save governance proposal");
+            }
+        }
+    }
+}
+
+/// Tally, from the votes in the chain up to its end height, every proposal the
+/// chain (at `height`) has passed, and release its deposit. Each release is
+/// stored with its proposal row in one transaction; one that does not persist
+/// is taken back out of the mempool and retried on the next pass.
+async fn close_governance_votes(state: &AppState, height: u64) -> Vec<Proposal> {
+    let (closed, settlements) = {
+        let mut governance = state.governance.lock().unwrap();
+        let mut blockchain = state.blockchain.lock().unwrap();
+
+        let closed = governance.close_voting(height, |proposal| {
+            let votes = governance::chain_votes(&blockchain.chain, &proposal.proposal_id, proposal.voting_ends_height);
+            governance::tally(proposal, &votes)
+        });
+        for proposal in &closed {
+            tracing::info!(proposal_id = %proposal.proposal_id, status = proposal.status.as_str(), "Governance vote closed");
+        }
+        let mut settlements = Vec::new();
+        for proposal in governance.settle_deposits() {
+            let settlement = governance::deposit_settlement(&proposal);
+            match blockchain.add_transaction(settlement.clone()) {
+                Ok(()) => settlements.push((proposal, blockchain.last_bundle(), settlement)),
+                Err(e) => {
+                    tracing::error!(proposal_id = %proposal.proposal_id, error = %e, "Failed to release governance deposit");
+                    governance.unsettle(&proposal.proposal_id);
+                }
+            }
+        }
+        (closed, settlements)
+    };
+
+    let mut stored = Vec::new();
+    for (proposal, bundle, settlement) in settlements {
+        let result = async {
+            let mut tx = state.storage.pool.begin().await?;
+            state.storage
+                .save_pending_bundle_atomic(bundle, TransactionPriority::Normal, std::slice::from_ref(&settlement), &mut tx)
+                .await?;
+            state.storage.save_governance_proposal_atomic(&proposal, &mut tx).await?;
+            tx.commit().await
+        }
+        .await;
+        match result {
+            Ok(()) => stored.push(proposal.proposal_id),
+            Err(e) => {
+                tracing::error!(proposal_id = %proposal.proposal_id, error = %e, "Failed to persist governance deposit release");
+                state.blockchain.lock().unwrap().remove_pending(std::slice::from_ref(&settlement));
+                state.governance.lock().unwrap().unsettle(&proposal.proposal_id);
+            }
+        }
+    }
+
+    // Closed proposals whose release did not go through are saved with their new status only
+    let governance = state.governance.lock().unwrap();
+    closed.into_iter()
+        .filter(|proposal| !stored.contains(&proposal.proposal_id))
+        .filter_map(|proposal| governance.proposal(&proposal.proposal_id).cloned())
+        .collect()
+}
+
+/// Apply a queued proposal to the live parameters and record the outcome at chain `height`
+async fn execute_governance_proposal(state: &AppState, proposal: &Proposal, height: u64) -> Option<Proposal> {
+    let mut consensus = state.cpv_consensus.lock().await;
+    let mut gas_fees = state.gas_fees.write().unwrap();
+    let mut s2e_rates = state.s2e_rates.write().unwrap();
+
+    let result = proposal.action.execute(ParameterTargets {
+        consensus: &mut consensus,
+        gas_fees: &mut gas_fees,
+        s2e_rates: &mut s2e_rates,
+    });
+    match &result {
+        Ok(()) => tracing::info!(proposal_id = %proposal.proposal_id, "Governance proposal executed"),
+        Err(e) => tracing::warn!(proposal_id = %proposal.proposal_id, error = %e, "Governance proposal failed"),
+    }
+    state.governance.lock().unwrap().record_execution(&proposal.proposal_id, height, result)
+}
+
+/// Governance proposals from storage. Executed ones are re-applied, in execution
+/// order, to the parameters before the node starts serving.
+async fn restore_governance(
+    storage: &BlockchainStorage,
+    consensus: &mut CPVConsensus,
+    gas_fees: &mut GasFeeCalculator,
+    s2e_rates: &mut S2ERates,
+) -> Governance {
+    let mut governance = Governance::new(GovernanceParams::from_env());
+    let proposals = match storage.load_governance_proposals().await {
+        Ok(proposals) => proposals,
+        Err(e) => {
+            println!("⚠️  Could not load governance proposals: {}", e);
+            return governance;
+        }
+    };
+
+    let mut executed: Vec<&Proposal> = proposals.iter()
+        .filter(|proposal| proposal.status == ProposalStatus::Executed)
+        .collect();
+    executed.sort_by_key(|proposal| proposal.executed_height);
+    for proposal in &executed {
+        let targets = ParameterTargets { consensus: &mut *consensus, gas_fees: &mut *gas_fees, s2e_rates: &mut *s2e_rates };
+        if let Err(e) = proposal.action.execute(targets) {
+            println!("⚠️  Could not re-apply governance proposal {}: {}", proposal.proposal_id, e);
+        }
+    }
+    println!("🗳️  Restored {} governance proposals ({} executed)", proposals.len(), executed.len());
+
+    for proposal in proposals {
+        governance.restore(proposal);
+    }
+    governance
+}
+
+/// Staking manager from storage, with the CPV validator contract in place and
+/// every economic validator's bond and delegated stake brought in line
+async fn restore_staking(storage: &BlockchainStorage, consensus: &mut CPVConsensus) -> StakingManager {
+    let mut staking = match storage.load_staking_state().await {
+        Ok(Some(staking)) => staking,
+        Ok(None) => StakingManager::new(),
+        Err(e) => {
+            println!("⚠️  Could not load staking state: {}", e);
+            StakingManager::new()
+        }
+    };
+
+    let (min_stake, max_stake, reward_frequency, slashing_enabled, slashing_bps) = StakingConfigs::economic_validators();
+    let created = staking.ensure_staking_contract(VALIDATOR_CONTRACT_ID, CreateStakingContractRequest {
+        name: "CPV Validators".to_string(),
+        purpose: "VALIDATORS".to_string(),
+        min_stake,
+        max_stake,
+        reward_frequency,
+        slashing_enabled,
+        slashing_bps,
+    });
+    if let Err(e) = created {
+        println!("⚠️  Could not create the validator staking contract: {}", e);
+    }
+    sync_validator_stakes(&mut staking, consensus);
+    staking
+}
+
+/// Mirror each economic validator's CPV bond in the validator contract, and the
+/// stake delegated to it back into CPV (proposer selection, fork choice and
+/// finality weigh the effective stake)
+pub(crate) fn sync_validator_stakes(staking: &mut StakingManager, consensus: &mut CPVConsensus) {
+    let bonds: Vec<(String, u64)> = consensus
+        .economic_validators
+        .values()
+        .map(|validator| (validator.address.clone(), if validator.is_active { validator.stake } else { 0 }))
+        .collect();
+    for (address, stake) in bonds {
+        if let Err(e) = staking.bond_validator(VALIDATOR_CONTRACT_ID, &address, stake) {
+            tracing::error!(validator = %address, error = %e, "Failed to bond validator stake");
+            continue;
+        }
+        let delegated = staking.delegated_stake(VALIDATOR_CONTRACT_ID, &address);
+        if let Err(e) = consensus.set_delegated_stake(&address, delegated) {
+            tracing::error!(validator = %address, error = %e, "Failed to set delegated stake");
+        }
+    }
+}
+
+/// Seconds between unbonding keeper passes (DUJYO_UNBONDING_KEEPER_SECS, default 60)
+fn unbonding_keeper_interval() -> Duration {
+    let secs = std::env::var("DUJYO_UNBONDING_KEEPER_SECS")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(60);
+    Duration::from_secs(secs)
+}
+
+/// Background keeper: credits unstaked DYO whose unbonding period has passed
+async fn unbonding_keeper_task(state: AppState) {
+    let mut interval = time::interval(unbonding_keeper_interval());
+
+    loop {
+        interval.tick().await;
+
+        let now = Utc::now().timestamp() as u64;
+        match state.storage.release_matured_unbonding(now).await {
+            Ok(released) => {
+                for entry in released {
+                    tracing::info!(entry_id = %entry.position_id, user = %entry.user, amount = %entry.payout(), "Unbonded DYO released");
+                }
+            }
+            Err(e) => tracing::error!(error = %e, "Failed to release matured unbonding entries"),
+        }
+    }
+}
+
+/// Orders loaded per keeper pass
+const ORDER_KEEPER_BATCH: i64 = 100;
+
+/// Seconds between order keeper passes (DUJYO_ORDER_KEEPER_SECS, default 5)
+fn order_keeper_interval() -> Duration {
+    let secs = std::env::var("DUJYO_ORDER_KEEPER_SECS")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(5);
+    Duration::from_secs(secs)
+}
+
+/// Background keeper: fills due limit/DCA orders against the pools and expires stale ones
+async fn order_keeper_task(state: AppState) {
+    let mut interval = time::interval(order_keeper_interval());
+
+    loop {
+        interval.tick().await;
+
+        let now = Utc::now().timestamp() as u64;
+        let orders = match state.storage.get_due_dex_orders(now, ORDER_KEEPER_BATCH).await {
+            Ok(orders) => orders,
+            Err(e) => {
+                tracing::error!(error = %e, "Failed to load due DEX orders");
+                continue;
+            }
+        };
+        for order in orders {
+            let order_id = order.order_id.clone();
+            if let Err(e) = process_dex_order(&state, order, now).await {
+                tracing::warn!(order_id = %order_id, error = %e, "DEX order not executed");
+            }
+        }
+    }
+}
+
+async fn process_dex_order(state: &AppState, mut order: Order, now: u64) -> Result<(), String> {
+    let pool_id = crate::dex::pool_id(&order.from_token, &order.to_token);
+
+    if order.is_expired(now) {
+        let closed = state.storage.close_dex_order(&order.order_id, None, OrderStatus::Expired).await
+            .map_err(|e| e.to_string())?;
+        if let Some(closed) = closed {
+            announce_order(state, &pool_id, closed.event(closed.remaining, Amount::ZERO)).await;
+        }
+        return Ok(());
+    }
+
+    let plan = {
+        let dex = state.dex.lock().unwrap();
+        order.plan_fill(&dex, now)?
+    };
+    let (amount_in, min_received) = match plan {
+        FillPlan::Wait => return Ok(()),
+        FillPlan::Skip => {
+            order.skip_slot(now);
+            return state.storage.reschedule_dex_order(&order.order_id, order.next_execution_at).await
+                .map_err(|e| e.to_string());
+        }
+        FillPlan::Execute { amount_in, min_received } => (amount_in, min_received),
+    };
+
+    // ✅ SECURITY: Take the input out of escrow first; a concurrent cancellation wins
+    let reserved = state.storage.reserve_dex_order_fill(&order.order_id, order.remaining, amount_in).await
+        .map_err(|e| e.to_string())?;
+    if !reserved {
+        return Ok(());
+    }
+
+    // The order authorizes the fill: it takes the user's next nonce in memory
+    // and must execute before the order expires
+    let swap_result = {
+        let mut dex = state.dex.lock().unwrap();
+        let nonce = dex.next_nonce(&order.user);
+        dex.execute_swap(crate::dex::SwapRequest {
+            from: order.from_token.clone(),
+            to: order.to_token.clone(),
+            amount: amount_in,
+            min_received,
+            user: order.user.clone(),
+            deadline: order.expires_at,
+            nonce,
+        })
+    };
+    let amount_out = match swap_result {
+        Ok(response) => response.amount_received.unwrap_or_default(),
+        Err(e) => {
+            state.storage.release_dex_order_fill(&order, amount_in).await.map_err(|e| e.to_string())?;
+            return Err(e);
+        }
+    };
+
+    order.apply_fill(amount_in, amount_out, now)?;
+    let fill = OrderFill {
+        fill_id: format!("fill_{}", uuid::Uuid::new_v4()),
+        order_id: order.order_id.clone(),
+        amount_in,
+        amount_out,
+        executed_at: now,
+    };
+    if let Err(e) = state.storage.record_dex_order_fill(&order, &fill).await {
+        tracing::error!(order_id = %order.order_id, error = %e, "Order swap executed but the fill was not recorded");
+        return Err(e.to_string());
+    }
+
+    for pool in sync_dex_pools(state, std::slice::from_ref(&pool_id)).await {
+        record_dex_transaction(state, &DexTransactionRecord {
+            tx_hash: &fill.fill_id,
+            from: &order.user,
+            to: "DEX_CONTRACT",
+            amount_in,
+            amount_out,
+            transaction_type: order.kind.as_str(),
+            token_in: Some(&order.from_token),
+            pool: &pool,
+        }).await;
+    }
+    announce_order(state, &pool_id, order.event(amount_in, amount_out)).await;
+    Ok(())
+}
+
+/// `DexUpdate` for an order fill, cancellation or expiry
+pub(crate) async fn announce_order(state: &AppState, pool_id: &str, event: OrderEvent) {
+    let (price, liquidity) = {
+        let dex = state.dex.lock().unwrap();
+        dex.get_pool(pool_id)
+            .map(|pool| (pool.reserve_b.ratio(pool.reserve_a), pool.total_liquidity.to_f64()))
+            .unwrap_or_default()
+    };
+    websocket::broadcast_order_update(&state.ws_tx, pool_id.to_string(), price, liquidity, event).await;
+}
+
 // Staking handlers
 async fn simple_stake_handler(
     State(state): State<AppState>,
@@ -1153,40 +2194,28 @@ async fn simple_stake_handler(
         match result {
             Some((dyo_balance, dys_balance, staked_balance)) => {
                 // Convert from micro-DYO (1_000_000 = 1 DYO) to DYO
-                let dyo = dyo_balance as f64 / 1_000_000.0;
-                let dys = dys_balance as f64 / 1_000_000.0;
-                let staked = staked_balance as f64 / 1_000_000.0;
+                let balance = TokenBalance::from_micro(dyo_balance, dys_balance, staked_balance);
                 
                 // If token_balances has 0 but legacy balance exists, use legacy balance
-                if dyo == 0.0 {
+                if balance.dyo.is_zero() {
                     let legacy_balance = state.storage.get_balance(&request.account).await.unwrap_or(0);
-                    let legacy_dyo = (legacy_balance as f64) / 100.0; // Convert centavos to DYO
-                    if legacy_dyo > 0.0 {
+                    let legacy_dyo = Amount::from_cents(legacy_balance); // Convert centavos to DYO
+                    if !legacy_dyo.is_zero() {
                         tracing::info!("Using legacy balance for {}: {} DYO", request.account, legacy_dyo);
-                        TokenBalance {
-                            dyo: legacy_dyo,
-                            dys,
-                            staked,
-                            total: legacy_dyo + dys + staked,
-                        }
+                        TokenBalance::new(legacy_dyo, balance.dys, balance.staked)
                     } else {
-                        TokenBalance { dyo, dys, staked, total: dyo + dys + staked }
+                        balance
                     }
                 } else {
-                    TokenBalance { dyo, dys, staked, total: dyo + dys + staked }
+                    balance
                 }
             },
             None => {
                 // No record in token_balances, check legacy balances
                 let legacy_balance = state.storage.get_balance(&request.account).await.unwrap_or(0);
-                let legacy_dyo = (legacy_balance as f64) / 100.0; // Convert centavos to DYO
+                let legacy_dyo = Amount::from_cents(legacy_balance); // Convert centavos to DYO
                 tracing::info!("No token_balances record, using legacy balance for {}: {} DYO", request.account, legacy_dyo);
-                TokenBalance {
-                    dyo: legacy_dyo,
-                    dys: 0.0,
-                    staked: 0.0,
-                    total: legacy_dyo,
-                }
+                TokenBalance::new(legacy_dyo, Amount::ZERO, Amount::ZERO)
             },
         }
     };
@@ -1195,14 +2224,14 @@ async fn simple_stake_handler(
     if token_balance.dyo < request.amount {
         return Ok(Json(StakeResponse {
             success: false,
-            message: format!("Insufficient balance for staking. Available: {:.2} DYO, Required: {:.2} DYO", 
+            message: format!("Insufficient balance for staking. Available: {} DYO, Required: {} DYO", 
                             token_balance.dyo, request.amount),
             tx_hash: None,
             new_balance: None,
         }));
     }
     
-    if request.amount < 1.0 {
+    if request.amount < Amount::ONE {
         return Ok(Json(StakeResponse {
             success: false,
             message: "Minimum stake is 1 DYO".to_string(),
@@ -1213,8 +2242,22 @@ async fn simple_stake_handler(
     
     // ✅ FIX: Update balance directly in database (not in-memory HashMap)
     let lock_period_days = request.lock_period_days.unwrap_or(30); // Default 30 days
-    let new_dyo_balance = token_balance.dyo - request.amount;
-    let new_staked_balance = token_balance.staked + request.amount;
+    let stake_micro = match micro_units(request.amount) {
+        Ok(micro) => micro,
+        Err(e) => {
+            return Ok(Json(StakeResponse {
+                success: false,
+                message: e,
+                tx_hash: None,
+                new_balance: None,
+            }));
+        }
+    };
+    let new_balance = TokenBalance::new(
+        token_balance.dyo.saturating_sub(request.amount),
+        token_balance.dys,
+        token_balance.staked.saturating_add(request.amount),
+    );
     
     // ✅ FIX: Create staking position with lock period
     let pool = &state.storage.pool;
@@ -1232,16 +2275,17 @@ async fn simple_stake_handler(
     )
     .bind(&position_id)
     .bind(&request.account)
-    .bind((request.amount * 1_000_000.0).round() as i64)
+    .bind(stake_micro)
     .bind(lock_period_days as i32)
     .bind(unlock_timestamp as i64)
     .execute(pool)
     .await;
     
     // ✅ FIX: Persist updated balance to database using direct SQL
-    let dyo_i64 = (new_dyo_balance * 1_000_000.0).round() as i64;
-    let dys_i64 = (token_balance.dys * 1_000_000.0).round() as i64;
-    let staked_i64 = (new_staked_balance * 1_000_000.0).round() as i64;
+    let (dyo_i64, dys_i64, staked_i64) = new_balance.to_micro().map_err(|e| {
+        tracing::error!("{}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
     
     if let Err(e) = sqlx::query(
         "INSERT INTO token_balances (address, dyo_balance, dys_balance, staked_balance, updated_at) 
@@ -1266,14 +2310,14 @@ async fn simple_stake_handler(
     
     let tx_hash = format!("STAKE_{}_{}", request.account, SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs());
     
-    tracing::info!("🏦 Staked {} DYO for user {} (new balance: {:.2} DYO, staked: {:.2} DYO)", 
-                   request.amount, request.account, new_dyo_balance, new_staked_balance);
+    tracing::info!("🏦 Staked {} DYO for user {} (new balance: {} DYO, staked: {} DYO)", 
+                   request.amount, request.account, new_balance.dyo, new_balance.staked);
     
     Ok(Json(StakeResponse {
         success: true,
         message: format!("Successfully staked {} DYO tokens", request.amount),
         tx_hash: Some(tx_hash),
-        new_balance: Some(new_dyo_balance),
+        new_balance: Some(new_balance.dyo),
     }))
 }
 
@@ -1300,28 +2344,22 @@ async fn simple_unstake_handler(
         
         match result {
             Some((dyo_balance, dys_balance, staked_balance)) => {
-                TokenBalance {
-                    dyo: dyo_balance as f64 / 1_000_000.0,
-                    dys: dys_balance as f64 / 1_000_000.0,
-                    staked: staked_balance as f64 / 1_000_000.0,
-                    total: (dyo_balance + dys_balance + staked_balance) as f64 / 1_000_000.0,
-                }
-            },
-            None => TokenBalance {
-                dyo: 0.0,
-                dys: 0.0,
-                staked: 0.0,
-                total: 0.0,
+                TokenBalance::from_micro(dyo_balance, dys_balance, staked_balance)
             },
+            None => TokenBalance::default(),
         }
     };
     
+    // Delegated DYO is held in the staked balance too; it leaves through undelegation
+    let delegated = state.staking.lock().await.delegated_by(VALIDATOR_CONTRACT_ID, &request.account);
+    let unstakable = token_balance.staked.saturating_sub(Amount::from_units(delegated));
+
     // Check if user has enough staked
-    if token_balance.staked < request.amount {
+    if unstakable < request.amount {
         return Ok(Json(StakeResponse {
             success: false,
-            message: format!("Insufficient staked balance. Available: {:.2} DYO, Required: {:.2} DYO", 
-                            token_balance.staked, request.amount),
+            message: format!("Insufficient staked balance. Available: {} DYO, Required: {} DYO", 
+                            unstakable, request.amount),
             tx_hash: None,
             new_balance: None,
         }));
@@ -1364,38 +2402,60 @@ async fn simple_unstake_handler(
         }
     }
     
-    // Update balance: unstake tokens
-    let new_dyo_balance = token_balance.dyo + request.amount;
-    let new_staked_balance = token_balance.staked - request.amount;
-    
-    let dyo_i64 = (new_dyo_balance * 1_000_000.0).round() as i64;
-    let dys_i64 = (token_balance.dys * 1_000_000.0).round() as i64;
-    let staked_i64 = (new_staked_balance * 1_000_000.0).round() as i64;
+    // Unstaked DYO waits out the unbonding period (still slashable) before
+    // the unbonding keeper credits it
+    let request_amount_micro = match micro_units(request.amount) {
+        Ok(micro) => micro,
+        Err(e) => {
+            return Ok(Json(StakeResponse {
+                success: false,
+                message: e,
+                tx_hash: None,
+                new_balance: None,
+            }));
+        }
+    };
+    let completes_at = current_timestamp + unbonding_period_secs();
+    let minimum_bond = state.cpv_consensus.lock().await.minimum_stake;
+    let (entry, remaining_bond) = match state.storage
+        .begin_unbonding(&request.account, request.amount, minimum_bond, current_timestamp, completes_at)
+        .await
+    {
+        Ok(Some(unbonding)) => unbonding,
+        Ok(None) => {
+            return Ok(Json(StakeResponse {
+                success: false,
+                message: "Insufficient staked balance".to_string(),
+                tx_hash: None,
+                new_balance: None,
+            }));
+        }
+        Err(e) => {
+            tracing::error!("Failed to start unbonding: {}", e);
+            return Ok(Json(StakeResponse {
+                success: false,
+                message: format!("Failed to start unbonding: {}", e),
+                tx_hash: None,
+                new_balance: None,
+            }));
+        }
+    };
     
-    if let Err(e) = sqlx::query(
-        "INSERT INTO token_balances (address, dyo_balance, dys_balance, staked_balance, updated_at) 
-         VALUES ($1, $2, $3, $4, NOW())
-         ON CONFLICT (address) DO UPDATE SET
-         dyo_balance = $2, dys_balance = $3, staked_balance = $4, updated_at = NOW()"
-    )
-    .bind(&request.account)
-    .bind(dyo_i64)
-    .bind(dys_i64)
-    .bind(staked_i64)
-    .execute(pool)
-    .await {
-        tracing::error!("Failed to update balance: {}", e);
-        return Ok(Json(StakeResponse {
-            success: false,
-            message: format!("Failed to update balance: {}", e),
-            tx_hash: None,
-            new_balance: None,
-        }));
+    // A validator unstaking its own bond leaves CPV and the validator contract with less at stake
+    if let Some(bond) = remaining_bond {
+        let mut staking = state.staking.lock().await;
+        let mut consensus = state.cpv_consensus.lock().await;
+        if let Some(validator) = consensus.economic_validators.get_mut(&request.account) {
+            validator.stake = bond;
+            if bond < minimum_bond {
+                validator.is_active = false;
+            }
+        }
+        sync_validator_stakes(&mut staking, &mut consensus);
     }
-    
+
     // Remove or update staking position if fully unstaked
     if let Some((position_id, position_amount)) = unlockable_amount {
-        let request_amount_micro = (request.amount * 1_000_000.0).round() as i64;
         if position_amount <= request_amount_micro {
             // Fully unstake this position
             let _ = sqlx::query("DELETE FROM staking_positions WHERE position_id = $1")
@@ -1412,24 +2472,31 @@ async fn simple_unstake_handler(
         }
     }
     
-    let tx_hash = format!("UNSTAKE_{}_{}", request.account, current_timestamp);
-    
-    tracing::info!("🏦 Unstaked {} DYO for user {} (new balance: {:.2} DYO)", 
-                   request.amount, request.account, new_dyo_balance);
+    tracing::info!("🏦 Unbonding {} DYO for user {} until {}", 
+                   request.amount, request.account, entry.completes_at);
     
     Ok(Json(StakeResponse {
         success: true,
-        message: format!("Successfully unstaked {} DYO tokens", request.amount),
-        tx_hash: Some(tx_hash),
-        new_balance: Some(new_dyo_balance),
+        message: format!(
+            "Unstaking {} DYO tokens; they are released after the unbonding period (at {})",
+            request.amount, entry.completes_at
+        ),
+        tx_hash: Some(entry.position_id),
+        new_balance: Some(token_balance.dyo),
     }))
 }
 
 // DEX handlers
 async fn execute_swap(
     State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
     Json(request): Json<SwapRequest>,
 ) -> Result<Json<SwapResponse>, StatusCode> {
+    // ✅ SECURITY: Only the owner can spend a balance on a swap
+    if claims.sub != request.user {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
     // ✅ FIX: Get balance from database (source of truth) instead of in-memory HashMap
     let token_balance = {
         let pool = &state.storage.pool;
@@ -1447,19 +2514,9 @@ async fn execute_swap(
         match result {
             Some((dyo_balance, dys_balance, staked_balance)) => {
                 // Convert from micro-DYO (1_000_000 = 1 DYO) to DYO
-                TokenBalance {
-                    dyo: dyo_balance as f64 / 1_000_000.0,
-                    dys: dys_balance as f64 / 1_000_000.0,
-                    staked: staked_balance as f64 / 1_000_000.0,
-                    total: (dyo_balance + dys_balance + staked_balance) as f64 / 1_000_000.0,
-                }
-            },
-            None => TokenBalance {
-                dyo: 0.0,
-                dys: 0.0,
-                staked: 0.0,
-                total: 0.0,
+                TokenBalance::from_micro(dyo_balance, dys_balance, staked_balance)
             },
+            None => TokenBalance::default(),
         }
     };
     
@@ -1482,7 +2539,7 @@ async fn execute_swap(
     if available_balance < request.amount {
         return Ok(Json(SwapResponse {
             success: false,
-            message: format!("Insufficient balance. Available: {:.2} {}, Required: {:.2} {}", 
+            message: format!("Insufficient balance. Available: {} {}, Required: {} {}", 
                             available_balance, request.from, request.amount, request.from),
             tx_hash: None,
             amount_received: None,
@@ -1490,15 +2547,77 @@ async fn execute_swap(
         }));
     }
     
-    // Convert to DEX types
+    // Convert to DEX types. Balances hold 6 decimals, the debit and the swapped amount must match
+    let amount_in = match request.amount.round_down_to(6) {
+        Ok(amount) => amount,
+        Err(e) => {
+            return Ok(Json(SwapResponse {
+                success: false,
+                message: e.to_string(),
+                tx_hash: None,
+                amount_received: None,
+                price_impact: None,
+            }));
+        }
+    };
     let dex_request = crate::dex::SwapRequest {
         from: request.from.clone(),
         to: request.to.clone(),
-        amount: request.amount,
+        amount: amount_in,
         min_received: request.min_received,
         user: request.user.clone(),
+        deadline: request.deadline,
+        nonce: request.nonce,
     };
     
+    // ✅ MEV: In batch mode the swap waits for the uniform-price auction of the next block
+    if batch_auction_enabled() {
+        let response = match queue_batch_swap(&state, dex_request).await {
+            Ok(swap) => SwapResponse {
+                success: true,
+                message: "Swap queued for the batch auction of the next block".to_string(),
+                tx_hash: Some(swap.swap_id),
+                amount_received: None,
+                price_impact: None,
+            },
+            Err(e) => SwapResponse {
+                success: false,
+                message: e,
+                tx_hash: None,
+                amount_received: None,
+                price_impact: None,
+            },
+        };
+        return Ok(Json(response));
+    }
+    
+    // ✅ SECURITY: Spend the nonce first (compare-and-set) so concurrent replays lose the race
+    if let Err(e) = spend_swap_nonce(&state, &request.user, request.nonce).await {
+        return Ok(Json(SwapResponse {
+            success: false,
+            message: e,
+            tx_hash: None,
+            amount_received: None,
+            price_impact: None,
+        }));
+    }
+
+    // ✅ SECURITY: Debit the input against the stored balance before the pools move
+    let debit_error = match state.storage.debit_dex_balance(&request.user, &request.from, amount_in).await {
+        Ok(true) => None,
+        Ok(false) => Some(format!("Insufficient {} balance for {}", request.from, amount_in)),
+        Err(e) => Some(e.to_string()),
+    };
+    if let Some(message) = debit_error {
+        return Ok(Json(SwapResponse {
+            success: false,
+            message,
+            tx_hash: None,
+            amount_received: None,
+            price_impact: None,
+        }));
+    }
+
     // Execute swap in DEX (release lock immediately)
     let swap_result = {
         let mut dex = state.dex.lock().unwrap();
@@ -1507,68 +2626,33 @@ async fn execute_swap(
     
     match swap_result {
         Ok(swap_response) => {
-            // ✅ FIX: Update balance directly in database (not in-memory HashMap)
-            let amount_received = swap_response.amount_received.unwrap_or(0.0);
-            
-            // Calculate new balances
-            let mut new_balance = token_balance.clone();
-            if request.from == "DYO" || request.from == "XWV" {
-                new_balance.dyo -= request.amount;
-            } else if request.from == "DYS" || request.from == "USXWV" {
-                new_balance.dys -= request.amount;
-            }
-            
-            if request.to == "DYO" || request.to == "XWV" {
-                new_balance.dyo += amount_received;
-            } else if request.to == "DYS" || request.to == "USXWV" {
-                new_balance.dys += amount_received;
+            // ✅ FIX: Credit the output on top of the stored balance (not an absolute write-back)
+            let amount_received = swap_response.amount_received.unwrap_or(Amount::ZERO);
+            if let Err(e) = state.storage.credit_dex_balance(&request.user, &request.to, amount_received).await {
+                tracing::error!("⚠️  Failed to update balance in DB: {}", e);
+                return Ok(Json(SwapResponse {
+                    success: false,
+                    message: format!("Swap executed but failed to update balance: {}", e),
+                    tx_hash: swap_response.tx_hash.clone(),
+                    amount_received: Some(amount_received),
+                    price_impact: swap_response.price_impact,
+                }));
             }
-            
-            new_balance.total = new_balance.dyo + new_balance.dys + new_balance.staked;
 
             // Persist DEX transaction to PostgreSQL
             if let Some(tx_hash) = &swap_response.tx_hash {
-                let pool_id = format!("{}_{}", request.from, request.to);
-                if let Err(e) = state.storage.save_dex_transaction(
-                    tx_hash,
-                    &request.user,
-                    "DEX_CONTRACT",
-                    request.amount as i64,
-                    amount_received as i64,
-                    &pool_id,
-                    "swap"
-                ).await {
-                    tracing::warn!("⚠️  Failed to save DEX transaction to DB: {}", e);
-                } else {
-                    tracing::info!("✅ DEX transaction saved to DB: {}", tx_hash);
-                }
-
-                // ✅ FIX: Update balances in PostgreSQL using direct SQL
-                let pool = &state.storage.pool;
-                let dyo_i64 = (new_balance.dyo * 1_000_000.0).round() as i64;
-                let dys_i64 = (new_balance.dys * 1_000_000.0).round() as i64;
-                let staked_i64 = (new_balance.staked * 1_000_000.0).round() as i64;
-                
-                if let Err(e) = sqlx::query(
-                    "INSERT INTO token_balances (address, dyo_balance, dys_balance, staked_balance, updated_at) 
-                     VALUES ($1, $2, $3, $4, NOW())
-                     ON CONFLICT (address) DO UPDATE SET
-                     dyo_balance = $2, dys_balance = $3, staked_balance = $4, updated_at = NOW()"
-                )
-                .bind(&request.user)
-                .bind(dyo_i64)
-                .bind(dys_i64)
-                .bind(staked_i64)
-                .execute(pool)
-                .await {
-                    tracing::error!("⚠️  Failed to update balance in DB: {}", e);
-                    return Ok(Json(SwapResponse {
-                        success: false,
-                        message: format!("Swap executed but failed to update balance: {}", e),
-                        tx_hash: swap_response.tx_hash.clone(),
-                        amount_received: Some(amount_received),
-                        price_impact: swap_response.price_impact,
-                    }));
+                let pool_id = crate::dex::pool_id(&request.from, &request.to);
+                for pool in sync_dex_pools(&state, std::slice::from_ref(&pool_id)).await {
+                    record_dex_transaction(&state, &DexTransactionRecord {
+                        tx_hash,
+                        from: &request.user,
+                        to: "DEX_CONTRACT",
+                        amount_in,
+                        amount_out: amount_received,
+                        transaction_type: "swap",
+                        token_in: Some(&request.from),
+                        pool: &pool,
+                    }).await;
                 }
             }
             
@@ -1583,6 +2667,10 @@ async fn execute_swap(
             Ok(Json(server_response))
         }
         Err(e) => {
+            // The pools did not move: give the debited input back
+            if let Err(refund_error) = state.storage.credit_dex_balance(&request.user, &request.from, amount_in).await {
+                tracing::error!(user = %request.user, error = %refund_error, "Failed swap but its input was not refunded");
+            }
             Ok(Json(SwapResponse {
                 success: false,
                 message: e,
@@ -1596,8 +2684,14 @@ async fn execute_swap(
 
 async fn add_liquidity(
     State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
     Json(request): Json<LiquidityRequest>,
 ) -> Result<Json<LiquidityResponse>, StatusCode> {
+    // ✅ SECURITY: Only the owner can deposit its balances into a pool
+    if claims.sub != request.user {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
     // Check if user has sufficient balances for both tokens
     if request.amounts.len() != 2 {
         return Ok(Json(LiquidityResponse {
@@ -1608,35 +2702,19 @@ async fn add_liquidity(
         }));
     }
     
-    let amount_a = request.amounts[0];
-    let amount_b = request.amounts[1];
-    
-    // Check balances (release lock immediately)
-    let (has_balance_a, has_balance_b) = {
-        let token = state.token.lock().unwrap();
-        (token.has_balance(&request.user, amount_a), token.has_balance(&request.user, amount_b))
+    // Balances hold 6 decimals, the debits and the deposited amounts must match
+    let (amount_a, amount_b) = match (request.amounts[0].round_down_to(6), request.amounts[1].round_down_to(6)) {
+        (Ok(amount_a), Ok(amount_b)) => (amount_a, amount_b),
+        (Err(e), _) | (_, Err(e)) => {
+            return Ok(Json(LiquidityResponse {
+                success: false,
+                message: e.to_string(),
+                tx_hash: None,
+                lp_tokens_minted: None,
+            }));
+        }
     };
     
-    // For simplicity, we'll assume the pool uses DUJYO and USDC
-    // In a real implementation, you'd get the token types from the pool
-    if !has_balance_a {
-        return Ok(Json(LiquidityResponse {
-            success: false,
-            message: "Insufficient balance for token A".to_string(),
-            tx_hash: None,
-            lp_tokens_minted: None,
-        }));
-    }
-    
-    if !has_balance_b {
-        return Ok(Json(LiquidityResponse {
-            success: false,
-            message: "Insufficient balance for token B".to_string(),
-            tx_hash: None,
-            lp_tokens_minted: None,
-        }));
-    }
-    
     // Convert to DEX types
     // Get pool info first to extract token_a and token_b
     let pool_info = match {
@@ -1654,91 +2732,86 @@ async fn add_liquidity(
         }
     };
     
+    // ✅ SECURITY: Debit the deposits against the stored balances before the pools
+    // move. The DYO side is paid on the ledger, which checks it when the leg is queued.
+    let mut debited: Vec<(&str, Amount)> = Vec::new();
+    for (symbol, amount, side) in [(pool_info.token_a.as_str(), amount_a, "A"), (pool_info.token_b.as_str(), amount_b, "B")] {
+        if symbol == NATIVE_TOKEN {
+            continue;
+        }
+        let debit_error = match state.storage.debit_dex_balance(&request.user, symbol, amount).await {
+            Ok(true) => None,
+            Ok(false) => Some(format!("Insufficient balance for token {}", side)),
+            Err(e) => Some(format!("Failed to deduct token {}: {}", side, e)),
+        };
+        if let Some(message) = debit_error {
+            refund_dex_balances(&state, &request.user, &debited).await;
+            return Ok(Json(LiquidityResponse {
+                success: false,
+                message,
+                tx_hash: None,
+                lp_tokens_minted: None,
+            }));
+        }
+        debited.push((symbol, amount));
+    }
+    
     let dex_request = crate::dex::LiquidityRequest {
         token_a: pool_info.token_a.clone(),
         token_b: pool_info.token_b.clone(),
-        amount_a: request.amounts.get(0).copied().unwrap_or(0.0),
-        amount_b: request.amounts.get(1).copied().unwrap_or(0.0),
+        amount_a,
+        amount_b,
         user: request.user.clone(),
     };
     
-    // Execute liquidity addition in DEX (release lock immediately)
-    let liquidity_result = {
-        let mut dex = state.dex.lock().unwrap();
-        dex.add_liquidity(dex_request)
-    };
+    // Execute liquidity addition in DEX together with its DYO leg on the ledger
+    let liquidity_result = change_liquidity(&state, &request.pool_id, &request.user, |dex| dex.add_liquidity(dex_request));
     
     match liquidity_result {
-        Ok(liquidity_response) => {
-            // Deduct tokens from user (release lock immediately)
-            let transfer_a_result = {
-                let mut token = state.token.lock().unwrap();
-                token.transfer(&request.user, "DEX_CONTRACT", amount_a, "")
-            };
-            
-            if let Err(e) = transfer_a_result {
-                return Ok(Json(LiquidityResponse {
-                    success: false,
-                    message: format!("Failed to deduct token A: {}", e),
-                    tx_hash: None,
-                    lp_tokens_minted: None,
-                }));
-            }
-            
-            let transfer_b_result = {
-                let mut token = state.token.lock().unwrap();
-                token.transfer(&request.user, "DEX_CONTRACT", amount_b, "")
-            };
-            
-            if let Err(e) = transfer_b_result {
-                return Ok(Json(LiquidityResponse {
-                    success: false,
-                    message: format!("Failed to deduct token B: {}", e),
-                    tx_hash: None,
-                    lp_tokens_minted: None,
-                }));
-            }
+        Ok((liquidity_response, ledger_leg)) => {
+            persist_liquidity_leg(&state, ledger_leg).await;
+
+            // Only the amounts matching the pool ratio are taken: refund the rest
+            let amount_a = liquidity_response.amount_a.unwrap_or(amount_a);
+            let amount_b = liquidity_response.amount_b.unwrap_or(amount_b);
+            let unused: Vec<(&str, Amount)> = debited
+                .iter()
+                .map(|&(symbol, amount)| {
+                    let taken = if symbol == pool_info.token_a { amount_a } else { amount_b };
+                    (symbol, amount.saturating_sub(taken))
+                })
+                .collect();
+            refund_dex_balances(&state, &request.user, &unused).await;
 
             // Persist liquidity transaction to PostgreSQL
             if let Some(tx_hash) = &liquidity_response.tx_hash {
-                if let Err(e) = state.storage.save_dex_transaction(
-                    tx_hash,
-                    &request.user,
-                    "DEX_CONTRACT",
-                    (amount_a + amount_b) as i64,
-                    0, // No output amount for liquidity
-                    &request.pool_id,
-                    "liquidity_add"
-                ).await {
-                    println!("⚠️  Failed to save liquidity transaction to DB: {}", e);
-                } else {
-                    println!("✅ Liquidity transaction saved to DB: {}", tx_hash);
+                for pool in sync_dex_pools(&state, std::slice::from_ref(&request.pool_id)).await {
+                    record_dex_transaction(&state, &DexTransactionRecord {
+                        tx_hash,
+                        from: &request.user,
+                        to: "DEX_CONTRACT",
+                        amount_in: amount_a.saturating_add(amount_b),
+                        amount_out: Amount::ZERO, // No output amount for liquidity
+                        transaction_type: "liquidity_add",
+                        token_in: None,
+                        pool: &pool,
+                    }).await;
                 }
 
-                // Save liquidity position
-                if let Some(lp_tokens) = liquidity_response.lp_tokens_minted {
+                // Save liquidity position (the provider's LP balance after this deposit)
+                if let Some(lp_balance) = liquidity_response.lp_balance {
                     let position_id = format!("{}_{}", request.user, request.pool_id);
                     if let Err(e) = state.storage.save_liquidity_position(
                         &position_id,
                         &request.user,
                         &request.pool_id,
-                        lp_tokens as i64
+                        lp_balance
                     ).await {
                         println!("Failed to save liquidity position to DB: {}", e);
                     } else {
                         println!("Liquidity position saved to DB: {}", position_id);
                     }
                 }
-
-                // Update balances in PostgreSQL
-                let current_balance = {
-                    let token = state.token.lock().unwrap();
-                    token.balance_of(&request.user) as u64
-                };
-                
-                if let Err(e) = state.storage.update_balance(&request.user, current_balance).await {
-                    println!("⚠️  Failed to update balance in DB: {}", e);
-                }
             }
             
             // Convert DEX response to server response
@@ -1751,6 +2824,8 @@ async fn add_liquidity(
             Ok(Json(server_response))
         }
         Err(e) => {
+            // The pools did not move: give the debited deposits back
+            refund_dex_balances(&state, &request.user, &debited).await;
             Ok(Json(LiquidityResponse {
                 success: false,
                 message: e,
@@ -1761,6 +2836,126 @@ async fn add_liquidity(
     }
 }
 
+async fn remove_liquidity(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(request): Json<RemoveLiquidityRequest>,
+) -> Result<Json<RemoveLiquidityResponse>, StatusCode> {
+    // ✅ SECURITY: Only the owner can burn an LP position
+    if claims.sub != request.user {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let (min_amount_a, min_amount_b) = match request.min_amounts.as_deref() {
+        None => (Amount::ZERO, Amount::ZERO),
+        Some([min_a, min_b]) => (*min_a, *min_b),
+        Some(_) => {
+            return Ok(Json(RemoveLiquidityResponse {
+                success: false,
+                message: "Must provide exactly 2 minimum amounts".to_string(),
+                tx_hash: None,
+                lp_tokens_burned: None,
+                amounts: None,
+            }));
+        }
+    };
+
+    // Execute liquidity removal in DEX together with its DYO leg on the ledger
+    let Some(pool) = state.dex.lock().unwrap().pools.get(&request.pool_id).cloned() else {
+        return Ok(Json(RemoveLiquidityResponse {
+            success: false,
+            message: "Pool not found".to_string(),
+            tx_hash: None,
+            lp_tokens_burned: None,
+            amounts: None,
+        }));
+    };
+    let removal_result = change_liquidity(&state, &request.pool_id, &request.user, |dex| {
+        dex.remove_liquidity(crate::dex::RemoveLiquidityRequest {
+            token_a: pool.token_a.clone(),
+            token_b: pool.token_b.clone(),
+            lp_tokens: request.lp_tokens,
+            min_amount_a,
+            min_amount_b,
+            user: request.user.clone(),
+        })
+    });
+
+    let liquidity_response = match removal_result {
+        Ok((response, ledger_leg)) => {
+            persist_liquidity_leg(&state, ledger_leg).await;
+            response
+        }
+        Err(e) => {
+            return Ok(Json(RemoveLiquidityResponse {
+                success: false,
+                message: e,
+                tx_hash: None,
+                lp_tokens_burned: None,
+                amounts: None,
+            }));
+        }
+    };
+    let amount_a = liquidity_response.amount_a.unwrap_or(Amount::ZERO);
+    let amount_b = liquidity_response.amount_b.unwrap_or(Amount::ZERO);
+
+    // Return the other reserve to the provider's stored balance (the DYO side left on the ledger)
+    let mut transfer_result = Ok(());
+    for (symbol, amount) in [(&pool.token_a, amount_a), (&pool.token_b, amount_b)] {
+        if symbol.as_str() != NATIVE_TOKEN && !amount.is_zero() {
+            transfer_result = state.storage.credit_dex_balance(&request.user, symbol, amount).await;
+            if transfer_result.is_err() {
+                break;
+            }
+        }
+    }
+
+    if let Err(e) = transfer_result {
+        return Ok(Json(RemoveLiquidityResponse {
+            success: false,
+            message: format!("Failed to return pool tokens: {}", e),
+            tx_hash: liquidity_response.tx_hash,
+            lp_tokens_burned: liquidity_response.lp_tokens_burned,
+            amounts: None,
+        }));
+    }
+
+    // Persist liquidity transaction to PostgreSQL
+    if let Some(tx_hash) = &liquidity_response.tx_hash {
+        for pool in sync_dex_pools(&state, std::slice::from_ref(&request.pool_id)).await {
+            record_dex_transaction(&state, &DexTransactionRecord {
+                tx_hash,
+                from: "DEX_CONTRACT",
+                to: &request.user,
+                amount_in: request.lp_tokens,
+                amount_out: amount_a.saturating_add(amount_b),
+                transaction_type: "liquidity_remove",
+                token_in: None,
+                pool: &pool,
+            }).await;
+        }
+
+        // Save liquidity position (remaining LP balance)
+        let position_id = format!("{}_{}", request.user, request.pool_id);
+        if let Err(e) = state.storage.save_liquidity_position(
+            &position_id,
+            &request.user,
+            &request.pool_id,
+            liquidity_response.lp_balance.unwrap_or(Amount::ZERO)
+        ).await {
+            println!("Failed to save liquidity position to DB: {}", e);
+        }
+    }
+
+    Ok(Json(RemoveLiquidityResponse {
+        success: liquidity_response.success,
+        message: liquidity_response.message,
+        tx_hash: liquidity_response.tx_hash,
+        lp_tokens_burned: liquidity_response.lp_tokens_burned,
+        amounts: Some(vec![amount_a, amount_b]),
+    }))
+}
+
 // Wallet handlers
 async fn connect_wallet(
     Json(request): Json<ConnectWalletRequest>,
@@ -2071,12 +3266,15 @@ pub fn create_router(state: AppState) -> Router {
         // Other public routes
         .merge(health::health_routes()) // ✅ Health check routes (public) - MOVED HERE
         .route("/blocks", get(get_blocks))
+        .route("/blocks/:height/tx/:hash/proof", get(get_transaction_proof)) // ✅ Merkle inclusion proof for light clients
         .route("/balance/:address", get(get_balance))
         .route("/balance-detail/:address", get(get_balance_detail))
         .route("/tokens/:address", get(get_tokens_by_owner))
         .route("/transactions/:address", get(get_transaction_history))
         .route("/pool/:id", get(get_pool))
-        .route("/ws", get(websocket_handler))
+        .route("/account/:address/nonce", get(get_account_nonce)) // ✅ SECURITY: Nonce for signed transactions
+        .route("/ws", get(websocket::ws_handler))
+        .route("/p2p", get(p2p_handler)) // Node-to-node sync protocol
         .route("/login", post(login_handler))
         .route("/register", post(crate::auth::register_handler))
         .route("/api/v1/auth/refresh", post(crate::auth::refresh_token_handler)) // ✅ Refresh token endpoint
@@ -2093,17 +3291,23 @@ pub fn create_router(state: AppState) -> Router {
         .nest("/api/v1/s2e", s2e_config::s2e_config_routes()) // ✅ S2E Configuration endpoint (PUBLIC - no auth required)
         .nest("/api/v1/s2e", s2e_dashboard::s2e_dashboard_routes()) // ✅ S2E Dashboard endpoint (PUBLIC - no auth required)
         .nest("/api/v1/s2e", s2e_user::s2e_user_routes()) // ✅ S2E User stats endpoint (PUBLIC - no auth required)
-        .nest("/api/v1/monitoring", monitoring::monitoring_routes()); // ✅ Monitoring and health check (PUBLIC)
+        .nest("/api/v1/monitoring", monitoring::monitoring_routes()) // ✅ Monitoring and health check (PUBLIC)
+        .route("/api/v1/gas/estimate", get(gas::estimate_gas_fees)) // Gas fee tiers at the current congestion (PUBLIC)
+        .route("/api/v1/consensus/stats", get(validator_registration::get_consensus_stats_public)) // ✅ CPV: Consensus stats (PUBLIC)
+        .route("/api/v1/consensus/evidence/:address", get(validator_registration::get_validator_evidence)); // ✅ SECURITY: Slashing evidence audit (PUBLIC)
     
     // Protected routes (require JWT authentication)
     // IMPORTANT: Apply middleware AFTER nesting routes so Axum can find them first
     let protected_routes = Router::new()
         .route("/transaction", post(submit_transaction))
+        .route("/account/key", post(register_account_key)) // ✅ SECURITY: Bind ed25519 key to account
         .route("/mint", post(mint_tokens))
         .route("/swap", post(execute_swap))
         .route("/stake", post(simple_stake_handler))
         .route("/unstake", post(simple_unstake_handler))
         .route("/liquidity/add", post(add_liquidity))
+        .route("/liquidity/remove", post(remove_liquidity))
+        .nest("/api/v1/consensus", validator_registration::validator_registration_routes()) // ✅ CPV: Validator registration
         // Stream-earn is handled by stream_earn_routes
         .nest("/api/v1/user", user::user_routes()) // ✅ User routes (become-artist, get type)
         .nest("/api/v1/onboarding", onboarding::onboarding_routes()) // ✅ ONBOARDING EXTENSION: Onboarding routes
@@ -2135,6 +3339,10 @@ pub fn create_router(state: AppState) -> Router {
         .nest("/api/v1/achievements", achievements::achievement_routes()) // ✅ Achievements routes
         .nest("/api/v1/trending", trending::trending_routes()) // ✅ Trending routes
         .nest("/api/v1/dex", dex::dex_routes()) // ✅ DEX routes
+        .nest("/api/v1/gas", gas::gas_routes()) // ✅ Gas auto-swap settings
+        .nest("/api/v1/governance", crate::routes::governance::governance_routes()) // ✅ Governance proposals and votes
+        .nest("/api/v1/staking", crate::routes::staking::staking_routes()) // ✅ Unbonding queue, validator delegations, redelegations and commissions
+        .nest("/api/v1/allowances", crate::routes::allowances::allowance_routes()) // ✅ DYO allowances and relayed permits
         .nest("/api/v1/nfts", nfts::nft_routes()) // ✅ NFT routes
         .nest("/api/v1/stripe", crate::routes::stripe::stripe_routes()) // ✅ Stripe (test) routes
         .nest("/api/v1/payments", crate::routes::payout::payout_routes()); // ✅ Simple payout route (MVP)
@@ -2289,10 +3497,10 @@ pub async fn start_server() -> Result<(), Box<dyn std::error::Error>> {
     storage.init_tables().await?;
     println!("✅ Database tables initialized");
     
-    // Load blockchain from database or create new one
-    let blockchain = match storage.load_blockchain().await {
+    // Restore blockchain (state snapshot + block replay) or create new one
+    let blockchain = match storage.restore_blockchain().await {
         Ok(loaded_blockchain) => {
-            println!("📚 Loaded blockchain from database with {} blocks", loaded_blockchain.chain.len());
+            println!("📚 Restored blockchain from snapshot + replay with {} blocks", loaded_blockchain.chain.len());
             Arc::new(Mutex::new(loaded_blockchain))
         }
         Err(e) => {
@@ -2303,7 +3511,32 @@ pub async fn start_server() -> Result<(), Box<dyn std::error::Error>> {
     };
     
     let token = Arc::new(Mutex::new(Token::new()));
-    let dex = Arc::new(Mutex::new(DEX::new()));
+    let mut dex = SecuredDEX::new();
+    // Restore DEX pools (rows migrated by 037_secured_dex.sql); a pool that
+    // fails the secured engine checks is skipped, never half-loaded
+    match storage.load_dex_pools().await {
+        Ok(pools) => {
+            for pool in pools {
+                let id = pool.id.clone();
+                match dex.restore_pool(pool) {
+                    Ok(()) => println!("💱 Restored DEX pool {}", id),
+                    Err(e) => println!("⚠️  Skipping DEX pool {}: {}", id, e),
+                }
+            }
+        }
+        Err(e) => println!("⚠️  Could not load DEX pools: {}", e),
+    }
+    match storage.load_dex_nonces().await {
+        Ok(spent) => dex.restore_nonces(&spent),
+        Err(e) => println!("⚠️  Could not load DEX nonces: {}", e),
+    }
+    // Batch swaps queued before a restart are refunded (the batch lives in memory)
+    match storage.refund_queued_dex_batch_swaps().await {
+        Ok(0) => {}
+        Ok(refunded) => println!("↩️  Refunded {} DEX batch swaps queued before the restart", refunded),
+        Err(e) => println!("⚠️  Could not refund queued DEX batch swaps: {}", e),
+    }
+    let dex = Arc::new(Mutex::new(dex));
     let websocket_clients = Arc::new(Mutex::new(Vec::new()));
     
     // ✅ FIX: Set JWT_SECRET if not present (for development)
@@ -2331,6 +3564,20 @@ pub async fn start_server() -> Result<(), Box<dyn std::error::Error>> {
         }
     };
     
+    // ✅ CPV: Consensus with database pool (validators, reputation, slashing)
+    let mut cpv_consensus = CPVConsensus::new();
+    cpv_consensus.set_db_pool(storage.pool.clone());
+    if let Err(e) = cpv_consensus.load_validators_from_db(&storage.pool).await {
+        println!("⚠️  Could not load CPV validators: {}", e);
+    }
+    // Governance: executed proposals set the CPV lambdas, gas fee configs and S2E rates
+    let mut gas_fees = GasFeeCalculator::new();
+    let mut s2e_rates = S2ERates::default();
+    let governance = restore_governance(&storage, &mut cpv_consensus, &mut gas_fees, &mut s2e_rates).await;
+    let staking = restore_staking(&storage, &mut cpv_consensus).await;
+    let cpv_consensus = Arc::new(TokioMutex::new(cpv_consensus));
+    let proposer_keys = Arc::new(ProposerKeyring::from_env());
+    
     let state = AppState {
         blockchain: blockchain.clone(),
         token: token.clone(),
@@ -2339,8 +3586,45 @@ pub async fn start_server() -> Result<(), Box<dyn std::error::Error>> {
         storage: storage.clone(),
         jwt_config: jwt_config.clone(),
         redis_pool, // ✅ MVP-CRITICAL: Redis pool for rate limiting
+        cpv_consensus,
+        proposer_keys,
+        peer_network: Arc::new(PeerNetwork::from_env()),
+        ws_tx: broadcast::channel(256).0,
+        finality: Arc::new(Mutex::new(FinalityGadget::from_env(chain_id()))),
+        evidence_pool: Arc::new(Mutex::new(EvidencePool::new())),
+        treasury: Arc::new(restore_treasury(&storage).await),
+        governance: Arc::new(Mutex::new(governance)),
+        gas_fees: Arc::new(RwLock::new(gas_fees)),
+        s2e_rates: Arc::new(RwLock::new(s2e_rates)),
+        staking: Arc::new(TokioMutex::new(staking)),
     };
     
+    // Connect to configured peers (DUJYO_PEERS) and sync the chain
+    for peer in PeerNetwork::configured_peers() {
+        let state_for_peer = state.clone();
+        tokio::spawn(async move {
+            peer_network::connect_to_peer(state_for_peer, peer).await;
+        });
+    }
+    
+    // Start the DEX order keeper (limit orders and DCA swaps)
+    let keeper_state = state.clone();
+    tokio::spawn(async move {
+        order_keeper_task(keeper_state).await;
+    });
+
+    // Start the governance keeper (vote closing and timelocked execution)
+    let governance_state = state.clone();
+    tokio::spawn(async move {
+        governance_keeper_task(governance_state).await;
+    });
+
+    // Start the unbonding keeper (releases unstaked DYO after the unbonding period)
+    let unbonding_state = state.clone();
+    tokio::spawn(async move {
+        unbonding_keeper_task(unbonding_state).await;
+    });
+
     // Start block production task
     let state_for_task = state.clone();
     tokio::spawn(async move {
@@ -2365,7 +3649,9 @@ pub async fn start_server() -> Result<(), Box<dyn std::error::Error>> {
     println!("   GET  /pool/{{id}} - Get pool information");
     println!("   POST /swap - Execute token swap (JWT protected)");
     println!("   POST /liquidity/add - Add liquidity (JWT protected)");
+    println!("   POST /liquidity/remove - Burn LP shares for both reserves (JWT protected)");
     println!("   WS   /ws - WebSocket for real-time updates");
+    println!("   WS   /p2p - Node-to-node sync");
     println!("Block production: every 10 seconds");
     
     axum::serve(listener, app).await?;
@@ -2,11 +2,37 @@ use sqlx::{PgPool, Row, FromRow};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use chrono::{DateTime, Utc};
-use crate::blockchain::blockchain::{Blockchain, Block, Transaction};
+use crate::blockchain::blockchain::{Blockchain, Block, PendingMeta, Transaction};
+use crate::blockchain::gas_fees::{AutoSwapSettings, FeeSplit};
+use crate::blockchain::ledger::{pool_account, TxKind};
+use crate::blockchain::real_blockchain::{TokenBalance, UnbondingEntry};
+use crate::blockchain::staking_rewards::StakingManager;
+use crate::blockchain::mempool::TransactionPriority;
+use crate::blockchain::state_store::{self, MempoolSnapshot, StateCommit, StateSnapshot, SNAPSHOTS_TO_KEEP};
+use crate::consensus::evidence::DoubleSignEvidence;
+use crate::consensus::finality::Attestation;
+use crate::dex::batch_auction::{BatchClearing, BatchFillStatus};
+use crate::dex::analytics::{Candle, CandleInterval, PoolState, VolumeTotals};
+use crate::dex::orders::{Order, OrderFill, OrderKind, OrderStatus};
+use crate::governance::{BondedStakes, Proposal};
+use crate::utils::amount::Amount;
 
 // Export r2_storage submodule
 pub mod r2_storage;
 
+// ✅ SECURITY: Stored double-signing evidence (audit trail for slashing)
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DbSlashingEvidence {
+    pub evidence_id: String,
+    pub validator_address: String,
+    pub height: i64,
+    pub kind: String,
+    pub evidence: serde_json::Value,
+    pub submitted_by: String,
+    pub slash_amount: i64,
+    pub created_at: DateTime<Utc>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct DbBlock {
     pub height: i64,
@@ -27,6 +53,9 @@ pub struct DbTransaction {
     pub status: String,
     pub block_height: Option<i64>,
     pub created_at: DateTime<Utc>,
+    pub transaction_type: Option<String>, // TxKind name ("transfer", "swap", "stake", ...)
+    pub nft_id: Option<String>,
+    pub kind: Option<serde_json::Value>, // Full TxKind (NULL for rows written before it existed)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
@@ -48,6 +77,20 @@ pub struct DbDexPool {
     pub updated_at: DateTime<Utc>,
 }
 
+/// A DEX row of `transactions`: one pool crossed by a swap, a liquidity change
+/// or an order fill, with the pool state it left behind
+pub struct DexTransactionRecord<'a> {
+    pub tx_hash: &'a str,
+    pub from: &'a str,
+    pub to: &'a str,
+    pub amount_in: Amount,
+    pub amount_out: Amount,
+    pub transaction_type: &'a str,
+    /// Input token of a swap (None for liquidity changes)
+    pub token_in: Option<&'a str>,
+    pub pool: &'a crate::dex::Pool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct DbDexLiquidityPosition {
     pub position_id: String,
@@ -61,12 +104,19 @@ pub struct DbDexLiquidityPosition {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct S2EPool {
     pub month_year: String,
-    pub total_amount: f64,
-    pub remaining_amount: f64,
-    pub artist_pool: f64,
-    pub listener_pool: f64,
-    pub artist_spent: f64,
-    pub listener_spent: f64,
+    pub total_amount: Amount,
+    pub remaining_amount: Amount,
+    pub artist_pool: Amount,
+    pub listener_pool: Amount,
+    pub artist_spent: Amount,
+    pub listener_spent: Amount,
+}
+
+impl S2EPool {
+    /// Share of the monthly pool still available, in percent (display only)
+    pub fn remaining_percent(&self) -> f64 {
+        self.remaining_amount.ratio(self.total_amount) * 100.0
+    }
 }
 
 pub struct BlockchainStorage {
@@ -130,6 +180,149 @@ impl BlockchainStorage {
         .execute(&self.pool)
         .await?;
 
+        // ✅ SECURITY: Public keys bound to accounts (ed25519, hex)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS account_keys (
+                address VARCHAR(255) PRIMARY KEY,
+                public_key VARCHAR(64) UNIQUE NOT NULL,
+                registered_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // ✅ SECURITY: Last accepted nonce per account (replay protection)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS account_nonces (
+                address VARCHAR(255) PRIMARY KEY,
+                nonce BIGINT NOT NULL DEFAULT 0,
+                updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // Fork choice: non-canonical blocks kept for possible reorganizations
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS side_blocks (
+                hash VARCHAR(255) PRIMARY KEY,
+                height BIGINT NOT NULL,
+                prev_hash VARCHAR(255) NOT NULL,
+                timestamp TIMESTAMPTZ NOT NULL,
+                tx_count INTEGER NOT NULL DEFAULT 0,
+                data JSONB NOT NULL
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // ✅ CPV: Validator attestations (one vote per validator and height)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS block_attestations (
+                validator VARCHAR(255) NOT NULL,
+                height BIGINT NOT NULL,
+                block_hash VARCHAR(255) NOT NULL,
+                public_key VARCHAR(64) NOT NULL,
+                signature VARCHAR(128) NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                PRIMARY KEY (validator, height)
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // ✅ CPV: Finalized checkpoint (single row)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS chain_finality (
+                id SMALLINT PRIMARY KEY DEFAULT 1 CHECK (id = 1),
+                finalized_height BIGINT NOT NULL,
+                finalized_hash VARCHAR(255) NOT NULL,
+                updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // ✅ SECURITY: Double-signing evidence (one row per offence)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS slashing_evidence (
+                evidence_id VARCHAR(64) PRIMARY KEY,
+                validator_address VARCHAR(255) NOT NULL,
+                height BIGINT NOT NULL,
+                kind VARCHAR(20) NOT NULL,
+                evidence JSONB NOT NULL,
+                submitted_by VARCHAR(255) NOT NULL,
+                slash_amount BIGINT NOT NULL DEFAULT 0,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // Committed chain state every DUJYO_SNAPSHOT_INTERVAL blocks (restore + replay on startup)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS state_snapshots (
+                height BIGINT PRIMARY KEY,
+                block_hash VARCHAR(255) NOT NULL,
+                state_root VARCHAR(64) NOT NULL,
+                balances JSONB NOT NULL,
+                nonces JSONB NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // Unified ledger: transaction kind on stored transactions, stakes/NFTs/votes in snapshots
+        sqlx::query(
+            r#"
+            ALTER TABLE transactions
+                ADD COLUMN IF NOT EXISTS transaction_type VARCHAR(50) DEFAULT 'transfer',
+                ADD COLUMN IF NOT EXISTS nft_id VARCHAR(255),
+                ADD COLUMN IF NOT EXISTS kind JSONB
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query("ALTER TABLE state_snapshots ADD COLUMN IF NOT EXISTS ledger JSONB NOT NULL DEFAULT '{}'")
+            .execute(&self.pool)
+            .await?;
+
+        // DEX analytics: swap direction and the pool state left by each DEX row
+        sqlx::query(
+            r#"
+            ALTER TABLE transactions
+                ADD COLUMN IF NOT EXISTS amount_in BIGINT,
+                ADD COLUMN IF NOT EXISTS amount_out BIGINT,
+                ADD COLUMN IF NOT EXISTS pool_id VARCHAR(255),
+                ADD COLUMN IF NOT EXISTS token_in VARCHAR(32),
+                ADD COLUMN IF NOT EXISTS reserve_a BIGINT,
+                ADD COLUMN IF NOT EXISTS reserve_b BIGINT,
+                ADD COLUMN IF NOT EXISTS total_supply BIGINT
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_transactions_pool_time ON transactions(pool_id, created_at)")
+            .execute(&self.pool)
+            .await?;
+
         // Create indexes for better performance
         sqlx::query("CREATE INDEX IF NOT EXISTS idx_transactions_from ON transactions(from_address)")
             .execute(&self.pool)
@@ -255,6 +448,72 @@ impl BlockchainStorage {
             .execute(&self.pool)
             .await?;
 
+        // DEX order book: limit orders and DCA swaps with escrowed input (see migration 035)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS dex_orders (
+                order_id VARCHAR(255) PRIMARY KEY,
+                user_address VARCHAR(255) NOT NULL,
+                kind VARCHAR(16) NOT NULL,
+                from_token VARCHAR(32) NOT NULL,
+                to_token VARCHAR(32) NOT NULL,
+                amount_in BIGINT NOT NULL,
+                remaining BIGINT NOT NULL,
+                amount_out BIGINT NOT NULL DEFAULT 0,
+                min_price NUMERIC(38, 18) NOT NULL DEFAULT 0,
+                slice_amount BIGINT NOT NULL,
+                interval_secs BIGINT NOT NULL DEFAULT 0,
+                next_execution_at TIMESTAMPTZ NOT NULL,
+                expires_at TIMESTAMPTZ NOT NULL,
+                status VARCHAR(16) NOT NULL DEFAULT 'open',
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                CHECK (remaining >= 0 AND remaining <= amount_in)
+            )
+            "#
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS dex_order_fills (
+                fill_id VARCHAR(255) PRIMARY KEY,
+                order_id VARCHAR(255) NOT NULL REFERENCES dex_orders(order_id),
+                amount_in BIGINT NOT NULL,
+                amount_out BIGINT NOT NULL,
+                executed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )
+            "#
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_dex_orders_user ON dex_orders(user_address, created_at DESC)")
+            .execute(&self.pool)
+            .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_dex_orders_due ON dex_orders(next_execution_at) WHERE status = 'open'")
+            .execute(&self.pool)
+            .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_dex_order_fills_order ON dex_order_fills(order_id, executed_at)")
+            .execute(&self.pool)
+            .await?;
+
+        // Last swap nonce spent per address (see migration 037)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS dex_nonces (
+                address VARCHAR(255) PRIMARY KEY,
+                last_nonce BIGINT NOT NULL CHECK (last_nonce >= 0),
+                updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )
+            "#
+        )
+        .execute(&self.pool)
+        .await?;
+
         // Create indexes for users table
         sqlx::query("CREATE INDEX IF NOT EXISTS idx_users_email ON users(email)")
             .execute(&self.pool)
@@ -327,34 +586,193 @@ impl BlockchainStorage {
         Ok(())
     }
 
-    // Load blockchain from database
-    pub async fn load_blockchain(&self) -> Result<Blockchain, sqlx::Error> {
-        let mut blockchain = Blockchain::new();
-
-        // Load all blocks from database
-        let blocks = sqlx::query_as::<_, DbBlock>(
+    // Restore the chain on startup: newest valid state snapshot + replay of the
+    // blocks above it (every replayed block must reproduce its state root).
+    // Databases written before snapshots existed adopt the balances/account_nonces
+    // tables once as the state at the head and snapshot it.
+    pub async fn restore_blockchain(&self) -> Result<Blockchain, sqlx::Error> {
+        let rows = sqlx::query_as::<_, DbBlock>(
             "SELECT height, hash, prev_hash, timestamp, tx_count, data FROM blocks ORDER BY height"
         )
         .fetch_all(&self.pool)
         .await?;
+        let blocks: Vec<Block> = rows.into_iter().map(block_from_row).collect();
+
+        let snapshots = self.load_snapshots().await?;
+        let mut restored = None;
+        for snapshot in &snapshots {
+            match state_store::restore(&blocks, snapshot) {
+                Ok(blockchain) => {
+                    tracing::info!(height = snapshot.height, "State restored from snapshot");
+                    restored = Some(blockchain);
+                    break;
+                }
+                Err(e) => tracing::warn!(height = snapshot.height, error = %e, "Skipping state snapshot"),
+            }
+        }
+
+        let mut blockchain = match restored {
+            Some(blockchain) => blockchain,
+            None if snapshots.is_empty() => {
+                let legacy_pending = self.legacy_pending_transactions().await?;
+                let snapshot = self.legacy_snapshot(&blocks, &legacy_pending).await?;
+                self.save_snapshot(&snapshot).await?;
+                tracing::info!(height = snapshot.height, "Adopted stored balances as state snapshot");
+                if !legacy_pending.is_empty() {
+                    tracing::warn!(
+                        count = legacy_pending.len(),
+                        "Dropping pending transactions stored without their signatures"
+                    );
+                }
+                state_store::restore(&blocks, &snapshot).map_err(sqlx::Error::Protocol)?
+            }
+            None => {
+                return Err(sqlx::Error::Protocol(
+                    "No state snapshot matches the stored chain".to_string(),
+                ));
+            }
+        };
+
+        // The mempool is re-applied on top of the committed state, bundle by bundle,
+        // exactly as it was accepted (signatures, gas legs and auto-swap legs included)
+        for (priority, transactions) in self.load_mempool().await? {
+            if let Err(e) = blockchain.add_transactions(transactions, priority) {
+                tracing::warn!(error = %e, "Dropping stored pending bundle");
+            }
+        }
+        // Bundle numbers restart with the process: store the mempool under the new ones
+        self.save_mempool(&MempoolSnapshot::capture(&blockchain)).await?;
+
+        // Fork choice: side branches still inside the reorg window
+        let side_blocks = sqlx::query_as::<_, DbBlock>(
+            "SELECT height, hash, prev_hash, timestamp, tx_count, data FROM side_blocks ORDER BY height"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        for db_block in side_blocks {
+            let block = block_from_row(db_block);
+            blockchain.side_blocks.insert(block.hash.clone(), block);
+        }
+
+        // ✅ CPV: Finalized checkpoint (only if it is still part of the stored chain)
+        let finalized: Option<(i64, String)> = sqlx::query_as(
+            "SELECT finalized_height, finalized_hash FROM chain_finality WHERE id = 1"
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        if let Some((height, hash)) = finalized {
+            if let Err(e) = blockchain.finalize(height as u64, &hash) {
+                tracing::warn!(error = %e, "Ignoring stored finalized checkpoint");
+            }
+        }
+
+        Ok(blockchain)
+    }
 
-        // Convert database blocks to blockchain blocks
-        for db_block in blocks {
-            let transactions: Vec<Transaction> = serde_json::from_value(db_block.data["transactions"].clone())
-                .unwrap_or_default();
+    // Pre-snapshot databases: the balances/account_nonces tables (which already
+    // include the pending transactions) become the committed state at the head
+    // Pending rows of the transactions table, only used to adopt a database that
+    // predates state snapshots (their effect is part of the stored balances)
+    async fn legacy_pending_transactions(&self) -> Result<Vec<Transaction>, sqlx::Error> {
+        let pending_txs = sqlx::query_as::<_, DbTransaction>(
+            "SELECT tx_hash, from_address, to_address, amount, nonce, status, block_height, created_at, 
+                    transaction_type, nft_id, kind 
+             FROM transactions 
+             WHERE status = 'pending' AND (kind IS NOT NULL OR COALESCE(transaction_type, 'transfer') = 'transfer')"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(pending_txs
+            .into_iter()
+            .map(|db_tx| {
+                let kind: TxKind = db_tx.kind
+                    .and_then(|kind| serde_json::from_value(kind).ok())
+                    .unwrap_or_default();
+                Transaction::system(db_tx.from_address, db_tx.to_address, db_tx.amount as u64, db_tx.nft_id)
+                    .with_kind(kind)
+            })
+            .collect())
+    }
 
-            let block = Block {
-                timestamp: db_block.timestamp.timestamp() as u64,
-                transactions,
-                previous_hash: db_block.prev_hash,
-                hash: db_block.hash,
-                validator: Some("system".to_string()),
-            };
+    // Stored mempool bundles, in admission order
+    async fn load_mempool(&self) -> Result<Vec<(TransactionPriority, Vec<Transaction>)>, sqlx::Error> {
+        let rows: Vec<(i64, String, serde_json::Value)> = sqlx::query_as(
+            "SELECT bundle, priority, transaction FROM mempool_transactions ORDER BY bundle, position"
+        )
+        .fetch_all(&self.pool)
+        .await?;
 
-            blockchain.chain.push(block);
+        // A bundle with an unreadable leg is dropped whole (its legs only make sense together)
+        let mut bundles: Vec<(i64, TransactionPriority, Option<Vec<Transaction>>)> = Vec::new();
+        for (bundle, priority, transaction) in rows {
+            if bundles.last().map(|(seq, _, _)| *seq) != Some(bundle) {
+                let priority = serde_json::from_value(serde_json::Value::String(priority)).unwrap_or_default();
+                bundles.push((bundle, priority, Some(Vec::new())));
+            }
+            let Some((_, _, transactions)) = bundles.last_mut() else { continue };
+            match serde_json::from_value::<Transaction>(transaction) {
+                Ok(transaction) => {
+                    if let Some(transactions) = transactions {
+                        transactions.push(transaction);
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!(bundle, error = %e, "Dropping unreadable mempool bundle");
+                    *transactions = None;
+                }
+            }
         }
+        Ok(bundles
+            .into_iter()
+            .filter_map(|(_, priority, transactions)| Some((priority, transactions?)))
+            .collect())
+    }
+
+    // Replace the stored mempool with `mempool`
+    pub async fn save_mempool(&self, mempool: &MempoolSnapshot) -> Result<(), sqlx::Error> {
+        let mut sqlx_tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM mempool_transactions")
+            .execute(&mut *sqlx_tx)
+            .await?;
+        write_mempool_entries(&mut sqlx_tx, &mempool.entries).await?;
+        sqlx_tx.commit().await?;
+        Ok(())
+    }
+
+    // Store a bundle the node just accepted into its mempool (within the submission's transaction)
+    pub async fn save_pending_bundle_atomic(
+        &self,
+        bundle: u64,
+        priority: TransactionPriority,
+        transactions: &[Transaction],
+        sqlx_tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), sqlx::Error> {
+        let meta = PendingMeta { priority, bundle };
+        let entries: Vec<(Transaction, PendingMeta)> =
+            transactions.iter().cloned().map(|transaction| (transaction, meta)).collect();
+        write_mempool_entries(sqlx_tx, &entries).await
+    }
+
+    // Store a bundle the node just accepted into its mempool
+    pub async fn save_pending_bundle(
+        &self,
+        bundle: u64,
+        priority: TransactionPriority,
+        transactions: &[Transaction],
+    ) -> Result<(), sqlx::Error> {
+        let mut sqlx_tx = self.pool.begin().await?;
+        self.save_pending_bundle_atomic(bundle, priority, transactions, &mut sqlx_tx).await?;
+        sqlx_tx.commit().await?;
+        Ok(())
+    }
+
+    async fn legacy_snapshot(&self, blocks: &[Block], pending: &[Transaction]) -> Result<StateSnapshot, sqlx::Error> {
+        let mut blockchain = Blockchain::new();
+        blockchain.chain.extend(blocks.iter().filter(|block| block.height > 0).cloned());
+        blockchain.pending_transactions = pending.to_vec();
 
-        // Load balances
         let balances = sqlx::query_as::<_, DbBalance>(
             "SELECT address, balance, updated_at FROM balances"
         )
@@ -364,87 +782,328 @@ impl BlockchainStorage {
         for db_balance in balances {
             blockchain.balances.insert(db_balance.address, db_balance.balance as u64);
         }
-
-        // Load pending transactions
-        let pending_txs = sqlx::query_as::<_, DbTransaction>(
-            "SELECT tx_hash, from_address, to_address, amount, nonce, status, block_height, created_at 
-             FROM transactions WHERE status = 'pending'"
+        // The DYO reserve the DEX starts with backs the seeded pool's ledger account
+        // (what the gas auto-swap pays DYO out of)
+        blockchain
+            .balances
+            .entry(pool_account(crate::dex::SEEDED_POOL_ID))
+            .or_insert(crate::dex::SEEDED_POOL_UNITS * 100); // ledger cents
+
+        // ✅ SECURITY: Last accepted nonce per account
+        let nonces: Vec<(String, i64)> = sqlx::query_as(
+            "SELECT address, nonce FROM account_nonces"
         )
         .fetch_all(&self.pool)
         .await?;
 
-        for db_tx in pending_txs {
-            let transaction = Transaction {
-                from: db_tx.from_address,
-                to: db_tx.to_address,
-                amount: db_tx.amount as u64,
-                nft_id: None,
-            };
-            blockchain.pending_transactions.push(transaction);
+        for (address, nonce) in nonces {
+            blockchain.nonces.insert(address, nonce as u64);
         }
 
-        Ok(blockchain)
+        Ok(StateSnapshot::capture(&blockchain))
+    }
+
+    // State snapshots, newest first
+    pub async fn load_snapshots(&self) -> Result<Vec<StateSnapshot>, sqlx::Error> {
+        let rows: Vec<(i64, String, String, serde_json::Value, serde_json::Value, serde_json::Value)> = sqlx::query_as(
+            "SELECT height, block_hash, state_root, balances, nonces, ledger FROM state_snapshots ORDER BY height DESC"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(height, block_hash, state_root, balances, nonces, ledger)| StateSnapshot {
+                height: height as u64,
+                block_hash,
+                state_root,
+                balances: serde_json::from_value(balances).unwrap_or_default(),
+                nonces: serde_json::from_value(nonces).unwrap_or_default(),
+                ledger: serde_json::from_value(ledger).unwrap_or_default(),
+            })
+            .collect())
+    }
+
+    // Treasury bookings of the canonical blocks (see migration 045), oldest first
+    pub async fn load_block_fees(&self) -> Result<Vec<(u64, FeeSplit)>, sqlx::Error> {
+        let rows: Vec<(i64, i64, i64, i64, i64)> = sqlx::query_as(
+            "SELECT height, treasury, validator, creative_pool, burn FROM treasury_block_fees ORDER BY height"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|(height, treasury, validator, creative_pool, burn)| {
+                (height as u64, FeeSplit {
+                    treasury: treasury as u64,
+                    validator: validator as u64,
+                    creative_pool: creative_pool as u64,
+                    burn: burn as u64,
+                })
+            })
+            .collect())
+    }
+
+    pub async fn save_snapshot(&self, snapshot: &StateSnapshot) -> Result<(), sqlx::Error> {
+        let mut sqlx_tx = self.pool.begin().await?;
+        write_snapshot(&mut sqlx_tx, snapshot).await?;
+        sqlx_tx.commit().await?;
+        Ok(())
     }
 
-    // Save a new block to database
-    pub async fn save_block(&self, block: &Block, height: i64) -> Result<(), sqlx::Error> {
-        let data = serde_json::json!({
-            "transactions": block.transactions,
-            "validator": block.validator
-        });
+    // Commit canonical blocks and the state they produced in one database transaction:
+    // block rows, confirmed transactions, touched balances/nonces and (every
+    // DUJYO_SNAPSHOT_INTERVAL blocks) a snapshot. A crash leaves either all or none.
+    pub async fn commit_blocks(&self, blocks: &[Block], commit: &StateCommit) -> Result<(), sqlx::Error> {
+        let mut sqlx_tx = self.pool.begin().await?;
+
+        for block in blocks {
+            // ON CONFLICT DO NOTHING handles duplicate blocks gracefully
+            sqlx::query(
+                "INSERT INTO blocks (height, hash, prev_hash, timestamp, tx_count, data) 
+                 VALUES ($1, $2, $3, $4, $5, $6)
+                 ON CONFLICT (hash) DO NOTHING"
+            )
+            .bind(block.height as i64)
+            .bind(&block.hash)
+            .bind(&block.previous_hash)
+            .bind(DateTime::from_timestamp(block.timestamp as i64, 0).unwrap_or_else(|| Utc::now()))
+            .bind(block.transactions.len() as i32)
+            .bind(block_data(block))
+            .execute(&mut *sqlx_tx)
+            .await?;
+
+            for transaction in &block.transactions {
+                sqlx::query(
+                    "UPDATE transactions SET status = 'confirmed', block_height = $1 WHERE tx_hash = $2"
+                )
+                .bind(block.height as i64)
+                .bind(transaction.tx_hash())
+                .execute(&mut *sqlx_tx)
+                .await?;
+            }
+        }
+
+        write_block_fees(&mut sqlx_tx, blocks).await?;
+        write_accounts(&mut sqlx_tx, &commit.balances, &commit.nonces).await?;
+        if let Some(snapshot) = &commit.snapshot {
+            write_snapshot(&mut sqlx_tx, snapshot).await?;
+        }
+        write_mempool(&mut sqlx_tx, &commit.mempool).await?;
+
+        sqlx_tx.commit().await?;
+        Ok(())
+    }
 
-        // Use ON CONFLICT DO NOTHING to handle duplicate blocks gracefully
+    // Fork choice: store a block that lost (or has not yet won) the fork choice
+    pub async fn save_side_block(&self, block: &Block) -> Result<(), sqlx::Error> {
         sqlx::query(
-            "INSERT INTO blocks (height, hash, prev_hash, timestamp, tx_count, data) 
+            "INSERT INTO side_blocks (hash, height, prev_hash, timestamp, tx_count, data) 
              VALUES ($1, $2, $3, $4, $5, $6)
              ON CONFLICT (hash) DO NOTHING"
         )
-        .bind(height)
         .bind(&block.hash)
+        .bind(block.height as i64)
         .bind(&block.previous_hash)
         .bind(DateTime::from_timestamp(block.timestamp as i64, 0).unwrap_or_else(|| Utc::now()))
         .bind(block.transactions.len() as i32)
-        .bind(data)
+        .bind(block_data(block))
         .execute(&self.pool)
         .await?;
 
-        // Update transaction statuses
-        for (index, _transaction) in block.transactions.iter().enumerate() {
-            let tx_hash = format!("{}_{}", block.hash, index);
+        Ok(())
+    }
+
+    // Fork choice: replace every block above the common ancestor in one database transaction.
+    // Orphaned blocks move to side_blocks, their transactions go back to 'pending', and the
+    // balances/nonces of the touched accounts are overwritten with the post-reorg state.
+    pub async fn apply_reorg(
+        &self,
+        common_ancestor_height: u64,
+        orphaned: &[Block],
+        adopted: &[Block],
+        balances: &[(String, u64)],
+        nonces: &[(String, u64)],
+        mempool: &MempoolSnapshot,
+    ) -> Result<(), sqlx::Error> {
+        let mut sqlx_tx = self.pool.begin().await?;
+
+        for block in orphaned {
             sqlx::query(
-                "UPDATE transactions SET status = 'confirmed', block_height = $1 WHERE tx_hash = $2"
+                "INSERT INTO side_blocks (hash, height, prev_hash, timestamp, tx_count, data) 
+                 VALUES ($1, $2, $3, $4, $5, $6)
+                 ON CONFLICT (hash) DO NOTHING"
             )
-            .bind(height)
-            .bind(tx_hash)
-            .execute(&self.pool)
+            .bind(&block.hash)
+            .bind(block.height as i64)
+            .bind(&block.previous_hash)
+            .bind(DateTime::from_timestamp(block.timestamp as i64, 0).unwrap_or_else(|| Utc::now()))
+            .bind(block.transactions.len() as i32)
+            .bind(block_data(block))
+            .execute(&mut *sqlx_tx)
             .await?;
         }
 
-        Ok(())
-    }
-
-    // Save a new transaction to database
-    pub async fn save_transaction(&self, transaction: &Transaction) -> Result<String, sqlx::Error> {
-        let tx_hash = format!("tx_{}", Utc::now().timestamp_millis());
-        
+        // transactions.block_height references blocks(height): detach before deleting
         sqlx::query(
-            "INSERT INTO transactions (tx_hash, from_address, to_address, amount, nonce, status) 
-             VALUES ($1, $2, $3, $4, $5, $6)"
+            "UPDATE transactions SET status = 'pending', block_height = NULL WHERE block_height > $1"
         )
-        .bind(&tx_hash)
-        .bind(&transaction.from)
-        .bind(&transaction.to)
-        .bind(transaction.amount as i64)
-        .bind(0i64) // nonce
-        .bind("pending")
-        .execute(&self.pool)
+        .bind(common_ancestor_height as i64)
+        .execute(&mut *sqlx_tx)
         .await?;
 
-        Ok(tx_hash)
-    }
+        sqlx::query("DELETE FROM blocks WHERE height > $1")
+            .bind(common_ancestor_height as i64)
+            .execute(&mut *sqlx_tx)
+            .await?;
 
-    // Save a new transaction to database within an existing transaction (atomic)
-    pub async fn save_transaction_atomic(
+        // Treasury bookings follow the canonical chain
+        sqlx::query("DELETE FROM treasury_block_fees WHERE height > $1")
+            .bind(common_ancestor_height as i64)
+            .execute(&mut *sqlx_tx)
+            .await?;
+        write_block_fees(&mut sqlx_tx, adopted).await?;
+
+        for block in adopted {
+            sqlx::query(
+                "INSERT INTO blocks (height, hash, prev_hash, timestamp, tx_count, data) 
+                 VALUES ($1, $2, $3, $4, $5, $6)"
+            )
+            .bind(block.height as i64)
+            .bind(&block.hash)
+            .bind(&block.previous_hash)
+            .bind(DateTime::from_timestamp(block.timestamp as i64, 0).unwrap_or_else(|| Utc::now()))
+            .bind(block.transactions.len() as i32)
+            .bind(block_data(block))
+            .execute(&mut *sqlx_tx)
+            .await?;
+
+            for transaction in &block.transactions {
+                sqlx::query(
+                    "UPDATE transactions SET status = 'confirmed', block_height = $1 WHERE tx_hash = $2"
+                )
+                .bind(block.height as i64)
+                .bind(transaction.tx_hash())
+                .execute(&mut *sqlx_tx)
+                .await?;
+            }
+
+            sqlx::query("DELETE FROM side_blocks WHERE hash = $1")
+                .bind(&block.hash)
+                .execute(&mut *sqlx_tx)
+                .await?;
+        }
+
+        // Snapshots of the orphaned branch no longer describe the canonical state
+        sqlx::query("DELETE FROM state_snapshots WHERE height > $1")
+            .bind(common_ancestor_height as i64)
+            .execute(&mut *sqlx_tx)
+            .await?;
+
+        write_accounts(&mut sqlx_tx, balances, nonces).await?;
+        // Orphaned transactions the new branch does not include are pending again
+        write_mempool(&mut sqlx_tx, mempool).await?;
+
+        sqlx_tx.commit().await?;
+        Ok(())
+    }
+
+    // ✅ CPV: Store a validator attestation (first vote per validator and height wins)
+    pub async fn save_attestation(&self, attestation: &Attestation) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO block_attestations (validator, height, block_hash, public_key, signature) 
+             VALUES ($1, $2, $3, $4, $5)
+             ON CONFLICT (validator, height) DO NOTHING"
+        )
+        .bind(&attestation.validator)
+        .bind(attestation.height as i64)
+        .bind(&attestation.block_hash)
+        .bind(&attestation.public_key)
+        .bind(&attestation.signature)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // ✅ CPV: Advance the finalized checkpoint (never moves backwards)
+    pub async fn save_finalized(&self, height: u64, hash: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO chain_finality (id, finalized_height, finalized_hash, updated_at) 
+             VALUES (1, $1, $2, NOW())
+             ON CONFLICT (id) DO UPDATE SET finalized_height = $1, finalized_hash = $2, updated_at = NOW()
+             WHERE chain_finality.finalized_height < $1"
+        )
+        .bind(height as i64)
+        .bind(hash)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // ✅ SECURITY: Store verified double-signing evidence. Returns false if this
+    // offence was already recorded (the validator must not be slashed twice).
+    pub async fn save_evidence(
+        &self,
+        evidence: &DoubleSignEvidence,
+        submitted_by: &str,
+        slash_amount: u64,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "INSERT INTO slashing_evidence (evidence_id, validator_address, height, kind, evidence, submitted_by, slash_amount) 
+             VALUES ($1, $2, $3, $4, $5, $6, $7)
+             ON CONFLICT (evidence_id) DO NOTHING"
+        )
+        .bind(evidence.id())
+        .bind(evidence.offender())
+        .bind(evidence.height() as i64)
+        .bind(evidence.kind())
+        .bind(serde_json::to_value(evidence).unwrap_or_default())
+        .bind(submitted_by)
+        .bind(slash_amount as i64)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    // ✅ SECURITY: Evidence recorded against a validator (most recent first)
+    pub async fn get_evidence_for_validator(&self, address: &str) -> Result<Vec<DbSlashingEvidence>, sqlx::Error> {
+        sqlx::query_as::<_, DbSlashingEvidence>(
+            "SELECT evidence_id, validator_address, height, kind, evidence, submitted_by, slash_amount, created_at 
+             FROM slashing_evidence WHERE validator_address = $1 ORDER BY created_at DESC LIMIT 100"
+        )
+        .bind(address)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    // Save a new transaction to database
+    pub async fn save_transaction(&self, transaction: &Transaction) -> Result<String, sqlx::Error> {
+        let tx_hash = format!("tx_{}", Utc::now().timestamp_millis());
+        
+        sqlx::query(
+            "INSERT INTO transactions (tx_hash, from_address, to_address, amount, nonce, status, transaction_type, nft_id, kind) 
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)"
+        )
+        .bind(&tx_hash)
+        .bind(&transaction.from)
+        .bind(&transaction.to)
+        .bind(transaction.amount as i64)
+        .bind(0i64) // nonce
+        .bind("pending")
+        .bind(transaction.kind.name())
+        .bind(&transaction.nft_id)
+        .bind(serde_json::json!(transaction.kind))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(tx_hash)
+    }
+
+    // Save a new transaction to database within an existing transaction (atomic)
+    pub async fn save_transaction_atomic(
         &self,
         transaction: &Transaction,
         sqlx_tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
@@ -452,8 +1111,8 @@ impl BlockchainStorage {
         let tx_hash = format!("tx_{}", Utc::now().timestamp_millis());
         
         sqlx::query(
-            "INSERT INTO transactions (tx_hash, from_address, to_address, amount, nonce, status) 
-             VALUES ($1, $2, $3, $4, $5, $6)"
+            "INSERT INTO transactions (tx_hash, from_address, to_address, amount, nonce, status, transaction_type, nft_id, kind) 
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)"
         )
         .bind(&tx_hash)
         .bind(&transaction.from)
@@ -461,12 +1120,118 @@ impl BlockchainStorage {
         .bind(transaction.amount as i64)
         .bind(0i64) // nonce
         .bind("pending")
+        .bind(transaction.kind.name())
+        .bind(&transaction.nft_id)
+        .bind(serde_json::json!(transaction.kind))
         .execute(&mut **sqlx_tx)
         .await?;
 
         Ok(tx_hash)
     }
 
+    // Save a signed transaction using its deterministic hash and nonce (atomic)
+    pub async fn save_signed_transaction_atomic(
+        &self,
+        transaction: &Transaction,
+        tx_hash: &str,
+        sqlx_tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), sqlx::Error> {
+        let nonce = transaction.auth.as_ref().map(|auth| auth.nonce as i64).unwrap_or(0);
+
+        sqlx::query(
+            "INSERT INTO transactions (tx_hash, from_address, to_address, amount, nonce, status, transaction_type, nft_id, kind) 
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)"
+        )
+        .bind(tx_hash)
+        .bind(&transaction.from)
+        .bind(&transaction.to)
+        .bind(transaction.amount as i64)
+        .bind(nonce)
+        .bind("pending")
+        .bind(transaction.kind.name())
+        .bind(&transaction.nft_id)
+        .bind(serde_json::json!(transaction.kind))
+        .execute(&mut **sqlx_tx)
+        .await?;
+
+        Ok(())
+    }
+
+    // ✅ SECURITY: Get the ed25519 public key registered for an account
+    pub async fn get_account_key(&self, address: &str) -> Result<Option<String>, sqlx::Error> {
+        sqlx::query_scalar("SELECT public_key FROM account_keys WHERE address = $1")
+            .bind(address)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    // ✅ SECURITY: Registered keys of several accounts at once (block verification)
+    pub async fn get_account_keys(&self, addresses: &[String]) -> Result<HashMap<String, String>, sqlx::Error> {
+        let rows: Vec<(String, String)> =
+            sqlx::query_as("SELECT address, public_key FROM account_keys WHERE address = ANY($1)")
+                .bind(addresses)
+                .fetch_all(&self.pool)
+                .await?;
+        Ok(rows.into_iter().collect())
+    }
+
+    // ✅ SECURITY: Bind a public key to an account (first registration wins, no overwrite)
+    pub async fn register_account_key(&self, address: &str, public_key: &str) -> Result<bool, sqlx::Error> {
+        let inserted: Option<String> = sqlx::query_scalar(
+            "INSERT INTO account_keys (address, public_key, registered_at) 
+             VALUES ($1, $2, NOW())
+             ON CONFLICT DO NOTHING
+             RETURNING address"
+        )
+        .bind(address)
+        .bind(public_key.to_lowercase())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(inserted.is_some())
+    }
+
+    // ✅ SECURITY: Last accepted nonce for an account (0 if none)
+    pub async fn get_account_nonce(&self, address: &str) -> Result<u64, sqlx::Error> {
+        let nonce: Option<i64> = sqlx::query_scalar("SELECT nonce FROM account_nonces WHERE address = $1")
+            .bind(address)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(nonce.unwrap_or(0) as u64)
+    }
+
+    // ✅ SECURITY: Compare-and-set the account nonce inside the submit transaction.
+    // Returns false if `nonce` is not exactly the stored nonce + 1 (replay or gap).
+    pub async fn commit_account_nonce_atomic(
+        &self,
+        address: &str,
+        nonce: u64,
+        sqlx_tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<bool, sqlx::Error> {
+        let result = if nonce == 1 {
+            sqlx::query(
+                "INSERT INTO account_nonces (address, nonce, updated_at) 
+                 VALUES ($1, 1, NOW())
+                 ON CONFLICT (address) DO NOTHING"
+            )
+            .bind(address)
+            .execute(&mut **sqlx_tx)
+            .await?
+        } else {
+            sqlx::query(
+                "UPDATE account_nonces SET nonce = $2, updated_at = NOW() 
+                 WHERE address = $1 AND nonce = $2 - 1"
+            )
+            .bind(address)
+            .bind(nonce as i64)
+            .execute(&mut **sqlx_tx)
+            .await?
+        };
+
+        Ok(result.rows_affected() == 1)
+    }
+
     // Update balance in database
     pub async fn update_balance(&self, address: &str, balance: u64) -> Result<(), sqlx::Error> {
             sqlx::query(
@@ -516,7 +1281,8 @@ impl BlockchainStorage {
     // Get transaction history for an address
     pub async fn get_transaction_history(&self, address: &str, limit: i64) -> Result<Vec<DbTransaction>, sqlx::Error> {
         sqlx::query_as::<_, DbTransaction>(
-            "SELECT tx_hash, from_address, to_address, amount, nonce, status, block_height, created_at 
+            "SELECT tx_hash, from_address, to_address, amount, nonce, status, block_height, created_at, 
+                    transaction_type, nft_id, kind 
              FROM transactions 
              WHERE from_address = $1 OR to_address = $1 
              ORDER BY created_at DESC 
@@ -573,50 +1339,218 @@ impl BlockchainStorage {
 
     // DEX-specific methods
     
-    // Save DEX transaction
-    pub async fn save_dex_transaction(
-        &self,
-        tx_hash: &str,
-        from: &str,
-        to: &str,
-        amount_in: i64,
-        amount_out: i64,
-        pool_id: &str,
-        transaction_type: &str,
-    ) -> Result<(), sqlx::Error> {
+    // DYO/DYS balances from token_balances (zero for unknown addresses)
+    pub async fn get_token_balance(&self, address: &str) -> Result<TokenBalance, sqlx::Error> {
+        let row = sqlx::query_as::<_, (i64, i64, i64)>(
+            "SELECT dyo_balance, dys_balance, staked_balance FROM token_balances WHERE address = $1"
+        )
+        .bind(address)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row
+            .map(|(dyo, dys, staked)| TokenBalance::from_micro(dyo, dys, staked))
+            .unwrap_or_default())
+    }
+
+    // Staked DYO per address in ledger cents (own stake and delegations), as governance counts it
+    pub async fn load_bonded_stakes(&self) -> Result<BondedStakes, sqlx::Error> {
+        let rows = sqlx::query_as::<_, (String, i64)>(
+            "SELECT address, staked_balance FROM token_balances WHERE staked_balance > 0"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut stakes = BondedStakes::new();
+        for (address, staked) in rows {
+            let cents = micro_from_column(staked)?
+                .to_cents_floor()
+                .map_err(|e| sqlx::Error::Protocol(format!("Invalid staked balance of {}: {}", address, e)))?;
+            stakes.insert(address, cents);
+        }
+        Ok(stakes)
+    }
+
+    pub async fn save_token_balance(&self, address: &str, balance: &TokenBalance) -> Result<(), sqlx::Error> {
+        let (dyo, dys, staked) = balance.to_micro().map_err(sqlx::Error::Protocol)?;
+        sqlx::query(
+            "INSERT INTO token_balances (address, dyo_balance, dys_balance, staked_balance, updated_at)
+             VALUES ($1, $2, $3, $4, NOW())
+             ON CONFLICT (address) DO UPDATE SET
+             dyo_balance = $2, dys_balance = $3, staked_balance = $4, updated_at = NOW()"
+        )
+        .bind(address)
+        .bind(dyo)
+        .bind(dys)
+        .bind(staked)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // Save DEX transaction (with the pool state it left, for candles and pool analytics)
+    pub async fn save_dex_transaction(&self, record: &DexTransactionRecord<'_>) -> Result<(), sqlx::Error> {
+        let amount_in = micro_column(record.amount_in)?;
+        let amount_out = micro_column(record.amount_out)?;
         sqlx::query(
             r#"
-            INSERT INTO transactions (tx_hash, from_address, to_address, amount, amount_in, amount_out, pool_id, transaction_type, status, created_at)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, 'pending', NOW())
+            INSERT INTO transactions (tx_hash, from_address, to_address, amount, amount_in, amount_out, pool_id, transaction_type,
+                token_in, reserve_a, reserve_b, total_supply, status, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, 'pending', NOW())
             ON CONFLICT (tx_hash) DO UPDATE SET
                 amount_in = EXCLUDED.amount_in,
                 amount_out = EXCLUDED.amount_out,
                 pool_id = EXCLUDED.pool_id,
-                transaction_type = EXCLUDED.transaction_type
+                transaction_type = EXCLUDED.transaction_type,
+                token_in = EXCLUDED.token_in,
+                reserve_a = EXCLUDED.reserve_a,
+                reserve_b = EXCLUDED.reserve_b,
+                total_supply = EXCLUDED.total_supply
             "#
         )
-        .bind(tx_hash)
-        .bind(from)
-        .bind(to)
+        .bind(record.tx_hash)
+        .bind(record.from)
+        .bind(record.to)
         .bind(amount_in) // Use amount_in as the main amount
         .bind(amount_in)
         .bind(amount_out)
-        .bind(pool_id)
-        .bind(transaction_type)
+        .bind(&record.pool.id)
+        .bind(record.transaction_type)
+        .bind(record.token_in)
+        .bind(micro_column(record.pool.reserve_a)?)
+        .bind(micro_column(record.pool.reserve_b)?)
+        .bind(micro_column(record.pool.total_liquidity)?)
         .execute(&self.pool)
         .await?;
 
         Ok(())
     }
 
+    /// Per-bucket OHLCV of a pool over `[from, to)` (buckets with activity only,
+    /// see `analytics::fill_candles`)
+    pub async fn get_dex_candles(
+        &self,
+        pool: &crate::dex::Pool,
+        interval: CandleInterval,
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<Candle>, sqlx::Error> {
+        let rows = sqlx::query_as::<_, (i64, String, String, String, String, Option<i64>, Option<i64>, i64)>(
+            r#"
+            WITH events AS (
+                SELECT FLOOR(EXTRACT(EPOCH FROM created_at) / $2)::BIGINT * $2 AS bucket,
+                       created_at,
+                       tx_hash,
+                       ROUND(reserve_b::NUMERIC / reserve_a, 18) AS price,
+                       CASE WHEN token_in = $3 THEN amount_in WHEN token_in IS NOT NULL THEN amount_out ELSE 0 END AS volume_a,
+                       CASE WHEN token_in = $3 THEN amount_out WHEN token_in IS NOT NULL THEN amount_in ELSE 0 END AS volume_b,
+                       token_in
+                FROM transactions
+                WHERE pool_id = $1 AND reserve_a > 0
+                  AND created_at >= to_timestamp($4) AND created_at < to_timestamp($5)
+            )
+            SELECT bucket,
+                   ((ARRAY_AGG(price ORDER BY created_at ASC, tx_hash ASC))[1])::TEXT,
+                   MAX(price)::TEXT,
+                   MIN(price)::TEXT,
+                   ((ARRAY_AGG(price ORDER BY created_at DESC, tx_hash DESC))[1])::TEXT,
+                   SUM(volume_a)::BIGINT,
+                   SUM(volume_b)::BIGINT,
+                   COUNT(token_in)
+            FROM events
+            GROUP BY bucket
+            ORDER BY bucket
+            "#
+        )
+        .bind(&pool.id)
+        .bind(interval.secs() as i64)
+        .bind(&pool.token_a)
+        .bind(from as f64)
+        .bind(to as f64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|(bucket, open, high, low, close, volume_a, volume_b, trades)| {
+                Ok(Candle {
+                    open_time: bucket.max(0) as u64,
+                    open: price_from_column(&open)?,
+                    high: price_from_column(&high)?,
+                    low: price_from_column(&low)?,
+                    close: price_from_column(&close)?,
+                    volume_a: micro_from_column(volume_a.unwrap_or(0))?,
+                    volume_b: micro_from_column(volume_b.unwrap_or(0))?,
+                    trades: trades.max(0) as u64,
+                })
+            })
+            .collect()
+    }
+
+    /// Last recorded state of a pool strictly before `before`
+    pub async fn get_dex_pool_state_before(&self, pool_id: &str, before: u64) -> Result<Option<PoolState>, sqlx::Error> {
+        let row = sqlx::query_as::<_, (i64, i64, i64, i64)>(
+            "SELECT EXTRACT(EPOCH FROM created_at)::BIGINT, reserve_a, reserve_b, total_supply
+             FROM transactions
+             WHERE pool_id = $1 AND reserve_a > 0 AND created_at < to_timestamp($2)
+             ORDER BY created_at DESC, tx_hash DESC LIMIT 1"
+        )
+        .bind(pool_id)
+        .bind(before as f64)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|(timestamp, reserve_a, reserve_b, total_supply)| {
+            Ok(PoolState {
+                timestamp: timestamp.max(0) as u64,
+                reserve_a: micro_from_column(reserve_a)?,
+                reserve_b: micro_from_column(reserve_b)?,
+                total_liquidity: micro_from_column(total_supply)?,
+            })
+        })
+        .transpose()
+    }
+
+    /// Swap totals of a pool since `since`
+    pub async fn get_dex_volume(&self, pool: &crate::dex::Pool, since: u64) -> Result<VolumeTotals, sqlx::Error> {
+        let (token_a_in, token_b_in, volume_a, volume_b, trades) =
+            sqlx::query_as::<_, (Option<i64>, Option<i64>, Option<i64>, Option<i64>, i64)>(
+                r#"
+                SELECT (SUM(amount_in) FILTER (WHERE token_in = $2))::BIGINT,
+                       (SUM(amount_in) FILTER (WHERE token_in = $3))::BIGINT,
+                       SUM(CASE WHEN token_in = $2 THEN amount_in ELSE amount_out END)::BIGINT,
+                       SUM(CASE WHEN token_in = $2 THEN amount_out ELSE amount_in END)::BIGINT,
+                       COUNT(*)
+                FROM transactions
+                WHERE pool_id = $1 AND token_in IS NOT NULL AND created_at >= to_timestamp($4)
+                "#
+            )
+            .bind(&pool.id)
+            .bind(&pool.token_a)
+            .bind(&pool.token_b)
+            .bind(since as f64)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(VolumeTotals {
+            token_a_in: micro_from_column(token_a_in.unwrap_or(0))?,
+            token_b_in: micro_from_column(token_b_in.unwrap_or(0))?,
+            volume_a: micro_from_column(volume_a.unwrap_or(0))?,
+            volume_b: micro_from_column(volume_b.unwrap_or(0))?,
+            trades: trades.max(0) as u64,
+        })
+    }
+
     // Update DEX pool reserves
     pub async fn update_dex_pool(
         &self,
         pool_id: &str,
-        reserve_a: i64,
-        reserve_b: i64,
-        total_supply: i64,
+        reserve_a: Amount,
+        reserve_b: Amount,
+        total_supply: Amount,
     ) -> Result<(), sqlx::Error> {
+        let (reserve_a, reserve_b, total_supply) =
+            (micro_column(reserve_a)?, micro_column(reserve_b)?, micro_column(total_supply)?);
         sqlx::query(
             r#"
             UPDATE dex_pools 
@@ -634,6 +1568,98 @@ impl BlockchainStorage {
         Ok(())
     }
 
+    // Save DEX pool state (created by the pool factory on first use)
+    pub async fn save_dex_pool(&self, pool: &crate::dex::Pool) -> Result<(), sqlx::Error> {
+        let (reserve_a, reserve_b) = (micro_column(pool.reserve_a)?, micro_column(pool.reserve_b)?);
+        // k_last of the stored (micro-rounded) reserves, so a restored pool
+        // always covers it
+        let k_last = micro_from_column(reserve_a)?.geometric_mean(micro_from_column(reserve_b)?);
+        sqlx::query(
+            r#"
+            INSERT INTO dex_pools (pool_id, token_a, token_b, reserve_a, reserve_b, total_supply, k_last, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, NOW(), NOW())
+            ON CONFLICT (pool_id) DO UPDATE SET
+                reserve_a = EXCLUDED.reserve_a,
+                reserve_b = EXCLUDED.reserve_b,
+                total_supply = EXCLUDED.total_supply,
+                k_last = EXCLUDED.k_last,
+                updated_at = NOW()
+            "#
+        )
+        .bind(&pool.id)
+        .bind(&pool.token_a)
+        .bind(&pool.token_b)
+        .bind(reserve_a)
+        .bind(reserve_b)
+        .bind(micro_column(pool.total_liquidity)?)
+        .bind(micro_column(k_last)?)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Every stored pool with the LP balances of its liquidity positions, for
+    /// `DEX::restore_pool` at startup
+    pub async fn load_dex_pools(&self) -> Result<Vec<crate::dex::Pool>, sqlx::Error> {
+        let rows: Vec<(String, String, String, i64, i64, i64, i64)> = sqlx::query_as(
+            "SELECT pool_id, token_a, token_b, reserve_a, reserve_b, total_supply, k_last FROM dex_pools ORDER BY pool_id"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        let positions: Vec<(String, String, i64)> = sqlx::query_as(
+            "SELECT pool_id, user_address, lp_tokens FROM dex_liquidity_positions WHERE lp_tokens > 0"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut pools = Vec::with_capacity(rows.len());
+        for (id, token_a, token_b, reserve_a, reserve_b, total_supply, k_last) in rows {
+            let mut lp_balances = HashMap::new();
+            for (_, user, lp_tokens) in positions.iter().filter(|(pool_id, _, _)| *pool_id == id) {
+                lp_balances.insert(user.clone(), micro_from_column(*lp_tokens)?);
+            }
+            pools.push(crate::dex::Pool {
+                id,
+                token_a,
+                token_b,
+                reserve_a: micro_from_column(reserve_a)?,
+                reserve_b: micro_from_column(reserve_b)?,
+                total_liquidity: micro_from_column(total_supply)?,
+                lp_balances,
+                k_last: micro_from_column(k_last)?,
+            });
+        }
+        Ok(pools)
+    }
+
+    // ✅ SECURITY: Spend a signed swap's nonce (compare-and-set). Returns false
+    // if `nonce` is not above the last nonce the address spent (replay).
+    pub async fn spend_dex_nonce(&self, address: &str, nonce: u64) -> Result<bool, sqlx::Error> {
+        let nonce = i64::try_from(nonce)
+            .map_err(|_| sqlx::Error::Protocol(format!("DEX nonce {} out of range", nonce)))?;
+        let result = sqlx::query(
+            "INSERT INTO dex_nonces (address, last_nonce, updated_at) 
+             VALUES ($1, $2, NOW())
+             ON CONFLICT (address) DO UPDATE SET last_nonce = EXCLUDED.last_nonce, updated_at = NOW()
+             WHERE dex_nonces.last_nonce < EXCLUDED.last_nonce"
+        )
+        .bind(address)
+        .bind(nonce)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Last swap nonce spent by each address, for `DEX::restore_nonces` at startup
+    pub async fn load_dex_nonces(&self) -> Result<Vec<(String, u64)>, sqlx::Error> {
+        let rows: Vec<(String, i64)> = sqlx::query_as("SELECT address, last_nonce FROM dex_nonces")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.into_iter().map(|(address, last)| (address, last as u64)).collect())
+    }
+
     // Get DEX pool
     pub async fn get_dex_pool(&self, pool_id: &str) -> Result<Option<DbDexPool>, sqlx::Error> {
         let pool = sqlx::query_as::<_, DbDexPool>(
@@ -652,8 +1678,9 @@ impl BlockchainStorage {
         position_id: &str,
         user_address: &str,
         pool_id: &str,
-        lp_tokens: i64,
+        lp_tokens: Amount,
     ) -> Result<(), sqlx::Error> {
+        let lp_tokens = micro_column(lp_tokens)?;
         sqlx::query(
             r#"
             INSERT INTO dex_liquidity_positions (position_id, user_address, pool_id, lp_tokens, created_at, updated_at)
@@ -686,103 +1713,896 @@ impl BlockchainStorage {
     }
 
     // ============================================================================
-    // S2E MONTHLY POOL METHODS
+    // DEX ORDER BOOK (limit orders and DCA swaps)
     // ============================================================================
+    // Every state change is a conditional update on `status = 'open'` (and on
+    // `remaining` for fills), so the keeper and a cancellation never both spend
+    // the same escrow. Balance changes use in-place increments in the same
+    // database transaction as the order update.
+
+    /// Escrow the order input from the user's balance and store the order.
+    /// Returns false (nothing written) when the balance is insufficient.
+    pub async fn place_dex_order(&self, order: &Order) -> Result<bool, sqlx::Error> {
+        let column = balance_column(&order.from_token)?;
+        let mut tx = self.pool.begin().await?;
+
+        let escrowed = sqlx::query(&format!(
+            "UPDATE token_balances SET {column} = {column} - $2, updated_at = NOW()
+             WHERE address = $1 AND {column} >= $2"
+        ))
+        .bind(&order.user)
+        .bind(micro_column(order.amount_in)?)
+        .execute(&mut *tx)
+        .await?;
+        if escrowed.rows_affected() == 0 {
+            tx.rollback().await?;
+            return Ok(false);
+        }
 
-    /// Get the current month's S2E pool
-    pub async fn get_current_pool(&self) -> Result<S2EPool, sqlx::Error> {
-        let month_year = Utc::now().format("%Y-%m").to_string();
-        
-        // Use ::float8 cast to get f64 directly from PostgreSQL DECIMAL
-        let row = sqlx::query(
+        sqlx::query(
             r#"
-            SELECT 
-                month_year, 
-                total_amount::float8 as total_amount,
-                remaining_amount::float8 as remaining_amount,
-                artist_pool::float8 as artist_pool,
-                listener_pool::float8 as listener_pool,
-                artist_spent::float8 as artist_spent,
-                listener_spent::float8 as listener_spent
-            FROM s2e_monthly_pools 
-            WHERE month_year = $1
+            INSERT INTO dex_orders (order_id, user_address, kind, from_token, to_token, amount_in, remaining,
+                amount_out, min_price, slice_amount, interval_secs, next_execution_at, expires_at, status, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9::NUMERIC, $10, $11, to_timestamp($12), to_timestamp($13), $14,
+                to_timestamp($15), NOW())
             "#
         )
-        .bind(&month_year)
-        .fetch_optional(&self.pool)
+        .bind(&order.order_id)
+        .bind(&order.user)
+        .bind(order.kind.as_str())
+        .bind(&order.from_token)
+        .bind(&order.to_token)
+        .bind(micro_column(order.amount_in)?)
+        .bind(micro_column(order.remaining)?)
+        .bind(micro_column(order.amount_out)?)
+        .bind(order.min_price.to_string())
+        .bind(micro_column(order.slice_amount)?)
+        .bind(order.interval_secs as i64)
+        .bind(order.next_execution_at as f64)
+        .bind(order.expires_at as f64)
+        .bind(order.status.as_str())
+        .bind(order.created_at as f64)
+        .execute(&mut *tx)
         .await?;
 
-        match row {
-            Some(row) => {
-                // With ::float8 cast in SQL, SQLx should return f64 directly
-                Ok(S2EPool {
-                    month_year: row.get::<String, _>("month_year"),
-                    total_amount: row.get::<f64, _>("total_amount"),
-                    remaining_amount: row.get::<f64, _>("remaining_amount"),
-                    artist_pool: row.get::<f64, _>("artist_pool"),
-                    listener_pool: row.get::<f64, _>("listener_pool"),
-                    artist_spent: row.get::<f64, _>("artist_spent"),
-                    listener_spent: row.get::<f64, _>("listener_spent"),
-                })
-            },
-            None => {
-                // Pool doesn't exist for this month - create it
-                let new_pool = S2EPool {
-                    month_year: month_year.clone(),
-                    total_amount: 1000000.0,
-                    remaining_amount: 1000000.0,
-                    artist_pool: 600000.0,
-                    listener_pool: 400000.0,
-                    artist_spent: 0.0,
-                    listener_spent: 0.0,
-                };
-                
-                sqlx::query(
-                    r#"
-                    INSERT INTO s2e_monthly_pools (
-                        month_year, total_amount, remaining_amount,
-                        artist_pool, listener_pool, artist_spent, listener_spent
-                    ) VALUES ($1, $2, $3, $4, $5, $6, $7)
-                    "#
-                )
-                .bind(&new_pool.month_year)
-                .bind(new_pool.total_amount)
-                .bind(new_pool.remaining_amount)
-                .bind(new_pool.artist_pool)
-                .bind(new_pool.listener_pool)
-                .bind(new_pool.artist_spent)
-                .bind(new_pool.listener_spent)
-                .execute(&self.pool)
-                .await?;
-                
-                Ok(new_pool)
-            }
-        }
+        tx.commit().await?;
+        Ok(true)
     }
 
-    /// Check if pool has sufficient funds for the requested tokens
-    pub async fn check_pool_has_funds(&self, tokens_needed: f64) -> Result<bool, sqlx::Error> {
-        let month_year = Utc::now().format("%Y-%m").to_string();
-        
-        let row = sqlx::query(
-            "SELECT remaining_amount FROM s2e_monthly_pools WHERE month_year = $1"
-        )
-        .bind(&month_year)
-        .fetch_optional(&self.pool)
+    pub async fn get_dex_order(&self, order_id: &str) -> Result<Option<Order>, sqlx::Error> {
+        let row = sqlx::query(&format!("SELECT {ORDER_COLUMNS} FROM dex_orders WHERE order_id = $1"))
+            .bind(order_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        row.as_ref().map(order_from_row).transpose()
+    }
+
+    pub async fn get_user_dex_orders(&self, user_address: &str, limit: i64) -> Result<Vec<Order>, sqlx::Error> {
+        let rows = sqlx::query(&format!(
+            "SELECT {ORDER_COLUMNS} FROM dex_orders WHERE user_address = $1 ORDER BY created_at DESC LIMIT $2"
+        ))
+        .bind(user_address)
+        .bind(limit)
+        .fetch_all(&self.pool)
         .await?;
+        rows.iter().map(order_from_row).collect()
+    }
 
-        match row {
-            Some(_row) => {
-                // Use ::float8 cast for direct f64 conversion
-                let remaining: Option<f64> = sqlx::query_scalar::<_, f64>(
-                    "SELECT remaining_amount::float8 FROM s2e_monthly_pools WHERE month_year = $1"
-                )
-                .bind(&month_year)
+    /// Open orders whose next execution (or expiry) is due, oldest first
+    pub async fn get_due_dex_orders(&self, now: u64, limit: i64) -> Result<Vec<Order>, sqlx::Error> {
+        let rows = sqlx::query(&format!(
+            "SELECT {ORDER_COLUMNS} FROM dex_orders
+             WHERE status = 'open' AND (next_execution_at <= to_timestamp($1) OR expires_at <= to_timestamp($1))
+             ORDER BY created_at ASC LIMIT $2"
+        ))
+        .bind(now as f64)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter().map(order_from_row).collect()
+    }
+
+    pub async fn get_dex_order_fills(&self, order_id: &str) -> Result<Vec<OrderFill>, sqlx::Error> {
+        let rows = sqlx::query_as::<_, (String, String, i64, i64, i64)>(
+            "SELECT fill_id, order_id, amount_in, amount_out, EXTRACT(EPOCH FROM executed_at)::BIGINT
+             FROM dex_order_fills WHERE order_id = $1 ORDER BY executed_at ASC"
+        )
+        .bind(order_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|(fill_id, order_id, amount_in, amount_out, executed_at)| {
+                Ok(OrderFill {
+                    fill_id,
+                    order_id,
+                    amount_in: micro_from_column(amount_in)?,
+                    amount_out: micro_from_column(amount_out)?,
+                    executed_at: executed_at.max(0) as u64,
+                })
+            })
+            .collect()
+    }
+
+    /// Close an open order (cancelled or expired) and refund its remaining input.
+    /// `user_address` restricts the update to the owner. Returns the closed
+    /// order, or None when it was not open (already filled, closed or not owned).
+    pub async fn close_dex_order(
+        &self,
+        order_id: &str,
+        user_address: Option<&str>,
+        status: OrderStatus,
+    ) -> Result<Option<Order>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let row = sqlx::query(&format!(
+            "UPDATE dex_orders SET status = $3, updated_at = NOW()
+             WHERE order_id = $1 AND status = 'open' AND ($2::VARCHAR IS NULL OR user_address = $2)
+             RETURNING {ORDER_COLUMNS}"
+        ))
+        .bind(order_id)
+        .bind(user_address)
+        .bind(status.as_str())
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(order) = row.as_ref().map(order_from_row).transpose()? else {
+            tx.rollback().await?;
+            return Ok(None);
+        };
+
+        credit_token_balance(&mut tx, &order.user, &order.from_token, order.remaining).await?;
+        tx.commit().await?;
+        Ok(Some(order))
+    }
+
+    /// Take `amount_in` out of an open order's escrow before its swap executes.
+    /// Fails (false) if the order changed since `expected_remaining` was read.
+    pub async fn reserve_dex_order_fill(
+        &self,
+        order_id: &str,
+        expected_remaining: Amount,
+        amount_in: Amount,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "UPDATE dex_orders SET remaining = remaining - $3, updated_at = NOW()
+             WHERE order_id = $1 AND status = 'open' AND remaining = $2 AND remaining >= $3"
+        )
+        .bind(order_id)
+        .bind(micro_column(expected_remaining)?)
+        .bind(micro_column(amount_in)?)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Undo `reserve_dex_order_fill` after a rejected swap: back into the order
+    /// if it is still open, otherwise straight back to the user
+    pub async fn release_dex_order_fill(&self, order: &Order, amount_in: Amount) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let restored = sqlx::query(
+            "UPDATE dex_orders SET remaining = remaining + $2, updated_at = NOW()
+             WHERE order_id = $1 AND status = 'open'"
+        )
+        .bind(&order.order_id)
+        .bind(micro_column(amount_in)?)
+        .execute(&mut *tx)
+        .await?;
+        if restored.rows_affected() == 0 {
+            credit_token_balance(&mut tx, &order.user, &order.from_token, amount_in).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Record an executed fill (reserved with `reserve_dex_order_fill`): store
+    /// it, credit the output to the user and advance the order (`order` is the
+    /// state after `Order::apply_fill`)
+    pub async fn record_dex_order_fill(&self, order: &Order, fill: &OrderFill) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            "INSERT INTO dex_order_fills (fill_id, order_id, amount_in, amount_out, executed_at)
+             VALUES ($1, $2, $3, $4, to_timestamp($5))"
+        )
+        .bind(&fill.fill_id)
+        .bind(&fill.order_id)
+        .bind(micro_column(fill.amount_in)?)
+        .bind(micro_column(fill.amount_out)?)
+        .bind(fill.executed_at as f64)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "UPDATE dex_orders SET amount_out = amount_out + $2, next_execution_at = to_timestamp($3),
+                 status = CASE WHEN status = 'open' AND remaining = 0 THEN 'filled' ELSE status END,
+                 updated_at = NOW()
+             WHERE order_id = $1"
+        )
+        .bind(&order.order_id)
+        .bind(micro_column(fill.amount_out)?)
+        .bind(order.next_execution_at as f64)
+        .execute(&mut *tx)
+        .await?;
+
+        credit_token_balance(&mut tx, &order.user, &order.to_token, fill.amount_out).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Move an open DCA order to its next slot
+    pub async fn reschedule_dex_order(&self, order_id: &str, next_execution_at: u64) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE dex_orders SET next_execution_at = to_timestamp($2), updated_at = NOW()
+             WHERE order_id = $1 AND status = 'open'"
+        )
+        .bind(order_id)
+        .bind(next_execution_at as f64)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    // ============================================================================
+    // DEX BATCH AUCTION
+    // ============================================================================
+    // Queued swaps hold their input in escrow. Settlement and refunds are
+    // conditional updates on `status = 'queued'`, so a swap is paid out once.
+
+    /// Escrow the swap input from the user's balance and queue the swap.
+    /// Returns false (nothing written) when the balance is insufficient.
+    pub async fn queue_dex_batch_swap(&self, swap_id: &str, request: &crate::dex::SwapRequest) -> Result<bool, sqlx::Error> {
+        let column = balance_column(&request.from)?;
+        let mut tx = self.pool.begin().await?;
+
+        let escrowed = sqlx::query(&format!(
+            "UPDATE token_balances SET {column} = {column} - $2, updated_at = NOW()
+             WHERE address = $1 AND {column} >= $2"
+        ))
+        .bind(&request.user)
+        .bind(micro_column(request.amount)?)
+        .execute(&mut *tx)
+        .await?;
+        if escrowed.rows_affected() == 0 {
+            tx.rollback().await?;
+            return Ok(false);
+        }
+
+        sqlx::query(
+            "INSERT INTO dex_batch_swaps (swap_id, user_address, from_token, to_token, amount_in, min_received, status, created_at)
+             VALUES ($1, $2, $3, $4, $5, $6, 'queued', NOW())"
+        )
+        .bind(swap_id)
+        .bind(&request.user)
+        .bind(&request.from)
+        .bind(&request.to)
+        .bind(micro_column(request.amount)?)
+        .bind(micro_column(request.min_received)?)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// Record the results of one pool of a cleared batch: credit the output of
+    /// filled swaps and refund the input of rejected ones, in one database transaction
+    pub async fn settle_dex_batch_clearing(&self, clearing: &BatchClearing) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        for fill in &clearing.fills {
+            let settled = sqlx::query(
+                "UPDATE dex_batch_swaps SET status = $2, amount_out = $3, reason = $4, settled_at = NOW()
+                 WHERE swap_id = $1 AND status = 'queued'"
+            )
+            .bind(&fill.swap_id)
+            .bind(fill.status.as_str())
+            .bind(micro_column(fill.amount_out)?)
+            .bind(&fill.reason)
+            .execute(&mut *tx)
+            .await?;
+            if settled.rows_affected() == 0 {
+                continue;
+            }
+            match fill.status {
+                BatchFillStatus::Filled => credit_token_balance(&mut tx, &fill.user, &fill.token_out, fill.amount_out).await?,
+                BatchFillStatus::Rejected => credit_token_balance(&mut tx, &fill.user, &fill.token_in, fill.amount_in).await?,
+            }
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Reject a queued swap and refund its input; returns false if it was not queued
+    pub async fn reject_dex_batch_swap(&self, swap_id: &str, reason: &str) -> Result<bool, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let row: Option<(String, String, i64)> = sqlx::query_as(
+            "UPDATE dex_batch_swaps SET status = 'rejected', reason = $2, settled_at = NOW()
+             WHERE swap_id = $1 AND status = 'queued'
+             RETURNING user_address, from_token, amount_in"
+        )
+        .bind(swap_id)
+        .bind(reason)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some((user, from_token, amount_in)) = row else {
+            tx.rollback().await?;
+            return Ok(false);
+        };
+        credit_token_balance(&mut tx, &user, &from_token, micro_from_column(amount_in)?).await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// Refund every swap still queued (the in-memory batch did not survive a
+    /// restart). Returns how many were refunded.
+    pub async fn refund_queued_dex_batch_swaps(&self) -> Result<u64, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let rows: Vec<(String, String, i64)> = sqlx::query_as(
+            "UPDATE dex_batch_swaps SET status = 'rejected', reason = 'Node restarted before the batch cleared', settled_at = NOW()
+             WHERE status = 'queued'
+             RETURNING user_address, from_token, amount_in"
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        for (user, from_token, amount_in) in &rows {
+            credit_token_balance(&mut tx, user, from_token, micro_from_column(*amount_in)?).await?;
+        }
+        tx.commit().await?;
+        Ok(rows.len() as u64)
+    }
+
+    /// Take what a user pays into the DEX (a swap input or a liquidity deposit)
+    /// from its token balance before the pools move. Returns false (nothing
+    /// debited) if the balance does not cover it.
+    pub async fn debit_dex_balance(&self, address: &str, token: &str, amount: Amount) -> Result<bool, sqlx::Error> {
+        let column = balance_column(token)?;
+        let debited = sqlx::query(&format!(
+            "UPDATE token_balances SET {column} = {column} - $2, updated_at = NOW()
+             WHERE address = $1 AND {column} >= $2"
+        ))
+        .bind(address)
+        .bind(micro_column(amount)?)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(debited.rows_affected() == 1)
+    }
+
+    /// Credit what the DEX pays a user (a swap output or withdrawn liquidity)
+    /// to its token balance, or refund a debit the pools did not take
+    pub async fn credit_dex_balance(&self, address: &str, token: &str, amount: Amount) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        credit_token_balance(&mut tx, address, token, amount).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // ============================================================================
+    // GOVERNANCE
+    // ============================================================================
+
+    /// Insert or update a governance proposal (kept whole as JSON, see migration 040)
+    pub async fn save_governance_proposal(&self, proposal: &Proposal) -> Result<(), sqlx::Error> {
+        let mut sqlx_tx = self.pool.begin().await?;
+        self.save_governance_proposal_atomic(proposal, &mut sqlx_tx).await?;
+        sqlx_tx.commit().await?;
+        Ok(())
+    }
+
+    /// Insert or update a governance proposal within the caller's transaction, so
+    /// the row commits together with the deposit escrow or release it records
+    pub async fn save_governance_proposal_atomic(
+        &self,
+        proposal: &Proposal,
+        sqlx_tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), sqlx::Error> {
+        let data = serde_json::to_value(proposal)
+            .map_err(|e| sqlx::Error::Protocol(format!("Invalid proposal {}: {}", proposal.proposal_id, e)))?;
+        sqlx::query(
+            "INSERT INTO governance_proposals (proposal_id, proposer, status, proposal, created_at, updated_at)
+             VALUES ($1, $2, $3, $4, to_timestamp($5), NOW())
+             ON CONFLICT (proposal_id) DO UPDATE SET
+             status = $3, proposal = $4, updated_at = NOW()"
+        )
+        .bind(&proposal.proposal_id)
+        .bind(&proposal.proposer)
+        .bind(proposal.status.as_str())
+        .bind(data)
+        .bind(proposal.created_at as f64)
+        .execute(&mut **sqlx_tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Every governance proposal, oldest first
+    pub async fn load_governance_proposals(&self) -> Result<Vec<Proposal>, sqlx::Error> {
+        let rows: Vec<(String, serde_json::Value)> = sqlx::query_as(
+            "SELECT proposal_id, proposal FROM governance_proposals ORDER BY created_at ASC"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|(proposal_id, data)| {
+                serde_json::from_value(data)
+                    .map_err(|e| sqlx::Error::Protocol(format!("Invalid proposal {}: {}", proposal_id, e)))
+            })
+            .collect()
+    }
+
+    // ============================================================================
+    // STAKING UNBONDING
+    // ============================================================================
+
+    /// Move `amount` of an account's own stake into a new unbonding entry, in
+    /// one database transaction. If the account is an active economic validator
+    /// its bond in validator_stakes shrinks by the same DYO (bonds count whole
+    /// DYO, so a fractional amount is refused rather than rounded), the entry
+    /// stays slashable for it, and the bond is deactivated once it falls below
+    /// `minimum_bond`. Returns None (nothing moved) if the staked balance does
+    /// not cover it, else the entry and the validator's remaining bond.
+    pub async fn begin_unbonding(
+        &self,
+        address: &str,
+        amount: Amount,
+        minimum_bond: u64,
+        now: u64,
+        completes_at: u64,
+    ) -> Result<Option<(UnbondingEntry, Option<u64>)>, sqlx::Error> {
+        let whole_dyo = amount.to_scaled(0).ok().and_then(|dyo| i64::try_from(dyo).ok());
+
+        let mut sqlx_tx = self.pool.begin().await?;
+        let remaining_bond: Option<i64> = sqlx::query_scalar(
+            "UPDATE validator_stakes
+             SET stake_amount = GREATEST(0, stake_amount - $2),
+                 is_active = GREATEST(0, stake_amount - $2) >= $3,
+                 unlocked_at = CASE WHEN GREATEST(0, stake_amount - $2) >= $3 THEN unlocked_at ELSE NOW() END,
+                 updated_at = NOW()
+             WHERE validator_address = $1 AND is_active = TRUE
+             RETURNING stake_amount"
+        )
+        .bind(address)
+        .bind(whole_dyo.unwrap_or(0))
+        .bind(minimum_bond as i64)
+        .fetch_optional(&mut *sqlx_tx)
+        .await?;
+        if remaining_bond.is_some() && whole_dyo.is_none() {
+            sqlx_tx.rollback().await?;
+            return Err(sqlx::Error::Protocol(format!(
+                "Validator bonds count whole DYO: cannot unbond {} DYO",
+                amount
+            )));
+        }
+        if remaining_bond.is_some_and(|bond| bond < minimum_bond as i64) {
+            sqlx::query("UPDATE blockchain_validators SET is_active = false, updated_at = NOW() WHERE address = $1")
+                .bind(address)
+                .execute(&mut *sqlx_tx)
+                .await?;
+        }
+
+        let validator = remaining_bond.map(|_| address);
+        let Some(entry) = self.begin_unbonding_atomic(address, validator, amount, now, completes_at, &mut sqlx_tx).await? else {
+            return Ok(None);
+        };
+        sqlx_tx.commit().await?;
+        Ok(Some((entry, remaining_bond.map(|bond| bond.max(0) as u64))))
+    }
+
+    /// Move `amount` from the staked balance into a new unbonding entry within
+    /// the caller's transaction. The entry is cut whenever `validator` is slashed
+    /// before it is released. Returns None if the staked balance does not cover it.
+    pub async fn begin_unbonding_atomic(
+        &self,
+        address: &str,
+        validator: Option<&str>,
+        amount: Amount,
+        now: u64,
+        completes_at: u64,
+        sqlx_tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<Option<UnbondingEntry>, sqlx::Error> {
+        let debited = sqlx::query(
+            "UPDATE token_balances SET staked_balance = staked_balance - $2, updated_at = NOW()
+             WHERE address = $1 AND staked_balance >= $2"
+        )
+        .bind(address)
+        .bind(micro_column(amount)?)
+        .execute(&mut **sqlx_tx)
+        .await?;
+        if debited.rows_affected() != 1 {
+            return Ok(None);
+        }
+
+        let entry = UnbondingEntry {
+            position_id: format!("unbond_{}", uuid::Uuid::new_v4()),
+            user: address.to_string(),
+            validator: validator.map(str::to_string),
+            amount,
+            fee: Amount::ZERO,
+            rewards: Amount::ZERO,
+            slashed: Amount::ZERO,
+            started_at: now,
+            completes_at,
+        };
+        sqlx::query(
+            "INSERT INTO staking_unbonding (entry_id, user_address, validator_address, amount, started_at, completes_at)
+             VALUES ($1, $2, $3, $4, $5, $6)"
+        )
+        .bind(&entry.position_id)
+        .bind(address)
+        .bind(validator)
+        .bind(micro_column(amount)?)
+        .bind(now as i64)
+        .bind(completes_at as i64)
+        .execute(&mut **sqlx_tx)
+        .await?;
+
+        Ok(Some(entry))
+    }
+
+    /// Unbonding entries of a user not yet released, soonest first
+    pub async fn get_unbonding_entries(&self, address: &str) -> Result<Vec<UnbondingEntry>, sqlx::Error> {
+        let rows: Vec<UnbondingRow> = sqlx::query_as(&format!(
+            "SELECT {UNBONDING_COLUMNS} FROM staking_unbonding
+             WHERE user_address = $1 AND released_at IS NULL
+             ORDER BY completes_at ASC"
+        ))
+        .bind(address)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(unbonding_from_row).collect()
+    }
+
+    /// Credit every matured unbonding entry to its owner's DYO balance and mark
+    /// it released, in one database transaction
+    pub async fn release_matured_unbonding(&self, now: u64) -> Result<Vec<UnbondingEntry>, sqlx::Error> {
+        let mut sqlx_tx = self.pool.begin().await?;
+
+        let rows: Vec<UnbondingRow> = sqlx::query_as(&format!(
+            "UPDATE staking_unbonding SET released_at = $1
+             WHERE released_at IS NULL AND completes_at <= $1
+             RETURNING {UNBONDING_COLUMNS}"
+        ))
+        .bind(now as i64)
+        .fetch_all(&mut *sqlx_tx)
+        .await?;
+
+        let mut released = Vec::with_capacity(rows.len());
+        for row in rows {
+            let entry = unbonding_from_row(row)?;
+            credit_token_balance(&mut sqlx_tx, &entry.user, "DYO", entry.payout()).await?;
+            released.push(entry);
+        }
+
+        sqlx_tx.commit().await?;
+        Ok(released)
+    }
+
+    // ============================================================================
+    // STAKING DELEGATIONS
+    // ============================================================================
+
+    /// Store the staking manager (see migration 043) within the caller's
+    /// transaction, next to the balance change it accounts for
+    pub async fn save_staking_state_atomic(
+        &self,
+        staking: &StakingManager,
+        sqlx_tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), sqlx::Error> {
+        let data = serde_json::to_value(staking)
+            .map_err(|e| sqlx::Error::Protocol(format!("Invalid staking state: {}", e)))?;
+        sqlx::query(
+            "INSERT INTO staking_state (state_id, state, updated_at)
+             VALUES (1, $1, NOW())
+             ON CONFLICT (state_id) DO UPDATE SET state = $1, updated_at = NOW()"
+        )
+        .bind(data)
+        .execute(&mut **sqlx_tx)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn save_staking_state(&self, staking: &StakingManager) -> Result<(), sqlx::Error> {
+        let mut sqlx_tx = self.pool.begin().await?;
+        self.save_staking_state_atomic(staking, &mut sqlx_tx).await?;
+        sqlx_tx.commit().await?;
+        Ok(())
+    }
+
+    /// Move a delegation's DYO from the delegator's balance into its staked
+    /// balance and store the staking manager that records it, in one database
+    /// transaction. Returns false (nothing moved) if the balance does not cover it.
+    pub async fn bond_delegation(
+        &self,
+        staking: &StakingManager,
+        delegator: &str,
+        amount: Amount,
+    ) -> Result<bool, sqlx::Error> {
+        let mut sqlx_tx = self.pool.begin().await?;
+
+        let debited = sqlx::query(
+            "UPDATE token_balances SET dyo_balance = dyo_balance - $2, staked_balance = staked_balance + $2, updated_at = NOW()
+             WHERE address = $1 AND dyo_balance >= $2"
+        )
+        .bind(delegator)
+        .bind(micro_column(amount)?)
+        .execute(&mut *sqlx_tx)
+        .await?;
+        if debited.rows_affected() != 1 {
+            return Ok(false);
+        }
+
+        self.save_staking_state_atomic(staking, &mut sqlx_tx).await?;
+        sqlx_tx.commit().await?;
+        Ok(true)
+    }
+
+    /// Queue an amount undelegated from `validator` for unbonding (slashable for
+    /// that validator until released) and store the staking manager that records
+    /// the undelegation, in one database transaction
+    pub async fn unbond_delegation(
+        &self,
+        staking: &StakingManager,
+        delegator: &str,
+        validator: &str,
+        amount: Amount,
+        now: u64,
+        completes_at: u64,
+    ) -> Result<Option<UnbondingEntry>, sqlx::Error> {
+        let mut sqlx_tx = self.pool.begin().await?;
+        let Some(entry) = self.begin_unbonding_atomic(delegator, Some(validator), amount, now, completes_at, &mut sqlx_tx).await? else {
+            return Ok(None);
+        };
+        self.save_staking_state_atomic(staking, &mut sqlx_tx).await?;
+        sqlx_tx.commit().await?;
+        Ok(Some(entry))
+    }
+
+    /// The stored staking manager, if any
+    pub async fn load_staking_state(&self) -> Result<Option<StakingManager>, sqlx::Error> {
+        let row: Option<(serde_json::Value,)> =
+            sqlx::query_as("SELECT state FROM staking_state WHERE state_id = 1")
                 .fetch_optional(&self.pool)
                 .await?;
-                let remaining_value = remaining.unwrap_or(0.0);
-                Ok(remaining_value >= tokens_needed)
+
+        row.map(|(data,)| {
+            serde_json::from_value(data)
+                .map_err(|e| sqlx::Error::Protocol(format!("Invalid staking state: {}", e)))
+        })
+        .transpose()
+    }
+
+    /// Cut the staked balances of slashed delegators and `slash_bps` of every
+    /// unreleased unbonding entry liable to `validator`, and store the staking
+    /// manager that recorded the cuts, in one database transaction
+    pub async fn save_delegation_slashes(
+        &self,
+        staking: &StakingManager,
+        validator: &str,
+        slash_bps: u64,
+        slashes: &[(String, Amount)],
+    ) -> Result<(), sqlx::Error> {
+        let mut sqlx_tx = self.pool.begin().await?;
+        for (address, cut) in slashes {
+            sqlx::query(
+                "UPDATE token_balances SET staked_balance = GREATEST(0, staked_balance - $2), updated_at = NOW()
+                 WHERE address = $1"
+            )
+            .bind(address)
+            .bind(micro_column(*cut)?)
+            .execute(&mut *sqlx_tx)
+            .await?;
+        }
+
+        let unbonding: Vec<UnbondingRow> = sqlx::query_as(&format!(
+            "SELECT {UNBONDING_COLUMNS} FROM staking_unbonding
+             WHERE validator_address = $1 AND released_at IS NULL
+             FOR UPDATE"
+        ))
+        .bind(validator)
+        .fetch_all(&mut *sqlx_tx)
+        .await?;
+        for row in unbonding {
+            let mut entry = unbonding_from_row(row)?;
+            let held = micro_column(entry.amount)?;
+            entry.slash(slash_bps).map_err(sqlx::Error::Protocol)?;
+            sqlx::query(
+                "UPDATE staking_unbonding SET amount = amount - $2, slashed_amount = slashed_amount + $2
+                 WHERE entry_id = $1"
+            )
+            .bind(&entry.position_id)
+            .bind(held - micro_column(entry.amount)?)
+            .execute(&mut *sqlx_tx)
+            .await?;
+        }
+
+        self.save_staking_state_atomic(staking, &mut sqlx_tx).await?;
+        sqlx_tx.commit().await?;
+        Ok(())
+    }
+
+    /// Add auto-compounded delegation rewards to the delegators' staked balances
+    /// and store the staking manager that compounded them, in one database transaction
+    pub async fn save_compounded_delegations(
+        &self,
+        staking: &StakingManager,
+        compounded: &[(String, Amount)],
+    ) -> Result<(), sqlx::Error> {
+        let mut sqlx_tx = self.pool.begin().await?;
+        for (address, amount) in compounded {
+            sqlx::query(
+                "INSERT INTO token_balances (address, staked_balance, updated_at) VALUES ($1, $2, NOW())
+                 ON CONFLICT (address) DO UPDATE SET staked_balance = token_balances.staked_balance + EXCLUDED.staked_balance, updated_at = NOW()"
+            )
+            .bind(address)
+            .bind(micro_column(*amount)?)
+            .execute(&mut *sqlx_tx)
+            .await?;
+        }
+        self.save_staking_state_atomic(staking, &mut sqlx_tx).await?;
+        sqlx_tx.commit().await?;
+        Ok(())
+    }
+
+    /// Credit claimed staking rewards to the claimer's DYO balance and store the
+    /// staking manager that paid them, in one database transaction
+    pub async fn pay_staking_rewards(
+        &self,
+        staking: &StakingManager,
+        address: &str,
+        amount: Amount,
+    ) -> Result<(), sqlx::Error> {
+        let mut sqlx_tx = self.pool.begin().await?;
+        credit_token_balance(&mut sqlx_tx, address, "DYO", amount).await?;
+        self.save_staking_state_atomic(staking, &mut sqlx_tx).await?;
+        sqlx_tx.commit().await?;
+        Ok(())
+    }
+
+    // ============================================================================
+    // GAS AUTO-SWAP
+    // ============================================================================
+
+    /// Gas auto-swap preferences of a user (defaults when never set)
+    pub async fn get_auto_swap_settings(&self, address: &str) -> Result<AutoSwapSettings, sqlx::Error> {
+        let row: Option<(bool, i32)> = sqlx::query_as(
+            "SELECT enabled, max_slippage_bps FROM gas_auto_swap_settings WHERE address = $1"
+        )
+        .bind(address)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row
+            .map(|(enabled, max_slippage_bps)| AutoSwapSettings {
+                enabled,
+                max_slippage_bps: max_slippage_bps.max(0) as u64,
+            })
+            .unwrap_or_default())
+    }
+
+    pub async fn save_auto_swap_settings(&self, address: &str, settings: &AutoSwapSettings) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO gas_auto_swap_settings (address, enabled, max_slippage_bps, updated_at)
+             VALUES ($1, $2, $3, NOW())
+             ON CONFLICT (address) DO UPDATE SET
+             enabled = $2, max_slippage_bps = $3, updated_at = NOW()"
+        )
+        .bind(address)
+        .bind(settings.enabled)
+        .bind(settings.max_slippage_bps as i32)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Debit the DYS paid by a gas auto-swap inside the submit transaction.
+    /// Returns false (nothing debited) if the balance does not cover it.
+    pub async fn debit_auto_swap_atomic(
+        &self,
+        address: &str,
+        amount: Amount,
+        sqlx_tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<bool, sqlx::Error> {
+        let debited = sqlx::query(
+            "UPDATE token_balances SET dys_balance = dys_balance - $2, updated_at = NOW()
+             WHERE address = $1 AND dys_balance >= $2"
+        )
+        .bind(address)
+        .bind(micro_column(amount)?)
+        .execute(&mut **sqlx_tx)
+        .await?;
+
+        Ok(debited.rows_affected() == 1)
+    }
+
+    // ============================================================================
+    // S2E MONTHLY POOL METHODS
+    // ============================================================================
+
+    /// Get the current month's S2E pool
+    pub async fn get_current_pool(&self) -> Result<S2EPool, sqlx::Error> {
+        let month_year = Utc::now().format("%Y-%m").to_string();
+        
+        // DECIMAL columns are read as text so no value goes through f64
+        let row = sqlx::query(
+            r#"
+            SELECT 
+                month_year, 
+                total_amount::text as total_amount,
+                remaining_amount::text as remaining_amount,
+                artist_pool::text as artist_pool,
+                listener_pool::text as listener_pool,
+                COALESCE(artist_spent, 0)::text as artist_spent,
+                COALESCE(listener_spent, 0)::text as listener_spent
+            FROM s2e_monthly_pools 
+            WHERE month_year = $1
+            "#
+        )
+        .bind(&month_year)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match row {
+            Some(row) => {
+                Ok(S2EPool {
+                    month_year: row.get::<String, _>("month_year"),
+                    total_amount: amount_column(&row, "total_amount")?,
+                    remaining_amount: amount_column(&row, "remaining_amount")?,
+                    artist_pool: amount_column(&row, "artist_pool")?,
+                    listener_pool: amount_column(&row, "listener_pool")?,
+                    artist_spent: amount_column(&row, "artist_spent")?,
+                    listener_spent: amount_column(&row, "listener_spent")?,
+                })
             },
+            None => {
+                // Pool doesn't exist for this month - create it
+                let new_pool = S2EPool {
+                    month_year: month_year.clone(),
+                    total_amount: Amount::from_units(1_000_000),
+                    remaining_amount: Amount::from_units(1_000_000),
+                    artist_pool: Amount::from_units(600_000),
+                    listener_pool: Amount::from_units(400_000),
+                    artist_spent: Amount::ZERO,
+                    listener_spent: Amount::ZERO,
+                };
+                
+                sqlx::query(
+                    r#"
+                    INSERT INTO s2e_monthly_pools (
+                        month_year, total_amount, remaining_amount,
+                        artist_pool, listener_pool, artist_spent, listener_spent
+                    ) VALUES ($1, $2::numeric, $3::numeric, $4::numeric, $5::numeric, $6::numeric, $7::numeric)
+                    "#
+                )
+                .bind(&new_pool.month_year)
+                .bind(new_pool.total_amount.to_string())
+                .bind(new_pool.remaining_amount.to_string())
+                .bind(new_pool.artist_pool.to_string())
+                .bind(new_pool.listener_pool.to_string())
+                .bind(new_pool.artist_spent.to_string())
+                .bind(new_pool.listener_spent.to_string())
+                .execute(&self.pool)
+                .await?;
+                
+                Ok(new_pool)
+            }
+        }
+    }
+
+    /// Check if pool has sufficient funds for the requested tokens
+    pub async fn check_pool_has_funds(&self, tokens_needed: Amount) -> Result<bool, sqlx::Error> {
+        let month_year = Utc::now().format("%Y-%m").to_string();
+        
+        let row = sqlx::query(
+            "SELECT remaining_amount::text as remaining_amount FROM s2e_monthly_pools WHERE month_year = $1"
+        )
+        .bind(&month_year)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match row {
+            Some(row) => Ok(amount_column(&row, "remaining_amount")? >= tokens_needed),
             None => Ok(false), // Pool doesn't exist - no funds available
         }
     }
@@ -790,27 +2610,29 @@ impl BlockchainStorage {
     /// Decrement the pool by the specified amounts (artist + listener tokens)
     pub async fn decrement_pool(
         &self,
-        artist_tokens: f64,
-        listener_tokens: f64,
+        artist_tokens: Amount,
+        listener_tokens: Amount,
     ) -> Result<(), sqlx::Error> {
         let month_year = Utc::now().format("%Y-%m").to_string();
-        let total_tokens = artist_tokens + listener_tokens;
+        let total_tokens = artist_tokens
+            .checked_add(listener_tokens)
+            .map_err(|e| sqlx::Error::Protocol(e.to_string()))?;
         
         sqlx::query(
             r#"
             UPDATE s2e_monthly_pools 
             SET 
-                remaining_amount = remaining_amount - $1,
-                artist_spent = artist_spent + $2,
-                listener_spent = listener_spent + $3,
+                remaining_amount = remaining_amount - $1::numeric,
+                artist_spent = artist_spent + $2::numeric,
+                listener_spent = listener_spent + $3::numeric,
                 updated_at = NOW()
             WHERE month_year = $4 
-            AND remaining_amount >= $1
+            AND remaining_amount >= $1::numeric
             "#
         )
-        .bind(total_tokens)
-        .bind(artist_tokens)
-        .bind(listener_tokens)
+        .bind(total_tokens.to_string())
+        .bind(artist_tokens.to_string())
+        .bind(listener_tokens.to_string())
         .bind(&month_year)
         .execute(&self.pool)
         .await?;
@@ -818,3 +2640,285 @@ impl BlockchainStorage {
         Ok(())
     }
 }
+
+// Upsert balances and nonces of the touched accounts inside a block/reorg commit
+async fn write_accounts(
+    sqlx_tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    balances: &[(String, u64)],
+    nonces: &[(String, u64)],
+) -> Result<(), sqlx::Error> {
+    for (address, balance) in balances {
+        sqlx::query(
+            "INSERT INTO balances (address, balance, updated_at) 
+             VALUES ($1, $2, NOW()) 
+             ON CONFLICT (address) 
+             DO UPDATE SET balance = $2, updated_at = NOW()"
+        )
+        .bind(address)
+        .bind(*balance as i64)
+        .execute(&mut **sqlx_tx)
+        .await?;
+    }
+
+    for (address, nonce) in nonces {
+        // No accepted transaction left: the next nonce is 1 again (see commit_account_nonce_atomic)
+        if *nonce == 0 {
+            sqlx::query("DELETE FROM account_nonces WHERE address = $1")
+                .bind(address)
+                .execute(&mut **sqlx_tx)
+                .await?;
+            continue;
+        }
+        sqlx::query(
+            "INSERT INTO account_nonces (address, nonce, updated_at) 
+             VALUES ($1, $2, NOW()) 
+             ON CONFLICT (address) 
+             DO UPDATE SET nonce = $2, updated_at = NOW()"
+        )
+        .bind(address)
+        .bind(*nonce as i64)
+        .execute(&mut **sqlx_tx)
+        .await?;
+    }
+
+    Ok(())
+}
+
+// Fee distribution of each committed block, booked by the treasury (see migration 045)
+async fn write_block_fees(
+    sqlx_tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    blocks: &[Block],
+) -> Result<(), sqlx::Error> {
+    for block in blocks {
+        let Some(fees) = block.fee_split() else { continue };
+        sqlx::query(
+            "INSERT INTO treasury_block_fees (block_hash, height, treasury, validator, creative_pool, burn)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             ON CONFLICT (block_hash) DO NOTHING"
+        )
+        .bind(&block.hash)
+        .bind(block.height as i64)
+        .bind(fees.treasury as i64)
+        .bind(fees.validator as i64)
+        .bind(fees.creative_pool as i64)
+        .bind(fees.burn as i64)
+        .execute(&mut **sqlx_tx)
+        .await?;
+    }
+    Ok(())
+}
+
+// Mempool left by a commit: the bundles assigned up to `last_bundle` are
+// rewritten (those still pending are kept); later ones were stored by their
+// submissions and stay as they are
+async fn write_mempool(
+    sqlx_tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    mempool: &MempoolSnapshot,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM mempool_transactions WHERE bundle <= $1")
+        .bind(mempool.last_bundle as i64)
+        .execute(&mut **sqlx_tx)
+        .await?;
+    write_mempool_entries(sqlx_tx, &mempool.entries).await
+}
+
+async fn write_mempool_entries(
+    sqlx_tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    entries: &[(Transaction, PendingMeta)],
+) -> Result<(), sqlx::Error> {
+    let mut position = 0;
+    let mut current_bundle = None;
+    for (transaction, meta) in entries {
+        position = if current_bundle == Some(meta.bundle) { position + 1 } else { 0 };
+        current_bundle = Some(meta.bundle);
+        sqlx::query(
+            "INSERT INTO mempool_transactions (bundle, position, priority, tx_hash, transaction) 
+             VALUES ($1, $2, $3, $4, $5)
+             ON CONFLICT (bundle, position) DO NOTHING"
+        )
+        .bind(meta.bundle as i64)
+        .bind(position)
+        .bind(meta.priority.as_str())
+        .bind(transaction.tx_hash())
+        .bind(serde_json::json!(transaction))
+        .execute(&mut **sqlx_tx)
+        .await?;
+    }
+    Ok(())
+}
+
+// Store a snapshot and prune all but the newest SNAPSHOTS_TO_KEEP
+async fn write_snapshot(
+    sqlx_tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    snapshot: &StateSnapshot,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO state_snapshots (height, block_hash, state_root, balances, nonces, ledger, created_at) 
+         VALUES ($1, $2, $3, $4, $5, $6, NOW())
+         ON CONFLICT (height) 
+         DO UPDATE SET block_hash = $2, state_root = $3, balances = $4, nonces = $5, ledger = $6, created_at = NOW()"
+    )
+    .bind(snapshot.height as i64)
+    .bind(&snapshot.block_hash)
+    .bind(&snapshot.state_root)
+    .bind(serde_json::json!(snapshot.balances))
+    .bind(serde_json::json!(snapshot.nonces))
+    .bind(serde_json::json!(snapshot.ledger))
+    .execute(&mut **sqlx_tx)
+    .await?;
+
+    sqlx::query(
+        "DELETE FROM state_snapshots WHERE height NOT IN 
+         (SELECT height FROM state_snapshots ORDER BY height DESC LIMIT $1)"
+    )
+    .bind(SNAPSHOTS_TO_KEEP)
+    .execute(&mut **sqlx_tx)
+    .await?;
+
+    Ok(())
+}
+
+// DECIMAL column selected as `::text`
+fn amount_column(row: &sqlx::postgres::PgRow, column: &str) -> Result<Amount, sqlx::Error> {
+    row.get::<String, _>(column)
+        .parse()
+        .map_err(|e: crate::utils::safe_math::SafeMathError| sqlx::Error::ColumnDecode {
+            index: column.to_string(),
+            source: Box::new(e),
+        })
+}
+
+// DEX amounts are stored in micro-tokens (6 decimals), like token_balances
+const ORDER_COLUMNS: &str = "order_id, user_address, kind, from_token, to_token, amount_in, remaining, amount_out, \
+    min_price::TEXT AS min_price, slice_amount, interval_secs, \
+    EXTRACT(EPOCH FROM next_execution_at)::BIGINT AS next_execution_at, \
+    EXTRACT(EPOCH FROM expires_at)::BIGINT AS expires_at, status, \
+    EXTRACT(EPOCH FROM created_at)::BIGINT AS created_at";
+
+fn order_from_row(row: &sqlx::postgres::PgRow) -> Result<Order, sqlx::Error> {
+    let kind: String = row.try_get("kind")?;
+    let status: String = row.try_get("status")?;
+    let min_price: String = row.try_get("min_price")?;
+    let seconds = |column: &str| -> Result<u64, sqlx::Error> { Ok(row.try_get::<i64, _>(column)?.max(0) as u64) };
+    Ok(Order {
+        order_id: row.try_get("order_id")?,
+        user: row.try_get("user_address")?,
+        kind: OrderKind::parse(&kind).ok_or_else(|| sqlx::Error::Protocol(format!("Unknown order kind {}", kind)))?,
+        from_token: row.try_get("from_token")?,
+        to_token: row.try_get("to_token")?,
+        amount_in: micro_from_column(row.try_get("amount_in")?)?,
+        remaining: micro_from_column(row.try_get("remaining")?)?,
+        amount_out: micro_from_column(row.try_get("amount_out")?)?,
+        min_price: min_price.parse().map_err(|e| sqlx::Error::Protocol(format!("Invalid min_price {}: {}", min_price, e)))?,
+        slice_amount: micro_from_column(row.try_get("slice_amount")?)?,
+        interval_secs: seconds("interval_secs")?,
+        next_execution_at: seconds("next_execution_at")?,
+        expires_at: seconds("expires_at")?,
+        status: OrderStatus::parse(&status).ok_or_else(|| sqlx::Error::Protocol(format!("Unknown order status {}", status)))?,
+        created_at: seconds("created_at")?,
+    })
+}
+
+const UNBONDING_COLUMNS: &str = "entry_id, user_address, validator_address, amount, slashed_amount, started_at, completes_at";
+
+type UnbondingRow = (String, String, Option<String>, i64, i64, i64, i64);
+
+// Unbonding entries of the live chain have no fee or rewards
+fn unbonding_from_row(row: UnbondingRow) -> Result<UnbondingEntry, sqlx::Error> {
+    let (entry_id, user, validator, amount, slashed, started_at, completes_at) = row;
+    Ok(UnbondingEntry {
+        position_id: entry_id,
+        user,
+        validator,
+        amount: micro_from_column(amount)?,
+        fee: Amount::ZERO,
+        rewards: Amount::ZERO,
+        slashed: micro_from_column(slashed)?,
+        started_at: started_at.max(0) as u64,
+        completes_at: completes_at.max(0) as u64,
+    })
+}
+
+/// token_balances column holding `token`
+fn balance_column(token: &str) -> Result<&'static str, sqlx::Error> {
+    match token {
+        "DYO" => Ok("dyo_balance"),
+        "DYS" => Ok("dys_balance"),
+        _ => Err(sqlx::Error::Protocol(format!("Unsupported token: {}", token))),
+    }
+}
+
+async fn credit_token_balance(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    address: &str,
+    token: &str,
+    amount: Amount,
+) -> Result<(), sqlx::Error> {
+    if amount.is_zero() {
+        return Ok(());
+    }
+    let column = balance_column(token)?;
+    sqlx::query(&format!(
+        "INSERT INTO token_balances (address, {column}, updated_at) VALUES ($1, $2, NOW())
+         ON CONFLICT (address) DO UPDATE SET {column} = token_balances.{column} + EXCLUDED.{column}, updated_at = NOW()"
+    ))
+    .bind(address)
+    .bind(micro_column(amount)?)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+fn price_from_column(value: &str) -> Result<Amount, sqlx::Error> {
+    value.parse().map_err(|e| sqlx::Error::Protocol(format!("Invalid price {}: {}", value, e)))
+}
+
+fn micro_from_column(value: i64) -> Result<Amount, sqlx::Error> {
+    u64::try_from(value)
+        .map(Amount::from_micro)
+        .map_err(|_| sqlx::Error::Protocol(format!("Negative token amount {} in the database", value)))
+}
+
+fn micro_column(amount: Amount) -> Result<i64, sqlx::Error> {
+    amount
+        .to_micro_floor()
+        .ok()
+        .and_then(|micro| i64::try_from(micro).ok())
+        .ok_or_else(|| sqlx::Error::Protocol(format!("Amount {} does not fit in a BIGINT column", amount)))
+}
+
+// JSON payload stored in blocks.data / side_blocks.data
+fn block_data(block: &Block) -> serde_json::Value {
+    serde_json::json!({
+        "transactions": block.transactions,
+        "validator": block.validator,
+        "merkle_root": block.merkle_root,
+        "state_root": block.state_root,
+        "proposer_signature": block.proposer_signature,
+        "vrf": block.vrf,
+        "dex_batches": block.dex_batches
+    })
+}
+
+fn block_from_row(db_block: DbBlock) -> Block {
+    let transactions: Vec<Transaction> = serde_json::from_value(db_block.data["transactions"].clone())
+        .unwrap_or_default();
+
+    let text_field = |key: &str| {
+        db_block.data[key].as_str().map(|value| value.to_string())
+    };
+
+    Block {
+        height: db_block.height as u64,
+        timestamp: db_block.timestamp.timestamp() as u64,
+        transactions,
+        previous_hash: db_block.prev_hash.clone(),
+        hash: db_block.hash.clone(),
+        validator: text_field("validator").or_else(|| Some("system".to_string())),
+        merkle_root: text_field("merkle_root").unwrap_or_default(),
+        state_root: text_field("state_root").unwrap_or_default(),
+        proposer_signature: text_field("proposer_signature"),
+        vrf: serde_json::from_value(db_block.data["vrf"].clone()).ok(),
+        dex_batches: serde_json::from_value(db_block.data["dex_batches"].clone()).unwrap_or_default(),
+    }
+}
@@ -281,23 +281,9 @@ impl OptimizedBlockchainStorage {
 
                 match result {
                     Some((dyo_balance, dys_balance, staked_balance)) => {
-                        let dyo = dyo_balance as f64 / 1_000_000.0;
-                        let dys = dys_balance as f64 / 1_000_000.0;
-                        let staked = staked_balance as f64 / 1_000_000.0;
-                        
-                        Ok(TokenBalance {
-                            dyo,
-                            dys,
-                            staked,
-                            total: dyo + dys + staked,
-                        })
+                        Ok(TokenBalance::from_micro(dyo_balance, dys_balance, staked_balance))
                     },
-                    None => Ok(TokenBalance {
-                        dyo: 0.0,
-                        dys: 0.0,
-                        staked: 0.0,
-                        total: 0.0,
-                    }),
+                    None => Ok(TokenBalance::default()),
                 }
             })
         }).await?;
@@ -323,10 +309,8 @@ impl OptimizedBlockchainStorage {
         debug!("💾 Updating token balance for {}: DYO={}, DYS={}, Staked={}", 
                address, balance.dyo, balance.dys, balance.staked);
 
-        // Convert to i64 with proper rounding
-        let dyo_i64 = (balance.dyo * 1_000_000.0).round() as i64;
-        let dys_i64 = (balance.dys * 1_000_000.0).round() as i64;
-        let staked_i64 = (balance.staked * 1_000_000.0).round() as i64;
+        // Convert to micro-DYO columns
+        let (dyo_i64, dys_i64, staked_i64) = balance.to_micro().map_err(sqlx::Error::Protocol)?;
 
         // Update database (master)
         self.db_manager.execute_write(|pool| {
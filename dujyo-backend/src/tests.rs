@@ -3,6 +3,7 @@
 #[cfg(test)]
 mod tests {
     use super::*; // Importar Token para las pruebas
+    use crate::utils::amount::Amount;
 
     fn setup() -> Token {
         Token::new()
@@ -11,9 +12,9 @@ mod tests {
     #[test]
     fn test_mint() {
         let mut token = setup();
-        let result = token.mint("address1", 100.0);
+        let result = token.mint("address1", Amount::from_units(100));
         assert!(result.is_ok());
-        assert_eq!(token.balance_of("address1"), 100.0);
+        assert_eq!(token.balance_of("address1"), Amount::from_units(100));
     }
 
     // Agrega más pruebas según sea necesario...
@@ -5,7 +5,7 @@
 //!
 //! IMPORTANT: These tests MUST FAIL if the exploits still work.
 
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, RwLock};
 use tokio::sync::Mutex as TokioMutex;
 use axum::{
     body::Body,
@@ -20,6 +20,15 @@ use crate::blockchain::token::Token;
 use crate::storage::BlockchainStorage;
 use crate::auth::{JwtConfig, Claims};
 use crate::consensus::cpv::CPVConsensus;
+use crate::consensus::proposer::ProposerKeyring;
+use crate::consensus::finality::{FinalityGadget, DEFAULT_FINALITY_THRESHOLD_BPS};
+use crate::consensus::evidence::EvidencePool;
+use crate::monetization::treasury::{TreasuryManager, TreasuryPolicy};
+use crate::governance::{Governance, GovernanceParams};
+use crate::blockchain::gas_fees::GasFeeCalculator;
+use crate::routes::stream_earn::S2ERates;
+use crate::blockchain::staking_rewards::StakingManager;
+use crate::p2p::peer_network::PeerNetwork;
 use crate::dex::DEX;
 use crate::payments::withdrawal_service::WithdrawalService;
 use crate::compliance::kyc_service::KycService;
@@ -60,6 +69,16 @@ async fn create_test_state() -> (AppState, PgPool) {
         storage: storage.clone(),
         jwt_config,
         cpv_consensus,
+        proposer_keys: Arc::new(ProposerKeyring::new()),
+        peer_network: Arc::new(PeerNetwork::new("test-node".to_string())),
+        ws_tx: tokio::sync::broadcast::channel(16).0,
+        finality: Arc::new(Mutex::new(FinalityGadget::new("dujyo-mainnet-1".to_string(), DEFAULT_FINALITY_THRESHOLD_BPS).unwrap())),
+        evidence_pool: Arc::new(Mutex::new(EvidencePool::new())),
+        treasury: Arc::new(TreasuryManager::new(TreasuryPolicy::default())),
+        governance: Arc::new(Mutex::new(Governance::new(GovernanceParams::default()))),
+        gas_fees: Arc::new(RwLock::new(GasFeeCalculator::new())),
+        s2e_rates: Arc::new(RwLock::new(S2ERates::default())),
+        staking: Arc::new(TokioMutex::new(StakingManager::new())),
     };
     
     (state, pool)
@@ -78,12 +97,12 @@ async fn create_test_user_with_balance(
     balance_dyo: u64,
 ) {
     let mut blockchain = state.blockchain.lock().unwrap();
-    let genesis_tx = Transaction {
-        from: "GENESIS".to_string(),
-        to: address.to_string(),
-        amount: balance_dyo * 100, // Convert to cents
-        nft_id: None,
-    };
+    let genesis_tx = Transaction::system(
+        "GENESIS".to_string(),
+        address.to_string(),
+        balance_dyo * 100, // Convert to cents
+        None,
+    );
     blockchain.add_transaction(genesis_tx).expect("Failed to create test balance");
 }
 
@@ -13,6 +13,7 @@ use crate::utils::vrf::VRFManager;
 use crate::utils::access_control::{AccessControlManager, Role, Permission};
 use crate::blockchain::staking_rewards::StakingManager;
 use crate::dex::{DEX, SwapRequest};
+use crate::utils::amount::Amount;
 
 #[cfg(test)]
 mod safe_math_tests {
@@ -370,9 +371,11 @@ mod dex_security_tests {
             id: "DUJYO_USDC".to_string(),
             token_a: "DUJYO".to_string(),
             token_b: "USDC".to_string(),
-            reserve_a: 1000000.0,
-            reserve_b: 1000000.0,
-            total_liquidity: 1000000.0,
+            reserve_a: Amount::from_units(1_000_000),
+            reserve_b: Amount::from_units(1_000_000),
+            total_liquidity: Amount::from_units(1_000_000),
+            lp_balances: Default::default(),
+            k_last: Amount::ZERO,
         };
         
         dex.pools.insert("DUJYO_USDC".to_string(), pool);
@@ -381,9 +384,11 @@ mod dex_security_tests {
         let swap_request = SwapRequest {
             from: "DUJYO".to_string(),
             to: "USDC".to_string(),
-            amount: 1000.0,
-            min_received: 900.0,
+            amount: Amount::from_units(1000),
+            min_received: Amount::from_units(900),
             user: "test_user".to_string(),
+            deadline: u64::MAX,
+            nonce: 0,
         };
         
         let result = dex.execute_swap(swap_request);
@@ -404,9 +409,11 @@ mod dex_security_tests {
             id: "DUJYO_USDC".to_string(),
             token_a: "DUJYO".to_string(),
             token_b: "USDC".to_string(),
-            reserve_a: 1000.0,
-            reserve_b: 1000.0,
-            total_liquidity: 1000.0,
+            reserve_a: Amount::from_units(1000),
+            reserve_b: Amount::from_units(1000),
+            total_liquidity: Amount::from_units(1000),
+            lp_balances: Default::default(),
+            k_last: Amount::ZERO,
         };
         
         dex.pools.insert("DUJYO_USDC".to_string(), pool);
@@ -415,9 +422,11 @@ mod dex_security_tests {
         let swap_request = SwapRequest {
             from: "DUJYO".to_string(),
             to: "USDC".to_string(),
-            amount: 500.0, // Large amount relative to pool size
-            min_received: 400.0, // High minimum expectation
+            amount: Amount::from_units(500), // Large amount relative to pool size
+            min_received: Amount::from_units(400), // High minimum expectation
             user: "test_user".to_string(),
+            deadline: u64::MAX,
+            nonce: 0,
         };
         
         let result = dex.execute_swap(swap_request);
@@ -434,9 +443,11 @@ mod dex_security_tests {
             id: "DUJYO_USDC".to_string(),
             token_a: "DUJYO".to_string(),
             token_b: "USDC".to_string(),
-            reserve_a: 1000000.0,
-            reserve_b: 1000000.0,
-            total_liquidity: 1000000.0,
+            reserve_a: Amount::from_units(1_000_000),
+            reserve_b: Amount::from_units(1_000_000),
+            total_liquidity: Amount::from_units(1_000_000),
+            lp_balances: Default::default(),
+            k_last: Amount::ZERO,
         };
         
         dex.pools.insert("DUJYO_USDC".to_string(), pool);
@@ -448,9 +459,11 @@ mod dex_security_tests {
             let swap_request = SwapRequest {
                 from: "DUJYO".to_string(),
                 to: "USDC".to_string(),
-                amount: 100.0,
-                min_received: 90.0,
+                amount: Amount::from_units(100),
+                min_received: Amount::from_units(90),
                 user: format!("user_{}", i),
+                deadline: u64::MAX,
+                nonce: 0,
             };
             
             let result = dex.execute_swap(swap_request);
@@ -506,9 +519,11 @@ mod integration_tests {
             id: "DUJYO_USDC".to_string(),
             token_a: "DUJYO".to_string(),
             token_b: "USDC".to_string(),
-            reserve_a: 1000000.0,
-            reserve_b: 1000000.0,
-            total_liquidity: 1000000.0,
+            reserve_a: Amount::from_units(1_000_000),
+            reserve_b: Amount::from_units(1_000_000),
+            total_liquidity: Amount::from_units(1_000_000),
+            lp_balances: Default::default(),
+            k_last: Amount::ZERO,
         };
         
         dex.pools.insert("DUJYO_USDC".to_string(), pool);
@@ -516,9 +531,11 @@ mod integration_tests {
         let swap_request = SwapRequest {
             from: "DUJYO".to_string(),
             to: "USDC".to_string(),
-            amount: 100.0,
-            min_received: 90.0,
+            amount: Amount::from_units(100),
+            min_received: Amount::from_units(90),
             user: "admin".to_string(),
+            deadline: u64::MAX,
+            nonce: 0,
         };
         
         let swap_result = dex.execute_swap(swap_request);
@@ -0,0 +1,436 @@
+//! Fixed-Point Token Amounts for Dujyo
+//!
+//! `Amount` is a non-negative token quantity with 18 decimals stored as a u128,
+//! used for DYO/DYS balances, DEX reserves, stream-to-earn rates and tax
+//! reporting. Arithmetic is checked (errors are `SafeMathError`) and
+//! multiplication/division goes through a 256-bit intermediate, so no value is
+//! ever rounded through `f64`.
+//!
+//! Amounts serialize as decimal strings (`"12.5"`). Deserialization also accepts
+//! JSON numbers, read through their decimal text, for clients that still send
+//! them.
+//!
+//! Storage keeps integer base units: the chain ledger and the `balances` table
+//! count cents (2 decimals) and `token_balances` counts micro-tokens (6 decimals).
+//! `from_cents` / `to_cents` and `from_micro` / `to_micro` convert exactly; the
+//! `_floor` variants truncate explicitly where a sub-unit remainder is expected.
+//! The chain ledger itself stays in u64 cents, since transaction amounts and fees
+//! are part of the signed transaction and the state root; gas pricing and every
+//! API boundary that reads or writes those balances go through `Amount`.
+//! Staking (the staking manager and `validator_stakes`) counts whole DYO as u64:
+//! amounts enter it through `from_units` / `to_scaled(0)`, so a fractional
+//! amount is refused instead of being rounded into a bond.
+
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::utils::safe_math::{SafeMathError, SafeMathResult};
+
+/// Decimals of every `Amount`
+pub const DECIMALS: u32 = 18;
+
+/// Decimals of the chain ledger (`Blockchain` balances, `balances` table)
+pub const CENT_DECIMALS: u32 = 2;
+
+/// Decimals of the `token_balances` table
+pub const MICRO_DECIMALS: u32 = 6;
+
+const SCALE: u128 = 1_000_000_000_000_000_000;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Amount(u128);
+
+impl Amount {
+    pub const ZERO: Amount = Amount(0);
+    /// One whole token
+    pub const ONE: Amount = Amount(SCALE);
+
+    /// From the raw 18-decimal representation
+    pub const fn from_raw(raw: u128) -> Self {
+        Amount(raw)
+    }
+
+    /// Raw 18-decimal representation
+    pub const fn raw(self) -> u128 {
+        self.0
+    }
+
+    /// Whole tokens
+    pub const fn from_units(units: u64) -> Self {
+        Amount(units as u128 * SCALE)
+    }
+
+    /// From an integer count of base units with `decimals` decimals
+    pub fn from_scaled(value: u128, decimals: u32) -> SafeMathResult<Self> {
+        if decimals > DECIMALS {
+            return Err(SafeMathError::PrecisionLoss);
+        }
+        value
+            .checked_mul(10u128.pow(DECIMALS - decimals))
+            .map(Amount)
+            .ok_or(SafeMathError::Overflow)
+    }
+
+    /// Integer count of base units with `decimals` decimals (fails on a remainder)
+    pub fn to_scaled(self, decimals: u32) -> SafeMathResult<u128> {
+        let factor = scale_factor(decimals)?;
+        if !self.0.is_multiple_of(factor) {
+            return Err(SafeMathError::PrecisionLoss);
+        }
+        Ok(self.0 / factor)
+    }
+
+    /// Integer count of base units with `decimals` decimals, remainder dropped
+    pub fn to_scaled_floor(self, decimals: u32) -> SafeMathResult<u128> {
+        Ok(self.0 / scale_factor(decimals)?)
+    }
+
+    /// Drop everything below `decimals` decimals
+    pub fn round_down_to(self, decimals: u32) -> SafeMathResult<Self> {
+        let factor = scale_factor(decimals)?;
+        Ok(Amount(self.0 - self.0 % factor))
+    }
+
+    /// Smallest amount with at most `decimals` decimals that is not below `self`
+    pub fn round_up_to(self, decimals: u32) -> SafeMathResult<Self> {
+        let factor = scale_factor(decimals)?;
+        match self.0 % factor {
+            0 => Ok(self),
+            remainder => self.0.checked_add(factor - remainder).map(Amount).ok_or(SafeMathError::Overflow),
+        }
+    }
+
+    pub const fn from_cents(cents: u64) -> Self {
+        Amount(cents as u128 * 10u128.pow(DECIMALS - CENT_DECIMALS))
+    }
+
+    pub fn to_cents(self) -> SafeMathResult<u64> {
+        narrow(self.to_scaled(CENT_DECIMALS)?)
+    }
+
+    pub fn to_cents_floor(self) -> SafeMathResult<u64> {
+        narrow(self.to_scaled_floor(CENT_DECIMALS)?)
+    }
+
+    pub const fn from_micro(micro: u64) -> Self {
+        Amount(micro as u128 * 10u128.pow(DECIMALS - MICRO_DECIMALS))
+    }
+
+    pub fn to_micro(self) -> SafeMathResult<u64> {
+        narrow(self.to_scaled(MICRO_DECIMALS)?)
+    }
+
+    pub fn to_micro_floor(self) -> SafeMathResult<u64> {
+        narrow(self.to_scaled_floor(MICRO_DECIMALS)?)
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn checked_add(self, other: Amount) -> SafeMathResult<Amount> {
+        self.0.checked_add(other.0).map(Amount).ok_or(SafeMathError::Overflow)
+    }
+
+    pub fn checked_sub(self, other: Amount) -> SafeMathResult<Amount> {
+        self.0.checked_sub(other.0).map(Amount).ok_or(SafeMathError::Underflow)
+    }
+
+    pub fn saturating_add(self, other: Amount) -> Amount {
+        Amount(self.0.saturating_add(other.0))
+    }
+
+    pub fn saturating_sub(self, other: Amount) -> Amount {
+        Amount(self.0.saturating_sub(other.0))
+    }
+
+    /// `self * numerator / denominator`, rounded down (256-bit intermediate)
+    pub fn mul_div(self, numerator: Amount, denominator: Amount) -> SafeMathResult<Amount> {
+        mul_div_floor(self.0, numerator.0, denominator.0).map(Amount)
+    }
+
+    /// `self * numerator / denominator` for integer ratios (rates, shares), rounded down
+    pub fn mul_ratio(self, numerator: u128, denominator: u128) -> SafeMathResult<Amount> {
+        mul_div_floor(self.0, numerator, denominator).map(Amount)
+    }
+
+    /// `self * bps / 10_000`, rounded down
+    pub fn mul_bps(self, bps: u64) -> SafeMathResult<Amount> {
+        self.mul_ratio(bps as u128, 10_000)
+    }
+
+    /// Product of two amounts (`1.5 * 2 = 3`), rounded down
+    pub fn checked_mul(self, other: Amount) -> SafeMathResult<Amount> {
+        mul_div_floor(self.0, other.0, SCALE).map(Amount)
+    }
+
+    /// Quotient of two amounts (`3 / 2 = 1.5`), rounded down
+    pub fn checked_div(self, other: Amount) -> SafeMathResult<Amount> {
+        mul_div_floor(self.0, SCALE, other.0).map(Amount)
+    }
+
+    /// `sqrt(self * other)`, rounded down (256-bit intermediate); the geometric
+    /// mean of two amounts keeps their scale, so it can be used as an LP supply
+    pub fn geometric_mean(self, other: Amount) -> Amount {
+        Amount(isqrt_wide(widening_mul(self.0, other.0)))
+    }
+
+    /// Exact 256-bit product as (high, low) halves; the tuples compare like the
+    /// products, so `a.wide_mul(b) >= c.wide_mul(d)` checks `a * b >= c * d`
+    pub fn wide_mul(self, other: Amount) -> (u128, u128) {
+        widening_mul(self.0, other.0)
+    }
+
+    /// Approximate value for display, prices and ratios only (never for balances)
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / SCALE as f64
+    }
+
+    /// `self / other` as a float (prices, price impact); 0 when `other` is zero
+    pub fn ratio(self, other: Amount) -> f64 {
+        if other.0 == 0 {
+            return 0.0;
+        }
+        self.0 as f64 / other.0 as f64
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let whole = self.0 / SCALE;
+        let fraction = self.0 % SCALE;
+        if fraction == 0 {
+            return write!(f, "{}", whole);
+        }
+        let digits = format!("{:018}", fraction);
+        write!(f, "{}.{}", whole, digits.trim_end_matches('0'))
+    }
+}
+
+impl FromStr for Amount {
+    type Err = SafeMathError;
+
+    /// Plain decimal notation: `"12"`, `"12.5"`, `".5"`; at most 18 decimals
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || SafeMathError::InvalidInput(format!("invalid amount: {:?}", value));
+        let (whole, fraction) = match value.split_once('.') {
+            Some((whole, fraction)) => (whole, fraction),
+            None => (value, ""),
+        };
+        if (whole.is_empty() && fraction.is_empty())
+            || !whole.bytes().all(|b| b.is_ascii_digit())
+            || !fraction.bytes().all(|b| b.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+        if fraction.len() > DECIMALS as usize {
+            return Err(SafeMathError::PrecisionLoss);
+        }
+
+        let whole: u128 = if whole.is_empty() { 0 } else { whole.parse().map_err(|_| SafeMathError::Overflow)? };
+        let fraction_raw: u128 = if fraction.is_empty() {
+            0
+        } else {
+            fraction.parse::<u128>().map_err(|_| invalid())? * 10u128.pow(DECIMALS - fraction.len() as u32)
+        };
+        whole
+            .checked_mul(SCALE)
+            .and_then(|raw| raw.checked_add(fraction_raw))
+            .map(Amount)
+            .ok_or(SafeMathError::Overflow)
+    }
+}
+
+impl Serialize for Amount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Amount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct AmountVisitor;
+
+        impl<'de> Visitor<'de> for AmountVisitor {
+            type Value = Amount;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a non-negative decimal amount")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Amount, E> {
+                value.parse().map_err(E::custom)
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Amount, E> {
+                Ok(Amount::from_units(value))
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<Amount, E> {
+                u64::try_from(value)
+                    .map(Amount::from_units)
+                    .map_err(|_| E::custom("amount cannot be negative"))
+            }
+
+            // Legacy clients: the shortest decimal text of the number, not its binary value
+            fn visit_f64<E: de::Error>(self, value: f64) -> Result<Amount, E> {
+                if !value.is_finite() || value < 0.0 {
+                    return Err(E::custom("amount must be a finite non-negative number"));
+                }
+                format!("{}", value).parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(AmountVisitor)
+    }
+}
+
+fn scale_factor(decimals: u32) -> SafeMathResult<u128> {
+    if decimals > DECIMALS {
+        return Err(SafeMathError::InvalidInput(format!("at most {} decimals", DECIMALS)));
+    }
+    Ok(10u128.pow(DECIMALS - decimals))
+}
+
+fn narrow(value: u128) -> SafeMathResult<u64> {
+    u64::try_from(value).map_err(|_| SafeMathError::Overflow)
+}
+
+/// floor(a * b / d) with a 256-bit intermediate product
+fn mul_div_floor(a: u128, b: u128, d: u128) -> SafeMathResult<u128> {
+    if d == 0 {
+        return Err(SafeMathError::DivisionByZero);
+    }
+    let (hi, lo) = widening_mul(a, b);
+    if hi == 0 {
+        return Ok(lo / d);
+    }
+    if hi >= d {
+        return Err(SafeMathError::Overflow);
+    }
+
+    // Binary long division of (hi, lo) by d; the remainder always stays below d
+    let mut remainder = hi;
+    let mut quotient: u128 = 0;
+    for bit in (0..128).rev() {
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | ((lo >> bit) & 1);
+        quotient <<= 1;
+        if carry == 1 || remainder >= d {
+            remainder = remainder.wrapping_sub(d);
+            quotient |= 1;
+        }
+    }
+    Ok(quotient)
+}
+
+/// floor(sqrt(n)) of a 256-bit (high, low) value; the root always fits in 128 bits
+fn isqrt_wide(n: (u128, u128)) -> u128 {
+    let (mut low, mut high) = (0u128, u128::MAX);
+    while low < high {
+        // Upper midpoint, so `low = mid` always makes progress
+        let mid = low + (high - low) / 2 + ((high - low) & 1);
+        if widening_mul(mid, mid) <= n {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    low
+}
+
+/// Full 256-bit product as (high, low) halves
+fn widening_mul(a: u128, b: u128) -> (u128, u128) {
+    const MASK: u128 = u64::MAX as u128;
+    let (a_hi, a_lo) = (a >> 64, a & MASK);
+    let (b_hi, b_lo) = (b >> 64, b & MASK);
+
+    let low = a_lo * b_lo;
+    let cross_1 = a_lo * b_hi;
+    let cross_2 = a_hi * b_lo;
+    let high = a_hi * b_hi;
+
+    let middle = (low >> 64) + (cross_1 & MASK) + (cross_2 & MASK);
+    let lo = (low & MASK) | (middle << 64);
+    let hi = high + (cross_1 >> 64) + (cross_2 >> 64) + (middle >> 64);
+    (hi, lo)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn amount(value: &str) -> Amount {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_display_round_trip() {
+        for text in ["0", "1", "12.5", "0.000000000000000001", "1000000.123456"] {
+            assert_eq!(amount(text).to_string(), text);
+        }
+        assert_eq!(amount(".5"), amount("0.50"));
+        assert!("1.0000000000000000001".parse::<Amount>().is_err());
+        assert!("-1".parse::<Amount>().is_err());
+        assert!("1e3".parse::<Amount>().is_err());
+    }
+
+    #[test]
+    fn test_serde_decimal_strings() {
+        let value = amount("0.1");
+        assert_eq!(serde_json::to_string(&value).unwrap(), "\"0.1\"");
+        assert_eq!(serde_json::from_str::<Amount>("\"0.1\"").unwrap(), value);
+        // Legacy numeric input keeps its decimal meaning (0.1 stays exactly 0.1)
+        assert_eq!(serde_json::from_str::<Amount>("0.1").unwrap(), value);
+        assert_eq!(serde_json::from_str::<Amount>("7").unwrap(), Amount::from_units(7));
+        assert!(serde_json::from_str::<Amount>("-1").is_err());
+    }
+
+    #[test]
+    fn test_base_unit_conversions() {
+        assert_eq!(Amount::from_cents(1_234), amount("12.34"));
+        assert_eq!(amount("12.34").to_cents().unwrap(), 1_234);
+        assert_eq!(amount("12.345").to_cents(), Err(SafeMathError::PrecisionLoss));
+        assert_eq!(amount("12.345").to_cents_floor().unwrap(), 1_234);
+        assert_eq!(amount("12.341").round_up_to(2).unwrap(), amount("12.35"));
+        assert_eq!(amount("12.34").round_up_to(2).unwrap(), amount("12.34"));
+        assert_eq!(Amount::from_micro(1_500_000), amount("1.5"));
+        assert_eq!(amount("1.5").to_micro().unwrap(), 1_500_000);
+    }
+
+    #[test]
+    fn test_no_float_drift() {
+        // 0.1 + 0.2 == 0.3 exactly, unlike f64
+        let sum = amount("0.1").checked_add(amount("0.2")).unwrap();
+        assert_eq!(sum, amount("0.3"));
+        assert_eq!(amount("0.1").checked_sub(amount("0.2")), Err(SafeMathError::Underflow));
+    }
+
+    #[test]
+    fn test_mul_div_wide_intermediate() {
+        // 10^12 tokens * 10^12 tokens overflows u128 before dividing
+        let reserve = Amount::from_units(1_000_000_000_000);
+        let out = reserve.mul_div(reserve, reserve).unwrap();
+        assert_eq!(out, reserve);
+        assert_eq!(amount("10").mul_bps(30).unwrap(), amount("0.03"));
+        assert_eq!(amount("1").mul_ratio(1, 3).unwrap().to_string(), "0.333333333333333333");
+        assert_eq!(amount("3").checked_div(amount("2")).unwrap(), amount("1.5"));
+        assert_eq!(amount("1").mul_ratio(1, 0), Err(SafeMathError::DivisionByZero));
+    }
+
+    #[test]
+    fn test_geometric_mean() {
+        assert_eq!(amount("4").geometric_mean(amount("9")), amount("6"));
+        assert_eq!(amount("2").geometric_mean(amount("1")).to_string(), "1.414213562373095048");
+        // 10^12 * 10^12 tokens needs the wide product
+        let reserve = Amount::from_units(1_000_000_000_000);
+        assert_eq!(reserve.geometric_mean(reserve), reserve);
+        assert_eq!(Amount::ZERO.geometric_mean(reserve), Amount::ZERO);
+        let max = Amount::from_raw(u128::MAX);
+        assert_eq!(max.geometric_mean(max), max);
+        assert!(max.wide_mul(Amount::from_raw(2)) > max.wide_mul(Amount::from_raw(1)));
+        assert_eq!(amount("3").wide_mul(amount("4")), amount("6").wide_mul(amount("2")));
+    }
+}
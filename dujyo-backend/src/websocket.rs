@@ -11,6 +11,9 @@ use std::sync::Arc;
 use tokio::sync::broadcast;
 use tracing::{error, info, warn};
 
+use crate::blockchain::fork_choice::ReorgEvent;
+use crate::dex::analytics::{Candle, CandleInterval};
+use crate::dex::orders::OrderEvent;
 use crate::server::AppState;
 
 // WebSocket message types
@@ -40,11 +43,29 @@ pub enum WsMessage {
         timestamp: i64,
         transactions: usize,
     },
+    // Canonical head switched to another branch (fork choice)
+    Reorg {
+        old_head: String,
+        new_head: String,
+        common_ancestor_height: u64,
+        orphaned_blocks: Vec<String>,
+        adopted_blocks: Vec<String>,
+        depth: u64,
+    },
     // DEX updates
     DexUpdate {
         pool: String,
         price: f64,
         liquidity: f64,
+        // Limit/DCA order fill, cancellation or expiry behind this update
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        order: Option<OrderEvent>,
+    },
+    // Current OHLCV candle of a pool, sent after every DEX row
+    DexCandle {
+        pool: String,
+        interval: CandleInterval,
+        candle: Candle,
     },
     // Staking updates
     StakingUpdate {
@@ -78,10 +99,8 @@ async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
     // Split the socket into sender and receiver
     let (mut sender, mut receiver) = socket.split();
 
-    // Create a broadcast channel for this connection
-    // FIXME: ws_tx field not in AppState - commenting out broadcast functionality
-    // // FIXME: ws_tx not in AppState - websocket broadcast disabled temporarily
-    // let mut rx = state.ws_tx.subscribe();
+    // Subscribe to server-wide notifications
+    let mut rx = state.ws_tx.subscribe();
 
     // Spawn a task to handle incoming messages from the client
     let mut recv_task = tokio::spawn(async move {
@@ -105,7 +124,7 @@ async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
                 Message::Binary(data) => {
                     info!("Received binary message: {} bytes", data.len());
                 }
-                Message::Ping(data) => {
+                Message::Ping(_) => {
                     info!("Received ping");
                     // Axum handles pong automatically
                 }
@@ -136,19 +155,25 @@ async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
         }
 
         // Listen for broadcast messages
+        let mut keepalive = tokio::time::interval(tokio::time::Duration::from_secs(30));
+        keepalive.tick().await;
         loop {
-            // FIXME: Broadcast functionality commented out due to missing ws_tx in AppState
-            // tokio::select! {
-            //     // Receive broadcast messages
-            //     result = rx.recv() => { ... }
-            // }
-            
-            // Send periodic ping to keep connection alive
-            tokio::time::sleep(tokio::time::Duration::from_secs(30)).await;
-            let ping = WsMessage::Ping;
-            if let Ok(json) = serde_json::to_string(&ping) {
-                if sender.send(Message::Text(json.into())).await.is_err() {
-                    error!("Failed to send ping");
+            let outgoing = tokio::select! {
+                // Receive broadcast messages
+                result = rx.recv() => match result {
+                    Ok(msg) => msg,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("WebSocket client lagged, skipped {} messages", skipped);
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                // Send periodic ping to keep connection alive
+                _ = keepalive.tick() => WsMessage::Ping,
+            };
+            if let Ok(json) = serde_json::to_string(&outgoing) {
+                if sender.send(Message::Text(json)).await.is_err() {
+                    error!("Failed to send websocket message");
                     break;
                 }
             }
@@ -232,6 +257,18 @@ pub async fn broadcast_new_block(
     broadcast_message(tx, msg).await;
 }
 
+pub async fn broadcast_reorg(tx: &broadcast::Sender<WsMessage>, event: &ReorgEvent) {
+    let msg = WsMessage::Reorg {
+        old_head: event.old_head.clone(),
+        new_head: event.new_head.clone(),
+        common_ancestor_height: event.common_ancestor_height,
+        orphaned_blocks: event.orphaned_blocks.clone(),
+        adopted_blocks: event.adopted_blocks.clone(),
+        depth: event.depth(),
+    };
+    broadcast_message(tx, msg).await;
+}
+
 pub async fn broadcast_dex_update(
     tx: &broadcast::Sender<WsMessage>,
     pool: String,
@@ -242,6 +279,37 @@ pub async fn broadcast_dex_update(
         pool,
         price,
         liquidity,
+        order: None,
+    };
+    broadcast_message(tx, msg).await;
+}
+
+pub async fn broadcast_order_update(
+    tx: &broadcast::Sender<WsMessage>,
+    pool: String,
+    price: f64,
+    liquidity: f64,
+    order: OrderEvent,
+) {
+    let msg = WsMessage::DexUpdate {
+        pool,
+        price,
+        liquidity,
+        order: Some(order),
+    };
+    broadcast_message(tx, msg).await;
+}
+
+pub async fn broadcast_dex_candle(
+    tx: &broadcast::Sender<WsMessage>,
+    pool: String,
+    interval: CandleInterval,
+    candle: Candle,
+) {
+    let msg = WsMessage::DexCandle {
+        pool,
+        interval,
+        candle,
     };
     broadcast_message(tx, msg).await;
 }
@@ -13,16 +13,21 @@ use xwavve_backend::blockchain::gas_fees::{
     GasFeeCalculator, GasFeeModel, NetworkState, TransactionType, UserTier,
     handle_gas_fee_with_auto_swap, AutoSwapResult,
 };
+use xwavve_backend::utils::amount::Amount;
 
 // ============================================================================
 // TEST HELPERS
 // ============================================================================
 
+fn decimal(text: &str) -> Amount {
+    text.parse().expect("valid decimal")
+}
+
 fn create_network_state(dyo_price_usd: f64, congestion: f64) -> NetworkState {
     NetworkState {
         congestion_level: congestion,
-        dyo_price_usd,
-        daily_volume: 1000.0,
+        dyo_price_usd: decimal(&dyo_price_usd.to_string()),
+        daily_volume: Amount::from_units(1_000),
     }
 }
 
@@ -85,7 +90,7 @@ async fn test_price_fixing_usd_to_dyo_conversion() {
             &UserTier::Regular,
             &network_state,
             false,
-        ).unwrap();
+        ).unwrap().to_f64();
         
         // Fee should be $0.001 USD base, then apply congestion multiplier
         let expected_fee_usd = 0.001;
@@ -113,7 +118,7 @@ async fn test_price_fixing_free_transactions() {
         &UserTier::Regular,
         &network_state,
         false,
-    ).unwrap();
+    ).unwrap().to_f64();
     assert_eq!(fee, 0.0, "StreamEarn should be free");
     
     // ProposeBlock should be free
@@ -123,7 +128,7 @@ async fn test_price_fixing_free_transactions() {
         &UserTier::Regular,
         &network_state,
         false,
-    ).unwrap();
+    ).unwrap().to_f64();
     assert_eq!(fee, 0.0, "ProposeBlock should be free");
 }
 
@@ -149,7 +154,7 @@ async fn test_price_fixing_fixed_fees_all_types() {
             &UserTier::Regular,
             &network_state,
             false,
-        ).unwrap();
+        ).unwrap().to_f64();
         
         // Apply congestion multiplier: 0.5 + (0.0 * 1.5) = 0.5x
         let congestion_multiplier = 0.5_f64;
@@ -159,7 +164,7 @@ async fn test_price_fixing_fixed_fees_all_types() {
         // min_fee is in USD, and may be different from base_fee
         let final_fee_usd = adjusted_fee_usd.max(min_fee_usd);
         
-        let expected_fee_dyo = final_fee_usd / network_state.dyo_price_usd;
+        let expected_fee_dyo = final_fee_usd / network_state.dyo_price_usd.to_f64();
         
         assert!(
             (fee_dyo - expected_fee_dyo).abs() < 0.0001_f64,
@@ -191,11 +196,11 @@ async fn test_price_fixing_hybrid_fees() {
     for (amount_dyo, _expected_fee_usd) in test_cases {
         let fee_dyo = calculator.calculate_gas_fee(
             &TransactionType::DexSwap,
-            Some(amount_dyo),
+            Some(decimal(&amount_dyo.to_string())),
             &UserTier::Regular,
             &network_state,
             false,
-        ).unwrap();
+        ).unwrap().to_f64();
         
         // ✅ FIX: Calculate expected fee based on actual implementation
         // 1. Percentage: amount_usd * 0.003
@@ -203,7 +208,7 @@ async fn test_price_fixing_hybrid_fees() {
         // 3. Apply congestion (0.5x): fee * 0.5
         // 4. Apply config min_fee (in USD): max(fee, min_fee)
         // 5. Apply config max_fee (in USD): min(fee, max_fee)
-        let amount_usd = amount_dyo * network_state.dyo_price_usd;
+        let amount_usd = amount_dyo * network_state.dyo_price_usd.to_f64();
         let percentage_fee_usd = amount_usd * 0.003_f64;
         let hybrid_fee_usd = percentage_fee_usd.max(0.01_f64).min(10.0_f64);
         let congestion_fee_usd = hybrid_fee_usd * 0.5_f64; // congestion = 0.0
@@ -211,7 +216,7 @@ async fn test_price_fixing_hybrid_fees() {
         let min_fee_usd = 0.01_f64;
         let max_fee_usd = 10.0_f64;
         let final_fee_usd = congestion_fee_usd.max(min_fee_usd).min(max_fee_usd);
-        let expected_fee_dyo = final_fee_usd / network_state.dyo_price_usd;
+        let expected_fee_dyo = final_fee_usd / network_state.dyo_price_usd.to_f64();
         
         assert!(
             (fee_dyo - expected_fee_dyo).abs() < 0.5_f64, // Allow more tolerance
@@ -236,7 +241,7 @@ async fn test_user_tier_discounts() {
         &UserTier::Regular,
         &network_state,
         false,
-    ).unwrap();
+    ).unwrap().to_f64();
     
     // Premium: 50% discount
     let premium_fee = calculator.calculate_gas_fee(
@@ -245,7 +250,7 @@ async fn test_user_tier_discounts() {
         &UserTier::Premium,
         &network_state,
         false,
-    ).unwrap();
+    ).unwrap().to_f64();
     assert!(
         (premium_fee - (base_fee_dyo * 0.5)).abs() < 0.0001,
         "Premium discount should be 50%: expected ~{}, got {}",
@@ -259,7 +264,7 @@ async fn test_user_tier_discounts() {
         &UserTier::CreativeValidator,
         &network_state,
         false,
-    ).unwrap();
+    ).unwrap().to_f64();
         assert!(
             (cv_fee - (base_fee_dyo * 0.5_f64)).abs() < 0.0001_f64,
         "CreativeValidator discount should be 50%"
@@ -272,7 +277,7 @@ async fn test_user_tier_discounts() {
         &UserTier::CommunityValidator,
         &network_state,
         false,
-    ).unwrap();
+    ).unwrap().to_f64();
         assert!(
             (comm_fee - (base_fee_dyo * 0.75_f64)).abs() < 0.0001_f64,
         "CommunityValidator discount should be 25%"
@@ -285,7 +290,7 @@ async fn test_user_tier_discounts() {
         &UserTier::EconomicValidator,
         &network_state,
         false,
-    ).unwrap();
+    ).unwrap().to_f64();
         assert!(
             (econ_fee - base_fee_dyo).abs() < 0.0001_f64,
         "EconomicValidator should have no discount"
@@ -312,7 +317,7 @@ async fn test_network_congestion_adjustment() {
             &UserTier::Regular,
             &network_state,
             false,
-        ).unwrap();
+        ).unwrap().to_f64();
         
         // Congestion multiplier: 0.5 + (congestion * 1.5)
         // So: 0.0 → 0.5x, 0.5 → 1.25x, 1.0 → 2.0x
@@ -323,7 +328,7 @@ async fn test_network_congestion_adjustment() {
         // For Transfer, min_fee = 0.001 USD (same as base fee)
         let min_fee_usd = 0.001_f64; // Already in USD
         let final_fee_usd = adjusted_fee_usd.max(min_fee_usd);
-        let expected_fee_dyo = final_fee_usd / network_state.dyo_price_usd;
+        let expected_fee_dyo = final_fee_usd / network_state.dyo_price_usd.to_f64();
         
         assert!(
             (fee_dyo - expected_fee_dyo).abs() < 0.0001_f64,
@@ -419,8 +424,8 @@ async fn test_zero_dyo_price_error() {
     let calculator = create_calculator();
     let network_state = NetworkState {
         congestion_level: 0.0,
-        dyo_price_usd: 0.0, // Invalid
-        daily_volume: 1000.0,
+        dyo_price_usd: Amount::ZERO, // Invalid
+        daily_volume: Amount::from_units(1_000),
     };
     
     let result = calculator.calculate_gas_fee(
@@ -439,24 +444,10 @@ async fn test_zero_dyo_price_error() {
 
 #[tokio::test]
 async fn test_negative_dyo_price_error() {
-    let calculator = create_calculator();
-    let network_state = NetworkState {
-        congestion_level: 0.0,
-        dyo_price_usd: -0.001, // Invalid
-        daily_volume: 1000.0,
-    };
-    
-    let result = calculator.calculate_gas_fee(
-        &TransactionType::Transfer,
-        None,
-        &UserTier::Regular,
-        &network_state,
-        false,
-    );
-    
+    // Prices are unsigned fixed-point amounts: a negative price cannot even be built
     assert!(
-        result.is_err(),
-        "Should return error for negative DYO price"
+        "-0.001".parse::<Amount>().is_err(),
+        "Should reject a negative DYO price"
     );
 }
 
@@ -469,20 +460,20 @@ async fn test_early_unstake_penalty() {
     // Regular unstake
     let regular_fee = calculator.calculate_gas_fee(
         &TransactionType::Unstake,
-        Some(unstake_amount),
+        Some(decimal(&unstake_amount.to_string())),
         &UserTier::Regular,
         &network_state,
         false, // Not early
-    ).unwrap();
+    ).unwrap().to_f64();
     
     // Early unstake (should have 1% penalty)
     let early_fee = calculator.calculate_gas_fee(
         &TransactionType::Unstake,
-        Some(unstake_amount),
+        Some(decimal(&unstake_amount.to_string())),
         &UserTier::Regular,
         &network_state,
         true, // Early
-    ).unwrap();
+    ).unwrap().to_f64();
     
     assert!(
         early_fee > regular_fee,
@@ -491,8 +482,8 @@ async fn test_early_unstake_penalty() {
     );
     
     // Early fee should be: base fee + (amount * dyo_price_usd * 0.01)
-    let expected_penalty_usd = unstake_amount * network_state.dyo_price_usd * 0.01;
-    let expected_penalty_dyo = expected_penalty_usd / network_state.dyo_price_usd;
+    let expected_penalty_usd = unstake_amount * network_state.dyo_price_usd.to_f64() * 0.01;
+    let expected_penalty_dyo = expected_penalty_usd / network_state.dyo_price_usd.to_f64();
     let fee_difference = early_fee - regular_fee;
     
         assert!(
@@ -533,11 +524,11 @@ async fn test_min_fee_enforcement() {
     // 5. Convert: $0.01 / $0.001 = 10 DYO
     let fee = calculator.calculate_gas_fee(
         &TransactionType::DexSwap,
-        Some(1.0), // Very small amount
+        Some(Amount::from_units(1)), // Very small amount
         &UserTier::Regular,
         &network_state,
         false,
-    ).unwrap();
+    ).unwrap().to_f64();
     
     // Expected: min_fee = $0.01 USD = 10 DYO (after applying min_fee)
     assert!(
@@ -555,14 +546,14 @@ async fn test_max_fee_enforcement() {
     // DexSwap has max fee of $10 USD
     let fee = calculator.calculate_gas_fee(
         &TransactionType::DexSwap,
-        Some(100000.0), // Very large amount
+        Some(Amount::from_units(100_000)), // Very large amount
         &UserTier::Regular,
         &network_state,
         false,
-    ).unwrap();
+    ).unwrap().to_f64();
     
     let max_fee_usd = 10.0;
-    let max_fee_dyo = max_fee_usd / network_state.dyo_price_usd;
+    let max_fee_dyo = max_fee_usd / network_state.dyo_price_usd.to_f64();
     
     assert!(
         fee <= max_fee_dyo,
@@ -615,7 +606,7 @@ async fn test_gas_fee_calculation_all_transaction_types() {
         
         let fee = result.unwrap();
         assert!(
-            fee >= 0.0,
+            fee >= Amount::ZERO,
             "Fee should be non-negative for {:?}: {}",
             tx_type, fee
         );
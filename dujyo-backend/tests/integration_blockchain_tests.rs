@@ -2,6 +2,7 @@
 use xwavve_backend::blockchain::native_token::*;
 use xwavve_backend::blockchain::staking_rewards::*;
 use xwavve_backend::dex::*;
+use xwavve_backend::utils::amount::Amount;
 use xwavve_backend::utils::safe_math::SafeMath;
 
 #[cfg(test)]
@@ -177,7 +178,7 @@ mod staking_integration_tests {
             max_stake: Some(1_000_000),
             reward_frequency: 86400,
             slashing_enabled: false,
-            slashing_rate: 0.0,
+            slashing_bps: 0,
         });
         
         assert!(contract_result.is_ok(), "Contract creation should succeed");
@@ -233,7 +234,7 @@ mod staking_integration_tests {
             max_stake: None,
             reward_frequency: 86400,
             slashing_enabled: false,
-            slashing_rate: 0.0,
+            slashing_bps: 0,
         });
         
         let response = contract_result.unwrap();
@@ -276,8 +277,8 @@ mod dex_integration_tests {
         let liquidity_result = dex.add_liquidity(LiquidityRequest {
             token_a: "DYO".to_string(),
             token_b: "DYS".to_string(),
-            amount_a: 1_000_000.0,
-            amount_b: 1_000_000.0,
+            amount_a: Amount::from_units(1_000_000),
+            amount_b: Amount::from_units(1_000_000),
             user: "liquidity_provider".to_string(),
         });
         
@@ -291,9 +292,11 @@ mod dex_integration_tests {
         let swap_result = dex.execute_swap(SwapRequest {
             from: "DYO".to_string(),
             to: "DYS".to_string(),
-            amount: 10_000.0,
-            min_received: 9_500.0,
+            amount: Amount::from_units(10_000),
+            min_received: Amount::from_units(9_500),
             user: "trader".to_string(),
+            deadline: u64::MAX,
+            nonce: 0,
         });
         
         assert!(swap_result.is_ok(), "Swap should succeed");
@@ -0,0 +1,480 @@
+//! Multi-node Sync Tests
+//!
+//! Three nodes listening on local WebSocket ports exchange `SyncMessage` frames
+//! and must converge on the same chain. Fork choice scenarios run on an
+//! in-memory network with the same message handling.
+
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use ed25519_dalek::SigningKey;
+use futures_util::{SinkExt, StreamExt};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::protocol::Message;
+use tokio_tungstenite::{accept_async, connect_async};
+use xwavve_backend::blockchain::block_verifier::BlockVerifier;
+use xwavve_backend::blockchain::blockchain::{Block, Blockchain, Transaction};
+use xwavve_backend::blockchain::fork_choice::{BlockImport, ProposerWeights};
+use xwavve_backend::blockchain::signed_transaction::{SignedTransaction, DEFAULT_CHAIN_ID};
+use xwavve_backend::consensus::proposer;
+use xwavve_backend::p2p::protocol::{SyncError, SyncMessage};
+use xwavve_backend::p2p::sync::ChainSync;
+
+// ============================================================================
+// TEST HELPERS
+// ============================================================================
+
+struct TestNode {
+    sync: ChainSync,
+    chain: Blockchain,
+}
+
+impl TestNode {
+    fn new(node_id: &str) -> Self {
+        let mut chain = Blockchain::new();
+        // Same initial allocation on every node
+        chain.balances.insert("DUalice".to_string(), 100_000);
+        TestNode {
+            sync: ChainSync::new(node_id.to_string(), DEFAULT_CHAIN_ID.to_string()),
+            chain,
+        }
+    }
+}
+
+struct TestNetwork {
+    nodes: Vec<TestNode>,
+    verifier: BlockVerifier,
+    links: Vec<(usize, usize)>,
+    queue: VecDeque<(usize, usize, SyncMessage)>,
+}
+
+impl TestNetwork {
+    fn new(count: usize) -> Self {
+        TestNetwork {
+            nodes: (0..count).map(|i| TestNode::new(&format!("node-{}", i))).collect(),
+            verifier: verifier(ProposerWeights::new()),
+            links: Vec::new(),
+            queue: VecDeque::new(),
+        }
+    }
+
+    fn neighbours(&self, node: usize) -> Vec<usize> {
+        self.links
+            .iter()
+            .filter_map(|&(a, b)| match node {
+                n if n == a => Some(b),
+                n if n == b => Some(a),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Open a connection: both sides send Hello
+    fn connect(&mut self, a: usize, b: usize) {
+        self.links.push((a, b));
+        let hello_a = self.nodes[a].sync.hello(&self.nodes[a].chain);
+        let hello_b = self.nodes[b].sync.hello(&self.nodes[b].chain);
+        self.queue.push_back((a, b, hello_a));
+        self.queue.push_back((b, a, hello_b));
+        self.run();
+    }
+
+    fn broadcast_from(&mut self, origin: usize, message: SyncMessage) {
+        for peer in self.neighbours(origin) {
+            self.queue.push_back((origin, peer, message.clone()));
+        }
+        self.run();
+    }
+
+    fn run(&mut self) {
+        while let Some((from, to, message)) = self.queue.pop_front() {
+            let node = &mut self.nodes[to];
+            let outcome = node
+                .sync
+                .handle_message(&mut node.chain, message, &self.verifier)
+                .expect("message should be accepted");
+            for reply in outcome.reply {
+                self.queue.push_back((to, from, reply));
+            }
+            for relay in outcome.relay {
+                for peer in self.neighbours(to) {
+                    if peer != from {
+                        self.queue.push_back((to, peer, relay.clone()));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Node running the WebSocket transport: every peer session registers an outbox,
+/// sends `Hello` first and feeds incoming frames to `ChainSync`
+#[derive(Clone)]
+struct WsNode {
+    node: Arc<Mutex<TestNode>>,
+    peers: Arc<Mutex<HashMap<String, mpsc::UnboundedSender<String>>>>,
+    verifier: Arc<BlockVerifier>,
+    address: SocketAddr,
+}
+
+impl WsNode {
+    /// Bind a local port and accept inbound peers in the background
+    async fn spawn(node: TestNode) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let ws_node = WsNode {
+            node: Arc::new(Mutex::new(node)),
+            peers: Arc::new(Mutex::new(HashMap::new())),
+            verifier: Arc::new(verifier(ProposerWeights::new())),
+            address: listener.local_addr().unwrap(),
+        };
+        let server = ws_node.clone();
+        tokio::spawn(async move {
+            let mut inbound = 0;
+            while let Ok((tcp, _)) = listener.accept().await {
+                let Ok(socket) = accept_async(tcp).await else {
+                    continue;
+                };
+                inbound += 1;
+                tokio::spawn(server.clone().session(format!("inbound-{}", inbound), socket));
+            }
+        });
+        ws_node
+    }
+
+    async fn connect(&self, other: &WsNode) {
+        let address = format!("ws://{}", other.address);
+        let (socket, _) = connect_async(address.as_str()).await.unwrap();
+        tokio::spawn(self.clone().session(address, socket));
+    }
+
+    async fn session<S>(self, peer_id: String, socket: tokio_tungstenite::WebSocketStream<S>)
+    where
+        S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+    {
+        let (mut sink, mut stream) = socket.split();
+        let (sender, mut outbox) = mpsc::unbounded_channel::<String>();
+        let hello = {
+            let node = self.node.lock().unwrap();
+            node.sync.hello(&node.chain)
+        };
+        sender.send(hello.encode().unwrap()).unwrap();
+        self.peers.lock().unwrap().insert(peer_id.clone(), sender);
+
+        let writer = async move {
+            while let Some(text) = outbox.recv().await {
+                if sink.send(Message::Text(text)).await.is_err() {
+                    break;
+                }
+            }
+        };
+        let reader = async {
+            while let Some(Ok(Message::Text(text))) = stream.next().await {
+                let message = SyncMessage::decode(&text).expect("peer frame should decode");
+                let outcome = {
+                    let mut guard = self.node.lock().unwrap();
+                    let node = &mut *guard;
+                    node.sync
+                        .handle_message(&mut node.chain, message, &self.verifier)
+                        .expect("message should be accepted")
+                };
+                for reply in outcome.reply {
+                    self.send_to(&peer_id, &reply);
+                }
+                for relay in outcome.relay {
+                    self.broadcast(&relay, Some(&peer_id));
+                }
+            }
+        };
+        tokio::join!(writer, reader);
+    }
+
+    fn send_to(&self, peer_id: &str, message: &SyncMessage) {
+        if let Some(sender) = self.peers.lock().unwrap().get(peer_id) {
+            let _ = sender.send(message.encode().unwrap());
+        }
+    }
+
+    fn broadcast(&self, message: &SyncMessage, except: Option<&str>) {
+        let text = message.encode().unwrap();
+        for (peer_id, sender) in self.peers.lock().unwrap().iter() {
+            if Some(peer_id.as_str()) != except {
+                let _ = sender.send(text.clone());
+            }
+        }
+    }
+
+    fn with_node<T>(&self, f: impl FnOnce(&mut TestNode) -> T) -> T {
+        f(&mut self.node.lock().unwrap())
+    }
+}
+
+/// Poll until every node satisfies `done` (frames travel asynchronously)
+async fn wait_until(nodes: &[WsNode], done: impl Fn(&TestNode) -> bool) {
+    let converged = async {
+        while !nodes.iter().all(|node| done(&node.node.lock().unwrap())) {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    };
+    tokio::time::timeout(Duration::from_secs(10), converged)
+        .await
+        .expect("nodes should converge over the WebSocket transport");
+}
+
+fn alice_key() -> SigningKey {
+    SigningKey::from_bytes(&[42u8; 32])
+}
+
+fn producer_key() -> SigningKey {
+    SigningKey::from_bytes(&[7u8; 32])
+}
+
+fn public_hex(key: &SigningKey) -> String {
+    hex::encode(key.verifying_key().to_bytes())
+}
+
+/// Keys every node has bound: alice signs transfers, the producer key proposes
+/// as the bootstrap node and as the CPV validators of these tests
+fn verifier(weights: ProposerWeights) -> BlockVerifier {
+    let producer = public_hex(&producer_key());
+    BlockVerifier::new(DEFAULT_CHAIN_ID.to_string(), weights)
+        .with_key("DUalice", &public_hex(&alice_key()))
+        .with_key("system", &producer)
+        .with_key("DUvalidator", &producer)
+        .with_key("DUnobody", &producer)
+}
+
+fn signed_transfer(chain: &Blockchain, amount: u64) -> SignedTransaction {
+    SignedTransaction::unsigned(
+        "DUalice".to_string(),
+        "DUbob".to_string(),
+        amount,
+        None,
+        chain.next_nonce("DUalice"),
+        DEFAULT_CHAIN_ID.to_string(),
+        50,
+    )
+    .signed_with(&alice_key())
+}
+
+/// Seal all pending transactions into a block on top of the local head
+fn produce_block(chain: &mut Blockchain, proposer_key: &SigningKey) -> Block {
+    produce_block_as(chain, proposer_key, "system")
+}
+
+fn produce_block_as(chain: &mut Blockchain, proposer_key: &SigningKey, proposer: &str) -> Block {
+    let head = chain.get_latest_block().clone();
+    let transactions = std::mem::take(&mut chain.pending_transactions);
+    let timestamp = head.timestamp + 10;
+    let seed = proposer::slot_seed(head.height + 1, &head.hash, proposer::slot_round(head.timestamp, timestamp));
+    let mut block = Block::new(
+        head.height + 1,
+        timestamp,
+        transactions,
+        head.hash,
+        chain.state_root(),
+        Some(proposer.to_string()),
+    );
+    block.seal(Some(proposer::prove_slot(proposer_key, &seed)), proposer_key);
+    chain.chain.push(block.clone());
+    block
+}
+
+// ============================================================================
+// SYNC TESTS
+// ============================================================================
+
+#[tokio::test]
+async fn test_three_nodes_converge() {
+    let producer_key = producer_key();
+
+    // Node 0 builds some history before anyone connects
+    let mut first = TestNode::new("node-0");
+    for _ in 0..2 {
+        let tx = signed_transfer(&first.chain, 1_000).into_transaction();
+        first.chain.add_transaction(tx).unwrap();
+    }
+    produce_block(&mut first.chain, &producer_key);
+    let tx = signed_transfer(&first.chain, 500).into_transaction();
+    first.chain.add_transaction(tx).unwrap();
+    produce_block(&mut first.chain, &producer_key);
+    produce_block(&mut first.chain, &producer_key);
+
+    let nodes = vec![
+        WsNode::spawn(first).await,
+        WsNode::spawn(TestNode::new("node-1")).await,
+        WsNode::spawn(TestNode::new("node-2")).await,
+    ];
+
+    // Line topology 0 - 1 - 2 over real sockets: range sync on handshake
+    nodes[1].connect(&nodes[0]).await;
+    nodes[2].connect(&nodes[1]).await;
+    wait_until(&nodes, |node| node.chain.get_latest_block().height == 3).await;
+
+    // Transaction gossip reaches node 2 through node 1
+    let tx = nodes[0].with_node(|node| {
+        let tx = signed_transfer(&node.chain, 250).into_transaction();
+        node.chain.add_transaction(tx.clone()).unwrap();
+        tx
+    });
+    nodes[0].broadcast(&SyncMessage::NewTransaction(tx), None);
+    wait_until(&nodes, |node| node.chain.pending_transactions.len() == 1).await;
+
+    // New block announcement is imported and relayed
+    let block = nodes[0].with_node(|node| produce_block(&mut node.chain, &producer_key));
+    nodes[0].broadcast(&SyncMessage::NewBlock(block.clone()), None);
+    wait_until(&nodes, |node| node.chain.get_latest_block().hash == block.hash).await;
+
+    let alice = nodes[0].with_node(|node| node.chain.get_balance("DUalice"));
+    for ws_node in &nodes {
+        ws_node.with_node(|node| {
+            assert!(node.chain.pending_transactions.is_empty());
+            assert_eq!(node.chain.get_balance("DUbob"), 2_750);
+            assert_eq!(node.chain.get_balance("DUalice"), alice);
+            assert_eq!(node.chain.next_nonce("DUalice"), 5);
+            assert!(node.chain.is_chain_valid());
+        });
+    }
+}
+
+#[test]
+fn test_incompatible_chain_rejected() {
+    let node = TestNode::new("node-a");
+    let mut other = TestNode::new("node-b");
+    other.sync = ChainSync::new("node-b".to_string(), "dujyo-testnet-1".to_string());
+
+    let hello = other.sync.hello(&other.chain);
+    let mut chain = node.chain.clone();
+    assert!(matches!(
+        node.sync.handle_message(&mut chain, hello, &verifier(ProposerWeights::new())),
+        Err(SyncError::IncompatiblePeer(_))
+    ));
+}
+
+#[test]
+fn test_tampered_block_rejected() {
+    let producer_key = producer_key();
+    let mut producer = TestNode::new("node-a");
+    let mut follower = TestNode::new("node-b");
+
+    let tx = signed_transfer(&producer.chain, 1_000).into_transaction();
+    producer.chain.add_transaction(tx).unwrap();
+    let mut block = produce_block(&mut producer.chain, &producer_key);
+    block.transactions[0].amount = 90_000;
+
+    assert!(matches!(
+        follower
+            .sync
+            .handle_message(&mut follower.chain, SyncMessage::NewBlock(block), &verifier(ProposerWeights::new())),
+        Err(SyncError::InvalidBlock(_))
+    ));
+    assert_eq!(follower.chain.get_balance("DUalice"), 100_000);
+}
+
+#[test]
+fn test_unauthenticated_blocks_rejected() {
+    let producer_key = producer_key();
+    let mut producer = TestNode::new("node-a");
+    let mut follower = TestNode::new("node-b");
+    let verifier = verifier(ProposerWeights::new());
+
+    // Sealed by a key that is not bound to the proposer
+    let mut impostor = producer.chain.clone();
+    let forged = produce_block(&mut impostor, &SigningKey::from_bytes(&[99u8; 32]));
+    assert!(matches!(
+        follower.sync.handle_message(&mut follower.chain, SyncMessage::NewBlock(forged), &verifier),
+        Err(SyncError::InvalidBlock(_))
+    ));
+
+    // Proposer moving alice's funds without her signature
+    let theft = Transaction::system("DUalice".to_string(), "DUmallory".to_string(), 90_000, None);
+    producer.chain.add_transaction(theft).unwrap();
+    let block = produce_block(&mut producer.chain, &producer_key);
+    assert!(matches!(
+        follower.sync.handle_message(&mut follower.chain, SyncMessage::NewBlock(block), &verifier),
+        Err(SyncError::InvalidBlock(_))
+    ));
+    assert_eq!(follower.chain.get_balance("DUalice"), 100_000);
+    assert_eq!(follower.chain.get_latest_block().height, 0);
+}
+
+// ============================================================================
+// FORK CHOICE TESTS
+// ============================================================================
+
+#[test]
+fn test_heavier_branch_reorganizes_peers() {
+    let producer_key = producer_key();
+    let mut network = TestNetwork::new(3);
+    network.verifier.weights.insert("DUvalidator".to_string(), 50_000);
+    network.connect(0, 1);
+    network.connect(1, 2);
+
+    // Shared history: one block everyone agrees on
+    let block = produce_block(&mut network.nodes[0].chain, &producer_key);
+    network.broadcast_from(0, SyncMessage::NewBlock(block));
+
+    // Node 0 (system proposer) extends by two blocks with a transfer to bob
+    let tx = signed_transfer(&network.nodes[0].chain, 1_000).into_transaction();
+    network.nodes[0].chain.add_transaction(tx).unwrap();
+    for _ in 0..2 {
+        let block = produce_block(&mut network.nodes[0].chain, &producer_key);
+        network.broadcast_from(0, SyncMessage::NewBlock(block));
+    }
+    assert_eq!(network.nodes[2].chain.get_balance("DUbob"), 1_000);
+    let orphaned_head = network.nodes[2].chain.get_latest_block().hash.clone();
+
+    // Node 2 builds a competing block at height 2 proposed by a heavy CPV validator
+    let mut fork = network.nodes[2].chain.clone();
+    fork.chain.truncate(2);
+    fork.balances = network.nodes[1].chain.balances.clone();
+    fork.balances.insert("DUbob".to_string(), 0);
+    fork.balances.insert("DUalice".to_string(), 100_000);
+    fork.nonces.clear();
+    fork.pending_transactions.clear();
+    let heavy = produce_block_as(&mut fork, &producer_key, "DUvalidator");
+    let import = network.nodes[2].chain.accept_block(heavy.clone(), &network.verifier).unwrap();
+    match import {
+        BlockImport::Reorganized(event) => {
+            assert_eq!(event.common_ancestor_height, 1);
+            assert_eq!(event.depth(), 2);
+            assert_eq!(event.dropped_transactions, 0);
+        }
+        other => panic!("expected reorg, got {:?}", other),
+    }
+    network.broadcast_from(2, SyncMessage::NewBlock(heavy.clone()));
+
+    // Every node switches to the heavy branch and gets alice's transfer back in the mempool
+    for node in &network.nodes {
+        assert_eq!(node.chain.get_latest_block().hash, heavy.hash);
+        assert_eq!(node.chain.get_latest_block().height, 2);
+        assert!(node.chain.side_blocks.contains_key(&orphaned_head));
+        assert_eq!(node.chain.pending_transactions.len(), 1);
+        assert_eq!(node.chain.get_balance("DUbob"), 1_000);
+        assert_eq!(node.chain.next_nonce("DUalice"), 2);
+        assert!(node.chain.is_chain_valid());
+    }
+}
+
+#[test]
+fn test_lighter_branch_kept_on_side() {
+    let producer_key = producer_key();
+    let mut canonical = TestNode::new("node-a");
+    let verifier = verifier(ProposerWeights::new());
+
+    let mut fork = canonical.chain.clone();
+    produce_block(&mut canonical.chain, &producer_key);
+    let head = produce_block(&mut canonical.chain, &producer_key);
+    let side = produce_block_as(&mut fork, &producer_key, "DUnobody");
+
+    let outcome = canonical
+        .sync
+        .handle_message(&mut canonical.chain, SyncMessage::NewBlock(side.clone()), &verifier)
+        .unwrap();
+    assert!(outcome.reorgs.is_empty());
+    assert_eq!(outcome.side_blocks.len(), 1);
+    assert_eq!(canonical.chain.get_latest_block().hash, head.hash);
+    assert!(canonical.chain.knows_block(&side.hash));
+}
@@ -178,7 +178,7 @@ mod staking_security_tests {
             max_stake: None,
             reward_frequency: 86400,
             slashing_enabled: false,
-            slashing_rate: 0.0,
+            slashing_bps: 0,
         };
         
         let contract_result = staking.create_staking_contract(create_request);
@@ -367,19 +367,31 @@ const DEXSwap: React.FC = () => {
         return expectedAmount * (1 - baseSlippage);
       };
 
+      // Every swap carries the next DEX nonce (replay protection) and a deadline
+      const apiBaseUrl = getApiBaseUrl();
+      const nonceResponse = await fetch(`${apiBaseUrl}/api/v1/dex/nonce`, {
+        headers: { 'Authorization': `Bearer ${token}` },
+        signal: abortController.signal
+      });
+      if (!nonceResponse.ok) {
+        throw new Error(`Could not get swap nonce: HTTP ${nonceResponse.status}`);
+      }
+      const { next_nonce } = await nonceResponse.json();
+
       // Prepare swap request - ensure consistent address format
       const swapRequest = {
         from: fromToken,
         to: toToken,
         amount: parseFloat(fromAmount),
         min_received: calculateMinReceived(parseFloat(toAmount)), // 2% slippage for native tokens
-        user: user?.uid || account // Use consistent user ID from auth
+        user: user?.uid || account, // Use consistent user ID from auth
+        deadline: Math.floor(Date.now() / 1000) + 300, // 5 minutes
+        nonce: next_nonce
       };
 
       console.log('Executing swap:', swapRequest);
 
       // Execute swap on blockchain
-      const apiBaseUrl = getApiBaseUrl();
       const response = await fetch(`${apiBaseUrl}/swap`, {
         method: 'POST',
         headers: {
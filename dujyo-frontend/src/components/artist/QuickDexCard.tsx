@@ -63,15 +63,27 @@ const QuickDexCard: React.FC<QuickDexCardProps> = ({
       const expectedAmount = amount; // 1:1 ratio for now
       const minReceived = expectedAmount * 0.98;
 
+      // Every swap carries the next DEX nonce (replay protection) and a deadline
+      const apiBaseUrl = getApiBaseUrl();
+      const nonceResponse = await fetch(`${apiBaseUrl}/api/v1/dex/nonce`, {
+        headers: { 'Authorization': `Bearer ${token}` },
+        signal: abortController.signal
+      });
+      if (!nonceResponse.ok) {
+        throw new Error(`Could not get swap nonce: HTTP ${nonceResponse.status}`);
+      }
+      const { next_nonce } = await nonceResponse.json();
+
       const swapRequest = {
         from: fromToken,
         to: toToken,
         amount: amount,
         min_received: minReceived,
-        user: user.uid
+        user: user.uid,
+        deadline: Math.floor(Date.now() / 1000) + 300, // 5 minutes
+        nonce: next_nonce
       };
 
-      const apiBaseUrl = getApiBaseUrl();
       const response = await fetch(`${apiBaseUrl}/swap`, {
         method: 'POST',
         headers: {